@@ -0,0 +1,154 @@
+//! Pan/zoom benchmark for the span view transforms.
+//!
+//! Simulates a 60 fps zoom gesture over a synthetic profile and compares
+//! allocating a fresh command list per frame against reusing one buffer.
+//! Run with `cargo bench -p flame-cat-core`.
+
+use std::hint::black_box;
+
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+use flame_cat_core::testing::synth::{self, DepthDistribution, SynthConfig};
+use flame_cat_core::views::{left_heavy, time_order};
+use flame_cat_protocol::{RenderCommand, Viewport, VisualProfile};
+
+/// Frames rendered per measured iteration (one second at 60 fps).
+const FRAMES: usize = 60;
+
+const VIEWPORT: Viewport = Viewport {
+    x: 0.0,
+    y: 0.0,
+    width: 1920.0,
+    height: 400.0,
+    dpr: 1.0,
+};
+
+/// Visible range of each frame of a zoom gesture.
+fn zoom_gesture() -> impl Iterator<Item = (f64, f64)> {
+    (0..FRAMES).map(|i| {
+        let t = i as f64 / FRAMES as f64;
+        (t * 10_000.0, 100_000.0 - t * 10_000.0)
+    })
+}
+
+fn profile() -> VisualProfile {
+    profile_with(DepthDistribution::Chain)
+}
+
+fn profile_with(depth: DepthDistribution) -> VisualProfile {
+    synth::generate(&SynthConfig {
+        threads: 8,
+        spans_per_thread: 12_000,
+        levels: 12,
+        depth,
+        names: 32,
+        ..Default::default()
+    })
+}
+
+fn time_order(c: &mut Criterion) {
+    let profile = profile();
+    let mut group = c.benchmark_group("time_order");
+    group.bench_function("fresh", |b| {
+        b.iter(|| {
+            for (s, e) in zoom_gesture() {
+                for thread in &profile.threads {
+                    black_box(time_order::render_time_order(
+                        &profile,
+                        &VIEWPORT,
+                        s,
+                        e,
+                        Some(thread.id),
+                    ));
+                }
+            }
+        });
+    });
+    let mut buffers: Vec<Vec<RenderCommand>> = vec![Vec::new(); profile.threads.len()];
+    group.bench_function("reused", |b| {
+        b.iter(|| {
+            for (s, e) in zoom_gesture() {
+                for (thread, buf) in profile.threads.iter().zip(&mut buffers) {
+                    time_order::render_time_order_into(
+                        buf,
+                        &profile,
+                        &VIEWPORT,
+                        s,
+                        e,
+                        Some(thread.id),
+                    );
+                    black_box(&buf);
+                }
+            }
+        });
+    });
+    group.finish();
+}
+
+fn left_heavy(c: &mut Criterion) {
+    let profile = profile();
+    let mut group = c.benchmark_group("left_heavy");
+    group.bench_function("fresh", |b| {
+        b.iter(|| {
+            for _ in 0..FRAMES {
+                for thread in &profile.threads {
+                    black_box(left_heavy::render_left_heavy(
+                        &profile,
+                        &VIEWPORT,
+                        Some(thread.id),
+                    ));
+                }
+            }
+        });
+    });
+    let mut buffers: Vec<Vec<RenderCommand>> = vec![Vec::new(); profile.threads.len()];
+    group.bench_function("reused", |b| {
+        b.iter(|| {
+            for _ in 0..FRAMES {
+                for (thread, buf) in profile.threads.iter().zip(&mut buffers) {
+                    left_heavy::render_left_heavy_into(buf, &profile, &VIEWPORT, Some(thread.id));
+                    black_box(&buf);
+                }
+            }
+        });
+    });
+    group.finish();
+}
+
+/// The reused-buffer zoom gesture over each stack shape the generator
+/// makes: deep chains, evenly spread depths and mostly shallow spans.
+fn depth_distribution(c: &mut Criterion) {
+    let mut group = c.benchmark_group("depth_distribution");
+    for (name, depth) in [
+        ("chain", DepthDistribution::Chain),
+        ("uniform", DepthDistribution::Uniform),
+        ("geometric", DepthDistribution::Geometric { decay: 0.5 }),
+    ] {
+        let profile = profile_with(depth);
+        let mut buffers: Vec<Vec<RenderCommand>> = vec![Vec::new(); profile.threads.len()];
+        group.bench_function(BenchmarkId::new("time_order", name), |b| {
+            b.iter(|| {
+                for (s, e) in zoom_gesture() {
+                    for (thread, buf) in profile.threads.iter().zip(&mut buffers) {
+                        time_order::render_time_order_into(
+                            buf,
+                            &profile,
+                            &VIEWPORT,
+                            s,
+                            e,
+                            Some(thread.id),
+                        );
+                        black_box(&buf);
+                    }
+                }
+            });
+        });
+    }
+    group.finish();
+}
+
+criterion_group! {
+    name = benches;
+    config = Criterion::default().sample_size(20);
+    targets = time_order, left_heavy, depth_distribution
+}
+criterion_main!(benches);
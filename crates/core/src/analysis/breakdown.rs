@@ -0,0 +1,334 @@
+//! Where a profile's time goes, per mapped binary, span category, span
+//! kind and [`CodeClass`].
+//!
+//! For sampled native profiles the binary a frame ran in (the app, libc,
+//! the kernel) is often a more useful split than the by-name colors of the
+//! [`legend`](crate::legend); [`binary_breakdown`] totals the profile
+//! per mapped binary, [`category_summary`] per semantic span category and
+//! span kind, and [`class_summary`] per [`CodeClass`].
+
+use std::collections::HashMap;
+
+use flame_cat_protocol::{ClassRules, CodeClass, Span, SpanKind, VisualProfile};
+use serde::Serialize;
+
+/// One mapped binary of a profile.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BinaryEntry {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub build_id: Option<String>,
+    /// Summed self time of the spans that ran in the binary.
+    pub total_time: f64,
+    pub span_count: usize,
+}
+
+/// Every binary of `profile` that spans ran in, heaviest first. Spans with
+/// no recorded binary are left out, so profiles of formats without
+/// mappings have none.
+pub fn binary_breakdown(profile: &VisualProfile) -> Vec<BinaryEntry> {
+    let mut totals = vec![(0.0, 0); profile.binaries.len()];
+    for span in profile.all_spans() {
+        if let Some(total) = span.binary.and_then(|b| totals.get_mut(b as usize)) {
+            total.0 += span.self_value;
+            total.1 += 1;
+        }
+    }
+    let mut breakdown: Vec<BinaryEntry> = profile
+        .binaries
+        .iter()
+        .zip(totals)
+        .filter(|(_, (_, span_count))| *span_count > 0)
+        .map(|(binary, (total_time, span_count))| BinaryEntry {
+            name: binary.name.to_string(),
+            build_id: binary.build_id.as_ref().map(ToString::to_string),
+            total_time,
+            span_count,
+        })
+        .collect();
+    breakdown.sort_by(|a, b| {
+        b.total_time
+            .total_cmp(&a.total_time)
+            .then_with(|| a.name.cmp(&b.name))
+    });
+    breakdown
+}
+
+/// Time of one span category or span kind.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CategoryTotal {
+    pub name: String,
+    /// Summed durations of the group's spans, leaving out spans nested in
+    /// another span of the same group so no time counts twice.
+    pub total_time: f64,
+    /// Summed self time of the group's spans.
+    pub self_time: f64,
+    pub span_count: usize,
+}
+
+/// Where a profile's time goes, per category and per span kind.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CategorySummary {
+    /// By [`Span::category`], heaviest self time first; spans without one
+    /// under [`UNCATEGORIZED`].
+    pub categories: Vec<CategoryTotal>,
+    /// By [`Span::kind`] (`event`, `sample`, `synthetic`), heaviest self
+    /// time first.
+    pub kinds: Vec<CategoryTotal>,
+}
+
+/// Group name of spans without a category.
+pub const UNCATEGORIZED: &str = "uncategorized";
+
+/// Total and self time of `profile`'s spans per category and per kind.
+pub fn category_summary(profile: &VisualProfile) -> CategorySummary {
+    fn kind_name(span: &Span) -> &str {
+        match span.kind {
+            SpanKind::Event => "event",
+            SpanKind::Sample => "sample",
+            SpanKind::Synthetic => "synthetic",
+        }
+    }
+    fn category_name(span: &Span) -> &str {
+        span.category
+            .as_ref()
+            .map_or(UNCATEGORIZED, |c| c.name.as_ref())
+    }
+    CategorySummary {
+        categories: group_totals(profile, category_name),
+        kinds: group_totals(profile, kind_name),
+    }
+}
+
+/// Total and self time of `profile`'s spans per [`CodeClass`] under
+/// `rules`, one row per class in [`CodeClass::ALL`] order, named by
+/// [`CodeClass::name`]; classes with no spans have zero rows.
+pub fn class_summary(profile: &VisualProfile, rules: &ClassRules) -> Vec<CategoryTotal> {
+    let classes: HashMap<u64, CodeClass> = profile
+        .all_spans()
+        .map(|s| (s.id, rules.classify(s, profile.binary_of(s))))
+        .collect();
+    let class_of = |s: &Span| classes.get(&s.id).map_or("", |c| c.name());
+    let mut totals = group_totals(profile, class_of);
+    CodeClass::ALL
+        .into_iter()
+        .map(|class| {
+            let at = totals.iter().position(|t| t.name == class.name());
+            at.map_or_else(
+                || CategoryTotal {
+                    name: class.name().to_string(),
+                    total_time: 0.0,
+                    self_time: 0.0,
+                    span_count: 0,
+                },
+                |at| totals.swap_remove(at),
+            )
+        })
+        .collect()
+}
+
+/// Totals of the spans of each group `group` puts them in, heaviest first.
+fn group_totals<'a>(
+    profile: &'a VisualProfile,
+    group: impl Fn(&'a Span) -> &'a str,
+) -> Vec<CategoryTotal> {
+    let mut totals: HashMap<&str, (f64, f64, usize)> = HashMap::new();
+    for thread in &profile.threads {
+        let by_id: HashMap<u64, &Span> = thread.spans.iter().map(|s| (s.id, s)).collect();
+        for span in &thread.spans {
+            let name = group(span);
+            let total = totals.entry(name).or_default();
+            total.1 += span.self_value;
+            total.2 += 1;
+            // Only the outermost span of a group adds its duration.
+            let mut parent = span.parent.and_then(|id| by_id.get(&id).copied());
+            while let Some(p) = parent {
+                if group(p) == name {
+                    break;
+                }
+                parent = p.parent.and_then(|id| by_id.get(&id).copied());
+            }
+            if parent.is_none() {
+                total.0 += span.end - span.start;
+            }
+        }
+    }
+    let mut rows: Vec<CategoryTotal> = totals
+        .into_iter()
+        .map(
+            |(name, (total_time, self_time, span_count))| CategoryTotal {
+                name: name.to_string(),
+                total_time,
+                self_time,
+                span_count,
+            },
+        )
+        .collect();
+    rows.sort_by(|a, b| {
+        b.self_time
+            .total_cmp(&a.self_time)
+            .then_with(|| a.name.cmp(&b.name))
+    });
+    rows
+}
+
+#[cfg(test)]
+mod tests {
+    use flame_cat_protocol::{Binary, SpanCategory, ValueUnit};
+
+    use super::*;
+    use crate::testing::fixtures::{profile, span, thread};
+
+    #[test]
+    fn totals_per_binary_category_and_class() {
+        let mut profile = profile(
+            ValueUnit::Microseconds,
+            vec![thread(
+                0,
+                vec![
+                    span(1, "react::render", 0.0, 60.0, None),
+                    span(2, "react::commit", 10.0, 30.0, Some(1)),
+                    span(3, "std.fmt", 30.0, 50.0, Some(1)),
+                    span(4, "idle", 60.0, 80.0, None),
+                ],
+            )],
+        );
+
+        assert!(binary_breakdown(&profile).is_empty());
+        profile.binaries = vec![
+            Binary {
+                name: "libc.so.6".into(),
+                build_id: None,
+            },
+            Binary {
+                name: "app".into(),
+                build_id: Some("f00d".into()),
+            },
+            Binary {
+                name: "unused.so".into(),
+                build_id: None,
+            },
+        ];
+        for (span, binary) in profile.threads[0].spans.iter_mut().zip([1, 1, 0]) {
+            span.binary = Some(binary);
+        }
+        let breakdown = binary_breakdown(&profile);
+        let rows: Vec<(&str, Option<&str>, f64, usize)> = breakdown
+            .iter()
+            .map(|e| {
+                (
+                    e.name.as_str(),
+                    e.build_id.as_deref(),
+                    e.total_time,
+                    e.span_count,
+                )
+            })
+            .collect();
+        assert_eq!(
+            rows,
+            [("app", Some("f00d"), 40.0, 2), ("libc.so.6", None, 20.0, 1)]
+        );
+
+        for (span, category) in
+            profile.threads[0]
+                .spans
+                .iter_mut()
+                .zip([Some("js"), Some("js"), Some("gc"), None])
+        {
+            span.category = category.map(|name| SpanCategory {
+                name: name.into(),
+                source: None,
+            });
+        }
+        let summary = category_summary(&profile);
+        let rows = |totals: &[CategoryTotal]| -> Vec<(String, f64, f64, usize)> {
+            totals
+                .iter()
+                .map(|t| (t.name.clone(), t.total_time, t.self_time, t.span_count))
+                .collect()
+        };
+        assert_eq!(
+            rows(&summary.categories),
+            [
+                ("js".to_string(), 60.0, 40.0, 2),
+                ("gc".to_string(), 20.0, 20.0, 1),
+                (UNCATEGORIZED.to_string(), 20.0, 20.0, 1),
+            ]
+        );
+        assert_eq!(rows(&summary.kinds), [("event".to_string(), 80.0, 80.0, 4)]);
+
+        let rules = ClassRules {
+            dependencies: vec!["react::".into()],
+            ..ClassRules::default()
+        };
+        assert_eq!(
+            rows(&class_summary(&profile, &rules)),
+            [
+                ("first_party".to_string(), 20.0, 20.0, 1),
+                ("dependency".to_string(), 60.0, 40.0, 2),
+                ("runtime".to_string(), 20.0, 20.0, 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn category_summary_groups_totals_and_orders() {
+        let empty = category_summary(&profile(ValueUnit::Microseconds, vec![]));
+        assert!(empty.categories.is_empty() && empty.kinds.is_empty());
+
+        let mut profile = profile(
+            ValueUnit::Microseconds,
+            vec![thread(
+                0,
+                vec![
+                    span(1, "run", 0.0, 100.0, None),
+                    span(2, "script", 10.0, 40.0, Some(1)),
+                    span(3, "collect", 40.0, 60.0, Some(1)),
+                    span(4, "idle", 100.0, 130.0, None),
+                    span(5, "idle", 130.0, 160.0, None),
+                    span(6, "boot", 160.0, 180.0, None),
+                ],
+            )],
+        );
+        for (span, category) in profile.threads[0].spans.iter_mut().zip([
+            Some("js"),
+            Some("js"),
+            Some("gc"),
+            None,
+            None,
+            Some("alpha"),
+        ]) {
+            span.category = category.map(|name| SpanCategory {
+                name: name.into(),
+                source: None,
+            });
+        }
+
+        let summary = category_summary(&profile);
+        let rows: Vec<(&str, f64, f64, usize)> = summary
+            .categories
+            .iter()
+            .map(|t| (t.name.as_str(), t.total_time, t.self_time, t.span_count))
+            .collect();
+        // A nested span of its own category adds no time; ties in self time
+        // go by name.
+        assert_eq!(
+            rows,
+            [
+                ("js", 100.0, 80.0, 2),
+                (UNCATEGORIZED, 60.0, 60.0, 2),
+                ("alpha", 20.0, 20.0, 1),
+                ("gc", 20.0, 20.0, 1),
+            ]
+        );
+        assert_eq!(
+            summary.kinds,
+            [CategoryTotal {
+                name: "event".to_string(),
+                total_time: 180.0,
+                self_time: 180.0,
+                span_count: 6,
+            }]
+        );
+    }
+}
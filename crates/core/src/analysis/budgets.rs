@@ -0,0 +1,247 @@
+//! Time budgets per span category.
+//!
+//! A budget such as `js < 50%` caps the share of the profile's self time
+//! spent in spans of one [`SpanCategory`](flame_cat_protocol::SpanCategory).
+//! Budgets that are broken become insights, and `flame-cat report --check`
+//! fails on them, so a performance budget can gate CI.
+
+use std::fmt;
+use std::str::FromStr;
+
+use flame_cat_protocol::{Span, VisualProfile};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use super::{Insight, InsightLink};
+
+#[derive(Debug, Error, PartialEq)]
+pub enum BudgetError {
+    #[error("budget {0:?} is not of the form `category < N%`")]
+    Malformed(String),
+    #[error("budget for {category} must be between 0% and 100%, not {percent}%")]
+    OutOfRange { category: String, percent: f64 },
+}
+
+/// Most of the profile's self time one category may take.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CategoryBudget {
+    /// Span category name, matched ignoring ASCII case.
+    pub category: String,
+    /// Share of the total self time, 0–100.
+    pub max_percent: f64,
+}
+
+impl fmt::Display for CategoryBudget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} < {}%", self.category, self.max_percent)
+    }
+}
+
+impl FromStr for CategoryBudget {
+    type Err = BudgetError;
+
+    /// Parse `category < N%`; `<=` and a missing `%` are accepted too.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let malformed = || BudgetError::Malformed(s.trim().to_string());
+        let (category, limit) = s.split_once('<').ok_or_else(malformed)?;
+        let limit = limit.trim_start_matches('=').trim();
+        let percent: f64 = limit
+            .strip_suffix('%')
+            .unwrap_or(limit)
+            .trim()
+            .parse()
+            .map_err(|_| malformed())?;
+        let category = category.trim();
+        if category.is_empty() {
+            return Err(malformed());
+        }
+        if !(0.0..=100.0).contains(&percent) {
+            return Err(BudgetError::OutOfRange {
+                category: category.to_string(),
+                percent,
+            });
+        }
+        Ok(Self {
+            category: category.to_string(),
+            max_percent: percent,
+        })
+    }
+}
+
+/// Parse a comma-separated list such as `js < 50%, layout < 10%`.
+pub fn parse_budgets(spec: &str) -> Result<Vec<CategoryBudget>, BudgetError> {
+    spec.split(',')
+        .filter(|part| !part.trim().is_empty())
+        .map(str::parse)
+        .collect()
+}
+
+/// How a profile fares against one budget.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BudgetResult {
+    pub budget: CategoryBudget,
+    /// The category's share of the total self time, 0–100.
+    pub percent: f64,
+    /// Summed self time of the category, in the profile's value unit.
+    pub self_time: f64,
+    /// Longest span of the category, as a place to start looking.
+    pub longest_span: Option<u64>,
+}
+
+impl BudgetResult {
+    pub fn exceeded(&self) -> bool {
+        self.percent > self.budget.max_percent
+    }
+
+    /// This result as an insight linking to the category's longest span.
+    pub fn insight(&self, profile: &VisualProfile) -> Insight {
+        let links = self
+            .longest_span
+            .and_then(|id| profile.span(id))
+            .map(|span| InsightLink::TimeRange {
+                label: format!("Longest {} span: {}", self.budget.category, span.name),
+                start: span.start,
+                end: span.end,
+            })
+            .into_iter()
+            .collect();
+        Insight {
+            kind: "category_budget",
+            title: format!("{} over budget", self.budget.category),
+            detail: format!(
+                "{} takes {:.1}% of the profile's self time ({}), over its {}% budget.",
+                self.budget.category,
+                self.percent,
+                profile.meta.format_value(self.self_time),
+                self.budget.max_percent
+            ),
+            links,
+        }
+    }
+}
+
+/// Evaluate every budget against `profile`, in the order given.
+pub fn evaluate_budgets(profile: &VisualProfile, budgets: &[CategoryBudget]) -> Vec<BudgetResult> {
+    let total: f64 = profile.all_spans().map(|s| s.self_value).sum();
+    budgets
+        .iter()
+        .map(|budget| {
+            let mut self_time = 0.0;
+            let mut longest: Option<&Span> = None;
+            for span in profile.all_spans().filter(|s| {
+                s.category
+                    .as_ref()
+                    .is_some_and(|c| c.name.eq_ignore_ascii_case(&budget.category))
+            }) {
+                self_time += span.self_value;
+                if longest.is_none_or(|l| span.duration() > l.duration()) {
+                    longest = Some(span);
+                }
+            }
+            BudgetResult {
+                budget: budget.clone(),
+                percent: if total > 0.0 {
+                    self_time / total * 100.0
+                } else {
+                    0.0
+                },
+                self_time,
+                longest_span: longest.map(|s| s.id),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use flame_cat_protocol::{SpanCategory, ValueUnit};
+
+    use super::*;
+    use crate::testing::fixtures::{self, span, thread};
+
+    fn categorized(id: u64, category: &str, start: f64, end: f64) -> Span {
+        Span {
+            category: Some(SpanCategory {
+                name: category.into(),
+                source: None,
+            }),
+            ..span(id, &format!("task{id}"), start, end, None)
+        }
+    }
+
+    fn profile() -> VisualProfile {
+        fixtures::profile(
+            ValueUnit::Microseconds,
+            vec![thread(
+                0,
+                vec![
+                    categorized(1, "js", 0.0, 40.0),
+                    categorized(2, "js", 40.0, 60.0),
+                    categorized(3, "layout", 60.0, 70.0),
+                    categorized(4, "paint", 70.0, 100.0),
+                ],
+            )],
+        )
+    }
+
+    #[test]
+    fn parses_budget_lists() {
+        let budgets = parse_budgets("JS < 50%, layout <= 10 ,").unwrap();
+        assert_eq!(
+            budgets,
+            [
+                CategoryBudget {
+                    category: "JS".into(),
+                    max_percent: 50.0
+                },
+                CategoryBudget {
+                    category: "layout".into(),
+                    max_percent: 10.0
+                },
+            ]
+        );
+        assert_eq!(budgets[0].to_string(), "JS < 50%");
+        assert_eq!(
+            parse_budgets("js 50%"),
+            Err(BudgetError::Malformed("js 50%".into()))
+        );
+        assert!(matches!(
+            parse_budgets("js < 150%"),
+            Err(BudgetError::OutOfRange { .. })
+        ));
+    }
+
+    #[test]
+    fn flags_categories_over_budget() {
+        let profile = profile();
+        let budgets = parse_budgets("js < 50%, layout < 10%, gc < 5%").unwrap();
+        let results = evaluate_budgets(&profile, &budgets);
+        let summary: Vec<(f64, bool, Option<u64>)> = results
+            .iter()
+            .map(|r| (r.percent, r.exceeded(), r.longest_span))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                (60.0, true, Some(1)),
+                (10.0, false, Some(3)),
+                (0.0, false, None)
+            ]
+        );
+
+        let insight = results[0].insight(&profile);
+        assert_eq!(insight.kind, "category_budget");
+        assert_eq!(
+            insight.detail,
+            "js takes 60.0% of the profile's self time (60µs), over its 50% budget."
+        );
+        assert_eq!(
+            insight.links,
+            [InsightLink::TimeRange {
+                label: "Longest js span: task1".into(),
+                start: 0.0,
+                end: 40.0
+            }]
+        );
+    }
+}
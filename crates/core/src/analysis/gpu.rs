@@ -0,0 +1,120 @@
+//! Links GPU queue work to the CPU calls that submitted it.
+//!
+//! Traces rarely record which submit produced which GPU workload, so each
+//! top-level span on a GPU track is attributed to the latest submit-like CPU
+//! span that started no later than it did. The gap between the two is the
+//! submission latency; long gaps on an idle queue point at CPU-bound frames.
+
+use flame_cat_protocol::{Span, TrackKind, VisualProfile};
+use serde::Serialize;
+
+/// Lowercased name fragments of CPU spans that hand work to a GPU queue.
+const SUBMIT_NAMES: [&str; 5] = [
+    "submit",
+    "executecommandlists",
+    "present",
+    "swapbuffers",
+    "flush",
+];
+
+/// A GPU span and the CPU span that submitted it.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct GpuSubmission {
+    pub submit_span: u64,
+    /// `ThreadGroup::id` of the submitting CPU thread.
+    pub submit_thread: u32,
+    pub submit_start: f64,
+    pub gpu_span: u64,
+    /// `ThreadGroup::id` of the GPU queue.
+    pub gpu_thread: u32,
+    pub gpu_start: f64,
+    /// Time from the submit span's start to the GPU span's start.
+    pub latency: f64,
+}
+
+fn is_submit(span: &Span) -> bool {
+    let name = span.name.to_lowercase();
+    SUBMIT_NAMES.iter().any(|s| name.contains(s))
+}
+
+/// Every top-level GPU span with a submit before it, in GPU track order.
+pub fn link_submissions(profile: &VisualProfile) -> Vec<GpuSubmission> {
+    let mut submits: Vec<(u32, &Span)> = profile
+        .threads
+        .iter()
+        .filter(|t| t.kind == TrackKind::Cpu)
+        .flat_map(|t| t.spans.iter().filter(|s| is_submit(s)).map(|s| (t.id, s)))
+        .collect();
+    if submits.is_empty() {
+        return Vec::new();
+    }
+    submits.sort_by(|a, b| a.1.start.total_cmp(&b.1.start));
+
+    let mut links = Vec::new();
+    for gpu in profile.threads.iter().filter(|t| t.kind == TrackKind::Gpu) {
+        for span in gpu.spans.iter().filter(|s| s.parent.is_none()) {
+            let before = submits.partition_point(|(_, s)| s.start <= span.start);
+            let Some(&(submit_thread, submit)) = before.checked_sub(1).map(|i| &submits[i]) else {
+                continue;
+            };
+            links.push(GpuSubmission {
+                submit_span: submit.id,
+                submit_thread,
+                submit_start: submit.start,
+                gpu_span: span.id,
+                gpu_thread: gpu.id,
+                gpu_start: span.start,
+                latency: span.start - submit.start,
+            });
+        }
+    }
+    links
+}
+
+#[cfg(test)]
+mod tests {
+    use flame_cat_protocol::{ThreadGroup, ValueUnit};
+
+    use super::*;
+    use crate::testing::fixtures::{profile, span, thread};
+
+    fn track(id: u32, kind: TrackKind, spans: Vec<Span>) -> ThreadGroup {
+        ThreadGroup {
+            kind,
+            ..thread(id, spans)
+        }
+    }
+
+    #[test]
+    fn links_gpu_work_to_the_latest_submit() {
+        let profile = profile(
+            ValueUnit::Microseconds,
+            vec![
+                track(
+                    0,
+                    TrackKind::Cpu,
+                    vec![
+                        span(1, "Frame", 0.0, 50.0, None),
+                        span(2, "vkQueueSubmit", 10.0, 12.0, Some(1)),
+                        span(3, "Present", 40.0, 45.0, Some(1)),
+                    ],
+                ),
+                track(
+                    1,
+                    TrackKind::Gpu,
+                    vec![
+                        span(10, "Early", 5.0, 8.0, None),
+                        span(11, "Shadows", 15.0, 30.0, None),
+                        span(12, "Pass", 16.0, 20.0, Some(11)),
+                        span(13, "Blit", 48.0, 50.0, None),
+                    ],
+                ),
+            ],
+        );
+        let links: Vec<(u64, u64, f64)> = link_submissions(&profile)
+            .iter()
+            .map(|l| (l.submit_span, l.gpu_span, l.latency))
+            .collect();
+        assert_eq!(links, [(2, 11, 5.0), (3, 13, 8.0)]);
+    }
+}
@@ -0,0 +1,114 @@
+//! Hottest complete stacks of a profile as folded-stack lines.
+//!
+//! A stack path runs from a root span to the span whose self time it
+//! carries, so `main;render;layout 420 (35.0%)` reads the same as a line
+//! of collapsed-stack output. Equal paths on different threads are merged.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use flame_cat_protocol::{SharedStr, VisualProfile};
+use serde::Serialize;
+
+/// One stack path and the self time spent at its leaf.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct HotPath {
+    /// Span names from root to leaf, joined with `;`.
+    pub stack: String,
+    /// Self time of the leaf, in the profile's value unit.
+    pub self_time: f64,
+    /// Share of the profile's total self time, 0–100.
+    pub percent: f64,
+}
+
+impl fmt::Display for HotPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} ({:.1}%)",
+            self.stack, self.self_time, self.percent
+        )
+    }
+}
+
+/// A node of the merged call tree: `(parent node, name)` is unique.
+struct Node {
+    parent: Option<usize>,
+    name: SharedStr,
+    self_time: f64,
+}
+
+/// The `n` stack paths with the most self time, heaviest first.
+pub fn hot_paths(profile: &VisualProfile, n: usize) -> Vec<HotPath> {
+    let mut nodes: Vec<Node> = Vec::new();
+    let mut children: HashMap<(Option<usize>, SharedStr), usize> = HashMap::new();
+    for thread in &profile.threads {
+        // Parents before children, whatever order the spans come in.
+        let mut order: Vec<usize> = (0..thread.spans.len()).collect();
+        order.sort_by_key(|&i| thread.spans[i].depth);
+        let mut node_of: HashMap<u64, usize> = HashMap::with_capacity(thread.spans.len());
+        for i in order {
+            let span = &thread.spans[i];
+            let parent = span.parent.and_then(|p| node_of.get(&p).copied());
+            let node = *children
+                .entry((parent, span.name.clone()))
+                .or_insert_with(|| {
+                    nodes.push(Node {
+                        parent,
+                        name: span.name.clone(),
+                        self_time: 0.0,
+                    });
+                    nodes.len() - 1
+                });
+            nodes[node].self_time += span.self_value;
+            node_of.insert(span.id, node);
+        }
+    }
+
+    let total: f64 = nodes.iter().map(|n| n.self_time).sum();
+    let mut ranked: Vec<usize> = (0..nodes.len())
+        .filter(|&i| nodes[i].self_time > 0.0)
+        .collect();
+    ranked.sort_by(|&a, &b| nodes[b].self_time.total_cmp(&nodes[a].self_time));
+    ranked.truncate(n);
+    ranked
+        .into_iter()
+        .map(|leaf| {
+            let mut names = Vec::new();
+            let mut at = Some(leaf);
+            while let Some(i) = at {
+                names.push(&*nodes[i].name);
+                at = nodes[i].parent;
+            }
+            names.reverse();
+            let self_time = nodes[leaf].self_time;
+            HotPath {
+                stack: names.join(";"),
+                self_time,
+                percent: if total > 0.0 {
+                    self_time / total * 100.0
+                } else {
+                    0.0
+                },
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ranks_complete_stacks_by_self_time() {
+        let profile = crate::parsers::collapsed::parse_collapsed(
+            b"main;render;layout 6\nmain;render 2\nmain;idle 1\nmain;render;layout 3\nmain 8\n",
+        )
+        .unwrap()
+        .into_visual_profile();
+        let paths = hot_paths(&profile, 2);
+        let lines: Vec<String> = paths.iter().map(ToString::to_string).collect();
+        assert_eq!(lines, ["main;render;layout 9 (45.0%)", "main 8 (40.0%)"]);
+        assert_eq!(hot_paths(&profile, 10).len(), 4);
+    }
+}
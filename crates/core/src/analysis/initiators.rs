@@ -0,0 +1,269 @@
+//! Event-loop attribution for JavaScript traces.
+//!
+//! Chrome and Node traces record each event-loop turn as a top-level task
+//! (`RunTask`) and the callbacks run inside it under names that reveal the
+//! API that scheduled them: `TimerFire` for `setTimeout`, `FireAnimationFrame`
+//! for `requestAnimationFrame` and so on. Summing callback time per API shows
+//! what keeps the loop busy; following flow arrows back from a callback shows
+//! which code scheduled it.
+
+use std::collections::{HashMap, HashSet};
+
+use flame_cat_protocol::{SharedStr, Span, VisualProfile};
+use serde::Serialize;
+
+use super::tasks::top_level_tasks;
+
+/// A scheduling API whose callbacks run as part of an event-loop turn.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InitiatorApi {
+    /// `setTimeout` / `setInterval`.
+    Timer,
+    /// `requestAnimationFrame`.
+    AnimationFrame,
+    /// `fetch` and `XMLHttpRequest` completions.
+    Fetch,
+    /// Promise reactions and `queueMicrotask`.
+    Microtask,
+}
+
+impl InitiatorApi {
+    /// API whose callback a span named `name` runs, if it is one.
+    pub fn of_callback(name: &str) -> Option<Self> {
+        match name {
+            "TimerFire" => Some(Self::Timer),
+            "FireAnimationFrame" => Some(Self::AnimationFrame),
+            "ResourceReceiveResponse" | "ResourceFinish" | "XHRLoad" | "XHRReadyStateChange" => {
+                Some(Self::Fetch)
+            }
+            "RunMicrotasks" | "V8.RunMicrotasks" => Some(Self::Microtask),
+            _ => None,
+        }
+    }
+
+    /// Name of the API as written in JavaScript.
+    pub fn js_name(self) -> &'static str {
+        match self {
+            Self::Timer => "setTimeout",
+            Self::AnimationFrame => "requestAnimationFrame",
+            Self::Fetch => "fetch",
+            Self::Microtask => "queueMicrotask",
+        }
+    }
+}
+
+/// Callback time of one [`InitiatorApi`].
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ApiTime {
+    pub api: InitiatorApi,
+    /// Summed duration of the callbacks, in the profile's value unit.
+    pub total_time: f64,
+    pub callbacks: usize,
+}
+
+/// Where the event loop's time went.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct EventLoopAttribution {
+    /// Top-level tasks across all threads.
+    pub turns: usize,
+    /// Summed duration of those tasks.
+    pub turn_time: f64,
+    /// Per-API callback time, heaviest first. Callbacks nested in another
+    /// callback count toward the outer one only.
+    pub by_api: Vec<ApiTime>,
+}
+
+impl EventLoopAttribution {
+    /// Turn time spent outside any recognized callback.
+    pub fn unattributed_time(&self) -> f64 {
+        self.turn_time - self.by_api.iter().map(|a| a.total_time).sum::<f64>()
+    }
+}
+
+/// Attribute the time of every thread's event-loop turns to the APIs that
+/// scheduled their callbacks.
+pub fn attribute_event_loop(profile: &VisualProfile) -> EventLoopAttribution {
+    let mut attribution = EventLoopAttribution::default();
+    let mut by_api: HashMap<InitiatorApi, (f64, usize)> = HashMap::new();
+    for thread in &profile.threads {
+        let tasks = top_level_tasks(thread);
+        attribution.turns += tasks.len();
+        attribution.turn_time += tasks.iter().map(|t| t.duration).sum::<f64>();
+        let task_ids: HashSet<u64> = tasks.iter().map(|t| t.span_id).collect();
+        let spans: HashMap<u64, &Span> = thread.spans.iter().map(|s| (s.id, s)).collect();
+
+        for span in &thread.spans {
+            let Some(api) = InitiatorApi::of_callback(&span.name) else {
+                continue;
+            };
+            // Only outermost callbacks inside a task count.
+            let mut in_task = task_ids.contains(&span.id);
+            let mut nested = false;
+            let mut parent = span.parent;
+            while let Some(p) = parent.and_then(|id| spans.get(&id)) {
+                in_task |= task_ids.contains(&p.id);
+                nested |= InitiatorApi::of_callback(&p.name).is_some();
+                parent = p.parent;
+            }
+            if in_task && !nested {
+                let entry = by_api.entry(api).or_default();
+                entry.0 += span.duration();
+                entry.1 += 1;
+            }
+        }
+    }
+    attribution.by_api = by_api
+        .into_iter()
+        .map(|(api, (total_time, callbacks))| ApiTime {
+            api,
+            total_time,
+            callbacks,
+        })
+        .collect();
+    attribution
+        .by_api
+        .sort_by(|a, b| b.total_time.total_cmp(&a.total_time));
+    attribution
+}
+
+/// One link of an initiator chain.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Initiator {
+    /// Span that scheduled the previous link.
+    pub span_id: u64,
+    pub name: SharedStr,
+    /// When the scheduling happened.
+    pub ts: f64,
+    /// API the scheduled callback was run by, when recognized.
+    pub api: Option<InitiatorApi>,
+}
+
+/// Chain of spans that led to `span_id` running, nearest first.
+///
+/// From the span and its ancestors, the innermost one with an incoming flow
+/// arrow is the callback; the span at the arrow's source scheduled it. The
+/// walk repeats from there until no arrow leads further back.
+pub fn get_task_initiators(profile: &VisualProfile, span_id: u64) -> Vec<Initiator> {
+    let mut chain = Vec::new();
+    let mut visited = HashSet::new();
+    let mut current = profile.span(span_id);
+    while let Some(span) = current.filter(|s| visited.insert(s.id)) {
+        let mut callback = Some(span);
+        let scheduled = loop {
+            let Some(candidate) = callback else {
+                break None;
+            };
+            let incoming = profile
+                .flows_for_span(candidate.id)
+                .into_iter()
+                .find(|f| !f.outgoing);
+            if let Some(flow) = incoming {
+                break Some((candidate, flow.other_end()));
+            }
+            callback = candidate.parent.and_then(|p| profile.span(p));
+        };
+        let Some((callback, (pid, tid, ts))) = scheduled else {
+            break;
+        };
+        let api = std::iter::successors(Some(callback), |s| s.parent.and_then(|p| profile.span(p)))
+            .find_map(|s| InitiatorApi::of_callback(&s.name));
+        current = profile.span_at(pid, tid, ts);
+        if let Some(initiator) = current {
+            chain.push(Initiator {
+                span_id: initiator.id,
+                name: initiator.name.clone(),
+                ts,
+                api,
+            });
+        }
+    }
+    chain
+}
+
+#[cfg(test)]
+mod tests {
+    use flame_cat_protocol::{FlowArrow, ThreadGroup, ValueUnit};
+
+    use super::*;
+    use crate::testing::fixtures::{span, thread};
+
+    fn flow(from_ts: f64, to_ts: f64) -> FlowArrow {
+        FlowArrow {
+            name: "flow".into(),
+            id: format!("{from_ts}").into(),
+            from_ts,
+            from_pid: 1,
+            from_tid: 1,
+            to_ts,
+            to_pid: 1,
+            to_tid: 1,
+        }
+    }
+
+    fn profile() -> VisualProfile {
+        let main = thread(
+            0,
+            vec![
+                span(1, "RunTask", 0.0, 20.0, None),
+                span(2, "EvaluateScript", 0.0, 20.0, Some(1)),
+                span(3, "RunMicrotasks", 15.0, 20.0, Some(1)),
+                span(4, "RunTask", 30.0, 60.0, None),
+                span(5, "TimerFire", 30.0, 50.0, Some(4)),
+                span(6, "onTimeout", 31.0, 50.0, Some(5)),
+                span(7, "RunMicrotasks", 40.0, 45.0, Some(6)),
+                span(8, "RunTask", 70.0, 80.0, None),
+                span(9, "FireAnimationFrame", 70.0, 78.0, Some(8)),
+                span(10, "draw", 71.0, 78.0, Some(9)),
+            ],
+        );
+        let mut profile = crate::testing::fixtures::profile(
+            ValueUnit::Microseconds,
+            vec![ThreadGroup {
+                pid: Some(1),
+                tid: Some(1),
+                ..main
+            }],
+        );
+        // The script installs the timer, the timer callback requests the
+        // animation frame.
+        profile.flow_arrows = vec![flow(5.0, 30.0), flow(35.0, 70.0)];
+        profile
+    }
+
+    #[test]
+    fn attributes_turn_time_per_api() {
+        let attribution = attribute_event_loop(&profile());
+        assert_eq!((attribution.turns, attribution.turn_time), (3, 60.0));
+        let by_api: Vec<(InitiatorApi, f64, usize)> = attribution
+            .by_api
+            .iter()
+            .map(|a| (a.api, a.total_time, a.callbacks))
+            .collect();
+        assert_eq!(
+            by_api,
+            [
+                (InitiatorApi::Timer, 20.0, 1),
+                (InitiatorApi::AnimationFrame, 8.0, 1),
+                (InitiatorApi::Microtask, 5.0, 1),
+            ]
+        );
+        assert_eq!(attribution.unattributed_time(), 27.0);
+    }
+
+    #[test]
+    fn follows_flows_back_to_the_scheduling_code() {
+        let profile = profile();
+        let chain = get_task_initiators(&profile, 10);
+        let links: Vec<(u64, Option<InitiatorApi>)> =
+            chain.iter().map(|i| (i.span_id, i.api)).collect();
+        assert_eq!(
+            links,
+            [
+                (6, Some(InitiatorApi::AnimationFrame)),
+                (2, Some(InitiatorApi::Timer)),
+            ]
+        );
+        assert!(get_task_initiators(&profile, 2).is_empty());
+    }
+}
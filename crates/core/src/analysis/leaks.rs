@@ -0,0 +1,241 @@
+//! Memory leak suspects from object lifecycle events.
+//!
+//! Object types whose live count only ever grows are leak suspects: every
+//! instance created during the recording is still alive at its end. Heap
+//! counters that grew while those objects piled up make the case stronger,
+//! so their growth windows are attached to each suspect.
+
+use std::collections::HashMap;
+
+use flame_cat_protocol::{CounterTrack, ObjectPhase, SharedStr, VisualProfile};
+use serde::Serialize;
+
+use super::{Insight, InsightLink};
+use crate::views::counter::format_counter_value;
+
+/// Fewer creations than this are too few to call a trend.
+pub const MIN_CREATIONS: usize = 3;
+
+/// An object type that was created repeatedly and never destroyed.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LeakSuspect {
+    pub name: SharedStr,
+    /// Instances alive at the end of the recording.
+    pub live_count: usize,
+    /// Index into `VisualProfile::object_events` of the first creation.
+    pub first_created: usize,
+    /// Index into `VisualProfile::object_events` of the last creation.
+    pub last_created: usize,
+    /// Heap-counter growth overlapping the creations, largest first.
+    pub heap_growth: Vec<HeapGrowth>,
+}
+
+/// A stretch over which a heap counter never went down.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct HeapGrowth {
+    pub counter: SharedStr,
+    pub start: f64,
+    pub end: f64,
+    /// Counter value gained over the window.
+    pub growth: f64,
+}
+
+/// Object types whose live count grows monotonically, most instances first.
+pub fn find_leak_suspects(profile: &VisualProfile) -> Vec<LeakSuspect> {
+    let mut order: Vec<usize> = (0..profile.object_events.len()).collect();
+    order.sort_by(|&a, &b| {
+        profile.object_events[a]
+            .ts
+            .total_cmp(&profile.object_events[b].ts)
+    });
+
+    #[derive(Default)]
+    struct Lifecycle {
+        creations: Vec<usize>,
+        destroyed: bool,
+    }
+    let mut by_name: HashMap<&str, Lifecycle> = HashMap::new();
+    for index in order {
+        let event = &profile.object_events[index];
+        let lifecycle = by_name.entry(&event.name).or_default();
+        match event.phase {
+            ObjectPhase::Create => lifecycle.creations.push(index),
+            ObjectPhase::Destroy => lifecycle.destroyed = true,
+            ObjectPhase::Snapshot => {}
+        }
+    }
+
+    let heap_windows: Vec<HeapGrowth> = profile
+        .counters
+        .iter()
+        .filter(|c| c.name.to_lowercase().contains("heap"))
+        .flat_map(growth_windows)
+        .collect();
+
+    let mut suspects: Vec<LeakSuspect> = by_name
+        .into_iter()
+        .filter(|(_, l)| l.creations.len() >= MIN_CREATIONS && !l.destroyed)
+        .map(|(name, l)| {
+            let (first_created, last_created) =
+                (l.creations[0], l.creations[l.creations.len() - 1]);
+            let (from, to) = (
+                profile.object_events[first_created].ts,
+                profile.object_events[last_created].ts,
+            );
+            let mut heap_growth: Vec<HeapGrowth> = heap_windows
+                .iter()
+                .filter(|w| w.start <= to && w.end >= from)
+                .cloned()
+                .collect();
+            heap_growth.sort_by(|a, b| b.growth.total_cmp(&a.growth));
+            LeakSuspect {
+                name: name.into(),
+                live_count: l.creations.len(),
+                first_created,
+                last_created,
+                heap_growth,
+            }
+        })
+        .collect();
+    suspects.sort_by(|a, b| {
+        b.live_count
+            .cmp(&a.live_count)
+            .then_with(|| a.name.cmp(&b.name))
+    });
+    suspects
+}
+
+/// Maximal runs of non-decreasing samples that end higher than they start.
+fn growth_windows(counter: &CounterTrack) -> Vec<HeapGrowth> {
+    let samples = &counter.samples;
+    let mut windows = Vec::new();
+    let mut start = 0;
+    for i in 1..=samples.len() {
+        if i < samples.len() && samples[i].value >= samples[i - 1].value {
+            continue;
+        }
+        let (first, last) = (&samples[start], &samples[i - 1]);
+        if last.value > first.value {
+            windows.push(HeapGrowth {
+                counter: counter.name.clone(),
+                start: first.ts,
+                end: last.ts,
+                growth: last.value - first.value,
+            });
+        }
+        start = i;
+    }
+    windows
+}
+
+impl LeakSuspect {
+    /// This suspect as an insight linking to its first and last creations
+    /// and the heap growth windows.
+    pub fn insight(&self, profile: &VisualProfile) -> Insight {
+        let event = |index: usize, label: &str| InsightLink::ObjectEvent {
+            label: label.to_string(),
+            index,
+            ts: profile.object_events[index].ts,
+        };
+        let mut links = vec![
+            event(self.first_created, "First created"),
+            event(self.last_created, "Last created"),
+        ];
+        let mut detail = format!(
+            "{} instances were created and none destroyed.",
+            self.live_count
+        );
+        if let Some(biggest) = self.heap_growth.first() {
+            let unit = profile
+                .counters
+                .iter()
+                .find(|c| c.name == biggest.counter)
+                .map(|c| c.unit);
+            if let Some(unit) = unit {
+                detail.push_str(&format!(
+                    " {} grew by {} meanwhile.",
+                    biggest.counter,
+                    format_counter_value(biggest.growth, &unit)
+                ));
+            }
+        }
+        links.extend(self.heap_growth.iter().map(|w| InsightLink::TimeRange {
+            label: format!("{} growth", w.counter),
+            start: w.start,
+            end: w.end,
+        }));
+        Insight {
+            kind: "memory_leak",
+            title: format!("Possible leak: {}", self.name),
+            detail,
+            links,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use flame_cat_protocol::{CounterSample, CounterUnit, ObjectEvent, ValueUnit};
+
+    use super::*;
+    use crate::testing::fixtures;
+
+    fn object(id: &str, name: &str, phase: ObjectPhase, ts: f64) -> ObjectEvent {
+        ObjectEvent {
+            id: id.into(),
+            name: name.into(),
+            phase,
+            ts,
+        }
+    }
+
+    #[test]
+    fn flags_types_that_are_never_destroyed() {
+        use ObjectPhase::{Create, Destroy, Snapshot};
+        let heap = [0.0, 10.0, 30.0, 20.0, 25.0, 60.0];
+        let mut profile = fixtures::profile(ValueUnit::Microseconds, vec![]);
+        profile.counters = vec![CounterTrack {
+            name: "JSHeapUsedSize".into(),
+            unit: CounterUnit::Bytes,
+            samples: heap
+                .iter()
+                .enumerate()
+                .map(|(i, &value)| CounterSample {
+                    ts: i as f64 * 20.0,
+                    value: value * 1024.0,
+                })
+                .collect(),
+        }];
+        profile.object_events = vec![
+            object("1", "Listener", Create, 10.0),
+            object("a", "Node", Create, 5.0),
+            object("2", "Listener", Create, 30.0),
+            object("a", "Node", Destroy, 6.0),
+            object("3", "Listener", Snapshot, 35.0),
+            object("3", "Listener", Create, 35.0),
+            object("b", "Node", Create, 40.0),
+            object("c", "Node", Create, 41.0),
+        ];
+
+        let suspects = find_leak_suspects(&profile);
+        assert_eq!(suspects.len(), 1);
+        let listener = &suspects[0];
+        assert_eq!((&*listener.name, listener.live_count), ("Listener", 3));
+        assert_eq!((listener.first_created, listener.last_created), (0, 5));
+        let windows: Vec<(f64, f64)> = listener
+            .heap_growth
+            .iter()
+            .map(|w| (w.start, w.end))
+            .collect();
+        assert_eq!(windows, [(0.0, 40.0)]);
+
+        let insight = listener.insight(&profile);
+        assert_eq!(insight.title, "Possible leak: Listener");
+        assert_eq!(
+            insight.detail,
+            "3 instances were created and none destroyed. JSHeapUsedSize grew by 30.0 KB meanwhile."
+        );
+        assert_eq!(insight.links.len(), 3);
+        assert_eq!(crate::analysis::insights(&profile, &[]), [insight]);
+    }
+}
@@ -0,0 +1,59 @@
+//! Analyses derived from a [`VisualProfile`]
+//! that hosts query as data, next to the views that draw it.
+
+pub mod breakdown;
+pub mod budgets;
+pub mod gpu;
+pub mod hot_paths;
+pub mod initiators;
+pub mod leaks;
+pub mod span_info;
+pub mod tasks;
+pub mod utilization;
+
+pub use budgets::{CategoryBudget, evaluate_budgets, parse_budgets};
+pub use hot_paths::{HotPath, hot_paths};
+pub use span_info::{ChildTotal, SpanInfo, span_info};
+
+use flame_cat_protocol::VisualProfile;
+use serde::Serialize;
+
+/// A finding worth pointing out to the user, with links into the profile.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Insight {
+    /// Check that produced the finding, e.g. `"memory_leak"`.
+    pub kind: &'static str,
+    pub title: String,
+    pub detail: String,
+    pub links: Vec<InsightLink>,
+}
+
+/// Something in the profile an [`Insight`] refers to.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum InsightLink {
+    /// Entry of `VisualProfile::object_events`.
+    ObjectEvent {
+        label: String,
+        index: usize,
+        ts: f64,
+    },
+    /// A window of the profile's clock.
+    TimeRange { label: String, start: f64, end: f64 },
+}
+
+/// Every insight the analyses find in `profile`, including each of
+/// `budgets` it breaks.
+pub fn insights(profile: &VisualProfile, budgets: &[CategoryBudget]) -> Vec<Insight> {
+    let mut insights: Vec<Insight> = leaks::find_leak_suspects(profile)
+        .iter()
+        .map(|s| s.insight(profile))
+        .collect();
+    insights.extend(
+        evaluate_budgets(profile, budgets)
+            .iter()
+            .filter(|r| r.exceeded())
+            .map(|r| r.insight(profile)),
+    );
+    insights
+}
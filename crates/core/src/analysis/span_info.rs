@@ -0,0 +1,110 @@
+//! One span and where its time went, for a detail panel: its direct
+//! children totalled by name.
+
+use std::collections::HashMap;
+
+use flame_cat_protocol::VisualProfile;
+use serde::Serialize;
+
+/// The direct children of one name under a span.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ChildTotal {
+    pub name: String,
+    /// Summed durations of the children of this name. Siblings never
+    /// overlap, so no time counts twice.
+    pub total_time: f64,
+    /// `total_time` as a percentage of the parent span's duration.
+    pub percent: f64,
+    pub span_count: usize,
+}
+
+/// One span and where its time went.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SpanInfo {
+    pub id: u64,
+    pub name: String,
+    pub start: f64,
+    pub end: f64,
+    pub depth: u32,
+    pub self_time: f64,
+    /// Direct children grouped by name, heaviest first.
+    pub children: Vec<ChildTotal>,
+}
+
+/// The span `span_id` of `profile` with its direct children totalled by
+/// name, or `None` if there is no such span.
+pub fn span_info(profile: &VisualProfile, span_id: u64) -> Option<SpanInfo> {
+    let (thread, span) = profile.threads.iter().find_map(|t| {
+        let span = t.spans.iter().find(|s| s.id == span_id)?;
+        Some((t, span))
+    })?;
+    let mut totals: HashMap<&str, (f64, usize)> = HashMap::new();
+    for child in thread.spans.iter().filter(|s| s.parent == Some(span_id)) {
+        let total = totals.entry(&child.name).or_default();
+        total.0 += child.duration();
+        total.1 += 1;
+    }
+    let duration = span.duration();
+    let mut children: Vec<ChildTotal> = totals
+        .into_iter()
+        .map(|(name, (total_time, span_count))| ChildTotal {
+            name: name.to_string(),
+            total_time,
+            percent: if duration > 0.0 {
+                total_time / duration * 100.0
+            } else {
+                0.0
+            },
+            span_count,
+        })
+        .collect();
+    children.sort_by(|a, b| {
+        b.total_time
+            .total_cmp(&a.total_time)
+            .then_with(|| a.name.cmp(&b.name))
+    });
+    Some(SpanInfo {
+        id: span.id,
+        name: span.name.to_string(),
+        start: span.start,
+        end: span.end,
+        depth: span.depth,
+        self_time: span.self_value,
+        children,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use flame_cat_protocol::ValueUnit;
+
+    use super::*;
+    use crate::testing::fixtures::{profile, span, thread};
+
+    #[test]
+    fn totals_children_by_name() {
+        let profile = profile(
+            ValueUnit::Microseconds,
+            vec![thread(
+                0,
+                vec![
+                    span(1, "task", 0.0, 100.0, None),
+                    span(2, "parse", 0.0, 10.0, Some(1)),
+                    span(3, "layout", 10.0, 50.0, Some(1)),
+                    span(4, "parse", 50.0, 70.0, Some(1)),
+                    span(5, "parse", 20.0, 30.0, Some(3)),
+                ],
+            )],
+        );
+        let info = span_info(&profile, 1).unwrap();
+        assert_eq!((info.name.as_str(), info.self_time), ("task", 30.0));
+        let rows: Vec<(&str, f64, f64, usize)> = info
+            .children
+            .iter()
+            .map(|c| (c.name.as_str(), c.total_time, c.percent, c.span_count))
+            .collect();
+        assert_eq!(rows, [("layout", 40.0, 40.0, 1), ("parse", 30.0, 30.0, 2)]);
+        assert!(span_info(&profile, 5).unwrap().children.is_empty());
+        assert!(span_info(&profile, 9).is_none());
+    }
+}
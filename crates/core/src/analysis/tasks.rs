@@ -0,0 +1,132 @@
+//! Top-level task segmentation.
+//!
+//! A thread's top-level spans are its tasks: in a browser trace each one is
+//! a turn of the event loop (`RunTask`), in most other profiles a unit of
+//! work the thread picked up. Like the task bars in Chrome DevTools, the
+//! list makes long stretches of uninterrupted work easy to spot.
+
+use std::collections::HashMap;
+
+use flame_cat_protocol::{SharedStr, Span, ThreadGroup, ValueUnit};
+use serde::Serialize;
+
+/// Tasks at least this long block input long enough to be noticed.
+pub const LONG_TASK_US: f64 = 50_000.0;
+
+/// One top-level task of a thread.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Task {
+    pub span_id: u64,
+    pub name: SharedStr,
+    pub start: f64,
+    pub end: f64,
+    pub duration: f64,
+    /// Longest direct child, which usually names what the task was doing.
+    pub dominant_child: Option<TaskChild>,
+}
+
+/// The longest direct child of a [`Task`].
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TaskChild {
+    pub span_id: u64,
+    pub name: SharedStr,
+    pub duration: f64,
+}
+
+/// [`LONG_TASK_US`] in `unit`, or `None` when `unit` is not a time.
+pub fn long_task_threshold(unit: ValueUnit) -> Option<f64> {
+    Some(LONG_TASK_US / unit.to_microseconds_factor()?)
+}
+
+/// Tasks of `thread` in start order.
+///
+/// Tasks are the depth-0 spans. A lone root with children, such as the
+/// `(root)` or `main` frame wrapping a whole sampled profile, is not a
+/// useful task, so its children are used instead.
+pub fn top_level_tasks(thread: &ThreadGroup) -> Vec<Task> {
+    let mut dominant: HashMap<u64, &Span> = HashMap::new();
+    for span in &thread.spans {
+        let Some(parent) = span.parent else {
+            continue;
+        };
+        dominant
+            .entry(parent)
+            .and_modify(|d| {
+                if span.duration() > d.duration() {
+                    *d = span;
+                }
+            })
+            .or_insert(span);
+    }
+
+    let mut roots = thread.spans.iter().filter(|s| s.parent.is_none());
+    let task_parent = match (roots.next(), roots.next()) {
+        (Some(root), None) if dominant.contains_key(&root.id) => Some(root.id),
+        _ => None,
+    };
+    let mut tasks: Vec<Task> = thread
+        .spans
+        .iter()
+        .filter(|s| s.parent == task_parent)
+        .map(|s| Task {
+            span_id: s.id,
+            name: s.name.clone(),
+            start: s.start,
+            end: s.end,
+            duration: s.duration(),
+            dominant_child: dominant.get(&s.id).map(|c| TaskChild {
+                span_id: c.id,
+                name: c.name.clone(),
+                duration: c.duration(),
+            }),
+        })
+        .collect();
+    tasks.sort_by(|a, b| a.start.total_cmp(&b.start));
+    tasks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::fixtures::{span, thread};
+
+    #[test]
+    fn tasks_are_top_level_spans_with_their_longest_child() {
+        let main = thread(
+            0,
+            vec![
+                span(3, "RunTask", 100.0, 180.0, None),
+                span(1, "RunTask", 0.0, 60.0, None),
+                span(2, "Layout", 5.0, 20.0, Some(1)),
+                span(4, "Paint", 20.0, 50.0, Some(1)),
+            ],
+        );
+        let tasks = top_level_tasks(&main);
+        let summary: Vec<(u64, f64, Option<&str>)> = tasks
+            .iter()
+            .map(|t| {
+                let child = t.dominant_child.as_ref().map(|c| &*c.name);
+                (t.span_id, t.duration, child)
+            })
+            .collect();
+        assert_eq!(summary, [(1, 60.0, Some("Paint")), (3, 80.0, None)]);
+
+        // A lone wrapping root is looked through.
+        let sampled = thread(
+            0,
+            vec![
+                span(1, "(root)", 0.0, 100.0, None),
+                span(2, "a", 0.0, 40.0, Some(1)),
+                span(3, "b", 50.0, 100.0, Some(1)),
+            ],
+        );
+        let ids: Vec<u64> = top_level_tasks(&sampled)
+            .iter()
+            .map(|t| t.span_id)
+            .collect();
+        assert_eq!(ids, [2, 3]);
+
+        assert_eq!(long_task_threshold(ValueUnit::Milliseconds), Some(50.0));
+        assert_eq!(long_task_threshold(ValueUnit::Samples), None);
+    }
+}
@@ -0,0 +1,146 @@
+//! Thread utilization derived from spans.
+//!
+//! A thread is busy while one of its top-level spans is open. Bucketing that
+//! over time gives a CPU-utilization style counter even for traces that
+//! recorded no CPU counters.
+
+use flame_cat_protocol::{CounterSample, CounterTrack, CounterUnit, ThreadGroup, VisualProfile};
+use serde::Serialize;
+
+/// Buckets are widened so no track gets more than this many.
+pub const MAX_BUCKETS: usize = 100_000;
+
+/// Busy fraction (0–1) of `thread` in each `bucket`-wide slice of
+/// `start..end`, i.e. how much of the slice its top-level spans cover.
+pub fn busy_fractions(thread: &ThreadGroup, start: f64, end: f64, bucket: f64) -> Vec<f64> {
+    if !(end > start && bucket > 0.0) {
+        return Vec::new();
+    }
+    let len = (((end - start) / bucket).ceil() as usize).min(MAX_BUCKETS);
+    let bucket = (end - start) / len as f64;
+    let mut busy = vec![0.0; len];
+    for span in thread.spans.iter().filter(|s| s.parent.is_none()) {
+        let (from, to) = (span.start.max(start), span.end.min(end));
+        if from >= to {
+            continue;
+        }
+        let first = ((from - start) / bucket) as usize;
+        let last = (((to - start) / bucket) as usize).min(len - 1);
+        for (i, slot) in busy.iter_mut().enumerate().take(last + 1).skip(first) {
+            let b_start = start + i as f64 * bucket;
+            let overlap = to.min(b_start + bucket) - from.max(b_start);
+            *slot += overlap.max(0.0) / bucket;
+        }
+    }
+    for slot in &mut busy {
+        *slot = slot.min(1.0);
+    }
+    busy
+}
+
+/// Utilization counter of one thread, or of all threads together.
+#[derive(Debug, Clone, Serialize)]
+pub struct Utilization {
+    /// `None` for the total across threads.
+    pub thread_id: Option<u32>,
+    /// Busy percentage per bucket, sampled at each bucket's start.
+    pub counter: CounterTrack,
+}
+
+/// Per-thread utilization of `profile` in buckets of `bucket_us`, followed
+/// by the total. The total sums the threads, so 200% means two busy
+/// threads. Profiles whose values are not times use `bucket_us` in their
+/// own unit.
+pub fn thread_utilization(profile: &VisualProfile, bucket_us: f64) -> Vec<Utilization> {
+    let bucket = match profile.meta.value_unit.to_microseconds_factor() {
+        Some(factor) => bucket_us / factor,
+        None => bucket_us,
+    };
+    let (start, end) = (profile.meta.start_time, profile.meta.end_time);
+    let per_thread: Vec<(u32, &str, Vec<f64>)> = profile
+        .threads
+        .iter()
+        .map(|t| (t.id, &*t.name, busy_fractions(t, start, end, bucket)))
+        .collect();
+    let len = per_thread.first().map_or(0, |(_, _, b)| b.len());
+    let step = if len > 0 {
+        (end - start) / len as f64
+    } else {
+        0.0
+    };
+    let counter = |name: String, busy: &[f64]| CounterTrack {
+        name: name.into(),
+        unit: CounterUnit::Percent,
+        samples: busy
+            .iter()
+            .enumerate()
+            .map(|(i, f)| CounterSample {
+                ts: start + i as f64 * step,
+                value: f * 100.0,
+            })
+            .collect(),
+    };
+
+    let mut total = vec![0.0; len];
+    let mut tracks: Vec<Utilization> = per_thread
+        .iter()
+        .map(|(id, name, busy)| {
+            for (sum, f) in total.iter_mut().zip(busy) {
+                *sum += f;
+            }
+            Utilization {
+                thread_id: Some(*id),
+                counter: counter(format!("{name} utilization"), busy),
+            }
+        })
+        .collect();
+    tracks.push(Utilization {
+        thread_id: None,
+        counter: counter("Total utilization".into(), &total),
+    });
+    tracks
+}
+
+#[cfg(test)]
+mod tests {
+    use flame_cat_protocol::ValueUnit;
+
+    use super::*;
+    use crate::testing::fixtures::{profile, span, thread};
+
+    #[test]
+    fn buckets_busy_time_per_thread_and_total() {
+        let main = thread(
+            0,
+            vec![
+                span(1, "work", 0.0, 15.0, None),
+                span(2, "work", 5.0, 10.0, Some(1)),
+                span(3, "work", 30.0, 40.0, None),
+            ],
+        );
+        assert_eq!(busy_fractions(&main, 0.0, 40.0, 10.0), [1.0, 0.5, 0.0, 1.0]);
+
+        let profile = profile(
+            ValueUnit::Milliseconds,
+            vec![main, thread(1, vec![span(4, "work", 0.0, 20.0, None)])],
+        );
+        // 20ms buckets over a millisecond profile.
+        let tracks = thread_utilization(&profile, 20_000.0);
+        let values: Vec<(Option<u32>, Vec<f64>)> = tracks
+            .iter()
+            .map(|u| {
+                let values = u.counter.samples.iter().map(|s| s.value).collect();
+                (u.thread_id, values)
+            })
+            .collect();
+        assert_eq!(
+            values,
+            [
+                (Some(0), vec![75.0, 50.0]),
+                (Some(1), vec![100.0, 0.0]),
+                (None, vec![175.0, 50.0]),
+            ]
+        );
+        assert_eq!(tracks[2].counter.samples[1].ts, 20.0);
+    }
+}
@@ -0,0 +1,424 @@
+//! Compact columnar cache for parsed profiles.
+//!
+//! Re-parsing a multi-gigabyte trace on every open is slow, so a parsed
+//! [`VisualProfile`] can be written once to a `.flamecache` file and loaded
+//! from it afterwards. Spans — the bulk of any profile — are stored as
+//! fixed-width little-endian columns with names interned into a shared
+//! string table; everything else is a small JSON blob.
+//!
+//! Layout:
+//!
+//! ```text
+//! magic "FLAMECAT" | version u32
+//! rest_len u64 | rest JSON (profile with every thread's spans removed)
+//! string_count u32 | (len u32, utf-8 bytes)*
+//! per thread: span_count u64, then one column per span field
+//! ```
+
+use std::collections::HashMap;
+
+use flame_cat_protocol::{SharedStr, Span, SpanCategory, SpanKind, ThreadGroup, VisualProfile};
+use thiserror::Error;
+
+use crate::parsers::bytes::{self, LittleEndian, Truncated};
+
+/// File signature at the start of every cache file.
+pub const MAGIC: &[u8; 8] = b"FLAMECAT";
+/// Current cache format version; bump on any layout change.
+pub const VERSION: u32 = 4;
+
+/// Sentinel for absent optional ids and string indices.
+const NONE_U64: u64 = u64::MAX;
+const NONE_U32: u32 = u32::MAX;
+
+/// Encoded size of one span across all fixed-width columns.
+const SPAN_BYTES: usize = 8 + 8 + 8 + 4 + 8 + 8 + 4 + 1 + 4 + 4 + 1 + 4 + 4;
+
+#[derive(Debug, Error)]
+pub enum CacheError {
+    #[error("not a flame.cat cache file")]
+    BadMagic,
+    #[error("unsupported cache version {0} (expected {VERSION})")]
+    UnsupportedVersion(u32),
+    #[error("cache file is truncated")]
+    Truncated,
+    #[error("cache file is corrupt: {0}")]
+    Corrupt(&'static str),
+    #[error("invalid metadata: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+/// Returns `true` if `data` starts with the cache signature.
+pub fn is_cache(data: &[u8]) -> bool {
+    data.starts_with(MAGIC)
+}
+
+/// Serialize `profile` into the columnar cache format.
+pub fn encode(profile: &VisualProfile) -> Vec<u8> {
+    let rest = VisualProfile {
+        meta: profile.meta.clone(),
+        threads: profile
+            .threads
+            .iter()
+            .map(|t| ThreadGroup {
+                id: t.id,
+                name: t.name.clone(),
+                kind: t.kind,
+                sort_key: t.sort_key,
+                spans: Vec::new(),
+                max_depth: t.max_depth,
+                pid: t.pid,
+                tid: t.tid,
+                process_name: t.process_name.clone(),
+            })
+            .collect(),
+        frames: profile.frames.clone(),
+        counters: profile.counters.clone(),
+        async_spans: profile.async_spans.clone(),
+        flow_arrows: profile.flow_arrows.clone(),
+        markers: profile.markers.clone(),
+        instant_events: profile.instant_events.clone(),
+        object_events: profile.object_events.clone(),
+        cpu_samples: profile.cpu_samples.clone(),
+        network_requests: profile.network_requests.clone(),
+        screenshots: profile.screenshots.clone(),
+        binaries: profile.binaries.clone(),
+    };
+    // Serializing plain data structures into a Vec cannot fail.
+    let rest_json = serde_json::to_vec(&rest).unwrap_or_default();
+
+    let mut strings = StringTable::default();
+    let mut columns = Vec::new();
+    for thread in &profile.threads {
+        let spans = &thread.spans;
+        put_u64(&mut columns, spans.len() as u64);
+        for s in spans {
+            put_u64(&mut columns, s.id);
+        }
+        for s in spans {
+            put_f64(&mut columns, s.start);
+        }
+        for s in spans {
+            put_f64(&mut columns, s.end);
+        }
+        for s in spans {
+            put_u32(&mut columns, s.depth);
+        }
+        for s in spans {
+            put_u64(&mut columns, s.parent.unwrap_or(NONE_U64));
+        }
+        for s in spans {
+            put_f64(&mut columns, s.self_value);
+        }
+        for s in spans {
+            let idx = strings.intern(&s.name);
+            put_u32(&mut columns, idx);
+        }
+        for s in spans {
+            columns.push(match s.kind {
+                SpanKind::Event => 0,
+                SpanKind::Sample => 1,
+                SpanKind::Synthetic => 2,
+            });
+        }
+        for s in spans {
+            let idx = s
+                .category
+                .as_ref()
+                .map_or(NONE_U32, |c| strings.intern(&c.name));
+            put_u32(&mut columns, idx);
+        }
+        for s in spans {
+            let idx = s
+                .category
+                .as_ref()
+                .and_then(|c| c.source.as_ref())
+                .map_or(NONE_U32, |src| strings.intern(src));
+            put_u32(&mut columns, idx);
+        }
+        for s in spans {
+            columns.push(u8::from(s.truncated));
+        }
+        for s in spans {
+            put_u32(&mut columns, s.binary.unwrap_or(NONE_U32));
+        }
+        // Inline chains: a length per span, then every chain's names.
+        for s in spans {
+            put_u32(&mut columns, s.inlined.len() as u32);
+        }
+        for name in spans.iter().flat_map(|s| &s.inlined) {
+            let idx = strings.intern(name);
+            put_u32(&mut columns, idx);
+        }
+    }
+
+    let mut out = Vec::with_capacity(16 + rest_json.len() + strings.bytes + columns.len());
+    out.extend_from_slice(MAGIC);
+    put_u32(&mut out, VERSION);
+    put_u64(&mut out, rest_json.len() as u64);
+    out.extend_from_slice(&rest_json);
+    put_u32(&mut out, strings.list.len() as u32);
+    for s in &strings.list {
+        put_u32(&mut out, s.len() as u32);
+        out.extend_from_slice(s.as_bytes());
+    }
+    out.extend_from_slice(&columns);
+    out
+}
+
+/// Load a profile from cache bytes (typically a memory-mapped file).
+pub fn decode(data: &[u8]) -> Result<VisualProfile, CacheError> {
+    let mut r = Reader::new(data);
+    if r.bytes(MAGIC.len())? != MAGIC {
+        return Err(CacheError::BadMagic);
+    }
+    let version = r.u32()?;
+    if version != VERSION {
+        return Err(CacheError::UnsupportedVersion(version));
+    }
+    let rest_len = r.length()?;
+    let mut profile: VisualProfile = serde_json::from_slice(r.bytes(rest_len)?)?;
+
+    let string_count = r.u32()? as usize;
+    let mut strings: Vec<SharedStr> = Vec::with_capacity(string_count.min(data.len()));
+    for _ in 0..string_count {
+        let len = r.u32()? as usize;
+        let s = std::str::from_utf8(r.bytes(len)?)
+            .map_err(|_| CacheError::Corrupt("string table is not UTF-8"))?;
+        strings.push(SharedStr::from(s));
+    }
+    let string = |idx: u32| -> Result<SharedStr, CacheError> {
+        strings
+            .get(idx as usize)
+            .cloned()
+            .ok_or(CacheError::Corrupt("string index out of range"))
+    };
+    let opt_string = |idx: u32| -> Result<Option<SharedStr>, CacheError> {
+        if idx == NONE_U32 {
+            Ok(None)
+        } else {
+            string(idx).map(Some)
+        }
+    };
+
+    for thread in &mut profile.threads {
+        let n = r.length()?;
+        // Reject counts the remaining data cannot possibly hold before allocating.
+        if n.saturating_mul(SPAN_BYTES) > r.remaining() {
+            return Err(CacheError::Truncated);
+        }
+        let ids = r.column(n, Reader::u64)?;
+        let starts = r.column(n, Reader::f64)?;
+        let ends = r.column(n, Reader::f64)?;
+        let depths = r.column(n, Reader::u32)?;
+        let parents = r.column(n, Reader::u64)?;
+        let self_values = r.column(n, Reader::f64)?;
+        let names = r.column(n, Reader::u32)?;
+        let kinds = r.bytes(n)?;
+        let cat_names = r.column(n, Reader::u32)?;
+        let cat_sources = r.column(n, Reader::u32)?;
+        let truncated = r.bytes(n)?;
+        let binaries = r.column(n, Reader::u32)?;
+        let inline_lens = r.column(n, Reader::u32)?;
+        let inline_total: usize = inline_lens.iter().map(|&len| len as usize).sum();
+        let mut inline_names = r.column(inline_total, Reader::u32)?.into_iter();
+
+        let mut spans = Vec::with_capacity(n);
+        for i in 0..n {
+            let kind = match kinds[i] {
+                0 => SpanKind::Event,
+                1 => SpanKind::Sample,
+                2 => SpanKind::Synthetic,
+                _ => return Err(CacheError::Corrupt("unknown span kind")),
+            };
+            let category = opt_string(cat_names[i])?
+                .map(|name| {
+                    Ok::<_, CacheError>(SpanCategory {
+                        name,
+                        source: opt_string(cat_sources[i])?,
+                    })
+                })
+                .transpose()?;
+            spans.push(Span {
+                id: ids[i],
+                name: string(names[i])?,
+                start: starts[i],
+                end: ends[i],
+                depth: depths[i],
+                parent: (parents[i] != NONE_U64).then_some(parents[i]),
+                self_value: self_values[i],
+                kind,
+                category,
+                truncated: truncated[i] != 0,
+                binary: (binaries[i] != NONE_U32).then_some(binaries[i]),
+                inlined: inline_names
+                    .by_ref()
+                    .take(inline_lens[i] as usize)
+                    .map(string)
+                    .collect::<Result<_, _>>()?,
+            });
+        }
+        thread.spans = spans;
+    }
+    Ok(profile)
+}
+
+/// Write `profile` to a cache file at `path`.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn write_cache(
+    path: impl AsRef<std::path::Path>,
+    profile: &VisualProfile,
+) -> std::io::Result<()> {
+    std::fs::write(path, encode(profile))
+}
+
+/// Memory-map and load a cache file written by [`write_cache`].
+#[cfg(not(target_arch = "wasm32"))]
+pub fn read_cache(
+    path: impl AsRef<std::path::Path>,
+) -> Result<VisualProfile, crate::parsers::ParseError> {
+    let data = crate::input::MappedFile::open(path)?;
+    Ok(decode(&data)?)
+}
+
+/// Interns strings into a dense index space.
+#[derive(Default)]
+struct StringTable<'a> {
+    index: HashMap<&'a str, u32>,
+    list: Vec<&'a str>,
+    bytes: usize,
+}
+
+impl<'a> StringTable<'a> {
+    fn intern(&mut self, s: &'a str) -> u32 {
+        if let Some(&idx) = self.index.get(s) {
+            return idx;
+        }
+        let idx = self.list.len() as u32;
+        self.index.insert(s, idx);
+        self.list.push(s);
+        self.bytes += 4 + s.len();
+        idx
+    }
+}
+
+fn put_u32(out: &mut Vec<u8>, v: u32) {
+    out.extend_from_slice(&v.to_le_bytes());
+}
+
+fn put_u64(out: &mut Vec<u8>, v: u64) {
+    out.extend_from_slice(&v.to_le_bytes());
+}
+
+fn put_f64(out: &mut Vec<u8>, v: f64) {
+    out.extend_from_slice(&v.to_le_bytes());
+}
+
+impl From<Truncated> for CacheError {
+    fn from(_: Truncated) -> Self {
+        Self::Truncated
+    }
+}
+
+type Reader<'a> = bytes::Reader<'a, LittleEndian>;
+
+impl Reader<'_> {
+    /// Read a `u64` length, rejecting values past the end of the data.
+    fn length(&mut self) -> Result<usize, CacheError> {
+        let n = self.u64()?;
+        usize::try_from(n)
+            .ok()
+            .filter(|&n| n <= self.remaining())
+            .ok_or(CacheError::Truncated)
+    }
+
+    fn column<T>(
+        &mut self,
+        n: usize,
+        read: fn(&mut Self) -> Result<T, Truncated>,
+    ) -> Result<Vec<T>, CacheError> {
+        Ok((0..n).map(|_| read(self)).collect::<Result<_, _>>()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parsers::chrome::parse_chrome_trace;
+
+    fn sample_profile() -> VisualProfile {
+        let json = r#"{"traceEvents":[
+            {"name":"thread_name","ph":"M","ts":0,"pid":1,"tid":1,"args":{"name":"Main"}},
+            {"name":"main","ph":"X","ts":0,"dur":100,"pid":1,"tid":1,"cat":"js"},
+            {"name":"child","ph":"X","ts":10,"dur":50,"pid":1,"tid":1},
+            {"name":"worker","ph":"X","ts":5,"dur":20,"pid":1,"tid":2},
+            {"name":"mark","ph":"R","ts":30,"pid":1,"tid":1},
+            {"name":"open","ph":"B","ts":40,"pid":1,"tid":2}
+        ]}"#;
+        parse_chrome_trace(json.as_bytes())
+            .unwrap()
+            .into_visual_profile()
+    }
+
+    #[test]
+    fn round_trip_preserves_spans_and_extras() {
+        let mut profile = sample_profile();
+        profile.binaries.push(flame_cat_protocol::Binary {
+            name: "app".into(),
+            build_id: Some("abc123".into()),
+        });
+        profile.threads[0].spans[0].binary = Some(0);
+        profile.threads[0].spans[1].inlined = vec!["push".into(), "grow".into()];
+        let bytes = encode(&profile);
+        assert!(is_cache(&bytes));
+
+        let loaded = decode(&bytes).unwrap();
+        assert_eq!(loaded.threads.len(), profile.threads.len());
+        for (a, b) in loaded.threads.iter().zip(&profile.threads) {
+            assert_eq!(a.name, b.name);
+            assert_eq!(a.max_depth, b.max_depth);
+            assert_eq!(a.spans.len(), b.spans.len());
+            for (x, y) in a.spans.iter().zip(&b.spans) {
+                assert_eq!(x.id, y.id);
+                assert_eq!(x.name, y.name);
+                assert_eq!(x.start, y.start);
+                assert_eq!(x.end, y.end);
+                assert_eq!(x.depth, y.depth);
+                assert_eq!(x.parent, y.parent);
+                assert_eq!(x.self_value, y.self_value);
+                assert_eq!(x.truncated, y.truncated);
+                assert_eq!(x.binary, y.binary);
+                assert_eq!(x.inlined, y.inlined);
+                assert_eq!(
+                    x.category.as_ref().map(|c| c.name.clone()),
+                    y.category.as_ref().map(|c| c.name.clone())
+                );
+            }
+        }
+        assert_eq!(loaded.markers.len(), profile.markers.len());
+        assert_eq!(loaded.binaries, profile.binaries);
+        assert_eq!(loaded.meta.end_time, profile.meta.end_time);
+        assert_eq!(loaded.meta.truncated_spans, 1);
+    }
+
+    #[test]
+    fn rejects_bad_input() {
+        assert!(matches!(decode(b"nope"), Err(CacheError::Truncated)));
+        assert!(matches!(
+            decode(b"NOTACACHEFILE"),
+            Err(CacheError::BadMagic)
+        ));
+
+        let mut bytes = encode(&sample_profile());
+        bytes[8] = 99;
+        assert!(matches!(
+            decode(&bytes),
+            Err(CacheError::UnsupportedVersion(99))
+        ));
+
+        let bytes = encode(&sample_profile());
+        for cut in [12, 20, bytes.len() / 2, bytes.len() - 1] {
+            assert!(decode(&bytes[..cut]).is_err(), "accepted {cut}-byte prefix");
+        }
+    }
+}
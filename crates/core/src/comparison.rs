@@ -0,0 +1,606 @@
+//! Comparison reports: how a session's primary profile differs from its
+//! baseline, as Markdown or a standalone HTML page, for attaching to
+//! regression reports.
+
+use std::collections::HashSet;
+use std::path::Path;
+
+use flame_cat_protocol::{
+    CounterTrack, CounterUnit, DurationFormat, FrameTiming, ProfileMeta, SharedStr,
+};
+use thiserror::Error;
+
+use crate::markdown::code;
+use crate::model::{DiffNormalization, Session};
+use crate::svg::escape_xml;
+use crate::views::counter::format_counter_value;
+use crate::views::ranked::{Baseline, RankedEntry, RankedGroup, RankedSort, get_ranked_entries};
+
+/// Rows listed per table of a report.
+pub const REPORT_ROWS: usize = 25;
+
+#[derive(Debug, Error)]
+pub enum ComparisonError {
+    #[error("a comparison needs at least two profiles, the session has {0}")]
+    NotEnoughProfiles(usize),
+}
+
+/// Document format of a comparison report.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportFormat {
+    Markdown,
+    Html,
+}
+
+impl ReportFormat {
+    /// Parse `markdown`/`md` or `html`/`htm`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "markdown" | "md" => Some(Self::Markdown),
+            "html" | "htm" => Some(Self::Html),
+            _ => None,
+        }
+    }
+
+    /// The format matching `path`'s extension, Markdown when it has none
+    /// or an unknown one.
+    pub fn for_path(path: &Path) -> Self {
+        path.extension()
+            .and_then(|ext| ext.to_str())
+            .and_then(Self::from_name)
+            .unwrap_or(Self::Markdown)
+    }
+}
+
+/// A function present in both profiles, with the baseline's times
+/// normalized.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FunctionDelta {
+    pub name: SharedStr,
+    pub self_time: f64,
+    pub total_time: f64,
+    pub baseline_self_time: f64,
+    pub baseline_total_time: f64,
+}
+
+impl FunctionDelta {
+    pub fn self_delta(&self) -> f64 {
+        self.self_time - self.baseline_self_time
+    }
+}
+
+/// A counter recorded by both profiles under the same name and unit.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CounterDelta {
+    pub name: SharedStr,
+    pub unit: CounterUnit,
+    /// Mean of the samples.
+    pub mean: f64,
+    pub peak: f64,
+    pub baseline_mean: f64,
+    pub baseline_peak: f64,
+}
+
+/// Frame timing summary of one profile, in its value unit.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct FrameStats {
+    pub count: usize,
+    pub dropped: usize,
+    pub mean: f64,
+    pub p95: f64,
+}
+
+impl FrameStats {
+    /// Summary of `frames`, `None` when there are none.
+    pub fn of(frames: &[FrameTiming]) -> Option<Self> {
+        if frames.is_empty() {
+            return None;
+        }
+        let mut durations: Vec<f64> = frames.iter().map(|f| f.duration).collect();
+        durations.sort_by(f64::total_cmp);
+        let p95 = (durations.len() * 95).div_ceil(100).max(1) - 1;
+        Some(Self {
+            count: frames.len(),
+            dropped: frames.iter().filter(|f| f.dropped).count(),
+            mean: durations.iter().sum::<f64>() / durations.len() as f64,
+            p95: durations[p95],
+        })
+    }
+}
+
+/// The differences between a session's primary profile and its baseline.
+#[derive(Debug, Clone)]
+pub struct Comparison {
+    pub label: String,
+    pub baseline_label: String,
+    pub normalization: DiffNormalization,
+    /// Functions in both profiles whose times changed, biggest self-time
+    /// change first.
+    pub changed: Vec<FunctionDelta>,
+    /// Functions only in the primary profile, by self time.
+    pub added: Vec<RankedEntry>,
+    /// Functions only in the baseline, by normalized self time.
+    pub removed: Vec<RankedEntry>,
+    /// Counters recorded by both profiles, by name.
+    pub counters: Vec<CounterDelta>,
+    /// Frame timing of the primary profile and the baseline, when either
+    /// has frames.
+    pub frames: Option<(FrameStats, FrameStats)>,
+    meta: ProfileMeta,
+    baseline_meta: ProfileMeta,
+}
+
+impl Comparison {
+    /// Compare the session's primary profile with its marked baseline, or
+    /// with the second profile when none is marked. Tables are capped at
+    /// [`REPORT_ROWS`] rows.
+    pub fn new(session: &Session) -> Result<Self, ComparisonError> {
+        let profiles = session.profiles();
+        if profiles.len() < 2 {
+            return Err(ComparisonError::NotEnoughProfiles(profiles.len()));
+        }
+        let index = session.baseline_index().filter(|&i| i != 0).unwrap_or(1);
+        let (primary, base) = (&profiles[0], &profiles[index]);
+        let normalization = session.diff_normalization();
+        let baseline = Baseline::normalized(&base.profile, &primary.profile, normalization);
+        let unit = primary.profile.meta.value_unit;
+
+        let entries = get_ranked_entries(
+            &primary.profile,
+            RankedGroup::Name,
+            RankedSort::SelfTime,
+            false,
+        );
+        let base_entries = get_ranked_entries(
+            &base.profile,
+            RankedGroup::Name,
+            RankedSort::SelfTime,
+            false,
+        );
+        let base_names: HashSet<&str> = base_entries.iter().map(|e| &*e.name).collect();
+        let names: HashSet<&str> = entries.iter().map(|e| &*e.name).collect();
+
+        let mut changed = Vec::new();
+        let mut added = Vec::new();
+        for entry in &entries {
+            if !base_names.contains(&*entry.name) {
+                added.push(entry.clone());
+            } else if let Some(old) = baseline.entry(&entry.name, unit)
+                && (old.self_time != entry.self_time || old.total_time != entry.total_time)
+            {
+                changed.push(FunctionDelta {
+                    name: entry.name.clone(),
+                    self_time: entry.self_time,
+                    total_time: entry.total_time,
+                    baseline_self_time: old.self_time,
+                    baseline_total_time: old.total_time,
+                });
+            }
+        }
+        changed.sort_by(|a, b| {
+            b.self_delta()
+                .abs()
+                .total_cmp(&a.self_delta().abs())
+                .then_with(|| a.name.cmp(&b.name))
+        });
+        changed.truncate(REPORT_ROWS);
+        added.truncate(REPORT_ROWS);
+        let removed = base_entries
+            .iter()
+            .filter(|e| !names.contains(&*e.name))
+            .filter_map(|e| baseline.entry(&e.name, unit))
+            .take(REPORT_ROWS)
+            .collect();
+
+        let mut counters: Vec<CounterDelta> = primary
+            .profile
+            .counters
+            .iter()
+            .filter_map(|track| {
+                let old = base
+                    .profile
+                    .counters
+                    .iter()
+                    .find(|t| t.name == track.name && t.unit == track.unit)?;
+                let (mean, peak) = counter_stats(track)?;
+                let (baseline_mean, baseline_peak) = counter_stats(old)?;
+                Some(CounterDelta {
+                    name: track.name.clone(),
+                    unit: track.unit,
+                    mean,
+                    peak,
+                    baseline_mean,
+                    baseline_peak,
+                })
+            })
+            .collect();
+        counters.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let frames = match (
+            FrameStats::of(&primary.profile.frames),
+            FrameStats::of(&base.profile.frames),
+        ) {
+            (None, None) => None,
+            (frames, old) => Some((frames.unwrap_or_default(), old.unwrap_or_default())),
+        };
+
+        Ok(Self {
+            label: primary.label.clone(),
+            baseline_label: base.label.clone(),
+            normalization,
+            changed,
+            added,
+            removed,
+            counters,
+            frames,
+            meta: primary.profile.meta.clone(),
+            baseline_meta: base.profile.meta.clone(),
+        })
+    }
+
+    /// The report as a `report` document, with times written as `format`
+    /// asks.
+    pub fn render(&self, report: ReportFormat, format: &DurationFormat) -> String {
+        let title = format!("Comparison: {} vs {}", self.label, self.baseline_label);
+        let intro = match self.normalization {
+            DiffNormalization::PerSample => "Baseline values are compared as recorded.",
+            DiffNormalization::PerSecond => {
+                "Baseline values are scaled to the same recording length."
+            }
+            DiffNormalization::PerTotal => "Baseline values are scaled to the same total.",
+        };
+        let tables = self.tables(format);
+        match report {
+            ReportFormat::Markdown => markdown(&title, intro, &tables),
+            ReportFormat::Html => html(&title, intro, &tables),
+        }
+    }
+
+    fn tables(&self, format: &DurationFormat) -> Vec<Table> {
+        let value = |v: f64| self.meta.format_value_with(v, format);
+        let time_change = |v: f64, old: f64| {
+            change(v, old, format, |d| {
+                self.meta.value_unit.format_value_with(d, format)
+            })
+        };
+        let mut tables = vec![Table {
+            title: "Summary",
+            headers: vec![
+                String::new(),
+                self.baseline_label.clone(),
+                self.label.clone(),
+            ],
+            code: false,
+            rows: vec![
+                vec![
+                    "Total".into(),
+                    self.baseline_meta
+                        .format_value_with(self.baseline_meta.total_value, format),
+                    value(self.meta.total_value),
+                ],
+                vec![
+                    "Format".into(),
+                    self.baseline_meta.source_format.to_string(),
+                    self.meta.source_format.to_string(),
+                ],
+            ],
+        }];
+        tables.push(Table {
+            title: "Biggest changes",
+            headers: header(&[
+                "Function",
+                "Baseline self",
+                "Self",
+                "Change",
+                "Baseline total",
+                "Total",
+            ]),
+            code: true,
+            rows: self
+                .changed
+                .iter()
+                .map(|d| {
+                    vec![
+                        d.name.to_string(),
+                        value(d.baseline_self_time),
+                        value(d.self_time),
+                        time_change(d.self_time, d.baseline_self_time),
+                        value(d.baseline_total_time),
+                        value(d.total_time),
+                    ]
+                })
+                .collect(),
+        });
+        let entry_rows = |entries: &[RankedEntry]| {
+            entries
+                .iter()
+                .map(|e| {
+                    vec![
+                        e.name.to_string(),
+                        value(e.self_time),
+                        value(e.total_time),
+                        e.count.to_string(),
+                    ]
+                })
+                .collect()
+        };
+        tables.push(Table {
+            title: "New functions",
+            headers: header(&["Function", "Self", "Total", "Calls"]),
+            code: true,
+            rows: entry_rows(&self.added),
+        });
+        tables.push(Table {
+            title: "Removed functions",
+            headers: header(&["Function", "Baseline self", "Baseline total", "Calls"]),
+            code: true,
+            rows: entry_rows(&self.removed),
+        });
+        tables.push(Table {
+            title: "Counters",
+            headers: header(&[
+                "Counter",
+                "Baseline mean",
+                "Mean",
+                "Change",
+                "Baseline peak",
+                "Peak",
+            ]),
+            code: false,
+            rows: self
+                .counters
+                .iter()
+                .map(|c| {
+                    let counter = |v: f64| format_counter_value(v, &c.unit);
+                    vec![
+                        c.name.to_string(),
+                        counter(c.baseline_mean),
+                        counter(c.mean),
+                        change(c.mean, c.baseline_mean, format, counter),
+                        counter(c.baseline_peak),
+                        counter(c.peak),
+                    ]
+                })
+                .collect(),
+        });
+        if let Some((frames, old)) = self.frames {
+            let old_value = |v: f64| self.baseline_meta.format_value_with(v, format);
+            tables.push(Table {
+                title: "Frames",
+                headers: vec![
+                    String::new(),
+                    self.baseline_label.clone(),
+                    self.label.clone(),
+                ],
+                code: false,
+                rows: vec![
+                    vec![
+                        "Frames".into(),
+                        old.count.to_string(),
+                        frames.count.to_string(),
+                    ],
+                    vec![
+                        "Dropped".into(),
+                        old.dropped.to_string(),
+                        frames.dropped.to_string(),
+                    ],
+                    vec!["Mean".into(), old_value(old.mean), value(frames.mean)],
+                    vec!["p95".into(), old_value(old.p95), value(frames.p95)],
+                ],
+            });
+        }
+        tables.retain(|t| !t.rows.is_empty());
+        tables
+    }
+}
+
+/// Mean and peak of a counter's samples.
+fn counter_stats(track: &CounterTrack) -> Option<(f64, f64)> {
+    if track.samples.is_empty() {
+        return None;
+    }
+    let sum: f64 = track.samples.iter().map(|s| s.value).sum();
+    let peak = track
+        .samples
+        .iter()
+        .map(|s| s.value)
+        .fold(f64::NEG_INFINITY, f64::max);
+    Some((sum / track.samples.len() as f64, peak))
+}
+
+/// `+x (+y%)` from `old` to `value`, with the magnitude written by `fmt`.
+fn change(value: f64, old: f64, format: &DurationFormat, fmt: impl Fn(f64) -> String) -> String {
+    let delta = value - old;
+    let sign = if delta < 0.0 { '-' } else { '+' };
+    let magnitude = fmt(delta.abs());
+    if old > 0.0 {
+        let percent = format.number((delta / old * 100.0).abs(), 1);
+        format!("{sign}{magnitude} ({sign}{percent}%)")
+    } else {
+        format!("{sign}{magnitude}")
+    }
+}
+
+struct Table {
+    title: &'static str,
+    headers: Vec<String>,
+    /// Whether the first column holds function names, set as code.
+    code: bool,
+    rows: Vec<Vec<String>>,
+}
+
+fn header(names: &[&str]) -> Vec<String> {
+    names.iter().map(|name| (*name).to_owned()).collect()
+}
+
+fn markdown(title: &str, intro: &str, tables: &[Table]) -> String {
+    let cell = |text: &str| text.replace('|', "\\|");
+    let mut out = format!("# {title}\n\n{intro}\n");
+    for table in tables {
+        out.push_str(&format!("\n## {}\n\n", table.title));
+        let headers: Vec<String> = table.headers.iter().map(|h| cell(h)).collect();
+        out.push_str(&format!("| {} |\n", headers.join(" | ")));
+        let align: Vec<&str> = (0..headers.len())
+            .map(|i| if i == 0 { "---" } else { "---:" })
+            .collect();
+        out.push_str(&format!("| {} |\n", align.join(" | ")));
+        for row in &table.rows {
+            let cells: Vec<String> = row
+                .iter()
+                .enumerate()
+                .map(|(i, text)| {
+                    if i == 0 && table.code {
+                        code(text)
+                    } else {
+                        cell(text)
+                    }
+                })
+                .collect();
+            out.push_str(&format!("| {} |\n", cells.join(" | ")));
+        }
+    }
+    out
+}
+
+const HTML_STYLE: &str = "body{font-family:system-ui,sans-serif;margin:2em;color:#222}\
+table{border-collapse:collapse;margin-bottom:1.5em}\
+th,td{border:1px solid #ccc;padding:4px 8px;text-align:right}\
+th:first-child,td:first-child{text-align:left}";
+
+fn html(title: &str, intro: &str, tables: &[Table]) -> String {
+    let title = escape_xml(title);
+    let mut out = format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <title>{title}</title>\n<style>{HTML_STYLE}</style>\n</head>\n<body>\n\
+         <h1>{title}</h1>\n<p>{}</p>\n",
+        escape_xml(intro)
+    );
+    for table in tables {
+        out.push_str(&format!("<h2>{}</h2>\n<table>\n<thead><tr>", table.title));
+        for header in &table.headers {
+            out.push_str(&format!("<th>{}</th>", escape_xml(header)));
+        }
+        out.push_str("</tr></thead>\n<tbody>\n");
+        for row in &table.rows {
+            out.push_str("<tr>");
+            for (i, text) in row.iter().enumerate() {
+                let text = escape_xml(text);
+                if i == 0 && table.code {
+                    out.push_str(&format!("<td><code>{text}</code></td>"));
+                } else {
+                    out.push_str(&format!("<td>{text}</td>"));
+                }
+            }
+            out.push_str("</tr>\n");
+        }
+        out.push_str("</tbody>\n</table>\n");
+    }
+    out.push_str("</body>\n</html>\n");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use flame_cat_protocol::{CounterSample, VisualProfile};
+
+    use super::*;
+    use crate::parsers::chrome::parse_chrome_trace;
+
+    fn trace(events: &str) -> VisualProfile {
+        parse_chrome_trace(format!(r#"{{"traceEvents":[{events}]}}"#).as_bytes())
+            .unwrap()
+            .into_visual_profile()
+    }
+
+    fn counter(values: &[f64]) -> CounterTrack {
+        CounterTrack {
+            name: "JS Heap".into(),
+            unit: CounterUnit::Bytes,
+            samples: values
+                .iter()
+                .enumerate()
+                .map(|(i, &value)| CounterSample {
+                    ts: i as f64,
+                    value,
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn compares_primary_against_baseline() {
+        let mut before = trace(
+            r#"{"name":"main","ph":"X","ts":0,"dur":1000,"pid":1,"tid":1},
+               {"name":"parse","ph":"X","ts":0,"dur":400,"pid":1,"tid":1},
+               {"name":"legacy","ph":"X","ts":500,"dur":100,"pid":1,"tid":1}"#,
+        );
+        let mut after = trace(
+            r#"{"name":"main","ph":"X","ts":0,"dur":1000,"pid":1,"tid":1},
+               {"name":"parse","ph":"X","ts":0,"dur":700,"pid":1,"tid":1},
+               {"name":"a|b","ph":"X","ts":800,"dur":50,"pid":1,"tid":1}"#,
+        );
+        before.counters.push(counter(&[1024.0, 3072.0]));
+        after.counters.push(counter(&[4096.0]));
+        let mut session = Session::from_profile(after, "after");
+        session.add_profile(before, "before");
+        session.set_baseline(Some(1)).unwrap();
+
+        let comparison = Comparison::new(&session).unwrap();
+        let changed: Vec<(&str, f64)> = comparison
+            .changed
+            .iter()
+            .map(|d| (&*d.name, d.self_delta()))
+            .collect();
+        assert_eq!(changed, [("parse", 300.0), ("main", -250.0)]);
+        let added: Vec<&str> = comparison.added.iter().map(|e| &*e.name).collect();
+        assert_eq!(added, ["a|b"]);
+        let removed: Vec<&str> = comparison.removed.iter().map(|e| &*e.name).collect();
+        assert_eq!(removed, ["legacy"]);
+        let heap = &comparison.counters[0];
+        assert_eq!((heap.mean, heap.baseline_mean), (4096.0, 2048.0));
+        assert_eq!((heap.peak, heap.baseline_peak), (4096.0, 3072.0));
+        assert!(comparison.frames.is_none());
+
+        let format = DurationFormat::default();
+        let markdown = comparison.render(ReportFormat::Markdown, &format);
+        assert!(markdown.starts_with("# Comparison: after vs before\n"));
+        assert!(markdown.contains("| `a\\|b` |"));
+        assert!(markdown.contains("## Removed functions"));
+        assert!(markdown.contains("(+100.0%)"));
+        let html = comparison.render(ReportFormat::Html, &format);
+        assert!(html.contains("<h2>Counters</h2>"));
+        assert!(html.contains("<td><code>a|b</code></td>"));
+        assert!(!html.contains("## "));
+    }
+
+    #[test]
+    fn needs_two_profiles_and_summarizes_frames() {
+        let session = Session::from_profile(trace(""), "only");
+        assert!(matches!(
+            Comparison::new(&session),
+            Err(ComparisonError::NotEnoughProfiles(1))
+        ));
+
+        let frames: Vec<FrameTiming> = (1..=20)
+            .map(|i| FrameTiming {
+                start: 0.0,
+                end: 0.0,
+                duration: f64::from(i),
+                dropped: i > 16,
+            })
+            .collect();
+        let stats = FrameStats::of(&frames).unwrap();
+        assert_eq!((stats.count, stats.dropped), (20, 4));
+        assert_eq!((stats.mean, stats.p95), (10.5, 19.0));
+        assert_eq!(FrameStats::of(&[]), None);
+
+        assert_eq!(
+            ReportFormat::for_path(Path::new("out/diff.HTML")),
+            ReportFormat::Html
+        );
+        assert_eq!(
+            ReportFormat::for_path(Path::new("diff")),
+            ReportFormat::Markdown
+        );
+    }
+}
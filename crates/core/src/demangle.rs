@@ -0,0 +1,75 @@
+//! Demangling C++ and Rust symbol names.
+//!
+//! perf, pprof and eBPF record native frames by their linker symbol, so a
+//! Rust function shows up as `_ZN4core3ptr13drop_in_place17h9b1c…E` and a
+//! C++ one as `_ZNSt6vectorIiSaIiEE9push_backERKi`. Parsers of those
+//! formats run their frames through [`demangle_frames`] unless the caller
+//! asked for raw names (see [`crate::parsers::FormatHint::raw_names`]).
+//!
+//! Rust symbols (legacy `_ZN…E` and v0 `_R…`) are tried first, without
+//! their hash suffix; anything else starting with `_Z` is read as an
+//! Itanium C++ symbol. Names that fail to demangle are left unchanged.
+
+use std::collections::HashMap;
+
+use crate::model::Frame;
+
+/// The readable form of a mangled `symbol`, or `None` if it is not a C++
+/// or Rust symbol.
+pub fn demangle(symbol: &str) -> Option<String> {
+    if let Ok(rust) = rustc_demangle::try_demangle(symbol) {
+        return Some(format!("{rust:#}"));
+    }
+    // Mach-O prefixes every symbol with an extra underscore.
+    let cpp = symbol
+        .strip_prefix('_')
+        .filter(|s| s.starts_with("_Z"))
+        .unwrap_or(symbol);
+    if !cpp.starts_with("_Z") {
+        return None;
+    }
+    cpp_demangle::Symbol::new(cpp).ok()?.demangle().ok()
+}
+
+/// Demangle every frame name that is a C++ or Rust symbol.
+pub fn demangle_frames(frames: &mut [Frame]) {
+    // Sampled profiles repeat the same few thousand symbols many times.
+    let mut demangled: HashMap<String, Option<String>> = HashMap::new();
+    for frame in frames {
+        if !frame.name.starts_with("_Z")
+            && !frame.name.starts_with("__Z")
+            && !frame.name.starts_with("_R")
+        {
+            continue;
+        }
+        let name = demangled
+            .entry(frame.name.clone())
+            .or_insert_with_key(|symbol| demangle(symbol));
+        if let Some(name) = name {
+            frame.name.clone_from(name);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn demangles_rust_and_cpp_symbols() {
+        assert_eq!(
+            demangle("_ZN4core3ptr13drop_in_place17h0123456789abcdefE").as_deref(),
+            Some("core::ptr::drop_in_place")
+        );
+        assert_eq!(
+            demangle("_RNvCs1234_7mycrate3foo").as_deref(),
+            Some("mycrate::foo")
+        );
+        assert_eq!(
+            demangle("_ZNSt6vectorIiSaIiEE9push_backERKi").as_deref(),
+            Some("std::vector<int, std::allocator<int> >::push_back(int const&)")
+        );
+        assert_eq!(demangle("__ZN3foo3barEv").as_deref(), Some("foo::bar()"));
+        assert_eq!(demangle("main"), None);
+    }
+}
@@ -0,0 +1,201 @@
+//! Pre-batched quad geometry for GPU renderers.
+//!
+//! A [`RenderCommand`] list costs the host one object per rect. A WebGL or
+//! WebGPU renderer drawing a million spans per frame wants flat instance
+//! buffers instead: [`batch_geometry`] packs the rects of a command list
+//! into parallel arrays, one instance per quad, with colors already
+//! resolved for the theme and color mode, ready to upload as typed arrays.
+
+use std::collections::HashMap;
+
+use flame_cat_protocol::{ColorMode, RenderCommand, SharedStr, ThemeToken, color_key, color_rgb};
+
+use crate::svg::resolve_color;
+
+/// [`GeometryBatch::label_ids`] entry of quads without a label.
+pub const NO_LABEL: u32 = u32::MAX;
+
+/// Alpha of dimmed quads, the canvas renderers' search fade.
+const DIMMED_ALPHA: u8 = 40;
+/// Alpha of overlaid runs, which stay see-through.
+const OVERLAY_ALPHA: u8 = 51;
+
+/// The rects of a command list as instance buffers. Quad `i` has its
+/// entries at `i * 4` in `rects`, `colors` and `border_colors`, and at `i`
+/// in `label_ids` and `frame_ids`.
+#[derive(Debug, Clone, Default)]
+pub struct GeometryBatch {
+    /// `x, y, w, h` of each quad, in the commands' coordinates.
+    pub rects: Vec<f32>,
+    /// `r, g, b, a` fill of each quad.
+    pub colors: Vec<u8>,
+    /// `r, g, b, a` one-pixel border of each quad; alpha 0 for none.
+    pub border_colors: Vec<u8>,
+    /// Index into `labels` of each quad's label, or [`NO_LABEL`].
+    pub label_ids: Vec<u32>,
+    /// Span id of each quad, or -1 for none. `f64` so ids survive the trip
+    /// to JS numbers.
+    pub frame_ids: Vec<f64>,
+    /// Every distinct label once, so repeated names cost one string.
+    pub labels: Vec<SharedStr>,
+    /// The commands that are not rects (text, lines, groups), in order, for
+    /// the host to draw over the quads.
+    pub overlay: Vec<RenderCommand>,
+}
+
+impl GeometryBatch {
+    /// Number of quads.
+    pub fn len(&self) -> usize {
+        self.label_ids.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.label_ids.is_empty()
+    }
+}
+
+/// Pack the rects of `commands` into a [`GeometryBatch`], colored the way
+/// the canvas renderer colors them in `color_mode` with the `dark` or
+/// light palette. Views emit no transforms or clips, so quads keep the
+/// commands' own coordinates.
+pub fn batch_geometry(
+    commands: &[RenderCommand],
+    color_mode: ColorMode,
+    dark: bool,
+) -> GeometryBatch {
+    let rect_count = commands
+        .iter()
+        .filter(|c| matches!(c, RenderCommand::DrawRect { .. }))
+        .count();
+    let mut batch = GeometryBatch {
+        rects: Vec::with_capacity(rect_count * 4),
+        colors: Vec::with_capacity(rect_count * 4),
+        border_colors: Vec::with_capacity(rect_count * 4),
+        label_ids: Vec::with_capacity(rect_count),
+        frame_ids: Vec::with_capacity(rect_count),
+        ..GeometryBatch::default()
+    };
+    let mut label_ids: HashMap<SharedStr, u32> = HashMap::new();
+
+    for cmd in commands {
+        let RenderCommand::DrawRect {
+            rect,
+            color,
+            border_color,
+            label,
+            frame_id,
+            dimmed,
+            span_name,
+        } = cmd
+        else {
+            batch.overlay.push(cmd.clone());
+            continue;
+        };
+        batch
+            .rects
+            .extend([rect.x, rect.y, rect.w, rect.h].map(|v| v as f32));
+
+        let name = span_name.as_ref().or(label.as_ref());
+        let (r, g, b) = match (color_mode, name) {
+            // Kernel frames keep their own color so they stand apart from
+            // user code.
+            (ColorMode::ByName, Some(name)) if *color != ThemeToken::KernelSpan => {
+                color_rgb(color_key(name), dark)
+            }
+            _ => token_rgb(*color, dark),
+        };
+        let a = if *dimmed {
+            DIMMED_ALPHA
+        } else if *color == ThemeToken::OverlaySpanFill {
+            OVERLAY_ALPHA
+        } else {
+            u8::MAX
+        };
+        batch.colors.extend([r, g, b, a]);
+        match border_color {
+            Some(token) => {
+                let (r, g, b) = token_rgb(*token, dark);
+                batch.border_colors.extend([r, g, b, u8::MAX]);
+            }
+            None => batch.border_colors.extend([0; 4]),
+        }
+
+        let label_id = label.as_ref().map_or(NO_LABEL, |label| {
+            *label_ids.entry(label.clone()).or_insert_with(|| {
+                batch.labels.push(label.clone());
+                (batch.labels.len() - 1) as u32
+            })
+        });
+        batch.label_ids.push(label_id);
+        batch.frame_ids.push(frame_id.map_or(-1.0, |id| id as f64));
+    }
+    batch
+}
+
+/// RGB of a theme token in the export palette.
+fn token_rgb(token: ThemeToken, dark: bool) -> (u8, u8, u8) {
+    let hex = resolve_color(token, dark).trim_start_matches('#');
+    let channel = |at: usize| {
+        hex.get(at..at + 2)
+            .and_then(|c| u8::from_str_radix(c, 16).ok())
+            .unwrap_or(0)
+    };
+    (channel(0), channel(2), channel(4))
+}
+
+#[cfg(test)]
+mod tests {
+    use flame_cat_protocol::{Point, Rect, TextAlign};
+
+    use super::*;
+
+    fn rect(x: f64, label: Option<&str>, frame_id: Option<u64>, dimmed: bool) -> RenderCommand {
+        RenderCommand::DrawRect {
+            rect: Rect::new(x, 20.0, 50.0, 18.0),
+            color: ThemeToken::FlameHot,
+            border_color: None,
+            label: label.map(SharedStr::from),
+            frame_id,
+            dimmed,
+            span_name: None,
+        }
+    }
+
+    #[test]
+    fn packs_rects_into_instance_buffers() {
+        let text = RenderCommand::DrawText {
+            position: Point { x: 0.0, y: 0.0 },
+            text: "Main".into(),
+            color: ThemeToken::TextPrimary,
+            font_size: 11.0,
+            align: TextAlign::Left,
+        };
+        let commands = [
+            rect(0.0, Some("react::render"), Some(7), false),
+            text,
+            rect(50.0, Some("react::render"), None, true),
+            rect(100.0, None, Some(9), false),
+        ];
+
+        let batch = batch_geometry(&commands, ColorMode::ByDepth, false);
+        assert_eq!(batch.len(), 3);
+        assert_eq!(&batch.rects[4..8], [50.0, 20.0, 50.0, 18.0]);
+        // FlameHot in the light palette is #dc3c14; the dimmed quad fades.
+        assert_eq!(&batch.colors[..4], [0xdc, 0x3c, 0x14, 255]);
+        assert_eq!(&batch.colors[4..8], [0xdc, 0x3c, 0x14, DIMMED_ALPHA]);
+        assert_eq!(batch.border_colors, [0; 12]);
+        assert_eq!(batch.labels, [SharedStr::from("react::render")]);
+        assert_eq!(batch.label_ids, [0, 0, NO_LABEL]);
+        assert_eq!(batch.frame_ids, [7.0, -1.0, 9.0]);
+        assert!(matches!(
+            batch.overlay.as_slice(),
+            [RenderCommand::DrawText { .. }]
+        ));
+
+        let (r, g, b) = color_rgb("react", true);
+        let by_name = batch_geometry(&commands, ColorMode::ByName, true);
+        assert_eq!(&by_name.colors[..4], [r, g, b, 255]);
+        // Unlabeled quads fall back to their token.
+        assert_eq!(&by_name.colors[8..], [0xf3, 0x8b, 0xa8, 255]);
+    }
+}
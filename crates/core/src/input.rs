@@ -0,0 +1,110 @@
+//! Native file input backed by memory maps.
+//!
+//! Mapping a trace instead of reading it into a `Vec<u8>` lets the OS page
+//! the raw bytes in on demand, so a multi-gigabyte trace doesn't need the same
+//! amount of heap before parsing even starts. Not available on wasm32.
+
+use std::fs::File;
+use std::ops::Deref;
+use std::path::Path;
+
+use flame_cat_protocol::VisualProfile;
+
+use crate::model::Profile;
+use crate::parsers::{self, ParseError, chrome};
+
+/// A read-only memory-mapped file that derefs to its bytes.
+pub struct MappedFile {
+    map: memmap2::Mmap,
+}
+
+impl MappedFile {
+    /// Memory-map the file at `path`.
+    pub fn open(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        let file = File::open(path)?;
+        // SAFETY: the map is read-only and only ever viewed as `&[u8]`. If
+        // another process truncates the file while it is mapped, reads may
+        // fault — the same trade-off every mmap-based reader accepts.
+        #[allow(unsafe_code)] // memmap2 exposes mapping only through an unsafe constructor
+        let map = unsafe { memmap2::Mmap::map(&file)? };
+        Ok(Self { map })
+    }
+}
+
+impl AsRef<[u8]> for MappedFile {
+    fn as_ref(&self) -> &[u8] {
+        &self.map
+    }
+}
+
+impl Deref for MappedFile {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &self.map
+    }
+}
+
+/// Memory-map `path` and parse it, auto-detecting the format.
+///
+/// Chrome traces are recognised from their first few kilobytes and handed
+/// straight to the streaming parser, skipping the generic JSON sniff in
+/// [`parsers::parse_auto`] that would otherwise build a full value tree.
+pub fn parse_file(path: impl AsRef<Path>) -> Result<Profile, ParseError> {
+    let data = MappedFile::open(path)?;
+    if chrome::looks_like_trace(&data)
+        && let Ok(profile) = chrome::parse_chrome_trace(&data)
+    {
+        return Ok(profile);
+    }
+    parsers::parse_auto(&data)
+}
+
+/// Memory-map `path` and parse it into the canonical `VisualProfile`.
+///
+/// Cache files written by [`crate::cache::write_cache`] load without parsing.
+pub fn parse_file_visual(path: impl AsRef<Path>) -> Result<VisualProfile, ParseError> {
+    let data = MappedFile::open(&path)?;
+    if crate::cache::is_cache(&data) {
+        return Ok(crate::cache::decode(&data)?);
+    }
+    drop(data);
+    parse_file(path).map(Profile::into_visual_profile)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_temp(name: &str, contents: &[u8]) -> std::path::PathBuf {
+        let path = std::env::temp_dir().join(format!("flame-cat-{}-{name}", std::process::id()));
+        std::fs::write(&path, contents).unwrap();
+        path
+    }
+
+    #[test]
+    fn parse_mapped_chrome_trace() {
+        let path = write_temp(
+            "trace.json",
+            br#"{"traceEvents":[{"name":"a","ph":"X","ts":0,"dur":10,"pid":1,"tid":1}]}"#,
+        );
+        let profile = parse_file(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(profile.frames.len(), 1);
+        assert_eq!(profile.metadata.format, "chrome");
+    }
+
+    #[test]
+    fn parse_mapped_falls_back_to_auto_detect() {
+        let path = write_temp("stacks.txt", b"main;foo;bar 10\nmain;baz 5\n");
+        let profile = parse_file(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert!(!profile.frames.is_empty());
+    }
+
+    #[test]
+    fn missing_file_is_io_error() {
+        let err = parse_file("/nonexistent/flame-cat/profile.json").unwrap_err();
+        assert!(matches!(err, ParseError::Io(_)));
+    }
+}
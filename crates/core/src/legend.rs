@@ -0,0 +1,138 @@
+//! Category legend for the by-name coloring scheme.
+//!
+//! [`ColorMode::ByName`](flame_cat_protocol::ColorMode::ByName) colors each
+//! span by the package prefix of its label ([`color_key`]). The legend lists
+//! those keys with their hue and how much of the profile they account for,
+//! so hosts can explain the colors and filter by them.
+//!
+//! [`analysis::breakdown`](crate::analysis::breakdown) totals a profile the
+//! same way per binary, category and code class.
+
+use std::collections::HashMap;
+
+use flame_cat_protocol::{VisualProfile, color_hue, color_key};
+use serde::Serialize;
+
+/// One color key of a profile.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LegendEntry {
+    pub category: String,
+    /// Hue in degrees; hosts pick saturation and lightness for their theme.
+    pub hue: f32,
+    /// Summed self time of the key's spans, in the profile's value unit.
+    /// Self time keeps nested spans of one key from counting twice.
+    pub total_time: f64,
+    pub span_count: usize,
+}
+
+/// Every color key in `profile`, heaviest first.
+pub fn category_legend(profile: &VisualProfile) -> Vec<LegendEntry> {
+    let mut totals: HashMap<&str, (f64, usize)> = HashMap::new();
+    for span in profile.all_spans() {
+        let total = totals.entry(color_key(&span.name)).or_default();
+        total.0 += span.self_value;
+        total.1 += 1;
+    }
+    let mut legend: Vec<LegendEntry> = totals
+        .into_iter()
+        .map(|(category, (total_time, span_count))| LegendEntry {
+            category: category.to_string(),
+            hue: color_hue(category),
+            total_time,
+            span_count,
+        })
+        .collect();
+    legend.sort_by(|a, b| {
+        b.total_time
+            .total_cmp(&a.total_time)
+            .then_with(|| a.category.cmp(&b.category))
+    });
+    legend
+}
+
+#[cfg(test)]
+mod tests {
+    use flame_cat_protocol::{
+        ProfileMeta, SharedStr, SourceFormat, Span, SpanKind, ThreadGroup, ValueUnit,
+    };
+
+    use super::*;
+
+    fn span(id: u64, name: &str, start: f64, end: f64, parent: Option<u64>) -> Span {
+        Span {
+            id,
+            name: SharedStr::from(name),
+            start,
+            end,
+            depth: u32::from(parent.is_some()),
+            parent,
+            self_value: 0.0,
+            kind: SpanKind::Event,
+            category: None,
+            truncated: false,
+            binary: None,
+            inlined: vec![],
+        }
+    }
+
+    #[test]
+    fn sums_self_time_per_color_key() {
+        let mut profile = VisualProfile {
+            meta: ProfileMeta {
+                name: None,
+                source_format: SourceFormat::Unknown,
+                value_unit: ValueUnit::Microseconds,
+                total_value: 100.0,
+                start_time: 0.0,
+                end_time: 100.0,
+                time_domain: None,
+                truncated_spans: 0,
+                repairs: Default::default(),
+                limits_hit: Default::default(),
+                partial: None,
+                sample_interval_us: None,
+                description: None,
+                tags: vec![],
+            },
+            threads: vec![ThreadGroup {
+                id: 0,
+                name: "Main".into(),
+                kind: Default::default(),
+                sort_key: 0,
+                max_depth: 1,
+                spans: vec![
+                    span(1, "react::render", 0.0, 60.0, None),
+                    span(2, "react::commit", 10.0, 30.0, Some(1)),
+                    span(3, "std.fmt", 30.0, 50.0, Some(1)),
+                    span(4, "idle", 60.0, 80.0, None),
+                ],
+                pid: None,
+                tid: None,
+                process_name: None,
+            }],
+            frames: vec![],
+            counters: vec![],
+            async_spans: vec![],
+            flow_arrows: vec![],
+            markers: vec![],
+            instant_events: vec![],
+            object_events: vec![],
+            cpu_samples: None,
+            network_requests: vec![],
+            screenshots: vec![],
+            binaries: vec![],
+        };
+        crate::transform::compute_self_times(&mut profile);
+
+        let legend = category_legend(&profile);
+        let rows: Vec<(&str, f64, usize)> = legend
+            .iter()
+            .map(|e| (e.category.as_str(), e.total_time, e.span_count))
+            .collect();
+        assert_eq!(
+            rows,
+            [("react", 40.0, 2), ("idle", 20.0, 1), ("std", 20.0, 1)]
+        );
+        assert_eq!(legend[0].hue, color_hue("react"));
+    }
+}
@@ -1,4 +1,19 @@
+pub mod analysis;
+pub mod cache;
+pub mod comparison;
+#[cfg(feature = "demangle")]
+pub mod demangle;
+pub mod geometry;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod input;
+pub mod legend;
+pub mod markdown;
 pub mod model;
 pub mod parsers;
+pub mod remote;
+pub mod sourcemap;
 pub mod svg;
+pub mod symbolicate;
+pub mod testing;
+pub mod transform;
 pub mod views;
@@ -0,0 +1,176 @@
+//! Markdown export of spans and annotations, for pasting profile evidence
+//! into code review comments and issues.
+
+use flame_cat_protocol::{DurationFormat, Span};
+
+use crate::model::Session;
+use crate::model::session_file::Annotation;
+
+/// A Markdown snippet listing `span_ids` of the session's primary profile
+/// as a table (function, `file:line`, duration, share of the total),
+/// followed by `annotations` and a link to `permalink`, with times written
+/// as `format` asks. Unknown span ids are skipped.
+pub fn evidence_markdown(
+    session: &Session,
+    span_ids: &[u64],
+    annotations: &[Annotation],
+    permalink: Option<&str>,
+    format: &DurationFormat,
+) -> String {
+    let mut out = String::new();
+    if let Some(entry) = session.profiles().first() {
+        let profile = &entry.profile;
+        let spans: Vec<&Span> = span_ids.iter().filter_map(|&id| profile.span(id)).collect();
+        if !spans.is_empty() {
+            let total = profile.meta.total_value;
+            out.push_str("| Function | Source | Duration | % of total |\n");
+            out.push_str("| --- | --- | --- | ---: |\n");
+            for span in spans {
+                let source = span
+                    .category
+                    .as_ref()
+                    .and_then(|c| c.source.as_deref())
+                    .map_or_else(String::new, |s| code(&file_line(s)));
+                let share = if total > 0.0 {
+                    format!("{}%", format.number(span.duration() / total * 100.0, 1))
+                } else {
+                    String::new()
+                };
+                out.push_str(&format!(
+                    "| {} | {source} | {} | {share} |\n",
+                    code(&span.name),
+                    profile.meta.format_value_with(span.duration(), format),
+                ));
+            }
+        }
+    }
+
+    if !annotations.is_empty() {
+        if !out.is_empty() {
+            out.push('\n');
+        }
+        let start = session.start_time();
+        let at = |us: f64| format.format_us(us - start);
+        for note in annotations {
+            let when = match note.end_us {
+                Some(end) => format!("{}–{}", at(note.start_us), at(end)),
+                None => at(note.start_us),
+            };
+            let lane = note
+                .lane
+                .as_deref()
+                .map_or_else(String::new, |lane| format!(" ({lane})"));
+            out.push_str(&format!("- **{when}**{lane}: {}\n", note.text.trim()));
+        }
+    }
+
+    if let Some(link) = permalink {
+        if !out.is_empty() {
+            out.push('\n');
+        }
+        out.push_str(&format!("[Open in flame.cat]({link})\n"));
+    }
+    out
+}
+
+/// `text` as inline code that is safe inside a table cell.
+pub(crate) fn code(text: &str) -> String {
+    let text = text.replace('|', "\\|");
+    if text.contains('`') {
+        format!("`` {text} ``")
+    } else {
+        format!("`{text}`")
+    }
+}
+
+/// `file:line` of a `url:line:column` source location, without the
+/// directories.
+fn file_line(source: &str) -> String {
+    let mut path = source;
+    let mut line = None;
+    for _ in 0..2 {
+        match path.rsplit_once(':') {
+            Some((head, tail)) if !tail.is_empty() && tail.bytes().all(|b| b.is_ascii_digit()) => {
+                path = head;
+                line = Some(tail);
+            }
+            _ => break,
+        }
+    }
+    let file = path.rsplit(['/', '\\']).next().unwrap_or(path);
+    match line {
+        Some(line) => format!("{file}:{line}"),
+        None => file.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use flame_cat_protocol::SpanCategory;
+
+    use super::*;
+    use crate::parsers::chrome::parse_chrome_trace;
+
+    #[test]
+    fn formats_spans_notes_and_link() {
+        let json = r#"{"traceEvents":[
+            {"name":"main","ph":"X","ts":0,"dur":2000,"pid":1,"tid":1},
+            {"name":"a|b","ph":"X","ts":100,"dur":500,"pid":1,"tid":1}
+        ]}"#;
+        let mut profile = parse_chrome_trace(json.as_bytes())
+            .unwrap()
+            .into_visual_profile();
+        profile.threads[0].spans[1].category = Some(SpanCategory {
+            name: "js".into(),
+            source: Some("https://example.com/js/app.js:12:4".into()),
+        });
+        let ids: Vec<u64> = profile.threads[0].spans.iter().map(|s| s.id).collect();
+        let session = Session::from_profile(profile, "trace");
+        let notes = [Annotation {
+            start_us: session.start_time() + 100.0,
+            end_us: Some(session.start_time() + 600.0),
+            text: "slow parse ".into(),
+            lane: Some("Main".into()),
+        }];
+
+        let markdown = evidence_markdown(
+            &session,
+            &ids,
+            &notes,
+            Some("https://example.com/s.flamecat"),
+            &DurationFormat::default(),
+        );
+        let lines: Vec<&str> = markdown.lines().collect();
+        assert_eq!(lines[0], "| Function | Source | Duration | % of total |");
+        assert_eq!(lines[2], "| `main` |  | 2.0ms | 100.0% |");
+        assert_eq!(lines[3], "| `a\\|b` | `app.js:12` | 500µs | 25.0% |");
+        assert_eq!(lines[5], "- **100µs–600µs** (Main): slow parse");
+        assert_eq!(
+            lines[7],
+            "[Open in flame.cat](https://example.com/s.flamecat)"
+        );
+
+        assert_eq!(
+            evidence_markdown(&session, &[], &[], None, &DurationFormat::default()),
+            ""
+        );
+
+        let comma = DurationFormat {
+            separators: flame_cat_protocol::Separators::PointComma,
+            decimals: Some(2),
+            ..Default::default()
+        };
+        let markdown = evidence_markdown(&session, &ids[1..], &[], None, &comma);
+        assert_eq!(
+            markdown.lines().nth(2),
+            Some("| `a\\|b` | `app.js:12` | 500,00µs | 25,0% |")
+        );
+    }
+
+    #[test]
+    fn shortens_source_locations() {
+        assert_eq!(file_line("https://example.com/js/app.js:12:4"), "app.js:12");
+        assert_eq!(file_line("src/main.rs:7"), "main.rs:7");
+        assert_eq!(file_line(r"C:\src\lib.rs"), "lib.rs");
+    }
+}
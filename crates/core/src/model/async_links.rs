@@ -0,0 +1,165 @@
+//! Correlation of async spans with the thread spans that executed them.
+//!
+//! Async spans (`b`/`e` pairs) describe logical operations that hop across
+//! tasks; the work itself shows up as ordinary spans on a thread. A link is
+//! found through a flow arrow sharing the async span's id (the arrow lands
+//! in the executing span), or failing that through a thread span of the
+//! same name in the same process that overlaps the async span.
+
+use std::collections::HashMap;
+
+use flame_cat_protocol::{FlowArrow, Span, VisualProfile};
+
+/// Async span ↔ executing span links for one profile.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AsyncLinks {
+    /// Executing span id per entry of `VisualProfile::async_spans`.
+    executing: Vec<Option<u64>>,
+    by_span: HashMap<u64, Vec<usize>>,
+}
+
+impl AsyncLinks {
+    pub fn build(profile: &VisualProfile) -> Self {
+        let mut by_name: HashMap<(u64, &str), Vec<(u64, &Span)>> = HashMap::new();
+        for thread in &profile.threads {
+            let Some((pid, tid)) = thread.source_thread() else {
+                continue;
+            };
+            for span in &thread.spans {
+                by_name
+                    .entry((pid, &span.name))
+                    .or_default()
+                    .push((tid, span));
+            }
+        }
+        let mut flows: HashMap<&str, &FlowArrow> = HashMap::new();
+        for arrow in &profile.flow_arrows {
+            flows.entry(&arrow.id).or_insert(arrow);
+        }
+
+        let executing: Vec<Option<u64>> = profile
+            .async_spans
+            .iter()
+            .map(|a| {
+                let via_flow = flows
+                    .get(&*a.id)
+                    .and_then(|f| profile.span_at(f.to_pid, f.to_tid, f.to_ts));
+                via_flow
+                    .or_else(|| {
+                        // Same thread first, then earliest start.
+                        by_name
+                            .get(&(a.pid, &*a.name))?
+                            .iter()
+                            .filter(|(_, s)| s.start <= a.end && s.end >= a.start)
+                            .min_by(|(ta, sa), (tb, sb)| {
+                                (*ta != a.tid)
+                                    .cmp(&(*tb != a.tid))
+                                    .then(sa.start.total_cmp(&sb.start))
+                            })
+                            .map(|&(_, s)| s)
+                    })
+                    .map(|s| s.id)
+            })
+            .collect();
+
+        let mut by_span: HashMap<u64, Vec<usize>> = HashMap::new();
+        for (index, span_id) in executing.iter().enumerate() {
+            if let Some(span_id) = span_id {
+                by_span.entry(*span_id).or_default().push(index);
+            }
+        }
+        Self { executing, by_span }
+    }
+
+    /// Span that executed async span `async_index`, if one was found.
+    pub fn executing_span(&self, async_index: usize) -> Option<u64> {
+        self.executing.get(async_index).copied().flatten()
+    }
+
+    /// Executing span ids, index-aligned with `VisualProfile::async_spans`.
+    pub fn executing_spans(&self) -> &[Option<u64>] {
+        &self.executing
+    }
+
+    /// Indices of the async spans executed by `span_id`.
+    pub fn async_spans_for(&self, span_id: u64) -> &[usize] {
+        self.by_span.get(&span_id).map_or(&[], Vec::as_slice)
+    }
+
+    /// `(async span index, executing span id)` pairs, as dependency edges
+    /// for path analyses.
+    pub fn edges(&self) -> impl Iterator<Item = (usize, u64)> + '_ {
+        self.executing
+            .iter()
+            .enumerate()
+            .filter_map(|(index, span_id)| Some((index, (*span_id)?)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use flame_cat_protocol::{AsyncSpan, ThreadGroup, ValueUnit};
+
+    use super::*;
+    use crate::testing::fixtures::{profile, span, thread};
+
+    fn track(id: u32, tid: u64, spans: Vec<Span>) -> ThreadGroup {
+        ThreadGroup {
+            name: format!("T{tid}").into(),
+            pid: Some(1),
+            tid: Some(tid),
+            ..thread(id, spans)
+        }
+    }
+
+    fn async_span(id: &str, name: &str, start: f64, end: f64) -> AsyncSpan {
+        AsyncSpan {
+            id: id.into(),
+            name: name.into(),
+            cat: None,
+            start,
+            end,
+            pid: 1,
+            tid: 1,
+        }
+    }
+
+    #[test]
+    fn links_by_flow_then_by_name() {
+        let mut profile = profile(
+            ValueUnit::Microseconds,
+            vec![
+                track(0, 1, vec![span(1, "fetch", 0.0, 10.0, None)]),
+                track(
+                    1,
+                    2,
+                    vec![
+                        span(2, "fetch", 20.0, 30.0, None),
+                        span(3, "onload", 40.0, 50.0, None),
+                    ],
+                ),
+            ],
+        );
+        profile.async_spans = vec![
+            async_span("a", "load", 0.0, 60.0),
+            async_span("b", "fetch", 5.0, 25.0),
+            async_span("c", "unrelated", 0.0, 60.0),
+        ];
+        profile.flow_arrows = vec![FlowArrow {
+            name: "load".into(),
+            id: "a".into(),
+            from_ts: 5.0,
+            from_pid: 1,
+            from_tid: 1,
+            to_ts: 45.0,
+            to_pid: 1,
+            to_tid: 2,
+        }];
+
+        let links = AsyncLinks::build(&profile);
+        assert_eq!(links.executing_spans(), [Some(3), Some(1), None]);
+        assert_eq!(links.async_spans_for(3), [0]);
+        assert!(links.async_spans_for(2).is_empty());
+        assert_eq!(links.edges().collect::<Vec<_>>(), [(0, 3), (1, 1)]);
+    }
+}
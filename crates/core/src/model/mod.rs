@@ -1,5 +1,22 @@
+pub mod async_links;
 pub mod profile;
+mod sanitize;
+pub mod selection;
 pub mod session;
+pub mod session_file;
+pub mod sidecar;
+pub mod thread_order;
 
+pub use async_links::AsyncLinks;
 pub use profile::{Frame, Profile, ProfileMetadata};
-pub use session::Session;
+pub use selection::{
+    Navigation, ProfileMatches, RowSpan, SelectionState, SpanStep, adjacent_span, row_spans,
+};
+pub use session::{
+    AlignError, Alignment, ClockAnchor, DiffNormalization, ProfileInfo, Session, SessionInfo,
+};
+pub use session_file::{
+    PinnedRow, PinnedSpan, SessionState, ViewRegion, load_session, save_session,
+};
+pub use sidecar::{SidecarError, SidecarImport, import_sidecar};
+pub use thread_order::{ThreadOrder, ThreadSort};
@@ -1,12 +1,12 @@
 use flame_cat_protocol::{
-    AsyncSpan, CounterTrack, CpuSamples, FlowArrow, InstantEvent, Marker, NetworkRequest,
-    ObjectEvent, ProfileMeta, Screenshot, SharedStr, SourceFormat, Span, SpanCategory, SpanKind,
-    ThreadGroup, TimeDomain, ValueUnit, VisualProfile,
+    AsyncSpan, Binary, CounterTrack, CpuSamples, FlowArrow, InstantEvent, LimitsHit, Marker,
+    NetworkRequest, ObjectEvent, PartialParse, ProfileMeta, Screenshot, SharedStr, SourceFormat,
+    Span, SpanCategory, SpanKind, ThreadGroup, TimeDomain, TrackKind, ValueUnit, VisualProfile,
 };
 use serde::{Deserialize, Serialize};
 
 /// A single stack frame span in the profile.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Frame {
     /// Unique identifier within this profile.
     pub id: u64,
@@ -26,6 +26,26 @@ pub struct Frame {
     pub self_time: f64,
     /// Thread or group name (for multi-thread traces).
     pub thread: Option<String>,
+    /// Still open when the trace ended; `end` is the trace end.
+    #[serde(default)]
+    pub truncated: bool,
+    /// Source process id, for formats that record one.
+    #[serde(default)]
+    pub pid: Option<u64>,
+    /// Source thread id, for formats that record one.
+    #[serde(default)]
+    pub tid: Option<u64>,
+    /// Source location as `url:line:column` (1-based) for JS frames, or
+    /// the bare URL a trace event refers to. Carried on the span's
+    /// category, so only kept when `category` is set.
+    #[serde(default)]
+    pub source: Option<String>,
+    /// Index into [`Profile::binaries`] of the binary the frame ran in.
+    #[serde(default)]
+    pub binary: Option<u32>,
+    /// Functions inlined into this frame, outermost first.
+    #[serde(default)]
+    pub inlined: Vec<String>,
 }
 
 impl Frame {
@@ -44,6 +64,13 @@ pub struct ProfileMetadata {
     /// Clock domain metadata for cross-profile alignment.
     #[serde(default)]
     pub time_domain: Option<TimeDomain>,
+    /// Unit of frame values, when the parser knows better than the format
+    /// default (e.g. a pprof profile weighted by sample count).
+    #[serde(default)]
+    pub value_unit: Option<ValueUnit>,
+    /// Sampling interval (µs) for sample-count profiles, when recorded.
+    #[serde(default)]
+    pub sample_interval_us: Option<f64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -77,6 +104,23 @@ pub struct Profile {
     /// Screenshots for filmstrip.
     #[serde(default)]
     pub screenshots: Vec<Screenshot>,
+    /// Mapped binaries, for formats that record them.
+    #[serde(default)]
+    pub binaries: Vec<Binary>,
+    /// Process names by pid.
+    #[serde(default)]
+    pub process_names: std::collections::BTreeMap<u64, String>,
+    /// Names of threads the source marks as GPU queues. Other threads are
+    /// classified by [`TrackKind::from_track_name`].
+    #[serde(default)]
+    pub gpu_threads: std::collections::BTreeSet<String>,
+    /// Set by parsers that recovered what they could of a damaged input.
+    #[serde(default)]
+    pub partial: Option<PartialParse>,
+    /// What parsers that enforce [`Limits`](crate::parsers::Limits) left
+    /// out while reading.
+    #[serde(default)]
+    pub limits_hit: LimitsHit,
 }
 
 impl Profile {
@@ -94,6 +138,11 @@ impl Profile {
             cpu_samples: None,
             network_requests: vec![],
             screenshots: vec![],
+            binaries: vec![],
+            process_names: std::collections::BTreeMap::new(),
+            gpu_threads: std::collections::BTreeSet::new(),
+            partial: None,
+            limits_hit: LimitsHit::default(),
         }
     }
 
@@ -112,7 +161,13 @@ impl Profile {
     }
 
     /// Convert this Profile into the canonical VisualProfile protocol.
-    pub fn into_visual_profile(self) -> VisualProfile {
+    ///
+    /// Malformed span trees are repaired first; see `ProfileMeta::repairs`.
+    /// Self values are then derived from the repaired tree by
+    /// [`compute_self_times`](crate::transform::compute_self_times).
+    pub fn into_visual_profile(mut self) -> VisualProfile {
+        let repairs = super::sanitize::sanitize_frames(&mut self.frames);
+
         let source_format = match self.metadata.format.as_str() {
             "chrome" => SourceFormat::ChromeTrace,
             "firefox" => SourceFormat::FirefoxGecko,
@@ -124,19 +179,42 @@ impl Profile {
             "tracy" => SourceFormat::Tracy,
             "pix" => SourceFormat::Pix,
             "ebpf" | "ebpf-perf" => SourceFormat::Ebpf,
+            "tracing" => SourceFormat::Tracing,
+            "tokio-console" => SourceFormat::TokioConsole,
+            "perfetto" => SourceFormat::Perfetto,
+            "perf" => SourceFormat::Perf,
+            "jfr" => SourceFormat::Jfr,
+            "nettrace" => SourceFormat::Nettrace,
+            "wpa" => SourceFormat::Wpa,
+            "instruments" => SourceFormat::Instruments,
+            "systrace" => SourceFormat::Systrace,
+            "ftrace" => SourceFormat::Ftrace,
+            "callgrind" => SourceFormat::Callgrind,
+            "massif" => SourceFormat::Massif,
+            "dhat" => SourceFormat::Dhat,
+            "heaptrack" => SourceFormat::Heaptrack,
             _ => SourceFormat::Unknown,
         };
 
-        let value_unit = match &source_format {
+        let value_unit = self.metadata.value_unit.unwrap_or(match &source_format {
             SourceFormat::CollapsedStacks | SourceFormat::Ebpf => ValueUnit::Samples,
             SourceFormat::Pprof => ValueUnit::Nanoseconds,
             _ => ValueUnit::Microseconds,
-        };
+        });
 
         let span_kind = match &source_format {
-            SourceFormat::CollapsedStacks | SourceFormat::Ebpf | SourceFormat::Pprof => {
-                SpanKind::Sample
-            }
+            SourceFormat::CollapsedStacks
+            | SourceFormat::Ebpf
+            | SourceFormat::Pprof
+            | SourceFormat::Perf
+            | SourceFormat::Jfr
+            | SourceFormat::Nettrace
+            | SourceFormat::Wpa
+            | SourceFormat::Instruments
+            | SourceFormat::Callgrind
+            | SourceFormat::Massif
+            | SourceFormat::Dhat
+            | SourceFormat::Heaptrack => SpanKind::Sample,
             _ => SpanKind::Event,
         };
 
@@ -150,9 +228,13 @@ impl Profile {
         let mut thread_cache: std::collections::HashMap<String, SharedStr> =
             std::collections::HashMap::new();
 
-        // Group frames by thread name
-        let mut thread_groups: std::collections::BTreeMap<SharedStr, Vec<Span>> =
+        // Group frames by source (pid, tid) and thread name, so same-numbered
+        // or same-named threads in different processes stay apart. The map's
+        // order fixes thread ids, so they are the same on every load.
+        type GroupKey = (Option<u64>, Option<u64>, SharedStr);
+        let mut thread_groups: std::collections::BTreeMap<GroupKey, Vec<Span>> =
             std::collections::BTreeMap::new();
+        let mut truncated_spans = 0;
 
         for f in self.frames {
             let name = name_cache
@@ -167,7 +249,7 @@ impl Profile {
                     .clone();
                 SpanCategory {
                     name: cat_name,
-                    source: None,
+                    source: f.source.map(SharedStr::from),
                 }
             });
 
@@ -189,29 +271,47 @@ impl Profile {
                 self_value: f.self_time,
                 kind: span_kind,
                 category,
+                truncated: f.truncated,
+                binary: f.binary,
+                inlined: f.inlined.into_iter().map(SharedStr::from).collect(),
             };
+            truncated_spans += usize::from(f.truncated);
 
-            thread_groups.entry(thread_name).or_default().push(span);
+            thread_groups
+                .entry((f.pid, f.tid, thread_name))
+                .or_default()
+                .push(span);
         }
 
         // Sort thread groups: put "CrRendererMain" or "Main" first, then by event count
         let mut threads: Vec<ThreadGroup> = thread_groups
             .into_iter()
             .enumerate()
-            .map(|(i, (name, spans))| ThreadGroup {
+            .map(|(i, ((pid, tid, name), spans))| ThreadGroup {
                 id: i as u32,
-                name: name.clone(),
                 sort_key: thread_sort_key(&name),
+                kind: if self.gpu_threads.contains(&*name) {
+                    TrackKind::Gpu
+                } else {
+                    TrackKind::from_track_name(&name)
+                },
+                name,
                 spans,
                 max_depth: 0,
+                pid,
+                tid,
+                process_name: pid
+                    .and_then(|p| self.process_names.get(&p))
+                    .map(|n| SharedStr::from(n.as_str())),
             })
             .collect();
+        qualify_thread_names(&mut threads);
         threads.sort_by_key(|t| t.sort_key);
         for t in &mut threads {
             t.compute_max_depth();
         }
 
-        VisualProfile {
+        let mut profile = VisualProfile {
             meta: ProfileMeta {
                 name: self.metadata.name.map(SharedStr::from),
                 source_format,
@@ -220,6 +320,13 @@ impl Profile {
                 start_time: self.metadata.start_time,
                 end_time: self.metadata.end_time,
                 time_domain: self.metadata.time_domain,
+                truncated_spans,
+                repairs,
+                limits_hit: self.limits_hit,
+                partial: self.partial,
+                sample_interval_us: self.metadata.sample_interval_us,
+                description: None,
+                tags: vec![],
             },
             threads,
             frames: vec![],
@@ -229,9 +336,45 @@ impl Profile {
             markers: self.markers,
             instant_events: self.instant_events,
             object_events: self.object_events,
-            cpu_samples: self.cpu_samples,
+            cpu_samples: self.cpu_samples.map(|samples| CpuSamples {
+                sample_interval_us: samples
+                    .sample_interval_us
+                    .or(self.metadata.sample_interval_us),
+                ..samples
+            }),
             network_requests: self.network_requests,
             screenshots: self.screenshots,
+            binaries: self.binaries,
+        };
+        crate::transform::compute_self_times(&mut profile);
+        profile
+    }
+}
+
+/// Make thread names unique for display. In multi-process traces each name
+/// is prefixed with its process; names that still collide get their tid.
+fn qualify_thread_names(threads: &mut [ThreadGroup]) {
+    let pids: std::collections::BTreeSet<u64> = threads.iter().filter_map(|t| t.pid).collect();
+    if pids.len() > 1 {
+        for t in threads.iter_mut() {
+            let Some(pid) = t.pid else { continue };
+            let process = t
+                .process_name
+                .as_ref()
+                .map_or_else(|| format!("pid {pid}"), ToString::to_string);
+            t.name = SharedStr::from(format!("{process} / {}", t.name));
+        }
+    }
+
+    let mut counts: std::collections::HashMap<SharedStr, usize> = std::collections::HashMap::new();
+    for t in threads.iter() {
+        *counts.entry(t.name.clone()).or_default() += 1;
+    }
+    for t in threads.iter_mut() {
+        if counts.get(&t.name).is_some_and(|&n| n > 1)
+            && let Some(tid) = t.tid
+        {
+            t.name = SharedStr::from(format!("{} ({tid})", t.name));
         }
     }
 }
@@ -242,6 +385,7 @@ fn thread_sort_key(name: &str) -> i64 {
         "CrRendererMain" => 0,
         "Main" => 1,
         n if n.contains("Main") => 2,
+        "User Timing" => 3,
         "Compositor" => 10,
         n if n.contains("Worker") => 20,
         n if n.contains("IO") => 30,
@@ -261,6 +405,8 @@ mod tests {
                 end_time: 200.0,
                 format: format.to_string(),
                 time_domain: None,
+                value_unit: None,
+                sample_interval_us: None,
             },
             vec![
                 Frame {
@@ -273,6 +419,12 @@ mod tests {
                     parent: None,
                     self_time: 80.0,
                     thread: None,
+                    truncated: false,
+                    pid: None,
+                    tid: None,
+                    source: None,
+                    binary: None,
+                    inlined: vec![],
                 },
                 Frame {
                     id: 1,
@@ -284,6 +436,12 @@ mod tests {
                     parent: Some(0),
                     self_time: 120.0,
                     thread: None,
+                    truncated: false,
+                    pid: None,
+                    tid: None,
+                    source: None,
+                    binary: None,
+                    inlined: vec![],
                 },
             ],
         )
@@ -313,6 +471,20 @@ mod tests {
             ("pprof", SourceFormat::Pprof),
             ("tracy", SourceFormat::Tracy),
             ("ebpf", SourceFormat::Ebpf),
+            ("tracing", SourceFormat::Tracing),
+            ("tokio-console", SourceFormat::TokioConsole),
+            ("perfetto", SourceFormat::Perfetto),
+            ("perf", SourceFormat::Perf),
+            ("jfr", SourceFormat::Jfr),
+            ("nettrace", SourceFormat::Nettrace),
+            ("wpa", SourceFormat::Wpa),
+            ("instruments", SourceFormat::Instruments),
+            ("systrace", SourceFormat::Systrace),
+            ("ftrace", SourceFormat::Ftrace),
+            ("callgrind", SourceFormat::Callgrind),
+            ("massif", SourceFormat::Massif),
+            ("dhat", SourceFormat::Dhat),
+            ("heaptrack", SourceFormat::Heaptrack),
             ("unknown-fmt", SourceFormat::Unknown),
         ] {
             let vp = sample_profile(fmt).into_visual_profile();
@@ -366,4 +538,44 @@ mod tests {
         assert_eq!(children.len(), 1);
         assert_eq!(children[0].name, "render");
     }
+
+    #[test]
+    fn thread_ids_follow_pid_and_tid() {
+        let frame = |id, tid, thread: &str| Frame {
+            id,
+            name: "work".into(),
+            start: id as f64,
+            end: id as f64 + 1.0,
+            depth: 0,
+            category: None,
+            parent: None,
+            self_time: 1.0,
+            thread: Some(thread.into()),
+            truncated: false,
+            pid: Some(1),
+            tid: Some(tid),
+            source: None,
+            binary: None,
+            inlined: vec![],
+        };
+        let mut profile = sample_profile("chrome");
+        profile.frames = vec![frame(0, 9, "A"), frame(1, 3, "B"), frame(2, 5, "A")];
+        let ids: Vec<(u32, Option<u64>)> = profile
+            .clone()
+            .into_visual_profile()
+            .threads
+            .iter()
+            .map(|t| (t.id, t.tid))
+            .collect();
+        assert_eq!(ids, [(0, Some(3)), (1, Some(5)), (2, Some(9))]);
+
+        profile.frames.reverse();
+        let reversed: Vec<(u32, Option<u64>)> = profile
+            .into_visual_profile()
+            .threads
+            .iter()
+            .map(|t| (t.id, t.tid))
+            .collect();
+        assert_eq!(reversed, ids);
+    }
 }
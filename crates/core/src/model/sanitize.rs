@@ -0,0 +1,184 @@
+//! Repairs malformed span trees before they are laid out.
+//!
+//! Some emitters produce spans that end before they start, children that
+//! extend past their parent, or siblings that overlap at the same depth.
+//! Left alone these corrupt depth layout and self-time math, so the
+//! conversion to [`VisualProfile`](flame_cat_protocol::VisualProfile) runs
+//! [`sanitize_frames`] first and records what it changed.
+
+use std::collections::HashMap;
+
+use flame_cat_protocol::SpanRepairs;
+
+use super::profile::Frame;
+
+/// Roots are grouped per thread: name plus source (pid, tid).
+type ThreadKey<'a> = (Option<&'a str>, Option<u64>, Option<u64>);
+
+/// Repair `frames` in place and report what was fixed.
+///
+/// Spans are walked top-down from the roots of each thread:
+/// - negative durations are clamped to zero length,
+/// - children are clamped to their parent's range,
+/// - a span overlapping an earlier sibling is nested under that sibling
+///   when fully contained, and otherwise trimmed to start where it ends,
+/// - depths are recomputed from the resulting tree.
+///
+/// Self times are left alone; they are derived from the repaired tree later.
+pub(crate) fn sanitize_frames(frames: &mut [Frame]) -> SpanRepairs {
+    let mut repairs = SpanRepairs::default();
+
+    for f in frames.iter_mut() {
+        if f.end.is_nan() || f.end < f.start {
+            f.end = f.start;
+            repairs.negative_durations += 1;
+        }
+    }
+
+    let index: HashMap<u64, usize> = frames.iter().enumerate().map(|(i, f)| (f.id, i)).collect();
+    let mut children: HashMap<usize, Vec<usize>> = HashMap::new();
+    let mut roots: HashMap<ThreadKey<'_>, Vec<usize>> = HashMap::new();
+    for (i, f) in frames.iter().enumerate() {
+        match f.parent.and_then(|p| index.get(&p)) {
+            Some(&p) if p != i => children.entry(p).or_default().push(i),
+            _ => roots
+                .entry((f.thread.as_deref(), f.pid, f.tid))
+                .or_default()
+                .push(i),
+        }
+    }
+    let mut worklist: Vec<(Option<usize>, Vec<usize>)> =
+        roots.into_values().map(|r| (None, r)).collect();
+
+    let mut visited = vec![false; frames.len()];
+    while let Some((parent, mut siblings)) = worklist.pop() {
+        let (depth, bounds) = match parent {
+            Some(p) => (frames[p].depth + 1, Some((frames[p].start, frames[p].end))),
+            None => (0, None),
+        };
+        siblings.sort_by(|&a, &b| frames[a].start.total_cmp(&frames[b].start));
+
+        let mut kept: Vec<usize> = Vec::with_capacity(siblings.len());
+        for i in siblings {
+            visited[i] = true;
+            let parent_id = parent.map(|p| frames[p].id);
+            let f = &mut frames[i];
+            if f.depth != depth || f.parent != parent_id {
+                f.depth = depth;
+                f.parent = parent_id;
+                repairs.redepthed += 1;
+            }
+            if let Some((lo, hi)) = bounds
+                && (f.start < lo || f.end > hi)
+            {
+                f.start = f.start.clamp(lo, hi);
+                f.end = f.end.clamp(f.start, hi);
+                repairs.clamped_to_parent += 1;
+            }
+
+            if let Some(&prev) = kept.last()
+                && frames[i].start < frames[prev].end
+            {
+                repairs.overlapping_siblings += 1;
+                if frames[i].end <= frames[prev].end {
+                    // Fully inside the previous sibling: it is really a child.
+                    frames[i].parent = Some(frames[prev].id);
+                    frames[i].depth = frames[prev].depth + 1;
+                    children.entry(prev).or_default().push(i);
+                    continue;
+                }
+                frames[i].start = frames[prev].end;
+            }
+            kept.push(i);
+        }
+
+        for i in kept {
+            if let Some(c) = children.remove(&i) {
+                worklist.push((Some(i), c));
+            }
+        }
+    }
+
+    // Anything unreachable from a root sits on a parent cycle; detach it.
+    for (f, _) in frames.iter_mut().zip(&visited).filter(|(_, v)| !**v) {
+        f.parent = None;
+        f.depth = 0;
+        repairs.redepthed += 1;
+    }
+
+    repairs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(id: u64, start: f64, end: f64, depth: u32, parent: Option<u64>) -> Frame {
+        Frame {
+            id,
+            name: format!("f{id}"),
+            start,
+            end,
+            depth,
+            category: None,
+            parent,
+            self_time: 0.0,
+            thread: None,
+            truncated: false,
+            pid: None,
+            tid: None,
+            source: None,
+            binary: None,
+            inlined: vec![],
+        }
+    }
+
+    #[test]
+    fn well_formed_tree_is_untouched() {
+        let mut frames = vec![
+            frame(0, 0.0, 100.0, 0, None),
+            frame(1, 10.0, 40.0, 1, Some(0)),
+            frame(2, 50.0, 90.0, 1, Some(0)),
+        ];
+        frames[0].self_time = 30.0;
+        assert_eq!(sanitize_frames(&mut frames), SpanRepairs::default());
+        assert_eq!(frames[0].self_time, 30.0);
+    }
+
+    #[test]
+    fn repairs_are_applied_and_counted() {
+        let mut frames = vec![
+            frame(0, 0.0, 100.0, 0, None),
+            // Extends past its parent.
+            frame(1, 10.0, 120.0, 1, Some(0)),
+            // Ends before it starts, and has the wrong depth.
+            frame(2, 50.0, 40.0, 3, Some(0)),
+            // Contained in sibling 1: becomes its child.
+            frame(3, 20.0, 30.0, 1, Some(0)),
+            // A root overlapping root 0 partially: trimmed.
+            frame(4, 90.0, 150.0, 0, None),
+        ];
+        let repairs = sanitize_frames(&mut frames);
+
+        assert_eq!(frames[1].end, 100.0);
+        assert_eq!(frames[3].parent, Some(1));
+        assert_eq!(frames[3].depth, 2);
+        assert_eq!(frames[4].start, 100.0);
+        assert_eq!(repairs.negative_durations, 1);
+        assert_eq!(repairs.clamped_to_parent, 1);
+        // Frame 2 (zero-length at 50) sits inside frame 1 as well.
+        assert_eq!(repairs.overlapping_siblings, 3);
+        assert!(repairs.redepthed >= 1);
+    }
+
+    #[test]
+    fn parent_cycles_are_detached() {
+        let mut frames = vec![
+            frame(0, 0.0, 10.0, 1, Some(1)),
+            frame(1, 0.0, 10.0, 1, Some(0)),
+        ];
+        let repairs = sanitize_frames(&mut frames);
+        assert!(frames.iter().all(|f| f.parent.is_none() && f.depth == 0));
+        assert_eq!(repairs.redepthed, 2);
+    }
+}
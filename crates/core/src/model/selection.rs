@@ -0,0 +1,373 @@
+//! Selection, hover and search-cursor state shared by every frontend.
+//!
+//! Frontends keep whatever display caches they need, but route every move
+//! through [`SelectionState`] so parent/child/sibling and next-match steps
+//! behave the same in the browser, the desktop app and the terminal.
+
+use flame_cat_protocol::{Span, VisualProfile};
+use serde::Serialize;
+
+use super::Session;
+
+/// A step through the call tree from the selected span.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Navigation {
+    Parent,
+    /// Earliest child; with nothing selected, the earliest top-level span.
+    FirstChild,
+    NextSibling,
+    PrevSibling,
+    /// Span at the far end of the earliest flow arrow touching the selection.
+    FollowFlow,
+}
+
+/// An arrow-key step between neighbouring spans of the flame chart.
+///
+/// Unlike [`Navigation`], steps follow the layout rather than the call
+/// tree: left and right cross to the adjacent span of the same row even
+/// when it has another parent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpanStep {
+    Left,
+    Right,
+    /// The span one row up that covers this one's start.
+    Up,
+    /// The earliest span one row down within this one.
+    Down,
+}
+
+impl SpanStep {
+    /// Parse `left`, `right`, `up` or `down`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "left" => Some(Self::Left),
+            "right" => Some(Self::Right),
+            "up" => Some(Self::Up),
+            "down" => Some(Self::Down),
+            _ => None,
+        }
+    }
+}
+
+/// A span as placed in one row of a thread's flame chart.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RowSpan {
+    pub frame_id: u64,
+    pub name: String,
+    pub start: f64,
+    pub end: f64,
+    pub depth: u32,
+    /// Thread group id.
+    pub thread: u32,
+}
+
+impl RowSpan {
+    fn new(span: &Span, thread: u32) -> Self {
+        Self {
+            frame_id: span.id,
+            name: span.name.to_string(),
+            start: span.start,
+            end: span.end,
+            depth: span.depth,
+            thread,
+        }
+    }
+}
+
+/// Spans of `thread` at `depth` that overlap `start..end`, by start time.
+pub fn row_spans(
+    profile: &VisualProfile,
+    thread: u32,
+    depth: u32,
+    start: f64,
+    end: f64,
+) -> Vec<RowSpan> {
+    let mut row: Vec<RowSpan> = profile
+        .threads
+        .iter()
+        .filter(|t| t.id == thread)
+        .flat_map(|t| &t.spans)
+        .filter(|s| s.depth == depth && s.end >= start && s.start <= end)
+        .map(|s| RowSpan::new(s, thread))
+        .collect();
+    row.sort_by(|a, b| a.start.total_cmp(&b.start));
+    row
+}
+
+/// The span one `step` away from `frame_id` in the flame chart layout.
+pub fn adjacent_span(profile: &VisualProfile, frame_id: u64, step: SpanStep) -> Option<RowSpan> {
+    let thread = profile
+        .threads
+        .iter()
+        .find(|t| t.spans.iter().any(|s| s.id == frame_id))?;
+    let span = thread.spans.iter().find(|s| s.id == frame_id)?;
+    let row = |depth: u32| thread.spans.iter().filter(move |s| s.depth == depth);
+    let by_start = |a: &&Span, b: &&Span| a.start.total_cmp(&b.start);
+    let target = match step {
+        SpanStep::Left => row(span.depth)
+            .filter(|s| s.start < span.start)
+            .max_by(by_start),
+        SpanStep::Right => row(span.depth)
+            .filter(|s| s.start > span.start)
+            .min_by(by_start),
+        SpanStep::Up => {
+            row(span.depth.checked_sub(1)?).find(|s| s.start <= span.start && span.start <= s.end)
+        }
+        SpanStep::Down => row(span.depth + 1)
+            .filter(|s| span.start <= s.start && s.start < span.end)
+            .min_by(by_start),
+    }?;
+    Some(RowSpan::new(target, thread.id))
+}
+
+/// Search matches within one profile of a session.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ProfileMatches {
+    /// Index of the profile in the session.
+    pub profile: usize,
+    pub label: String,
+    pub count: usize,
+    /// Id of the earliest matching span.
+    pub first_span: u64,
+    /// Start of the earliest matching span on the session timeline (µs).
+    pub first_match_us: f64,
+}
+
+/// Selected and hovered span ids plus the active search query.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SelectionState {
+    selected: Option<u64>,
+    hovered: Option<u64>,
+    /// Case-insensitive substring matched against span names.
+    pub search: String,
+}
+
+impl SelectionState {
+    pub fn selected(&self) -> Option<u64> {
+        self.selected
+    }
+
+    pub fn select(&mut self, frame_id: Option<u64>) {
+        self.selected = frame_id;
+    }
+
+    pub fn hovered(&self) -> Option<u64> {
+        self.hovered
+    }
+
+    pub fn hover(&mut self, frame_id: Option<u64>) {
+        self.hovered = frame_id;
+    }
+
+    /// Move the selection one step through the call tree. Returns the newly
+    /// selected span, or `None` (leaving the selection alone) when there is
+    /// nowhere to go.
+    pub fn navigate<'a>(
+        &mut self,
+        profile: &'a VisualProfile,
+        navigation: Navigation,
+    ) -> Option<&'a Span> {
+        let target = match (navigation, self.selected) {
+            (Navigation::FirstChild, current) => profile
+                .children(current)
+                .into_iter()
+                .min_by(|a, b| a.start.total_cmp(&b.start)),
+            (_, None) => None,
+            (Navigation::Parent, Some(id)) => {
+                profile.span(id)?.parent.and_then(|p| profile.span(p))
+            }
+            (Navigation::NextSibling | Navigation::PrevSibling, Some(id)) => {
+                let siblings = profile.siblings(id);
+                let pos = siblings.iter().position(|s| s.id == id)?;
+                if navigation == Navigation::NextSibling {
+                    siblings.get(pos + 1).copied()
+                } else {
+                    pos.checked_sub(1).and_then(|i| siblings.get(i).copied())
+                }
+            }
+            (Navigation::FollowFlow, Some(id)) => {
+                profile.flows_for_span(id).iter().find_map(|flow| {
+                    let (pid, tid, ts) = flow.other_end();
+                    profile.span_at(pid, tid, ts)
+                })
+            }
+        }?;
+        self.selected = Some(target.id);
+        Some(target)
+    }
+
+    /// Spans in `threads` (all when empty) whose name matches the search,
+    /// ordered by start time. Empty while no search is active.
+    pub fn matches<'a>(&self, profile: &'a VisualProfile, threads: &[u32]) -> Vec<&'a Span> {
+        if self.search.is_empty() {
+            return Vec::new();
+        }
+        let query = self.search.to_lowercase();
+        let mut matches: Vec<&Span> = profile
+            .threads
+            .iter()
+            .filter(|t| threads.is_empty() || threads.contains(&t.id))
+            .flat_map(|t| &t.spans)
+            .filter(|s| s.name.to_lowercase().contains(&query))
+            .collect();
+        matches.sort_by(|a, b| a.start.total_cmp(&b.start));
+        matches
+    }
+
+    /// Select the next (or previous) search match after the current
+    /// selection, wrapping around. Starts from the first (or last) match
+    /// when the selection is not itself a match.
+    pub fn next_match<'a>(
+        &mut self,
+        profile: &'a VisualProfile,
+        threads: &[u32],
+        forward: bool,
+    ) -> Option<&'a Span> {
+        let matches = self.matches(profile, threads);
+        if matches.is_empty() {
+            return None;
+        }
+        let current = self
+            .selected
+            .and_then(|id| matches.iter().position(|s| s.id == id));
+        let len = matches.len();
+        let next = match (current, forward) {
+            (Some(pos), true) => (pos + 1) % len,
+            (Some(pos), false) => (pos + len - 1) % len,
+            (None, true) => 0,
+            (None, false) => len - 1,
+        };
+        let span = matches[next];
+        self.selected = Some(span.id);
+        Some(span)
+    }
+
+    /// Matches of the search in every thread of every profile in `session`,
+    /// in profile order. Profiles without a match are left out.
+    pub fn session_matches(&self, session: &Session) -> Vec<ProfileMatches> {
+        session
+            .profiles()
+            .iter()
+            .enumerate()
+            .filter_map(|(index, entry)| {
+                let matches = self.matches(&entry.profile, &[]);
+                let first = matches.first()?;
+                Some(ProfileMatches {
+                    profile: index,
+                    label: entry.label.clone(),
+                    count: matches.len(),
+                    first_span: first.id,
+                    first_match_us: entry.to_session_time(first.start),
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use flame_cat_protocol::ValueUnit;
+
+    use super::*;
+    use crate::testing::fixtures::{self, span, thread};
+
+    fn profile() -> VisualProfile {
+        // Stored out of start order on purpose.
+        fixtures::profile(
+            ValueUnit::Microseconds,
+            vec![thread(
+                0,
+                vec![
+                    span(1, "main", 0.0, 100.0, None),
+                    span(3, "paint", 50.0, 90.0, Some(1)),
+                    span(2, "layout", 10.0, 40.0, Some(1)),
+                ],
+            )],
+        )
+    }
+
+    #[test]
+    fn navigates_call_tree() {
+        let profile = profile();
+        let mut sel = SelectionState::default();
+        let step = |sel: &mut SelectionState, nav| sel.navigate(&profile, nav).map(|s| s.id);
+
+        assert_eq!(step(&mut sel, Navigation::Parent), None);
+        assert_eq!(step(&mut sel, Navigation::FirstChild), Some(1));
+        assert_eq!(step(&mut sel, Navigation::FirstChild), Some(2));
+        assert_eq!(step(&mut sel, Navigation::PrevSibling), None);
+        assert_eq!(step(&mut sel, Navigation::NextSibling), Some(3));
+        assert_eq!(step(&mut sel, Navigation::NextSibling), None);
+        assert_eq!(sel.selected(), Some(3));
+        assert_eq!(step(&mut sel, Navigation::Parent), Some(1));
+    }
+
+    #[test]
+    fn steps_through_rows() {
+        let profile = profile();
+        let ids: Vec<u64> = row_spans(&profile, 0, 1, 30.0, 60.0)
+            .iter()
+            .map(|s| s.frame_id)
+            .collect();
+        assert_eq!(ids, [2, 3]);
+        assert!(row_spans(&profile, 0, 1, 95.0, 99.0).is_empty());
+        assert!(row_spans(&profile, 7, 0, 0.0, 100.0).is_empty());
+
+        let step = |id, step| adjacent_span(&profile, id, step).map(|s| s.frame_id);
+        assert_eq!(step(2, SpanStep::Right), Some(3));
+        assert_eq!(step(3, SpanStep::Right), None);
+        assert_eq!(step(3, SpanStep::Left), Some(2));
+        assert_eq!(step(3, SpanStep::Up), Some(1));
+        assert_eq!(step(1, SpanStep::Up), None);
+        assert_eq!(step(1, SpanStep::Down), Some(2));
+        assert_eq!(step(2, SpanStep::Down), None);
+        assert_eq!(step(9, SpanStep::Left), None);
+        assert_eq!(SpanStep::from_name("down"), Some(SpanStep::Down));
+    }
+
+    #[test]
+    fn cycles_through_matches() {
+        let profile = profile();
+        let mut sel = SelectionState {
+            search: "A".into(),
+            ..Default::default()
+        };
+        let ids: Vec<u64> = sel.matches(&profile, &[]).iter().map(|s| s.id).collect();
+        assert_eq!(ids, [1, 2, 3]);
+        assert!(sel.matches(&profile, &[7]).is_empty());
+
+        let mut next = |forward| sel.next_match(&profile, &[], forward).map(|s| s.id);
+        assert_eq!(next(false), Some(3));
+        assert_eq!(next(true), Some(1));
+        assert_eq!(next(false), Some(3));
+        assert_eq!(next(false), Some(2));
+    }
+
+    #[test]
+    fn groups_session_matches_by_profile() {
+        let mut session = Session::from_profile(profile(), "a");
+        let mut other = profile();
+        other.threads[0]
+            .spans
+            .retain(|s| s.name.as_ref() != "layout");
+        session.add_profile(other, "b");
+        session.profiles_mut()[1].offset_us = 1_000.0;
+
+        let mut sel = SelectionState {
+            search: "LAY".into(),
+            ..Default::default()
+        };
+        let found = sel.session_matches(&session);
+        assert_eq!(found.len(), 1);
+        assert_eq!((found[0].label.as_str(), found[0].count), ("a", 1));
+        assert_eq!(found[0].first_span, 2);
+
+        sel.search = "p".into();
+        let found: Vec<_> = sel
+            .session_matches(&session)
+            .into_iter()
+            .map(|m| (m.profile, m.count, m.first_span, m.first_match_us))
+            .collect();
+        assert_eq!(found, [(0, 1, 3, 50.0), (1, 1, 3, 1_050.0)]);
+    }
+}
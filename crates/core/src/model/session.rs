@@ -1,7 +1,17 @@
-use flame_cat_protocol::VisualProfile;
+use std::collections::HashMap;
+
+use flame_cat_protocol::{ClassRules, ProfileMetaEdit, SharedStr, ThreadGroup, VisualProfile};
 use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use super::thread_order::ThreadOrder;
+use crate::transform::{self, NameRewrite, NameRewriteError, NameRewriter};
 
 /// A profiling entry within a session — one loaded profile with alignment data.
+///
+/// Local timestamps map to the session timeline through the linear transform
+/// `session = local_µs * rate + offset_us`. Plain offset alignment leaves
+/// `rate` at 1.0; drift correction ([`Session::correct_drift`]) fits both.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ProfileEntry {
     /// The parsed visual profile.
@@ -9,21 +19,111 @@ pub struct ProfileEntry {
     /// Offset in µs to apply to all timestamps when mapping to the unified
     /// session timeline. Computed from clock domain alignment.
     pub offset_us: f64,
+    /// Clock rate relative to the session timeline (1.0 = no drift).
+    #[serde(default = "unit_rate")]
+    pub rate: f64,
     /// Human-readable label for this profile source.
     pub label: String,
+    /// How `offset_us` was chosen; unknown for entries of older session files.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub alignment: Option<Alignment>,
+    /// Span names from before the session's name rewrites, in
+    /// [`VisualProfile::all_spans`] order; `None` while none are set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    original_names: Option<Vec<SharedStr>>,
+}
+
+/// How a profile was placed on the session timeline.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Alignment {
+    /// The first profile, whose clock the session timeline follows.
+    Origin,
+    /// Same clock as an earlier profile; timestamps are used as they are.
+    SameClock,
+    /// `performance.now()` time placed after a trace's `navigationStart`.
+    NavigationStart,
+    /// A marker `name` occurring once in this profile and once in the
+    /// profile at index `reference`, taken as the same instant.
+    Marker { name: String, reference: usize },
+    /// Nothing in common: the profile starts with the session.
+    SessionStart,
+    /// Fitted through two anchors by drift correction.
+    Drift,
+}
+
+fn unit_rate() -> f64 {
+    1.0
+}
+
+/// A point known to be simultaneous on a profile's clock and the session
+/// timeline, used to fit a drift correction.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ClockAnchor {
+    /// Timestamp in the profile's own value unit.
+    pub local: f64,
+    /// The same instant on the session timeline (µs).
+    pub session_us: f64,
+}
+
+#[derive(Debug, Error, PartialEq)]
+pub enum AlignError {
+    #[error("no profile at index {0}")]
+    NoSuchProfile(usize),
+    #[error("profile values are not time-based")]
+    NotTimeBased,
+    #[error("drift anchors must be at distinct times")]
+    DegenerateAnchors,
+    #[error("drift anchors are in opposite order on the two clocks")]
+    ReversedAnchors,
+    #[error("marker {0:?} not found in both profiles")]
+    MarkerNotFound(String),
 }
 
 impl ProfileEntry {
-    /// Map a timestamp from this profile's local time to the unified session
-    /// timeline, applying the offset and unit normalization.
-    pub fn to_session_time(&self, local_time: f64) -> f64 {
-        let factor = self
-            .profile
+    /// Restore the span names from before the session's name rewrites.
+    fn restore_names(&mut self) {
+        if let Some(names) = self.original_names.take() {
+            let spans = self
+                .profile
+                .threads
+                .iter_mut()
+                .flat_map(|t| t.spans.iter_mut());
+            for (span, name) in spans.zip(names) {
+                span.name = name;
+            }
+        }
+    }
+
+    /// Restore the original span names, then apply `rewriter`, keeping the
+    /// originals while it has rules.
+    fn rewrite_names(&mut self, rewriter: &NameRewriter) -> usize {
+        self.restore_names();
+        if !rewriter.is_empty() {
+            self.original_names = Some(self.profile.all_spans().map(|s| s.name.clone()).collect());
+        }
+        transform::rewrite_names(&mut self.profile, rewriter)
+    }
+
+    /// Factor converting this profile's values to µs.
+    fn us_factor(&self) -> f64 {
+        self.profile
             .meta
             .value_unit
             .to_microseconds_factor()
-            .unwrap_or(1.0);
-        local_time * factor + self.offset_us
+            .unwrap_or(1.0)
+    }
+
+    /// Map a timestamp from this profile's local time to the unified session
+    /// timeline, applying unit normalization and the drift/offset transform.
+    pub fn to_session_time(&self, local_time: f64) -> f64 {
+        local_time * self.us_factor() * self.rate + self.offset_us
+    }
+
+    /// Inverse of [`ProfileEntry::to_session_time`], for turning a session
+    /// window back into this profile's local time range.
+    pub fn to_local_time(&self, session_time: f64) -> f64 {
+        (session_time - self.offset_us) / self.rate / self.us_factor()
     }
 
     /// Start time on the unified session timeline (µs).
@@ -35,6 +135,66 @@ impl ProfileEntry {
     pub fn session_end(&self) -> f64 {
         self.to_session_time(self.profile.meta.end_time)
     }
+
+    /// Apply a user edit to the profile's metadata. A new name also becomes
+    /// the entry's label.
+    pub fn edit_meta(&mut self, edit: &ProfileMetaEdit) {
+        self.profile.meta.apply_edit(edit);
+        if edit.sample_interval_us.is_some()
+            && let Some(samples) = &mut self.profile.cpu_samples
+        {
+            samples.sample_interval_us = self.profile.meta.sample_interval_us;
+        }
+        if edit.name.is_some()
+            && let Some(name) = &self.profile.meta.name
+        {
+            self.label = name.to_string();
+        }
+    }
+}
+
+/// How baseline values are scaled before the primary profile is compared
+/// with them, so recordings of different length or sampling rate line up.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DiffNormalization {
+    /// As recorded: every sample or microsecond counts the same.
+    #[default]
+    PerSample,
+    /// By recording length, as if the baseline ran as long as the primary.
+    PerSecond,
+    /// By share of each profile's total, which also cancels out different
+    /// sampling rates.
+    PerTotal,
+}
+
+impl DiffNormalization {
+    pub const ALL: [Self; 3] = [Self::PerSample, Self::PerSecond, Self::PerTotal];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::PerSample => "per_sample",
+            Self::PerSecond => "per_second",
+            Self::PerTotal => "per_total",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|n| n.name() == name)
+    }
+
+    /// Human-readable label for menus.
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::PerSample => "Per sample",
+            Self::PerSecond => "Per second",
+            Self::PerTotal => "Per total",
+        }
+    }
+
+    fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
 }
 
 /// Multi-profile session container.
@@ -45,6 +205,21 @@ impl ProfileEntry {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Session {
     profiles: Vec<ProfileEntry>,
+    /// How threads are laid out in every view of this session.
+    #[serde(default, skip_serializing_if = "ThreadOrder::is_default")]
+    thread_order: ThreadOrder,
+    /// Index of the profile others are compared against.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    baseline: Option<usize>,
+    /// How the baseline is scaled for comparison.
+    #[serde(default, skip_serializing_if = "DiffNormalization::is_default")]
+    normalization: DiffNormalization,
+    /// Rules every profile's span names are rewritten with.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    name_rewrites: Vec<NameRewrite>,
+    /// How spans are sorted into first-party, dependency and runtime code.
+    #[serde(default, skip_serializing_if = "ClassRules::is_default")]
+    class_rules: ClassRules,
 }
 
 impl Session {
@@ -52,6 +227,11 @@ impl Session {
     pub fn new() -> Self {
         Self {
             profiles: Vec::new(),
+            thread_order: ThreadOrder::default(),
+            baseline: None,
+            normalization: DiffNormalization::default(),
+            name_rewrites: Vec::new(),
+            class_rules: ClassRules::default(),
         }
     }
 
@@ -68,12 +248,89 @@ impl Session {
     /// clock domain with existing profiles. Otherwise offset is 0 (manual
     /// alignment required).
     pub fn add_profile(&mut self, profile: VisualProfile, label: impl Into<String>) {
-        let offset_us = self.compute_offset(&profile);
-        self.profiles.push(ProfileEntry {
+        let (offset_us, alignment) = self.compute_offset(&profile);
+        let mut entry = ProfileEntry {
             profile,
             offset_us,
+            rate: 1.0,
             label: label.into(),
-        });
+            alignment: Some(alignment),
+            original_names: None,
+        };
+        // The rules were checked when they were set, so they compile.
+        if let Ok(rewriter) = NameRewriter::new(&self.name_rewrites) {
+            entry.rewrite_names(&rewriter);
+        }
+        self.profiles.push(entry);
+    }
+
+    /// Replace the constant offset of profile `index` with a linear transform
+    /// fitted through two anchors, correcting clock drift (rate mismatch) as
+    /// well as skew. Anchors should be as far apart as possible.
+    pub fn correct_drift(
+        &mut self,
+        index: usize,
+        a: ClockAnchor,
+        b: ClockAnchor,
+    ) -> Result<(), AlignError> {
+        let entry = self
+            .profiles
+            .get_mut(index)
+            .ok_or(AlignError::NoSuchProfile(index))?;
+        let factor = entry
+            .profile
+            .meta
+            .value_unit
+            .to_microseconds_factor()
+            .ok_or(AlignError::NotTimeBased)?;
+        let local_span = (b.local - a.local) * factor;
+        let session_span = b.session_us - a.session_us;
+        if local_span == 0.0 || session_span == 0.0 {
+            return Err(AlignError::DegenerateAnchors);
+        }
+        let rate = session_span / local_span;
+        if rate <= 0.0 {
+            return Err(AlignError::ReversedAnchors);
+        }
+        entry.rate = rate;
+        entry.offset_us = a.session_us - a.local * factor * rate;
+        entry.alignment = Some(Alignment::Drift);
+        Ok(())
+    }
+
+    /// Fit a drift correction for profile `index` against `reference` using
+    /// two markers present in both (e.g. `navigationStart` and a later shared
+    /// event). The first occurrence of each name is used.
+    pub fn correct_drift_by_markers(
+        &mut self,
+        index: usize,
+        reference: usize,
+        first: &str,
+        second: &str,
+    ) -> Result<(), AlignError> {
+        let entry = self
+            .profiles
+            .get(index)
+            .ok_or(AlignError::NoSuchProfile(index))?;
+        let reference = self
+            .profiles
+            .get(reference)
+            .ok_or(AlignError::NoSuchProfile(reference))?;
+        let anchor = |name: &str| {
+            let find =
+                |p: &VisualProfile| p.markers.iter().find(|m| &*m.name == name).map(|m| m.ts);
+            let local = find(&entry.profile);
+            let reference_ts = find(&reference.profile);
+            match (local, reference_ts) {
+                (Some(local), Some(ts)) => Ok(ClockAnchor {
+                    local,
+                    session_us: reference.to_session_time(ts),
+                }),
+                _ => Err(AlignError::MarkerNotFound(name.to_string())),
+            }
+        };
+        let (a, b) = (anchor(first)?, anchor(second)?);
+        self.correct_drift(index, a, b)
     }
 
     /// All profile entries in the session.
@@ -82,10 +339,111 @@ impl Session {
     }
 
     /// Mutable access to profile entries (for manual offset adjustment).
+    /// Span renames go through [`Session::rename_spans`] instead, so the
+    /// session's name rewrites stay on top of them.
     pub fn profiles_mut(&mut self) -> &mut [ProfileEntry] {
         &mut self.profiles
     }
 
+    /// Rename spans of profile `index` with `rename` (symbolication, source
+    /// maps), which sees the names from before the session's name rewrites;
+    /// the rewrites are then applied again to the result. Returns what
+    /// `rename` returns.
+    pub fn rename_spans<R>(
+        &mut self,
+        index: usize,
+        rename: impl FnOnce(&mut VisualProfile) -> R,
+    ) -> Result<R, AlignError> {
+        let entry = self
+            .profiles
+            .get_mut(index)
+            .ok_or(AlignError::NoSuchProfile(index))?;
+        entry.restore_names();
+        let renamed = rename(&mut entry.profile);
+        // The rules were checked when they were set, so they compile.
+        if let Ok(rewriter) = NameRewriter::new(&self.name_rewrites) {
+            entry.rewrite_names(&rewriter);
+        }
+        Ok(renamed)
+    }
+
+    /// Display order of threads.
+    pub fn thread_order(&self) -> &ThreadOrder {
+        &self.thread_order
+    }
+
+    pub fn set_thread_order(&mut self, order: ThreadOrder) {
+        self.thread_order = order;
+    }
+
+    /// Index of the baseline profile, if one is marked.
+    pub fn baseline_index(&self) -> Option<usize> {
+        self.baseline
+    }
+
+    /// The baseline profile, if one is marked.
+    pub fn baseline(&self) -> Option<&ProfileEntry> {
+        self.profiles.get(self.baseline?)
+    }
+
+    /// Mark profile `index` as the baseline, or clear it with `None`.
+    pub fn set_baseline(&mut self, index: Option<usize>) -> Result<(), AlignError> {
+        if let Some(index) = index
+            && index >= self.profiles.len()
+        {
+            return Err(AlignError::NoSuchProfile(index));
+        }
+        self.baseline = index;
+        Ok(())
+    }
+
+    pub fn diff_normalization(&self) -> DiffNormalization {
+        self.normalization
+    }
+
+    pub fn set_diff_normalization(&mut self, normalization: DiffNormalization) {
+        self.normalization = normalization;
+    }
+
+    /// Rules span names are rewritten with.
+    pub fn name_rewrites(&self) -> &[NameRewrite] {
+        &self.name_rewrites
+    }
+
+    /// Rewrite the span names of every profile with `rules`, in place of
+    /// any earlier rules: names are rewritten from what they were before
+    /// the first rules were set, and empty `rules` restore them. Returns
+    /// how many names differ from those originals.
+    pub fn set_name_rewrites(
+        &mut self,
+        rules: Vec<NameRewrite>,
+    ) -> Result<usize, NameRewriteError> {
+        let rewriter = NameRewriter::new(&rules)?;
+        self.name_rewrites = rules;
+        Ok(self
+            .profiles
+            .iter_mut()
+            .map(|entry| entry.rewrite_names(&rewriter))
+            .sum())
+    }
+
+    /// How spans are sorted into first-party, dependency and runtime code.
+    pub fn class_rules(&self) -> &ClassRules {
+        &self.class_rules
+    }
+
+    pub fn set_class_rules(&mut self, rules: ClassRules) {
+        self.class_rules = rules;
+    }
+
+    /// Threads of profile `index` in display order.
+    pub fn ordered_threads(&self, index: usize) -> Vec<&ThreadGroup> {
+        self.profiles
+            .get(index)
+            .map(|e| self.thread_order.arrange(&e.profile.threads))
+            .unwrap_or_default()
+    }
+
     /// Number of profiles in the session.
     pub fn len(&self) -> usize {
         self.profiles.len()
@@ -123,21 +481,46 @@ impl Session {
         }
     }
 
+    /// Where each profile sits on the session timeline, and why.
+    pub fn info(&self) -> SessionInfo {
+        SessionInfo {
+            start_us: self.start_time(),
+            end_us: self.end_time(),
+            baseline: self.baseline,
+            profiles: self
+                .profiles
+                .iter()
+                .map(|e| ProfileInfo {
+                    label: e.label.clone(),
+                    offset_us: e.offset_us,
+                    rate: e.rate,
+                    start_us: e.session_start(),
+                    end_us: e.session_end(),
+                    alignment: e.alignment.clone(),
+                })
+                .collect(),
+            name_rewrites: self.name_rewrites.clone(),
+            class_rules: self.class_rules.clone(),
+        }
+    }
+
     /// Compute the offset for a new profile based on clock domain compatibility.
     ///
-    /// Four cases:
+    /// Five cases:
     /// 1. Same clock (e.g. both `LinuxMonotonic`): offset=0, unit normalization
     ///    is enough.
     /// 2. `PerformanceNow` ↔ `LinuxMonotonic` with `navigation_start_us`:
     ///    exact alignment — `monotonic = navigation_start + performance.now()`.
     /// 3. `PerformanceNow` ↔ `LinuxMonotonic` without `navigation_start_us`:
     ///    fall through to case 4.
-    /// 4. No time domain or incompatible clocks: align start to session start.
-    fn compute_offset(&self, profile: &VisualProfile) -> f64 {
+    /// 4. No usable clock relation, but a marker shared with an earlier
+    ///    profile: align the marker (see [`Session::marker_offset`]).
+    /// 5. Otherwise: align start to session start.
+    fn compute_offset(&self, profile: &VisualProfile) -> (f64, Alignment) {
         use flame_cat_protocol::ClockKind;
 
         if self.profiles.is_empty() {
-            return 0.0;
+            return (0.0, Alignment::Origin);
         }
 
         if let Some(ref new_td) = profile.meta.time_domain {
@@ -151,7 +534,7 @@ impl Session {
 
                 // Same clock kind: directly comparable.
                 if new_td.clock_kind == existing_td.clock_kind {
-                    return 0.0;
+                    return (0.0, Alignment::SameClock);
                 }
 
                 // PerformanceNow ↔ LinuxMonotonic: use navigationStart anchor.
@@ -162,10 +545,10 @@ impl Session {
                 if let Some(nav_start_us) = nav_start {
                     if new_td.clock_kind == ClockKind::PerformanceNow {
                         // New profile uses performance.now(): session_time = nav_start + local_time_µs
-                        return nav_start_us;
+                        return (nav_start_us, Alignment::NavigationStart);
                     }
                     // New profile is LinuxMonotonic, existing is PerformanceNow (rare)
-                    return 0.0;
+                    return (0.0, Alignment::NavigationStart);
                 }
 
                 // Compatible but no navigationStart — fall through.
@@ -174,16 +557,81 @@ impl Session {
         }
 
         // No compatible clock found (or no time domain at all).
-        // Align new profile's start to the existing session's start.
-        let session_start = self.start_time();
         let new_factor = profile
             .meta
             .value_unit
             .to_microseconds_factor()
             .unwrap_or(1.0);
+        if let Some(aligned) = self.marker_offset(profile, new_factor) {
+            return aligned;
+        }
+        // Align new profile's start to the existing session's start.
+        let session_start = self.start_time();
         let new_start_us = profile.meta.start_time * new_factor;
-        session_start - new_start_us
+        (session_start - new_start_us, Alignment::SessionStart)
     }
+
+    /// Offset that puts a marker of `profile` at the same session time as
+    /// the marker of that name in an earlier profile. Only names occurring
+    /// exactly once in both count; `navigationStart` is preferred, then the
+    /// marker earliest in `profile`. Earlier profiles are tried in order.
+    fn marker_offset(&self, profile: &VisualProfile, factor: f64) -> Option<(f64, Alignment)> {
+        fn unique_markers(profile: &VisualProfile) -> HashMap<&str, Option<f64>> {
+            let mut markers: HashMap<&str, Option<f64>> = HashMap::new();
+            for m in &profile.markers {
+                markers
+                    .entry(&m.name)
+                    .and_modify(|ts| *ts = None)
+                    .or_insert(Some(m.ts));
+            }
+            markers
+        }
+        let local = unique_markers(profile);
+        self.profiles.iter().enumerate().find_map(|(index, entry)| {
+            let reference = unique_markers(&entry.profile);
+            let (name, local_ts, reference_ts) = local
+                .iter()
+                .filter_map(|(&name, &ts)| Some((name, ts?, (*reference.get(name)?)?)))
+                .min_by(|a, b| {
+                    (a.0 != "navigationStart")
+                        .cmp(&(b.0 != "navigationStart"))
+                        .then(a.1.total_cmp(&b.1))
+                        .then(a.0.cmp(b.0))
+                })?;
+            Some((
+                entry.to_session_time(reference_ts) - local_ts * factor,
+                Alignment::Marker {
+                    name: name.to_string(),
+                    reference: index,
+                },
+            ))
+        })
+    }
+}
+
+/// Placement of every profile on the session timeline, for hosts.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SessionInfo {
+    pub start_us: f64,
+    pub end_us: f64,
+    pub baseline: Option<usize>,
+    pub profiles: Vec<ProfileInfo>,
+    /// Rules span names are rewritten with.
+    pub name_rewrites: Vec<NameRewrite>,
+    /// How spans are sorted into first-party, dependency and runtime code.
+    pub class_rules: ClassRules,
+}
+
+/// One profile of a [`SessionInfo`].
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ProfileInfo {
+    pub label: String,
+    pub offset_us: f64,
+    pub rate: f64,
+    /// Extent on the session timeline (µs).
+    pub start_us: f64,
+    pub end_us: f64,
+    pub alignment: Option<Alignment>,
 }
 
 impl Default for Session {
@@ -214,10 +662,18 @@ mod tests {
                 start_time: start,
                 end_time: end,
                 time_domain,
+                truncated_spans: 0,
+                repairs: Default::default(),
+                limits_hit: Default::default(),
+                partial: None,
+                sample_interval_us: None,
+                description: None,
+                tags: vec![],
             },
             threads: vec![ThreadGroup {
                 id: 0,
                 name: "Main".into(),
+                kind: Default::default(),
                 sort_key: 0,
                 max_depth: 0,
                 spans: vec![Span {
@@ -230,7 +686,13 @@ mod tests {
                     self_value: end - start,
                     kind: SpanKind::Event,
                     category: None,
+                    truncated: false,
+                    binary: None,
+                    inlined: vec![],
                 }],
+                pid: None,
+                tid: None,
+                process_name: None,
             }],
             frames: vec![],
             counters: vec![],
@@ -242,6 +704,7 @@ mod tests {
             cpu_samples: None,
             network_requests: vec![],
             screenshots: vec![],
+            binaries: vec![],
         }
     }
 
@@ -379,6 +842,7 @@ mod tests {
 
         // React offset should be navigationStart
         let react_entry = &session.profiles()[1];
+        assert_eq!(react_entry.alignment, Some(Alignment::NavigationStart));
         assert!(
             (react_entry.offset_us - 325_186_769_518.0).abs() < 1.0,
             "React offset should be navigationStart: got {}, expected 325186769518",
@@ -393,4 +857,205 @@ mod tests {
             expected_start,
         );
     }
+
+    #[test]
+    fn drift_correction_fits_linear_transform() {
+        let p1 = make_profile(0.0, 10_000.0, ValueUnit::Microseconds, None);
+        // Second clock runs 0.1% fast and starts 500µs later.
+        let p2 = make_profile(0.0, 10_010.0, ValueUnit::Microseconds, None);
+        let mut session = Session::from_profile(p1, "p1");
+        session.add_profile(p2, "p2");
+
+        let a = ClockAnchor {
+            local: 1_001.0,
+            session_us: 1_500.0,
+        };
+        let b = ClockAnchor {
+            local: 9_009.0,
+            session_us: 9_500.0,
+        };
+        session.correct_drift(1, a, b).unwrap();
+        let entry = &session.profiles()[1];
+        assert!((entry.rate - 8_000.0 / 8_008.0).abs() < 1e-12);
+        assert!((entry.to_session_time(1_001.0) - 1_500.0).abs() < 1e-6);
+        assert!((entry.to_session_time(9_009.0) - 9_500.0).abs() < 1e-6);
+        assert!((entry.to_local_time(entry.to_session_time(4_321.0)) - 4_321.0).abs() < 1e-6);
+
+        assert_eq!(
+            session.correct_drift(1, a, a),
+            Err(AlignError::DegenerateAnchors)
+        );
+        assert_eq!(
+            session.correct_drift(
+                1,
+                a,
+                ClockAnchor {
+                    local: 9_009.0,
+                    session_us: 0.0
+                }
+            ),
+            Err(AlignError::ReversedAnchors)
+        );
+        assert_eq!(
+            session.correct_drift(5, a, b),
+            Err(AlignError::NoSuchProfile(5))
+        );
+    }
+
+    #[test]
+    fn auto_align_by_shared_markers() {
+        use flame_cat_protocol::{Marker, MarkerScope};
+        let marker = |ts: f64, name: &str| Marker {
+            ts,
+            name: name.into(),
+            scope: MarkerScope::Global,
+            category: None,
+        };
+        let mut p1 = make_profile(0.0, 10_000.0, ValueUnit::Microseconds, None);
+        p1.markers = vec![
+            marker(500.0, "tick"),
+            marker(600.0, "tick"),
+            marker(2_000.0, "app:ready"),
+            marker(5_000.0, "navigationStart"),
+        ];
+        let mut p2 = make_profile(40.0, 50.0, ValueUnit::Milliseconds, None);
+        p2.markers = vec![
+            marker(41.0, "tick"),
+            marker(42.0, "app:ready"),
+            marker(45.0, "navigationStart"),
+        ];
+        let mut p3 = make_profile(0.0, 10.0, ValueUnit::Milliseconds, None);
+        p3.markers = vec![marker(1.0, "tick"), marker(3.0, "app:ready")];
+        let p4 = make_profile(70.0, 80.0, ValueUnit::Microseconds, None);
+
+        let mut session = Session::from_profile(p1, "p1");
+        session.add_profile(p2, "p2");
+        session.add_profile(p3, "p3");
+        session.add_profile(p4, "p4");
+        let info = session.info();
+        let alignments: Vec<_> = info.profiles.iter().map(|p| p.alignment.clone()).collect();
+        assert_eq!(
+            alignments,
+            [
+                Some(Alignment::Origin),
+                Some(Alignment::Marker {
+                    name: "navigationStart".into(),
+                    reference: 0
+                }),
+                // `tick` is ambiguous in p1.
+                Some(Alignment::Marker {
+                    name: "app:ready".into(),
+                    reference: 0
+                }),
+                Some(Alignment::SessionStart),
+            ]
+        );
+        let entries = session.profiles();
+        assert!((entries[1].to_session_time(45.0) - 5_000.0).abs() < 1e-6);
+        assert!((entries[2].to_session_time(3.0) - 2_000.0).abs() < 1e-6);
+        assert_eq!(info.profiles[3].start_us, info.start_us);
+    }
+
+    #[test]
+    fn drift_correction_from_shared_markers() {
+        use flame_cat_protocol::{Marker, MarkerScope};
+        let marker = |ts: f64, name: &str| Marker {
+            ts,
+            name: name.into(),
+            scope: MarkerScope::Global,
+            category: None,
+        };
+        let mut p1 = make_profile(0.0, 10_000.0, ValueUnit::Microseconds, None);
+        p1.markers = vec![marker(100.0, "navigationStart"), marker(8_100.0, "load")];
+        let mut p2 = make_profile(0.0, 10_000.0, ValueUnit::Milliseconds, None);
+        p2.markers = vec![marker(0.0, "navigationStart"), marker(8.0, "load")];
+
+        let mut session = Session::from_profile(p1, "p1");
+        session.add_profile(p2, "p2");
+        session
+            .correct_drift_by_markers(1, 0, "navigationStart", "load")
+            .unwrap();
+        let entry = &session.profiles()[1];
+        assert!((entry.to_session_time(0.0) - 100.0).abs() < 1e-6);
+        assert!((entry.to_session_time(8.0) - 8_100.0).abs() < 1e-6);
+        assert_eq!(entry.alignment, Some(Alignment::Drift));
+
+        assert_eq!(
+            session.correct_drift_by_markers(1, 0, "navigationStart", "missing"),
+            Err(AlignError::MarkerNotFound("missing".into()))
+        );
+    }
+
+    #[test]
+    fn name_rewrites_replace_earlier_rules() {
+        let rule = |pattern: &str, replacement: &str| NameRewrite {
+            pattern: pattern.into(),
+            replacement: replacement.into(),
+        };
+        let mut profile = make_profile(0.0, 100.0, ValueUnit::Microseconds, None);
+        profile.threads[0].spans[0].name = "Vec<u8>::push::{{closure}}#12".into();
+        let mut session = Session::from_profile(profile.clone(), "p1");
+        let name = |session: &Session, index: usize| {
+            session.profiles()[index].profile.threads[0].spans[0]
+                .name
+                .clone()
+        };
+
+        assert_eq!(
+            session.set_name_rewrites(vec![rule(r"#\d+$", "")]).unwrap(),
+            1
+        );
+        assert_eq!(&*name(&session, 0), "Vec<u8>::push::{{closure}}");
+        session
+            .set_name_rewrites(vec![rule(r"<[^>]*>", "<…>")])
+            .unwrap();
+        assert_eq!(&*name(&session, 0), "Vec<…>::push::{{closure}}#12");
+        // Profiles added later are rewritten too.
+        session.add_profile(profile, "p2");
+        assert_eq!(&*name(&session, 1), "Vec<…>::push::{{closure}}#12");
+
+        let json = serde_json::to_string(&session).unwrap();
+        let mut reloaded: Session = serde_json::from_str(&json).unwrap();
+        assert_eq!(reloaded.name_rewrites(), session.name_rewrites());
+        assert_eq!(reloaded.set_name_rewrites(vec![]).unwrap(), 0);
+        assert_eq!(&*name(&reloaded, 1), "Vec<u8>::push::{{closure}}#12");
+
+        assert!(session.set_name_rewrites(vec![rule("(", "")]).is_err());
+        assert_eq!(session.name_rewrites().len(), 1);
+    }
+
+    #[test]
+    fn name_rewrites_keep_symbolicated_names() {
+        let mut profile = make_profile(0.0, 100.0, ValueUnit::Microseconds, None);
+        profile.threads[0].spans[0].name = "0x7f0000001010".into();
+        let mut session = Session::from_profile(profile, "p1");
+        let name = |session: &Session| {
+            session.profiles()[0].profile.threads[0].spans[0]
+                .name
+                .clone()
+        };
+        let rules = vec![NameRewrite {
+            pattern: "^render$".into(),
+            replacement: "draw".into(),
+        }];
+        session.set_name_rewrites(rules).unwrap();
+
+        let mut symbolicator = crate::symbolicate::Symbolicator::new();
+        symbolicator
+            .add_perf_map("7f0000001000 40 render\n")
+            .unwrap();
+        let resolved = session
+            .rename_spans(0, |profile| symbolicator.apply_visual(profile))
+            .unwrap();
+        assert_eq!(resolved, 1);
+        // The rules apply to the resolved name...
+        assert_eq!(&*name(&session), "draw");
+        // ...and clearing them goes back to it, not to the address.
+        session.set_name_rewrites(vec![]).unwrap();
+        assert_eq!(&*name(&session), "render");
+        assert_eq!(
+            session.rename_spans(1, |_| ()),
+            Err(AlignError::NoSuchProfile(1))
+        );
+    }
 }
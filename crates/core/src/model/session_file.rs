@@ -0,0 +1,447 @@
+//! Versioned `.flamecat` session files.
+//!
+//! A session file bundles every loaded profile (with its alignment offset
+//! and label) together with the user's annotations, hidden functions and
+//! view state, so an investigation can be closed and reopened exactly as it
+//! was left. Files are JSON with a `format`/`version` header written first,
+//! which keeps them sniffable from the first few bytes.
+
+use std::collections::BTreeMap;
+
+use flame_cat_protocol::SharedStr;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use super::Session;
+
+/// Value of the `format` header field.
+pub const SESSION_FORMAT: &str = "flamecat-session";
+/// Current session file version. Readers reject files from newer versions.
+pub const SESSION_VERSION: u32 = 1;
+
+#[derive(Debug, Error)]
+pub enum SessionFileError {
+    #[error("invalid session JSON: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("not a flame.cat session file")]
+    NotASession,
+    #[error("session file version {0} is newer than supported version {SESSION_VERSION}")]
+    UnsupportedVersion(u32),
+}
+
+/// Everything saved alongside the profiles themselves.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SessionState {
+    /// User notes pinned to points or ranges on the session timeline.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub annotations: Vec<Annotation>,
+    /// Function names the user chose to hide from views.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub hidden_functions: Vec<String>,
+    /// Named viewports the user saved, in the order they were added.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub regions: Vec<ViewRegion>,
+    /// Limits per counter name. Samples that break them are shaded; see
+    /// `CounterUnit::exceeds_budget`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub counter_budgets: BTreeMap<String, f64>,
+    /// Span ids of the primary profile the user bookmarked, oldest first.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub bookmarks: Vec<u64>,
+    /// Spans in the comparison tray, in the order they were pinned.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pins: Vec<PinnedSpan>,
+    #[serde(default)]
+    pub view: ViewState,
+}
+
+impl SessionState {
+    /// Save a region, replacing any region of the same name in place.
+    pub fn save_region(&mut self, region: ViewRegion) {
+        match self.regions.iter_mut().find(|r| r.name == region.name) {
+            Some(existing) => *existing = region,
+            None => self.regions.push(region),
+        }
+    }
+
+    pub fn region(&self, name: &str) -> Option<&ViewRegion> {
+        self.regions.iter().find(|r| r.name == name)
+    }
+
+    /// Bookmark a span, or remove its bookmark. Returns whether it is
+    /// bookmarked now.
+    pub fn toggle_bookmark(&mut self, span_id: u64) -> bool {
+        match self.bookmarks.iter().position(|&id| id == span_id) {
+            Some(index) => {
+                self.bookmarks.remove(index);
+                false
+            }
+            None => {
+                self.bookmarks.push(span_id);
+                true
+            }
+        }
+    }
+
+    /// Add notes, e.g. from a sidecar, skipping any already present so a
+    /// file imported twice adds nothing. Returns how many were added.
+    pub fn add_annotations(&mut self, notes: impl IntoIterator<Item = Annotation>) -> usize {
+        let before = self.annotations.len();
+        for note in notes {
+            if !self.annotations.contains(&note) {
+                self.annotations.push(note);
+            }
+        }
+        self.annotations.len() - before
+    }
+
+    /// Pin a span to the comparison tray, or unpin it. Returns whether it
+    /// is pinned now.
+    pub fn toggle_pin(&mut self, pin: PinnedSpan) -> bool {
+        match self.pins.iter().position(|&p| p == pin) {
+            Some(index) => {
+                self.pins.remove(index);
+                false
+            }
+            None => {
+                self.pins.push(pin);
+                true
+            }
+        }
+    }
+
+    /// The pinned spans of `session` side by side, in pin order. Pins whose
+    /// profile or span is gone are left out.
+    pub fn pinned_spans(&self, session: &Session) -> Vec<PinnedRow> {
+        let rows: Vec<PinnedRow> = self
+            .pins
+            .iter()
+            .filter_map(|&pin| {
+                let entry = session.profiles().get(pin.profile)?;
+                let span = entry.profile.span(pin.span)?;
+                Some(PinnedRow {
+                    pin,
+                    label: entry.label.clone(),
+                    name: span.name.clone(),
+                    start_us: entry.to_session_time(span.start),
+                    end_us: entry.to_session_time(span.end),
+                    duration: span.duration(),
+                    self_value: span.self_value,
+                    ratio: None,
+                })
+            })
+            .collect();
+        let first = rows.first().map(|r| r.duration);
+        rows.into_iter()
+            .map(|row| PinnedRow {
+                ratio: first.filter(|&d| d > 0.0).map(|d| row.duration / d),
+                ..row
+            })
+            .collect()
+    }
+}
+
+/// A span in the comparison tray. Spans of any profile of the session can
+/// be pinned, so repeated runs of one operation line up side by side.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PinnedSpan {
+    /// Index of the profile in the session.
+    pub profile: usize,
+    pub span: u64,
+}
+
+/// A pinned span as the comparison tray shows it.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PinnedRow {
+    pub pin: PinnedSpan,
+    /// Label of the span's profile.
+    pub label: String,
+    pub name: SharedStr,
+    /// Extent of the span on the session timeline (µs).
+    pub start_us: f64,
+    pub end_us: f64,
+    /// Duration and self time, in the profile's value unit.
+    pub duration: f64,
+    pub self_value: f64,
+    /// Duration relative to the first pinned span, e.g. 1.5 for half again
+    /// as long.
+    pub ratio: Option<f64>,
+}
+
+/// A named viewport, e.g. "startup" or "first interaction".
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ViewRegion {
+    pub name: String,
+    /// Window as fractions of the session duration, like [`ViewState`].
+    pub view_start: f64,
+    pub view_end: f64,
+}
+
+/// A user note on the session timeline.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Annotation {
+    /// Session time (µs) the note refers to.
+    pub start_us: f64,
+    /// End of the annotated range, for range notes.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub end_us: Option<f64>,
+    pub text: String,
+    /// Lane the note is attached to, by name; `None` for the whole timeline.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lane: Option<String>,
+}
+
+/// Viewer state restored when a session is reopened.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ViewState {
+    /// Active view (`"time_order"`, `"left_heavy"`, …).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub view_type: Option<String>,
+    /// Visible window as fractions of the session duration.
+    pub view_start: f64,
+    pub view_end: f64,
+    #[serde(default)]
+    pub scroll_y: f32,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub search: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub selected_frame: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub theme: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color_mode: Option<String>,
+    /// Per-lane layout, matched back to lanes by name.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub lanes: Vec<LaneView>,
+}
+
+impl Default for ViewState {
+    fn default() -> Self {
+        Self {
+            view_type: None,
+            view_start: 0.0,
+            view_end: 1.0,
+            scroll_y: 0.0,
+            search: String::new(),
+            selected_frame: None,
+            theme: None,
+            color_mode: None,
+            lanes: Vec::new(),
+        }
+    }
+}
+
+/// Saved layout for one lane.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LaneView {
+    pub name: String,
+    pub visible: bool,
+    pub height: f32,
+}
+
+#[derive(Serialize)]
+struct SessionFileOut<'a> {
+    format: &'static str,
+    version: u32,
+    session: &'a Session,
+    #[serde(flatten)]
+    state: &'a SessionState,
+}
+
+/// A whole `.flamecat` file. The header fields default so that other JSON
+/// deserializes too and is then rejected as not a session.
+#[derive(Deserialize)]
+struct SessionFileIn {
+    #[serde(default)]
+    format: String,
+    #[serde(default)]
+    version: u32,
+    #[serde(default)]
+    session: Option<Session>,
+    #[serde(flatten)]
+    state: SessionState,
+}
+
+/// Returns `true` if `data` looks like a session file (cheap prefix check).
+pub fn is_session_file(data: &[u8]) -> bool {
+    let head = &data[..data.len().min(128)];
+    let head = String::from_utf8_lossy(head);
+    let compact: String = head.chars().filter(|c| !c.is_whitespace()).collect();
+    compact.starts_with(&format!("{{\"format\":\"{SESSION_FORMAT}\""))
+}
+
+/// Serialize a session and its state to `.flamecat` JSON.
+pub fn save_session(session: &Session, state: &SessionState) -> Result<String, SessionFileError> {
+    Ok(serde_json::to_string(&SessionFileOut {
+        format: SESSION_FORMAT,
+        version: SESSION_VERSION,
+        session,
+        state,
+    })?)
+}
+
+/// Parse a `.flamecat` file back into a session and its state.
+pub fn load_session(data: &[u8]) -> Result<(Session, SessionState), SessionFileError> {
+    let file: SessionFileIn = serde_json::from_slice(data)?;
+    if file.format != SESSION_FORMAT {
+        return Err(SessionFileError::NotASession);
+    }
+    if file.version > SESSION_VERSION {
+        return Err(SessionFileError::UnsupportedVersion(file.version));
+    }
+    let session = file.session.ok_or(SessionFileError::NotASession)?;
+    Ok((session, file.state))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parsers::chrome::parse_chrome_trace;
+
+    fn sample_session() -> Session {
+        let json = r#"{"traceEvents":[
+            {"name":"main","ph":"X","ts":0,"dur":100,"pid":1,"tid":1}
+        ]}"#;
+        let profile = parse_chrome_trace(json.as_bytes())
+            .unwrap()
+            .into_visual_profile();
+        let mut session = Session::from_profile(profile.clone(), "first");
+        session.add_profile(profile, "second");
+        session.profiles_mut()[1].offset_us = 42.0;
+        session
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut session = sample_session();
+        session.set_thread_order(crate::model::ThreadOrder {
+            sort: crate::model::ThreadSort::BusyTime,
+            pinned: vec!["Main".into()],
+        });
+        session.profiles_mut()[0].edit_meta(&flame_cat_protocol::ProfileMetaEdit {
+            name: Some(" checkout ".into()),
+            description: Some("cold start".into()),
+            tags: Some(vec!["prod".into(), " ".into(), "prod".into(), "v2".into()]),
+            sample_interval_us: Some(250.0),
+        });
+        session.set_baseline(Some(1)).unwrap();
+        session.set_diff_normalization(crate::model::DiffNormalization::PerTotal);
+        assert_eq!(
+            session.set_baseline(Some(2)),
+            Err(crate::model::AlignError::NoSuchProfile(2))
+        );
+        let mut state = SessionState {
+            annotations: vec![Annotation {
+                start_us: 10.0,
+                end_us: Some(20.0),
+                text: "slow here".into(),
+                lane: Some("Main".into()),
+            }],
+            hidden_functions: vec!["(idle)".into()],
+            regions: vec![],
+            counter_budgets: BTreeMap::from([("power.rails.cpu".into(), 1_500.0)]),
+            bookmarks: vec![],
+            pins: vec![],
+            view: ViewState {
+                view_type: Some("left_heavy".into()),
+                view_start: 0.25,
+                view_end: 0.5,
+                search: "main".into(),
+                ..ViewState::default()
+            },
+        };
+        let region = |name: &str, view_start, view_end| ViewRegion {
+            name: name.into(),
+            view_start,
+            view_end,
+        };
+        state.save_region(region("startup", 0.0, 0.5));
+        state.save_region(region("idle", 0.5, 1.0));
+        state.save_region(region("startup", 0.0, 0.25));
+        for id in [3, 1, 3, 2] {
+            state.toggle_bookmark(id);
+        }
+        for (profile, span) in [(1, 0), (0, 0), (0, 7)] {
+            state.toggle_pin(PinnedSpan { profile, span });
+        }
+        let json = save_session(&session, &state).unwrap();
+        assert!(is_session_file(json.as_bytes()));
+
+        let (loaded, loaded_state) = load_session(json.as_bytes()).unwrap();
+        assert_eq!(loaded.len(), 2);
+        assert_eq!(loaded.profiles()[1].label, "second");
+        assert_eq!(loaded.baseline_index(), Some(1));
+        assert_eq!(
+            loaded.diff_normalization(),
+            crate::model::DiffNormalization::PerTotal
+        );
+        let first = &loaded.profiles()[0];
+        assert_eq!(first.label, "checkout");
+        assert_eq!(first.profile.meta.name.as_deref(), Some("checkout"));
+        assert_eq!(
+            first.profile.meta.description.as_deref(),
+            Some("cold start")
+        );
+        assert_eq!(first.profile.meta.tags, ["prod", "v2"]);
+        assert_eq!(first.profile.meta.sample_interval_us, Some(250.0));
+        assert_eq!(loaded.profiles()[1].offset_us, 42.0);
+        assert_eq!(loaded.profiles()[0].profile.span_count(), 1);
+        assert_eq!(loaded.thread_order(), session.thread_order());
+        assert_eq!(loaded_state.annotations.len(), 1);
+        assert_eq!(loaded_state.hidden_functions, vec!["(idle)".to_string()]);
+        assert_eq!(loaded_state.view.view_type.as_deref(), Some("left_heavy"));
+        assert_eq!(loaded_state.view.view_start, 0.25);
+        assert_eq!(
+            loaded_state.regions,
+            [region("startup", 0.0, 0.25), region("idle", 0.5, 1.0)]
+        );
+        assert_eq!(loaded_state.region("idle"), Some(&region("idle", 0.5, 1.0)));
+        assert_eq!(loaded_state.counter_budgets, state.counter_budgets);
+        assert_eq!(loaded_state.bookmarks, [1, 2]);
+        assert_eq!(loaded_state.pins, state.pins);
+    }
+
+    #[test]
+    fn compares_pinned_spans_across_profiles() {
+        let mut session = sample_session();
+        let slow = &mut session.profiles_mut()[1].profile.threads[0].spans[0];
+        slow.end = 150.0;
+        slow.self_value = 150.0;
+        let id = slow.id;
+        let mut state = SessionState::default();
+        for profile in [0, 1, 2] {
+            state.toggle_pin(PinnedSpan { profile, span: id });
+        }
+        let rows = state.pinned_spans(&session);
+        let summary: Vec<(&str, f64, f64, Option<f64>)> = rows
+            .iter()
+            .map(|r| (r.label.as_str(), r.start_us, r.duration, r.ratio))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("first", 0.0, 100.0, Some(1.0)),
+                ("second", 42.0, 150.0, Some(1.5))
+            ]
+        );
+        assert!(!state.toggle_pin(PinnedSpan {
+            profile: 0,
+            span: id
+        }));
+        assert_eq!(state.pinned_spans(&session)[0].ratio, Some(1.0));
+    }
+
+    #[test]
+    fn rejects_other_files_and_newer_versions() {
+        assert!(!is_session_file(br#"{"traceEvents":[]}"#));
+        assert!(matches!(
+            load_session(br#"{"traceEvents":[]}"#),
+            Err(SessionFileError::NotASession)
+        ));
+        let future = format!(r#"{{"format":"{SESSION_FORMAT}","version":99}}"#);
+        assert!(matches!(
+            load_session(future.as_bytes()),
+            Err(SessionFileError::UnsupportedVersion(99))
+        ));
+    }
+}
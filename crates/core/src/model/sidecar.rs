@@ -0,0 +1,180 @@
+//! Annotation sidecar files.
+//!
+//! A sidecar is a small JSON file shipped next to a profile, typically
+//! written by a logging pipeline, that marks deploy points, test phases and
+//! the like:
+//!
+//! ```json
+//! {"annotations": [
+//!   {"text": "deploy v2", "ts": 1200.5},
+//!   {"text": "warm-up", "ts": 0, "end": 5000, "lane": "Main"},
+//!   {"text": "checkout test", "span": "runCheckoutTest"}
+//! ]}
+//! ```
+//!
+//! A bare array of entries is accepted too. Timestamps are on the
+//! profile's own clock and in its value unit, like the spans they sit
+//! between; an entry keyed by span name covers the earliest span of that
+//! name instead.
+
+use serde::Deserialize;
+use thiserror::Error;
+
+use super::Session;
+use super::session_file::Annotation;
+
+#[derive(Debug, Error)]
+pub enum SidecarError {
+    #[error("invalid annotation sidecar: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("no profile at index {0}")]
+    NoSuchProfile(usize),
+    #[error("annotation {0} has neither a `ts` nor a `span`")]
+    Unanchored(usize),
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum SidecarFile {
+    Wrapped { annotations: Vec<SidecarEntry> },
+    Bare(Vec<SidecarEntry>),
+}
+
+#[derive(Deserialize)]
+struct SidecarEntry {
+    text: String,
+    #[serde(default)]
+    ts: Option<f64>,
+    #[serde(default)]
+    end: Option<f64>,
+    /// Name of the span the note covers, when there is no `ts`.
+    #[serde(default)]
+    span: Option<String>,
+    #[serde(default)]
+    lane: Option<String>,
+}
+
+/// Annotations read from a sidecar, placed on the session timeline.
+#[derive(Debug, Clone, Default)]
+pub struct SidecarImport {
+    pub annotations: Vec<Annotation>,
+    /// Span names of entries no span of the profile matched, in file order.
+    pub unresolved: Vec<String>,
+}
+
+/// Read the sidecar `data` for the profile at `profile` in `session`.
+pub fn import_sidecar(
+    data: &[u8],
+    session: &Session,
+    profile: usize,
+) -> Result<SidecarImport, SidecarError> {
+    let entries = match serde_json::from_slice(data)? {
+        SidecarFile::Wrapped { annotations } => annotations,
+        SidecarFile::Bare(entries) => entries,
+    };
+    let entry = session
+        .profiles()
+        .get(profile)
+        .ok_or(SidecarError::NoSuchProfile(profile))?;
+    let mut import = SidecarImport::default();
+    for (index, note) in entries.into_iter().enumerate() {
+        let (start, end, lane) = match (note.ts, &note.span) {
+            (Some(ts), _) => (ts, note.end, note.lane),
+            (None, Some(name)) => {
+                let found = entry
+                    .profile
+                    .threads
+                    .iter()
+                    .flat_map(|t| t.spans.iter().map(move |s| (t, s)))
+                    .filter(|(_, s)| s.name.as_ref() == name.as_str())
+                    .min_by(|a, b| a.1.start.total_cmp(&b.1.start));
+                let Some((thread, span)) = found else {
+                    import.unresolved.push(name.clone());
+                    continue;
+                };
+                (
+                    span.start,
+                    Some(span.end),
+                    note.lane.or_else(|| Some(thread.name.to_string())),
+                )
+            }
+            (None, None) => return Err(SidecarError::Unanchored(index)),
+        };
+        import.annotations.push(Annotation {
+            start_us: entry.to_session_time(start),
+            end_us: end.map(|e| entry.to_session_time(e)),
+            text: note.text,
+            lane,
+        });
+    }
+    Ok(import)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parsers::chrome::parse_chrome_trace;
+
+    fn session() -> Session {
+        let json = r#"{"traceEvents":[
+            {"name":"thread_name","ph":"M","ts":0,"pid":1,"tid":1,"args":{"name":"Main"}},
+            {"name":"runTests","ph":"X","ts":100,"dur":50,"pid":1,"tid":1},
+            {"name":"runTests","ph":"X","ts":300,"dur":20,"pid":1,"tid":1}
+        ]}"#;
+        let profile = parse_chrome_trace(json.as_bytes())
+            .unwrap()
+            .into_visual_profile();
+        let mut session = Session::from_profile(profile.clone(), "first");
+        session.add_profile(profile, "second");
+        session.profiles_mut()[1].offset_us = 1_000.0;
+        session
+    }
+
+    #[test]
+    fn places_notes_by_time_or_span_name() {
+        let data = br#"{"annotations":[
+            {"text":"deploy","ts":120},
+            {"text":"tests","span":"runTests"},
+            {"text":"phase","ts":0,"end":10,"lane":"Other"},
+            {"text":"gone","span":"missing"}
+        ]}"#;
+        let import = import_sidecar(data, &session(), 1).unwrap();
+        let notes: Vec<(&str, f64, Option<f64>, Option<&str>)> = import
+            .annotations
+            .iter()
+            .map(|a| (a.text.as_str(), a.start_us, a.end_us, a.lane.as_deref()))
+            .collect();
+        assert_eq!(
+            notes,
+            [
+                ("deploy", 1_120.0, None, None),
+                ("tests", 1_100.0, Some(1_150.0), Some("Main")),
+                ("phase", 1_000.0, Some(1_010.0), Some("Other")),
+            ]
+        );
+        assert_eq!(import.unresolved, ["missing"]);
+
+        let mut state = crate::model::SessionState::default();
+        assert_eq!(state.add_annotations(import.annotations.clone()), 3);
+        assert_eq!(state.add_annotations(import.annotations), 0);
+    }
+
+    #[test]
+    fn accepts_bare_arrays_and_rejects_bad_entries() {
+        let session = session();
+        let import = import_sidecar(br#"[{"text":"a","ts":1}]"#, &session, 0).unwrap();
+        assert_eq!(import.annotations.len(), 1);
+        assert!(matches!(
+            import_sidecar(br#"[{"text":"a"},{"text":"b"}]"#, &session, 0),
+            Err(SidecarError::Unanchored(0))
+        ));
+        assert!(matches!(
+            import_sidecar(b"[]", &session, 2),
+            Err(SidecarError::NoSuchProfile(2))
+        ));
+        assert!(matches!(
+            import_sidecar(br#"{"traceEvents":[]}"#, &session, 0),
+            Err(SidecarError::Json(_))
+        ));
+    }
+}
@@ -0,0 +1,194 @@
+//! User-chosen display order of a profile's threads.
+
+use flame_cat_protocol::ThreadGroup;
+use serde::{Deserialize, Serialize};
+
+/// How threads are ordered below the pinned ones.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ThreadSort {
+    /// Most spans first; ties keep the parser's main-thread-first order.
+    #[default]
+    SpanCount,
+    /// Alphabetically by display name.
+    Name,
+    /// Most time covered by top-level spans first.
+    BusyTime,
+    /// Earliest first span first.
+    FirstActivity,
+}
+
+impl ThreadSort {
+    pub const ALL: [Self; 4] = [
+        Self::SpanCount,
+        Self::Name,
+        Self::BusyTime,
+        Self::FirstActivity,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::SpanCount => "span_count",
+            Self::Name => "name",
+            Self::BusyTime => "busy_time",
+            Self::FirstActivity => "first_activity",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|s| s.name() == name)
+    }
+
+    /// Human-readable label for menus.
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::SpanCount => "Span count",
+            Self::Name => "Name",
+            Self::BusyTime => "Busy time",
+            Self::FirstActivity => "First activity",
+        }
+    }
+}
+
+/// Thread ordering: pinned threads first (in pin order), then the rest by
+/// [`ThreadSort`]. Threads are referred to by display name, which is unique
+/// within a profile and stable across reloads.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ThreadOrder {
+    #[serde(default)]
+    pub sort: ThreadSort,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pinned: Vec<String>,
+}
+
+fn busy_time(thread: &ThreadGroup) -> f64 {
+    thread
+        .spans
+        .iter()
+        .filter(|s| s.depth == 0)
+        .map(flame_cat_protocol::Span::duration)
+        .sum()
+}
+
+fn first_activity(thread: &ThreadGroup) -> f64 {
+    thread
+        .spans
+        .iter()
+        .map(|s| s.start)
+        .fold(f64::INFINITY, f64::min)
+}
+
+impl ThreadOrder {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    pub fn is_pinned(&self, name: &str) -> bool {
+        self.pinned.iter().any(|p| p == name)
+    }
+
+    /// Pin `name` below the already pinned threads, or unpin it.
+    pub fn set_pinned(&mut self, name: &str, pinned: bool) {
+        self.pinned.retain(|p| p != name);
+        if pinned {
+            self.pinned.push(name.to_string());
+        }
+    }
+
+    /// `threads` in display order.
+    pub fn arrange<'a>(&self, threads: &'a [ThreadGroup]) -> Vec<&'a ThreadGroup> {
+        let mut ordered: Vec<&ThreadGroup> = threads.iter().collect();
+        match self.sort {
+            ThreadSort::SpanCount => ordered.sort_by_key(|t| std::cmp::Reverse(t.spans.len())),
+            ThreadSort::Name => ordered.sort_by(|a, b| a.name.cmp(&b.name)),
+            ThreadSort::BusyTime => {
+                ordered.sort_by(|a, b| busy_time(b).total_cmp(&busy_time(a)));
+            }
+            ThreadSort::FirstActivity => {
+                ordered.sort_by(|a, b| first_activity(a).total_cmp(&first_activity(b)));
+            }
+        }
+        // Stable sort: unpinned threads keep their relative order.
+        ordered.sort_by_key(|t| {
+            self.pinned
+                .iter()
+                .position(|p| **p == *t.name)
+                .unwrap_or(usize::MAX)
+        });
+        ordered
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use flame_cat_protocol::{SharedStr, Span, SpanKind};
+
+    use super::*;
+
+    fn thread(id: u32, name: &str, spans: &[(f64, f64)]) -> ThreadGroup {
+        ThreadGroup {
+            id,
+            name: SharedStr::from(name),
+            kind: Default::default(),
+            sort_key: 0,
+            pid: None,
+            tid: None,
+            process_name: None,
+            spans: spans
+                .iter()
+                .enumerate()
+                .map(|(i, &(start, end))| Span {
+                    id: u64::from(id) * 100 + i as u64,
+                    name: SharedStr::from("work"),
+                    start,
+                    end,
+                    depth: 0,
+                    parent: None,
+                    self_value: end - start,
+                    kind: SpanKind::Event,
+                    category: None,
+                    truncated: false,
+                    binary: None,
+                    inlined: vec![],
+                })
+                .collect(),
+            max_depth: 0,
+        }
+    }
+
+    fn names(order: &ThreadOrder, threads: &[ThreadGroup]) -> Vec<String> {
+        order
+            .arrange(threads)
+            .iter()
+            .map(|t| t.name.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn sorts_and_pins() {
+        let threads = [
+            thread(0, "io", &[(50.0, 60.0), (70.0, 80.0), (90.0, 95.0)]),
+            thread(1, "main", &[(10.0, 100.0)]),
+            thread(2, "gc", &[(5.0, 6.0), (7.0, 8.0)]),
+        ];
+        let mut order = ThreadOrder::default();
+        assert_eq!(names(&order, &threads), ["io", "gc", "main"]);
+
+        order.sort = ThreadSort::Name;
+        assert_eq!(names(&order, &threads), ["gc", "io", "main"]);
+        order.sort = ThreadSort::BusyTime;
+        assert_eq!(names(&order, &threads), ["main", "io", "gc"]);
+        order.sort = ThreadSort::FirstActivity;
+        assert_eq!(names(&order, &threads), ["gc", "main", "io"]);
+
+        order.set_pinned("io", true);
+        order.set_pinned("main", true);
+        assert_eq!(names(&order, &threads), ["io", "main", "gc"]);
+        order.set_pinned("io", false);
+        assert_eq!(names(&order, &threads), ["main", "gc", "io"]);
+        assert_eq!(
+            ThreadSort::from_name("busy_time"),
+            Some(ThreadSort::BusyTime)
+        );
+    }
+}
@@ -0,0 +1,122 @@
+//! Bounds-checked reads over a byte slice, for the binary formats, in
+//! either byte order. Callers turn [`Truncated`] into their own error and
+//! add the reads only their format needs.
+
+use std::marker::PhantomData;
+
+/// Byte order of the integers a [`Reader`] reads.
+pub(crate) trait ByteOrder {
+    fn u16(bytes: [u8; 2]) -> u16;
+    fn u32(bytes: [u8; 4]) -> u32;
+    fn u64(bytes: [u8; 8]) -> u64;
+}
+
+/// Least significant byte first.
+pub(crate) struct LittleEndian;
+
+/// Most significant byte first.
+pub(crate) struct BigEndian;
+
+impl ByteOrder for LittleEndian {
+    fn u16(bytes: [u8; 2]) -> u16 {
+        u16::from_le_bytes(bytes)
+    }
+
+    fn u32(bytes: [u8; 4]) -> u32 {
+        u32::from_le_bytes(bytes)
+    }
+
+    fn u64(bytes: [u8; 8]) -> u64 {
+        u64::from_le_bytes(bytes)
+    }
+}
+
+impl ByteOrder for BigEndian {
+    fn u16(bytes: [u8; 2]) -> u16 {
+        u16::from_be_bytes(bytes)
+    }
+
+    fn u32(bytes: [u8; 4]) -> u32 {
+        u32::from_be_bytes(bytes)
+    }
+
+    fn u64(bytes: [u8; 8]) -> u64 {
+        u64::from_be_bytes(bytes)
+    }
+}
+
+/// A read ran past the end of the data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Truncated;
+
+/// Cursor over a byte slice whose integers are in byte order `E`.
+pub(crate) struct Reader<'a, E> {
+    data: &'a [u8],
+    pos: usize,
+    order: PhantomData<E>,
+}
+
+impl<'a, E: ByteOrder> Reader<'a, E> {
+    pub(crate) fn new(data: &'a [u8]) -> Self {
+        Self {
+            data,
+            pos: 0,
+            order: PhantomData,
+        }
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.pos >= self.data.len()
+    }
+
+    /// Number of bytes not read yet.
+    pub(crate) fn remaining(&self) -> usize {
+        self.data.len() - self.pos
+    }
+
+    /// Offset of the next byte from the start of the data.
+    pub(crate) fn position(&self) -> usize {
+        self.pos
+    }
+
+    /// Everything not read yet, leaving the reader empty.
+    pub(crate) fn rest(&mut self) -> &'a [u8] {
+        let rest = &self.data[self.pos..];
+        self.pos = self.data.len();
+        rest
+    }
+
+    pub(crate) fn bytes(&mut self, n: usize) -> Result<&'a [u8], Truncated> {
+        let bytes = self
+            .pos
+            .checked_add(n)
+            .and_then(|end| self.data.get(self.pos..end))
+            .ok_or(Truncated)?;
+        self.pos += n;
+        Ok(bytes)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], Truncated> {
+        self.bytes(N)?.try_into().map_err(|_| Truncated)
+    }
+
+    pub(crate) fn u8(&mut self) -> Result<u8, Truncated> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    pub(crate) fn u16(&mut self) -> Result<u16, Truncated> {
+        self.array().map(E::u16)
+    }
+
+    pub(crate) fn u32(&mut self) -> Result<u32, Truncated> {
+        self.array().map(E::u32)
+    }
+
+    pub(crate) fn u64(&mut self) -> Result<u64, Truncated> {
+        self.array().map(E::u64)
+    }
+
+    pub(crate) fn f64(&mut self) -> Result<f64, Truncated> {
+        self.u64().map(f64::from_bits)
+    }
+}
@@ -0,0 +1,332 @@
+use std::collections::HashMap;
+
+use flame_cat_protocol::ValueUnit;
+use thiserror::Error;
+
+use crate::model::{Frame, Profile, ProfileMetadata};
+
+#[derive(Debug, Error)]
+pub enum CallgrindParseError {
+    #[error("invalid UTF-8: {0}")]
+    Utf8(#[from] std::str::Utf8Error),
+    #[error("no costs found")]
+    Empty,
+}
+
+/// Subtrees under this share of the total cost fold into their caller's
+/// self cost, which keeps call graphs with many paths from exploding.
+const MIN_SHARE: f64 = 1e-5;
+
+/// Returns `true` if `text` is a Valgrind callgrind profile
+/// (`callgrind.out.<pid>`).
+pub fn is_callgrind(text: &str) -> bool {
+    let mut head = text.len().min(64 * 1024);
+    while !text.is_char_boundary(head) {
+        head -= 1;
+    }
+    let head = &text[..head];
+    head.trim_start().starts_with("# callgrind format")
+        || (head.lines().any(|l| l.starts_with("events:"))
+            && head.lines().any(|l| l.starts_with("fn=")))
+}
+
+/// A function of the call graph, with its costs in the first event.
+#[derive(Debug, Default)]
+struct Function {
+    name: String,
+    self_cost: f64,
+    /// Callees with the inclusive cost of all calls to each.
+    calls: Vec<(usize, f64)>,
+}
+
+/// The name a compressed `(id) name` or `(id)` spec stands for, recording
+/// new ids in `names`.
+fn resolve(names: &mut HashMap<u64, String>, spec: &str) -> String {
+    let spec = spec.trim();
+    if let Some(rest) = spec.strip_prefix('(')
+        && let Some((id, name)) = rest.split_once(')')
+        && let Ok(id) = id.parse()
+    {
+        let name = name.trim();
+        if name.is_empty() {
+            return names.get(&id).cloned().unwrap_or_default();
+        }
+        names.insert(id, name.to_string());
+        return name.to_string();
+    }
+    spec.to_string()
+}
+
+/// Parse a Valgrind callgrind profile into a weighted `Profile`.
+///
+/// Callgrind records a call graph rather than stacks: the self cost of
+/// each function (`fn=` and the cost lines under it) and the inclusive
+/// cost of each caller → callee edge (`cfn=`, `calls=` and the cost line
+/// after it). Stacks are rebuilt from the functions nobody calls, giving
+/// each callee the share of its own costs that the edge accounts for;
+/// recursive calls stay in the caller's self cost. Values are the first
+/// event of the `events:` line, instructions for the default `Ir`.
+pub fn parse_callgrind(data: &[u8]) -> Result<Profile, CallgrindParseError> {
+    let text = std::str::from_utf8(data)?;
+
+    let mut files = HashMap::new();
+    let mut fn_names = HashMap::new();
+    let mut functions: Vec<Function> = Vec::new();
+    // Functions by file and name, and call edges by caller and callee, as
+    // indices.
+    let mut by_key: HashMap<(String, String), usize> = HashMap::new();
+    let mut edges: HashMap<(usize, usize), usize> = HashMap::new();
+    let mut intern = |functions: &mut Vec<Function>, file: &str, name: String| {
+        *by_key
+            .entry((file.to_string(), name.clone()))
+            .or_insert_with(|| {
+                functions.push(Function {
+                    name,
+                    ..Function::default()
+                });
+                functions.len() - 1
+            })
+    };
+
+    let mut cmd = None;
+    let mut positions = 1;
+    let mut instructions = true;
+    let mut file = String::new();
+    let mut current: Option<usize> = None;
+    let mut callee_file: Option<String> = None;
+    let mut callee_name: Option<String> = None;
+    // The callee whose inclusive cost the next cost line holds.
+    let mut call: Option<usize> = None;
+
+    for line in text.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if line.starts_with(|c: char| c.is_ascii_digit() || matches!(c, '+' | '-' | '*')) {
+            let cost: f64 = line
+                .split_whitespace()
+                .nth(positions)
+                .and_then(|c| c.parse().ok())
+                .unwrap_or(0.0);
+            let Some(caller) = current else { continue };
+            match call.take() {
+                Some(callee) => {
+                    let at = *edges.entry((caller, callee)).or_insert_with(|| {
+                        functions[caller].calls.push((callee, 0.0));
+                        functions[caller].calls.len() - 1
+                    });
+                    functions[caller].calls[at].1 += cost;
+                }
+                None => functions[caller].self_cost += cost,
+            }
+            continue;
+        }
+        let Some(split) = line.find(['=', ':']) else {
+            continue;
+        };
+        let (key, value) = (&line[..split], &line[split + 1..]);
+        match key {
+            "cmd" => cmd = Some(value.trim().to_string()),
+            "positions" => positions = value.split_whitespace().count().max(1),
+            "events" => instructions = value.split_whitespace().next() == Some("Ir"),
+            "fl" => file = resolve(&mut files, value),
+            // Files of inlined code share the file names.
+            "fi" | "fe" => {
+                resolve(&mut files, value);
+            }
+            "fn" => {
+                let name = resolve(&mut fn_names, value);
+                current = Some(intern(&mut functions, &file, name));
+            }
+            "cfi" | "cfl" => callee_file = Some(resolve(&mut files, value)),
+            "cfn" => callee_name = Some(resolve(&mut fn_names, value)),
+            "calls" => {
+                // A callee's file defaults to the caller's.
+                let callee_file = callee_file.take();
+                if let Some(name) = callee_name.take() {
+                    let file = callee_file.as_deref().unwrap_or(&file);
+                    call = Some(intern(&mut functions, file, name));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let inclusive: Vec<f64> = functions
+        .iter()
+        .map(|f| f.self_cost + f.calls.iter().map(|c| c.1).sum::<f64>())
+        .collect();
+    let mut called = vec![0.0; functions.len()];
+    for f in &functions {
+        for &(callee, cost) in &f.calls {
+            called[callee] += cost;
+        }
+    }
+    // What no caller accounts for is a root of the stacks.
+    let roots: Vec<(usize, f64)> = inclusive
+        .iter()
+        .zip(&called)
+        .map(|(inclusive, called)| inclusive - called)
+        .enumerate()
+        .filter(|&(_, cost)| cost > 0.0)
+        .collect();
+    let total: f64 = roots.iter().map(|r| r.1).sum();
+    if total <= 0.0 {
+        return Err(CallgrindParseError::Empty);
+    }
+
+    let mut stacks = Stacks {
+        functions: &functions,
+        inclusive: &inclusive,
+        min_cost: total * MIN_SHARE,
+        on_stack: vec![false; functions.len()],
+        frames: Vec::new(),
+    };
+    let mut offset = 0.0;
+    for (root, cost) in roots {
+        stacks.expand(root, cost, offset, 0, None);
+        offset += cost;
+    }
+
+    Ok(Profile::new(
+        ProfileMetadata {
+            name: cmd,
+            start_time: 0.0,
+            end_time: total,
+            format: "callgrind".to_string(),
+            time_domain: None,
+            value_unit: Some(if instructions {
+                ValueUnit::Instructions
+            } else {
+                ValueUnit::Weight
+            }),
+            sample_interval_us: None,
+        },
+        stacks.frames,
+    ))
+}
+
+/// Frames rebuilt from the call graph.
+struct Stacks<'a> {
+    functions: &'a [Function],
+    inclusive: &'a [f64],
+    min_cost: f64,
+    on_stack: Vec<bool>,
+    frames: Vec<Frame>,
+}
+
+impl Stacks<'_> {
+    /// Lay out `cost` of function `f` from `start`, and its callees within.
+    fn expand(&mut self, f: usize, cost: f64, start: f64, depth: u32, parent: Option<u64>) {
+        let id = self.frames.len();
+        self.frames.push(Frame {
+            id: id as u64,
+            name: self.functions[f].name.clone(),
+            start,
+            end: start + cost,
+            depth,
+            category: None,
+            parent,
+            self_time: 0.0,
+            thread: None,
+            truncated: false,
+            pid: None,
+            tid: None,
+            source: None,
+            binary: None,
+            inlined: vec![],
+        });
+        self.on_stack[f] = true;
+        let share = cost / self.inclusive[f];
+        let mut offset = start;
+        for &(callee, edge) in &self.functions[f].calls {
+            let cost = edge * share;
+            if self.on_stack[callee] || cost <= 0.0 || cost < self.min_cost {
+                continue;
+            }
+            self.expand(callee, cost, offset, depth + 1, Some(id as u64));
+            offset += cost;
+        }
+        self.frames[id].self_time = start + cost - offset;
+        self.on_stack[f] = false;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CALLGRIND: &str = "\
+# callgrind format
+version: 1
+creator: callgrind-3.22.0
+cmd: ./app
+positions: line
+events: Ir Dr
+
+fl=(1) app.c
+fn=(1) main
+10 5 1
+cfl=(2) util.c
+cfn=(3) log
+calls=1 30
+12 50 4
+cfn=(2) work
+calls=2 20
+11 300
+
+fn=(2)
+20 200 20
+cfl=(2)
+cfn=(3)
+calls=1 30
++1 100
+
+fl=(2)
+fn=(3)
+30 150
+summary: 355
+";
+
+    #[test]
+    fn rebuilds_stacks_from_the_call_graph() {
+        assert!(is_callgrind(CALLGRIND));
+        let profile = parse_callgrind(CALLGRIND.as_bytes()).unwrap();
+        assert_eq!(profile.metadata.name.as_deref(), Some("./app"));
+        assert_eq!(profile.metadata.value_unit, Some(ValueUnit::Instructions));
+
+        let frames: Vec<_> = profile
+            .frames
+            .iter()
+            .map(|f| (f.name.as_str(), f.depth, f.start, f.end, f.self_time))
+            .collect();
+        assert_eq!(
+            frames,
+            [
+                ("main", 0, 0.0, 355.0, 5.0),
+                // log's 150 instructions split between its two callers.
+                ("log", 1, 0.0, 50.0, 50.0),
+                ("work", 1, 50.0, 350.0, 200.0),
+                ("log", 2, 50.0, 150.0, 100.0),
+            ]
+        );
+        assert_eq!(profile.metadata.end_time, 355.0);
+    }
+
+    #[test]
+    fn recursion_stays_in_the_caller() {
+        let text = "events: Ir\nfn=main\n1 1\ncfn=fib\ncalls=1 1\n1 100\nfn=fib\n1 100\ncfn=fib\ncalls=5 1\n1 90\n";
+        let profile = parse_callgrind(text.as_bytes()).unwrap();
+        let frames: Vec<_> = profile
+            .frames
+            .iter()
+            .map(|f| (f.name.as_str(), f.end - f.start, f.self_time))
+            .collect();
+        assert_eq!(frames, [("main", 101.0, 1.0), ("fib", 100.0, 100.0)]);
+        assert!(matches!(
+            parse_callgrind(b"events: Ir\n"),
+            Err(CallgrindParseError::Empty)
+        ));
+    }
+}
@@ -1,11 +1,16 @@
+use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
+
 use flame_cat_protocol::{
     AsyncSpan, ClockKind, CounterSample, CounterTrack, CounterUnit, CpuNode, CpuSamples, FlowArrow,
-    InstantEvent, Marker, MarkerScope, NetworkRequest, ObjectEvent, ObjectPhase, Screenshot,
-    SharedStr, TimeDomain,
+    InstantEvent, LimitsHit, Marker, MarkerScope, NetworkRequest, ObjectEvent, ObjectPhase,
+    PartialParse, Screenshot, SharedStr, TimeDomain,
 };
 use serde::Deserialize;
 use thiserror::Error;
 
+use super::Limits;
+use super::limits::{cancelled, cut_string};
 use crate::model::{Frame, Profile, ProfileMetadata};
 
 /// Deserialize an optional id that can be either a string or a number.
@@ -27,6 +32,24 @@ where
     })
 }
 
+/// How many leading bytes [`looks_like_trace`] inspects.
+const SNIFF_LEN: usize = 4096;
+
+/// Cheap prefix check for Chrome trace JSON (object or array form), which
+/// holds for a trace cut short as well.
+pub fn looks_like_trace(data: &[u8]) -> bool {
+    let head = &data[..data.len().min(SNIFF_LEN)];
+    let head = String::from_utf8_lossy(head);
+    let trimmed = head.trim_start();
+    if trimmed.starts_with('{') {
+        trimmed.contains("\"traceEvents\"")
+    } else if trimmed.starts_with('[') {
+        trimmed.contains("\"ph\"")
+    } else {
+        false
+    }
+}
+
 #[derive(Debug, Error)]
 pub enum ChromeParseError {
     #[error("invalid JSON: {0}")]
@@ -82,17 +105,190 @@ impl TraceEvent {
     }
 }
 
+/// Accumulates trace events as they are deserialized, one at a time.
+///
+/// Metadata events (`ph: "M"`) are consumed immediately and never buffered,
+/// so only the events that need timestamp sorting are kept in memory — the
+/// full document is never materialized as a `serde_json::Value` tree.
+///
+/// Past `limits.max_events` events are counted but no longer kept, and
+/// names are cut to `limits.max_string_len` as they arrive. Once `cancel`
+/// is set, deserializing stops at the next event.
+#[derive(Clone)]
+struct EventCollector<'c> {
+    events: Vec<TraceEvent>,
+    thread_names: std::collections::HashMap<(u64, u64), String>,
+    process_names: std::collections::BTreeMap<u64, String>,
+    navigation_start_us: Option<f64>,
+    limits: Limits,
+    hit: LimitsHit,
+    cancel: Option<&'c AtomicBool>,
+}
+
+impl Default for EventCollector<'_> {
+    fn default() -> Self {
+        Self::new(Limits::NONE, None)
+    }
+}
+
+impl<'c> EventCollector<'c> {
+    fn new(limits: Limits, cancel: Option<&'c AtomicBool>) -> Self {
+        Self {
+            events: Vec::new(),
+            thread_names: Default::default(),
+            process_names: Default::default(),
+            navigation_start_us: None,
+            limits,
+            hit: LimitsHit::default(),
+            cancel,
+        }
+    }
+
+    fn cancelled(&self) -> bool {
+        cancelled(self.cancel)
+    }
+
+    fn push(&mut self, mut event: TraceEvent) {
+        if event.ph == "M"
+            && let Some(name) = event
+                .args
+                .as_ref()
+                .and_then(|a| a.get("name"))
+                .and_then(|n| n.as_str())
+        {
+            match event.name.as_str() {
+                "thread_name" => {
+                    self.thread_names
+                        .insert((event.pid, event.tid), name.to_string());
+                }
+                "process_name" => {
+                    self.process_names.insert(event.pid, name.to_string());
+                }
+                _ => {}
+            }
+        }
+        // Extract navigationStart (= performance.timeOrigin on monotonic clock)
+        if self.navigation_start_us.is_none()
+            && event.name == "navigationStart"
+            && event.cat == "blink.user_timing"
+        {
+            self.navigation_start_us = Some(event.ts);
+        }
+        if event.ph == "M" {
+            return;
+        }
+        if self.events.len() >= self.limits.max_events {
+            self.hit.events_dropped += 1;
+            return;
+        }
+        if let Some(name) = cut_string(&event.name, self.limits.max_string_len) {
+            event.name = name;
+            self.hit.strings_cut += 1;
+        }
+        self.events.push(event);
+    }
+
+    /// Append everything collected by `other`, as if its events had been
+    /// pushed here after ours.
+    fn extend(&mut self, other: EventCollector<'_>) {
+        self.events.extend(other.events);
+        self.thread_names.extend(other.thread_names);
+        self.process_names.extend(other.process_names);
+        self.navigation_start_us = self.navigation_start_us.or(other.navigation_start_us);
+        self.hit += other.hit;
+    }
+}
+
+/// Streams the elements of a `traceEvents` array into an [`EventCollector`].
+struct EventArraySeed<'a, 'c>(&'a mut EventCollector<'c>);
+
+impl<'de> serde::de::DeserializeSeed<'de> for EventArraySeed<'_, '_> {
+    type Value = ();
+
+    fn deserialize<D: serde::Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de> serde::de::Visitor<'de> for EventArraySeed<'_, '_> {
+    type Value = ();
+
+    fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str("an array of trace events")
+    }
+
+    fn visit_seq<A: serde::de::SeqAccess<'de>>(self, mut seq: A) -> Result<(), A::Error> {
+        while let Some(event) = seq.next_element::<TraceEvent>()? {
+            self.0.push(event);
+            if self.0.cancelled() {
+                return Err(serde::de::Error::custom("parse cancelled"));
+            }
+        }
+        Ok(())
+    }
+}
+
 /// Top-level Chrome trace JSON — supports both array format and object format.
-#[derive(Debug, Deserialize)]
-#[serde(untagged)]
-enum TraceFile {
-    Object {
-        #[serde(rename = "traceEvents")]
-        trace_events: Vec<TraceEvent>,
-        #[serde(default)]
-        metadata: Option<serde_json::Value>,
-    },
-    Array(Vec<TraceEvent>),
+///
+/// Yields the top-level `metadata` object, or `None` for array-format traces.
+/// Object-format traces without a `traceEvents` key are rejected.
+struct TraceFileSeed<'a, 'c>(&'a mut EventCollector<'c>);
+
+impl<'de> serde::de::DeserializeSeed<'de> for TraceFileSeed<'_, '_> {
+    type Value = Option<Option<serde_json::Value>>;
+
+    fn deserialize<D: serde::Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_any(self)
+    }
+}
+
+impl<'de> serde::de::Visitor<'de> for TraceFileSeed<'_, '_> {
+    /// `None` when an object-format trace has no `traceEvents` array.
+    type Value = Option<Option<serde_json::Value>>;
+
+    fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str("a trace event array or an object with traceEvents")
+    }
+
+    fn visit_seq<A: serde::de::SeqAccess<'de>>(self, seq: A) -> Result<Self::Value, A::Error> {
+        serde::de::Visitor::visit_seq(EventArraySeed(self.0), seq)?;
+        Ok(Some(None))
+    }
+
+    fn visit_map<A: serde::de::MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut saw_events = false;
+        let mut metadata = None;
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                "traceEvents" => {
+                    map.next_value_seed(EventArraySeed(self.0))?;
+                    saw_events = true;
+                }
+                "metadata" => metadata = map.next_value::<Option<serde_json::Value>>()?,
+                _ => {
+                    map.next_value::<serde::de::IgnoredAny>()?;
+                }
+            }
+        }
+        Ok(saw_events.then_some(metadata))
+    }
+}
+
+/// Drive a JSON deserializer over a whole trace, feeding events to `collector`.
+fn collect_events<'de, R: serde_json::de::Read<'de>>(
+    mut deserializer: serde_json::Deserializer<R>,
+    collector: &mut EventCollector<'_>,
+) -> Result<Option<serde_json::Value>, ChromeParseError> {
+    use serde::de::DeserializeSeed;
+
+    let metadata = TraceFileSeed(collector)
+        .deserialize(&mut deserializer)?
+        .ok_or(ChromeParseError::MissingTraceEvents)?;
+    deserializer.end()?;
+    Ok(metadata)
 }
 
 /// Metadata extracted from top-level Chrome trace fields.
@@ -155,6 +351,68 @@ fn is_react_scheduler_event(event: &TraceEvent) -> bool {
     })
 }
 
+/// Check if a trace event is a plain `performance.measure()` entry, i.e. a
+/// user timing that is not one of React's own performance tracks.
+fn is_user_timing_measure(event: &TraceEvent) -> bool {
+    event.cat.contains("blink.user_timing")
+        && !is_react_component_event(event)
+        && !is_react_scheduler_event(event)
+}
+
+/// Nest user timing measures by containment on a virtual "User Timing"
+/// thread, the way DevTools' Timings track stacks them. A measure becomes a
+/// child of the innermost earlier measure that fully contains it. One that
+/// partly overlaps a measure still open goes to the first further track
+/// ("User Timing 2", ...) it nests on, keeping its own start and end.
+fn push_user_timing_frames(
+    frames: &mut Vec<Frame>,
+    next_id: &mut u64,
+    mut measures: Vec<(f64, f64, String)>,
+) {
+    // Outer measures first: earliest start, then longest.
+    measures.sort_by(|a, b| a.0.total_cmp(&b.0).then(b.1.total_cmp(&a.1)));
+    // Measures still open on each track as (id, end), outermost first.
+    let mut tracks: Vec<Vec<(u64, f64)>> = Vec::new();
+    for (start, end, name) in measures {
+        for open in &mut tracks {
+            while open.last().is_some_and(|&(_, open_end)| open_end <= start) {
+                open.pop();
+            }
+        }
+        let track = tracks
+            .iter()
+            .position(|open| open.last().is_none_or(|&(_, open_end)| open_end >= end))
+            .unwrap_or(tracks.len());
+        if track == tracks.len() {
+            tracks.push(Vec::new());
+        }
+        let open = &mut tracks[track];
+        let id = *next_id;
+        *next_id += 1;
+        frames.push(Frame {
+            id,
+            name,
+            start,
+            end,
+            depth: open.len() as u32,
+            category: Some("blink.user_timing".to_string()),
+            parent: open.last().map(|&(parent, _)| parent),
+            self_time: 0.0,
+            thread: Some(match track {
+                0 => "User Timing".to_string(),
+                n => format!("User Timing {}", n + 1),
+            }),
+            truncated: false,
+            pid: None,
+            tid: None,
+            source: None,
+            binary: None,
+            inlined: vec![],
+        });
+        open.push((id, end));
+    }
+}
+
 /// Extract the React component self-time color severity from a trace event.
 /// React uses: primary-light (<0.5ms), primary (<10ms), primary-dark (<100ms), error (>=100ms).
 fn extract_react_color(event: &TraceEvent) -> Option<&str> {
@@ -166,6 +424,147 @@ fn extract_react_color(event: &TraceEvent) -> Option<&str> {
     })
 }
 
+/// URL in a trace event's `args.data`, such as the script an
+/// `EvaluateScript` ran or the resource a network event loaded.
+fn event_url(event: &TraceEvent) -> Option<String> {
+    event
+        .args
+        .as_ref()
+        .and_then(|args| args.get("data"))
+        .and_then(|data| data.get("url"))
+        .and_then(|url| url.as_str())
+        .filter(|url| !url.is_empty())
+        .map(str::to_string)
+}
+
+/// Whether a `ResourceSendRequest` was sent by a script, and the URL of
+/// its initiator: the document for parser-initiated requests, the
+/// innermost script on the calling stack otherwise.
+fn request_initiator(data: &serde_json::Value) -> (bool, Option<String>) {
+    let frame_url = |frames: Option<&serde_json::Value>| {
+        frames
+            .and_then(serde_json::Value::as_array)?
+            .iter()
+            .filter_map(|f| f.get("url").and_then(serde_json::Value::as_str))
+            .find(|url| !url.is_empty())
+            .map(str::to_string)
+    };
+    // Older traces carry only the sending script's stack.
+    let Some(initiator) = data.get("initiator") else {
+        let url = frame_url(data.get("stackTrace"));
+        return (url.is_some(), url);
+    };
+    let by_script = initiator.get("type").and_then(serde_json::Value::as_str) == Some("script");
+    let url = initiator
+        .get("url")
+        .and_then(serde_json::Value::as_str)
+        .filter(|url| !url.is_empty())
+        .map(str::to_string)
+        .or_else(|| frame_url(initiator.get("stack").and_then(|s| s.get("callFrames"))));
+    (by_script, url)
+}
+
+/// Where a network request was sent and where its response was handled,
+/// as `(ts, pid, tid)`.
+#[derive(Clone)]
+struct RequestPoints {
+    send: (f64, u64, u64),
+    arrival: Option<(f64, u64, u64)>,
+    by_script: bool,
+}
+
+impl RequestPoints {
+    /// The end of the request an initiator arrow points at.
+    fn target(&self) -> (f64, u64, u64) {
+        self.arrival.unwrap_or(self.send)
+    }
+}
+
+/// Flow arrows from whatever triggered each network request to where its
+/// response was handled.
+///
+/// A script-initiated request starts at the script span it was sent from,
+/// preferring one running the initiator's URL. Any other request with a
+/// known initiator starts where the latest earlier request for that URL
+/// arrived, so document → stylesheet → font reads as a chain of arrows.
+fn initiator_arrows(
+    requests: &[NetworkRequest],
+    points: &std::collections::HashMap<String, RequestPoints>,
+    frames: &[Frame],
+) -> Vec<FlowArrow> {
+    let points: Vec<Option<&RequestPoints>> = requests
+        .iter()
+        .map(|r| points.get(r.request_id.as_ref()))
+        .collect();
+
+    // Innermost script span around each script-initiated send.
+    let mut by_thread: std::collections::HashMap<(u64, u64), Vec<usize>> =
+        std::collections::HashMap::new();
+    for (i, p) in points.iter().enumerate() {
+        if let Some(p) = p
+            && p.by_script
+        {
+            by_thread.entry((p.send.1, p.send.2)).or_default().push(i);
+        }
+    }
+    let mut senders: Vec<Option<(bool, u32)>> = vec![None; requests.len()];
+    for frame in frames {
+        let (Some(pid), Some(tid), Some(source)) = (frame.pid, frame.tid, &frame.source) else {
+            continue;
+        };
+        for &i in by_thread.get(&(pid, tid)).into_iter().flatten() {
+            let ts = points[i].map_or(f64::NAN, |p| p.send.0);
+            if frame.start <= ts && ts <= frame.end {
+                let rank = (
+                    requests[i].initiator.as_deref() == Some(source.as_str()),
+                    frame.depth,
+                );
+                if senders[i].is_none_or(|best| rank > best) {
+                    senders[i] = Some(rank);
+                }
+            }
+        }
+    }
+
+    let mut by_url: std::collections::HashMap<&str, Vec<usize>> = std::collections::HashMap::new();
+    for (i, r) in requests.iter().enumerate() {
+        by_url.entry(r.url.as_ref()).or_default().push(i);
+    }
+
+    let mut arrows = Vec::new();
+    for (i, req) in requests.iter().enumerate() {
+        let Some(p) = points[i] else { continue };
+        let from = if senders[i].is_some() {
+            Some(p.send)
+        } else {
+            req.initiator.as_deref().and_then(|url| {
+                by_url
+                    .get(url)?
+                    .iter()
+                    .rev()
+                    .find(|&&j| j != i && requests[j].send_ts < req.send_ts)
+                    .and_then(|&j| points[j])
+                    .map(RequestPoints::target)
+            })
+        };
+        let to = p.target();
+        let Some(from) = from.filter(|&from| from != to) else {
+            continue;
+        };
+        arrows.push(FlowArrow {
+            name: req.url.clone(),
+            id: req.request_id.clone(),
+            from_ts: from.0,
+            from_pid: from.1,
+            from_tid: from.2,
+            to_ts: to.0,
+            to_pid: to.1,
+            to_tid: to.2,
+        });
+    }
+    arrows
+}
+
 /// Extract changed props from a React DEV-mode trace event.
 /// In DEV builds, React emits a `properties` array with changed prop details.
 #[cfg(test)]
@@ -193,18 +592,75 @@ fn extract_react_properties(event: &TraceEvent) -> Option<Vec<(String, String)>>
     })
 }
 
-/// Guess the counter unit from its name.
-fn guess_counter_unit(name: &str) -> CounterUnit {
+/// Guess the counter unit from its name, with the factor that converts raw
+/// values to that unit.
+pub(super) fn guess_counter_unit(name: &str) -> (CounterUnit, f64) {
     let lower = name.to_lowercase();
-    if lower.contains("heap") || lower.contains("memory") || lower.contains("bytes") {
-        CounterUnit::Bytes
+    if let Some(power) = guess_power_unit(&lower) {
+        power
+    } else if lower.contains("heap") || lower.contains("memory") || lower.contains("bytes") {
+        (CounterUnit::Bytes, 1.0)
     } else if lower.contains("percent") || lower.contains("%") {
-        CounterUnit::Percent
+        (CounterUnit::Percent, 1.0)
     } else {
-        CounterUnit::Count
+        (CounterUnit::Count, 1.0)
     }
 }
 
+/// Power, thermal and battery counters, named the way Perfetto records
+/// Android power rails (`power.rails.cpu`), battery (`batt.capacity_pct`)
+/// and thermal zones, or like the `Power (mW)` columns of ETW exports.
+fn guess_power_unit(lower: &str) -> Option<(CounterUnit, f64)> {
+    let words: Vec<&str> = lower
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .collect();
+    let has = |word: &str| words.contains(&word);
+    if lower.contains("power.rails") {
+        // Cumulative energy, turned into power by `energy_to_power`.
+        return Some((CounterUnit::Milliwatts, 1.0));
+    }
+    if (has("batt") || has("battery"))
+        && ["capacity", "pct", "percent", "level"]
+            .iter()
+            .any(|w| lower.contains(w))
+    {
+        return Some((CounterUnit::BatteryPercent, 1.0));
+    }
+    if lower.contains("temperature") || lower.contains("thermal") || has("temp") {
+        let scale = if has("mc") || lower.contains("millicelsius") {
+            1e-3
+        } else {
+            1.0
+        };
+        return Some((CounterUnit::Celsius, scale));
+    }
+    if has("power") || has("mw") || has("uw") || has("watts") {
+        let scale = if has("uw") {
+            1e-3
+        } else if has("w") || has("watts") {
+            1e3
+        } else {
+            1.0
+        };
+        return Some((CounterUnit::Milliwatts, scale));
+    }
+    None
+}
+
+/// Average power (mW) between consecutive samples of a cumulative energy
+/// counter in µWs, which is how Perfetto records power rails.
+fn energy_to_power(samples: &[CounterSample]) -> Vec<CounterSample> {
+    samples
+        .windows(2)
+        .filter(|w| w[1].ts > w[0].ts)
+        .map(|w| CounterSample {
+            ts: w[0].ts,
+            value: (w[1].value - w[0].value) * 1e3 / (w[1].ts - w[0].ts),
+        })
+        .collect()
+}
+
 /// Extract counters from an UpdateCounters instant event's `data` field.
 fn extract_update_counters(
     data: &serde_json::Value,
@@ -260,6 +716,14 @@ fn extract_cpu_profile_chunk(
                 .and_then(|v| v.as_str())
                 .and_then(|s| s.parse::<u32>().ok())
                 .unwrap_or(0);
+            let source = call_frame.and_then(|cf| {
+                let position = |key| cf.get(key).and_then(serde_json::Value::as_i64);
+                crate::sourcemap::source_location(
+                    cf.get("url")?.as_str()?,
+                    position("lineNumber")?,
+                    position("columnNumber")?,
+                )
+            });
 
             // Deduplicate: only add if we haven't seen this id yet
             if !nodes.iter().any(|n| n.id == id) {
@@ -268,6 +732,7 @@ fn extract_cpu_profile_chunk(
                     parent,
                     function_name: SharedStr::from(function_name),
                     script_id,
+                    source: source.map(SharedStr::from),
                 });
             }
         }
@@ -303,567 +768,918 @@ fn extract_cpu_profile_chunk(
 }
 
 /// Parse a Chrome DevTools trace JSON into a `Profile`.
+///
+/// Events are deserialized one at a time rather than as a whole document,
+/// which keeps peak memory close to the size of the retained events.
 pub fn parse_chrome_trace(data: &[u8]) -> Result<Profile, ChromeParseError> {
-    let trace_file: TraceFile = serde_json::from_slice(data)?;
-    let (events, trace_meta) = match trace_file {
-        TraceFile::Object {
-            trace_events,
-            metadata,
-        } => (trace_events, extract_trace_metadata(&metadata)),
-        TraceFile::Array(events) => (
-            events,
-            TraceMetadata {
-                time_domain: None,
-                navigation_start_us: None,
-            },
-        ),
-    };
+    parse_chrome_trace_limited(data, &Limits::NONE, None, false)
+}
 
-    // Collect thread name metadata and navigationStart
-    let mut thread_names: std::collections::HashMap<(u64, u64), String> =
-        std::collections::HashMap::new();
-    let mut navigation_start_us = trace_meta.navigation_start_us;
-    for event in &events {
-        if event.ph == "M"
-            && event.name == "thread_name"
-            && let Some(name) = event
-                .args
-                .as_ref()
-                .and_then(|a| a.get("name"))
-                .and_then(|n| n.as_str())
-        {
-            thread_names.insert((event.pid, event.tid), name.to_string());
-        }
-        // Extract navigationStart (= performance.timeOrigin on monotonic clock)
-        if navigation_start_us.is_none()
-            && event.name == "navigationStart"
-            && event.cat == "blink.user_timing"
+/// Like [`parse_chrome_trace`], but a trace cut short mid-download or
+/// corrupted part way keeps the events that parsed before the damage, and
+/// [`Profile::partial`] records where it stopped. Fails only when not a
+/// single event parsed.
+pub fn parse_chrome_trace_partial(data: &[u8]) -> Result<Profile, ChromeParseError> {
+    parse_chrome_trace_limited(data, &Limits::NONE, None, true)
+}
+
+/// [`parse_chrome_trace`], or [`parse_chrome_trace_partial`] when
+/// `recover`, within `limits`: events past the event limit are never kept,
+/// names are cut as they are read and spans deeper than the depth limit
+/// are left out of the stacks. Setting `cancel` fails the parse at the
+/// next event.
+pub(crate) fn parse_chrome_trace_limited(
+    data: &[u8],
+    limits: &Limits,
+    cancel: Option<&AtomicBool>,
+    recover: bool,
+) -> Result<Profile, ChromeParseError> {
+    let mut collector = EventCollector::new(*limits, cancel);
+    match collect_events(serde_json::Deserializer::from_slice(data), &mut collector) {
+        Ok(metadata) => Ok(build_profile(collector, &metadata)),
+        Err(ChromeParseError::Json(e))
+            if recover && !collector.events.is_empty() && !collector.cancelled() =>
         {
-            navigation_start_us = Some(event.ts);
+            let partial = PartialParse {
+                events: collector.events.len(),
+                cut_off: collector
+                    .events
+                    .iter()
+                    .map(|e| e.ts)
+                    .fold(f64::NEG_INFINITY, f64::max),
+                error: e.to_string().into(),
+            };
+            // The top-level metadata usually follows the events, so it is
+            // lost with them.
+            let mut profile = build_profile(collector, &None);
+            profile.partial = Some(partial);
+            Ok(profile)
         }
+        Err(e) => Err(e),
     }
+}
 
-    // Store navigationStart on the time domain if found
-    let mut trace_meta = trace_meta;
-    if let Some(nav_start) = navigation_start_us {
-        if let Some(ref mut td) = trace_meta.time_domain {
-            td.navigation_start_us = Some(nav_start);
+/// Parse a Chrome DevTools trace from any byte stream into a `Profile`.
+///
+/// Like [`parse_chrome_trace`], but reads incrementally so the raw JSON never
+/// has to be held in memory at once — useful for multi-gigabyte traces read
+/// straight from disk or a decompressor.
+pub fn parse_chrome_trace_reader<R: std::io::Read>(reader: R) -> Result<Profile, ChromeParseError> {
+    let mut collector = EventCollector::default();
+    let metadata = collect_events(
+        serde_json::Deserializer::from_reader(reader),
+        &mut collector,
+    )?;
+    Ok(build_profile(collector, &metadata))
+}
+
+/// A Chrome trace assembled from batches of events as they arrive, for
+/// following a recording that is still in progress.
+///
+/// Each batch is either a bare event array or an object with `traceEvents`,
+/// exactly like a whole trace file, and is added to the spans and tracks
+/// built from the batches before it. [`ChromeTraceStream::profile`] builds a
+/// `Profile` from everything received so far; `B` events whose `E` has not
+/// arrived yet are closed at the latest timestamp and flagged truncated.
+///
+/// Batches are read within the stream's [`Limits`], counted over the whole
+/// stream: events past the event limit are dropped, names cut and spans
+/// past the depth limit left out, and batches past the memory budget are
+/// skipped. What was left out shows in the profile's `limits_hit`.
+pub struct ChromeTraceStream {
+    collector: EventCollector<'static>,
+    metadata: Option<serde_json::Value>,
+    builder: ProfileBuilder,
+    /// Events handed to `builder`, counting those handed again.
+    processed: usize,
+    /// Bytes of the batches read so far.
+    received: usize,
+    cancel: Option<Arc<AtomicBool>>,
+}
+
+/// Most events handed to the builder over a stream's life, as a multiple
+/// of the event limit. Past it, events of a batch that go back before the
+/// latest one received are dropped instead of rebuilding everything.
+const MAX_REBUILDS: usize = 4;
+
+impl ChromeTraceStream {
+    /// A stream reading batches within `limits`. Setting `cancel` rejects
+    /// the batch being read at its next event, and every batch after it.
+    pub fn new(limits: Limits, cancel: Option<Arc<AtomicBool>>) -> Self {
+        Self {
+            collector: EventCollector::new(limits, None),
+            metadata: None,
+            builder: ProfileBuilder::new(),
+            processed: 0,
+            received: 0,
+            cancel,
         }
-        trace_meta.navigation_start_us = Some(nav_start);
     }
 
-    let mut frames: Vec<Frame> = Vec::with_capacity(events.len());
-    let mut next_id: u64 = 0;
+    /// Append one batch of events. A batch that fails to parse is rejected
+    /// whole, leaving the stream as it was.
+    ///
+    /// Only the new events are processed, unless the batch goes back before
+    /// the latest event already received: then the stacks around it change
+    /// and everything is processed again, as long as that stays within
+    /// [`MAX_REBUILDS`] times the event limit.
+    pub fn push_batch(&mut self, data: &[u8]) -> Result<(), ChromeParseError> {
+        let limits = self.collector.limits;
+        if self.received.saturating_add(data.len()) > limits.input_budget() {
+            self.collector.hit.bytes_skipped += data.len();
+            return Ok(());
+        }
+        let remaining = Limits {
+            max_events: limits
+                .max_events
+                .saturating_sub(self.collector.events.len()),
+            ..limits
+        };
+        let mut batch = EventCollector::new(remaining, self.cancel.as_deref());
+        let metadata = collect_events(serde_json::Deserializer::from_slice(data), &mut batch)?;
+        self.received += data.len();
+        batch.events.sort_by(|a, b| a.ts.total_cmp(&b.ts));
+        let late = batch
+            .events
+            .partition_point(|e| e.ts < self.builder.last_ts);
+        let mut start = self.collector.events.len();
+        let rebuild = late > 0
+            && self.processed.saturating_add(start + batch.events.len())
+                <= limits.max_events.saturating_mul(MAX_REBUILDS);
+        if late > 0 && !rebuild {
+            batch.events.drain(..late);
+            batch.hit.events_dropped += late;
+        }
+        self.collector.extend(batch);
+        if rebuild {
+            self.collector.events.sort_by(|a, b| a.ts.total_cmp(&b.ts));
+            self.builder = ProfileBuilder::new();
+            start = 0;
+        }
+        self.builder.add(
+            &self.collector.events[start..],
+            &self.collector.thread_names,
+            &self.collector.limits,
+        );
+        self.processed += self.collector.events.len() - start;
+        if self.metadata.is_none() {
+            self.metadata = metadata;
+        }
+        Ok(())
+    }
 
-    // Stack of (frame_index, event) for matching B/E pairs per thread.
-    let mut stacks: std::collections::HashMap<(u64, u64), Vec<usize>> =
-        std::collections::HashMap::new();
+    /// Number of buffered (non-metadata) events.
+    pub fn event_count(&self) -> usize {
+        self.collector.events.len()
+    }
 
-    // Non-span event collectors
-    let mut instant_events: Vec<InstantEvent> = Vec::new();
-    let mut markers: Vec<Marker> = Vec::new();
-    let mut object_events: Vec<ObjectEvent> = Vec::new();
-    // Network request correlation
-    let mut net_sends: std::collections::HashMap<String, NetworkRequest> =
-        std::collections::HashMap::new();
-    let mut network_requests: Vec<NetworkRequest> = Vec::new();
+    /// Number of events processed into spans and tracks so far: each event
+    /// once, plus all of them again for every batch that arrived out of order.
+    pub fn events_processed(&self) -> usize {
+        self.processed
+    }
 
-    let mut screenshots: Vec<Screenshot> = Vec::new();
+    /// Build a profile from all events received so far.
+    pub fn profile(&self) -> Profile {
+        self.builder.clone().finish(&self.collector, &self.metadata)
+    }
+}
 
-    // Counter state: name → (unit, samples)
-    let mut counter_map: std::collections::HashMap<String, (CounterUnit, Vec<CounterSample>)> =
-        std::collections::HashMap::new();
+/// Turn the streamed events into a `Profile`.
+fn build_profile(
+    mut collector: EventCollector<'_>,
+    metadata: &Option<serde_json::Value>,
+) -> Profile {
+    // Sort events by timestamp for correct stack reconstruction.
+    // Metadata events were already consumed while streaming.
+    collector.events.sort_by(|a, b| a.ts.total_cmp(&b.ts));
+    let mut builder = ProfileBuilder::new();
+    builder.add(
+        &collector.events,
+        &collector.thread_names,
+        &collector.limits,
+    );
+    builder.finish(&collector, metadata)
+}
 
-    // Async span state: (cat, id) → pending begin event
-    let mut async_begins: std::collections::HashMap<(String, String), (f64, String, u64, u64)> =
-        std::collections::HashMap::new();
-    let mut async_spans: Vec<AsyncSpan> = Vec::new();
+/// Spans and tracks of a trace built up event by event, so a stream can
+/// add each batch to what it already built.
+#[derive(Clone)]
+struct ProfileBuilder {
+    frames: Vec<Frame>,
+    next_id: u64,
+    /// Stack of frame indices for matching B/E pairs per thread.
+    stacks: std::collections::HashMap<(u64, u64), Vec<usize>>,
+    /// `B` events past the depth limit whose `E` has not come yet, per thread.
+    deep_opens: std::collections::HashMap<(u64, u64), usize>,
+    /// Spans left out for sitting past the depth limit.
+    too_deep: usize,
+
+    instant_events: Vec<InstantEvent>,
+    markers: Vec<Marker>,
+    object_events: Vec<ObjectEvent>,
+    /// Requests sent but not finished yet, by request id.
+    net_sends: std::collections::HashMap<String, NetworkRequest>,
+    network_requests: Vec<NetworkRequest>,
+    request_points: std::collections::HashMap<String, RequestPoints>,
+    screenshots: Vec<Screenshot>,
+    /// Counter samples by name, with their unit.
+    counter_map: std::collections::HashMap<String, (CounterUnit, Vec<CounterSample>)>,
+    /// Async begins waiting for their end, by `(cat, id)`.
+    async_begins: std::collections::HashMap<(String, String), (f64, String, u64, u64)>,
+    async_spans: Vec<AsyncSpan>,
+    /// `performance.measure()` entries as (start, end, name), nested into
+    /// the "User Timing" track once all of them are known.
+    user_measures: Vec<(f64, f64, String)>,
+    /// Flow starts waiting for their end, by id.
+    flow_starts: std::collections::HashMap<String, (f64, (u64, u64), String)>,
+    flow_arrows: Vec<FlowArrow>,
+    cpu_nodes: Vec<CpuNode>,
+    cpu_samples: Vec<u32>,
+    cpu_timestamps: Vec<f64>,
+
+    /// Latest end of any event, where `B` events still open are closed.
+    trace_end: f64,
+    /// Timestamp of the last event added.
+    last_ts: f64,
+}
 
-    // Flow event state: id → pending start event
-    let mut flow_starts: std::collections::HashMap<String, (f64, u64, String)> =
-        std::collections::HashMap::new();
-    let mut flow_arrows: Vec<FlowArrow> = Vec::new();
+impl ProfileBuilder {
+    fn new() -> Self {
+        Self {
+            frames: Vec::new(),
+            next_id: 0,
+            stacks: std::collections::HashMap::new(),
+            deep_opens: std::collections::HashMap::new(),
+            too_deep: 0,
+            instant_events: Vec::new(),
+            markers: Vec::new(),
+            object_events: Vec::new(),
+            net_sends: std::collections::HashMap::new(),
+            network_requests: Vec::new(),
+            request_points: std::collections::HashMap::new(),
+            screenshots: Vec::new(),
+            counter_map: std::collections::HashMap::new(),
+            async_begins: std::collections::HashMap::new(),
+            async_spans: Vec::new(),
+            user_measures: Vec::new(),
+            flow_starts: std::collections::HashMap::new(),
+            flow_arrows: Vec::new(),
+            cpu_nodes: Vec::new(),
+            cpu_samples: Vec::new(),
+            cpu_timestamps: Vec::new(),
+            trace_end: f64::NEG_INFINITY,
+            last_ts: f64::NEG_INFINITY,
+        }
+    }
 
-    // CPU sample state
-    let mut cpu_nodes: Vec<CpuNode> = Vec::new();
-    let mut cpu_samples: Vec<u32> = Vec::new();
-    let mut cpu_timestamps: Vec<f64> = Vec::new();
+    /// Add `events`, sorted by timestamp and none earlier than those added
+    /// before.
+    fn add(
+        &mut self,
+        events: &[TraceEvent],
+        thread_names: &std::collections::HashMap<(u64, u64), String>,
+        limits: &Limits,
+    ) {
+        let Self {
+            frames,
+            next_id,
+            stacks,
+            deep_opens,
+            too_deep,
+            instant_events,
+            markers,
+            object_events,
+            net_sends,
+            network_requests,
+            request_points,
+            screenshots,
+            counter_map,
+            async_begins,
+            async_spans,
+            user_measures,
+            flow_starts,
+            flow_arrows,
+            cpu_nodes,
+            cpu_samples,
+            cpu_timestamps,
+            trace_end,
+            last_ts,
+        } = self;
+
+        for event in events {
+            *last_ts = event.ts;
+            *trace_end = trace_end.max(event.ts + event.dur.unwrap_or(0.0));
+            let key = (event.pid, event.tid);
+            let thread_name = thread_names.get(&key).cloned();
+
+            match event.ph.as_str() {
+                "X" if is_user_timing_measure(event) => {
+                    user_measures.push((
+                        event.ts,
+                        event.ts + event.dur.unwrap_or(0.0),
+                        event.name.trim_start_matches('\u{200b}').to_string(),
+                    ));
+                }
 
-    // Sort events by timestamp for correct stack reconstruction.
-    let mut sorted_events: Vec<TraceEvent> = events
-        .into_iter()
-        .filter(|e| e.ph != "M") // metadata already processed
-        .collect();
-    sorted_events.sort_by(|a, b| a.ts.total_cmp(&b.ts));
-
-    for event in &sorted_events {
-        let key = (event.pid, event.tid);
-        let thread_name = thread_names.get(&key).cloned();
-
-        match event.ph.as_str() {
-            // === Duration events (existing) ===
-            "X" | "B" | "E" => {
-                // Pop completed X events from the stack before processing.
-                if let Some(stack) = stacks.get_mut(&key) {
-                    while let Some(&top_idx) = stack.last() {
-                        let top = &frames[top_idx];
-                        if top.end > top.start && top.end <= event.ts {
-                            stack.pop();
-                        } else {
-                            break;
+                // === Duration events (existing) ===
+                "X" | "B" | "E" => {
+                    // Pop completed X events from the stack before processing.
+                    if let Some(stack) = stacks.get_mut(&key) {
+                        while let Some(&top_idx) = stack.last() {
+                            let top = &frames[top_idx];
+                            if top.end > top.start && top.end <= event.ts {
+                                stack.pop();
+                            } else {
+                                break;
+                            }
                         }
                     }
-                }
 
-                let category = if is_react_component_event(event) {
-                    let color = extract_react_color(event).unwrap_or("primary");
-                    Some(format!("react.component.{color}"))
-                } else if is_react_scheduler_event(event) {
-                    let track = event
-                        .args
-                        .as_ref()
-                        .and_then(|a| a.get("detail"))
-                        .and_then(|d| d.get("devtools"))
-                        .and_then(|dt| dt.get("track"))
-                        .and_then(|t| t.as_str())
-                        .unwrap_or("unknown");
-                    Some(format!("react.scheduler.{}", track.to_lowercase()))
-                } else if event.cat.is_empty() {
-                    None
-                } else {
-                    Some(event.cat.clone())
-                };
-
-                let effective_thread = if is_react_component_event(event) {
-                    Some("React Components".to_string())
-                } else if is_react_scheduler_event(event) {
-                    let track = event
-                        .args
-                        .as_ref()
-                        .and_then(|a| a.get("detail"))
-                        .and_then(|d| d.get("devtools"))
-                        .and_then(|dt| dt.get("track"))
-                        .and_then(|t| t.as_str())
-                        .unwrap_or("Scheduler");
-                    Some(format!("React Scheduler: {track}"))
-                } else {
-                    thread_name
-                };
-
-                let name = event.name.trim_start_matches('\u{200b}').to_string();
-
-                match event.ph.as_str() {
-                    "X" => {
-                        let dur = event.dur.unwrap_or(0.0);
-                        let depth = stacks.entry(key).or_default().len() as u32;
-                        let parent_id = stacks
-                            .get(&key)
-                            .and_then(|s| s.last())
-                            .map(|&idx| frames[idx].id);
-
-                        let id = next_id;
-                        next_id += 1;
-                        let frame_idx = frames.len();
-                        frames.push(Frame {
-                            id,
-                            name,
-                            start: event.ts,
-                            end: event.ts + dur,
-                            depth,
-                            category,
-                            parent: parent_id,
-                            self_time: 0.0,
-                            thread: effective_thread,
-                        });
-                        stacks.entry(key).or_default().push(frame_idx);
-                    }
-                    "B" => {
-                        let depth = stacks.entry(key).or_default().len() as u32;
-                        let parent_id = stacks
-                            .get(&key)
-                            .and_then(|s| s.last())
-                            .map(|&idx| frames[idx].id);
-
-                        let id = next_id;
-                        next_id += 1;
-                        let frame_idx = frames.len();
-                        frames.push(Frame {
-                            id,
-                            name,
-                            start: event.ts,
-                            end: event.ts,
-                            depth,
-                            category,
-                            parent: parent_id,
-                            self_time: 0.0,
-                            thread: effective_thread,
-                        });
-                        stacks.entry(key).or_default().push(frame_idx);
-                    }
-                    "E" => {
-                        if let Some(frame_idx) = stacks.entry(key).or_default().pop() {
-                            frames[frame_idx].end = event.ts;
+                    let category = if is_react_component_event(event) {
+                        let color = extract_react_color(event).unwrap_or("primary");
+                        Some(format!("react.component.{color}"))
+                    } else if is_react_scheduler_event(event) {
+                        let track = event
+                            .args
+                            .as_ref()
+                            .and_then(|a| a.get("detail"))
+                            .and_then(|d| d.get("devtools"))
+                            .and_then(|dt| dt.get("track"))
+                            .and_then(|t| t.as_str())
+                            .unwrap_or("unknown");
+                        Some(format!("react.scheduler.{}", track.to_lowercase()))
+                    } else if event.cat.is_empty() {
+                        None
+                    } else {
+                        Some(event.cat.clone())
+                    };
+
+                    let effective_thread = if is_react_component_event(event) {
+                        Some("React Components".to_string())
+                    } else if is_react_scheduler_event(event) {
+                        let track = event
+                            .args
+                            .as_ref()
+                            .and_then(|a| a.get("detail"))
+                            .and_then(|d| d.get("devtools"))
+                            .and_then(|dt| dt.get("track"))
+                            .and_then(|t| t.as_str())
+                            .unwrap_or("Scheduler");
+                        Some(format!("React Scheduler: {track}"))
+                    } else {
+                        thread_name
+                    };
+                    // React tracks are logical and not tied to the emitting thread.
+                    let (pid, tid) =
+                        if is_react_component_event(event) || is_react_scheduler_event(event) {
+                            (None, None)
+                        } else {
+                            (Some(event.pid), Some(event.tid))
+                        };
+
+                    let name = event.name.trim_start_matches('\u{200b}').to_string();
+
+                    match event.ph.as_str() {
+                        "X" => {
+                            let dur = event.dur.unwrap_or(0.0);
+                            let depth = stacks.entry(key).or_default().len() as u32;
+                            if depth > limits.max_depth {
+                                *too_deep += 1;
+                                continue;
+                            }
+                            let parent_id = stacks
+                                .get(&key)
+                                .and_then(|s| s.last())
+                                .map(|&idx| frames[idx].id);
+
+                            let id = *next_id;
+                            *next_id += 1;
+                            let frame_idx = frames.len();
+                            frames.push(Frame {
+                                id,
+                                name,
+                                start: event.ts,
+                                end: event.ts + dur,
+                                depth,
+                                category,
+                                parent: parent_id,
+                                self_time: 0.0,
+                                thread: effective_thread,
+                                truncated: false,
+                                pid,
+                                tid,
+                                source: event_url(event),
+                                binary: None,
+                                inlined: vec![],
+                            });
+                            stacks.entry(key).or_default().push(frame_idx);
+                        }
+                        "B" => {
+                            let depth = stacks.entry(key).or_default().len() as u32;
+                            if depth > limits.max_depth {
+                                *too_deep += 1;
+                                *deep_opens.entry(key).or_default() += 1;
+                                continue;
+                            }
+                            let parent_id = stacks
+                                .get(&key)
+                                .and_then(|s| s.last())
+                                .map(|&idx| frames[idx].id);
+
+                            let id = *next_id;
+                            *next_id += 1;
+                            let frame_idx = frames.len();
+                            frames.push(Frame {
+                                id,
+                                name,
+                                start: event.ts,
+                                end: event.ts,
+                                depth,
+                                category,
+                                parent: parent_id,
+                                self_time: 0.0,
+                                thread: effective_thread,
+                                // Open until the matching `E` arrives.
+                                truncated: true,
+                                pid,
+                                tid,
+                                source: event_url(event),
+                                binary: None,
+                                inlined: vec![],
+                            });
+                            stacks.entry(key).or_default().push(frame_idx);
                         }
+                        "E" => {
+                            // An `E` of a `B` left out for depth closes nothing,
+                            // being inside everything on the stack.
+                            if let Some(open) = deep_opens.get_mut(&key).filter(|n| **n > 0) {
+                                *open -= 1;
+                                continue;
+                            }
+                            // Close the innermost open `B`. An `E` with no open `B`
+                            // (recording started mid-span) has nothing to close.
+                            if let Some(stack) = stacks.get_mut(&key)
+                                && let Some(pos) =
+                                    stack.iter().rposition(|&idx| frames[idx].truncated)
+                            {
+                                let frame_idx = stack[pos];
+                                stack.truncate(pos);
+                                frames[frame_idx].end = event.ts;
+                                frames[frame_idx].truncated = false;
+                            }
+                        }
+                        _ => {}
                     }
-                    _ => {}
                 }
-            }
 
-            // === Instant events (ph:"I" or "i") ===
-            "I" | "i" => {
-                let scope = match event.s.as_deref() {
-                    Some("g") => MarkerScope::Global,
-                    Some("p") => MarkerScope::Process,
-                    _ => MarkerScope::Thread,
-                };
-
-                // Extract UpdateCounters → counter tracks
-                if event.name == "UpdateCounters"
-                    && let Some(data) = event.args.as_ref().and_then(|a| a.get("data"))
-                {
-                    extract_update_counters(data, event.ts, &mut counter_map);
-                }
+                // === Instant events (ph:"I" or "i") ===
+                "I" | "i" => {
+                    let scope = match event.s.as_deref() {
+                        Some("g") => MarkerScope::Global,
+                        Some("p") => MarkerScope::Process,
+                        _ => MarkerScope::Thread,
+                    };
+
+                    // Extract UpdateCounters → counter tracks
+                    if event.name == "UpdateCounters"
+                        && let Some(data) = event.args.as_ref().and_then(|a| a.get("data"))
+                    {
+                        extract_update_counters(data, event.ts, counter_map);
+                    }
 
-                // Network request correlation
-                if let Some(data) = event.args.as_ref().and_then(|a| a.get("data")) {
-                    match event.name.as_str() {
-                        "ResourceSendRequest" => {
-                            if let Some(rid) = data.get("requestId").and_then(|v| v.as_str()) {
-                                let url = data.get("url").and_then(|v| v.as_str()).unwrap_or("");
-                                net_sends.insert(
-                                    rid.to_string(),
-                                    NetworkRequest {
-                                        request_id: SharedStr::from(rid),
-                                        url: SharedStr::from(url),
-                                        send_ts: event.ts,
-                                        response_ts: None,
-                                        finish_ts: None,
-                                        mime_type: None,
-                                        from_cache: false,
-                                    },
-                                );
+                    // Network request correlation
+                    if let Some(data) = event.args.as_ref().and_then(|a| a.get("data")) {
+                        match event.name.as_str() {
+                            "ResourceSendRequest" => {
+                                if let Some(rid) = data.get("requestId").and_then(|v| v.as_str()) {
+                                    let url =
+                                        data.get("url").and_then(|v| v.as_str()).unwrap_or("");
+                                    let (by_script, initiator) = request_initiator(data);
+                                    request_points.insert(
+                                        rid.to_string(),
+                                        RequestPoints {
+                                            send: (event.ts, event.pid, event.tid),
+                                            arrival: None,
+                                            by_script,
+                                        },
+                                    );
+                                    net_sends.insert(
+                                        rid.to_string(),
+                                        NetworkRequest {
+                                            request_id: SharedStr::from(rid),
+                                            url: SharedStr::from(url),
+                                            send_ts: event.ts,
+                                            response_ts: None,
+                                            finish_ts: None,
+                                            mime_type: None,
+                                            from_cache: false,
+                                            initiator: initiator.map(SharedStr::from),
+                                        },
+                                    );
+                                }
                             }
-                        }
-                        "ResourceReceiveResponse" => {
-                            if let Some(rid) =
-                                data.get("requestId").and_then(serde_json::Value::as_str)
-                                && let Some(req) = net_sends.get_mut(rid)
-                            {
-                                req.response_ts = Some(event.ts);
-                                if let Some(mime) =
-                                    data.get("mimeType").and_then(serde_json::Value::as_str)
+                            "ResourceReceiveResponse" => {
+                                if let Some(rid) =
+                                    data.get("requestId").and_then(serde_json::Value::as_str)
+                                    && let Some(req) = net_sends.get_mut(rid)
                                 {
-                                    req.mime_type = Some(SharedStr::from(mime));
+                                    req.response_ts = Some(event.ts);
+                                    if let Some(mime) =
+                                        data.get("mimeType").and_then(serde_json::Value::as_str)
+                                    {
+                                        req.mime_type = Some(SharedStr::from(mime));
+                                    }
+                                    req.from_cache = data
+                                        .get("fromCache")
+                                        .and_then(serde_json::Value::as_bool)
+                                        .unwrap_or(false);
+                                    if let Some(points) = request_points.get_mut(rid) {
+                                        points.arrival = Some((event.ts, event.pid, event.tid));
+                                    }
                                 }
-                                req.from_cache = data
-                                    .get("fromCache")
-                                    .and_then(serde_json::Value::as_bool)
-                                    .unwrap_or(false);
                             }
-                        }
-                        "ResourceFinish" => {
-                            if let Some(rid) = data.get("requestId").and_then(|v| v.as_str()) {
-                                if let Some(mut req) = net_sends.remove(rid) {
-                                    req.finish_ts = Some(event.ts);
-                                    network_requests.push(req);
-                                } else {
-                                    // Finish without send — skip
+                            "ResourceFinish" => {
+                                if let Some(rid) = data.get("requestId").and_then(|v| v.as_str()) {
+                                    if let Some(mut req) = net_sends.remove(rid) {
+                                        req.finish_ts = Some(event.ts);
+                                        if let Some(points) = request_points.get_mut(rid) {
+                                            points
+                                                .arrival
+                                                .get_or_insert((event.ts, event.pid, event.tid));
+                                        }
+                                        network_requests.push(req);
+                                    } else {
+                                        // Finish without send — skip
+                                    }
                                 }
                             }
+                            _ => {}
                         }
-                        _ => {}
                     }
-                }
 
-                // Screenshot extraction
-                if event.name == "Screenshot"
-                    && let Some(snap) = event
-                        .args
-                        .as_ref()
-                        .and_then(|a| a.get("snapshot"))
-                        .and_then(|v| v.as_str())
-                {
-                    screenshots.push(Screenshot {
+                    // Screenshot extraction
+                    if event.name == "Screenshot"
+                        && let Some(snap) = event
+                            .args
+                            .as_ref()
+                            .and_then(|a| a.get("snapshot"))
+                            .and_then(|v| v.as_str())
+                    {
+                        screenshots.push(Screenshot {
+                            ts: event.ts,
+                            data: snap.to_string(),
+                        });
+                    }
+
+                    instant_events.push(InstantEvent {
                         ts: event.ts,
-                        data: snap.to_string(),
+                        name: SharedStr::from(event.name.as_str()),
+                        cat: if event.cat.is_empty() {
+                            None
+                        } else {
+                            Some(SharedStr::from(event.cat.as_str()))
+                        },
+                        scope,
+                        pid: event.pid,
+                        tid: event.tid,
                     });
                 }
 
-                instant_events.push(InstantEvent {
-                    ts: event.ts,
-                    name: SharedStr::from(event.name.as_str()),
-                    cat: if event.cat.is_empty() {
-                        None
+                // === Mark events (ph:"R") — Web Vitals and navigation timing ===
+                "R" => {
+                    let category = match event.name.as_str() {
+                        "firstPaint"
+                        | "firstContentfulPaint"
+                        | "firstMeaningfulPaint"
+                        | "largestContentfulPaint::Candidate" => Some("web-vital"),
+                        "InteractiveTime" => Some("web-vital"),
+                        "LayoutShift" => Some("web-vital"),
+                        "navigationStart"
+                        | "fetchStart"
+                        | "responseEnd"
+                        | "domLoading"
+                        | "domInteractive"
+                        | "domContentLoadedEventStart"
+                        | "domContentLoadedEventEnd"
+                        | "domComplete"
+                        | "loadEventStart"
+                        | "loadEventEnd" => Some("navigation"),
+                        _ => None,
+                    };
+                    // Normalize LCP candidate name
+                    let name = if event.name == "largestContentfulPaint::Candidate" {
+                        "LCP"
                     } else {
-                        Some(SharedStr::from(event.cat.as_str()))
-                    },
-                    scope,
-                    pid: event.pid,
-                    tid: event.tid,
-                });
-            }
-
-            // === Mark events (ph:"R") — Web Vitals and navigation timing ===
-            "R" => {
-                let category = match event.name.as_str() {
-                    "firstPaint"
-                    | "firstContentfulPaint"
-                    | "firstMeaningfulPaint"
-                    | "largestContentfulPaint::Candidate" => Some("web-vital"),
-                    "InteractiveTime" => Some("web-vital"),
-                    "LayoutShift" => Some("web-vital"),
-                    "navigationStart"
-                    | "fetchStart"
-                    | "responseEnd"
-                    | "domLoading"
-                    | "domInteractive"
-                    | "domContentLoadedEventStart"
-                    | "domContentLoadedEventEnd"
-                    | "domComplete"
-                    | "loadEventStart"
-                    | "loadEventEnd" => Some("navigation"),
-                    _ => None,
-                };
-                // Normalize LCP candidate name
-                let name = if event.name == "largestContentfulPaint::Candidate" {
-                    "LCP"
-                } else {
-                    &event.name
-                };
-                markers.push(Marker {
-                    ts: event.ts,
-                    name: SharedStr::from(name),
-                    scope: MarkerScope::Global,
-                    category: category.map(SharedStr::from),
-                });
-            }
+                        &event.name
+                    };
+                    markers.push(Marker {
+                        ts: event.ts,
+                        name: SharedStr::from(name),
+                        scope: MarkerScope::Global,
+                        category: category.map(SharedStr::from),
+                    });
+                }
 
-            // === Counter events (ph:"C") ===
-            "C" => {
-                if let Some(obj) = event.args.as_ref().and_then(|a| a.as_object()) {
-                    for (counter_name, value) in obj {
-                        if let Some(v) = value.as_f64() {
-                            let full_name = if event.name.is_empty() {
-                                counter_name.clone()
-                            } else {
-                                format!("{} — {}", event.name, counter_name)
-                            };
-                            let unit = guess_counter_unit(&full_name);
-                            let entry = counter_map.entry(full_name).or_insert((unit, Vec::new()));
-                            entry.1.push(CounterSample {
-                                ts: event.ts,
-                                value: v,
-                            });
+                // === Counter events (ph:"C") ===
+                "C" => {
+                    if let Some(obj) = event.args.as_ref().and_then(|a| a.as_object()) {
+                        for (counter_name, value) in obj {
+                            if let Some(v) = value.as_f64() {
+                                let full_name = if event.name.is_empty() {
+                                    counter_name.clone()
+                                } else {
+                                    format!("{} — {}", event.name, counter_name)
+                                };
+                                let (unit, scale) = guess_counter_unit(&full_name);
+                                let entry =
+                                    counter_map.entry(full_name).or_insert((unit, Vec::new()));
+                                entry.1.push(CounterSample {
+                                    ts: event.ts,
+                                    value: v * scale,
+                                });
+                            }
                         }
                     }
                 }
-            }
 
-            // === Async events (ph:"b"/"e"/"n") ===
-            "b" => {
-                if let Some(id) = event.effective_id() {
-                    async_begins.insert(
-                        (event.cat.clone(), id.clone()),
-                        (event.ts, event.name.clone(), event.pid, event.tid),
-                    );
+                // === Async events (ph:"b"/"e"/"n") ===
+                "b" => {
+                    if let Some(id) = event.effective_id() {
+                        async_begins.insert(
+                            (event.cat.clone(), id.clone()),
+                            (event.ts, event.name.clone(), event.pid, event.tid),
+                        );
+                    }
                 }
-            }
-            "e" => {
-                if let Some(id) = event.effective_id() {
-                    let begin_key = (event.cat.clone(), id.clone());
-                    if let Some((start_ts, name, pid, tid)) = async_begins.remove(&begin_key) {
+                "e" => {
+                    if let Some(id) = event.effective_id() {
+                        let begin_key = (event.cat.clone(), id.clone());
+                        if let Some((start_ts, name, pid, tid)) = async_begins.remove(&begin_key) {
+                            if is_user_timing_measure(event) {
+                                let name = name.trim_start_matches('\u{200b}').to_string();
+                                user_measures.push((start_ts, event.ts, name));
+                                continue;
+                            }
+                            async_spans.push(AsyncSpan {
+                                id: SharedStr::from(id.as_str()),
+                                name: SharedStr::from(name.as_str()),
+                                cat: if event.cat.is_empty() {
+                                    None
+                                } else {
+                                    Some(SharedStr::from(event.cat.as_str()))
+                                },
+                                start: start_ts,
+                                end: event.ts,
+                                pid,
+                                tid,
+                            });
+                        }
+                    }
+                }
+                "n" => {
+                    // Async instant — we store as a zero-duration async span
+                    if let Some(id) = event.effective_id() {
                         async_spans.push(AsyncSpan {
                             id: SharedStr::from(id.as_str()),
-                            name: SharedStr::from(name.as_str()),
+                            name: SharedStr::from(event.name.as_str()),
                             cat: if event.cat.is_empty() {
                                 None
                             } else {
                                 Some(SharedStr::from(event.cat.as_str()))
                             },
-                            start: start_ts,
+                            start: event.ts,
                             end: event.ts,
-                            pid,
-                            tid,
+                            pid: event.pid,
+                            tid: event.tid,
                         });
                     }
                 }
-            }
-            "n" => {
-                // Async instant — we store as a zero-duration async span
-                if let Some(id) = event.effective_id() {
-                    async_spans.push(AsyncSpan {
-                        id: SharedStr::from(id.as_str()),
-                        name: SharedStr::from(event.name.as_str()),
-                        cat: if event.cat.is_empty() {
-                            None
-                        } else {
-                            Some(SharedStr::from(event.cat.as_str()))
-                        },
-                        start: event.ts,
-                        end: event.ts,
-                        pid: event.pid,
-                        tid: event.tid,
-                    });
-                }
-            }
 
-            // === Flow events (ph:"s"/"f"/"t") ===
-            "s" => {
-                if let Some(id) = event.effective_id() {
-                    flow_starts.insert(id.clone(), (event.ts, event.tid, event.name.clone()));
-                }
-            }
-            "f" => {
-                if let Some(id) = event.effective_id()
-                    && let Some((from_ts, from_tid, name)) = flow_starts.remove(&id)
-                {
-                    flow_arrows.push(FlowArrow {
-                        name: SharedStr::from(name.as_str()),
-                        id: SharedStr::from(id.as_str()),
-                        from_ts,
-                        from_tid,
-                        to_ts: event.ts,
-                        to_tid: event.tid,
-                    });
+                // === Flow events (ph:"s"/"f"/"t") ===
+                "s" => {
+                    if let Some(id) = event.effective_id() {
+                        flow_starts.insert(id.clone(), (event.ts, key, event.name.clone()));
+                    }
                 }
-            }
-            "t" => {
-                // Flow step: end current flow, start new one
-                if let Some(id) = event.effective_id() {
-                    if let Some((from_ts, from_tid, name)) = flow_starts.remove(&id) {
+                "f" => {
+                    if let Some(id) = event.effective_id()
+                        && let Some((from_ts, (from_pid, from_tid), name)) = flow_starts.remove(&id)
+                    {
                         flow_arrows.push(FlowArrow {
                             name: SharedStr::from(name.as_str()),
                             id: SharedStr::from(id.as_str()),
                             from_ts,
+                            from_pid,
                             from_tid,
                             to_ts: event.ts,
+                            to_pid: event.pid,
                             to_tid: event.tid,
                         });
                     }
-                    flow_starts.insert(id.clone(), (event.ts, event.tid, event.name.clone()));
                 }
-            }
+                "t" => {
+                    // Flow step: end current flow, start new one
+                    if let Some(id) = event.effective_id() {
+                        if let Some((from_ts, (from_pid, from_tid), name)) = flow_starts.remove(&id)
+                        {
+                            flow_arrows.push(FlowArrow {
+                                name: SharedStr::from(name.as_str()),
+                                id: SharedStr::from(id.as_str()),
+                                from_ts,
+                                from_pid,
+                                from_tid,
+                                to_ts: event.ts,
+                                to_pid: event.pid,
+                                to_tid: event.tid,
+                            });
+                        }
+                        flow_starts.insert(id.clone(), (event.ts, key, event.name.clone()));
+                    }
+                }
 
-            // === CPU profiler samples (ph:"P") ===
-            "P" => {
-                if let Some(data) = event.args.as_ref().and_then(|a| a.get("data")) {
-                    extract_cpu_profile_chunk(
-                        data,
-                        event.ts,
-                        &mut cpu_nodes,
-                        &mut cpu_samples,
-                        &mut cpu_timestamps,
-                    );
+                // === CPU profiler samples (ph:"P") ===
+                "P" => {
+                    if let Some(data) = event.args.as_ref().and_then(|a| a.get("data")) {
+                        extract_cpu_profile_chunk(
+                            data,
+                            event.ts,
+                            cpu_nodes,
+                            cpu_samples,
+                            cpu_timestamps,
+                        );
+                    }
                 }
-            }
 
-            // === Object lifecycle (ph:"N"/"O"/"D") ===
-            "N" | "O" | "D" => {
-                let phase = match event.ph.as_str() {
-                    "N" => ObjectPhase::Create,
-                    "O" => ObjectPhase::Snapshot,
-                    _ => ObjectPhase::Destroy,
-                };
-                let obj_id = event.effective_id().unwrap_or_default();
-                object_events.push(ObjectEvent {
-                    id: SharedStr::from(obj_id.as_str()),
-                    name: SharedStr::from(event.name.as_str()),
-                    phase,
-                    ts: event.ts,
-                });
-            }
+                // === Object lifecycle (ph:"N"/"O"/"D") ===
+                "N" | "O" | "D" => {
+                    let phase = match event.ph.as_str() {
+                        "N" => ObjectPhase::Create,
+                        "O" => ObjectPhase::Snapshot,
+                        _ => ObjectPhase::Destroy,
+                    };
+                    let obj_id = event.effective_id().unwrap_or_default();
+                    object_events.push(ObjectEvent {
+                        id: SharedStr::from(obj_id.as_str()),
+                        name: SharedStr::from(event.name.as_str()),
+                        phase,
+                        ts: event.ts,
+                    });
+                }
 
-            _ => {}
+                _ => {}
+            }
         }
     }
 
-    // Compute self_time = duration - sum(children durations)
-    let child_time: std::collections::HashMap<u64, f64> = {
-        let mut map: std::collections::HashMap<u64, f64> = std::collections::HashMap::new();
-        for f in &frames {
-            if let Some(pid) = f.parent {
-                *map.entry(pid).or_default() += f.duration();
+    /// The profile of everything added so far.
+    fn finish(
+        self,
+        collector: &EventCollector<'_>,
+        metadata: &Option<serde_json::Value>,
+    ) -> Profile {
+        let Self {
+            mut frames,
+            mut next_id,
+            too_deep,
+            instant_events,
+            markers,
+            object_events,
+            net_sends,
+            mut network_requests,
+            request_points,
+            screenshots,
+            counter_map,
+            async_spans,
+            user_measures,
+            mut flow_arrows,
+            cpu_nodes,
+            cpu_samples,
+            cpu_timestamps,
+            trace_end,
+            ..
+        } = self;
+        let mut trace_meta = extract_trace_metadata(metadata);
+
+        // Store navigationStart on the time domain if found
+        if let Some(nav_start) = collector.navigation_start_us {
+            if let Some(ref mut td) = trace_meta.time_domain {
+                td.navigation_start_us = Some(nav_start);
             }
+            trace_meta.navigation_start_us = Some(nav_start);
         }
-        map
-    };
-    for f in &mut frames {
-        let children_total = child_time.get(&f.id).copied().unwrap_or(0.0);
-        f.self_time = (f.duration() - children_total).max(0.0);
-    }
-
-    // Determine time range from all event types
-    let mut min_ts = f64::INFINITY;
-    let mut max_ts = f64::NEG_INFINITY;
-    for f in &frames {
-        min_ts = min_ts.min(f.start);
-        max_ts = max_ts.max(f.end);
-    }
-    for e in &instant_events {
-        min_ts = min_ts.min(e.ts);
-        max_ts = max_ts.max(e.ts);
-    }
-    for m in &markers {
-        min_ts = min_ts.min(m.ts);
-        max_ts = max_ts.max(m.ts);
-    }
-    for a in &async_spans {
-        min_ts = min_ts.min(a.start);
-        max_ts = max_ts.max(a.end);
-    }
-
-    // Build counter tracks from collected data
-    let counters: Vec<CounterTrack> = counter_map
-        .into_iter()
-        .map(|(name, (unit, mut samples))| {
-            samples.sort_by(|a, b| a.ts.total_cmp(&b.ts));
-            CounterTrack {
-                name: SharedStr::from(name.as_str()),
-                unit,
-                samples,
+
+        // A stream may name a thread only after its first events were added.
+        for f in frames.iter_mut().filter(|f| f.thread.is_none()) {
+            if let (Some(pid), Some(tid)) = (f.pid, f.tid) {
+                f.thread = collector.thread_names.get(&(pid, tid)).cloned();
             }
-        })
-        .collect();
+        }
 
-    // Build CPU samples
-    let cpu_sample_data = if !cpu_nodes.is_empty() {
-        Some(CpuSamples {
-            nodes: cpu_nodes,
-            samples: cpu_samples,
-            timestamps: cpu_timestamps,
-        })
-    } else {
-        None
-    };
+        push_user_timing_frames(&mut frames, &mut next_id, user_measures);
 
-    // Flush remaining network sends (no finish event)
-    for (_, req) in net_sends {
-        network_requests.push(req);
-    }
-    // Sort network requests by send timestamp
-    network_requests.sort_by(|a, b| a.send_ts.total_cmp(&b.send_ts));
-
-    let mut profile = Profile::new(
-        ProfileMetadata {
-            name: None,
-            start_time: if min_ts.is_finite() { min_ts } else { 0.0 },
-            end_time: if max_ts.is_finite() { max_ts } else { 0.0 },
-            format: "chrome".to_string(),
-            time_domain: trace_meta.time_domain,
-        },
-        frames,
-    );
-    profile.counters = counters;
-    profile.async_spans = async_spans;
-    profile.flow_arrows = flow_arrows;
-    profile.markers = markers;
-    profile.instant_events = instant_events;
-    profile.object_events = object_events;
-    profile.cpu_samples = cpu_sample_data;
-    profile.network_requests = network_requests;
-    profile.screenshots = screenshots;
-
-    Ok(profile)
+        // `B` events still open here were cut off by the end of the recording.
+        // Close them at the last timestamp in the trace so they keep their extent.
+        for f in frames.iter_mut().filter(|f| f.truncated) {
+            f.end = trace_end.max(f.start);
+        }
+
+        // Determine time range from all event types
+        let mut min_ts = f64::INFINITY;
+        let mut max_ts = f64::NEG_INFINITY;
+        for f in &frames {
+            min_ts = min_ts.min(f.start);
+            max_ts = max_ts.max(f.end);
+        }
+        for e in &instant_events {
+            min_ts = min_ts.min(e.ts);
+            max_ts = max_ts.max(e.ts);
+        }
+        for m in &markers {
+            min_ts = min_ts.min(m.ts);
+            max_ts = max_ts.max(m.ts);
+        }
+        for a in &async_spans {
+            min_ts = min_ts.min(a.start);
+            max_ts = max_ts.max(a.end);
+        }
+
+        // Build counter tracks from collected data, by name so lanes keep their
+        // order across loads.
+        let mut counters: Vec<CounterTrack> = counter_map
+            .into_iter()
+            .map(|(name, (unit, mut samples))| {
+                samples.sort_by(|a, b| a.ts.total_cmp(&b.ts));
+                if name.to_lowercase().contains("power.rails") {
+                    samples = energy_to_power(&samples);
+                }
+                CounterTrack {
+                    name: SharedStr::from(name.as_str()),
+                    unit,
+                    samples,
+                }
+            })
+            .collect();
+        counters.sort_by(|a, b| a.name.cmp(&b.name));
+
+        // Build CPU samples
+        let cpu_sample_data = if !cpu_nodes.is_empty() {
+            Some(CpuSamples {
+                nodes: cpu_nodes,
+                samples: cpu_samples,
+                timestamps: cpu_timestamps,
+                sample_interval_us: None,
+            })
+        } else {
+            None
+        };
+
+        // Flush remaining network sends (no finish event)
+        for (_, req) in net_sends {
+            network_requests.push(req);
+        }
+        // Sort network requests by send timestamp, then id for unfinished ones
+        // flushed from the map in arbitrary order.
+        network_requests.sort_by(|a, b| {
+            a.send_ts
+                .total_cmp(&b.send_ts)
+                .then_with(|| a.request_id.cmp(&b.request_id))
+        });
+        flow_arrows.extend(initiator_arrows(
+            &network_requests,
+            &request_points,
+            &frames,
+        ));
+
+        let mut profile = Profile::new(
+            ProfileMetadata {
+                name: None,
+                start_time: if min_ts.is_finite() { min_ts } else { 0.0 },
+                end_time: if max_ts.is_finite() { max_ts } else { 0.0 },
+                format: "chrome".to_string(),
+                time_domain: trace_meta.time_domain,
+                value_unit: None,
+                sample_interval_us: None,
+            },
+            frames,
+        );
+        profile.counters = counters;
+        profile.async_spans = async_spans;
+        profile.flow_arrows = flow_arrows;
+        profile.markers = markers;
+        profile.instant_events = instant_events;
+        profile.object_events = object_events;
+        profile.cpu_samples = cpu_sample_data;
+        profile.network_requests = network_requests;
+        profile.screenshots = screenshots;
+        profile.process_names = collector.process_names.clone();
+        profile.limits_hit = LimitsHit {
+            too_deep,
+            ..collector.hit
+        };
+
+        profile
+    }
 }
 
 #[cfg(test)]
@@ -874,7 +1690,8 @@ mod tests {
     fn parse_complete_events() {
         let json = r#"{"traceEvents":[
             {"name":"main","ph":"X","ts":0,"dur":100,"pid":1,"tid":1,"cat":""},
-            {"name":"child","ph":"X","ts":10,"dur":40,"pid":1,"tid":1,"cat":"func"}
+            {"name":"child","ph":"X","ts":10,"dur":40,"pid":1,"tid":1,"cat":"func",
+             "args":{"data":{"url":"https://example.com/app.js"}}}
         ]}"#;
 
         let profile = parse_chrome_trace(json.as_bytes()).unwrap();
@@ -891,6 +1708,8 @@ mod tests {
         assert_eq!(child.depth, 1);
         assert_eq!(child.parent, Some(main_frame.id));
         assert_eq!(child.category.as_deref(), Some("func"));
+        assert_eq!(child.source.as_deref(), Some("https://example.com/app.js"));
+        assert_eq!(main_frame.source, None);
     }
 
     #[test]
@@ -909,12 +1728,234 @@ mod tests {
         assert_eq!(outer.name, "outer");
         assert_eq!(outer.depth, 0);
         assert_eq!(outer.duration(), 100.0);
-        assert_eq!(outer.self_time, 60.0);
 
         let inner = &profile.frames[1];
         assert_eq!(inner.name, "inner");
         assert_eq!(inner.depth, 1);
         assert_eq!(inner.parent, Some(outer.id));
+        assert!(!outer.truncated && !inner.truncated);
+
+        let visual = profile.into_visual_profile();
+        assert_eq!(visual.span(0).map(|s| s.self_value), Some(60.0));
+    }
+
+    #[test]
+    fn unmatched_begin_is_closed_at_trace_end() {
+        let json = r#"[
+            {"name":"stray","ph":"E","ts":0,"pid":1,"tid":1},
+            {"name":"outer","ph":"B","ts":0,"pid":1,"tid":1},
+            {"name":"inner","ph":"B","ts":10,"pid":1,"tid":1},
+            {"name":"inner","ph":"E","ts":20,"pid":1,"tid":1},
+            {"name":"other","ph":"X","ts":30,"dur":90,"pid":1,"tid":2}
+        ]"#;
+
+        let profile = parse_chrome_trace(json.as_bytes()).unwrap();
+        let outer = &profile.frames[0];
+        assert_eq!(outer.name, "outer");
+        assert!(outer.truncated);
+        assert_eq!(outer.end, 120.0);
+        assert!(!profile.frames[1].truncated);
+
+        let visual = profile.into_visual_profile();
+        assert_eq!(visual.meta.truncated_spans, 1);
+        assert_eq!(visual.span(0).map(|s| s.self_value), Some(110.0));
+    }
+
+    #[test]
+    fn same_tid_in_different_processes_stays_apart() {
+        let json = r#"[
+            {"name":"process_name","ph":"M","ts":0,"pid":1,"tid":0,"args":{"name":"Browser"}},
+            {"name":"process_name","ph":"M","ts":0,"pid":2,"tid":0,"args":{"name":"Renderer"}},
+            {"name":"thread_name","ph":"M","ts":0,"pid":1,"tid":7,"args":{"name":"Main"}},
+            {"name":"thread_name","ph":"M","ts":0,"pid":2,"tid":7,"args":{"name":"Main"}},
+            {"name":"a","ph":"X","ts":0,"dur":10,"pid":1,"tid":7},
+            {"name":"b","ph":"X","ts":0,"dur":10,"pid":2,"tid":7},
+            {"name":"post","ph":"s","ts":1,"pid":1,"tid":7,"id":"9"},
+            {"name":"post","ph":"f","ts":5,"pid":2,"tid":7,"id":"9"}
+        ]"#;
+
+        let profile = parse_chrome_trace(json.as_bytes()).unwrap();
+        assert_eq!(
+            profile.process_names.get(&2).map(String::as_str),
+            Some("Renderer")
+        );
+        let arrow = &profile.flow_arrows[0];
+        assert_eq!((arrow.from_pid, arrow.to_pid), (1, 2));
+
+        let visual = profile.into_visual_profile();
+        assert_eq!(visual.threads.len(), 2);
+        let mut names: Vec<&str> = visual.threads.iter().map(|t| &*t.name).collect();
+        names.sort_unstable();
+        assert_eq!(names, ["Browser / Main", "Renderer / Main"]);
+        let renderer = visual
+            .threads
+            .iter()
+            .find(|t| t.source_thread() == Some((2, 7)))
+            .unwrap();
+        assert_eq!(renderer.process_name.as_deref(), Some("Renderer"));
+    }
+
+    #[test]
+    fn stream_extends_profile_batch_by_batch() {
+        let mut stream = ChromeTraceStream::new(Limits::default(), None);
+        stream
+            .push_batch(
+                br#"[{"name":"thread_name","ph":"M","ts":0,"pid":1,"tid":1,"args":{"name":"Main"}},
+                     {"name":"task","ph":"B","ts":0,"pid":1,"tid":1}]"#,
+            )
+            .unwrap();
+        let live = stream.profile();
+        assert_eq!(live.frames.len(), 1);
+        assert!(live.frames[0].truncated);
+
+        assert!(stream.push_batch(b"[{not json").is_err());
+        stream
+            .push_batch(
+                br#"{"traceEvents":[{"name":"task","ph":"E","ts":40,"pid":1,"tid":1},
+                {"name":"next","ph":"X","ts":50,"dur":10,"pid":1,"tid":1}]}"#,
+            )
+            .unwrap();
+        assert_eq!(stream.event_count(), 3);
+        let done = stream.profile();
+        assert_eq!(done.frames.len(), 2);
+        assert!(done.frames.iter().all(|f| !f.truncated));
+        assert_eq!(done.frames[0].thread.as_deref(), Some("Main"));
+        assert_eq!(done.metadata.end_time, 60.0);
+    }
+
+    #[test]
+    fn stream_processes_each_batch_once() {
+        let mut stream = ChromeTraceStream::new(Limits::default(), None);
+        stream
+            .push_batch(
+                br#"[{"name":"task","ph":"B","ts":0,"pid":1,"tid":1},
+                     {"name":"tick","ph":"i","ts":5,"pid":1,"tid":1}]"#,
+            )
+            .unwrap();
+        stream
+            .push_batch(
+                br#"[{"name":"thread_name","ph":"M","ts":0,"pid":1,"tid":1,"args":{"name":"Main"}},
+                     {"name":"task","ph":"E","ts":40,"pid":1,"tid":1},
+                     {"name":"next","ph":"X","ts":50,"dur":10,"pid":1,"tid":1}]"#,
+            )
+            .unwrap();
+        // The second batch only added its own two events.
+        assert_eq!(stream.events_processed(), 4);
+        let live = stream.profile();
+        let spans: Vec<_> = live
+            .frames
+            .iter()
+            .map(|f| (f.name.as_str(), f.start, f.end, f.thread.as_deref()))
+            .collect();
+        assert_eq!(
+            spans,
+            [
+                ("task", 0.0, 40.0, Some("Main")),
+                ("next", 50.0, 60.0, Some("Main"))
+            ]
+        );
+        assert_eq!(live.instant_events.len(), 1);
+
+        // A batch from before the latest event rebuilds everything.
+        stream
+            .push_batch(br#"[{"name":"early","ph":"X","ts":45,"dur":2,"pid":1,"tid":1}]"#)
+            .unwrap();
+        assert_eq!(stream.events_processed(), 4 + 5);
+        assert_eq!(stream.profile().frames[1].name, "early");
+    }
+
+    #[test]
+    fn stream_reads_batches_within_limits() {
+        let limits = Limits {
+            max_events: 3,
+            max_string_len: 8,
+            max_memory: 400 * 8,
+            ..Limits::default()
+        };
+        let cancel = Arc::new(AtomicBool::new(false));
+        let mut stream = ChromeTraceStream::new(limits, Some(cancel.clone()));
+        stream
+            .push_batch(
+                br#"[{"name":"a_rather_long_name","ph":"X","ts":10,"dur":1,"pid":1,"tid":1},
+                     {"name":"b","ph":"X","ts":20,"dur":1,"pid":1,"tid":1}]"#,
+            )
+            .unwrap();
+        // The event limit counts the events of earlier batches.
+        stream
+            .push_batch(
+                br#"[{"name":"c","ph":"X","ts":30,"dur":1,"pid":1,"tid":1},
+                     {"name":"d","ph":"X","ts":40,"dur":1,"pid":1,"tid":1}]"#,
+            )
+            .unwrap();
+        assert_eq!(stream.event_count(), 3);
+        let hit = stream.profile().limits_hit;
+        assert_eq!((hit.events_dropped, hit.strings_cut), (1, 1));
+        assert_eq!(stream.profile().frames[0].name, "a_rat…");
+
+        // Past the memory budget whole batches are skipped.
+        let big = format!(
+            r#"[{{"name":"{}","ph":"i","ts":50,"pid":1,"tid":1}}]"#,
+            "x".repeat(400)
+        );
+        stream.push_batch(big.as_bytes()).unwrap();
+        assert_eq!(stream.profile().limits_hit.bytes_skipped, big.len());
+
+        cancel.store(true, std::sync::atomic::Ordering::Relaxed);
+        assert!(
+            stream
+                .push_batch(br#"[{"name":"e","ph":"i","ts":60,"pid":1,"tid":1}]"#)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn stream_bounds_out_of_order_rebuilds() {
+        let limits = Limits {
+            max_events: 10,
+            ..Limits::default()
+        };
+        let mut stream = ChromeTraceStream::new(limits, None);
+        let event =
+            |ts: u32| format!(r#"[{{"name":"e","ph":"X","ts":{ts},"dur":1,"pid":1,"tid":1}}]"#);
+        stream.push_batch(event(1_000).as_bytes()).unwrap();
+        // Each batch from before the latest event rebuilds everything...
+        for ts in 1..=7 {
+            stream.push_batch(event(ts).as_bytes()).unwrap();
+        }
+        assert_eq!(stream.events_processed(), (1..=8).sum::<usize>());
+        // ...until that would pass four times the event limit; then the
+        // late events are dropped instead.
+        stream.push_batch(event(8).as_bytes()).unwrap();
+        assert_eq!(stream.events_processed(), 36);
+        assert_eq!(stream.event_count(), 8);
+        assert_eq!(stream.profile().limits_hit.events_dropped, 1);
+    }
+
+    #[test]
+    fn sniff_chrome_prefix() {
+        assert!(looks_like_trace(b"  {\"traceEvents\": []}"));
+        assert!(looks_like_trace(b"[{\"ph\":\"X\"}]"));
+        assert!(!looks_like_trace(b"{\"nodes\": []}"));
+        assert!(!looks_like_trace(b"main;foo 1"));
+    }
+
+    #[test]
+    fn truncated_trace_recovers_complete_events() {
+        let trace = br#"{"traceEvents":[
+            {"name":"a","ph":"X","ts":0,"dur":10,"pid":1,"tid":1},
+            {"name":"b","ph":"X","ts":20,"dur":10,"pid":1,"tid":1},
+            {"name":"c","ph":"X","ts":40,"d"#;
+        assert!(parse_chrome_trace(trace).is_err());
+
+        let profile = parse_chrome_trace_partial(trace).unwrap();
+        assert_eq!(profile.frames.len(), 2);
+        let partial = profile.partial.unwrap();
+        assert_eq!(partial.events, 2);
+        assert_eq!(partial.cut_off, 20.0);
+
+        let whole = parse_chrome_trace_partial(br#"[{"name":"a","ph":"X","ts":0,"dur":1}]"#);
+        assert!(whole.unwrap().partial.is_none());
+        assert!(parse_chrome_trace_partial(br#"{"traceEvents":[{"na"#).is_err());
     }
 
     #[test]
@@ -931,6 +1972,30 @@ mod tests {
         assert!(profile.frames.is_empty());
     }
 
+    #[test]
+    fn missing_trace_events_is_rejected() {
+        let json = r#"{"metadata":{"clock-domain":"LINUX_CLOCK_MONOTONIC"}}"#;
+        let err = parse_chrome_trace(json.as_bytes()).unwrap_err();
+        assert!(matches!(err, ChromeParseError::MissingTraceEvents));
+    }
+
+    #[test]
+    fn reader_matches_slice_parse() {
+        let json = r#"{"otherData":{"version":"1"},"traceEvents":[
+            {"name":"thread_name","ph":"M","ts":0,"pid":1,"tid":2,"args":{"name":"Main"}},
+            {"name":"b","ph":"X","ts":5,"dur":2,"pid":1,"tid":2},
+            {"name":"a","ph":"X","ts":0,"dur":10,"pid":1,"tid":2}
+        ],"metadata":{"clock-domain":"LINUX_CLOCK_MONOTONIC"}}"#;
+        let from_slice = parse_chrome_trace(json.as_bytes()).unwrap();
+        let from_reader = parse_chrome_trace_reader(std::io::Cursor::new(json)).unwrap();
+        assert_eq!(from_reader.frames.len(), 2);
+        assert_eq!(from_reader.frames[0].name, "a");
+        assert_eq!(from_reader.frames[1].depth, 1);
+        assert_eq!(from_reader.frames[1].thread.as_deref(), Some("Main"));
+        assert!(from_reader.metadata.time_domain.is_some());
+        assert_eq!(from_slice.frames.len(), from_reader.frames.len());
+    }
+
     #[test]
     fn parse_react_component_events() {
         // Simulate React 19.2 Performance Track events in a Chrome trace.
@@ -996,6 +2061,70 @@ mod tests {
         assert_eq!(react_frames[1].depth, 1); // Header (inside App)
     }
 
+    #[test]
+    fn user_timing_measures_nest_on_own_thread() {
+        let json = r#"{"traceEvents":[
+            {"name":"load","ph":"b","id":"0x1","ts":100,"pid":1,"tid":1,"cat":"blink.user_timing"},
+            {"name":"fetch","ph":"b","id":"0x2","ts":110,"pid":1,"tid":1,"cat":"blink.user_timing"},
+            {"name":"fetch","ph":"e","id":"0x2","ts":150,"pid":1,"tid":1,"cat":"blink.user_timing"},
+            {"name":"\u200bparse","ph":"X","ts":160,"dur":20,"pid":1,"tid":1,"cat":"blink.user_timing"},
+            {"name":"load","ph":"e","id":"0x1","ts":200,"pid":1,"tid":1,"cat":"blink.user_timing"},
+            {"name":"later","ph":"X","ts":300,"dur":10,"pid":1,"tid":1,"cat":"blink.user_timing"},
+            {"name":"mark","ph":"R","ts":120,"pid":1,"tid":1,"cat":"blink.user_timing"}
+        ]}"#;
+
+        let profile = parse_chrome_trace(json.as_bytes()).unwrap();
+        assert!(profile.async_spans.is_empty());
+        assert_eq!(profile.markers.len(), 1);
+
+        let by_name = |name: &str| profile.frames.iter().find(|f| f.name == name).unwrap();
+        let (load, fetch, parse, later) = (
+            by_name("load"),
+            by_name("fetch"),
+            by_name("parse"),
+            by_name("later"),
+        );
+        assert!(
+            profile
+                .frames
+                .iter()
+                .all(|f| f.thread.as_deref() == Some("User Timing") && f.pid.is_none())
+        );
+        assert_eq!((load.depth, load.parent), (0, None));
+        assert_eq!((fetch.depth, fetch.parent), (1, Some(load.id)));
+        assert_eq!((parse.depth, parse.parent), (1, Some(load.id)));
+        assert_eq!((later.depth, later.parent), (0, None));
+        let load_id = load.id;
+
+        let visual = profile.into_visual_profile();
+        assert_eq!(visual.span(load_id).map(|s| s.self_value), Some(40.0));
+    }
+
+    #[test]
+    fn partly_overlapping_measures_keep_their_times() {
+        let json = r#"{"traceEvents":[
+            {"name":"fetch","ph":"X","ts":100,"dur":100,"pid":1,"tid":1,"cat":"blink.user_timing"},
+            {"name":"render","ph":"X","ts":150,"dur":100,"pid":1,"tid":1,"cat":"blink.user_timing"},
+            {"name":"paint","ph":"X","ts":160,"dur":20,"pid":1,"tid":1,"cat":"blink.user_timing"}
+        ]}"#;
+        let visual = parse_chrome_trace(json.as_bytes())
+            .unwrap()
+            .into_visual_profile();
+        assert_eq!(visual.meta.repairs.total(), 0);
+        let span = |name: &str| {
+            let thread = visual
+                .threads
+                .iter()
+                .find(|t| t.spans.iter().any(|s| s.name == name))
+                .unwrap();
+            let s = thread.spans.iter().find(|s| s.name == name).unwrap();
+            (thread.name.as_str(), s.start, s.end, s.depth)
+        };
+        assert_eq!(span("fetch"), ("User Timing", 100.0, 200.0, 0));
+        assert_eq!(span("paint"), ("User Timing", 160.0, 180.0, 1));
+        assert_eq!(span("render"), ("User Timing 2", 150.0, 250.0, 0));
+    }
+
     #[test]
     fn parse_react_scheduler_events() {
         let json = r#"{"traceEvents":[
@@ -1123,6 +2252,41 @@ mod tests {
             .find(|c| c.name.as_ref().contains("allocated"))
             .expect("should have allocated counter");
         assert_eq!(allocated.samples.len(), 2);
+
+        // Sorted by name, whatever order the args map yields them in.
+        let names: Vec<&str> = profile.counters.iter().map(|c| &*c.name).collect();
+        let mut sorted = names.clone();
+        sorted.sort_unstable();
+        assert_eq!(names, sorted);
+    }
+
+    #[test]
+    fn parse_power_and_thermal_counters() {
+        let json = r#"{"traceEvents":[
+            {"name":"power.rails.cpu.big","ph":"C","ts":0,"pid":1,"tid":1,"args":{"value":0}},
+            {"name":"power.rails.cpu.big","ph":"C","ts":1000,"pid":1,"tid":1,"args":{"value":500}},
+            {"name":"power.rails.cpu.big","ph":"C","ts":3000,"pid":1,"tid":1,"args":{"value":4500}},
+            {"name":"thermal_zone0","ph":"C","ts":0,"pid":1,"tid":1,"args":{"temp_mc":41500}},
+            {"name":"batt.capacity_pct","ph":"C","ts":0,"pid":1,"tid":1,"args":{"value":87}}
+        ]}"#;
+
+        let profile = parse_chrome_trace(json.as_bytes()).unwrap();
+        let counter = |prefix: &str| {
+            profile
+                .counters
+                .iter()
+                .find(|c| c.name.starts_with(prefix))
+                .expect("counter present")
+        };
+        let rail = counter("power.rails");
+        assert_eq!(rail.unit, CounterUnit::Milliwatts);
+        // 500µWs over 1ms, then 4000µWs over 2ms.
+        let power: Vec<(f64, f64)> = rail.samples.iter().map(|s| (s.ts, s.value)).collect();
+        assert_eq!(power, [(0.0, 500.0), (1000.0, 2000.0)]);
+        let thermal = counter("thermal");
+        assert_eq!(thermal.unit, CounterUnit::Celsius);
+        assert_eq!(thermal.samples[0].value, 41.5);
+        assert_eq!(counter("batt").unit, CounterUnit::BatteryPercent);
     }
 
     #[test]
@@ -1187,6 +2351,39 @@ mod tests {
         assert_eq!(profile.flow_arrows[1].to_tid, 3);
     }
 
+    #[test]
+    fn network_requests_link_to_their_initiators() {
+        let json = r#"{"traceEvents":[
+            {"name":"ResourceSendRequest","ph":"I","ts":0,"pid":1,"tid":1,"args":{"data":{"requestId":"1","url":"https://a.test/"}}},
+            {"name":"ResourceReceiveResponse","ph":"I","ts":10,"pid":1,"tid":1,"args":{"data":{"requestId":"1"}}},
+            {"name":"ResourceSendRequest","ph":"I","ts":12,"pid":1,"tid":1,"args":{"data":{"requestId":"2","url":"https://a.test/app.js","initiator":{"type":"parser","url":"https://a.test/"}}}},
+            {"name":"ResourceFinish","ph":"I","ts":20,"pid":1,"tid":2,"args":{"data":{"requestId":"2"}}},
+            {"name":"EvaluateScript","ph":"X","ts":30,"dur":20,"pid":1,"tid":1,"args":{"data":{"url":"https://a.test/app.js"}}},
+            {"name":"FunctionCall","ph":"X","ts":35,"dur":10,"pid":1,"tid":1,"args":{"data":{"url":"https://lib.test/x.js"}}},
+            {"name":"ResourceSendRequest","ph":"I","ts":40,"pid":1,"tid":1,"args":{"data":{"requestId":"3","url":"https://a.test/data.json","initiator":{"type":"script","stack":{"callFrames":[{"url":""},{"url":"https://a.test/app.js"}]}}}}},
+            {"name":"ResourceReceiveResponse","ph":"I","ts":60,"pid":1,"tid":1,"args":{"data":{"requestId":"3"}}}
+        ]}"#;
+        let profile = parse_chrome_trace(json.as_bytes()).unwrap();
+        let initiators: Vec<Option<&str>> = profile
+            .network_requests
+            .iter()
+            .map(|r| r.initiator.as_deref())
+            .collect();
+        assert_eq!(
+            initiators,
+            [None, Some("https://a.test/"), Some("https://a.test/app.js")]
+        );
+
+        let arrows: Vec<(&str, f64, u64, f64, u64)> = profile
+            .flow_arrows
+            .iter()
+            .map(|a| (a.id.as_ref(), a.from_ts, a.from_tid, a.to_ts, a.to_tid))
+            .collect();
+        // The parser-initiated script chains from the document's response;
+        // the fetch starts inside the script span that ran `app.js`.
+        assert_eq!(arrows, [("2", 10.0, 1, 20.0, 2), ("3", 40.0, 1, 60.0, 1)]);
+    }
+
     #[test]
     fn parse_object_events() {
         let json = r#"{"traceEvents":[
@@ -1,5 +1,10 @@
+use std::sync::atomic::AtomicBool;
+
+use flame_cat_protocol::{LimitsHit, SpanCategory};
 use thiserror::Error;
 
+use super::Limits;
+use super::limits::{cancelled, cut_string};
 use crate::model::{Frame, Profile, ProfileMetadata};
 
 #[derive(Debug, Error)]
@@ -16,13 +21,37 @@ pub enum CollapsedParseError {
 /// where frames are separated by `;` and the count is the last whitespace-separated token.
 ///
 /// Used by: `perf script | stackcollapse-perf.pl`, dtrace, FlameGraph tools.
+///
+/// Frames that `stackcollapse-perf.pl --kernel` suffixes with `_[k]` get
+/// the [`SpanCategory::KERNEL`] category without the suffix; the other
+/// frames of such a file are [`SpanCategory::USER`]. Frames `--inline`
+/// suffixes with `_[i]` were inlined into the frame before them and fold
+/// into its [`Frame::inlined`] chain.
 pub fn parse_collapsed(data: &[u8]) -> Result<Profile, CollapsedParseError> {
+    parse_collapsed_limited(data, &Limits::NONE, None)
+}
+
+/// [`parse_collapsed`] within `limits`: frames past the depth limit fold
+/// into the deepest one kept, names are cut as they are read, and once a
+/// sample does not fit in the event limit no later one is kept. Setting
+/// `cancel` stops reading at the next line.
+pub(crate) fn parse_collapsed_limited(
+    data: &[u8],
+    limits: &Limits,
+    cancel: Option<&AtomicBool>,
+) -> Result<Profile, CollapsedParseError> {
     let text = std::str::from_utf8(data)?;
+    let annotated = text.contains(KERNEL_SUFFIX);
     let mut frames: Vec<Frame> = Vec::new();
     let mut next_id: u64 = 0;
     let mut offset: f64 = 0.0;
+    let mut hit = LimitsHit::default();
+    let mut full = false;
 
     for line in text.lines() {
+        if cancelled(cancel) {
+            break;
+        }
         let line = line.trim();
         if line.is_empty() || line.starts_with('#') {
             continue;
@@ -49,30 +78,83 @@ pub fn parse_collapsed(data: &[u8]) -> Result<Profile, CollapsedParseError> {
         let sample_start = offset;
         let sample_end = offset + count;
 
+        let line_start = frames.len();
+        let mut strings_cut = 0;
         let mut parent_id: Option<u64> = None;
-        for (depth, name) in stack_parts.iter().enumerate() {
+        let mut depth: u32 = 0;
+        for name in &stack_parts {
             let name = name.trim();
             if name.is_empty() {
                 continue;
             }
+            if depth > limits.max_depth {
+                if !name.ends_with(INLINE_SUFFIX) {
+                    hit.too_deep += 1;
+                }
+                continue;
+            }
+            if let Some(inlined) = name.strip_suffix(INLINE_SUFFIX)
+                && parent_id.is_some()
+                && let Some(caller) = frames.last_mut()
+            {
+                caller.inlined.push(inlined.to_string());
+                continue;
+            }
+            let (name, kernel) = match strip_kernel_suffix(name) {
+                Some(name) => (name, true),
+                None => (name, false),
+            };
+            let category = match (kernel, annotated) {
+                (true, _) => Some(SpanCategory::KERNEL.to_string()),
+                (false, true) => Some(SpanCategory::USER.to_string()),
+                (false, false) => None,
+            };
+            let name = match cut_string(name, limits.max_string_len) {
+                Some(cut) => {
+                    strings_cut += 1;
+                    cut
+                }
+                None => name.to_string(),
+            };
             let id = next_id;
             next_id += 1;
 
-            let is_leaf = depth == stack_parts.len() - 1;
-
             frames.push(Frame {
                 id,
-                name: name.to_string(),
+                name,
                 start: sample_start,
                 end: sample_end,
-                depth: depth as u32,
-                category: None,
+                depth,
+                category,
                 parent: parent_id,
-                self_time: if is_leaf { count } else { 0.0 },
+                self_time: 0.0,
                 thread: None,
+                truncated: false,
+                pid: None,
+                tid: None,
+                source: None,
+                binary: None,
+                inlined: vec![],
             });
 
             parent_id = Some(id);
+            depth += 1;
+        }
+        if full || frames.len() > limits.max_events {
+            hit.events_dropped += frames.len() - line_start;
+            frames.truncate(line_start);
+            next_id = line_start as u64;
+            full = true;
+            offset = sample_end;
+            continue;
+        }
+        hit.strings_cut += strings_cut;
+        // The innermost frame, with whatever was inlined into it, is where
+        // the sample landed.
+        if parent_id.is_some()
+            && let Some(leaf) = frames.last_mut()
+        {
+            leaf.self_time = count;
         }
 
         offset = sample_end;
@@ -82,28 +164,13 @@ pub fn parse_collapsed(data: &[u8]) -> Result<Profile, CollapsedParseError> {
         return Err(CollapsedParseError::Empty);
     }
 
-    // Recompute self_time: each non-leaf frame's self_time = duration - sum(children)
-    let child_time = {
-        let mut map = std::collections::HashMap::<u64, f64>::new();
-        for f in &frames {
-            if let Some(pid) = f.parent {
-                *map.entry(pid).or_default() += f.duration();
-            }
-        }
-        map
-    };
-    for f in &mut frames {
-        let children_total = child_time.get(&f.id).copied().unwrap_or(0.0);
-        f.self_time = (f.duration() - children_total).max(0.0);
-    }
-
     let start_time = frames.iter().map(|f| f.start).fold(f64::INFINITY, f64::min);
     let end_time = frames
         .iter()
         .map(|f| f.end)
         .fold(f64::NEG_INFINITY, f64::max);
 
-    Ok(Profile::new(
+    let mut profile = Profile::new(
         ProfileMetadata {
             name: None,
             start_time: if start_time.is_finite() {
@@ -114,9 +181,24 @@ pub fn parse_collapsed(data: &[u8]) -> Result<Profile, CollapsedParseError> {
             end_time: if end_time.is_finite() { end_time } else { 0.0 },
             format: "collapsed".to_string(),
             time_domain: None,
+            value_unit: None,
+            sample_interval_us: None,
         },
         frames,
-    ))
+    );
+    profile.limits_hit = hit;
+    Ok(profile)
+}
+
+/// Suffix the FlameGraph tools mark kernel frames with.
+const KERNEL_SUFFIX: &str = "_[k]";
+
+/// Suffix the FlameGraph tools mark inlined frames with.
+const INLINE_SUFFIX: &str = "_[i]";
+
+/// `name` without its kernel annotation, if it has one.
+pub(crate) fn strip_kernel_suffix(name: &str) -> Option<&str> {
+    name.strip_suffix(KERNEL_SUFFIX)
 }
 
 #[cfg(test)]
@@ -139,6 +221,59 @@ mod tests {
         assert_eq!(bar.depth, 2);
     }
 
+    #[test]
+    fn kernel_annotations_become_categories() {
+        let input = b"main;read;sys_read_[k];vfs_read_[k] 3\nmain;work 1\n";
+        let profile = parse_collapsed(input).unwrap();
+        let frames: Vec<(&str, Option<&str>)> = profile
+            .frames
+            .iter()
+            .map(|f| (f.name.as_str(), f.category.as_deref()))
+            .collect();
+        assert_eq!(
+            frames,
+            [
+                ("main", Some("user")),
+                ("read", Some("user")),
+                ("sys_read", Some("kernel")),
+                ("vfs_read", Some("kernel")),
+                ("main", Some("user")),
+                ("work", Some("user")),
+            ]
+        );
+
+        let plain = parse_collapsed(b"main;work 1\n").unwrap();
+        assert!(plain.frames.iter().all(|f| f.category.is_none()));
+    }
+
+    #[test]
+    fn inlined_frames_fold_into_their_caller() {
+        let input = b"main;run;push_[i];grow_[i];alloc 4\nmain;run;push_[i] 2\n";
+        let profile = parse_collapsed(input).unwrap();
+        let frames: Vec<(&str, u32, Vec<&str>, f64)> = profile
+            .frames
+            .iter()
+            .map(|f| {
+                (
+                    f.name.as_str(),
+                    f.depth,
+                    f.inlined.iter().map(String::as_str).collect(),
+                    f.self_time,
+                )
+            })
+            .collect();
+        assert_eq!(
+            frames,
+            [
+                ("main", 0, vec![], 0.0),
+                ("run", 1, vec!["push", "grow"], 0.0),
+                ("alloc", 2, vec![], 4.0),
+                ("main", 0, vec![], 0.0),
+                ("run", 1, vec!["push"], 2.0),
+            ]
+        );
+    }
+
     #[test]
     fn skips_comments_and_empty_lines() {
         let input = b"# comment\n\nmain;foo 5\n";
@@ -2,6 +2,7 @@ use serde::Deserialize;
 use thiserror::Error;
 
 use crate::model::{Frame, Profile, ProfileMetadata};
+use crate::transform::{DEFAULT_GAP_FACTOR, SampleGaps};
 
 #[derive(Debug, Error)]
 pub enum CpuProfileParseError {
@@ -11,6 +12,23 @@ pub enum CpuProfileParseError {
     MissingNodes,
 }
 
+/// Tuning for [`parse_cpuprofile_with_options`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CpuProfileOptions {
+    /// Samples further apart than this many sampling intervals are not
+    /// stitched together: the thread is taken to have slept in between.
+    /// `f64::INFINITY` stitches across every gap.
+    pub gap_factor: f64,
+}
+
+impl Default for CpuProfileOptions {
+    fn default() -> Self {
+        Self {
+            gap_factor: DEFAULT_GAP_FACTOR,
+        }
+    }
+}
+
 /// V8 CPU profile node.
 #[derive(Debug, Deserialize)]
 struct CpuProfileNode {
@@ -27,6 +45,24 @@ struct CallFrame {
     function_name: String,
     #[serde(default)]
     url: Option<String>,
+    #[serde(default = "no_position", rename = "lineNumber")]
+    line_number: i64,
+    #[serde(default = "no_position", rename = "columnNumber")]
+    column_number: i64,
+}
+
+fn no_position() -> i64 {
+    -1
+}
+
+impl CallFrame {
+    fn source(&self) -> Option<String> {
+        crate::sourcemap::source_location(
+            self.url.as_deref()?,
+            self.line_number,
+            self.column_number,
+        )
+    }
 }
 
 /// V8 CPU profile top-level structure (.cpuprofile files).
@@ -63,6 +99,14 @@ fn compute_depth(
 ///
 /// Used by: Node.js `--cpu-prof`, Chrome DevTools CPU profiler, Deno.
 pub fn parse_cpuprofile(data: &[u8]) -> Result<Profile, CpuProfileParseError> {
+    parse_cpuprofile_with_options(data, &CpuProfileOptions::default())
+}
+
+/// [`parse_cpuprofile`] with explicit options.
+pub fn parse_cpuprofile_with_options(
+    data: &[u8],
+    options: &CpuProfileOptions,
+) -> Result<Profile, CpuProfileParseError> {
     let cpu_profile: CpuProfile = serde_json::from_slice(data)?;
 
     if cpu_profile.nodes.is_empty() {
@@ -84,7 +128,13 @@ pub fn parse_cpuprofile(data: &[u8]) -> Result<Profile, CpuProfileParseError> {
 
     // If we have samples + timeDeltas, reconstruct timeline from sample data.
     if !cpu_profile.samples.is_empty() && !cpu_profile.time_deltas.is_empty() {
-        return parse_from_samples(&cpu_profile, &node_map, &parent_map, &mut depth_cache);
+        return parse_from_samples(
+            &cpu_profile,
+            options,
+            &node_map,
+            &parent_map,
+            &mut depth_cache,
+        );
     }
 
     // Fallback: build frames from the node tree with synthetic timing.
@@ -138,6 +188,12 @@ pub fn parse_cpuprofile(data: &[u8]) -> Result<Profile, CpuProfileParseError> {
             parent: parent_frame_id,
             self_time: 0.0,
             thread: None,
+            truncated: false,
+            pid: None,
+            tid: None,
+            source: node.call_frame.source(),
+            binary: None,
+            inlined: vec![],
         });
 
         // Leaf nodes get 1.0 unit of time.
@@ -175,8 +231,6 @@ pub fn parse_cpuprofile(data: &[u8]) -> Result<Profile, CpuProfileParseError> {
         }
     }
 
-    compute_self_times(&mut frames);
-
     Ok(Profile::new(
         ProfileMetadata {
             name: None,
@@ -184,6 +238,8 @@ pub fn parse_cpuprofile(data: &[u8]) -> Result<Profile, CpuProfileParseError> {
             end_time: cpu_profile.end_time,
             format: "cpuprofile".to_string(),
             time_domain: None,
+            value_unit: None,
+            sample_interval_us: None,
         },
         frames,
     ))
@@ -192,6 +248,7 @@ pub fn parse_cpuprofile(data: &[u8]) -> Result<Profile, CpuProfileParseError> {
 /// Reconstruct timeline from V8 sample data.
 fn parse_from_samples(
     cpu_profile: &CpuProfile,
+    options: &CpuProfileOptions,
     node_map: &std::collections::HashMap<u64, &CpuProfileNode>,
     parent_map: &std::collections::HashMap<u64, u64>,
     depth_cache: &mut std::collections::HashMap<u64, u32>,
@@ -206,6 +263,7 @@ fn parse_from_samples(
         t += delta;
         timestamps.push(t);
     }
+    let gaps = SampleGaps::measure(&timestamps, options.gap_factor);
 
     // For each sample, walk up the stack to build the full call stack.
     // Merge adjacent identical stacks into continuous frames.
@@ -290,6 +348,12 @@ fn parse_from_samples(
                 parent: parent_frame_id,
                 self_time: 0.0,
                 thread: None,
+                truncated: false,
+                pid: None,
+                tid: None,
+                source: node.call_frame.source(),
+                binary: None,
+                inlined: vec![],
             });
 
             active_stacks.push(ActiveFrame {
@@ -297,6 +361,15 @@ fn parse_from_samples(
                 node_id: nid,
             });
         }
+
+        // Across a long gap, end the whole stack one interval after the
+        // sample instead of stretching it to the next one.
+        let sample_end = gaps.map_or(next_time, |g| g.sample_end(sample_time, next_time));
+        if sample_end < next_time {
+            for af in active_stacks.drain(..) {
+                frames[af.frame_idx].end = sample_end;
+            }
+        }
     }
 
     // Close remaining active frames.
@@ -304,8 +377,6 @@ fn parse_from_samples(
         frames[af.frame_idx].end = cpu_profile.end_time;
     }
 
-    compute_self_times(&mut frames);
-
     Ok(Profile::new(
         ProfileMetadata {
             name: None,
@@ -313,27 +384,13 @@ fn parse_from_samples(
             end_time: cpu_profile.end_time,
             format: "cpuprofile".to_string(),
             time_domain: None,
+            value_unit: None,
+            sample_interval_us: gaps.map(|g| g.interval),
         },
         frames,
     ))
 }
 
-fn compute_self_times(frames: &mut [Frame]) {
-    let child_time = {
-        let mut map = std::collections::HashMap::<u64, f64>::new();
-        for f in frames.iter() {
-            if let Some(pid) = f.parent {
-                *map.entry(pid).or_default() += f.duration();
-            }
-        }
-        map
-    };
-    for f in frames.iter_mut() {
-        let children_total = child_time.get(&f.id).copied().unwrap_or(0.0);
-        f.self_time = (f.duration() - children_total).max(0.0);
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -388,6 +445,38 @@ mod tests {
         assert!(work.is_some());
     }
 
+    #[test]
+    fn long_gaps_split_stitched_spans() {
+        let json = r#"{
+            "nodes": [
+                {"id":1,"callFrame":{"functionName":"(root)"},"children":[2]},
+                {"id":2,"callFrame":{"functionName":"work"},"children":[]}
+            ],
+            "startTime": 0,
+            "endTime": 1010,
+            "samples": [2, 2, 2, 2],
+            "timeDeltas": [0, 10, 10, 980]
+        }"#;
+
+        let work_spans = |options: &CpuProfileOptions| -> Vec<(f64, f64)> {
+            parse_cpuprofile_with_options(json.as_bytes(), options)
+                .unwrap()
+                .frames
+                .iter()
+                .filter(|f| f.name == "work")
+                .map(|f| (f.start, f.end))
+                .collect()
+        };
+        assert_eq!(
+            work_spans(&CpuProfileOptions::default()),
+            [(0.0, 30.0), (1000.0, 1010.0)]
+        );
+        let stitched = CpuProfileOptions {
+            gap_factor: f64::INFINITY,
+        };
+        assert_eq!(work_spans(&stitched), [(0.0, 1010.0)]);
+    }
+
     #[test]
     fn empty_nodes_errors() {
         let json = r#"{"nodes":[],"startTime":0,"endTime":0,"samples":[],"timeDeltas":[]}"#;
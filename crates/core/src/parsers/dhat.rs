@@ -0,0 +1,146 @@
+use flame_cat_protocol::ValueUnit;
+use serde::Deserialize;
+use thiserror::Error;
+
+use super::massif::frame_name;
+use crate::model::{Frame, Profile, ProfileMetadata};
+
+#[derive(Debug, Error)]
+pub enum DhatParseError {
+    #[error("invalid JSON: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("no allocation points found")]
+    Empty,
+}
+
+/// A `dhat.out.<pid>` file, as far as stacks go.
+#[derive(Deserialize)]
+struct DhatFile {
+    /// `heap`, `copy` or `ad-hoc`.
+    #[serde(default)]
+    mode: String,
+    cmd: Option<String>,
+    /// Program points: one allocation (or copy) stack each.
+    pps: Vec<ProgramPoint>,
+    /// Frame table the program points' stacks index into.
+    ftbl: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct ProgramPoint {
+    /// Total bytes (or ad-hoc units) over the whole run.
+    tb: f64,
+    /// Frames, innermost first.
+    #[serde(default)]
+    fs: Vec<usize>,
+}
+
+/// Parse Valgrind DHAT JSON output into a byte-weighted `Profile`.
+///
+/// Each program point becomes a stack weighted by the bytes allocated
+/// there over the whole run (bytes copied in `--mode=copy`, units in
+/// `--mode=ad-hoc`). DHAT keeps no heap size over time, so unlike massif
+/// there are no counter tracks.
+pub fn parse_dhat(data: &[u8]) -> Result<Profile, DhatParseError> {
+    let file: DhatFile = serde_json::from_slice(data)?;
+
+    let mut frames: Vec<Frame> = Vec::new();
+    let mut offset = 0.0;
+    for pp in file.pps.iter().filter(|pp| pp.tb > 0.0) {
+        let mut parent = None;
+        let names = pp.fs.iter().rev().filter_map(|&f| file.ftbl.get(f));
+        for (depth, name) in names.enumerate() {
+            let id = frames.len() as u64;
+            frames.push(Frame {
+                id,
+                name: frame_name(name).to_string(),
+                start: offset,
+                end: offset + pp.tb,
+                depth: depth as u32,
+                category: None,
+                parent,
+                self_time: 0.0,
+                thread: None,
+                truncated: false,
+                pid: None,
+                tid: None,
+                source: None,
+                binary: None,
+                inlined: vec![],
+            });
+            parent = Some(id);
+        }
+        if let Some(leaf) = parent.and_then(|id| frames.get_mut(id as usize)) {
+            leaf.self_time = pp.tb;
+            offset += pp.tb;
+        }
+    }
+    if frames.is_empty() {
+        return Err(DhatParseError::Empty);
+    }
+
+    Ok(Profile::new(
+        ProfileMetadata {
+            name: file.cmd,
+            start_time: 0.0,
+            end_time: offset,
+            format: "dhat".to_string(),
+            time_domain: None,
+            value_unit: Some(if file.mode == "ad-hoc" {
+                ValueUnit::Weight
+            } else {
+                ValueUnit::Bytes
+            }),
+            sample_interval_us: None,
+        },
+        frames,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn program_points_become_allocation_stacks() {
+        let json = r#"{
+            "dhatFileVersion": 2, "mode": "heap", "verb": "Allocated",
+            "cmd": "./app", "pid": 42, "te": 1000, "tg": 800,
+            "pps": [
+                {"tb": 1024, "tbk": 2, "mb": 1024, "fs": [1, 2, 3]},
+                {"tb": 0, "tbk": 0, "fs": [1, 3]},
+                {"tb": 64, "tbk": 1, "mb": 64, "fs": [1, 3]}
+            ],
+            "ftbl": [
+                "[root]",
+                "0x483DD99: malloc (in /usr/libexec/valgrind/vgpreload_dhat-amd64-linux.so)",
+                "0x10915E: parse(char const*) (parse.c:12)",
+                "0x1091A0: main (main.c:4)"
+            ]
+        }"#;
+        let profile = parse_dhat(json.as_bytes()).unwrap();
+        assert_eq!(profile.metadata.name.as_deref(), Some("./app"));
+        assert_eq!(profile.metadata.value_unit, Some(ValueUnit::Bytes));
+        assert_eq!(profile.metadata.end_time, 1088.0);
+
+        let frames: Vec<_> = profile
+            .frames
+            .iter()
+            .map(|f| (f.name.as_str(), f.depth, f.start, f.end, f.self_time))
+            .collect();
+        assert_eq!(
+            frames,
+            [
+                ("main", 0, 0.0, 1024.0, 0.0),
+                ("parse(char const*)", 1, 0.0, 1024.0, 0.0),
+                ("malloc", 2, 0.0, 1024.0, 1024.0),
+                ("main", 0, 1024.0, 1088.0, 0.0),
+                ("malloc", 1, 1024.0, 1088.0, 64.0),
+            ]
+        );
+        assert_eq!(
+            crate::parsers::detect_formats(json.as_bytes())[0].format,
+            "dhat"
+        );
+    }
+}
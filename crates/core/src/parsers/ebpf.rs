@@ -1,5 +1,7 @@
+use flame_cat_protocol::SpanCategory;
 use thiserror::Error;
 
+use super::collapsed::strip_kernel_suffix;
 use crate::model::{Frame, Profile, ProfileMetadata};
 
 #[derive(Debug, Error)]
@@ -33,6 +35,9 @@ pub enum EbpfParseError {
 /// 3. **Collapsed stacks** (delegated to collapsed parser, detected at higher level).
 ///
 /// This parser auto-detects between bpftrace block format and perf script format.
+/// Frames are categorized as [`SpanCategory::KERNEL`] or [`SpanCategory::USER`]:
+/// kernel frames are those in a `[kernel.*]` module, at a kernel-half
+/// address (`ffff…`, 16 digits) or suffixed `_[k]`.
 pub fn parse_ebpf(data: &[u8]) -> Result<Profile, EbpfParseError> {
     let text = std::str::from_utf8(data)?;
 
@@ -107,9 +112,8 @@ fn parse_bpftrace(text: &str) -> Result<Profile, EbpfParseError> {
             let sample_end = offset + count;
             let mut parent_id: Option<u64> = None;
 
-            for (depth, name) in stack_lines.iter().enumerate() {
-                // Strip address prefix if present (e.g., "ffffffff810a func_name")
-                let clean_name = strip_address(name);
+            for (depth, line) in stack_lines.iter().enumerate() {
+                let (clean_name, kernel) = parse_bpftrace_frame(line);
                 let is_leaf = depth == stack_lines.len() - 1;
 
                 let id = next_id;
@@ -121,10 +125,16 @@ fn parse_bpftrace(text: &str) -> Result<Profile, EbpfParseError> {
                     start: offset,
                     end: sample_end,
                     depth: depth as u32,
-                    category: Some("ebpf".to_string()),
+                    category: category(kernel),
                     parent: parent_id,
                     self_time: if is_leaf { count } else { 0.0 },
                     thread: None,
+                    truncated: false,
+                    pid: None,
+                    tid: None,
+                    source: None,
+                    binary: None,
+                    inlined: vec![],
                 });
 
                 parent_id = Some(id);
@@ -140,7 +150,6 @@ fn parse_bpftrace(text: &str) -> Result<Profile, EbpfParseError> {
         return Err(EbpfParseError::Empty);
     }
 
-    compute_self_times(&mut frames);
     build_profile(frames, "ebpf")
 }
 
@@ -158,7 +167,7 @@ fn parse_perf_script(text: &str) -> Result<Profile, EbpfParseError> {
     let mut next_id: u64 = 0;
     let mut offset: f64 = 0.0;
 
-    let mut current_stack: Vec<String> = Vec::new();
+    let mut current_stack: Vec<(String, bool)> = Vec::new();
 
     for line in text.lines() {
         let trimmed = line.trim();
@@ -171,7 +180,7 @@ fn parse_perf_script(text: &str) -> Result<Profile, EbpfParseError> {
                 let sample_end = offset + 1.0;
                 let mut parent_id: Option<u64> = None;
 
-                for (depth, name) in current_stack.iter().enumerate() {
+                for (depth, (name, kernel)) in current_stack.iter().enumerate() {
                     let is_leaf = depth == current_stack.len() - 1;
                     let id = next_id;
                     next_id += 1;
@@ -182,10 +191,16 @@ fn parse_perf_script(text: &str) -> Result<Profile, EbpfParseError> {
                         start: offset,
                         end: sample_end,
                         depth: depth as u32,
-                        category: Some("perf".to_string()),
+                        category: category(*kernel),
                         parent: parent_id,
                         self_time: if is_leaf { 1.0 } else { 0.0 },
                         thread: None,
+                        truncated: false,
+                        pid: None,
+                        tid: None,
+                        source: None,
+                        binary: None,
+                        inlined: vec![],
                     });
 
                     parent_id = Some(id);
@@ -199,9 +214,9 @@ fn parse_perf_script(text: &str) -> Result<Profile, EbpfParseError> {
 
         // Stack frame line: starts with whitespace + hex address
         if line.starts_with('\t') || line.starts_with("        ") || line.starts_with("    ") {
-            let name = parse_perf_frame(trimmed);
+            let (name, kernel) = parse_perf_frame(trimmed);
             if !name.is_empty() {
-                current_stack.push(name);
+                current_stack.push((name, kernel));
             }
         }
         // Otherwise it's a header line (process name, pid, etc.) — skip.
@@ -213,7 +228,7 @@ fn parse_perf_script(text: &str) -> Result<Profile, EbpfParseError> {
         let sample_end = offset + 1.0;
         let mut parent_id: Option<u64> = None;
 
-        for (depth, name) in current_stack.iter().enumerate() {
+        for (depth, (name, kernel)) in current_stack.iter().enumerate() {
             let is_leaf = depth == current_stack.len() - 1;
             let id = next_id;
             next_id += 1;
@@ -224,10 +239,16 @@ fn parse_perf_script(text: &str) -> Result<Profile, EbpfParseError> {
                 start: offset,
                 end: sample_end,
                 depth: depth as u32,
-                category: Some("perf".to_string()),
+                category: category(*kernel),
                 parent: parent_id,
                 self_time: if is_leaf { 1.0 } else { 0.0 },
                 thread: None,
+                truncated: false,
+                pid: None,
+                tid: None,
+                source: None,
+                binary: None,
+                inlined: vec![],
             });
 
             parent_id = Some(id);
@@ -238,35 +259,71 @@ fn parse_perf_script(text: &str) -> Result<Profile, EbpfParseError> {
         return Err(EbpfParseError::Empty);
     }
 
-    compute_self_times(&mut frames);
     build_profile(frames, "ebpf-perf")
 }
 
 /// Parse a perf script frame line like `ffffffff810a func_name+0x10 (/path/module)`
-fn parse_perf_frame(line: &str) -> String {
+/// into the function name and whether it ran in the kernel.
+fn parse_perf_frame(line: &str) -> (String, bool) {
     let parts: Vec<&str> = line.splitn(2, ' ').collect();
     if parts.len() < 2 {
-        return strip_address(line);
+        return (strip_address(line), false);
     }
 
     // Second part is "func_name+0x10 (/path/module)" — take function name
     let func_part = parts[1].trim();
 
     // Remove module in parens at the end
-    let func_part = if let Some(paren_pos) = func_part.rfind('(') {
-        func_part[..paren_pos].trim()
+    let (func_part, module) = if let Some(paren_pos) = func_part.rfind('(') {
+        (func_part[..paren_pos].trim(), &func_part[paren_pos + 1..])
     } else {
-        func_part
+        (func_part, "")
     };
+    let kernel = module.starts_with("[kernel") || is_kernel_address(parts[0]);
+
+    // perf could not symbolize the frame: keep the address for `symbolicate`.
+    if func_part == "[unknown]" && parts[0].chars().all(|c| c.is_ascii_hexdigit()) {
+        return (format!("0x{}", parts[0]), kernel);
+    }
 
     // Remove +0xOFFSET suffix
-    if let Some(plus_pos) = func_part.rfind('+') {
+    let name = if let Some(plus_pos) = func_part.rfind('+') {
         func_part[..plus_pos].to_string()
     } else {
         func_part.to_string()
+    };
+    (name, kernel)
+}
+
+/// Parse a bpftrace stack line, optionally address-prefixed, into the frame
+/// name and whether it ran in the kernel.
+fn parse_bpftrace_frame(line: &str) -> (String, bool) {
+    let kernel = line
+        .trim()
+        .split_once(' ')
+        .is_some_and(|(addr, _)| is_kernel_address(addr));
+    let name = strip_address(line);
+    match strip_kernel_suffix(&name) {
+        Some(name) => (name.to_string(), true),
+        None => (name, kernel),
     }
 }
 
+/// Whether `addr` is a full 64-bit address in the kernel half, as on x86-64
+/// and arm64 Linux.
+fn is_kernel_address(addr: &str) -> bool {
+    addr.len() == 16 && addr.starts_with("ffff") && addr.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+pub(super) fn category(kernel: bool) -> Option<String> {
+    let name = if kernel {
+        SpanCategory::KERNEL
+    } else {
+        SpanCategory::USER
+    };
+    Some(name.to_string())
+}
+
 /// Strip leading hex address from a frame name.
 fn strip_address(name: &str) -> String {
     let trimmed = name.trim();
@@ -280,22 +337,6 @@ fn strip_address(name: &str) -> String {
     trimmed.to_string()
 }
 
-fn compute_self_times(frames: &mut [Frame]) {
-    let child_time = {
-        let mut map = std::collections::HashMap::<u64, f64>::new();
-        for f in frames.iter() {
-            if let Some(pid) = f.parent {
-                *map.entry(pid).or_default() += f.duration();
-            }
-        }
-        map
-    };
-    for f in frames.iter_mut() {
-        let children_total = child_time.get(&f.id).copied().unwrap_or(0.0);
-        f.self_time = (f.duration() - children_total).max(0.0);
-    }
-}
-
 fn build_profile(frames: Vec<Frame>, format: &str) -> Result<Profile, EbpfParseError> {
     let start_time = frames.iter().map(|f| f.start).fold(f64::INFINITY, f64::min);
     let end_time = frames
@@ -314,6 +355,8 @@ fn build_profile(frames: Vec<Frame>, format: &str) -> Result<Profile, EbpfParseE
             end_time: if end_time.is_finite() { end_time } else { 0.0 },
             format: format.to_string(),
             time_domain: None,
+            value_unit: None,
+            sample_interval_us: None,
         },
         frames,
     ))
@@ -349,6 +392,38 @@ mod tests {
         assert_eq!(profile.frames[1].name, "func_a");
     }
 
+    #[test]
+    fn kernel_frames_are_told_apart() {
+        let input = b"process 1234 12345.678: 1 cycles:\n\tffffffff81a0b3a0 do_syscall_64+0x40 ([kernel.kallsyms])\n\tffffffff810b read+0x20 (/lib/libc.so.6)\n\n";
+        let profile = parse_ebpf(input).unwrap();
+        let frames: Vec<(&str, Option<&str>)> = profile
+            .frames
+            .iter()
+            .map(|f| (f.name.as_str(), f.category.as_deref()))
+            .collect();
+        assert_eq!(
+            frames,
+            [("read", Some("user")), ("do_syscall_64", Some("kernel"))]
+        );
+
+        let input = b"@[\n    ffffffff81a0b3a0 vfs_read+0x10\n    tcp_sendmsg_[k]\n    7f3a1b2c4d5e main\n]: 2\n";
+        let profile = parse_ebpf(input).unwrap();
+        let kernel: Vec<bool> = profile
+            .frames
+            .iter()
+            .map(|f| f.category.as_deref() == Some(SpanCategory::KERNEL))
+            .collect();
+        assert_eq!(kernel, [false, true, true]);
+        assert_eq!(profile.frames[1].name, "tcp_sendmsg");
+    }
+
+    #[test]
+    fn unknown_perf_frames_keep_their_address() {
+        let input = b"process 1234 12345.678: 1 cycles:\n\t7f3a1b2c [unknown] (/tmp/perf-1234.map)\n\tffffffff810b main+0x20 (/bin/app)\n\n";
+        let profile = parse_ebpf(input).unwrap();
+        assert_eq!(profile.frames[1].name, "0x7f3a1b2c");
+    }
+
     #[test]
     fn empty_input_errors() {
         assert!(parse_ebpf(b"").is_err());
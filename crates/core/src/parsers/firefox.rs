@@ -18,6 +18,9 @@ struct GeckoProfile {
     threads: Vec<GeckoThread>,
     #[serde(default)]
     meta: Option<GeckoMeta>,
+    /// Content/GPU/etc. subprocess profiles, each with its own `meta`.
+    #[serde(default)]
+    processes: Vec<GeckoProfile>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -34,6 +37,15 @@ struct GeckoMeta {
 struct GeckoThread {
     #[serde(default)]
     name: Option<String>,
+    /// Numeric in raw profiles, a string in processed ones.
+    #[serde(default)]
+    pid: Option<serde_json::Value>,
+    #[serde(default)]
+    tid: Option<serde_json::Value>,
+    #[serde(default, rename = "processName")]
+    process_name: Option<String>,
+    #[serde(default, rename = "processType")]
+    process_type: Option<String>,
     #[serde(rename = "stackTable")]
     stack_table: Option<GeckoStackTable>,
     #[serde(rename = "frameTable")]
@@ -69,36 +81,136 @@ struct GeckoSamples {
 
 /// Parse a Firefox/Gecko profiler JSON into a `Profile`.
 ///
+/// Threads of nested subprocess profiles (`processes[]`) are included as
+/// their own thread groups, labelled with their process.
+///
 /// Used by: Firefox DevTools profiler, `profiler.firefox.com`.
 pub fn parse_firefox(data: &[u8]) -> Result<Profile, FirefoxParseError> {
     let gecko: GeckoProfile = serde_json::from_slice(data)?;
 
-    if gecko.threads.is_empty() {
+    let mut builder = Builder::default();
+    builder.add_process(&gecko, 0.0, 1.0);
+    let Builder {
+        frames: all_frames,
+        process_names,
+        thread_count,
+        ..
+    } = builder;
+    if thread_count == 0 {
         return Err(FirefoxParseError::NoThreads);
     }
 
-    let mut all_frames: Vec<Frame> = Vec::new();
-    let mut next_id: u64 = 0;
+    let start_time = all_frames
+        .iter()
+        .map(|f| f.start)
+        .fold(f64::INFINITY, f64::min);
+    let end_time = all_frames
+        .iter()
+        .map(|f| f.end)
+        .fold(f64::NEG_INFINITY, f64::max);
+
+    let mut profile = Profile::new(
+        ProfileMetadata {
+            name: gecko.meta.as_ref().and_then(|m| m.product.clone()),
+            start_time: if start_time.is_finite() {
+                start_time
+            } else {
+                0.0
+            },
+            end_time: if end_time.is_finite() { end_time } else { 0.0 },
+            format: "firefox".to_string(),
+            time_domain: None,
+            value_unit: None,
+            sample_interval_us: None,
+        },
+        all_frames,
+    );
+    profile.process_names = process_names;
+    Ok(profile)
+}
+
+/// Read a pid/tid that may be encoded as a number or a numeric string.
+fn id_value(value: Option<&serde_json::Value>) -> Option<u64> {
+    match value? {
+        serde_json::Value::Number(n) => n.as_u64(),
+        serde_json::Value::String(s) => s.parse().ok(),
+        _ => None,
+    }
+}
+
+/// Accumulates frames across the parent process and its subprocesses.
+#[derive(Default)]
+struct Builder {
+    frames: Vec<Frame>,
+    next_id: u64,
+    process_names: std::collections::BTreeMap<u64, String>,
+    thread_count: usize,
+    /// Stand-in pids for processes whose threads carry none, numbered from
+    /// the top of the range so they cannot collide with real ones.
+    next_synthetic_pid: u64,
+}
+
+impl Builder {
+    /// Add every thread of `gecko` and, recursively, of its subprocesses.
+    ///
+    /// Sample times are relative to each process's own `meta.startTime`,
+    /// so each process is shifted by its own start; processes without one
+    /// inherit their parent's start and interval.
+    fn add_process(&mut self, gecko: &GeckoProfile, parent_start: f64, parent_interval: f64) {
+        let profile_start = gecko
+            .meta
+            .as_ref()
+            .and_then(|m| m.start_time)
+            .unwrap_or(parent_start);
+        let interval = gecko
+            .meta
+            .as_ref()
+            .and_then(|m| m.interval)
+            .unwrap_or(parent_interval);
+
+        let synthetic_index = self.next_synthetic_pid;
+        self.next_synthetic_pid += 1;
+        let fallback_pid = u64::MAX - synthetic_index;
+
+        for thread in &gecko.threads {
+            self.thread_count += 1;
+            let pid = id_value(thread.pid.as_ref()).unwrap_or(fallback_pid);
+            let label = thread
+                .process_name
+                .clone()
+                .or_else(|| thread.process_type.clone())
+                .or_else(|| {
+                    (pid == fallback_pid).then(|| match synthetic_index {
+                        0 => "Parent Process".to_string(),
+                        n => format!("Process {n}"),
+                    })
+                });
+            if let Some(label) = label {
+                self.process_names.entry(pid).or_insert(label);
+            }
+            self.add_thread(thread, profile_start, interval, pid);
+        }
+
+        for sub in &gecko.processes {
+            self.add_process(sub, profile_start, interval);
+        }
+    }
 
-    let profile_start = gecko
-        .meta
-        .as_ref()
-        .and_then(|m| m.start_time)
-        .unwrap_or(0.0);
-    let interval = gecko.meta.as_ref().and_then(|m| m.interval).unwrap_or(1.0);
+    fn add_thread(&mut self, thread: &GeckoThread, profile_start: f64, interval: f64, pid: u64) {
+        let all_frames = &mut self.frames;
+        let tid = id_value(thread.tid.as_ref());
 
-    for thread in &gecko.threads {
         let Some(stack_table) = &thread.stack_table else {
-            continue;
+            return;
         };
         let Some(frame_table) = &thread.frame_table else {
-            continue;
+            return;
         };
         let Some(string_table) = &thread.string_table else {
-            continue;
+            return;
         };
         let Some(samples) = &thread.samples else {
-            continue;
+            return;
         };
 
         // Resolve frame name: funcTable.name -> stringTable, or frameTable.func -> stringTable.
@@ -185,8 +297,8 @@ pub fn parse_firefox(data: &[u8]) -> Result<Profile, FirefoxParseError> {
                     None
                 };
 
-                let id = next_id;
-                next_id += 1;
+                let id = self.next_id;
+                self.next_id += 1;
                 let fidx = all_frames.len();
 
                 all_frames.push(Frame {
@@ -198,7 +310,13 @@ pub fn parse_firefox(data: &[u8]) -> Result<Profile, FirefoxParseError> {
                     category: thread.name.clone(),
                     parent: parent_id,
                     self_time: 0.0,
-                    thread: None,
+                    thread: thread.name.clone(),
+                    truncated: false,
+                    pid: Some(pid),
+                    tid,
+                    source: None,
+                    binary: None,
+                    inlined: vec![],
                 });
 
                 active_stacks.push(ActiveFrame {
@@ -216,45 +334,6 @@ pub fn parse_firefox(data: &[u8]) -> Result<Profile, FirefoxParseError> {
             }
         }
     }
-
-    // Compute self times.
-    let child_time = {
-        let mut map = std::collections::HashMap::<u64, f64>::new();
-        for f in &all_frames {
-            if let Some(pid) = f.parent {
-                *map.entry(pid).or_default() += f.duration();
-            }
-        }
-        map
-    };
-    for f in &mut all_frames {
-        let children_total = child_time.get(&f.id).copied().unwrap_or(0.0);
-        f.self_time = (f.duration() - children_total).max(0.0);
-    }
-
-    let start_time = all_frames
-        .iter()
-        .map(|f| f.start)
-        .fold(f64::INFINITY, f64::min);
-    let end_time = all_frames
-        .iter()
-        .map(|f| f.end)
-        .fold(f64::NEG_INFINITY, f64::max);
-
-    Ok(Profile::new(
-        ProfileMetadata {
-            name: gecko.meta.as_ref().and_then(|m| m.product.clone()),
-            start_time: if start_time.is_finite() {
-                start_time
-            } else {
-                0.0
-            },
-            end_time: if end_time.is_finite() { end_time } else { 0.0 },
-            format: "firefox".to_string(),
-            time_domain: None,
-        },
-        all_frames,
-    ))
 }
 
 #[cfg(test)]
@@ -302,6 +381,52 @@ mod tests {
         assert!(has_work);
     }
 
+    #[test]
+    fn subprocess_threads_are_kept_apart() {
+        let thread = |extra: &str| {
+            format!(
+                r#"{{
+                    "name": "GeckoMain", {extra}
+                    "stackTable": {{"frame": [0], "prefix": [null]}},
+                    "frameTable": {{"func": [0]}},
+                    "stringTable": ["main"],
+                    "samples": {{"stack": [0, 0], "time": [0.0, 1.0]}}
+                }}"#
+            )
+        };
+        let json = format!(
+            r#"{{
+                "meta": {{"interval": 1.0, "startTime": 100.0}},
+                "threads": [{}],
+                "processes": [{{
+                    "meta": {{"interval": 1.0, "startTime": 150.0}},
+                    "threads": [{}]
+                }}]
+            }}"#,
+            thread(""),
+            thread(r#""pid": "42", "tid": 7, "processName": "Web Content","#),
+        );
+
+        let profile = parse_firefox(json.as_bytes()).unwrap();
+        let content: Vec<_> = profile
+            .frames
+            .iter()
+            .filter(|f| f.pid == Some(42))
+            .collect();
+        assert_eq!(content.len(), 1);
+        // Subprocess samples are shifted by the subprocess's own start time.
+        assert_eq!(content[0].start, 150.0);
+        assert_eq!(content[0].tid, Some(7));
+
+        let visual = profile.into_visual_profile();
+        let mut names: Vec<&str> = visual.threads.iter().map(|t| &*t.name).collect();
+        names.sort_unstable();
+        assert_eq!(
+            names,
+            ["Parent Process / GeckoMain", "Web Content / GeckoMain"]
+        );
+    }
+
     #[test]
     fn no_threads_errors() {
         let json = r#"{"threads":[]}"#;
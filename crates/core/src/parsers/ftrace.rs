@@ -0,0 +1,373 @@
+use std::collections::HashMap;
+
+use flame_cat_protocol::FlowArrow;
+use thiserror::Error;
+
+use super::systrace::micros;
+use crate::model::{Frame, Profile, ProfileMetadata};
+
+#[derive(Debug, Error)]
+pub enum FtraceParseError {
+    #[error("invalid UTF-8: {0}")]
+    Utf8(#[from] std::str::Utf8Error),
+    #[error("no sched_switch events found")]
+    Empty,
+}
+
+/// Returns `true` if `text` is ftrace text (`trace`/`trace_pipe` or
+/// `trace-cmd report`) with scheduler events.
+pub fn is_ftrace(text: &str) -> bool {
+    let mut head = text.len().min(64 * 1024);
+    while !text.is_char_boundary(head) {
+        head -= 1;
+    }
+    text[..head]
+        .lines()
+        .filter_map(parse_line)
+        .any(|line| line.sched().is_some())
+}
+
+/// One event line of an ftrace buffer.
+#[derive(Debug, PartialEq)]
+struct Line<'a> {
+    cpu: u32,
+    /// Microseconds since boot.
+    ts: f64,
+    event: &'a str,
+    payload: &'a str,
+}
+
+/// A task as scheduler events name it.
+#[derive(Debug, PartialEq)]
+struct Task<'a> {
+    comm: &'a str,
+    pid: u64,
+}
+
+#[derive(Debug, PartialEq)]
+enum Sched<'a> {
+    /// `prev` leaves the CPU for `next`; pid 0 is the idle task.
+    Switch { prev: Task<'a>, next: Task<'a> },
+    /// `task` becomes runnable.
+    Wakeup(Task<'a>),
+}
+
+/// Parse a line like
+/// `bash-1234  ( 1234) [001] d..2  1234.567890: sched_switch: prev_comm=…`,
+/// where the `( tgid)` and flags columns are optional. Comments and lines
+/// without an event yield `None`.
+fn parse_line(line: &str) -> Option<Line<'_>> {
+    if line.trim_start().starts_with('#') {
+        return None;
+    }
+    // The CPU column is the first `[nnn]` followed by the timestamp.
+    line.match_indices('[').find_map(|(open, _)| {
+        let (cpu, rest) = line[open + 1..].split_once(']')?;
+        if cpu.is_empty() || !cpu.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        let (columns, body) = rest.split_once(": ")?;
+        let mut columns = columns.split_whitespace();
+        let ts = micros(columns.next_back()?)?;
+        // At most a flags column sits between CPU and timestamp.
+        if columns.count() > 1 {
+            return None;
+        }
+        let (event, payload) = body.split_once(':')?;
+        Some(Line {
+            cpu: cpu.parse().ok()?,
+            ts,
+            event: event.trim(),
+            payload: payload.trim(),
+        })
+    })
+}
+
+impl<'a> Line<'a> {
+    /// The scheduler event of the line, in raw ftrace (`prev_comm=bash
+    /// prev_pid=1234 …`) or `trace-cmd report` (`bash:1234 [120] S ==> …`)
+    /// form.
+    fn sched(&self) -> Option<Sched<'a>> {
+        let payload = self.payload;
+        match self.event {
+            "sched_switch" => {
+                let (prev, next) = if payload.contains("prev_comm=") {
+                    (
+                        field_task(payload, "prev_comm", "prev_pid")?,
+                        field_task(payload, "next_comm", "next_pid")?,
+                    )
+                } else {
+                    let (prev, next) = payload.split_once(" ==> ")?;
+                    // `comm:pid [prio] state`
+                    let (prev, _state) = prev.trim().rsplit_once(' ')?;
+                    (report_task(prev)?, report_task(next)?)
+                };
+                Some(Sched::Switch { prev, next })
+            }
+            "sched_wakeup" | "sched_wakeup_new" => {
+                Some(Sched::Wakeup(if payload.contains("comm=") {
+                    field_task(payload, "comm", "pid")?
+                } else {
+                    report_task(payload)?
+                }))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// The task named by `comm_key=… pid_key=…` fields.
+fn field_task<'a>(payload: &'a str, comm_key: &str, pid_key: &str) -> Option<Task<'a>> {
+    Some(Task {
+        comm: field(payload, comm_key)?,
+        pid: field(payload, pid_key)?.parse().ok()?,
+    })
+}
+
+/// The value of `key=value` in `payload`. Values run up to the next
+/// ` key=` or ` ==>`, since a comm may contain spaces.
+fn field<'a>(payload: &'a str, key: &str) -> Option<&'a str> {
+    let (at, _) = payload.match_indices(key).find(|&(i, _)| {
+        (i == 0 || payload[..i].ends_with(' ')) && payload[i + key.len()..].starts_with('=')
+    })?;
+    let value = &payload[at + key.len() + 1..];
+    let end = value
+        .match_indices(' ')
+        .map(|(i, _)| i)
+        .find(|&i| {
+            let next = &value[i + 1..];
+            next.starts_with("==>")
+                || next.split_once('=').is_some_and(|(key, _)| {
+                    !key.is_empty() && key.bytes().all(|b| b.is_ascii_lowercase() || b == b'_')
+                })
+        })
+        .unwrap_or(value.len());
+    Some(&value[..end])
+}
+
+/// The task of a `trace-cmd report` `comm:pid [prio] …` column.
+fn report_task(text: &str) -> Option<Task<'_>> {
+    let task = text
+        .trim()
+        .split_once(" [")
+        .map_or(text.trim(), |(task, _)| task);
+    let (comm, pid) = task.rsplit_once(':')?;
+    Some(Task {
+        comm,
+        pid: pid.parse().ok()?,
+    })
+}
+
+/// Parse kernel ftrace text — the `trace` or `trace_pipe` files of tracefs,
+/// or `trace-cmd report` output — into a `Profile` of per-CPU lanes.
+///
+/// Every `sched_switch` ends the slice of the task leaving the CPU and
+/// starts one for the task coming on; the idle task gets none. Slices are
+/// named `comm pid` and sit on a `CPU n` lane, recorded as pid 0 with the
+/// CPU number as tid. A `sched_wakeup` becomes a flow arrow from the CPU
+/// that woke the task to where it next ran. Other events are skipped.
+pub fn parse_ftrace(data: &[u8]) -> Result<Profile, FtraceParseError> {
+    let text = std::str::from_utf8(data)?;
+    let lines: Vec<Line<'_>> = text.lines().filter_map(parse_line).collect();
+    let origin = lines.iter().map(|l| l.ts).fold(f64::INFINITY, f64::min);
+    let end = lines.iter().map(|l| l.ts - origin).fold(0.0, f64::max);
+
+    let mut frames: Vec<Frame> = Vec::new();
+    // When each CPU was first seen, and its running slice as an index
+    // into `frames`.
+    let mut first_seen: HashMap<u32, f64> = HashMap::new();
+    let mut running: HashMap<u32, usize> = HashMap::new();
+    // Where and when each woken task was woken.
+    let mut woken: HashMap<u64, (u32, f64)> = HashMap::new();
+    let mut flow_arrows = Vec::new();
+
+    for line in &lines {
+        let ts = line.ts - origin;
+        let cpu = line.cpu;
+        let since = *first_seen.entry(cpu).or_insert(ts);
+        match line.sched() {
+            Some(Sched::Switch { prev, next }) => {
+                match running.remove(&cpu) {
+                    Some(open) => {
+                        let frame = &mut frames[open];
+                        frame.end = ts;
+                        frame.truncated = false;
+                    }
+                    // The task ran since before the CPU's first event.
+                    None if prev.pid != 0 && since < ts => {
+                        frames.push(slice(frames.len(), &prev, cpu, since, ts));
+                    }
+                    None => {}
+                }
+                if next.pid == 0 {
+                    continue;
+                }
+                if let Some((from_cpu, from_ts)) = woken.remove(&next.pid) {
+                    flow_arrows.push(FlowArrow {
+                        name: "wakeup".into(),
+                        id: flow_arrows.len().to_string().into(),
+                        from_ts,
+                        from_pid: 0,
+                        from_tid: from_cpu.into(),
+                        to_ts: ts,
+                        to_pid: 0,
+                        to_tid: cpu.into(),
+                    });
+                }
+                running.insert(cpu, frames.len());
+                let mut frame = slice(frames.len(), &next, cpu, ts, end);
+                frame.truncated = true;
+                frames.push(frame);
+            }
+            Some(Sched::Wakeup(task)) if task.pid != 0 => {
+                woken.insert(task.pid, (cpu, ts));
+            }
+            _ => {}
+        }
+    }
+    if frames.is_empty() {
+        return Err(FtraceParseError::Empty);
+    }
+    for frame in &mut frames {
+        frame.self_time = frame.duration();
+    }
+
+    let mut profile = Profile::new(
+        ProfileMetadata {
+            name: None,
+            start_time: 0.0,
+            end_time: end,
+            format: "ftrace".to_string(),
+            time_domain: None,
+            value_unit: None,
+            sample_interval_us: None,
+        },
+        frames,
+    );
+    profile.flow_arrows = flow_arrows;
+    Ok(profile)
+}
+
+/// A slice of `task` running on `cpu`.
+fn slice(id: usize, task: &Task<'_>, cpu: u32, start: f64, end: f64) -> Frame {
+    Frame {
+        id: id as u64,
+        name: format!("{} {}", task.comm, task.pid),
+        start,
+        end,
+        depth: 0,
+        category: None,
+        parent: None,
+        self_time: 0.0,
+        thread: Some(format!("CPU {cpu}")),
+        truncated: false,
+        pid: Some(0),
+        tid: Some(cpu.into()),
+        source: None,
+        binary: None,
+        inlined: vec![],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TRACE_PIPE: &str = "\
+# tracer: nop
+#
+#           TASK-PID     CPU#  ||||   TIMESTAMP  FUNCTION
+#              | |         |   ||||      |         |
+            bash-1234    [001] d..2   100.000100: sched_wakeup: comm=Web Content pid=2000 prio=120 target_cpu=000
+          <idle>-0       [000] d..2   100.000200: sched_switch: prev_comm=swapper/0 prev_pid=0 prev_prio=120 prev_state=R ==> next_comm=Web Content next_pid=2000 next_prio=120
+            bash-1234    [001] d..2   100.000300: sched_switch: prev_comm=bash prev_pid=1234 prev_prio=120 prev_state=S ==> next_comm=swapper/1 next_pid=0 next_prio=120
+     Web Content-2000    [000] d..2   100.000900: sched_switch: prev_comm=Web Content prev_pid=2000 prev_prio=120 prev_state=S ==> next_comm=kworker/0:1 next_pid=56 next_prio=120
+";
+
+    #[test]
+    fn reads_both_line_forms() {
+        let raw = parse_line(
+            "  bash-1234 ( 1234) [001] d..2 100.000300: sched_switch: prev_comm=bash prev_pid=1234 prev_prio=120 prev_state=S ==> next_comm=swapper/1 next_pid=0 next_prio=120",
+        )
+        .unwrap();
+        assert_eq!(
+            (raw.cpu, raw.ts, raw.event),
+            (1, 100_000_300.0, "sched_switch")
+        );
+        let report = parse_line(
+            "  bash-1234  [001]   100.000300: sched_switch:         bash:1234 [120] S ==> swapper/1:0 [120]",
+        )
+        .unwrap();
+        assert_eq!(raw.sched(), report.sched());
+        assert_eq!(
+            report.sched(),
+            Some(Sched::Switch {
+                prev: Task {
+                    comm: "bash",
+                    pid: 1234
+                },
+                next: Task {
+                    comm: "swapper/1",
+                    pid: 0
+                },
+            })
+        );
+        let wakeup = parse_line(
+            "  bash-1234  [001]   100.000100: sched_wakeup:         kworker/1:1:56 [120] success=1 CPU:001",
+        )
+        .unwrap();
+        assert_eq!(
+            wakeup.sched(),
+            Some(Sched::Wakeup(Task {
+                comm: "kworker/1:1",
+                pid: 56
+            }))
+        );
+        assert_eq!(parse_line("# tracer: nop"), None);
+    }
+
+    #[test]
+    fn cpu_lanes_and_wakeup_arrows() {
+        assert!(is_ftrace(TRACE_PIPE));
+        let profile = parse_ftrace(TRACE_PIPE.as_bytes()).unwrap();
+        assert_eq!(profile.metadata.format, "ftrace");
+
+        let slices: Vec<_> = profile
+            .frames
+            .iter()
+            .map(|f| {
+                (
+                    f.thread.as_deref().unwrap(),
+                    f.name.as_str(),
+                    f.start,
+                    f.end,
+                    f.self_time,
+                    f.truncated,
+                )
+            })
+            .collect();
+        assert_eq!(
+            slices,
+            [
+                ("CPU 0", "Web Content 2000", 100.0, 800.0, 700.0, false),
+                // Running before the CPU's first event.
+                ("CPU 1", "bash 1234", 0.0, 200.0, 200.0, false),
+                // Still running when the trace stops.
+                ("CPU 0", "kworker/0:1 56", 800.0, 800.0, 0.0, true),
+            ]
+        );
+
+        let arrow = &profile.flow_arrows[0];
+        assert_eq!(
+            (arrow.from_tid, arrow.from_ts, arrow.to_tid, arrow.to_ts),
+            (1, 0.0, 0, 100.0)
+        );
+        // Detection tries systrace first, which finds no atrace markers.
+        let detected = crate::parsers::parse_auto(TRACE_PIPE.as_bytes()).unwrap();
+        assert_eq!(detected.metadata.format, "ftrace");
+        assert!(matches!(
+            parse_ftrace(b"# tracer: nop\n"),
+            Err(FtraceParseError::Empty)
+        ));
+    }
+}
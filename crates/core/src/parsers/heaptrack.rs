@@ -0,0 +1,338 @@
+use std::io::Read;
+
+use flame_cat_protocol::{CounterSample, CounterTrack, CounterUnit, ValueUnit};
+use thiserror::Error;
+
+use super::collapsed::{self, CollapsedParseError};
+use super::limits::{DecompressError, Limits, decompress_within};
+use super::pprof::is_gzip;
+use crate::model::{Frame, Profile, ProfileMetadata};
+
+#[derive(Debug, Error)]
+pub enum HeaptrackParseError {
+    #[error("gzip: {0}")]
+    Gzip(#[from] DecompressError),
+    #[error("invalid UTF-8: {0}")]
+    Utf8(#[from] std::str::Utf8Error),
+    #[error("flamegraph export: {0}")]
+    Collapsed(#[from] CollapsedParseError),
+    #[error("no allocations found")]
+    Empty,
+}
+
+/// Bytes of (decompressed) input detection looks at.
+const HEAD: u64 = 64 * 1024;
+
+/// Returns `true` if `data` is a heaptrack recording (`heaptrack.<app>.<pid>.gz`),
+/// gzip-compressed or not.
+pub fn is_heaptrack(data: &[u8]) -> bool {
+    if is_gzip(data) {
+        let mut head = Vec::new();
+        // A truncated stream still leaves what decoded before the damage.
+        let _ = flate2::read::MultiGzDecoder::new(data)
+            .take(HEAD)
+            .read_to_end(&mut head);
+        is_records(&head)
+    } else {
+        is_records(&data[..data.len().min(HEAD as usize)])
+    }
+}
+
+/// Whether `data` starts like heaptrack's record stream: a `v` version
+/// line, then a `X` command line somewhere after it.
+fn is_records(data: &[u8]) -> bool {
+    let first = data.split(|&b| b == b'\n').next().unwrap_or_default();
+    first.strip_prefix(b"v ").is_some_and(|version| {
+        !version.is_empty() && version.iter().all(|b| b.is_ascii_hexdigit() || *b == b' ')
+    }) && data.windows(3).any(|w| w == b"\nX ")
+}
+
+/// Parse a heaptrack recording into a byte-weighted `Profile`.
+///
+/// Each allocation stack is weighted by the bytes allocated there over the
+/// whole run, and the heap size at each timestamp of the recording becomes
+/// a counter track, its times spread over the same extent as the stacks
+/// like massif's. Recordings are read as heaptrack writes them, gzipped,
+/// or decompressed.
+///
+/// Input that is not a recording is taken as `heaptrack_print -F` folded
+/// stacks, whose counts are bytes when exported with a byte cost type
+/// (`--flamegraph-cost-type allocated`, `peak` or `leaked`).
+pub fn parse_heaptrack(data: &[u8]) -> Result<Profile, HeaptrackParseError> {
+    parse_heaptrack_limited(data, &Limits::NONE)
+}
+
+/// [`parse_heaptrack`] that inflates gzip only within the memory budget of
+/// `limits`, and reads a flamegraph export within them.
+pub(crate) fn parse_heaptrack_limited(
+    data: &[u8],
+    limits: &Limits,
+) -> Result<Profile, HeaptrackParseError> {
+    let raw;
+    let data = if is_gzip(data) {
+        raw = decompress_within(flate2::read::MultiGzDecoder::new(data), limits)?;
+        &raw[..]
+    } else {
+        data
+    };
+    if !is_records(data) {
+        let mut profile = collapsed::parse_collapsed_limited(data, limits, None)?;
+        profile.metadata.format = "heaptrack".to_string();
+        profile.metadata.value_unit = Some(ValueUnit::Bytes);
+        return Ok(profile);
+    }
+    let text = std::str::from_utf8(data)?;
+    let hex = |field: Option<&str>| field.and_then(|f| u64::from_str_radix(f, 16).ok());
+
+    let mut cmd = None;
+    let mut file_version = 0;
+    let mut strings: Vec<&str> = Vec::new();
+    // Frame names of each instruction pointer, innermost inlined first.
+    let mut ips: Vec<Vec<String>> = Vec::new();
+    // `(instruction pointer, parent trace)` of each trace, both 1-based.
+    let mut traces: Vec<(usize, usize)> = Vec::new();
+    // `(size, trace)` of each allocation site.
+    let mut infos: Vec<(f64, usize)> = Vec::new();
+    let mut allocated: Vec<f64> = Vec::new();
+    let mut heap = 0.0;
+    let mut samples: Vec<CounterSample> = Vec::new();
+
+    for line in text.lines() {
+        let (mode, rest) = line.split_once(' ').unwrap_or((line, ""));
+        let mut fields = rest.split(' ');
+        match mode {
+            "v" => file_version = hex(fields.nth(1)).unwrap_or(0),
+            "X" => cmd = Some(rest.to_string()),
+            // File format 3 prefixes strings with their length, so they may
+            // hold spaces at either end.
+            "s" if file_version >= 3 => strings.push(rest.split_once(' ').map_or("", |s| s.1)),
+            "s" => strings.push(rest),
+            "i" => {
+                let ip = fields.next().unwrap_or_default();
+                let module = hex(fields.next()).and_then(|m| string(&strings, m));
+                let fields: Vec<u64> = fields.filter_map(|f| hex(Some(f))).collect();
+                let mut names: Vec<String> = fields
+                    .chunks(3)
+                    .filter_map(|frame| string(&strings, frame[0]))
+                    .map(str::to_string)
+                    .collect();
+                if names.is_empty() {
+                    let module = module.map(|m| m.rsplit('/').next().unwrap_or(m));
+                    names.push(module.map_or_else(|| format!("0x{ip}"), str::to_string));
+                }
+                // The first frame is the function itself, the rest what
+                // was inlined into it, innermost first.
+                names.rotate_left(1);
+                ips.push(names);
+            }
+            "t" => traces.push((
+                hex(fields.next()).unwrap_or(0) as usize,
+                hex(fields.next()).unwrap_or(0) as usize,
+            )),
+            "a" => {
+                let size = hex(fields.next()).unwrap_or(0) as f64;
+                infos.push((size, hex(fields.next()).unwrap_or(0) as usize));
+                allocated.push(0.0);
+            }
+            "+" | "-" => {
+                let Some(index) = hex(fields.next()).map(|i| i as usize) else {
+                    continue;
+                };
+                let Some(&(size, _)) = infos.get(index) else {
+                    continue;
+                };
+                if mode == "+" {
+                    allocated[index] += size;
+                    heap += size;
+                } else {
+                    heap -= size;
+                }
+            }
+            "c" => samples.push(CounterSample {
+                ts: hex(fields.next()).unwrap_or(0) as f64,
+                value: heap,
+            }),
+            _ => {}
+        }
+    }
+
+    let mut by_trace = vec![0.0; traces.len() + 1];
+    for (&(_, trace), bytes) in infos.iter().zip(&allocated) {
+        if let Some(total) = by_trace.get_mut(trace) {
+            *total += bytes;
+        }
+    }
+
+    let mut frames: Vec<Frame> = Vec::new();
+    let mut offset = 0.0;
+    for (trace, &bytes) in by_trace.iter().enumerate().skip(1) {
+        if bytes <= 0.0 {
+            continue;
+        }
+        let mut stack: Vec<&str> = Vec::new();
+        let mut at = trace;
+        // Parents come before their children, so a well-formed chain ends
+        // within `traces.len()` steps.
+        for _ in 0..traces.len() {
+            let Some(&(ip, parent)) = at.checked_sub(1).and_then(|t| traces.get(t)) else {
+                break;
+            };
+            if let Some(names) = ip.checked_sub(1).and_then(|i| ips.get(i)) {
+                stack.extend(names.iter().map(String::as_str));
+            }
+            at = parent;
+        }
+        let mut parent = None;
+        for (depth, name) in stack.iter().rev().enumerate() {
+            let id = frames.len() as u64;
+            frames.push(Frame {
+                id,
+                name: name.to_string(),
+                start: offset,
+                end: offset + bytes,
+                depth: depth as u32,
+                category: None,
+                parent,
+                self_time: 0.0,
+                thread: None,
+                truncated: false,
+                pid: None,
+                tid: None,
+                source: None,
+                binary: None,
+                inlined: vec![],
+            });
+            parent = Some(id);
+        }
+        if let Some(leaf) = parent.and_then(|id| frames.get_mut(id as usize)) {
+            leaf.self_time = bytes;
+            offset += bytes;
+        }
+    }
+    if frames.is_empty() {
+        return Err(HeaptrackParseError::Empty);
+    }
+
+    let last = samples.iter().map(|s| s.ts).fold(0.0, f64::max);
+    let scale = if last > 0.0 { offset / last } else { 0.0 };
+    for sample in &mut samples {
+        sample.ts *= scale;
+    }
+
+    let mut profile = Profile::new(
+        ProfileMetadata {
+            name: cmd,
+            start_time: 0.0,
+            end_time: offset,
+            format: "heaptrack".to_string(),
+            time_domain: None,
+            value_unit: Some(ValueUnit::Bytes),
+            sample_interval_us: None,
+        },
+        frames,
+    );
+    if !samples.is_empty() {
+        profile.counters = vec![CounterTrack {
+            name: "Heap".into(),
+            unit: CounterUnit::Bytes,
+            samples,
+        }];
+    }
+    Ok(profile)
+}
+
+/// Entry `index` of heaptrack's 1-based string table; 0 means none.
+fn string<'a>(strings: &[&'a str], index: u64) -> Option<&'a str> {
+    strings.get((index as usize).checked_sub(1)?).copied()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use super::*;
+
+    const RECORDING: &str = "\
+v 10300 3
+X ./app --run
+I 1000 4000
+s c /usr/bin/app
+s 4 main
+s 4 load
+s 5 parse
+s 7 read_u8
+i 4005f0 1 2
+i 4005a6 1 3 0 0 5 0 0 4 0 0
+i 400700 1
+t 1 0
+t 2 1
+t 3 0
+a 400 2
+a 20 3
+c 0
++ 0
++ 1
++ 1
+c a
+- 0
+c 14
+";
+
+    #[test]
+    fn allocation_stacks_weighted_by_bytes() {
+        let mut gz = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        gz.write_all(RECORDING.as_bytes()).unwrap();
+        let gz = gz.finish().unwrap();
+        assert!(is_heaptrack(&gz) && is_heaptrack(RECORDING.as_bytes()));
+        assert_eq!(crate::parsers::detect_formats(&gz)[0].format, "heaptrack");
+
+        let profile = parse_heaptrack(&gz).unwrap();
+        assert_eq!(profile.metadata.name.as_deref(), Some("./app --run"));
+        assert_eq!(profile.metadata.value_unit, Some(ValueUnit::Bytes));
+        assert_eq!(profile.metadata.end_time, 1088.0);
+
+        let frames: Vec<_> = profile
+            .frames
+            .iter()
+            .map(|f| (f.name.as_str(), f.depth, f.start, f.end, f.self_time))
+            .collect();
+        assert_eq!(
+            frames,
+            [
+                ("main", 0, 0.0, 1024.0, 0.0),
+                ("load", 1, 0.0, 1024.0, 0.0),
+                ("parse", 2, 0.0, 1024.0, 0.0),
+                ("read_u8", 3, 0.0, 1024.0, 1024.0),
+                ("app", 0, 1024.0, 1088.0, 64.0),
+            ]
+        );
+        let heap: Vec<_> = profile.counters[0]
+            .samples
+            .iter()
+            .map(|s| (s.ts, s.value))
+            .collect();
+        assert_eq!(heap, [(0.0, 0.0), (544.0, 1088.0), (1088.0, 64.0)]);
+    }
+
+    #[test]
+    fn flamegraph_export_is_in_bytes() {
+        let profile = parse_heaptrack(b"main;load 1024\nmain 64\n").unwrap();
+        assert_eq!(profile.metadata.format, "heaptrack");
+        assert_eq!(profile.metadata.value_unit, Some(ValueUnit::Bytes));
+        assert_eq!(profile.metadata.end_time, 1088.0);
+    }
+
+    #[test]
+    fn gzip_inflates_within_the_budget() {
+        let mut gz = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::best());
+        gz.write_all(&vec![b'\n'; 1 << 20]).unwrap();
+        let limits = Limits {
+            max_memory: 64 * 1024,
+            ..Limits::default()
+        };
+        assert!(matches!(
+            parse_heaptrack_limited(&gz.finish().unwrap(), &limits),
+            Err(HeaptrackParseError::Gzip(DecompressError::TooLarge { .. }))
+        ));
+    }
+}
@@ -0,0 +1,387 @@
+//! Instruments Time Profiler tables, as exported by `xctrace export`.
+//!
+//! `xctrace export --input run.trace --xpath
+//! '/trace-toc/run[@number="1"]/data/table[@schema="time-profile"]'`
+//! writes a `<trace-query-result>` with one `<row>` per sample: its
+//! `sample-time` and `weight` in ns, the `thread` (holding its `tid` and
+//! `process`) and the `backtrace` of `frame`s, leaf first, each naming its
+//! `binary`. Any element may instead be a `ref` to an earlier one with the
+//! same `id`, which is how repeated threads, stacks and frames are written.
+
+use std::collections::{BTreeMap, HashMap};
+use std::rc::Rc;
+
+use flame_cat_protocol::Binary;
+use quick_xml::Reader;
+use quick_xml::events::{BytesStart, Event};
+use thiserror::Error;
+
+use super::perf::{Sample, stitch_samples};
+use crate::model::{Frame, Profile, ProfileMetadata};
+
+#[derive(Debug, Error)]
+pub enum InstrumentsParseError {
+    #[error("invalid UTF-8: {0}")]
+    Utf8(#[from] std::str::Utf8Error),
+    #[error("XML: {0}")]
+    Xml(#[from] quick_xml::Error),
+    #[error("not an xctrace export")]
+    NotInstruments,
+    #[error("row {row}: {message}")]
+    Malformed { row: usize, message: &'static str },
+    #[error("no samples found")]
+    Empty,
+}
+
+/// Root element of `xctrace export` query output.
+const ROOT: &[u8] = b"<trace-query-result";
+
+/// Whether `data` is `xctrace export` query output.
+pub fn is_xctrace_export(data: &[u8]) -> bool {
+    let head = &data[..data.len().min(512)];
+    head.trim_ascii_start().starts_with(b"<?xml") && head.windows(ROOT.len()).any(|w| w == ROOT)
+}
+
+/// Parse an `xctrace export` of a Time Profiler table into a `Profile`.
+///
+/// Each thread's samples become frames on a lane named after the thread,
+/// stitched like other sampled formats, with the sample weight as the
+/// interval. Frames record the binary they ran in; unsymbolicated frames
+/// are named by address. Times are µs from the start of the recording.
+pub fn parse_instruments(data: &[u8]) -> Result<Profile, InstrumentsParseError> {
+    if !is_xctrace_export(data) {
+        return Err(InstrumentsParseError::NotInstruments);
+    }
+    let mut reader = Reader::from_str(std::str::from_utf8(data)?);
+    let mut elements = Elements::default();
+    let mut trace = Trace::default();
+    // Elements open inside the current row, innermost last.
+    let mut open: Vec<Element> = Vec::new();
+    let mut rows = 0;
+    loop {
+        match reader.read_event()? {
+            Event::Start(e) => {
+                if e.name().as_ref() == b"row" {
+                    rows += 1;
+                    open.push(Element::new(&e)?);
+                } else if !open.is_empty() {
+                    open.push(Element::new(&e)?);
+                }
+            }
+            Event::Empty(e) if !open.is_empty() => {
+                let element = Element::new(&e)?;
+                let node = elements.resolve(element);
+                if let Some(parent) = open.last_mut() {
+                    parent.children.push(node);
+                }
+            }
+            Event::Text(t) => {
+                if let Some(element) = open.last_mut() {
+                    element
+                        .text
+                        .push_str(&t.decode().map_err(quick_xml::Error::from)?);
+                }
+            }
+            Event::End(_) => {
+                let Some(element) = open.pop() else {
+                    continue;
+                };
+                let node = elements.resolve(element);
+                match open.last_mut() {
+                    Some(parent) => parent.children.push(node),
+                    None => trace.row(&node, rows)?,
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    trace.finish()
+}
+
+/// An element being read, or a resolved one.
+#[derive(Default)]
+struct Element {
+    name: String,
+    attrs: Vec<(String, String)>,
+    text: String,
+    children: Vec<Rc<Element>>,
+}
+
+impl Element {
+    fn new(e: &BytesStart<'_>) -> Result<Self, quick_xml::Error> {
+        let mut attrs = Vec::new();
+        for attr in e.attributes() {
+            let attr = attr?;
+            attrs.push((
+                String::from_utf8_lossy(attr.key.as_ref()).into_owned(),
+                attr.unescape_value()?.into_owned(),
+            ));
+        }
+        Ok(Self {
+            name: String::from_utf8_lossy(e.name().as_ref()).into_owned(),
+            attrs,
+            ..Self::default()
+        })
+    }
+
+    fn attr(&self, name: &str) -> Option<&str> {
+        self.attrs
+            .iter()
+            .find(|(k, _)| k == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    fn child(&self, name: &str) -> Option<&Element> {
+        self.children
+            .iter()
+            .find(|c| c.name == name)
+            .map(AsRef::as_ref)
+    }
+
+    /// First element named `name` at or below this one.
+    fn find(&self, name: &str) -> Option<&Element> {
+        if self.name == name {
+            return Some(self);
+        }
+        self.children.iter().find_map(|c| c.find(name))
+    }
+
+    fn number(&self) -> Option<f64> {
+        self.text.trim().parse().ok()
+    }
+}
+
+/// Elements by `id`, for later `ref`s.
+#[derive(Default)]
+struct Elements(HashMap<String, Rc<Element>>);
+
+impl Elements {
+    /// `element`, or the one it refers to; remembered if it has an id.
+    fn resolve(&mut self, element: Element) -> Rc<Element> {
+        if let Some(target) = element.attr("ref").and_then(|r| self.0.get(r)) {
+            return Rc::clone(target);
+        }
+        let id = element.attr("id").map(str::to_string);
+        let node = Rc::new(element);
+        if let Some(id) = id {
+            self.0.insert(id, Rc::clone(&node));
+        }
+        node
+    }
+}
+
+#[derive(Default)]
+struct Trace {
+    /// Frame names and binaries by location id.
+    locations: Vec<(String, Option<u32>)>,
+    location_index: HashMap<(String, Option<u32>), u32>,
+    binaries: Vec<Binary>,
+    /// Binary ids by name and UUID.
+    binary_index: HashMap<(String, Option<String>), u32>,
+    threads: BTreeMap<(u64, u64), Vec<Sample>>,
+    thread_names: HashMap<(u64, u64), String>,
+    process_names: BTreeMap<u64, String>,
+    interval_us: Option<f64>,
+}
+
+impl Trace {
+    fn row(&mut self, row: &Element, index: usize) -> Result<(), InstrumentsParseError> {
+        let malformed = |message| InstrumentsParseError::Malformed {
+            row: index,
+            message,
+        };
+        let ts = row
+            .find("sample-time")
+            .and_then(Element::number)
+            .ok_or_else(|| malformed("no sample time"))?
+            / 1e3;
+        let thread = row.find("thread").ok_or_else(|| malformed("no thread"))?;
+        let tid = thread
+            .child("tid")
+            .and_then(Element::number)
+            .ok_or_else(|| malformed("no thread id"))? as u64;
+        let process = thread.child("process");
+        let pid = process
+            .and_then(|p| p.child("pid"))
+            .and_then(Element::number)
+            .map_or(0, |pid| pid as u64);
+        if let Some(name) = process.and_then(|p| p.attr("fmt")) {
+            self.process_names
+                .entry(pid)
+                .or_insert_with(|| strip_suffix(name).to_string());
+        }
+        if let Some(name) = thread.attr("fmt") {
+            self.thread_names
+                .entry((pid, tid))
+                .or_insert_with(|| strip_suffix(name).to_string());
+        }
+        if self.interval_us.is_none() {
+            self.interval_us = row
+                .find("weight")
+                .and_then(Element::number)
+                .map(|ns| ns / 1e3)
+                .filter(|us| *us > 0.0);
+        }
+        // Frames come leaf first.
+        let stack = row
+            .find("backtrace")
+            .map(|bt| {
+                let mut stack: Vec<u32> = bt
+                    .children
+                    .iter()
+                    .filter(|f| f.name == "frame")
+                    .map(|f| self.location(f))
+                    .collect();
+                stack.reverse();
+                stack
+            })
+            .unwrap_or_default();
+        self.threads
+            .entry((pid, tid))
+            .or_default()
+            .push(Sample { ts, stack });
+        Ok(())
+    }
+
+    fn location(&mut self, frame: &Element) -> u32 {
+        let binary = frame.child("binary").map(|b| {
+            let name = b.attr("name").unwrap_or("?");
+            let uuid = b.attr("UUID");
+            *self
+                .binary_index
+                .entry((name.to_string(), uuid.map(str::to_string)))
+                .or_insert_with(|| {
+                    self.binaries.push(Binary {
+                        name: name.into(),
+                        build_id: uuid.map(Into::into),
+                    });
+                    self.binaries.len() as u32 - 1
+                })
+        });
+        let name = frame.attr("name").or(frame.attr("addr")).unwrap_or("?");
+        *self
+            .location_index
+            .entry((name.to_string(), binary))
+            .or_insert_with(|| {
+                self.locations.push((name.to_string(), binary));
+                self.locations.len() as u32 - 1
+            })
+    }
+
+    fn finish(self) -> Result<Profile, InstrumentsParseError> {
+        if self.threads.is_empty() {
+            return Err(InstrumentsParseError::Empty);
+        }
+        let mut frames: Vec<Frame> = Vec::new();
+        for ((pid, tid), mut samples) in self.threads {
+            let thread = self
+                .thread_names
+                .get(&(pid, tid))
+                .cloned()
+                .unwrap_or_else(|| format!("Thread {tid:#x}"));
+            stitch_samples(&mut frames, &mut samples, self.interval_us, |location| {
+                let (name, binary) = &self.locations[location as usize];
+                Frame {
+                    name: name.clone(),
+                    thread: Some(thread.clone()),
+                    pid: Some(pid),
+                    tid: Some(tid),
+                    binary: *binary,
+                    ..Frame::default()
+                }
+            });
+        }
+
+        let start_time = frames.iter().map(|f| f.start).fold(f64::INFINITY, f64::min);
+        let end_time = frames
+            .iter()
+            .map(|f| f.end)
+            .fold(f64::NEG_INFINITY, f64::max);
+        let mut profile = Profile::new(
+            ProfileMetadata {
+                name: None,
+                start_time,
+                end_time,
+                format: "instruments".to_string(),
+                time_domain: None,
+                value_unit: None,
+                sample_interval_us: self.interval_us,
+            },
+            frames,
+        );
+        profile.binaries = self.binaries;
+        profile.process_names = self.process_names;
+        Ok(profile)
+    }
+}
+
+/// A thread or process label without its trailing `(…)`, e.g.
+/// `Main Thread 0x1a2b` of `Main Thread 0x1a2b (MyApp, pid: 42)`.
+fn strip_suffix(label: &str) -> &str {
+    label
+        .rsplit_once(" (")
+        .map_or(label, |(name, _)| name)
+        .trim()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXPORT: &str = r#"<?xml version="1.0"?>
+<trace-query-result>
+<node xpath='//trace-toc[1]/run[1]/data[1]/table[1]'><schema name="time-profile"><col><mnemonic>time</mnemonic></col></schema>
+<row><sample-time id="1" fmt="00:00.001.000">1000000</sample-time><thread id="2" fmt="Main Thread 0x1a2b (MyApp, pid: 42)"><tid id="3" fmt="0x1a2b">6699</tid><process id="4" fmt="MyApp (42)"><pid id="5" fmt="42">42</pid></process></thread><weight id="6" fmt="1.00 ms">1000000</weight><backtrace id="7"><frame id="8" name="draw&lt;T&gt;" addr="0x1008"><binary id="9" name="MyApp" UUID="AB-CD"/></frame><frame id="10" name="main" addr="0x1000"><binary ref="9"/></frame></backtrace></row>
+<row><sample-time id="11" fmt="00:00.002.000">2000000</sample-time><thread ref="2"/><weight ref="6"/><backtrace ref="7"/></row>
+<row><sample-time id="12" fmt="00:00.003.000">3000000</sample-time><thread ref="2"/><weight ref="6"/><backtrace id="13"><frame id="14" addr="0x7ff0"/><frame ref="10"/></backtrace></row>
+<row><sample-time id="15" fmt="00:00.004.000">4000000</sample-time><sentinel/><thread id="16" fmt="Worker 0x1a2c (MyApp, pid: 42)"><tid id="17" fmt="0x1a2c">6700</tid><process ref="4"/></thread><weight ref="6"/><backtrace ref="13"/></row>
+</node></trace-query-result>
+"#;
+
+    #[test]
+    fn parses_time_profile_rows() {
+        assert!(is_xctrace_export(EXPORT.as_bytes()));
+        let profile = parse_instruments(EXPORT.as_bytes()).unwrap();
+        assert_eq!(profile.metadata.format, "instruments");
+        assert_eq!(profile.metadata.sample_interval_us, Some(1_000.0));
+        assert_eq!(profile.process_names[&42], "MyApp");
+
+        let frames: Vec<_> = profile
+            .frames
+            .iter()
+            .map(|f| {
+                (
+                    f.thread.as_deref().unwrap(),
+                    f.name.as_str(),
+                    f.depth,
+                    f.start,
+                    f.end,
+                )
+            })
+            .collect();
+        assert_eq!(
+            frames,
+            [
+                ("Main Thread 0x1a2b", "main", 0, 1_000.0, 4_000.0),
+                ("Main Thread 0x1a2b", "draw<T>", 1, 1_000.0, 3_000.0),
+                ("Main Thread 0x1a2b", "0x7ff0", 1, 3_000.0, 4_000.0),
+                ("Worker 0x1a2c", "main", 0, 4_000.0, 5_000.0),
+                ("Worker 0x1a2c", "0x7ff0", 1, 4_000.0, 5_000.0),
+            ]
+        );
+        assert_eq!(profile.binaries.len(), 1);
+        assert_eq!(profile.binaries[0].build_id.as_deref(), Some("AB-CD"));
+        assert_eq!(profile.frames[0].binary, Some(0));
+        assert_eq!(profile.frames[2].binary, None);
+    }
+
+    #[test]
+    fn rejects_other_xml() {
+        assert!(!is_xctrace_export(b"<?xml version=\"1.0\"?><svg/>"));
+        assert!(matches!(
+            parse_instruments(b"<?xml version=\"1.0\"?><trace-query-result></trace-query-result>"),
+            Err(InstrumentsParseError::Empty)
+        ));
+    }
+}
@@ -0,0 +1,1131 @@
+//! Java Flight Recorder recordings (`.jfr`).
+//!
+//! A recording is a sequence of chunks. Each starts with a header giving
+//! the chunk's clock and holds events: a metadata event describing the
+//! fields of every type, checkpoint events carrying constant pools
+//! (threads, stack traces, methods, symbols, ...) that other events refer
+//! to by index, and the recorded events themselves. Values are decoded
+//! from the metadata, so only the events used here are looked up by name:
+//! execution samples, garbage collections and monitor waits and enters.
+
+use std::collections::{BTreeMap, HashMap};
+
+use flame_cat_protocol::{
+    AsyncSpan, ClockKind, CpuNode, CpuSamples, Marker, MarkerScope, TimeDomain,
+};
+use thiserror::Error;
+
+use super::bytes::{self, BigEndian, Truncated};
+use super::perf::{Sample, stitch_samples};
+use crate::model::{Frame, Profile, ProfileMetadata};
+
+#[derive(Debug, Error)]
+pub enum JfrParseError {
+    #[error("not a JFR recording")]
+    NotJfr,
+    #[error("unsupported JFR recording: {0}")]
+    Unsupported(&'static str),
+    #[error("malformed JFR recording: {0}")]
+    Malformed(&'static str),
+    #[error("no samples, garbage collections or monitor events found")]
+    Empty,
+}
+
+impl From<Truncated> for JfrParseError {
+    fn from(_: Truncated) -> Self {
+        Self::Malformed("event runs past its end")
+    }
+}
+
+// Layouts from the JDK's jdk.jfr.internal.consumer.ChunkHeader.
+const MAGIC: &[u8; 4] = b"FLR\0";
+const HEADER_SIZE: usize = 68;
+const FEATURE_COMPRESSED_INTS: u32 = 1;
+
+const METADATA_EVENT: u64 = 0;
+const CHECKPOINT_EVENT: u64 = 1;
+
+/// Deepest nesting of metadata elements or inline values accepted.
+const MAX_DEPTH: usize = 32;
+
+/// Whether `data` starts like a JFR recording.
+pub fn is_jfr(data: &[u8]) -> bool {
+    data.starts_with(MAGIC)
+}
+
+/// Parse a JFR recording into a `Profile`.
+///
+/// `jdk.ExecutionSample` stacks become both CPU samples and frames per
+/// Java thread, stitched like other sampled formats. Garbage collections
+/// become markers and async spans in the `gc` category, and
+/// `jdk.JavaMonitorWait`/`jdk.JavaMonitorEnter` async spans in the `lock`
+/// category on the waiting thread. Times are wall-clock µs.
+pub fn parse_jfr(data: &[u8]) -> Result<Profile, JfrParseError> {
+    if !is_jfr(data) {
+        return Err(JfrParseError::NotJfr);
+    }
+    let mut recording = Recording::default();
+    let mut rest = data;
+    while !rest.is_empty() {
+        let mut header = Reader::new(rest, false);
+        if header.bytes(4)? != MAGIC {
+            return Err(JfrParseError::Malformed("chunk without magic"));
+        }
+        let major = header.raw_u16()?;
+        header.raw_u16()?; // minor
+        if major != 1 && major != 2 {
+            return Err(JfrParseError::Unsupported("file version"));
+        }
+        let size = header.offset()?;
+        if size < HEADER_SIZE || size > rest.len() {
+            return Err(JfrParseError::Malformed("chunk size out of range"));
+        }
+        let (chunk, next) = rest.split_at(size);
+        header.offset()?; // last constant pool, found by scanning instead
+        let metadata = header.offset()?;
+        let clock = Clock {
+            start_nanos: header.raw_u64()? as i64,
+            start_ticks: {
+                header.raw_u64()?; // duration
+                header.raw_u64()? as i64
+            },
+            ticks_per_second: header.raw_u64()? as f64,
+        };
+        let compressed = header.raw_u32()? & FEATURE_COMPRESSED_INTS != 0;
+        if clock.ticks_per_second <= 0.0 {
+            return Err(JfrParseError::Malformed("chunk without a clock"));
+        }
+        recording.chunk(chunk, metadata, clock, compressed)?;
+        rest = next;
+    }
+    recording.finish()
+}
+
+/// Big-endian reads over a byte slice, with integers LEB128-style
+/// compressed when the chunk says so.
+struct Reader<'a> {
+    raw: bytes::Reader<'a, BigEndian>,
+    compressed: bool,
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8], compressed: bool) -> Self {
+        Self {
+            raw: bytes::Reader::new(data),
+            compressed,
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.raw.is_empty()
+    }
+
+    fn position(&self) -> usize {
+        self.raw.position()
+    }
+
+    fn bytes(&mut self, n: usize) -> Result<&'a [u8], JfrParseError> {
+        Ok(self.raw.bytes(n)?)
+    }
+
+    fn byte(&mut self) -> Result<u8, JfrParseError> {
+        Ok(self.raw.u8()?)
+    }
+
+    fn raw_u16(&mut self) -> Result<u16, JfrParseError> {
+        Ok(self.raw.u16()?)
+    }
+
+    fn raw_u32(&mut self) -> Result<u32, JfrParseError> {
+        Ok(self.raw.u32()?)
+    }
+
+    fn raw_u64(&mut self) -> Result<u64, JfrParseError> {
+        Ok(self.raw.u64()?)
+    }
+
+    /// A header offset or size within the file.
+    fn offset(&mut self) -> Result<usize, JfrParseError> {
+        usize::try_from(self.raw_u64()?)
+            .map_err(|_| JfrParseError::Malformed("offset out of range"))
+    }
+
+    /// Seven bits per byte, low bits first; a ninth byte holds eight.
+    fn varint(&mut self) -> Result<u64, JfrParseError> {
+        let mut value = 0;
+        for shift in (0..56).step_by(7) {
+            let b = self.byte()?;
+            value |= u64::from(b & 0x7f) << shift;
+            if b & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Ok(value | u64::from(self.byte()?) << 56)
+    }
+
+    fn long(&mut self) -> Result<i64, JfrParseError> {
+        Ok(if self.compressed {
+            self.varint()?
+        } else {
+            self.raw_u64()?
+        } as i64)
+    }
+
+    fn int(&mut self) -> Result<i32, JfrParseError> {
+        Ok(if self.compressed {
+            self.varint()? as u32
+        } else {
+            self.raw_u32()?
+        } as i32)
+    }
+
+    fn short(&mut self) -> Result<i16, JfrParseError> {
+        Ok(if self.compressed {
+            self.varint()? as u16
+        } else {
+            self.raw_u16()?
+        } as i16)
+    }
+
+    /// An element count, which cannot exceed the bytes left.
+    fn count(&mut self) -> Result<usize, JfrParseError> {
+        usize::try_from(self.int()?)
+            .ok()
+            .filter(|&n| n <= self.raw.remaining())
+            .ok_or(JfrParseError::Malformed("count out of range"))
+    }
+
+    /// A string, or a reference into the pool of `string_class`.
+    fn string(&mut self, string_class: u64) -> Result<Value, JfrParseError> {
+        Ok(match self.byte()? {
+            0 => Value::Null,
+            1 => Value::Str(String::new()),
+            2 => Value::Ref(string_class, self.long()?),
+            3 => {
+                let n = self.count()?;
+                Value::Str(String::from_utf8_lossy(self.bytes(n)?).into_owned())
+            }
+            4 => {
+                let n = self.count()?;
+                let chars = (0..n)
+                    .map(|_| self.short().map(|c| c as u16))
+                    .collect::<Result<Vec<u16>, _>>()?;
+                Value::Str(String::from_utf16_lossy(&chars))
+            }
+            5 => {
+                let n = self.count()?;
+                Value::Str(self.bytes(n)?.iter().map(|&b| char::from(b)).collect())
+            }
+            _ => return Err(JfrParseError::Malformed("unknown string encoding")),
+        })
+    }
+}
+
+/// A decoded value.
+#[derive(Debug, Clone)]
+enum Value {
+    Null,
+    Int(i64),
+    Float(f64),
+    Str(String),
+    /// Entry `key` of the constant pool of a class.
+    Ref(u64, i64),
+    /// Field values of a class, in metadata order.
+    Object(u64, Vec<Value>),
+    Array(Vec<Value>),
+}
+
+static NULL: Value = Value::Null;
+
+#[derive(Debug)]
+struct Class {
+    name: String,
+    fields: Vec<Field>,
+}
+
+#[derive(Debug)]
+struct Field {
+    name: String,
+    class: u64,
+    /// Stored as an index into the class's constant pool.
+    pool: bool,
+    array: bool,
+}
+
+/// A node of the metadata event's element tree.
+struct Element {
+    name: String,
+    attributes: HashMap<String, String>,
+    children: Vec<Element>,
+}
+
+impl Element {
+    fn read(r: &mut Reader<'_>, strings: &[String], depth: usize) -> Result<Self, JfrParseError> {
+        if depth > MAX_DEPTH {
+            return Err(JfrParseError::Malformed("metadata nested too deeply"));
+        }
+        let string = |r: &mut Reader<'_>| {
+            usize::try_from(r.int()?)
+                .ok()
+                .and_then(|i| strings.get(i).cloned())
+                .ok_or(JfrParseError::Malformed("string index out of range"))
+        };
+        let name = string(r)?;
+        let mut attributes = HashMap::new();
+        for _ in 0..r.count()? {
+            let key = string(r)?;
+            attributes.insert(key, string(r)?);
+        }
+        let mut children = Vec::new();
+        for _ in 0..r.count()? {
+            children.push(Self::read(r, strings, depth + 1)?);
+        }
+        Ok(Self {
+            name,
+            attributes,
+            children,
+        })
+    }
+
+    /// Every `class` element below this one.
+    fn classes<'e>(&'e self, out: &mut Vec<&'e Element>) {
+        for child in &self.children {
+            if child.name == "class" {
+                out.push(child);
+            } else {
+                child.classes(out);
+            }
+        }
+    }
+}
+
+/// Converts a chunk's ticks to µs.
+#[derive(Debug, Clone, Copy)]
+struct Clock {
+    start_nanos: i64,
+    start_ticks: i64,
+    ticks_per_second: f64,
+}
+
+impl Clock {
+    fn us(&self, ticks: i64) -> f64 {
+        // Both come from the file; a corrupt header must not overflow.
+        self.start_nanos as f64 / 1e3 + self.duration_us(ticks.saturating_sub(self.start_ticks))
+    }
+
+    fn duration_us(&self, ticks: i64) -> f64 {
+        ticks as f64 * 1e6 / self.ticks_per_second
+    }
+}
+
+/// One chunk's types and constant pools.
+#[derive(Default)]
+struct Chunk {
+    classes: HashMap<u64, Class>,
+    pools: HashMap<(u64, i64), Value>,
+    string_class: u64,
+}
+
+impl Chunk {
+    fn metadata(&mut self, event: &[u8], compressed: bool) -> Result<(), JfrParseError> {
+        let mut r = Reader::new(event, compressed);
+        r.int()?; // size
+        if r.long()? != METADATA_EVENT as i64 {
+            return Err(JfrParseError::Malformed("metadata offset points elsewhere"));
+        }
+        r.long()?; // start time
+        r.long()?; // duration
+        r.long()?; // metadata id
+        let strings = (0..r.count()?)
+            .map(|_| match r.string(0)? {
+                Value::Str(s) => Ok(s),
+                _ => Ok(String::new()),
+            })
+            .collect::<Result<Vec<_>, JfrParseError>>()?;
+        let root = Element::read(&mut r, &strings, 0)?;
+        let mut classes = Vec::new();
+        root.classes(&mut classes);
+        for class in classes {
+            let attr = |e: &Element, key: &str| e.attributes.get(key).cloned().unwrap_or_default();
+            let id = |e: &Element, key: &str| {
+                attr(e, key)
+                    .parse::<u64>()
+                    .map_err(|_| JfrParseError::Malformed("type without an id"))
+            };
+            let fields = class
+                .children
+                .iter()
+                .filter(|c| c.name == "field")
+                .map(|f| {
+                    Ok(Field {
+                        name: attr(f, "name"),
+                        class: id(f, "class")?,
+                        pool: attr(f, "constantPool") == "true",
+                        array: attr(f, "dimension") == "1",
+                    })
+                })
+                .collect::<Result<_, JfrParseError>>()?;
+            let name = attr(class, "name");
+            let class_id = id(class, "id")?;
+            if name == "java.lang.String" {
+                self.string_class = class_id;
+            }
+            self.classes.insert(class_id, Class { name, fields });
+        }
+        Ok(())
+    }
+
+    /// Store a checkpoint event's constant pool entries.
+    fn checkpoint(&mut self, r: &mut Reader<'_>) -> Result<(), JfrParseError> {
+        r.long()?; // start time
+        r.long()?; // duration
+        r.long()?; // delta to the previous checkpoint
+        r.byte()?; // checkpoint kind
+        for _ in 0..r.count()? {
+            let class = r.long()? as u64;
+            for _ in 0..r.count()? {
+                let key = r.long()?;
+                let value = self.value(r, class, 0)?;
+                self.pools.insert((class, key), value);
+            }
+        }
+        Ok(())
+    }
+
+    fn value(&self, r: &mut Reader<'_>, class: u64, depth: usize) -> Result<Value, JfrParseError> {
+        if depth > MAX_DEPTH {
+            return Err(JfrParseError::Malformed("value nested too deeply"));
+        }
+        let Some(c) = self.classes.get(&class) else {
+            return Err(JfrParseError::Malformed("value of an undeclared type"));
+        };
+        Ok(match c.name.as_str() {
+            "boolean" | "byte" => Value::Int(i64::from(r.byte()? as i8)),
+            "short" | "char" => Value::Int(i64::from(r.short()?)),
+            "int" => Value::Int(i64::from(r.int()?)),
+            "long" => Value::Int(r.long()?),
+            "float" => Value::Float(f64::from(f32::from_bits(r.raw_u32()?))),
+            "double" => Value::Float(f64::from_bits(r.raw_u64()?)),
+            "java.lang.String" => r.string(self.string_class)?,
+            _ => {
+                let mut values = Vec::with_capacity(c.fields.len());
+                for field in &c.fields {
+                    values.push(if field.array {
+                        let mut items = Vec::new();
+                        for _ in 0..r.count()? {
+                            items.push(self.field(r, field, depth)?);
+                        }
+                        Value::Array(items)
+                    } else {
+                        self.field(r, field, depth)?
+                    });
+                }
+                Value::Object(class, values)
+            }
+        })
+    }
+
+    fn field(
+        &self,
+        r: &mut Reader<'_>,
+        field: &Field,
+        depth: usize,
+    ) -> Result<Value, JfrParseError> {
+        if field.pool {
+            Ok(Value::Ref(field.class, r.long()?))
+        } else {
+            self.value(r, field.class, depth + 1)
+        }
+    }
+
+    /// `value`, following constant pool references.
+    fn resolve<'v>(&'v self, mut value: &'v Value) -> &'v Value {
+        for _ in 0..MAX_DEPTH {
+            let Value::Ref(class, key) = value else {
+                return value;
+            };
+            match self.pools.get(&(*class, *key)) {
+                Some(v) => value = v,
+                None => return &NULL,
+            }
+        }
+        &NULL
+    }
+
+    /// Field `name` of the object `value` refers to.
+    fn get<'v>(&'v self, value: &'v Value, name: &str) -> &'v Value {
+        let Value::Object(class, values) = self.resolve(value) else {
+            return &NULL;
+        };
+        self.classes[class]
+            .fields
+            .iter()
+            .position(|f| f.name == name)
+            .and_then(|i| values.get(i))
+            .map_or(&NULL, |v| self.resolve(v))
+    }
+
+    fn int(&self, value: &Value) -> Option<i64> {
+        match self.resolve(value) {
+            Value::Int(i) => Some(*i),
+            Value::Float(f) => Some(*f as i64),
+            _ => None,
+        }
+    }
+
+    /// A string, or the string a single-field wrapper such as a symbol or
+    /// a GC name holds.
+    fn text<'v>(&'v self, value: &'v Value) -> Option<&'v str> {
+        match self.resolve(value) {
+            Value::Str(s) => Some(s),
+            Value::Object(_, values) if values.len() == 1 => self.text(&values[0]),
+            _ => None,
+        }
+    }
+}
+
+/// A Java thread as `(id, name)`.
+fn thread(chunk: &Chunk, value: &Value) -> Option<(u64, String)> {
+    let id = chunk
+        .int(chunk.get(value, "javaThreadId"))
+        .filter(|&id| id > 0)
+        .or_else(|| chunk.int(chunk.get(value, "osThreadId")))?;
+    let name = chunk
+        .text(chunk.get(value, "javaName"))
+        .or_else(|| chunk.text(chunk.get(value, "osName")))
+        .map_or_else(|| format!("Thread {id}"), str::to_string);
+    Some((id as u64, name))
+}
+
+/// A class's name in source form (`java.lang.String`).
+fn class_name(chunk: &Chunk, class: &Value) -> Option<String> {
+    Some(chunk.text(chunk.get(class, "name"))?.replace('/', "."))
+}
+
+#[derive(Default)]
+struct Recording {
+    locations: Vec<String>,
+    location_index: HashMap<String, u32>,
+    thread_names: BTreeMap<u64, String>,
+    threads: BTreeMap<u64, Vec<Sample>>,
+    markers: Vec<Marker>,
+    async_spans: Vec<AsyncSpan>,
+}
+
+impl Recording {
+    fn chunk(
+        &mut self,
+        data: &[u8],
+        metadata: usize,
+        clock: Clock,
+        compressed: bool,
+    ) -> Result<(), JfrParseError> {
+        let mut chunk = Chunk::default();
+        let event = data
+            .get(metadata..)
+            .filter(|_| metadata >= HEADER_SIZE)
+            .ok_or(JfrParseError::Malformed("metadata offset out of range"))?;
+        chunk.metadata(event, compressed)?;
+
+        // Events may refer to pools written after them, so decode them
+        // once every checkpoint is in.
+        let mut events = Vec::new();
+        let mut r = Reader::new(&data[HEADER_SIZE..], compressed);
+        while !r.is_empty() {
+            let start = r.position();
+            let size = r.int()?;
+            let header = r.position() - start;
+            let body = usize::try_from(size)
+                .ok()
+                .and_then(|n| n.checked_sub(header))
+                .filter(|&n| n > 0)
+                .and_then(|n| r.bytes(n).ok())
+                .ok_or(JfrParseError::Malformed("event size out of range"))?;
+            let mut body = Reader::new(body, compressed);
+            let kind = body.long()? as u64;
+            match kind {
+                METADATA_EVENT => {}
+                CHECKPOINT_EVENT => chunk.checkpoint(&mut body)?,
+                _ => events.push((kind, body)),
+            }
+        }
+
+        for (kind, mut body) in events {
+            let name = match chunk.classes.get(&kind) {
+                Some(class) => class.name.as_str(),
+                None => continue,
+            };
+            if !matches!(
+                name,
+                "jdk.ExecutionSample"
+                    | "jdk.GarbageCollection"
+                    | "jdk.JavaMonitorWait"
+                    | "jdk.JavaMonitorEnter"
+            ) {
+                continue;
+            }
+            let name = name.to_string();
+            let event = chunk.value(&mut body, kind, 0)?;
+            self.event(&chunk, &name, &event, clock);
+        }
+        Ok(())
+    }
+
+    fn event(&mut self, chunk: &Chunk, name: &str, event: &Value, clock: Clock) {
+        let Some(ticks) = chunk.int(chunk.get(event, "startTime")) else {
+            return;
+        };
+        let start = clock.us(ticks);
+        let end = start
+            + chunk
+                .int(chunk.get(event, "duration"))
+                .map_or(0.0, |d| clock.duration_us(d));
+        match name {
+            "jdk.ExecutionSample" => {
+                let Some((tid, thread_name)) = thread(chunk, chunk.get(event, "sampledThread"))
+                else {
+                    return;
+                };
+                let Value::Array(frames) = chunk.get(chunk.get(event, "stackTrace"), "frames")
+                else {
+                    return;
+                };
+                // Stack traces run from the leaf out.
+                let stack: Vec<u32> = frames
+                    .iter()
+                    .rev()
+                    .map(|frame| {
+                        let method = chunk.get(frame, "method");
+                        let class = class_name(chunk, chunk.get(method, "type"));
+                        let method = chunk.text(chunk.get(method, "name")).unwrap_or("?");
+                        let name = match class {
+                            Some(class) => format!("{class}.{method}"),
+                            None => method.to_string(),
+                        };
+                        self.location(name)
+                    })
+                    .collect();
+                if stack.is_empty() {
+                    return;
+                }
+                self.thread_names.insert(tid, thread_name);
+                self.threads
+                    .entry(tid)
+                    .or_default()
+                    .push(Sample { ts: start, stack });
+            }
+            "jdk.GarbageCollection" => {
+                let gc = chunk.text(chunk.get(event, "name")).unwrap_or("GC");
+                let id = chunk.int(chunk.get(event, "gcId")).unwrap_or_default();
+                let span = match chunk.text(chunk.get(event, "cause")) {
+                    Some(cause) => format!("{gc}: {cause}"),
+                    None => gc.to_string(),
+                };
+                self.markers.push(Marker {
+                    ts: start,
+                    name: gc.into(),
+                    scope: MarkerScope::Global,
+                    category: Some("gc".into()),
+                });
+                self.async_spans.push(AsyncSpan {
+                    id: format!("gc-{id}").into(),
+                    name: span.into(),
+                    cat: Some("gc".into()),
+                    start,
+                    end,
+                    pid: 0,
+                    tid: 0,
+                });
+            }
+            _ => {
+                let tid = thread(chunk, chunk.get(event, "eventThread")).map_or(0, |(id, _)| id);
+                let monitor = class_name(chunk, chunk.get(event, "monitorClass"))
+                    .unwrap_or_else(|| "?".to_string());
+                let action = if name == "jdk.JavaMonitorWait" {
+                    "Monitor wait"
+                } else {
+                    "Monitor enter"
+                };
+                self.async_spans.push(AsyncSpan {
+                    id: format!("monitor-{}", self.async_spans.len()).into(),
+                    name: format!("{action}: {monitor}").into(),
+                    cat: Some("lock".into()),
+                    start,
+                    end,
+                    pid: 0,
+                    tid,
+                });
+            }
+        }
+    }
+
+    fn location(&mut self, name: String) -> u32 {
+        if let Some(&id) = self.location_index.get(&name) {
+            return id;
+        }
+        let id = self.locations.len() as u32;
+        self.location_index.insert(name.clone(), id);
+        self.locations.push(name);
+        id
+    }
+
+    /// A call tree of every sample under a node per thread, with the
+    /// samples in time order.
+    fn cpu_samples(&self) -> Option<CpuSamples> {
+        if self.threads.is_empty() {
+            return None;
+        }
+        let node = |id: u32, parent: Option<u32>, name: &str| CpuNode {
+            id,
+            parent,
+            function_name: name.into(),
+            script_id: 0,
+            source: None,
+        };
+        let mut nodes = vec![node(1, None, "(root)")];
+        let mut children: HashMap<(u32, u32), u32> = HashMap::new();
+        let mut samples: Vec<(f64, u32)> = Vec::new();
+        for (tid, thread) in &self.threads {
+            let thread_node = nodes.len() as u32 + 1;
+            nodes.push(node(thread_node, Some(1), &self.thread_names[tid]));
+            for sample in thread {
+                let mut parent = thread_node;
+                for &location in &sample.stack {
+                    parent = *children.entry((parent, location)).or_insert_with(|| {
+                        let id = nodes.len() as u32 + 1;
+                        nodes.push(node(id, Some(parent), &self.locations[location as usize]));
+                        id
+                    });
+                }
+                samples.push((sample.ts, parent));
+            }
+        }
+        samples.sort_by(|a, b| a.0.total_cmp(&b.0));
+        Some(CpuSamples {
+            nodes,
+            samples: samples.iter().map(|&(_, node)| node).collect(),
+            timestamps: samples.iter().map(|&(ts, _)| ts).collect(),
+            sample_interval_us: None,
+        })
+    }
+
+    fn finish(self) -> Result<Profile, JfrParseError> {
+        if self.threads.is_empty() && self.async_spans.is_empty() {
+            return Err(JfrParseError::Empty);
+        }
+        let cpu_samples = self.cpu_samples();
+        let mut frames: Vec<Frame> = Vec::new();
+        for (tid, mut samples) in self.threads {
+            let thread = &self.thread_names[&tid];
+            stitch_samples(&mut frames, &mut samples, None, |location| Frame {
+                name: self.locations[location as usize].clone(),
+                thread: Some(thread.clone()),
+                tid: Some(tid),
+                ..Frame::default()
+            });
+        }
+
+        let starts = frames.iter().map(|f| f.start);
+        let ends = frames.iter().map(|f| f.end);
+        let start_time = starts
+            .chain(self.async_spans.iter().map(|s| s.start))
+            .fold(f64::INFINITY, f64::min);
+        let end_time = ends
+            .chain(self.async_spans.iter().map(|s| s.end))
+            .fold(f64::NEG_INFINITY, f64::max);
+        let mut profile = Profile::new(
+            ProfileMetadata {
+                name: None,
+                start_time,
+                end_time,
+                format: "jfr".to_string(),
+                time_domain: Some(TimeDomain {
+                    clock_kind: ClockKind::WallClock,
+                    origin_label: Some("JFR".into()),
+                    navigation_start_us: None,
+                }),
+                value_unit: None,
+                sample_interval_us: None,
+            },
+            frames,
+        );
+        profile.cpu_samples = cpu_samples;
+        profile.markers = self.markers;
+        profile.async_spans = self.async_spans;
+        Ok(profile)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Writer for building JFR chunks by hand, with compressed integers.
+    #[derive(Default)]
+    struct Out(Vec<u8>);
+
+    impl Out {
+        fn varint(mut self, mut v: u64) -> Self {
+            while v >= 0x80 {
+                self.0.push(v as u8 | 0x80);
+                v >>= 7;
+            }
+            self.0.push(v as u8);
+            self
+        }
+
+        fn string(mut self, s: &str) -> Self {
+            self.0.push(3);
+            let mut out = self.varint(s.len() as u64);
+            out.0.extend_from_slice(s.as_bytes());
+            out
+        }
+
+        fn bytes(mut self, v: &[u8]) -> Self {
+            self.0.extend_from_slice(v);
+            self
+        }
+
+        /// An event with its size padded to four bytes, as the JDK writes it.
+        fn event(mut self, kind: u64, body: &Out) -> Self {
+            let body = Out::default().varint(kind).bytes(&body.0);
+            let size = body.0.len() as u32 + 4;
+            for shift in [0, 7, 14] {
+                self.0.push((size >> shift) as u8 & 0x7f | 0x80);
+            }
+            self.0.push((size >> 21) as u8 & 0x7f);
+            self.bytes(&body.0)
+        }
+    }
+
+    /// A metadata element: name, attributes, children.
+    struct El(&'static str, Vec<(&'static str, String)>, Vec<El>);
+
+    fn write(el: &El, strings: &mut Vec<String>, out: Out) -> Out {
+        let mut index = |s: &str| {
+            let i = strings.iter().position(|x| x == s).unwrap_or_else(|| {
+                strings.push(s.to_string());
+                strings.len() - 1
+            });
+            i as u64
+        };
+        let mut out = out.varint(index(el.0)).varint(el.1.len() as u64);
+        for (key, value) in &el.1 {
+            out = out.varint(index(key)).varint(index(value));
+        }
+        out = out.varint(el.2.len() as u64);
+        for child in &el.2 {
+            out = write(child, strings, out);
+        }
+        out
+    }
+
+    /// A class element; fields are `(name, type, constant pool, array)`.
+    fn class(id: u64, name: &str, fields: &[(&'static str, u64, bool, bool)]) -> El {
+        let fields = fields
+            .iter()
+            .map(|&(name, class, pool, array)| {
+                let mut attrs = vec![("name", name.to_string()), ("class", class.to_string())];
+                if pool {
+                    attrs.push(("constantPool", "true".into()));
+                }
+                if array {
+                    attrs.push(("dimension", "1".into()));
+                }
+                El("field", attrs, vec![])
+            })
+            .collect();
+        El(
+            "class",
+            vec![("id", id.to_string()), ("name", name.to_string())],
+            fields,
+        )
+    }
+
+    const LONG: u64 = 20;
+    const INT: u64 = 21;
+    const STRING: u64 = 23;
+    const THREAD: u64 = 30;
+    const STACK_TRACE: u64 = 31;
+    const STACK_FRAME: u64 = 32;
+    const METHOD: u64 = 33;
+    const CLASS: u64 = 34;
+    const SYMBOL: u64 = 35;
+    const GC_NAME: u64 = 36;
+    const EXECUTION_SAMPLE: u64 = 100;
+    const GARBAGE_COLLECTION: u64 = 101;
+    const MONITOR_WAIT: u64 = 102;
+
+    fn metadata() -> Out {
+        let classes = vec![
+            class(LONG, "long", &[]),
+            class(INT, "int", &[]),
+            class(STRING, "java.lang.String", &[]),
+            class(
+                THREAD,
+                "java.lang.Thread",
+                &[
+                    ("javaName", STRING, false, false),
+                    ("javaThreadId", LONG, false, false),
+                ],
+            ),
+            class(
+                STACK_TRACE,
+                "jdk.types.StackTrace",
+                &[("frames", STACK_FRAME, false, true)],
+            ),
+            class(
+                STACK_FRAME,
+                "jdk.types.StackFrame",
+                &[
+                    ("method", METHOD, true, false),
+                    ("lineNumber", INT, false, false),
+                ],
+            ),
+            class(
+                METHOD,
+                "jdk.types.Method",
+                &[("type", CLASS, true, false), ("name", SYMBOL, true, false)],
+            ),
+            class(CLASS, "java.lang.Class", &[("name", SYMBOL, true, false)]),
+            class(
+                SYMBOL,
+                "jdk.types.Symbol",
+                &[("string", STRING, false, false)],
+            ),
+            class(
+                GC_NAME,
+                "jdk.types.GCName",
+                &[("name", STRING, false, false)],
+            ),
+            class(
+                EXECUTION_SAMPLE,
+                "jdk.ExecutionSample",
+                &[
+                    ("startTime", LONG, false, false),
+                    ("sampledThread", THREAD, true, false),
+                    ("stackTrace", STACK_TRACE, true, false),
+                ],
+            ),
+            class(
+                GARBAGE_COLLECTION,
+                "jdk.GarbageCollection",
+                &[
+                    ("startTime", LONG, false, false),
+                    ("duration", LONG, false, false),
+                    ("gcId", INT, false, false),
+                    ("name", GC_NAME, true, false),
+                ],
+            ),
+            class(
+                MONITOR_WAIT,
+                "jdk.JavaMonitorWait",
+                &[
+                    ("startTime", LONG, false, false),
+                    ("duration", LONG, false, false),
+                    ("eventThread", THREAD, true, false),
+                    ("monitorClass", CLASS, true, false),
+                ],
+            ),
+        ];
+        let root = El(
+            "root",
+            vec![],
+            vec![
+                El("metadata", vec![], classes),
+                El("region", vec![], vec![]),
+            ],
+        );
+        let mut strings = Vec::new();
+        let tree = write(&root, &mut strings, Out::default());
+        let mut out = Out::default()
+            .varint(0)
+            .varint(0)
+            .varint(1)
+            .varint(strings.len() as u64);
+        for s in &strings {
+            out = out.string(s);
+        }
+        out.bytes(&tree.0)
+    }
+
+    fn checkpoint() -> Out {
+        let frame = |method: u64, line: u64| Out::default().varint(method).varint(line);
+        let stack = |frames: &[Out]| {
+            let mut out = Out::default().varint(frames.len() as u64);
+            for f in frames {
+                out = out.bytes(&f.0);
+            }
+            out
+        };
+        Out::default()
+            .bytes(&[0, 0, 0, 0])
+            .varint(6)
+            // Symbols
+            .varint(SYMBOL)
+            .varint(3)
+            .varint(1)
+            .string("com/example/App")
+            .varint(2)
+            .string("main")
+            .varint(3)
+            .string("work")
+            .varint(CLASS)
+            .varint(1)
+            .varint(1)
+            .varint(1)
+            .varint(METHOD)
+            .varint(2)
+            .varint(1)
+            .varint(1)
+            .varint(2)
+            .varint(2)
+            .varint(1)
+            .varint(3)
+            // Stack traces, leaf first
+            .varint(STACK_TRACE)
+            .varint(2)
+            .varint(1)
+            .bytes(&stack(&[frame(2, 12), frame(1, 5)]).0)
+            .varint(2)
+            .bytes(&stack(&[frame(1, 6)]).0)
+            .varint(THREAD)
+            .varint(1)
+            .varint(1)
+            .string("main")
+            .varint(7)
+            .varint(GC_NAME)
+            .varint(1)
+            .varint(1)
+            .string("G1New")
+    }
+
+    /// A one-chunk recording of `events`, ticking in µs from 1s.
+    fn recording(events: &Out) -> Vec<u8> {
+        let metadata_offset = HEADER_SIZE + events.0.len();
+        let body = Out::default()
+            .bytes(&events.0)
+            .event(METADATA_EVENT, &metadata());
+        let size = (HEADER_SIZE + body.0.len()) as u64;
+        let mut out = MAGIC.to_vec();
+        out.extend_from_slice(&2u16.to_be_bytes());
+        out.extend_from_slice(&0u16.to_be_bytes());
+        for v in [
+            size,
+            HEADER_SIZE as u64,
+            metadata_offset as u64,
+            1_000_000_000,
+            0,
+            0,
+            1_000_000,
+        ] {
+            out.extend_from_slice(&v.to_be_bytes());
+        }
+        out.extend_from_slice(&FEATURE_COMPRESSED_INTS.to_be_bytes());
+        out.extend_from_slice(&body.0);
+        out
+    }
+
+    #[test]
+    fn samples_gc_and_monitor_events() {
+        let sample = |ticks: u64, stack: u64| Out::default().varint(ticks).varint(1).varint(stack);
+        let events = Out::default()
+            .event(CHECKPOINT_EVENT, &checkpoint())
+            .event(EXECUTION_SAMPLE, &sample(1_000, 1))
+            .event(EXECUTION_SAMPLE, &sample(2_000, 1))
+            .event(EXECUTION_SAMPLE, &sample(3_000, 2))
+            .event(
+                GARBAGE_COLLECTION,
+                &Out::default().varint(1_500).varint(200).varint(4).varint(1),
+            )
+            .event(
+                MONITOR_WAIT,
+                &Out::default().varint(2_500).varint(300).varint(1).varint(1),
+            );
+        let profile = parse_jfr(&recording(&events)).unwrap();
+
+        let base = 1_000_000.0;
+        let frames: Vec<(&str, f64, f64, u32)> = profile
+            .frames
+            .iter()
+            .map(|f| (f.name.as_str(), f.start - base, f.end - base, f.depth))
+            .collect();
+        assert_eq!(
+            frames,
+            [
+                ("com.example.App.main", 1_000.0, 4_000.0, 0),
+                ("com.example.App.work", 1_000.0, 3_000.0, 1),
+            ]
+        );
+        assert!(
+            profile
+                .frames
+                .iter()
+                .all(|f| f.thread.as_deref() == Some("main") && f.tid == Some(7))
+        );
+
+        let cpu = profile.cpu_samples.as_ref().unwrap();
+        let names: Vec<&str> = cpu.nodes.iter().map(|n| &*n.function_name).collect();
+        assert_eq!(
+            names,
+            [
+                "(root)",
+                "main",
+                "com.example.App.main",
+                "com.example.App.work"
+            ]
+        );
+        assert_eq!(cpu.samples, [4, 4, 3]);
+
+        assert_eq!(profile.markers.len(), 1);
+        assert_eq!(&*profile.markers[0].name, "G1New");
+        let spans: Vec<(&str, &str, f64, f64, u64)> = profile
+            .async_spans
+            .iter()
+            .map(|s| {
+                (
+                    &*s.name,
+                    s.cat.as_deref().unwrap_or_default(),
+                    s.start - base,
+                    s.end - base,
+                    s.tid,
+                )
+            })
+            .collect();
+        assert_eq!(
+            spans,
+            [
+                ("G1New", "gc", 1_500.0, 1_700.0, 0),
+                ("Monitor wait: com.example.App", "lock", 2_500.0, 2_800.0, 7),
+            ]
+        );
+    }
+
+    #[test]
+    fn detection_and_errors() {
+        let empty = recording(&Out::default());
+        assert!(is_jfr(&empty));
+        assert!(matches!(parse_jfr(&empty), Err(JfrParseError::Empty)));
+        assert!(matches!(
+            parse_jfr(b"{\"traceEvents\":[]}"),
+            Err(JfrParseError::NotJfr)
+        ));
+        assert!(matches!(
+            parse_jfr(&empty[..40]),
+            Err(JfrParseError::Malformed(_))
+        ));
+    }
+
+    #[test]
+    fn corrupt_start_ticks_do_not_overflow() {
+        let sample = Out::default().varint(1_000).varint(1).varint(1);
+        let events = Out::default()
+            .event(CHECKPOINT_EVENT, &checkpoint())
+            .event(EXECUTION_SAMPLE, &sample);
+        let mut data = recording(&events);
+        // The top byte of the chunk's start ticks.
+        data[48] = 0x80;
+        let profile = parse_jfr(&data).unwrap();
+        assert!(profile.frames.iter().all(|f| f.start.is_finite()));
+    }
+}
@@ -0,0 +1,464 @@
+//! Resource limits for parsing untrusted input.
+//!
+//! A corrupt or hostile file must not take the host down, least of all a
+//! WASM instance with a few GiB of address space. Input past the memory
+//! budget is cut at a line boundary before parsing, which keeps text
+//! formats and one-event-per-line Chrome traces loadable. Chrome traces and
+//! collapsed stacks, the formats large inputs mostly come in, enforce the
+//! event, depth and string limits while they read, so a hostile file never
+//! builds its full profile; what other parsers return is trimmed to the
+//! limits afterwards. Compressed input is inflated within the same memory
+//! budget, so a few bytes of gzip cannot expand past it. What was left out is recorded in
+//! `ProfileMeta::limits_hit`.
+
+use std::borrow::Cow;
+use std::io::Read;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use flame_cat_protocol::{LimitsHit, Span, VisualProfile};
+use thiserror::Error;
+
+use super::{FormatHint, ParseError, parse_visual_within};
+
+/// Parsed JSON takes several times the memory of its text.
+const PARSE_EXPANSION: usize = 8;
+
+/// Upper bounds for a limited parse.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Limits {
+    /// Most events kept; parsers stop collecting past it, and spans after
+    /// it (by start time) are dropped from what other parsers return.
+    pub max_events: usize,
+    /// Deepest span level kept.
+    pub max_depth: u32,
+    /// Longest span name kept, in bytes.
+    pub max_string_len: usize,
+    /// Rough peak memory allowed for parsing, in bytes.
+    pub max_memory: usize,
+}
+
+impl Limits {
+    /// No limits at all, for trusted input.
+    pub const NONE: Self = Self {
+        max_events: usize::MAX,
+        max_depth: u32::MAX,
+        max_string_len: usize::MAX,
+        max_memory: usize::MAX,
+    };
+
+    /// Most input bytes parsed, leaving room for what parsing expands
+    /// them to within the memory budget.
+    pub(crate) fn input_budget(&self) -> usize {
+        self.max_memory / PARSE_EXPANSION
+    }
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            max_events: 5_000_000,
+            max_depth: 1_024,
+            max_string_len: 4_096,
+            max_memory: 1 << 30,
+        }
+    }
+}
+
+/// [`super::parse_auto_visual`] within `limits`, returning what fits.
+pub fn parse_auto_with_limits(data: &[u8], limits: &Limits) -> Result<VisualProfile, ParseError> {
+    let mut profiles = parse_visual_with_limits(data, &FormatHint::default(), limits)?;
+    Ok(profiles.remove(0))
+}
+
+/// [`parse_visual_with_hint`] within `limits`, returning what fits.
+pub fn parse_visual_with_limits(
+    data: &[u8],
+    hint: &FormatHint,
+    limits: &Limits,
+) -> Result<Vec<VisualProfile>, ParseError> {
+    parse_limited(data, hint, limits, None)
+}
+
+/// [`parse_visual_with_limits`] that gives up with [`ParseError::Cancelled`]
+/// once another thread sets `cancel`. Chrome traces and collapsed stacks
+/// check it between events, other formats once they are parsed.
+pub fn parse_visual_cancellable(
+    data: &[u8],
+    hint: &FormatHint,
+    limits: &Limits,
+    cancel: &AtomicBool,
+) -> Result<Vec<VisualProfile>, ParseError> {
+    parse_limited(data, hint, limits, Some(cancel))
+}
+
+/// Why [`decompress_within`] gave up.
+#[derive(Debug, Error)]
+pub enum DecompressError {
+    #[error("{0}")]
+    Io(#[from] std::io::Error),
+    #[error("decompresses to over the {limit} byte parse budget")]
+    TooLarge { limit: usize },
+}
+
+/// Read a decompressing `reader` to its end, giving up once the output
+/// passes the parse budget of `limits`.
+pub(crate) fn decompress_within(
+    reader: impl Read,
+    limits: &Limits,
+) -> Result<Vec<u8>, DecompressError> {
+    let budget = limits.input_budget();
+    let mut out = Vec::new();
+    reader.take(budget as u64 + 1).read_to_end(&mut out)?;
+    if out.len() > budget {
+        return Err(DecompressError::TooLarge { limit: budget });
+    }
+    Ok(out)
+}
+
+/// Whether `cancel` has been set.
+pub(crate) fn cancelled(cancel: Option<&AtomicBool>) -> bool {
+    cancel.is_some_and(|c| c.load(Ordering::Relaxed))
+}
+
+fn parse_limited(
+    data: &[u8],
+    hint: &FormatHint,
+    limits: &Limits,
+    cancel: Option<&AtomicBool>,
+) -> Result<Vec<VisualProfile>, ParseError> {
+    let budget = limits.input_budget();
+    let too_large = || ParseError::TooLarge {
+        size: data.len(),
+        limit: budget,
+    };
+    let (input, bytes_skipped) = if data.len() <= budget {
+        (Cow::Borrowed(data), 0)
+    } else if crate::cache::is_cache(data) {
+        return Err(too_large());
+    } else {
+        let (kept, parsed) = cut_input(data, budget).ok_or_else(too_large)?;
+        (Cow::Owned(kept), data.len() - parsed)
+    };
+    let mut profiles = match parse_visual_within(&input, hint, limits, cancel) {
+        _ if cancelled(cancel) => return Err(ParseError::Cancelled),
+        Ok(profiles) => profiles,
+        Err(_) if bytes_skipped > 0 => return Err(too_large()),
+        Err(e) => return Err(e),
+    };
+    for profile in &mut profiles {
+        let trimmed = limit_profile(profile, limits);
+        profile.meta.limits_hit += LimitsHit {
+            bytes_skipped,
+            ..trimmed
+        };
+    }
+    Ok(profiles)
+}
+
+/// The part of `data` before the last line break within `budget` bytes,
+/// with a JSON array or object closed again so Chrome's one event per line
+/// layout still parses, and how many input bytes it covers. `None` without
+/// a line break to cut at.
+fn cut_input(data: &[u8], budget: usize) -> Option<(Vec<u8>, usize)> {
+    let end = data[..budget].iter().rposition(|&b| b == b'\n')?;
+    let mut kept = data[..end].to_vec();
+    let json = match data.iter().find(|b| !b.is_ascii_whitespace()) {
+        Some(b'[') => Some(&b"]"[..]),
+        Some(b'{') => Some(&b"]}"[..]),
+        _ => None,
+    };
+    if let Some(close) = json {
+        while kept
+            .last()
+            .is_some_and(|&b| b == b',' || b.is_ascii_whitespace())
+        {
+            kept.pop();
+        }
+        kept.extend_from_slice(close);
+    }
+    Some((kept, end))
+}
+
+/// `name` cut at a character boundary and marked with an ellipsis, in all
+/// at most `max_len` bytes, or `None` if it fits.
+pub(crate) fn cut_string(name: &str, max_len: usize) -> Option<String> {
+    if name.len() <= max_len {
+        return None;
+    }
+    let mut end = max_len.saturating_sub('…'.len_utf8());
+    while !name.is_char_boundary(end) {
+        end -= 1;
+    }
+    Some(format!("{}…", &name[..end]))
+}
+
+/// Drop spans past the event and depth limits and cut long names, for
+/// parsers that do not enforce the limits themselves. Spans that lose
+/// children get their self time back.
+fn limit_profile(profile: &mut VisualProfile, limits: &Limits) -> LimitsHit {
+    let mut hit = LimitsHit::default();
+    // Keep the earliest spans: a parent starts no later and sits shallower
+    // than its children, so cutting in (start, depth) order never orphans a
+    // kept span. The id breaks the remaining ties, keeping exactly
+    // `max_events` spans however many start together.
+    let order = |s: &Span| (s.start, s.depth, s.id);
+    let cmp = |a: &(f64, u32, u64), b: &(f64, u32, u64)| {
+        a.0.total_cmp(&b.0).then(a.1.cmp(&b.1)).then(a.2.cmp(&b.2))
+    };
+    let cutoff = if profile.span_count() > limits.max_events {
+        let mut keys: Vec<_> = profile.all_spans().map(order).collect();
+        let (_, nth, _) = keys.select_nth_unstable_by(limits.max_events, cmp);
+        Some(*nth)
+    } else {
+        None
+    };
+    let mut trimmed = false;
+    for thread in &mut profile.threads {
+        let before = thread.spans.len();
+        thread.spans.retain(|s| {
+            if cutoff.is_some_and(|cutoff| cmp(&order(s), &cutoff).is_ge()) {
+                hit.events_dropped += 1;
+                false
+            } else if s.depth > limits.max_depth {
+                hit.too_deep += 1;
+                false
+            } else {
+                true
+            }
+        });
+        if thread.spans.len() != before {
+            thread.compute_max_depth();
+            trimmed = true;
+        }
+        for span in &mut thread.spans {
+            if let Some(name) = cut_string(&span.name, limits.max_string_len) {
+                span.name = name.into();
+                hit.strings_cut += 1;
+            }
+        }
+    }
+    if trimmed {
+        crate::transform::compute_self_times(profile);
+    }
+    hit
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use super::*;
+
+    #[test]
+    fn trims_parsed_spans_to_the_limits() {
+        let long = "x".repeat(50);
+        let data = format!("main;a;b;c 1\nmain;{long} 1\nmain;d 1\n");
+        let limits = Limits {
+            max_events: 6,
+            max_depth: 2,
+            max_string_len: 10,
+            ..Default::default()
+        };
+        let profile = parse_auto_with_limits(data.as_bytes(), &limits).unwrap();
+        let hit = profile.meta.limits_hit;
+        assert_eq!(
+            (hit.events_dropped, hit.too_deep, hit.strings_cut),
+            (2, 1, 1)
+        );
+        assert!(hit.any());
+        assert!(
+            profile
+                .all_spans()
+                .all(|s| s.depth <= 2 && s.name.len() <= 13)
+        );
+        // Each line is one sample of its own stack: the third sample is
+        // past the event limit and `c` is too deep.
+        assert_eq!(profile.span_count(), 5);
+
+        let whole = parse_auto_with_limits(data.as_bytes(), &Limits::default()).unwrap();
+        assert!(!whole.meta.limits_hit.any());
+    }
+
+    #[test]
+    fn keeps_spans_up_to_the_limit_when_many_start_together() {
+        let mut profile = crate::testing::synth::generate(&crate::testing::synth::SynthConfig {
+            threads: 2,
+            spans_per_thread: 500,
+            ..Default::default()
+        });
+        // Every span starts at once, like instants or a burst of samples.
+        for span in profile.threads.iter_mut().flat_map(|t| &mut t.spans) {
+            span.start = 0.0;
+        }
+        let limits = Limits {
+            max_events: 300,
+            ..Limits::NONE
+        };
+        let hit = limit_profile(&mut profile, &limits);
+        assert_eq!(profile.span_count(), 300);
+        assert_eq!(hit.events_dropped, 700);
+        for thread in &profile.threads {
+            let ids: std::collections::HashSet<u64> = thread.spans.iter().map(|s| s.id).collect();
+            assert!(
+                thread
+                    .spans
+                    .iter()
+                    .all(|s| s.parent.is_none_or(|p| ids.contains(&p)))
+            );
+        }
+    }
+
+    #[test]
+    fn hostile_input_is_limited_while_parsing() {
+        let limits = Limits {
+            max_events: 100,
+            max_depth: 8,
+            max_string_len: 16,
+            ..Default::default()
+        };
+        let long = "n".repeat(1_000);
+
+        // A stack 10,000 frames deep, then 10,000 samples with long names.
+        let mut folded = format!("{} 5\n", vec!["f"; 10_000].join(";"));
+        for _ in 0..10_000 {
+            folded += &format!("main;{long} 1\n");
+        }
+        let profile =
+            super::super::parse_format("collapsed", folded.as_bytes(), false, &limits, None)
+                .unwrap();
+        // Nine frames of the deep stack and 45 samples of two fit.
+        assert_eq!(profile.frames.len(), 99);
+        let hit = profile.limits_hit;
+        assert_eq!(
+            (hit.events_dropped, hit.too_deep, hit.strings_cut),
+            ((10_000 - 45) * 2, 10_000 - 9, 45)
+        );
+        // The folded frames' sample lands on the deepest one kept.
+        assert_eq!(profile.frames[8].self_time, 5.0);
+
+        // 10,000 nested spans, each named at length.
+        let events: Vec<String> = (0..10_000)
+            .map(|i| {
+                format!(
+                    r#"{{"name":"{long}","ph":"X","ts":{i},"dur":{},"pid":1,"tid":1}}"#,
+                    2 * (10_000 - i)
+                )
+            })
+            .collect();
+        let trace = format!("[{}]", events.join(","));
+        let profile =
+            super::super::parse_format("chrome", trace.as_bytes(), false, &limits, None).unwrap();
+        assert_eq!(profile.frames.len(), 9);
+        assert!(profile.frames.iter().all(|f| f.name.len() <= 16));
+        let hit = profile.limits_hit;
+        assert_eq!(
+            (hit.events_dropped, hit.too_deep, hit.strings_cut),
+            (10_000 - 100, 100 - 9, 100)
+        );
+
+        // The innermost span kept has no children left, so all of it is
+        // its own time.
+        let profile = parse_auto_with_limits(trace.as_bytes(), &limits).unwrap();
+        let deepest = profile.all_spans().find(|s| s.depth == 8).unwrap();
+        assert_eq!(deepest.self_value, deepest.duration());
+        assert_eq!(profile.meta.limits_hit, hit);
+    }
+
+    #[test]
+    fn cancelling_stops_the_parse() {
+        let events: Vec<String> = (0..1_000)
+            .map(|i| format!(r#"{{"name":"e{i}","ph":"X","ts":{i},"dur":1,"pid":1,"tid":1}}"#))
+            .collect();
+        let trace = format!("[{}]", events.join(","));
+        let (hint, limits) = (FormatHint::default(), Limits::default());
+        let cancel = AtomicBool::new(false);
+        let profiles = parse_visual_cancellable(trace.as_bytes(), &hint, &limits, &cancel).unwrap();
+        assert_eq!(profiles[0].span_count(), 1_000);
+
+        cancel.store(true, Ordering::Relaxed);
+        for data in [trace.as_bytes(), b"main;work 5\n"] {
+            assert!(matches!(
+                parse_visual_cancellable(data, &hint, &limits, &cancel),
+                Err(ParseError::Cancelled)
+            ));
+        }
+        // The trace is read no further than its first event, and what was
+        // read is not kept as a partial profile.
+        let Err(super::super::chrome::ChromeParseError::Json(e)) =
+            super::super::chrome::parse_chrome_trace_limited(
+                trace.as_bytes(),
+                &limits,
+                Some(&cancel),
+                true,
+            )
+        else {
+            panic!("cancelled parse loaded a profile");
+        };
+        assert!(e.column() <= events[0].len() + 2, "{e}");
+    }
+
+    #[test]
+    fn trimming_gives_parents_their_self_time_back() {
+        let json = br#"{"events":[{"name":"Frame","start":0,"end":10,"children":[
+            {"name":"Draw","start":2,"end":8,"children":[]}]}]}"#;
+        let limits = Limits {
+            max_depth: 0,
+            ..Default::default()
+        };
+        let profile = parse_auto_with_limits(json, &limits).unwrap();
+        assert_eq!(profile.meta.limits_hit.too_deep, 1);
+        let spans: Vec<_> = profile.all_spans().map(|s| s.self_value).collect();
+        assert_eq!(spans, [10.0]);
+    }
+
+    #[test]
+    fn cuts_oversized_input_at_a_line() {
+        let events: Vec<String> = (0..100)
+            .map(|i| {
+                format!(
+                    r#"{{"name":"e{i}","ph":"X","ts":{},"dur":5,"pid":1,"tid":1}}"#,
+                    i * 10
+                )
+            })
+            .collect();
+        let data = format!("[\n{}\n]", events.join(",\n"));
+        let limits = Limits {
+            max_memory: data.len() / 2 * PARSE_EXPANSION,
+            ..Default::default()
+        };
+        let profile = parse_auto_with_limits(data.as_bytes(), &limits).unwrap();
+        let kept = profile.span_count();
+        assert!((40..60).contains(&kept), "{kept}");
+        assert!(profile.meta.limits_hit.bytes_skipped > 0);
+
+        // A single line cannot be cut.
+        let line = data.replace('\n', "");
+        assert!(matches!(
+            parse_auto_with_limits(line.as_bytes(), &limits),
+            Err(ParseError::TooLarge { .. })
+        ));
+    }
+
+    #[test]
+    fn gzip_inflates_within_the_budget() {
+        let mut gz = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::best());
+        gz.write_all(&vec![0; 1 << 20]).unwrap();
+        let bomb = gz.finish().unwrap();
+        let limits = Limits {
+            max_memory: 64 * 1024,
+            ..Limits::default()
+        };
+        let pprof = FormatHint {
+            format: Some("pprof".to_string()),
+            ..FormatHint::default()
+        };
+        assert!(matches!(
+            parse_visual_with_limits(&bomb, &pprof, &limits),
+            Err(ParseError::Pprof(
+                super::super::pprof::PprofParseError::Gzip(DecompressError::TooLarge {
+                    limit: 8192
+                })
+            ))
+        ));
+    }
+}
@@ -0,0 +1,314 @@
+use flame_cat_protocol::{CounterSample, CounterTrack, CounterUnit, ValueUnit};
+use thiserror::Error;
+
+use crate::model::{Frame, Profile, ProfileMetadata};
+
+#[derive(Debug, Error)]
+pub enum MassifParseError {
+    #[error("invalid UTF-8: {0}")]
+    Utf8(#[from] std::str::Utf8Error),
+    #[error("no snapshots found")]
+    Empty,
+}
+
+/// Name massif gives the root of every heap tree.
+const ALLOC_FNS: &str = "(heap allocation functions)";
+
+/// Returns `true` if `text` is a Valgrind massif profile
+/// (`massif.out.<pid>`).
+pub fn is_massif(text: &str) -> bool {
+    let mut head = text.len().min(64 * 1024);
+    while !text.is_char_boundary(head) {
+        head -= 1;
+    }
+    let head = &text[..head];
+    head.trim_start().starts_with("desc:")
+        && head.lines().any(|l| l.starts_with("snapshot="))
+        && head.lines().any(|l| l.starts_with("mem_heap_B="))
+}
+
+/// Function name of a Valgrind stack entry such as
+/// `0x4005A6: main (app.c:10)`, without the address and location.
+pub(super) fn frame_name(entry: &str) -> &str {
+    let entry = entry.trim();
+    let entry = match entry.split_once(": ") {
+        Some((addr, rest)) if addr.starts_with("0x") => rest,
+        _ => entry,
+    };
+    match entry.rfind(" (") {
+        Some(at) if at > 0 && entry.ends_with(')') => &entry[..at],
+        _ => entry,
+    }
+}
+
+/// One snapshot: its time and heap sizes, and the heap tree of detailed
+/// snapshots as `(depth, bytes, name)` in preorder.
+#[derive(Debug, Default)]
+struct Snapshot {
+    time: f64,
+    heap: f64,
+    extra: f64,
+    stacks: f64,
+    peak: bool,
+    tree: Vec<(usize, f64, String)>,
+}
+
+/// Parse a Valgrind massif profile into a byte-weighted `Profile`.
+///
+/// The heap tree of the peak snapshot (or of the largest detailed one)
+/// becomes allocation stacks weighted by the bytes live at that point.
+/// Massif's trees hang callers under the allocation functions, so each
+/// node's own bytes turn into a stack running from the node down to them.
+/// Heap size, allocator overhead and stack size over every snapshot
+/// become counter tracks, their times spread over the same extent as the
+/// stacks since massif's time axis (instructions, ms or bytes) has no
+/// place in a byte-weighted profile.
+pub fn parse_massif(data: &[u8]) -> Result<Profile, MassifParseError> {
+    let text = std::str::from_utf8(data)?;
+
+    let mut cmd = None;
+    let mut snapshots: Vec<Snapshot> = Vec::new();
+    for line in text.lines() {
+        if let Some(node) = line.trim_start().strip_prefix('n')
+            && let Some((count, node)) = node.split_once(": ")
+            && count.bytes().all(|b| b.is_ascii_digit())
+            && let Some(snapshot) = snapshots.last_mut()
+        {
+            let depth = line.len() - line.trim_start().len();
+            let (bytes, name) = node.split_once(' ').unwrap_or((node, ""));
+            let name = if name.starts_with(ALLOC_FNS) {
+                ALLOC_FNS
+            } else if name.contains("below massif's threshold") {
+                "(below threshold)"
+            } else {
+                frame_name(name)
+            };
+            snapshot
+                .tree
+                .push((depth, bytes.parse().unwrap_or(0.0), name.to_string()));
+            continue;
+        }
+        let Some((key, value)) = line.split_once(['=', ':']) else {
+            continue;
+        };
+        let number = || value.trim().parse().unwrap_or(0.0);
+        match key {
+            "cmd" => cmd = Some(value.trim().to_string()),
+            "snapshot" => snapshots.push(Snapshot::default()),
+            _ => {
+                let Some(snapshot) = snapshots.last_mut() else {
+                    continue;
+                };
+                match key {
+                    "time" => snapshot.time = number(),
+                    "mem_heap_B" => snapshot.heap = number(),
+                    "mem_heap_extra_B" => snapshot.extra = number(),
+                    "mem_stacks_B" => snapshot.stacks = number(),
+                    "heap_tree" => snapshot.peak = value.trim() == "peak",
+                    _ => {}
+                }
+            }
+        }
+    }
+    if snapshots.is_empty() {
+        return Err(MassifParseError::Empty);
+    }
+
+    let tree = snapshots
+        .iter()
+        .filter(|s| !s.tree.is_empty())
+        .max_by(|a, b| a.peak.cmp(&b.peak).then(a.heap.total_cmp(&b.heap)))
+        .map_or(&[][..], |s| s.tree.as_slice());
+    let frames = stacks(tree);
+    let stacked: f64 = frames.iter().map(|f| f.self_time).sum();
+    let end = if stacked > 0.0 {
+        stacked
+    } else {
+        snapshots.iter().map(|s| s.heap).fold(0.0, f64::max)
+    };
+    if end <= 0.0 {
+        return Err(MassifParseError::Empty);
+    }
+
+    let last = snapshots.iter().map(|s| s.time).fold(0.0, f64::max);
+    let scale = if last > 0.0 { end / last } else { 0.0 };
+    let track = |name: &str, value: fn(&Snapshot) -> f64| CounterTrack {
+        name: name.into(),
+        unit: CounterUnit::Bytes,
+        samples: snapshots
+            .iter()
+            .map(|s| CounterSample {
+                ts: s.time * scale,
+                value: value(s),
+            })
+            .collect(),
+    };
+    let mut counters = vec![track("Heap", |s| s.heap)];
+    if snapshots.iter().any(|s| s.extra > 0.0) {
+        counters.push(track("Heap overhead", |s| s.extra));
+    }
+    if snapshots.iter().any(|s| s.stacks > 0.0) {
+        counters.push(track("Stacks", |s| s.stacks));
+    }
+
+    let mut profile = Profile::new(
+        ProfileMetadata {
+            name: cmd,
+            start_time: 0.0,
+            end_time: end,
+            format: "massif".to_string(),
+            time_domain: None,
+            value_unit: Some(ValueUnit::Bytes),
+            sample_interval_us: None,
+        },
+        frames,
+    );
+    profile.counters = counters;
+    Ok(profile)
+}
+
+/// Allocation stacks of a preorder heap tree, outermost caller first, laid
+/// end to end.
+fn stacks(tree: &[(usize, f64, String)]) -> Vec<Frame> {
+    // Bytes of each node's children, to leave its own.
+    let mut children = vec![0.0; tree.len()];
+    let mut path: Vec<usize> = Vec::new();
+    let mut paths = Vec::with_capacity(tree.len());
+    for (i, (depth, bytes, _)) in tree.iter().enumerate() {
+        while path.last().is_some_and(|&p| tree[p].0 >= *depth) {
+            path.pop();
+        }
+        if let Some(&parent) = path.last() {
+            children[parent] += bytes;
+        }
+        path.push(i);
+        paths.push(path.clone());
+    }
+
+    let mut frames: Vec<Frame> = Vec::new();
+    let mut offset = 0.0;
+    for (path, children) in paths.iter().zip(children) {
+        let Some(&node) = path.last() else { continue };
+        let own = tree[node].1 - children;
+        if own <= 0.0 {
+            continue;
+        }
+        let mut parent = None;
+        for (depth, &i) in path.iter().rev().enumerate() {
+            let id = frames.len() as u64;
+            frames.push(Frame {
+                id,
+                name: tree[i].2.clone(),
+                start: offset,
+                end: offset + own,
+                depth: depth as u32,
+                category: None,
+                parent,
+                self_time: 0.0,
+                thread: None,
+                truncated: false,
+                pid: None,
+                tid: None,
+                source: None,
+                binary: None,
+                inlined: vec![],
+            });
+            parent = Some(id);
+        }
+        if let Some(leaf) = frames.last_mut() {
+            leaf.self_time = own;
+        }
+        offset += own;
+    }
+    frames
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MASSIF: &str = "\
+desc: (none)
+cmd: ./app --run
+time_unit: i
+#-----------
+snapshot=0
+#-----------
+time=0
+mem_heap_B=0
+mem_heap_extra_B=0
+mem_stacks_B=0
+heap_tree=empty
+#-----------
+snapshot=1
+#-----------
+time=1000
+mem_heap_B=1200
+mem_heap_extra_B=16
+mem_stacks_B=0
+heap_tree=peak
+n3: 1200 (heap allocation functions) malloc/new/new[], --alloc-fns, etc.
+ n1: 1000 0x4005A6: load (app.c:10)
+  n0: 1000 0x4005F0: main (app.c:20)
+ n0: 150 0x4006A0: main (app.c:22)
+ n0: 50 in 2 places, all below massif's threshold (1.00%)
+#-----------
+snapshot=2
+#-----------
+time=2000
+mem_heap_B=400
+mem_heap_extra_B=8
+mem_stacks_B=0
+heap_tree=empty
+";
+
+    #[test]
+    fn peak_tree_becomes_allocation_stacks() {
+        assert!(is_massif(MASSIF));
+        let profile = parse_massif(MASSIF.as_bytes()).unwrap();
+        assert_eq!(profile.metadata.name.as_deref(), Some("./app --run"));
+        assert_eq!(profile.metadata.value_unit, Some(ValueUnit::Bytes));
+        assert_eq!(profile.metadata.end_time, 1200.0);
+
+        let frames: Vec<_> = profile
+            .frames
+            .iter()
+            .map(|f| (f.name.as_str(), f.depth, f.start, f.end, f.self_time))
+            .collect();
+        assert_eq!(
+            frames,
+            [
+                ("main", 0, 0.0, 1000.0, 0.0),
+                ("load", 1, 0.0, 1000.0, 0.0),
+                (ALLOC_FNS, 2, 0.0, 1000.0, 1000.0),
+                ("main", 0, 1000.0, 1150.0, 0.0),
+                (ALLOC_FNS, 1, 1000.0, 1150.0, 150.0),
+                ("(below threshold)", 0, 1150.0, 1200.0, 0.0),
+                (ALLOC_FNS, 1, 1150.0, 1200.0, 50.0),
+            ]
+        );
+
+        let counters: Vec<_> = profile
+            .counters
+            .iter()
+            .map(|c| {
+                let samples: Vec<_> = c.samples.iter().map(|s| (s.ts, s.value)).collect();
+                (c.name.as_ref(), samples)
+            })
+            .collect();
+        assert_eq!(
+            counters,
+            [
+                ("Heap", vec![(0.0, 0.0), (600.0, 1200.0), (1200.0, 400.0)]),
+                (
+                    "Heap overhead",
+                    vec![(0.0, 0.0), (600.0, 16.0), (1200.0, 8.0)]
+                ),
+            ]
+        );
+        assert!(matches!(
+            parse_massif(b"desc: (none)\n"),
+            Err(MassifParseError::Empty)
+        ));
+    }
+}
@@ -1,16 +1,40 @@
+pub(crate) mod bytes;
+pub mod callgrind;
 pub mod chrome;
 pub mod collapsed;
 pub mod cpuprofile;
+pub mod dhat;
 pub mod ebpf;
 pub mod firefox;
+pub mod ftrace;
+pub mod heaptrack;
+pub mod instruments;
+pub mod jfr;
+pub mod limits;
+pub mod massif;
+pub mod nettrace;
+pub mod perf;
+pub mod perfetto;
 pub mod pix;
 pub mod pprof;
+mod protobuf;
 pub mod react;
 pub mod speedscope;
+pub mod systrace;
+pub mod tokio_console;
 pub mod tracy;
+pub mod wpa;
+
+pub use limits::{
+    DecompressError, Limits, parse_auto_with_limits, parse_visual_cancellable,
+    parse_visual_with_limits,
+};
+
+use std::sync::atomic::AtomicBool;
 
 use crate::model::Profile;
 use flame_cat_protocol::VisualProfile;
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
 #[derive(Debug, Error)]
@@ -35,115 +59,556 @@ pub enum ParseError {
     Pprof(#[from] pprof::PprofParseError),
     #[error("ebpf: {0}")]
     Ebpf(#[from] ebpf::EbpfParseError),
+    #[error("callgrind: {0}")]
+    Callgrind(#[from] callgrind::CallgrindParseError),
+    #[error("massif: {0}")]
+    Massif(#[from] massif::MassifParseError),
+    #[error("dhat: {0}")]
+    Dhat(#[from] dhat::DhatParseError),
+    #[error("heaptrack: {0}")]
+    Heaptrack(#[from] heaptrack::HeaptrackParseError),
+    #[error("ftrace: {0}")]
+    Ftrace(#[from] ftrace::FtraceParseError),
+    #[error("instruments: {0}")]
+    Instruments(#[from] instruments::InstrumentsParseError),
+    #[error("jfr: {0}")]
+    Jfr(#[from] jfr::JfrParseError),
+    #[error("nettrace: {0}")]
+    Nettrace(#[from] nettrace::NettraceParseError),
+    #[error("perf: {0}")]
+    Perf(#[from] perf::PerfParseError),
+    #[error("perfetto: {0}")]
+    Perfetto(#[from] perfetto::PerfettoParseError),
+    #[error("systrace: {0}")]
+    Systrace(#[from] systrace::SystraceParseError),
+    #[error("tokio-console: {0}")]
+    TokioConsole(#[from] tokio_console::TokioConsoleParseError),
+    #[error("wpa: {0}")]
+    Wpa(#[from] wpa::WpaParseError),
+    #[error("cache: {0}")]
+    Cache(#[from] crate::cache::CacheError),
+    #[error("io: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("remote: {0}")]
+    Remote(#[from] crate::remote::RemoteError),
+    #[error("input of {size} bytes is over the {limit} byte parse budget")]
+    TooLarge { size: usize, limit: usize },
     #[error("unable to detect format")]
     UnknownFormat,
+    #[error("no detected format could parse the input: {}", describe_attempts(.0))]
+    AllCandidatesFailed(Vec<FailedCandidate>),
+    #[error("unknown format hint {0:?}")]
+    UnknownFormatHint(String),
+    #[error("parse cancelled")]
+    Cancelled,
+}
+
+/// What a host knows about an input that auto-detection cannot tell.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FormatHint {
+    /// Parse as this format instead of detecting it: one of the format
+    /// names parsers record ("chrome", "speedscope", "pprof", ...).
+    pub format: Option<String>,
+    /// Load each profile of a multi-profile file (speedscope) as its own
+    /// session profile rather than as threads of a single one.
+    pub split_profiles: bool,
+    /// Load what parses of a truncated or corrupt Chrome trace instead of
+    /// failing; the profile's `partial` says where parsing stopped.
+    pub recover: bool,
+    /// Keep C++ and Rust symbols in perf, pprof and eBPF frames mangled,
+    /// as recorded, instead of demangling them.
+    pub raw_names: bool,
+}
+
+/// Whether a JSON object has the shape of a speedscope file.
+fn is_speedscope(obj: &serde_json::Map<String, serde_json::Value>) -> bool {
+    obj.get("$schema")
+        .and_then(|s| s.as_str())
+        .is_some_and(|s| s.contains("speedscope"))
+        || (obj.contains_key("shared") && obj.contains_key("profiles"))
+}
+
+/// Parse `data` as the named format, skipping detection.
+pub fn parse_as(format: &str, data: &[u8]) -> Result<Profile, ParseError> {
+    parse_format(format, data, false, &Limits::NONE, None)
+}
+
+/// Parse `data` as `format`. Only the Chrome and collapsed parsers enforce
+/// `limits` and watch `cancel` themselves; pprof and heaptrack inflate gzip
+/// within them. See [`limits`].
+fn parse_format(
+    format: &str,
+    data: &[u8],
+    raw_names: bool,
+    limits: &Limits,
+    cancel: Option<&AtomicBool>,
+) -> Result<Profile, ParseError> {
+    let mut profile = match format {
+        "chrome" => chrome::parse_chrome_trace_limited(data, limits, cancel, false)?,
+        "collapsed" => collapsed::parse_collapsed_limited(data, limits, cancel)?,
+        "cpuprofile" => cpuprofile::parse_cpuprofile(data)?,
+        "ebpf" => ebpf::parse_ebpf(data)?,
+        "firefox" => firefox::parse_firefox(data)?,
+        "callgrind" => callgrind::parse_callgrind(data)?,
+        "dhat" => dhat::parse_dhat(data)?,
+        "massif" => massif::parse_massif(data)?,
+        "ftrace" => ftrace::parse_ftrace(data)?,
+        "heaptrack" => heaptrack::parse_heaptrack_limited(data, limits)?,
+        "instruments" => instruments::parse_instruments(data)?,
+        "jfr" => jfr::parse_jfr(data)?,
+        "nettrace" => nettrace::parse_nettrace(data)?,
+        "perf" => perf::parse_perf(data)?,
+        "perfetto" => perfetto::parse_perfetto(data)?,
+        "pix" => pix::parse_pix(data)?,
+        "pprof" => pprof::parse_pprof_limited(data, limits)?,
+        "react" => react::parse_react_profile(data)?,
+        "speedscope" => speedscope::parse_speedscope(data)?,
+        "systrace" => systrace::parse_systrace(data)?,
+        "tokio-console" => tokio_console::parse_tokio_console(data)?,
+        "tracy" => tracy::parse_tracy(data)?,
+        "wpa" => wpa::parse_wpa(data)?,
+        _ => return Err(ParseError::UnknownFormatHint(format.to_string())),
+    };
+    if !raw_names {
+        demangle_symbols(format, &mut profile);
+    }
+    Ok(profile)
+}
+
+/// Demangle the frames of formats that record native linker symbols.
+#[cfg(feature = "demangle")]
+fn demangle_symbols(format: &str, profile: &mut Profile) {
+    if matches!(format, "ebpf" | "perf" | "pprof") {
+        crate::demangle::demangle_frames(&mut profile.frames);
+    }
+}
+
+#[cfg(not(feature = "demangle"))]
+fn demangle_symbols(_format: &str, _profile: &mut Profile) {}
+
+/// Parse `data` following `hint`. Yields several profiles only when the
+/// hint asks for a multi-profile file to be split.
+pub fn parse_with_hint(data: &[u8], hint: &FormatHint) -> Result<Vec<Profile>, ParseError> {
+    parse_within(data, hint, &Limits::NONE, None)
 }
 
-/// Auto-detect the profile format and parse it.
+/// [`parse_with_hint`], with `limits` and `cancel` heeded by the parsers
+/// that support them.
+fn parse_within(
+    data: &[u8],
+    hint: &FormatHint,
+    limits: &Limits,
+    cancel: Option<&AtomicBool>,
+) -> Result<Vec<Profile>, ParseError> {
+    let speedscope = match hint.format.as_deref() {
+        Some(format) => format == "speedscope",
+        None => {
+            hint.split_profiles
+                && serde_json::from_slice::<serde_json::Value>(data)
+                    .ok()
+                    .as_ref()
+                    .and_then(|v| v.as_object())
+                    .is_some_and(is_speedscope)
+        }
+    };
+    if hint.split_profiles && speedscope {
+        return Ok(speedscope::parse_speedscope_profiles(data)?);
+    }
+    let chrome = match hint.format.as_deref() {
+        Some(format) => format == "chrome",
+        None => chrome::looks_like_trace(data),
+    };
+    if hint.recover && chrome {
+        return Ok(vec![chrome::parse_chrome_trace_limited(
+            data, limits, cancel, true,
+        )?]);
+    }
+    match hint.format.as_deref() {
+        Some(format) => parse_format(format, data, hint.raw_names, limits, cancel).map(|p| vec![p]),
+        None => parse_detected(data, hint.raw_names, limits, cancel).map(|p| vec![p]),
+    }
+}
+
+/// How strongly an input's shape points at a format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Confidence {
+    /// A permissive format that accepts most text.
+    Low,
+    /// The input has the format's structure, which others may share.
+    Medium,
+    /// A magic number, schema or key only this format uses.
+    High,
+}
+
+impl std::fmt::Display for Confidence {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Low => "low",
+            Self::Medium => "medium",
+            Self::High => "high",
+        })
+    }
+}
+
+/// A format detection thinks an input may be in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Candidate {
+    /// Format name as [`parse_as`] takes it.
+    pub format: &'static str,
+    pub confidence: Confidence,
+}
+
+/// A candidate format that failed to parse the input.
+#[derive(Debug)]
+pub struct FailedCandidate {
+    pub candidate: Candidate,
+    pub error: ParseError,
+}
+
+impl Candidate {
+    pub const fn new(format: &'static str, confidence: Confidence) -> Self {
+        Self { format, confidence }
+    }
+}
+
+impl std::fmt::Display for FailedCandidate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} ({} confidence)",
+            self.error, self.candidate.confidence
+        )
+    }
+}
+
+fn describe_attempts(attempts: &[FailedCandidate]) -> String {
+    attempts
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join("; then ")
+}
+
+/// Formats `data` may be in, most likely first. Formats of equal
+/// confidence keep detection order.
 ///
 /// Detection strategy:
-/// 1. Try to parse as JSON first (most formats are JSON-based).
-/// 2. Inspect top-level keys to identify the format.
-/// 3. Fall back to text-based formats (collapsed stacks, perf script, bpftrace).
-pub fn parse_auto(data: &[u8]) -> Result<Profile, ParseError> {
-    // Try JSON-based formats first.
-    if let Ok(value) = serde_json::from_slice::<serde_json::Value>(data) {
-        if let Some(obj) = value.as_object() {
-            // Speedscope: has "$schema" containing "speedscope" or has "shared" + "profiles"
-            if obj.contains_key("$schema")
-                && obj["$schema"]
-                    .as_str()
-                    .is_some_and(|s| s.contains("speedscope"))
-            {
-                return Ok(speedscope::parse_speedscope(data)?);
-            }
-            if obj.contains_key("shared") && obj.contains_key("profiles") {
-                return Ok(speedscope::parse_speedscope(data)?);
-            }
+/// 1. Try to parse as JSON first (most formats are JSON-based) and inspect
+///    the top-level keys.
+/// 2. Recognise binary perf.data, JFR and nettrace files by their magic,
+///    Perfetto traces by their packet framing, Instruments exports by
+///    their XML root and heaptrack recordings, gzipped or not, by their
+///    version line.
+/// 3. Fall back to text-based formats (systrace and atrace captures, ftrace
+///    and `trace-cmd report` text, callgrind and massif profiles, WPA table
+///    exports, collapsed stacks, perf script, bpftrace) when nothing
+///    JSON-shaped matched.
+pub fn detect_formats(data: &[u8]) -> Vec<Candidate> {
+    use Confidence::{High, Low, Medium};
+    let mut candidates = Vec::new();
 
-            // React DevTools: has "dataForRoots"
-            if obj.contains_key("dataForRoots") {
-                return Ok(react::parse_react_profile(data)?);
-            }
+    let json = serde_json::from_slice::<serde_json::Value>(data).ok();
+    if let Some(obj) = json.as_ref().and_then(|v| v.as_object()) {
+        // Speedscope: has "$schema" containing "speedscope" or has "shared" + "profiles"
+        if obj
+            .get("$schema")
+            .and_then(|s| s.as_str())
+            .is_some_and(|s| s.contains("speedscope"))
+        {
+            candidates.push(Candidate::new("speedscope", High));
+        } else if is_speedscope(obj) {
+            candidates.push(Candidate::new("speedscope", Medium));
+        }
+
+        // React DevTools: has "dataForRoots"
+        if obj.contains_key("dataForRoots") {
+            candidates.push(Candidate::new("react", High));
+        }
 
+        if let Some(threads) = obj.get("threads").and_then(|v| v.as_array()) {
             // Tracy: has "threads" with "zones"
-            if let Some(threads) = obj.get("threads").and_then(|v| v.as_array())
-                && threads.iter().any(|t| t.get("zones").is_some())
-            {
-                return Ok(tracy::parse_tracy(data)?);
+            if threads.iter().any(|t| t.get("zones").is_some()) {
+                candidates.push(Candidate::new("tracy", Medium));
             }
-
             // Firefox Gecko: has "threads" array with stackTable/frameTable
-            if let Some(threads) = obj.get("threads").and_then(|v| v.as_array())
-                && threads
-                    .iter()
-                    .any(|t| t.get("stackTable").is_some() || t.get("frameTable").is_some())
+            if threads
+                .iter()
+                .any(|t| t.get("stackTable").is_some() || t.get("frameTable").is_some())
             {
-                return Ok(firefox::parse_firefox(data)?);
+                candidates.push(Candidate::new("firefox", Medium));
             }
+        }
 
-            // PIX: has "events" array with objects containing "start"
-            if let Some(events) = obj.get("events").and_then(|v| v.as_array())
-                && events.iter().any(|e| e.get("start").is_some())
-            {
-                return Ok(pix::parse_pix(data)?);
-            }
+        // PIX: has "events" array with objects containing "start", which
+        // plenty of other JSON has too
+        if let Some(events) = obj.get("events").and_then(|v| v.as_array())
+            && events.iter().any(|e| e.get("start").is_some())
+        {
+            candidates.push(Candidate::new("pix", Low));
+        }
 
-            // pprof JSON: has "samples" + "locations" + "functions"
-            if obj.contains_key("samples")
-                && obj.contains_key("locations")
-                && obj.contains_key("functions")
-            {
-                return Ok(pprof::parse_pprof(data)?);
-            }
+        // pprof JSON: has "samples" + "locations" + "functions"
+        if obj.contains_key("samples")
+            && obj.contains_key("locations")
+            && obj.contains_key("functions")
+        {
+            candidates.push(Candidate::new("pprof", Medium));
+        }
 
-            // V8 CPU profile: has "nodes" + "startTime" + "endTime"
-            if obj.contains_key("nodes")
-                && obj.contains_key("startTime")
-                && obj.contains_key("endTime")
-            {
-                return Ok(cpuprofile::parse_cpuprofile(data)?);
-            }
+        // V8 CPU profile: has "nodes" + "startTime" + "endTime"
+        if obj.contains_key("nodes") && obj.contains_key("startTime") && obj.contains_key("endTime")
+        {
+            candidates.push(Candidate::new("cpuprofile", Medium));
+        }
 
-            // Chrome trace: has "traceEvents"
-            if obj.contains_key("traceEvents") {
-                return Ok(chrome::parse_chrome_trace(data)?);
-            }
+        // Valgrind DHAT output: has "dhatFileVersion"
+        if obj.contains_key("dhatFileVersion") {
+            candidates.push(Candidate::new("dhat", High));
         }
 
-        // Chrome trace array format: top-level JSON array with objects containing "ph"
-        if let Some(arr) = value.as_array()
-            && arr.iter().any(|v| v.get("ph").is_some())
-        {
-            return Ok(chrome::parse_chrome_trace(data)?);
+        // Chrome trace: has "traceEvents"
+        if obj.contains_key("traceEvents") {
+            candidates.push(Candidate::new("chrome", High));
         }
     }
 
-    // Not JSON — try text-based formats.
+    // Chrome trace array format: top-level JSON array with objects containing "ph"
+    if let Some(arr) = json.as_ref().and_then(|v| v.as_array())
+        && arr.iter().any(|v| v.get("ph").is_some())
+    {
+        candidates.push(Candidate::new("chrome", Medium));
+    }
+
+    // `perf record` output
+    if perf::is_perf_data(data) {
+        candidates.push(Candidate::new("perf", High));
+    }
+
+    // Java Flight Recorder recording
+    if jfr::is_jfr(data) {
+        candidates.push(Candidate::new("jfr", High));
+    }
+
+    // .NET EventPipe trace
+    if nettrace::is_nettrace(data) {
+        candidates.push(Candidate::new("nettrace", High));
+    }
+
+    // Instruments Time Profiler table from `xctrace export`
+    if instruments::is_xctrace_export(data) {
+        candidates.push(Candidate::new("instruments", High));
+    }
+
+    // heaptrack recording, usually gzipped
+    if heaptrack::is_heaptrack(data) {
+        candidates.push(Candidate::new("heaptrack", High));
+    }
+
+    // Go's binary pprof profiles are gzipped profile.proto
+    if pprof::is_gzip(data) {
+        candidates.push(Candidate::new("pprof", Medium));
+    }
 
-    // eBPF bpftrace/perf script format
+    // Perfetto protobuf trace
+    if perfetto::is_perfetto(data) {
+        candidates.push(Candidate::new("perfetto", Medium));
+    }
+
+    // Not JSON — try text-based formats.
     if let Ok(text) = std::str::from_utf8(data)
-        && (text.contains("@[")
+        && candidates.is_empty()
+    {
+        // Android systrace HTML or raw atrace text
+        if systrace::is_systrace(text) {
+            candidates.push(Candidate::new("systrace", High));
+        }
+
+        // Kernel ftrace or `trace-cmd report` text with scheduler events.
+        // Android captures have those too, so systrace is tried first.
+        if ftrace::is_ftrace(text) {
+            candidates.push(Candidate::new("ftrace", High));
+        }
+
+        // Valgrind callgrind profile
+        if callgrind::is_callgrind(text) {
+            candidates.push(Candidate::new("callgrind", High));
+        }
+
+        // Valgrind massif profile
+        if massif::is_massif(text) {
+            candidates.push(Candidate::new("massif", High));
+        }
+
+        // Windows Performance Analyzer table export (CSV)
+        if wpa::is_wpa_csv(text) {
+            candidates.push(Candidate::new("wpa", Medium));
+        }
+
+        // tokio console-subscriber recording (newline-delimited JSON)
+        if tokio_console::is_tokio_console(text) {
+            candidates.push(Candidate::new("tokio-console", Medium));
+        }
+
+        // eBPF bpftrace/perf script format
+        if text.contains("@[")
             || text
                 .lines()
-                .any(|l| l.starts_with('\t') && l.trim().len() > 8))
-        && let Ok(profile) = ebpf::parse_ebpf(data)
-    {
-        return Ok(profile);
-    }
+                .any(|l| l.starts_with('\t') && l.trim().len() > 8)
+        {
+            candidates.push(Candidate::new("ebpf", Medium));
+        }
 
-    // Collapsed/folded stacks (most permissive text format — try last)
-    if let Ok(profile) = collapsed::parse_collapsed(data) {
-        return Ok(profile);
+        // Collapsed/folded stacks (most permissive text format — try last)
+        candidates.push(Candidate::new("collapsed", Low));
     }
 
-    Err(ParseError::UnknownFormat)
+    candidates.sort_by_key(|c| std::cmp::Reverse(c.confidence));
+    candidates
+}
+
+/// Auto-detect the profile format and parse it, trying each candidate of
+/// [`detect_formats`] in turn until one succeeds.
+///
+/// When several candidates fail, the error lists every attempt.
+pub fn parse_auto(data: &[u8]) -> Result<Profile, ParseError> {
+    parse_detected(data, false, &Limits::NONE, None)
+}
+
+fn parse_detected(
+    data: &[u8],
+    raw_names: bool,
+    limits: &Limits,
+    cancel: Option<&AtomicBool>,
+) -> Result<Profile, ParseError> {
+    let mut attempts = Vec::new();
+    for candidate in detect_formats(data) {
+        if limits::cancelled(cancel) {
+            return Err(ParseError::Cancelled);
+        }
+        match parse_format(candidate.format, data, raw_names, limits, cancel) {
+            Ok(profile) => return Ok(profile),
+            Err(error) => attempts.push(FailedCandidate { candidate, error }),
+        }
+    }
+    match attempts.len() {
+        0 => Err(ParseError::UnknownFormat),
+        1 => Err(attempts.remove(0).error),
+        _ => Err(ParseError::AllCandidatesFailed(attempts)),
+    }
 }
 
 /// Auto-detect the profile format and parse it into the canonical VisualProfile.
 ///
 /// This is the primary entry point for all profile loading. Every profiling
 /// format is normalized into the common VisualProfile IR.
+///
+/// Columnar cache files (see [`crate::cache`]) are recognised by their magic
+/// bytes and loaded directly, skipping parsing entirely.
 pub fn parse_auto_visual(data: &[u8]) -> Result<VisualProfile, ParseError> {
+    if crate::cache::is_cache(data) {
+        return Ok(crate::cache::decode(data)?);
+    }
     parse_auto(data).map(Profile::into_visual_profile)
 }
+
+/// [`parse_auto_visual`] following `hint`; see [`parse_with_hint`].
+pub fn parse_visual_with_hint(
+    data: &[u8],
+    hint: &FormatHint,
+) -> Result<Vec<VisualProfile>, ParseError> {
+    parse_visual_within(data, hint, &Limits::NONE, None)
+}
+
+/// [`parse_visual_with_hint`], with `limits` and `cancel` heeded by the
+/// parsers that support them.
+fn parse_visual_within(
+    data: &[u8],
+    hint: &FormatHint,
+    limits: &Limits,
+    cancel: Option<&AtomicBool>,
+) -> Result<Vec<VisualProfile>, ParseError> {
+    if crate::cache::is_cache(data) {
+        return Ok(vec![crate::cache::decode(data)?]);
+    }
+    Ok(parse_within(data, hint, limits, cancel)?
+        .into_iter()
+        .map(Profile::into_visual_profile)
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "demangle")]
+    #[test]
+    fn native_symbols_are_demangled_unless_raw_names() {
+        let script = b"app 1 1.000000: cycles:\n\tffff0001 _ZN3foo3barEv+0x10 (/usr/bin/app)\n\n";
+        let names = |hint: &FormatHint| {
+            let profile = parse_with_hint(script, hint).unwrap().remove(0);
+            profile.frames[0].name.clone()
+        };
+        assert_eq!(names(&FormatHint::default()), "foo::bar()");
+        let raw = FormatHint {
+            raw_names: true,
+            ..FormatHint::default()
+        };
+        assert_eq!(names(&raw), "_ZN3foo3barEv");
+    }
+
+    #[test]
+    fn recover_hint_loads_truncated_chrome_traces() {
+        let trace = br#"[{"name":"a","ph":"X","ts":0,"dur":10,"pid":1,"tid":1},{"name":"#;
+        let recover = FormatHint {
+            recover: true,
+            ..FormatHint::default()
+        };
+        let profiles = parse_visual_with_hint(trace, &recover).unwrap();
+        assert_eq!(profiles[0].meta.partial.as_ref().unwrap().events, 1);
+        assert!(parse_visual_with_hint(trace, &FormatHint::default()).is_err());
+    }
+
+    #[test]
+    fn ranks_candidates_by_confidence() {
+        let json = br#"{"events":[{"start":0}],"traceEvents":[]}"#;
+        assert_eq!(
+            detect_formats(json),
+            [
+                Candidate::new("chrome", Confidence::High),
+                Candidate::new("pix", Confidence::Low),
+            ]
+        );
+        assert_eq!(
+            detect_formats(b"main;work 3\n"),
+            [Candidate::new("collapsed", Confidence::Low)]
+        );
+        assert!(detect_formats(&[0xff, 0xfe]).is_empty());
+    }
+
+    #[test]
+    fn falls_back_to_the_next_candidate() {
+        let json = br#"{"nodes":"none","startTime":0,"endTime":1,
+            "events":[{"name":"Draw","start":0,"end":10}]}"#;
+        assert_eq!(parse_auto(json).unwrap().metadata.format, "pix");
+
+        let json = br#"{"nodes":"none","startTime":0,"endTime":1,"events":[{"start":"soon"}]}"#;
+        let err = parse_auto(json).unwrap_err();
+        let ParseError::AllCandidatesFailed(attempts) = &err else {
+            panic!("unexpected error {err}");
+        };
+        let tried: Vec<&str> = attempts.iter().map(|a| a.candidate.format).collect();
+        assert_eq!(tried, ["cpuprofile", "pix"]);
+        let message = err.to_string();
+        assert!(message.contains("cpuprofile: "), "{message}");
+        assert!(
+            message.contains("(medium confidence); then pix: "),
+            "{message}"
+        );
+        assert!(matches!(
+            parse_auto(&[0xff, 0xfe]),
+            Err(ParseError::UnknownFormat)
+        ));
+    }
+}
@@ -0,0 +1,853 @@
+//! .NET EventPipe traces (`.nettrace`), as written by `dotnet-trace`.
+//!
+//! The file is a FastSerialization object stream: after the magic comes a
+//! `Trace` object holding the clock, then blocks of metadata (which
+//! provider and event id each metadata id stands for), stacks (instruction
+//! pointers by stack id), events, and sequence points that end the scope of
+//! stack ids. Event headers are usually compressed, each field a delta
+//! from or a repeat of the previous event's in the block. Only the events
+//! used here are decoded: sample profiler thread samples, garbage
+//! collections, and the JIT and method load events that name managed code.
+
+use std::collections::{BTreeMap, HashMap};
+
+use flame_cat_protocol::{AsyncSpan, ClockKind, Marker, MarkerScope, TimeDomain};
+use thiserror::Error;
+
+use super::bytes::{self, LittleEndian, Truncated};
+use super::perf::{Sample, stitch_samples};
+use crate::model::{Frame, Profile, ProfileMetadata};
+
+#[derive(Debug, Error)]
+pub enum NettraceParseError {
+    #[error("not a nettrace file")]
+    NotNettrace,
+    #[error("unsupported nettrace file: {0}")]
+    Unsupported(&'static str),
+    #[error("malformed nettrace file: {0}")]
+    Malformed(&'static str),
+    #[error("no samples, garbage collections or JIT events found")]
+    Empty,
+}
+
+impl From<Truncated> for NettraceParseError {
+    fn from(_: Truncated) -> Self {
+        Self::Malformed("data runs past its end")
+    }
+}
+
+// Layouts from the runtime's EventPipe format documentation
+// (docs/design/specs/EventPipe/NetTraceFormat.md in dotnet/runtime).
+const MAGIC: &[u8; 8] = b"Nettrace";
+const SERIALIZATION: &[u8] = b"!FastSerialization.1";
+const TAG_NULL_REFERENCE: u8 = 1;
+const TAG_BEGIN_PRIVATE_OBJECT: u8 = 5;
+const TAG_END_OBJECT: u8 = 6;
+/// Newest `Trace` object version laid out as read here.
+const MAX_TRACE_VERSION: u32 = 5;
+
+const BLOCK_COMPRESSED_HEADERS: u16 = 1;
+const HEADER_METADATA_ID: u8 = 1;
+const HEADER_CAPTURE_THREAD_AND_SEQUENCE: u8 = 2;
+const HEADER_THREAD_ID: u8 = 4;
+const HEADER_STACK_ID: u8 = 8;
+const HEADER_ACTIVITY_ID: u8 = 16;
+const HEADER_RELATED_ACTIVITY_ID: u8 = 32;
+const HEADER_DATA_LENGTH: u8 = 128;
+
+const SAMPLE_PROFILER: &str = "Microsoft-DotNETCore-SampleProfiler";
+const RUNTIME: &str = "Microsoft-Windows-DotNETRuntime";
+const RUNDOWN: &str = "Microsoft-Windows-DotNETRuntimeRundown";
+const THREAD_SAMPLE: u32 = 0;
+const GC_START: u32 = 1;
+const GC_END: u32 = 2;
+/// `MethodLoadVerbose`, or `MethodDCStartVerbose` from the rundown provider.
+const METHOD_LOAD: u32 = 143;
+/// `MethodUnloadVerbose`, or `MethodDCEndVerbose` from the rundown provider.
+const METHOD_UNLOAD: u32 = 144;
+const METHOD_JITTING_STARTED: u32 = 145;
+
+/// `GCStart` reasons, by value.
+const GC_REASONS: [&str; 10] = [
+    "allocation",
+    "induced",
+    "low memory",
+    "empty",
+    "large object allocation",
+    "out of space (SOH)",
+    "out of space (LOH)",
+    "induced, not forced",
+    "internal",
+    "induced low memory",
+];
+
+/// Whether `data` starts like a nettrace file.
+pub fn is_nettrace(data: &[u8]) -> bool {
+    data.starts_with(MAGIC)
+}
+
+/// Parse an EventPipe trace into a `Profile`.
+///
+/// Sample profiler stacks become frames per thread, stitched like other
+/// sampled formats, with managed code named from method load and rundown
+/// events and anything else left as its address. Garbage collections
+/// become markers and async spans in the `gc` category, and JIT
+/// compilations async spans in the `jit` category on the compiling thread.
+/// Times are wall-clock µs.
+pub fn parse_nettrace(data: &[u8]) -> Result<Profile, NettraceParseError> {
+    if !is_nettrace(data) {
+        return Err(NettraceParseError::NotNettrace);
+    }
+    let mut r = Reader::new(data);
+    r.bytes(MAGIC.len())?;
+    let len = r.u32()? as usize;
+    if r.bytes(len)? != SERIALIZATION {
+        return Err(NettraceParseError::Unsupported("serialization format"));
+    }
+
+    let mut trace = Trace::default();
+    loop {
+        match r.u8()? {
+            TAG_NULL_REFERENCE => break,
+            TAG_BEGIN_PRIVATE_OBJECT => {}
+            _ => return Err(NettraceParseError::Malformed("expected an object")),
+        }
+        let (name, version) = type_descriptor(&mut r)?;
+        if name == "Trace" {
+            if version > MAX_TRACE_VERSION {
+                return Err(NettraceParseError::Unsupported("format version"));
+            }
+            trace.header(&mut r)?;
+        } else {
+            // Blocks are 4-byte aligned in the file.
+            let size = r.u32()? as usize;
+            r.align()?;
+            let block = r.bytes(size)?;
+            match name {
+                "MetadataBlock" => trace.metadata_block(block)?,
+                "StackBlock" => trace.stack_block(block)?,
+                "EventBlock" => trace.event_block(block)?,
+                // Stack ids are only valid up to the next sequence point.
+                "SPBlock" => trace.stacks.clear(),
+                _ => return Err(NettraceParseError::Unsupported("object type")),
+            }
+        }
+        if r.u8()? != TAG_END_OBJECT {
+            return Err(NettraceParseError::Malformed("object not closed"));
+        }
+    }
+    trace.finish()
+}
+
+/// An object's type: its name and version.
+fn type_descriptor<'a>(r: &mut Reader<'a>) -> Result<(&'a str, u32), NettraceParseError> {
+    if r.u8()? != TAG_BEGIN_PRIVATE_OBJECT || r.u8()? != TAG_NULL_REFERENCE {
+        return Err(NettraceParseError::Malformed("bad type descriptor"));
+    }
+    let version = r.u32()?;
+    r.u32()?; // minimum reader version
+    let len = r.u32()? as usize;
+    let name = std::str::from_utf8(r.bytes(len)?)
+        .map_err(|_| NettraceParseError::Malformed("type name is not UTF-8"))?;
+    if r.u8()? != TAG_END_OBJECT {
+        return Err(NettraceParseError::Malformed("type descriptor not closed"));
+    }
+    Ok((name, version))
+}
+
+type Reader<'a> = bytes::Reader<'a, LittleEndian>;
+
+impl Reader<'_> {
+    /// Seven bits per byte, low bits first.
+    fn varint(&mut self) -> Result<u64, NettraceParseError> {
+        let mut value = 0;
+        for shift in (0..64).step_by(7) {
+            let b = self.u8()?;
+            value |= u64::from(b & 0x7f) << shift;
+            if b & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(NettraceParseError::Malformed("varint too long"))
+    }
+
+    /// A NUL-terminated UTF-16 string.
+    fn utf16(&mut self) -> Result<String, NettraceParseError> {
+        let mut chars = Vec::new();
+        loop {
+            match self.u16()? {
+                0 => return Ok(String::from_utf16_lossy(&chars)),
+                c => chars.push(c),
+            }
+        }
+    }
+
+    /// Skip to the next multiple of four bytes.
+    fn align(&mut self) -> Result<(), NettraceParseError> {
+        let pos = self.position();
+        self.bytes(pos.next_multiple_of(4) - pos)?;
+        Ok(())
+    }
+}
+
+/// The fields of an event header that are kept here.
+#[derive(Default, Clone, Copy)]
+struct EventHeader {
+    metadata_id: u32,
+    thread_id: u64,
+    stack_id: u32,
+    timestamp: u64,
+    payload_size: usize,
+}
+
+/// The events of a metadata or event block with their headers.
+fn block_events(
+    block: &[u8],
+    mut event: impl FnMut(&EventHeader, &[u8]) -> Result<(), NettraceParseError>,
+) -> Result<(), NettraceParseError> {
+    let mut r = Reader::new(block);
+    let header_size = usize::from(r.u16()?);
+    let flags = r.u16()?;
+    r.bytes(
+        header_size
+            .checked_sub(4)
+            .ok_or(NettraceParseError::Malformed("block header too short"))?,
+    )?;
+    let mut header = EventHeader::default();
+    while !r.is_empty() {
+        if flags & BLOCK_COMPRESSED_HEADERS != 0 {
+            let flags = r.u8()?;
+            if flags & HEADER_METADATA_ID != 0 {
+                header.metadata_id = r.varint()? as u32;
+            }
+            if flags & HEADER_CAPTURE_THREAD_AND_SEQUENCE != 0 {
+                r.varint()?; // sequence number delta
+                r.varint()?; // capture thread
+                r.varint()?; // processor
+            }
+            if flags & HEADER_THREAD_ID != 0 {
+                header.thread_id = r.varint()?;
+            }
+            if flags & HEADER_STACK_ID != 0 {
+                header.stack_id = r.varint()? as u32;
+            }
+            header.timestamp = header.timestamp.wrapping_add(r.varint()?);
+            if flags & HEADER_ACTIVITY_ID != 0 {
+                r.bytes(16)?;
+            }
+            if flags & HEADER_RELATED_ACTIVITY_ID != 0 {
+                r.bytes(16)?;
+            }
+            if flags & HEADER_DATA_LENGTH != 0 {
+                header.payload_size = r.varint()? as usize;
+            }
+            event(&header, r.bytes(header.payload_size)?)?;
+        } else {
+            let size = r.u32()? as usize;
+            let mut e = Reader::new(r.bytes(size)?);
+            // The top bit flags events sorted by time.
+            header.metadata_id = e.u32()? & 0x7fff_ffff;
+            e.u32()?; // sequence number
+            header.thread_id = e.u64()?;
+            e.u64()?; // capture thread
+            e.u32()?; // processor
+            header.stack_id = e.u32()?;
+            header.timestamp = e.u64()?;
+            e.bytes(32)?; // activity and related activity ids
+            header.payload_size = e.u32()? as usize;
+            event(&header, e.bytes(header.payload_size)?)?;
+            r.align()?;
+        }
+    }
+    Ok(())
+}
+
+/// A method's name and code range, from a method load or rundown event.
+struct Method {
+    start: u64,
+    end: u64,
+    name: String,
+}
+
+/// Read the start of a method event: its id, code range (when it has
+/// one) and `Namespace.Name`.
+fn method(payload: &[u8], ranged: bool) -> Result<(u64, Method), NettraceParseError> {
+    let mut r = Reader::new(payload);
+    let id = r.u64()?;
+    r.u64()?; // module id
+    let (start, end) = if ranged {
+        let start = r.u64()?;
+        let size = u64::from(r.u32()?);
+        r.u32()?; // token
+        r.u32()?; // flags
+        (start, start.saturating_add(size))
+    } else {
+        r.u32()?; // token
+        r.u32()?; // IL size
+        (0, 0)
+    };
+    let namespace = r.utf16()?;
+    let name = r.utf16()?;
+    let name = if namespace.is_empty() {
+        name
+    } else {
+        format!("{namespace}.{name}")
+    };
+    Ok((id, Method { start, end, name }))
+}
+
+#[derive(Default)]
+struct Trace {
+    /// Wall-clock µs at `sync_ticks`.
+    sync_us: f64,
+    sync_ticks: u64,
+    ticks_per_second: f64,
+    /// Set once the `Trace` header has been read; blocks can't be decoded
+    /// before that.
+    pointer_size: Option<usize>,
+    /// The sample profiler's period.
+    sample_interval_us: Option<f64>,
+    /// Provider and event id by metadata id.
+    metadata: HashMap<u32, (String, u32)>,
+    /// Instruction pointers, leaf first, by stack id.
+    stacks: HashMap<u32, Vec<u64>>,
+    /// Distinct sampled stacks, which are named once every method is known.
+    sampled_stacks: Vec<Vec<u64>>,
+    sampled_index: HashMap<Vec<u64>, u32>,
+    /// Per thread, samples of an index into `sampled_stacks`.
+    threads: BTreeMap<u64, Vec<(f64, u32)>>,
+    methods: Vec<Method>,
+    /// JIT compilations not yet loaded, by method id.
+    jitting: HashMap<u64, (f64, String, u64)>,
+    /// GCs not yet ended, by count.
+    collections: HashMap<u32, (f64, String)>,
+    markers: Vec<Marker>,
+    async_spans: Vec<AsyncSpan>,
+}
+
+impl Trace {
+    fn header(&mut self, r: &mut Reader<'_>) -> Result<(), NettraceParseError> {
+        // A Windows SYSTEMTIME in UTC: year, month, weekday, day, hour,
+        // minute, second, millisecond.
+        let mut time = [0; 8];
+        for field in &mut time {
+            *field = i64::from(r.u16()?);
+        }
+        let [year, month, _, day, hour, minute, second, millis] = time;
+        let seconds =
+            days_from_civil(year, month, day) * 86_400 + hour * 3600 + minute * 60 + second;
+        self.sync_us = (seconds * 1000 + millis) as f64 * 1e3;
+        self.sync_ticks = r.u64()?;
+        self.ticks_per_second = r.u64()? as f64;
+        let pointer_size = r.u32()? as usize;
+        r.u32()?; // process id
+        r.u32()?; // processor count
+        let period_ns = r.u32()?;
+        self.sample_interval_us = (period_ns > 0).then(|| f64::from(period_ns) / 1e3);
+        if self.ticks_per_second <= 0.0 {
+            return Err(NettraceParseError::Malformed("trace without a clock"));
+        }
+        if pointer_size != 4 && pointer_size != 8 {
+            return Err(NettraceParseError::Unsupported("pointer size"));
+        }
+        self.pointer_size = Some(pointer_size);
+        Ok(())
+    }
+
+    /// The header's pointer size, or an error for a block that comes first.
+    fn pointer_size(&self) -> Result<usize, NettraceParseError> {
+        self.pointer_size.ok_or(NettraceParseError::Malformed(
+            "block before the trace header",
+        ))
+    }
+
+    fn us(&self, ticks: u64) -> f64 {
+        self.sync_us + (ticks as f64 - self.sync_ticks as f64) * 1e6 / self.ticks_per_second
+    }
+
+    fn metadata_block(&mut self, block: &[u8]) -> Result<(), NettraceParseError> {
+        block_events(block, |_, payload| {
+            let mut r = Reader::new(payload);
+            let id = r.u32()?;
+            let provider = r.utf16()?;
+            let event = r.u32()?;
+            self.metadata.insert(id, (provider, event));
+            Ok(())
+        })
+    }
+
+    fn stack_block(&mut self, block: &[u8]) -> Result<(), NettraceParseError> {
+        let pointer_size = self.pointer_size()?;
+        let mut r = Reader::new(block);
+        let first = r.u32()?;
+        let count = r.u32()?;
+        for id in first..first.saturating_add(count) {
+            let size = r.u32()? as usize;
+            let ips = r
+                .bytes(size)?
+                .chunks_exact(pointer_size)
+                .map(|ip| {
+                    let mut bytes = [0; 8];
+                    bytes[..ip.len()].copy_from_slice(ip);
+                    u64::from_le_bytes(bytes)
+                })
+                .collect();
+            self.stacks.insert(id, ips);
+        }
+        Ok(())
+    }
+
+    fn event_block(&mut self, block: &[u8]) -> Result<(), NettraceParseError> {
+        // Timestamps are read against the header's clock.
+        self.pointer_size()?;
+        block_events(block, |header, payload| self.event(header, payload))
+    }
+
+    fn event(&mut self, header: &EventHeader, payload: &[u8]) -> Result<(), NettraceParseError> {
+        let Some((provider, event)) = self.metadata.get(&header.metadata_id) else {
+            return Ok(());
+        };
+        let ts = self.us(header.timestamp);
+        match (provider.as_str(), *event) {
+            (SAMPLE_PROFILER, THREAD_SAMPLE) => {
+                let Some(stack) = self.stacks.get(&header.stack_id).filter(|s| !s.is_empty())
+                else {
+                    return Ok(());
+                };
+                let index = match self.sampled_index.get(stack) {
+                    Some(&index) => index,
+                    None => {
+                        let index = self.sampled_stacks.len() as u32;
+                        self.sampled_index.insert(stack.clone(), index);
+                        self.sampled_stacks.push(stack.clone());
+                        index
+                    }
+                };
+                self.threads
+                    .entry(header.thread_id)
+                    .or_default()
+                    .push((ts, index));
+            }
+            (RUNTIME | RUNDOWN, METHOD_LOAD | METHOD_UNLOAD) => {
+                let (id, method) = method(payload, true)?;
+                if *event == METHOD_LOAD
+                    && provider == RUNTIME
+                    && let Some((start, name, tid)) = self.jitting.remove(&id)
+                {
+                    self.async_spans.push(AsyncSpan {
+                        id: format!("jit-{}", self.async_spans.len()).into(),
+                        name: format!("JIT {name}").into(),
+                        cat: Some("jit".into()),
+                        start,
+                        end: ts,
+                        pid: 0,
+                        tid,
+                    });
+                }
+                if method.end > method.start {
+                    self.methods.push(method);
+                }
+            }
+            (RUNTIME, METHOD_JITTING_STARTED) => {
+                let (id, method) = method(payload, false)?;
+                self.jitting.insert(id, (ts, method.name, header.thread_id));
+            }
+            (RUNTIME, GC_START) => {
+                let mut r = Reader::new(payload);
+                let count = r.u32()?;
+                let generation = r.u32()?;
+                let reason = r.u32()?;
+                let name = format!("GC gen{generation}");
+                let span = match GC_REASONS.get(reason as usize) {
+                    Some(reason) => format!("{name}: {reason}"),
+                    None => name.clone(),
+                };
+                self.markers.push(Marker {
+                    ts,
+                    name: name.into(),
+                    scope: MarkerScope::Global,
+                    category: Some("gc".into()),
+                });
+                self.collections.insert(count, (ts, span));
+            }
+            (RUNTIME, GC_END) => {
+                let count = Reader::new(payload).u32()?;
+                if let Some((start, name)) = self.collections.remove(&count) {
+                    self.async_spans.push(AsyncSpan {
+                        id: format!("gc-{count}").into(),
+                        name: name.into(),
+                        cat: Some("gc".into()),
+                        start,
+                        end: ts,
+                        pid: 0,
+                        tid: 0,
+                    });
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// The managed method whose code holds `ip`, else the address.
+    fn name(&self, ip: u64) -> String {
+        let i = self.methods.partition_point(|m| m.start <= ip);
+        match i.checked_sub(1).map(|i| &self.methods[i]) {
+            Some(method) if ip < method.end => method.name.clone(),
+            _ => format!("{ip:#x}"),
+        }
+    }
+
+    fn finish(mut self) -> Result<Profile, NettraceParseError> {
+        if self.threads.is_empty() && self.async_spans.is_empty() {
+            return Err(NettraceParseError::Empty);
+        }
+        self.methods.sort_by_key(|m| m.start);
+
+        // Name each distinct stack root first, as location ids.
+        let mut locations: Vec<String> = Vec::new();
+        let mut location_index: HashMap<String, u32> = HashMap::new();
+        let stacks: Vec<Vec<u32>> = self
+            .sampled_stacks
+            .iter()
+            .map(|stack| {
+                stack
+                    .iter()
+                    .rev()
+                    .map(|&ip| {
+                        let name = self.name(ip);
+                        *location_index.entry(name.clone()).or_insert_with(|| {
+                            locations.push(name);
+                            locations.len() as u32 - 1
+                        })
+                    })
+                    .collect()
+            })
+            .collect();
+
+        let mut frames: Vec<Frame> = Vec::new();
+        for (&tid, samples) in &self.threads {
+            let mut samples: Vec<Sample> = samples
+                .iter()
+                .map(|&(ts, stack)| Sample {
+                    ts,
+                    stack: stacks[stack as usize].clone(),
+                })
+                .collect();
+            let thread = format!("Thread {tid}");
+            stitch_samples(
+                &mut frames,
+                &mut samples,
+                self.sample_interval_us,
+                |location| Frame {
+                    name: locations[location as usize].clone(),
+                    thread: Some(thread.clone()),
+                    tid: Some(tid),
+                    ..Frame::default()
+                },
+            );
+        }
+
+        let start_time = frames
+            .iter()
+            .map(|f| f.start)
+            .chain(self.async_spans.iter().map(|s| s.start))
+            .fold(f64::INFINITY, f64::min);
+        let end_time = frames
+            .iter()
+            .map(|f| f.end)
+            .chain(self.async_spans.iter().map(|s| s.end))
+            .fold(f64::NEG_INFINITY, f64::max);
+        let mut profile = Profile::new(
+            ProfileMetadata {
+                name: None,
+                start_time,
+                end_time,
+                format: "nettrace".to_string(),
+                time_domain: Some(TimeDomain {
+                    clock_kind: ClockKind::WallClock,
+                    origin_label: Some("EventPipe".into()),
+                    navigation_start_us: None,
+                }),
+                value_unit: None,
+                sample_interval_us: self.sample_interval_us,
+            },
+            frames,
+        );
+        profile.markers = self.markers;
+        profile.async_spans = self.async_spans;
+        Ok(profile)
+    }
+}
+
+/// Days since 1970-01-01 of a proleptic Gregorian date.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Writer for building nettrace files by hand.
+    #[derive(Default)]
+    struct Out(Vec<u8>);
+
+    impl Out {
+        fn bytes(mut self, v: &[u8]) -> Self {
+            self.0.extend_from_slice(v);
+            self
+        }
+
+        fn u16(self, v: u16) -> Self {
+            self.bytes(&v.to_le_bytes())
+        }
+
+        fn u32(self, v: u32) -> Self {
+            self.bytes(&v.to_le_bytes())
+        }
+
+        fn u64(self, v: u64) -> Self {
+            self.bytes(&v.to_le_bytes())
+        }
+
+        fn varint(mut self, mut v: u64) -> Self {
+            while v >= 0x80 {
+                self.0.push(v as u8 | 0x80);
+                v >>= 7;
+            }
+            self.0.push(v as u8);
+            self
+        }
+
+        fn utf16(self, s: &str) -> Self {
+            s.encode_utf16().chain([0]).fold(self, Out::u16)
+        }
+
+        /// An object with its type descriptor; blocks pad their content to
+        /// four bytes.
+        fn object(self, name: &str, body: &Out, block: bool) -> Self {
+            let mut out = self
+                .bytes(&[
+                    TAG_BEGIN_PRIVATE_OBJECT,
+                    TAG_BEGIN_PRIVATE_OBJECT,
+                    TAG_NULL_REFERENCE,
+                ])
+                .u32(4)
+                .u32(0)
+                .u32(name.len() as u32)
+                .bytes(name.as_bytes())
+                .bytes(&[TAG_END_OBJECT]);
+            if block {
+                out = out.u32(body.0.len() as u32);
+                while !out.0.len().is_multiple_of(4) {
+                    out.0.push(0);
+                }
+            }
+            out.bytes(&body.0).bytes(&[TAG_END_OBJECT])
+        }
+
+        /// An event with a compressed header.
+        fn event(self, metadata: u32, thread: u64, stack: u32, delta: u64, payload: &Out) -> Self {
+            self.bytes(&[HEADER_METADATA_ID
+                | HEADER_THREAD_ID
+                | HEADER_STACK_ID
+                | HEADER_DATA_LENGTH])
+                .varint(metadata.into())
+                .varint(thread)
+                .varint(stack.into())
+                .varint(delta)
+                .varint(payload.0.len() as u64)
+                .bytes(&payload.0)
+        }
+    }
+
+    /// The header of a metadata or event block, with compressed events.
+    fn block_header() -> Out {
+        Out::default()
+            .u16(20)
+            .u16(BLOCK_COMPRESSED_HEADERS)
+            .u64(0)
+            .u64(0)
+    }
+
+    fn metadata(id: u32, provider: &str, event: u32) -> Out {
+        Out::default()
+            .u32(id)
+            .utf16(provider)
+            .u32(event)
+            .utf16("")
+            .u64(0)
+            .u32(0)
+            .u32(4)
+    }
+
+    fn method_load(id: u64, start: u64, namespace: &str, name: &str) -> Out {
+        Out::default()
+            .u64(id)
+            .u64(1)
+            .u64(start)
+            .u32(0x100)
+            .u32(0)
+            .u32(0)
+            .utf16(namespace)
+            .utf16(name)
+            .utf16("void ()")
+    }
+
+    /// A trace of thread 7 sampled three times while a gen0 GC runs, after
+    /// `Worker.Run` is JIT compiled. Ticks are µs after the sync point.
+    fn trace() -> Vec<u8> {
+        let header = Out::default()
+            .u16(2024)
+            .u16(1)
+            .u16(1)
+            .u16(1)
+            .bytes(&[0; 8])
+            .u64(1000)
+            .u64(1_000_000)
+            .u32(8)
+            .u32(42)
+            .u32(4)
+            .u32(1_000_000);
+        let metadata = [
+            metadata(1, SAMPLE_PROFILER, THREAD_SAMPLE),
+            metadata(2, RUNTIME, METHOD_LOAD),
+            metadata(3, RUNTIME, METHOD_JITTING_STARTED),
+            metadata(4, RUNTIME, GC_START),
+            metadata(5, RUNTIME, GC_END),
+        ]
+        .iter()
+        .fold(block_header(), |block, m| block.event(0, 0, 0, 0, m));
+        let stacks = Out::default()
+            .u32(1)
+            .u32(2)
+            .u32(16)
+            .u64(0x2010)
+            .u64(0x1010)
+            .u32(16)
+            .u64(0x9999)
+            .u64(0x1010);
+        let jitting = Out::default()
+            .u64(2)
+            .u64(1)
+            .u32(0)
+            .u32(64)
+            .utf16("Worker")
+            .utf16("Run")
+            .utf16("void ()");
+        let sample = Out::default().u32(1);
+        let gc_start = Out::default().u32(1).u32(0).u32(0).u32(0);
+        let gc_end = Out::default().u32(1).u32(0);
+        let events = block_header()
+            .event(3, 7, 0, 1100, &jitting)
+            .event(2, 7, 0, 50, &method_load(2, 0x2000, "Worker", "Run"))
+            .event(2, 7, 0, 10, &method_load(1, 0x1000, "", "Program.Main"))
+            .event(1, 7, 1, 840, &sample)
+            .event(1, 7, 1, 1000, &sample)
+            .event(4, 1, 0, 500, &gc_start)
+            .event(1, 7, 2, 500, &sample)
+            .event(5, 1, 0, 500, &gc_end);
+
+        let mut file = Out::default()
+            .bytes(MAGIC)
+            .u32(SERIALIZATION.len() as u32)
+            .bytes(SERIALIZATION)
+            .object("Trace", &header, false)
+            .object("MetadataBlock", &metadata, true)
+            .object("StackBlock", &stacks, true)
+            .object("EventBlock", &events, true)
+            .object("SPBlock", &Out::default().u64(0).u32(0), true);
+        file.0.push(TAG_NULL_REFERENCE);
+        file.0
+    }
+
+    #[test]
+    fn parse_samples_gc_and_jit() {
+        let profile = crate::parsers::parse_auto(&trace()).unwrap();
+        assert_eq!(profile.metadata.format, "nettrace");
+
+        let sync = 1_704_067_200e6;
+        let mut frames: Vec<_> = profile
+            .frames
+            .iter()
+            .map(|f| (f.name.as_str(), f.depth, f.start - sync))
+            .collect();
+        frames.sort_by(|a, b| a.1.cmp(&b.1).then(a.2.total_cmp(&b.2)));
+        assert_eq!(
+            frames,
+            [
+                ("Program.Main", 0, 1000.0),
+                ("Worker.Run", 1, 1000.0),
+                ("0x9999", 1, 3000.0),
+            ]
+        );
+        assert!(
+            profile
+                .frames
+                .iter()
+                .all(|f| f.thread.as_deref() == Some("Thread 7"))
+        );
+
+        assert_eq!(profile.markers[0].name.as_ref(), "GC gen0");
+        let spans: Vec<_> = profile
+            .async_spans
+            .iter()
+            .map(|s| (s.name.as_ref(), s.cat.as_deref(), s.end - s.start))
+            .collect();
+        assert_eq!(
+            spans,
+            [
+                ("JIT Worker.Run", Some("jit"), 50.0),
+                ("GC gen0: allocation", Some("gc"), 1000.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_truncated_and_foreign_input() {
+        let mut data = trace();
+        data.truncate(data.len() - 20);
+        assert!(matches!(
+            parse_nettrace(&data),
+            Err(NettraceParseError::Malformed(_))
+        ));
+        assert!(matches!(
+            parse_nettrace(b"FLR\0"),
+            Err(NettraceParseError::NotNettrace)
+        ));
+    }
+
+    #[test]
+    fn rejects_blocks_before_the_trace_header() {
+        let stacks = Out::default().u32(1).u32(1).u32(8).u64(0x1010);
+        let mut data = Out::default()
+            .bytes(MAGIC)
+            .u32(SERIALIZATION.len() as u32)
+            .bytes(SERIALIZATION)
+            .object("StackBlock", &stacks, true);
+        data.0.push(TAG_NULL_REFERENCE);
+        assert!(matches!(
+            parse_nettrace(&data.0),
+            Err(NettraceParseError::Malformed(_))
+        ));
+        assert!(crate::parsers::parse_auto(&data.0).is_err());
+
+        let events = block_header().event(1, 7, 0, 0, &Out::default().u32(1));
+        let mut data = Out::default()
+            .bytes(MAGIC)
+            .u32(SERIALIZATION.len() as u32)
+            .bytes(SERIALIZATION)
+            .object("EventBlock", &events, true);
+        data.0.push(TAG_NULL_REFERENCE);
+        assert!(matches!(
+            parse_nettrace(&data.0),
+            Err(NettraceParseError::Malformed(_))
+        ));
+    }
+}
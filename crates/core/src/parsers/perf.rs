@@ -0,0 +1,869 @@
+//! Linux `perf record` output (`perf.data`).
+//!
+//! The file header points at the event attributes, which fix the layout of
+//! sample records, and at the data section: a stream of records that map
+//! binaries into processes (`MMAP`/`MMAP2`), name and fork threads
+//! (`COMM`/`FORK`) and capture call chains (`SAMPLE`). Samples are stitched
+//! into frames per thread, keeping their timestamps and thread names.
+//!
+//! perf.data holds no symbols, so frames keep their addresses — relative to
+//! the mapped binary where one is known — for `symbolicate` to resolve.
+
+use std::collections::{BTreeMap, HashMap};
+
+use flame_cat_protocol::{Binary, SharedStr};
+use thiserror::Error;
+
+use super::bytes::{self, LittleEndian, Truncated};
+use super::ebpf::category;
+use crate::model::{Frame, Profile, ProfileMetadata};
+use crate::transform::{DEFAULT_GAP_FACTOR, SampleGaps};
+
+#[derive(Debug, Error)]
+pub enum PerfParseError {
+    #[error("not a perf.data file")]
+    NotPerfData,
+    #[error("unsupported perf.data: {0}")]
+    Unsupported(&'static str),
+    #[error("malformed perf.data: {0}")]
+    Malformed(&'static str),
+    #[error("no samples found")]
+    Empty,
+}
+
+impl From<Truncated> for PerfParseError {
+    fn from(_: Truncated) -> Self {
+        Self::Malformed("record runs past its end")
+    }
+}
+
+// Layouts from linux/perf_event.h and tools/perf/util/header.h.
+const MAGIC: &[u8; 8] = b"PERFILE2";
+const MAGIC_BIG_ENDIAN: &[u8; 8] = b"2ELIFREP";
+const FILE_HEADER_SIZE: u64 = 104;
+const SECTION_SIZE: usize = 16;
+
+const RECORD_MMAP: u32 = 1;
+const RECORD_COMM: u32 = 3;
+const RECORD_FORK: u32 = 7;
+const RECORD_SAMPLE: u32 = 9;
+const RECORD_MMAP2: u32 = 10;
+
+const MISC_CPUMODE_MASK: u16 = 7;
+const MISC_KERNEL: u16 = 1;
+const MISC_GUEST_KERNEL: u16 = 4;
+const MISC_MMAP_BUILD_ID: u16 = 1 << 14;
+const MISC_BUILD_ID_SIZE: u16 = 1 << 15;
+
+const SAMPLE_IP: u64 = 1 << 0;
+const SAMPLE_TID: u64 = 1 << 1;
+const SAMPLE_TIME: u64 = 1 << 2;
+const SAMPLE_ADDR: u64 = 1 << 3;
+const SAMPLE_READ: u64 = 1 << 4;
+const SAMPLE_CALLCHAIN: u64 = 1 << 5;
+const SAMPLE_ID: u64 = 1 << 6;
+const SAMPLE_CPU: u64 = 1 << 7;
+const SAMPLE_PERIOD: u64 = 1 << 8;
+const SAMPLE_STREAM_ID: u64 = 1 << 9;
+const SAMPLE_IDENTIFIER: u64 = 1 << 16;
+
+const READ_TOTAL_TIME_ENABLED: u64 = 1 << 0;
+const READ_TOTAL_TIME_RUNNING: u64 = 1 << 1;
+const READ_ID: u64 = 1 << 2;
+const READ_GROUP: u64 = 1 << 3;
+const READ_LOST: u64 = 1 << 4;
+
+/// `perf_event_attr.freq`: `sample_period` holds a frequency in Hz.
+const ATTR_FREQ: u64 = 1 << 10;
+
+/// Call chain entries from here up mark a context switch, not an address.
+const CONTEXT_MAX: u64 = -4095_i64 as u64;
+const CONTEXT_KERNEL: u64 = -128_i64 as u64;
+const CONTEXT_GUEST_KERNEL: u64 = -2176_i64 as u64;
+
+/// Feature bit of the build id table appended after the data section.
+const FEATURE_BUILD_ID: u32 = 2;
+
+/// The `pid` kernel mappings are recorded under.
+const KERNEL_PID: u32 = u32::MAX;
+
+/// Whether `data` starts like a `perf record` output file.
+pub fn is_perf_data(data: &[u8]) -> bool {
+    data.starts_with(MAGIC) || data.starts_with(MAGIC_BIG_ENDIAN)
+}
+
+/// Parse a `perf.data` file into a `Profile`.
+///
+/// Each thread's samples become frames on a lane named after the thread's
+/// command, with timestamps converted to µs. A sample lasts until the
+/// thread's next one, or one sampling interval across long gaps. Frames
+/// in the kernel get the [`SpanCategory::KERNEL`] category, others
+/// [`SpanCategory::USER`].
+///
+/// Only little-endian files written by `perf record` to a file are read;
+/// pipe-mode output and samples without `TID` and `TIME` are rejected.
+///
+/// [`SpanCategory::KERNEL`]: flame_cat_protocol::SpanCategory::KERNEL
+/// [`SpanCategory::USER`]: flame_cat_protocol::SpanCategory::USER
+pub fn parse_perf(data: &[u8]) -> Result<Profile, PerfParseError> {
+    let mut r = Reader::new(data);
+    match r.bytes(8) {
+        Ok(magic) if magic == MAGIC => {}
+        Ok(magic) if magic == MAGIC_BIG_ENDIAN => {
+            return Err(PerfParseError::Unsupported("big-endian file"));
+        }
+        _ => return Err(PerfParseError::NotPerfData),
+    }
+    if r.u64()? < FILE_HEADER_SIZE {
+        return Err(PerfParseError::Unsupported("pipe-mode output"));
+    }
+    let attr_size = usize::try_from(r.u64()?)
+        .map_err(|_| PerfParseError::Malformed("attribute size out of range"))?;
+    let attrs = r.section(data)?;
+    let (data_offset, data_size) = (r.u64()?, r.u64()?);
+    let records = section(data, data_offset, data_size)?;
+    r.bytes(SECTION_SIZE)?; // event types, unused since perf 3.x
+    let features = [r.u64()?, r.u64()?, r.u64()?, r.u64()?];
+
+    let mut state = State::default();
+    state.attributes(data, attrs, attr_size)?;
+    let feature_table = data_offset
+        .checked_add(data_size)
+        .and_then(|offset| data.get(usize::try_from(offset).ok()?..))
+        .unwrap_or_default();
+    state.build_ids(data, feature_table, features)?;
+
+    let mut r = Reader::new(records);
+    while !r.is_empty() {
+        let kind = r.u32()?;
+        let misc = r.u16()?;
+        let size = usize::from(r.u16()?);
+        let body = r.bytes(
+            size.checked_sub(8)
+                .ok_or(PerfParseError::Malformed("record shorter than its header"))?,
+        )?;
+        match kind {
+            RECORD_MMAP => state.mmap(body, false, misc)?,
+            RECORD_MMAP2 => state.mmap(body, true, misc)?,
+            RECORD_COMM => state.comm(body)?,
+            RECORD_FORK => state.fork(body)?,
+            RECORD_SAMPLE => state.sample(body, misc)?,
+            _ => {}
+        }
+    }
+    state.finish()
+}
+
+type Reader<'a> = bytes::Reader<'a, LittleEndian>;
+
+impl<'a> Reader<'a> {
+    fn skip_u64s(&mut self, n: u64) -> Result<(), PerfParseError> {
+        let n = usize::try_from(n.saturating_mul(8))
+            .map_err(|_| PerfParseError::Malformed("record runs past its end"))?;
+        self.bytes(n)?;
+        Ok(())
+    }
+
+    /// A `perf_file_section` (offset and size) resolved against `file`.
+    fn section(&mut self, file: &'a [u8]) -> Result<&'a [u8], PerfParseError> {
+        let (offset, size) = (self.u64()?, self.u64()?);
+        section(file, offset, size)
+    }
+
+    /// A NUL-padded string filling the rest of the record.
+    fn string(&mut self) -> String {
+        let rest = self.rest();
+        let end = rest.iter().position(|&b| b == 0).unwrap_or(rest.len());
+        String::from_utf8_lossy(&rest[..end]).into_owned()
+    }
+}
+
+fn section(file: &[u8], offset: u64, size: u64) -> Result<&[u8], PerfParseError> {
+    usize::try_from(offset)
+        .ok()
+        .zip(usize::try_from(size).ok())
+        .and_then(|(offset, size)| file.get(offset..offset.checked_add(size)?))
+        .ok_or(PerfParseError::Malformed(
+            "section runs past the end of the file",
+        ))
+}
+
+/// What an event's `perf_event_attr` says about its sample records.
+#[derive(Debug, Clone, Copy)]
+struct Attr {
+    sample_type: u64,
+    read_format: u64,
+    /// Sampling frequency in Hz, when sampling by frequency.
+    freq: Option<u64>,
+}
+
+/// A binary mapped into a process.
+#[derive(Debug, Clone, Copy)]
+struct Mapping {
+    end: u64,
+    pgoff: u64,
+    binary: u32,
+    /// The file can be symbolized by offset (not anonymous or JIT memory,
+    /// nor the kernel, whose addresses stay absolute).
+    relative: bool,
+}
+
+/// A distinct frame: what it is called, where it ran and whether in the
+/// kernel.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct Location {
+    name: String,
+    binary: Option<u32>,
+    kernel: bool,
+}
+
+#[derive(Default)]
+struct State {
+    attrs: Vec<Attr>,
+    /// Sample id to index in `attrs`.
+    attr_ids: HashMap<u64, usize>,
+    /// Build ids from the file's build id table, by file name.
+    build_ids: HashMap<String, String>,
+    binaries: Vec<Binary>,
+    binary_index: HashMap<String, u32>,
+    /// Mappings of each process by start address; kernel ones under
+    /// [`KERNEL_PID`].
+    mappings: HashMap<u32, BTreeMap<u64, Mapping>>,
+    locations: Vec<Location>,
+    location_index: HashMap<Location, u32>,
+    /// Location of each `(pid, address)` seen, until the process maps
+    /// something new.
+    resolved: HashMap<(u32, u64), u32>,
+    comms: HashMap<u32, String>,
+    threads: BTreeMap<(u32, u32), Vec<Sample>>,
+}
+
+/// A sampled call chain.
+pub(super) struct Sample {
+    /// Time in µs.
+    pub(super) ts: f64,
+    /// Location ids, root first.
+    pub(super) stack: Vec<u32>,
+}
+
+impl State {
+    fn attributes(
+        &mut self,
+        file: &[u8],
+        attrs: &[u8],
+        attr_size: usize,
+    ) -> Result<(), PerfParseError> {
+        // Each entry is a `perf_event_attr` followed by the section of ids
+        // its samples carry.
+        if attr_size < 48 + SECTION_SIZE {
+            return Err(PerfParseError::Malformed("attribute too short"));
+        }
+        for entry in attrs.chunks_exact(attr_size) {
+            let mut r = Reader::new(entry);
+            r.bytes(16)?; // type, size, config
+            let period = r.u64()?;
+            let sample_type = r.u64()?;
+            let read_format = r.u64()?;
+            let flags = r.u64()?;
+            let index = self.attrs.len();
+            self.attrs.push(Attr {
+                sample_type,
+                read_format,
+                freq: (flags & ATTR_FREQ != 0 && period > 0).then_some(period),
+            });
+
+            let mut r = Reader::new(&entry[attr_size - SECTION_SIZE..]);
+            let mut ids = Reader::new(r.section(file)?);
+            while !ids.is_empty() {
+                self.attr_ids.insert(ids.u64()?, index);
+            }
+        }
+        if self.attrs.is_empty() {
+            return Err(PerfParseError::Malformed("no event attributes"));
+        }
+        Ok(())
+    }
+
+    /// Read the build id table, if the file has one. Feature sections
+    /// follow the data section, one per set feature bit in bit order.
+    fn build_ids(
+        &mut self,
+        file: &[u8],
+        table: &[u8],
+        features: [u64; 4],
+    ) -> Result<(), PerfParseError> {
+        if features[0] & (1 << FEATURE_BUILD_ID) == 0 {
+            return Ok(());
+        }
+        let index = (features[0] & ((1 << FEATURE_BUILD_ID) - 1)).count_ones() as usize;
+        let mut r = Reader::new(table.get(index * SECTION_SIZE..).unwrap_or_default());
+        let mut r = Reader::new(r.section(file)?);
+        while !r.is_empty() {
+            r.u32()?; // type
+            let misc = r.u16()?;
+            let size = usize::from(r.u16()?);
+            let mut event = Reader::new(r.bytes(size.checked_sub(8).ok_or(
+                PerfParseError::Malformed("build id record shorter than its header"),
+            )?)?);
+            event.u32()?; // pid
+            let id = event.bytes(24)?;
+            let len = if misc & MISC_BUILD_ID_SIZE != 0 {
+                usize::from(id[20]).min(20)
+            } else {
+                20
+            };
+            let name = event.string();
+            self.build_ids.insert(name, hex(&id[..len]));
+        }
+        Ok(())
+    }
+
+    fn mmap(&mut self, body: &[u8], mmap2: bool, misc: u16) -> Result<(), PerfParseError> {
+        let mut r = Reader::new(body);
+        let pid = r.u32()?;
+        r.u32()?; // tid
+        let (start, len, pgoff) = (r.u64()?, r.u64()?, r.u64()?);
+        let mut build_id = None;
+        if mmap2 {
+            let id = r.bytes(24)?;
+            if misc & MISC_MMAP_BUILD_ID != 0 {
+                let len = usize::from(id[0]).min(20);
+                build_id = Some(hex(&id[4..4 + len]));
+            }
+            r.bytes(8)?; // prot, flags
+        }
+        let file = r.string();
+        let name = match file.find(']') {
+            // `[kernel.kallsyms]_text` and the like.
+            Some(end) if file.starts_with('[') => &file[..=end],
+            _ => &file,
+        };
+        // Anonymous and JIT memory: keep absolute addresses for perf maps.
+        if name.starts_with("//") {
+            return Ok(());
+        }
+        let mapping = Mapping {
+            end: start.saturating_add(len),
+            pgoff,
+            binary: self.binary(name, build_id),
+            relative: name.starts_with('/') && pid != KERNEL_PID,
+        };
+        self.mappings.entry(pid).or_default().insert(start, mapping);
+        self.resolved.retain(|&(p, _), _| p != pid);
+        Ok(())
+    }
+
+    fn binary(&mut self, name: &str, build_id: Option<String>) -> u32 {
+        if let Some(&index) = self.binary_index.get(name) {
+            return index;
+        }
+        let index = self.binaries.len() as u32;
+        let build_id = build_id.or_else(|| self.build_ids.get(name).cloned());
+        self.binaries.push(Binary {
+            name: name.into(),
+            build_id: build_id.map(SharedStr::from),
+        });
+        self.binary_index.insert(name.to_string(), index);
+        index
+    }
+
+    fn comm(&mut self, body: &[u8]) -> Result<(), PerfParseError> {
+        let mut r = Reader::new(body);
+        r.u32()?; // pid
+        let tid = r.u32()?;
+        let name = r.string();
+        self.comms.insert(tid, name);
+        Ok(())
+    }
+
+    /// New threads are named after the thread that created them until they
+    /// rename themselves.
+    fn fork(&mut self, body: &[u8]) -> Result<(), PerfParseError> {
+        let mut r = Reader::new(body);
+        r.u32()?; // pid
+        r.u32()?; // ppid
+        let tid = r.u32()?;
+        let parent = r.u32()?;
+        if let Some(name) = self.comms.get(&parent).cloned() {
+            self.comms.entry(tid).or_insert(name);
+        }
+        Ok(())
+    }
+
+    fn sample(&mut self, body: &[u8], misc: u16) -> Result<(), PerfParseError> {
+        // With several events the attribute is found by the sample id,
+        // which `IDENTIFIER` puts first whatever else is recorded.
+        let mut attr = self.attrs[0];
+        if self.attrs.len() > 1 && attr.sample_type & SAMPLE_IDENTIFIER != 0 {
+            let id = Reader::new(body).u64()?;
+            if let Some(&index) = self.attr_ids.get(&id) {
+                attr = self.attrs[index];
+            }
+        }
+        let st = attr.sample_type;
+        if st & SAMPLE_TID == 0 || st & SAMPLE_TIME == 0 {
+            return Err(PerfParseError::Unsupported("samples without TID and TIME"));
+        }
+
+        let mut r = Reader::new(body);
+        let has = |bit: u64| u64::from(st & bit != 0);
+        r.skip_u64s(has(SAMPLE_IDENTIFIER))?;
+        let ip = if st & SAMPLE_IP != 0 {
+            Some(r.u64()?)
+        } else {
+            None
+        };
+        let pid = r.u32()?;
+        let tid = r.u32()?;
+        let time = r.u64()?;
+        r.skip_u64s(
+            has(SAMPLE_ADDR)
+                + has(SAMPLE_ID)
+                + has(SAMPLE_STREAM_ID)
+                + has(SAMPLE_CPU)
+                + has(SAMPLE_PERIOD),
+        )?;
+        if st & SAMPLE_READ != 0 {
+            skip_read(&mut r, attr.read_format)?;
+        }
+
+        let mode = misc & MISC_CPUMODE_MASK;
+        let mut kernel = mode == MISC_KERNEL || mode == MISC_GUEST_KERNEL;
+        let mut stack = Vec::new();
+        if st & SAMPLE_CALLCHAIN != 0 {
+            let nr = r.u64()?;
+            if nr.saturating_mul(8) > r.remaining() as u64 {
+                return Err(PerfParseError::Malformed("call chain runs past its record"));
+            }
+            for _ in 0..nr {
+                let address = r.u64()?;
+                if address >= CONTEXT_MAX {
+                    kernel = address == CONTEXT_KERNEL || address == CONTEXT_GUEST_KERNEL;
+                } else {
+                    stack.push(self.locate(pid, address, kernel));
+                }
+            }
+        } else if let Some(ip) = ip {
+            stack.push(self.locate(pid, ip, kernel));
+        }
+        if stack.is_empty() {
+            return Ok(());
+        }
+        // Call chains run from the leaf out.
+        stack.reverse();
+        self.threads.entry((pid, tid)).or_default().push(Sample {
+            ts: time as f64 / 1_000.0,
+            stack,
+        });
+        Ok(())
+    }
+
+    /// Location id of `address` sampled in process `pid`.
+    fn locate(&mut self, pid: u32, address: u64, kernel: bool) -> u32 {
+        let space = if kernel { KERNEL_PID } else { pid };
+        if let Some(&id) = self.resolved.get(&(space, address)) {
+            return id;
+        }
+        let mapping = self
+            .mappings
+            .get(&space)
+            .and_then(|maps| maps.range(..=address).next_back())
+            .filter(|(_, m)| address < m.end);
+        let location = match mapping {
+            Some((&start, m)) if m.relative => Location {
+                name: crate::symbolicate::module_frame_name(
+                    &self.binaries[m.binary as usize].name,
+                    address - start + m.pgoff,
+                ),
+                binary: Some(m.binary),
+                kernel,
+            },
+            _ => Location {
+                name: format!("{address:#x}"),
+                binary: mapping.map(|(_, m)| m.binary),
+                kernel,
+            },
+        };
+        let id = match self.location_index.get(&location) {
+            Some(&id) => id,
+            None => {
+                let id = self.locations.len() as u32;
+                self.location_index.insert(location.clone(), id);
+                self.locations.push(location);
+                id
+            }
+        };
+        self.resolved.insert((space, address), id);
+        id
+    }
+
+    fn finish(self) -> Result<Profile, PerfParseError> {
+        if self.threads.is_empty() {
+            return Err(PerfParseError::Empty);
+        }
+        // With sampling by frequency every thread shares one interval;
+        // otherwise each thread's is measured from its samples.
+        let freq_interval = self
+            .attrs
+            .iter()
+            .find_map(|a| a.freq)
+            .map(|hz| 1_000_000.0 / hz as f64);
+
+        let mut frames: Vec<Frame> = Vec::new();
+        let process_names = self
+            .threads
+            .keys()
+            .filter_map(|&(pid, _)| Some((u64::from(pid), self.comms.get(&pid)?.clone())))
+            .collect();
+        for ((pid, tid), mut samples) in self.threads {
+            let thread = self.comms.get(&tid);
+            stitch_samples(&mut frames, &mut samples, freq_interval, |location| {
+                let loc = &self.locations[location as usize];
+                Frame {
+                    name: loc.name.clone(),
+                    category: category(loc.kernel),
+                    thread: thread.cloned(),
+                    pid: Some(u64::from(pid)),
+                    tid: Some(u64::from(tid)),
+                    binary: loc.binary,
+                    ..Frame::default()
+                }
+            });
+        }
+
+        let start_time = frames.iter().map(|f| f.start).fold(f64::INFINITY, f64::min);
+        let end_time = frames
+            .iter()
+            .map(|f| f.end)
+            .fold(f64::NEG_INFINITY, f64::max);
+        let mut profile = Profile::new(
+            ProfileMetadata {
+                name: None,
+                start_time,
+                end_time,
+                format: "perf".to_string(),
+                time_domain: None,
+                value_unit: None,
+                sample_interval_us: freq_interval,
+            },
+            frames,
+        );
+        profile.binaries = self.binaries;
+        profile.process_names = process_names;
+        Ok(profile)
+    }
+}
+
+/// Stitch one thread's `samples` into `frames`, merging frames that stay
+/// on the stack from one sample to the next. A sample lasts until the
+/// next one, or one interval — `interval` as recorded, else measured from
+/// the samples — across long gaps. `frame` gives the fields of a
+/// new frame at a location; its timing, depth and parent are filled in.
+pub(super) fn stitch_samples(
+    frames: &mut Vec<Frame>,
+    samples: &mut [Sample],
+    interval: Option<f64>,
+    mut frame: impl FnMut(u32) -> Frame,
+) {
+    samples.sort_by(|a, b| a.ts.total_cmp(&b.ts));
+    let timestamps: Vec<f64> = samples.iter().map(|s| s.ts).collect();
+    let gaps = SampleGaps::for_samples(&timestamps, interval, DEFAULT_GAP_FACTOR);
+    let interval = gaps.map_or(0.0, |g| g.interval);
+
+    // Open frames as (index in `frames`, location).
+    let mut active: Vec<(usize, u32)> = Vec::new();
+    for (i, Sample { ts, stack }) in samples.iter().enumerate() {
+        let ts = *ts;
+        let next = timestamps.get(i + 1).copied().unwrap_or(ts + interval);
+        let common = active
+            .iter()
+            .zip(stack)
+            .take_while(|((_, a), b)| a == *b)
+            .count();
+        active.truncate(common);
+        for (depth, &location) in stack.iter().enumerate().skip(common) {
+            frames.push(Frame {
+                id: frames.len() as u64,
+                start: ts,
+                end: ts,
+                depth: depth as u32,
+                parent: active.last().map(|&(index, _)| frames[index].id),
+                ..frame(location)
+            });
+            active.push((frames.len() - 1, location));
+        }
+        // Across a long gap the thread was off-CPU: end the stack one
+        // interval after the sample.
+        let end = gaps.map_or(next, |g| g.sample_end(ts, next));
+        for &(index, _) in &active {
+            frames[index].end = end;
+        }
+        if end < next {
+            active.clear();
+        }
+    }
+}
+
+/// Skip a sample's `READ` values, laid out as `read_format` says.
+fn skip_read(r: &mut Reader<'_>, read_format: u64) -> Result<(), PerfParseError> {
+    let has = |bit: u64| u64::from(read_format & bit != 0);
+    let times = has(READ_TOTAL_TIME_ENABLED) + has(READ_TOTAL_TIME_RUNNING);
+    let per_value = 1 + has(READ_ID) + has(READ_LOST);
+    if read_format & READ_GROUP != 0 {
+        let nr = r.u64()?;
+        r.skip_u64s(nr.saturating_mul(per_value).saturating_add(times))
+    } else {
+        r.skip_u64s(per_value + times)
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Little-endian writer for building perf.data files by hand.
+    #[derive(Default)]
+    struct Out(Vec<u8>);
+
+    impl Out {
+        fn u16(mut self, v: u16) -> Self {
+            self.0.extend_from_slice(&v.to_le_bytes());
+            self
+        }
+
+        fn u32(mut self, v: u32) -> Self {
+            self.0.extend_from_slice(&v.to_le_bytes());
+            self
+        }
+
+        fn u64(mut self, v: u64) -> Self {
+            self.0.extend_from_slice(&v.to_le_bytes());
+            self
+        }
+
+        fn bytes(mut self, v: &[u8]) -> Self {
+            self.0.extend_from_slice(v);
+            self
+        }
+
+        /// A NUL-terminated string padded to 8 bytes.
+        fn string(mut self, s: &str) -> Self {
+            self.0.extend_from_slice(s.as_bytes());
+            self.0.push(0);
+            while !self.0.len().is_multiple_of(8) {
+                self.0.push(0);
+            }
+            self
+        }
+
+        fn record(self, kind: u32, misc: u16, body: Out) -> Self {
+            let size = 8 + body.0.len() as u16;
+            let mut out = self.u32(kind).u16(misc).u16(size);
+            out.0.extend(body.0);
+            out
+        }
+    }
+
+    const STACK: u64 = SAMPLE_IP | SAMPLE_TID | SAMPLE_TIME | SAMPLE_PERIOD | SAMPLE_CALLCHAIN;
+
+    fn sample(pid: u32, tid: u32, ms: u64, chain: &[u64]) -> Out {
+        let mut body = Out::default()
+            .u64(
+                chain
+                    .iter()
+                    .copied()
+                    .find(|&a| a < CONTEXT_MAX)
+                    .unwrap_or(0),
+            )
+            .u32(pid)
+            .u32(tid)
+            .u64(ms * 1_000_000)
+            .u64(1)
+            .u64(chain.len() as u64);
+        for &address in chain {
+            body = body.u64(address);
+        }
+        body
+    }
+
+    /// A perf.data file with one event, `records` and a build id table.
+    fn perf_data(records: &Out, build_ids: &Out) -> Vec<u8> {
+        let attr_size = 64 + SECTION_SIZE as u64;
+        let attrs_offset = FILE_HEADER_SIZE;
+        let data_offset = attrs_offset + attr_size;
+        let data_size = records.0.len() as u64;
+        let features_offset = data_offset + data_size;
+        let build_ids_offset = features_offset + SECTION_SIZE as u64;
+        Out::default()
+            .bytes(MAGIC)
+            .u64(FILE_HEADER_SIZE)
+            .u64(attr_size)
+            .u64(attrs_offset)
+            .u64(attr_size)
+            .u64(data_offset)
+            .u64(data_size)
+            .u64(0)
+            .u64(0)
+            .u64(1 << FEATURE_BUILD_ID)
+            .u64(0)
+            .u64(0)
+            .u64(0)
+            // perf_event_attr: type, size, config, sample_freq, sample_type,
+            // read_format, flags, then fields not read.
+            .u32(0)
+            .u32(64)
+            .u64(0)
+            .u64(1_000)
+            .u64(STACK)
+            .u64(0)
+            .u64(ATTR_FREQ)
+            .bytes(&[0; 16])
+            .u64(0)
+            .u64(0)
+            .bytes(&records.0)
+            .u64(build_ids_offset)
+            .u64(build_ids.0.len() as u64)
+            .bytes(&build_ids.0)
+            .0
+    }
+
+    #[test]
+    fn samples_become_frames_per_thread() {
+        let kernel_text = 0xffff_ffff_8100_0000;
+        let records = Out::default()
+            .record(RECORD_COMM, 0, Out::default().u32(10).u32(10).string("app"))
+            .record(
+                RECORD_FORK,
+                0,
+                Out::default().u32(10).u32(10).u32(11).u32(10).u64(0),
+            )
+            .record(
+                RECORD_MMAP2,
+                MISC_MMAP_BUILD_ID,
+                Out::default()
+                    .u32(10)
+                    .u32(10)
+                    .u64(0x40_0000)
+                    .u64(0x1000)
+                    .u64(0x2000)
+                    .bytes(&[2, 0, 0, 0, 0xab, 0xcd])
+                    .bytes(&[0; 18])
+                    .u32(5)
+                    .u32(2)
+                    .string("/usr/bin/app"),
+            )
+            .record(
+                RECORD_MMAP,
+                0,
+                Out::default()
+                    .u32(KERNEL_PID)
+                    .u32(0)
+                    .u64(kernel_text)
+                    .u64(0x100_0000)
+                    .u64(kernel_text)
+                    .string("[kernel.kallsyms]_text"),
+            )
+            .record(
+                RECORD_SAMPLE,
+                MISC_KERNEL,
+                sample(
+                    10,
+                    10,
+                    1,
+                    &[
+                        CONTEXT_KERNEL,
+                        kernel_text + 0x100,
+                        -512_i64 as u64,
+                        0x40_0120,
+                        0x40_0010,
+                    ],
+                ),
+            )
+            .record(
+                RECORD_SAMPLE,
+                2,
+                sample(10, 10, 2, &[-512_i64 as u64, 0x40_0120, 0x40_0010]),
+            )
+            .record(RECORD_SAMPLE, 2, sample(10, 11, 3, &[0x7f00_0000_1000]));
+        let build_ids = Out::default()
+            .u32(0)
+            .u16(1)
+            .u16(8 + 4 + 24 + 20)
+            .u32(KERNEL_PID)
+            .bytes(&[0x12, 0x34])
+            .bytes(&[0; 22])
+            .string("[kernel.kallsyms]");
+        let profile = parse_perf(&perf_data(&records, &build_ids)).unwrap();
+
+        let frames: Vec<(&str, f64, f64, u32, Option<u64>)> = profile
+            .frames
+            .iter()
+            .map(|f| (f.name.as_str(), f.start, f.end, f.depth, f.tid))
+            .collect();
+        assert_eq!(
+            frames,
+            [
+                ("app+0x2010", 1_000.0, 3_000.0, 0, Some(10)),
+                ("app+0x2120", 1_000.0, 3_000.0, 1, Some(10)),
+                ("0xffffffff81000100", 1_000.0, 2_000.0, 2, Some(10)),
+                ("0x7f0000001000", 3_000.0, 4_000.0, 0, Some(11)),
+            ]
+        );
+        // The forked thread is named after its parent.
+        assert!(
+            profile
+                .frames
+                .iter()
+                .all(|f| f.thread.as_deref() == Some("app"))
+        );
+        let kernel: Vec<bool> = profile
+            .frames
+            .iter()
+            .map(|f| f.category.as_deref() == Some("kernel"))
+            .collect();
+        assert_eq!(kernel, [false, false, true, false]);
+
+        let binaries: Vec<(&str, Option<&str>)> = profile
+            .binaries
+            .iter()
+            .map(|b| (b.name.as_ref(), b.build_id.as_deref()))
+            .collect();
+        assert_eq!(
+            binaries,
+            [
+                ("/usr/bin/app", Some("abcd")),
+                (
+                    "[kernel.kallsyms]",
+                    Some("1234000000000000000000000000000000000000")
+                ),
+            ]
+        );
+        assert_eq!(profile.frames[2].binary, Some(1));
+        assert_eq!(profile.frames[3].binary, None);
+        assert_eq!(
+            profile.process_names.get(&10).map(String::as_str),
+            Some("app")
+        );
+        assert_eq!(profile.metadata.sample_interval_us, Some(1_000.0));
+    }
+
+    #[test]
+    fn detection_and_errors() {
+        let empty = perf_data(&Out::default(), &Out::default());
+        assert!(is_perf_data(&empty));
+        assert!(matches!(parse_perf(&empty), Err(PerfParseError::Empty)));
+        assert!(!is_perf_data(b"{\"traceEvents\":[]}"));
+        assert!(matches!(
+            parse_perf(b"PERFILE2\x10\0\0\0\0\0\0\0"),
+            Err(PerfParseError::Unsupported(_))
+        ));
+        assert!(matches!(
+            parse_perf(&empty[..60]),
+            Err(PerfParseError::Malformed(_))
+        ));
+    }
+}
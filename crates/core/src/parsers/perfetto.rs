@@ -0,0 +1,675 @@
+//! Perfetto protobuf traces (`.perfetto-trace`, `.pftrace`).
+//!
+//! A trace is a stream of `TracePacket`s. Track descriptors name the
+//! process, thread and counter tracks; `TrackEvent`s begin and end slices,
+//! mark instants and set counter values on them. Event names and
+//! categories may be interned per packet sequence. Only the fields
+//! flame.cat draws are decoded; everything else is skipped by wire type,
+//! so no schema or protobuf runtime is needed.
+
+use std::collections::{BTreeMap, HashMap};
+
+use flame_cat_protocol::{
+    CounterSample, CounterTrack, CounterUnit, FlowArrow, InstantEvent, MarkerScope, SharedStr,
+};
+use thiserror::Error;
+
+use super::protobuf::{Fields, Value, WireError};
+use crate::model::{Frame, Profile, ProfileMetadata};
+
+#[derive(Debug, Error)]
+pub enum PerfettoParseError {
+    #[error("malformed protobuf: {0}")]
+    Malformed(&'static str),
+    #[error("no track events found")]
+    Empty,
+}
+
+impl From<WireError> for PerfettoParseError {
+    fn from(e: WireError) -> Self {
+        Self::Malformed(e.0)
+    }
+}
+
+// Field numbers from perfetto/protos/perfetto/trace.
+const TRACE_PACKET: u32 = 1;
+const PACKET_TIMESTAMP: u32 = 8;
+const PACKET_SEQUENCE_ID: u32 = 10;
+const PACKET_TRACK_EVENT: u32 = 11;
+const PACKET_INTERNED_DATA: u32 = 12;
+const PACKET_SEQUENCE_FLAGS: u32 = 13;
+const PACKET_DEFAULTS: u32 = 59;
+const PACKET_TRACK_DESCRIPTOR: u32 = 60;
+const SEQ_INCREMENTAL_STATE_CLEARED: u64 = 1;
+
+const EVENT_CATEGORY_IIDS: u32 = 3;
+const EVENT_TYPE: u32 = 9;
+const EVENT_NAME_IID: u32 = 10;
+const EVENT_TRACK_UUID: u32 = 11;
+const EVENT_CATEGORIES: u32 = 22;
+const EVENT_NAME: u32 = 23;
+const EVENT_COUNTER_VALUE: u32 = 30;
+const EVENT_FLOW_IDS_OLD: u32 = 36;
+const EVENT_TERMINATING_FLOW_IDS_OLD: u32 = 42;
+const EVENT_DOUBLE_COUNTER_VALUE: u32 = 44;
+const EVENT_FLOW_IDS: u32 = 47;
+const EVENT_TERMINATING_FLOW_IDS: u32 = 48;
+
+const TYPE_SLICE_BEGIN: u64 = 1;
+const TYPE_SLICE_END: u64 = 2;
+const TYPE_INSTANT: u64 = 3;
+const TYPE_COUNTER: u64 = 4;
+
+/// Whether `data` looks like a Perfetto trace: a run of length-delimited
+/// `packet` fields that exactly fills it.
+pub fn is_perfetto(data: &[u8]) -> bool {
+    if data.first() != Some(&((TRACE_PACKET << 3) as u8 | 2)) {
+        return false;
+    }
+    let mut fields = Fields::new(data);
+    fields.all(|f| matches!(f, Ok((TRACE_PACKET, Value::Bytes(_)))))
+}
+
+/// Parse a binary Perfetto trace into a `Profile`.
+///
+/// Slices become frames on a lane per track: thread tracks are named after
+/// their thread, other tracks after themselves. Counter tracks become
+/// counters, instants instant events and flow ids flow arrows. Timestamps
+/// are taken in the trace's default clock and converted to µs.
+pub fn parse_perfetto(data: &[u8]) -> Result<Profile, PerfettoParseError> {
+    let mut state = State::default();
+    for field in Fields::new(data) {
+        if let (TRACE_PACKET, Value::Bytes(packet)) = field? {
+            state.packet(packet)?;
+        }
+    }
+    state.finish()
+}
+
+/// What a track descriptor says about a track.
+#[derive(Debug, Default)]
+struct Track {
+    name: Option<String>,
+    parent: Option<u64>,
+    pid: Option<u64>,
+    tid: Option<u64>,
+    thread_name: Option<String>,
+    counter: Option<CounterInfo>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct CounterInfo {
+    /// `CounterDescriptor.Unit`: 1 ns, 2 count, 3 bytes.
+    unit: u64,
+    multiplier: f64,
+    incremental: bool,
+}
+
+/// One track event, resolved against its sequence's interned data.
+#[derive(Debug)]
+struct Event {
+    ts: f64,
+    kind: u64,
+    track: u64,
+    name: String,
+    category: Option<String>,
+    value: Option<f64>,
+    flows: Vec<u64>,
+    terminating_flows: Vec<u64>,
+}
+
+/// Interned strings and defaults of one packet sequence.
+#[derive(Debug, Default)]
+struct Sequence {
+    names: HashMap<u64, String>,
+    categories: HashMap<u64, String>,
+    default_track: Option<u64>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    tracks: HashMap<u64, Track>,
+    sequences: HashMap<u64, Sequence>,
+    events: Vec<Event>,
+}
+
+impl State {
+    fn packet(&mut self, packet: &[u8]) -> Result<(), PerfettoParseError> {
+        let mut ts = None;
+        let mut sequence_id = 0;
+        let mut flags = 0;
+        let mut event = None;
+        let mut interned = None;
+        let mut defaults = None;
+        for field in Fields::new(packet) {
+            match field? {
+                (PACKET_TIMESTAMP, v) => ts = v.as_u64(),
+                (PACKET_SEQUENCE_ID, v) => sequence_id = v.as_u64().unwrap_or(0),
+                (PACKET_SEQUENCE_FLAGS, v) => flags = v.as_u64().unwrap_or(0),
+                (PACKET_TRACK_EVENT, Value::Bytes(b)) => event = Some(b),
+                (PACKET_INTERNED_DATA, Value::Bytes(b)) => interned = Some(b),
+                (PACKET_DEFAULTS, Value::Bytes(b)) => defaults = Some(b),
+                (PACKET_TRACK_DESCRIPTOR, Value::Bytes(b)) => self.track_descriptor(b)?,
+                _ => {}
+            }
+        }
+
+        let sequence = self.sequences.entry(sequence_id).or_default();
+        if flags & SEQ_INCREMENTAL_STATE_CLEARED != 0 {
+            sequence.names.clear();
+            sequence.categories.clear();
+        }
+        if let Some(defaults) = defaults {
+            // TracePacketDefaults.track_event_defaults.track_uuid
+            for field in Fields::new(defaults) {
+                if let (11, Value::Bytes(track_defaults)) = field? {
+                    for field in Fields::new(track_defaults) {
+                        if let (11, v) = field? {
+                            sequence.default_track = v.as_u64();
+                        }
+                    }
+                }
+            }
+        }
+        if let Some(interned) = interned {
+            for field in Fields::new(interned) {
+                let (number, Value::Bytes(entry)) = field? else {
+                    continue;
+                };
+                let table = match number {
+                    1 => &mut sequence.categories,
+                    2 => &mut sequence.names,
+                    _ => continue,
+                };
+                let (mut iid, mut name) = (None, None);
+                for field in Fields::new(entry) {
+                    match field? {
+                        (1, v) => iid = v.as_u64(),
+                        (2, v) => name = v.as_str(),
+                        _ => {}
+                    }
+                }
+                if let (Some(iid), Some(name)) = (iid, name) {
+                    table.insert(iid, name.to_string());
+                }
+            }
+        }
+        if let Some(event) = event {
+            let event = track_event(event, ts.unwrap_or(0), sequence)?;
+            self.events.push(event);
+        }
+        Ok(())
+    }
+
+    fn track_descriptor(&mut self, data: &[u8]) -> Result<(), PerfettoParseError> {
+        let mut uuid = None;
+        let mut track = Track::default();
+        for field in Fields::new(data) {
+            match field? {
+                (1, v) => uuid = v.as_u64(),
+                (2 | 10, v) => track.name = v.as_str().map(str::to_string),
+                (5, v) => track.parent = v.as_u64(),
+                // ProcessDescriptor
+                (3, Value::Bytes(b)) => {
+                    for field in Fields::new(b) {
+                        match field? {
+                            (1, v) => track.pid = v.as_u64().map(int32),
+                            (6, v) => track.name = v.as_str().map(str::to_string),
+                            (2, v) if track.name.is_none() => {
+                                track.name = v.as_str().map(str::to_string);
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+                // ThreadDescriptor
+                (4, Value::Bytes(b)) => {
+                    for field in Fields::new(b) {
+                        match field? {
+                            (1, v) => track.pid = v.as_u64().map(int32),
+                            (2, v) => track.tid = v.as_u64().map(int32),
+                            (5, v) => track.thread_name = v.as_str().map(str::to_string),
+                            _ => {}
+                        }
+                    }
+                }
+                // CounterDescriptor
+                (8, Value::Bytes(b)) => {
+                    let mut counter = CounterInfo {
+                        unit: 0,
+                        multiplier: 1.0,
+                        incremental: false,
+                    };
+                    for field in Fields::new(b) {
+                        match field? {
+                            (3, v) => counter.unit = v.as_u64().unwrap_or(0),
+                            (4, v) => {
+                                counter.multiplier = v.as_u64().map_or(1.0, |m| m as i64 as f64);
+                            }
+                            (5, v) => counter.incremental = v.as_u64().is_some_and(|b| b != 0),
+                            _ => {}
+                        }
+                    }
+                    track.counter = Some(counter);
+                }
+                _ => {}
+            }
+        }
+        let uuid = uuid.ok_or(PerfettoParseError::Malformed(
+            "track descriptor without uuid",
+        ))?;
+        self.tracks.insert(uuid, track);
+        Ok(())
+    }
+
+    /// The lane a track's slices go on, with its pid and tid.
+    fn lane(&self, uuid: u64) -> (String, Option<u64>, Option<u64>) {
+        let Some(track) = self.tracks.get(&uuid) else {
+            return (format!("Track {uuid}"), None, None);
+        };
+        if let Some(tid) = track.tid {
+            let name = track
+                .thread_name
+                .clone()
+                .or_else(|| track.name.clone())
+                .unwrap_or_else(|| format!("Thread {tid}"));
+            return (name, track.pid, Some(tid));
+        }
+        // The nearest ancestor that knows its process, bounded against cycles.
+        let pid =
+            std::iter::successors(Some(track), |t| t.parent.and_then(|p| self.tracks.get(&p)))
+                .take(64)
+                .find_map(|t| t.pid);
+        let name = track
+            .name
+            .clone()
+            .unwrap_or_else(|| format!("Track {uuid}"));
+        (name, pid, None)
+    }
+
+    fn finish(mut self) -> Result<Profile, PerfettoParseError> {
+        if self.events.is_empty() {
+            return Err(PerfettoParseError::Empty);
+        }
+        self.events.sort_by(|a, b| a.ts.total_cmp(&b.ts));
+
+        let mut frames: Vec<Frame> = Vec::new();
+        let mut stacks: HashMap<u64, Vec<usize>> = HashMap::new();
+        let mut counters: BTreeMap<u64, (CounterUnit, f64, Vec<CounterSample>)> = BTreeMap::new();
+        let mut instant_events = Vec::new();
+        let mut flow_arrows = Vec::new();
+        // Last event of each flow still waiting for its next step.
+        let mut open_flows: HashMap<u64, (String, f64, u64, u64)> = HashMap::new();
+
+        for event in &self.events {
+            let (lane, pid, tid) = self.lane(event.track);
+            match event.kind {
+                TYPE_SLICE_BEGIN => {
+                    let stack = stacks.entry(event.track).or_default();
+                    frames.push(Frame {
+                        id: frames.len() as u64,
+                        name: event.name.clone(),
+                        start: event.ts,
+                        end: event.ts,
+                        depth: stack.len() as u32,
+                        category: event.category.clone(),
+                        parent: stack.last().map(|&i| frames[i].id),
+                        self_time: 0.0,
+                        thread: Some(lane),
+                        // Open until its end event arrives.
+                        truncated: true,
+                        pid,
+                        tid,
+                        source: None,
+                        binary: None,
+                        inlined: vec![],
+                    });
+                    stack.push(frames.len() - 1);
+                }
+                TYPE_SLICE_END => {
+                    if let Some(i) = stacks.get_mut(&event.track).and_then(Vec::pop) {
+                        frames[i].end = event.ts;
+                        frames[i].truncated = false;
+                    }
+                }
+                TYPE_INSTANT => instant_events.push(InstantEvent {
+                    ts: event.ts,
+                    name: SharedStr::from(event.name.as_str()),
+                    cat: event.category.as_deref().map(SharedStr::from),
+                    scope: if tid.is_some() {
+                        MarkerScope::Thread
+                    } else {
+                        MarkerScope::Process
+                    },
+                    pid: pid.unwrap_or(0),
+                    tid: tid.unwrap_or(0),
+                }),
+                TYPE_COUNTER => {
+                    let Some(value) = event.value else {
+                        continue;
+                    };
+                    let info = self.tracks.get(&event.track).and_then(|t| t.counter);
+                    let (_, total, samples) = counters.entry(event.track).or_insert_with(|| {
+                        let (unit, scale) = match info.map(|c| c.unit) {
+                            Some(1) => (CounterUnit::Microseconds, 1e-3),
+                            Some(2) => (CounterUnit::Count, 1.0),
+                            Some(3) => (CounterUnit::Bytes, 1.0),
+                            _ => super::chrome::guess_counter_unit(&lane),
+                        };
+                        let multiplier = info.map_or(1.0, |c| c.multiplier);
+                        (unit, scale * multiplier, Vec::new())
+                    });
+                    let value = value * *total;
+                    let value = match (info.is_some_and(|c| c.incremental), samples.last()) {
+                        (true, Some(last)) => last.value + value,
+                        _ => value,
+                    };
+                    samples.push(CounterSample {
+                        ts: event.ts,
+                        value,
+                    });
+                }
+                _ => {}
+            }
+
+            for &flow in event.flows.iter().chain(&event.terminating_flows) {
+                let here = (
+                    event.name.clone(),
+                    event.ts,
+                    pid.unwrap_or(0),
+                    tid.unwrap_or(0),
+                );
+                if let Some((name, from_ts, from_pid, from_tid)) = open_flows.remove(&flow) {
+                    flow_arrows.push(FlowArrow {
+                        name: SharedStr::from(name),
+                        id: SharedStr::from(flow.to_string()),
+                        from_ts,
+                        from_pid,
+                        from_tid,
+                        to_ts: here.1,
+                        to_pid: here.2,
+                        to_tid: here.3,
+                    });
+                }
+                if !event.terminating_flows.contains(&flow) {
+                    open_flows.insert(flow, here);
+                }
+            }
+        }
+
+        // Slices still open were cut off by the end of the recording.
+        let trace_end = self.events.last().map_or(0.0, |e| e.ts);
+        for f in frames.iter_mut().filter(|f| f.truncated) {
+            f.end = trace_end.max(f.start);
+        }
+
+        let process_names = self
+            .tracks
+            .values()
+            .filter(|t| t.tid.is_none())
+            .filter_map(|t| Some((t.pid?, t.name.clone()?)))
+            .collect();
+        let counters = counters
+            .into_iter()
+            .map(|(uuid, (unit, _, samples))| CounterTrack {
+                name: SharedStr::from(self.lane(uuid).0),
+                unit,
+                samples,
+            })
+            .collect();
+
+        let mut profile = Profile::new(
+            ProfileMetadata {
+                name: None,
+                start_time: self.events.first().map_or(0.0, |e| e.ts),
+                end_time: trace_end,
+                format: "perfetto".to_string(),
+                time_domain: None,
+                value_unit: None,
+                sample_interval_us: None,
+            },
+            frames,
+        );
+        profile.counters = counters;
+        profile.instant_events = instant_events;
+        profile.flow_arrows = flow_arrows;
+        profile.process_names = process_names;
+        Ok(profile)
+    }
+}
+
+/// A varint-encoded `int32`, such as a pid or tid, as the unsigned value
+/// trace event parsers use.
+fn int32(raw: u64) -> u64 {
+    u64::from(raw as i64 as i32 as u32)
+}
+
+fn track_event(data: &[u8], ts_ns: u64, sequence: &Sequence) -> Result<Event, PerfettoParseError> {
+    let mut event = Event {
+        ts: ts_ns as f64 / 1000.0,
+        kind: 0,
+        track: sequence.default_track.unwrap_or(0),
+        name: String::new(),
+        category: None,
+        value: None,
+        flows: Vec::new(),
+        terminating_flows: Vec::new(),
+    };
+    let mut categories: Vec<&str> = Vec::new();
+    for field in Fields::new(data) {
+        match field? {
+            (EVENT_TYPE, v) => event.kind = v.as_u64().unwrap_or(0),
+            (EVENT_TRACK_UUID, v) => event.track = v.as_u64().unwrap_or(event.track),
+            (EVENT_NAME, v) => event.name = v.as_str().unwrap_or_default().to_string(),
+            (EVENT_NAME_IID, v) => {
+                if let Some(name) = v.as_u64().and_then(|iid| sequence.names.get(&iid)) {
+                    event.name = name.clone();
+                }
+            }
+            (EVENT_CATEGORIES, v) => categories.extend(v.as_str()),
+            (EVENT_CATEGORY_IIDS, v) => categories.extend(
+                v.as_u64()
+                    .and_then(|iid| sequence.categories.get(&iid))
+                    .map(String::as_str),
+            ),
+            (EVENT_COUNTER_VALUE, v) => event.value = v.as_u64().map(|n| n as i64 as f64),
+            (EVENT_DOUBLE_COUNTER_VALUE, v) => {
+                event.value = v.as_u64().map(f64::from_bits);
+            }
+            (EVENT_FLOW_IDS | EVENT_FLOW_IDS_OLD, v) => event.flows.extend(v.as_u64()),
+            (EVENT_TERMINATING_FLOW_IDS | EVENT_TERMINATING_FLOW_IDS_OLD, v) => {
+                event.terminating_flows.extend(v.as_u64());
+            }
+            _ => {}
+        }
+    }
+    if !categories.is_empty() {
+        event.category = Some(categories.join(","));
+    }
+    Ok(event)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parsers::protobuf::Msg;
+
+    fn packet(ts: u64, event: Msg) -> Msg {
+        Msg::default()
+            .varint(PACKET_TIMESTAMP, ts)
+            .varint(PACKET_SEQUENCE_ID, 1)
+            .msg(PACKET_TRACK_EVENT, event)
+    }
+
+    fn event(ts: u64, kind: u64, track: u64) -> Msg {
+        packet(
+            ts,
+            Msg::default()
+                .varint(EVENT_TYPE, kind)
+                .varint(EVENT_TRACK_UUID, track),
+        )
+    }
+
+    fn trace(packets: Vec<Msg>) -> Vec<u8> {
+        packets
+            .into_iter()
+            .fold(Msg::default(), |t, p| t.msg(TRACE_PACKET, p))
+            .0
+    }
+
+    fn sample_trace() -> Vec<u8> {
+        let process = Msg::default()
+            .varint(1, 10)
+            .msg(3, Msg::default().varint(1, 42).str(6, "browser"));
+        let thread = Msg::default().varint(1, 11).varint(5, 10).msg(
+            4,
+            Msg::default()
+                .varint(1, 42)
+                .varint(2, 7)
+                .str(5, "CrRendererMain"),
+        );
+        let counter = Msg::default()
+            .varint(1, 12)
+            .varint(5, 10)
+            .str(2, "mem.rss")
+            .msg(8, Msg::default().varint(3, 3));
+        let interned = Msg::default()
+            .varint(PACKET_SEQUENCE_ID, 1)
+            .varint(PACKET_SEQUENCE_FLAGS, SEQ_INCREMENTAL_STATE_CLEARED)
+            .msg(
+                PACKET_INTERNED_DATA,
+                Msg::default()
+                    .msg(1, Msg::default().varint(1, 1).str(2, "blink"))
+                    .msg(2, Msg::default().varint(1, 1).str(2, "Layout")),
+            );
+        let begin = |ts, name: &str| {
+            packet(
+                ts,
+                Msg::default()
+                    .varint(EVENT_TYPE, TYPE_SLICE_BEGIN)
+                    .varint(EVENT_TRACK_UUID, 11)
+                    .str(EVENT_NAME, name)
+                    .varint(EVENT_FLOW_IDS_OLD, 5),
+            )
+        };
+        let interned_begin = packet(
+            20_000,
+            Msg::default()
+                .varint(EVENT_TYPE, TYPE_SLICE_BEGIN)
+                .varint(EVENT_TRACK_UUID, 11)
+                .varint(EVENT_NAME_IID, 1)
+                .varint(EVENT_CATEGORY_IIDS, 1),
+        );
+        let counter_value = |ts, v| {
+            packet(
+                ts,
+                Msg::default()
+                    .varint(EVENT_TYPE, TYPE_COUNTER)
+                    .varint(EVENT_TRACK_UUID, 12)
+                    .double(EVENT_DOUBLE_COUNTER_VALUE, v),
+            )
+        };
+        let instant = packet(
+            50_000,
+            Msg::default()
+                .varint(EVENT_TYPE, TYPE_INSTANT)
+                .varint(EVENT_TRACK_UUID, 11)
+                .str(EVENT_NAME, "Commit")
+                .varint(EVENT_TERMINATING_FLOW_IDS_OLD, 5),
+        );
+        trace(vec![
+            Msg::default().msg(PACKET_TRACK_DESCRIPTOR, process),
+            Msg::default().msg(PACKET_TRACK_DESCRIPTOR, thread),
+            Msg::default().msg(PACKET_TRACK_DESCRIPTOR, counter),
+            interned,
+            begin(10_000, "RunTask"),
+            interned_begin,
+            event(40_000, TYPE_SLICE_END, 11),
+            counter_value(15_000, 1024.0),
+            instant,
+            counter_value(100_000, 2048.0),
+            event(80_000, TYPE_SLICE_END, 11),
+            begin(90_000, "Open"),
+        ])
+    }
+
+    #[test]
+    fn lowers_slices_counters_and_instants() {
+        let data = sample_trace();
+        assert!(is_perfetto(&data));
+        let profile = parse_perfetto(&data).unwrap();
+        assert_eq!(profile.metadata.format, "perfetto");
+        assert_eq!(
+            (profile.metadata.start_time, profile.metadata.end_time),
+            (10.0, 100.0)
+        );
+
+        let frames: Vec<(&str, f64, f64, u32, bool)> = profile
+            .frames
+            .iter()
+            .map(|f| (f.name.as_str(), f.start, f.end, f.depth, f.truncated))
+            .collect();
+        assert_eq!(
+            frames,
+            [
+                ("RunTask", 10.0, 80.0, 0, false),
+                ("Layout", 20.0, 40.0, 1, false),
+                ("Open", 90.0, 100.0, 0, true),
+            ]
+        );
+        assert_eq!(profile.frames[1].category.as_deref(), Some("blink"));
+        let main = &profile.frames[0];
+        assert_eq!(
+            (main.thread.as_deref(), main.pid, main.tid),
+            (Some("CrRendererMain"), Some(42), Some(7))
+        );
+        assert_eq!(
+            profile.process_names.get(&42).map(String::as_str),
+            Some("browser")
+        );
+
+        let [rss] = profile.counters.as_slice() else {
+            panic!("one counter track");
+        };
+        assert_eq!(
+            (rss.name.as_ref(), rss.unit),
+            ("mem.rss", CounterUnit::Bytes)
+        );
+        let values: Vec<(f64, f64)> = rss.samples.iter().map(|s| (s.ts, s.value)).collect();
+        assert_eq!(values, [(15.0, 1024.0), (100.0, 2048.0)]);
+
+        assert_eq!(profile.instant_events.len(), 1);
+        assert_eq!(profile.instant_events[0].name.as_ref(), "Commit");
+        let [flow] = profile.flow_arrows.as_slice() else {
+            panic!("one flow");
+        };
+        assert_eq!((flow.from_ts, flow.to_ts, flow.to_tid), (10.0, 50.0, 7));
+
+        let visual = profile.into_visual_profile();
+        assert_eq!(
+            visual.meta.source_format,
+            flame_cat_protocol::SourceFormat::Perfetto
+        );
+        assert_eq!(visual.threads[0].name.as_ref(), "CrRendererMain");
+    }
+
+    #[test]
+    fn detection_and_errors() {
+        assert!(!is_perfetto(b"\nmain;work 1\n"));
+        assert!(!is_perfetto(br#"{"traceEvents":[]}"#));
+        assert!(matches!(
+            parse_perfetto(&trace(vec![Msg::default().varint(PACKET_TIMESTAMP, 1)])),
+            Err(PerfettoParseError::Empty)
+        ));
+        let mut cut = sample_trace();
+        cut.truncate(cut.len() - 3);
+        assert!(matches!(
+            parse_perfetto(&cut),
+            Err(PerfettoParseError::Malformed(_))
+        ));
+        assert!(matches!(
+            crate::parsers::parse_auto(&sample_trace()),
+            Ok(p) if p.metadata.format == "perfetto"
+        ));
+    }
+}
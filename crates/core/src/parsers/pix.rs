@@ -56,15 +56,13 @@ pub fn parse_pix(data: &[u8]) -> Result<Profile, PixParseError> {
     let mut next_id: u64 = 0;
 
     for event in &export.events {
-        flatten_pix_event(event, 0, None, &mut frames, &mut next_id);
+        flatten_pix_event(event, 0, None, None, &mut frames, &mut next_id);
     }
 
     if frames.is_empty() {
         return Err(PixParseError::NoEvents);
     }
 
-    compute_self_times(&mut frames);
-
     let start_time = frames.iter().map(|f| f.start).fold(f64::INFINITY, f64::min);
     let end_time = frames
         .iter()
@@ -82,20 +80,26 @@ pub fn parse_pix(data: &[u8]) -> Result<Profile, PixParseError> {
             end_time: if end_time.is_finite() { end_time } else { 0.0 },
             format: "pix".to_string(),
             time_domain: None,
+            value_unit: None,
+            sample_interval_us: None,
         },
         frames,
     ))
 }
 
+/// Flatten `event` and its children. Children without a `thread` of their
+/// own run on their parent's, so a queue's work stays on its track.
 fn flatten_pix_event(
     event: &PixEvent,
     depth: u32,
     parent_id: Option<u64>,
+    parent_thread: Option<&str>,
     frames: &mut Vec<Frame>,
     next_id: &mut u64,
 ) {
     let id = *next_id;
     *next_id += 1;
+    let thread = event.thread.as_deref().or(parent_thread);
 
     let actual_depth = event.depth.unwrap_or(depth);
     let end = event
@@ -112,27 +116,17 @@ fn flatten_pix_event(
         category: event.category.clone().or_else(|| event.thread.clone()),
         parent: parent_id,
         self_time: 0.0,
-        thread: None,
+        thread: thread.map(ToString::to_string),
+        truncated: false,
+        pid: None,
+        tid: None,
+        source: None,
+        binary: None,
+        inlined: vec![],
     });
 
     for child in &event.children {
-        flatten_pix_event(child, actual_depth + 1, Some(id), frames, next_id);
-    }
-}
-
-fn compute_self_times(frames: &mut [Frame]) {
-    let child_time = {
-        let mut map = std::collections::HashMap::<u64, f64>::new();
-        for f in frames.iter() {
-            if let Some(pid) = f.parent {
-                *map.entry(pid).or_default() += f.duration();
-            }
-        }
-        map
-    };
-    for f in frames.iter_mut() {
-        let children_total = child_time.get(&f.id).copied().unwrap_or(0.0);
-        f.self_time = (f.duration() - children_total).max(0.0);
+        flatten_pix_event(child, actual_depth + 1, Some(id), thread, frames, next_id);
     }
 }
 
@@ -169,6 +163,26 @@ mod tests {
         assert_eq!(render.depth, 0);
     }
 
+    #[test]
+    fn queues_become_gpu_tracks() {
+        let json = r#"{"events":[
+            {"name":"ExecuteCommandLists","thread":"Render Thread","start":0,"end":5},
+            {"name":"Frame","thread":"Direct Queue","start":6,"end":20,"children":[
+                {"name":"ShadowPass","start":6,"end":10}
+            ]}
+        ]}"#;
+        let profile = parse_pix(json.as_bytes()).unwrap().into_visual_profile();
+        let queue = profile
+            .threads
+            .iter()
+            .find(|t| &*t.name == "Direct Queue")
+            .unwrap();
+        assert_eq!(queue.kind, flame_cat_protocol::TrackKind::Gpu);
+        assert_eq!(queue.spans.len(), 2);
+        let render = profile.threads.iter().find(|t| &*t.name == "Render Thread");
+        assert_eq!(render.unwrap().kind, flame_cat_protocol::TrackKind::Cpu);
+    }
+
     #[test]
     fn parse_with_duration() {
         let json = r#"{"events":[{"name":"A","start":0,"duration":100,"children":[]}]}"#;
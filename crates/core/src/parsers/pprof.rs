@@ -1,56 +1,130 @@
 use serde::Deserialize;
 use thiserror::Error;
 
+use flame_cat_protocol::{Binary, ValueUnit};
+
+use super::limits::{DecompressError, Limits, decompress_within};
+use super::protobuf::{Fields, Value, WireError, varints};
 use crate::model::{Frame, Profile, ProfileMetadata};
 
 #[derive(Debug, Error)]
 pub enum PprofParseError {
     #[error("invalid JSON: {0}")]
     Json(#[from] serde_json::Error),
+    #[error("gzip: {0}")]
+    Gzip(#[from] DecompressError),
+    #[error("malformed profile.proto: {0}")]
+    Protobuf(&'static str),
     #[error("no samples found")]
     NoSamples,
 }
 
-/// pprof JSON format (as produced by `go tool pprof -json` or pprof-rs JSON export).
-///
-/// This handles the JSON representation of pprof data. For binary protobuf pprof,
-/// convert first with `go tool pprof -proto` then export to JSON.
-#[derive(Debug, Deserialize)]
-struct PprofJson {
+impl From<WireError> for PprofParseError {
+    fn from(e: WireError) -> Self {
+        Self::Protobuf(e.0)
+    }
+}
+
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// Whether `data` is gzip-compressed, as `profile.proto` files written by
+/// Go's `runtime/pprof` and `go tool pprof -proto` are.
+pub fn is_gzip(data: &[u8]) -> bool {
+    data.starts_with(&GZIP_MAGIC)
+}
+
+/// A pprof profile, from the JSON export (pprof-rs, or `profile.proto`
+/// converted with protobuf's JSON mapping) or decoded from the binary
+/// `profile.proto` encoding by [`decode_proto`].
+#[derive(Debug, Default, Deserialize)]
+struct PprofProfile {
     #[serde(default)]
     samples: Vec<PprofSample>,
     #[serde(default)]
     locations: Vec<PprofLocation>,
     #[serde(default)]
     functions: Vec<PprofFunction>,
+    #[serde(default, alias = "mapping")]
+    mappings: Vec<PprofMapping>,
     #[serde(default, rename = "stringTable")]
     string_table: Vec<String>,
     #[serde(default, rename = "durationNanos")]
     duration_nanos: Option<u64>,
+    #[serde(default, rename = "sampleType")]
+    sample_type: Vec<PprofValueType>,
+    #[serde(default, rename = "periodType")]
+    period_type: Option<PprofValueType>,
+    #[serde(default)]
+    period: i64,
+    /// Type name of the sample value to show, when not the first.
+    #[serde(default, rename = "defaultSampleType")]
+    default_sample_type: u64,
+}
+
+/// Kind and unit of a sample value or period, as indexes into the string
+/// table.
+#[derive(Debug, Default, Deserialize)]
+struct PprofValueType {
+    #[serde(default, rename = "type")]
+    kind: u64,
+    #[serde(default)]
+    unit: u64,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize)]
 struct PprofSample {
     #[serde(default, rename = "locationId")]
     location_id: Vec<u64>,
     #[serde(default)]
     value: Vec<i64>,
+    #[serde(default)]
+    label: Vec<PprofLabel>,
 }
 
-#[derive(Debug, Deserialize)]
+/// A sample label such as a goroutine's `pprof.Labels`; only string
+/// labels are used.
+#[derive(Debug, Default, Deserialize)]
+struct PprofLabel {
+    #[serde(default)]
+    key: u64,
+    #[serde(default)]
+    str: u64,
+}
+
+#[derive(Debug, Default, Deserialize)]
 struct PprofLocation {
     id: u64,
+    #[serde(default, rename = "mappingId")]
+    mapping_id: u64,
+    #[serde(default)]
+    address: u64,
     #[serde(default)]
     line: Vec<PprofLine>,
 }
 
-#[derive(Debug, Deserialize)]
+/// A binary mapped into the profiled process.
+#[derive(Debug, Default, Deserialize)]
+struct PprofMapping {
+    id: u64,
+    #[serde(default, rename = "memoryStart")]
+    memory_start: u64,
+    #[serde(default, rename = "memoryLimit")]
+    memory_limit: u64,
+    #[serde(default, rename = "fileOffset")]
+    file_offset: u64,
+    #[serde(default)]
+    filename: u64,
+    #[serde(default, rename = "buildId")]
+    build_id: u64,
+}
+
+#[derive(Debug, Default, Deserialize)]
 struct PprofLine {
     #[serde(default, rename = "functionId")]
     function_id: u64,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize)]
 struct PprofFunction {
     id: u64,
     #[serde(default)]
@@ -59,10 +133,159 @@ struct PprofFunction {
     file_name: Option<u64>,
 }
 
-/// Parse a pprof JSON export into a `Profile`.
+/// Parse a pprof profile into a `Profile`: a JSON export, or a binary
+/// `profile.proto`, gzip-compressed or not.
+///
+/// Frames are weighted by the default sample type, else the first. String
+/// labels put samples on a lane named after them (`handler=/api`).
 pub fn parse_pprof(data: &[u8]) -> Result<Profile, PprofParseError> {
-    let pprof: PprofJson = serde_json::from_slice(data)?;
+    parse_pprof_limited(data, &Limits::NONE)
+}
+
+/// [`parse_pprof`] that inflates gzip only within the memory budget of
+/// `limits`.
+pub(crate) fn parse_pprof_limited(
+    data: &[u8],
+    limits: &Limits,
+) -> Result<Profile, PprofParseError> {
+    if is_gzip(data) {
+        let raw = decompress_within(flate2::read::MultiGzDecoder::new(data), limits)?;
+        return build(&decode(&raw)?);
+    }
+    build(&decode(data)?)
+}
+
+/// A JSON export when `data` looks like JSON, else `profile.proto`.
+fn decode(data: &[u8]) -> Result<PprofProfile, PprofParseError> {
+    if data.trim_ascii_start().starts_with(b"{") {
+        Ok(serde_json::from_slice(data)?)
+    } else {
+        decode_proto(data)
+    }
+}
+
+// Field numbers from github.com/google/pprof/proto/profile.proto.
+const PROFILE_SAMPLE_TYPE: u32 = 1;
+const PROFILE_SAMPLE: u32 = 2;
+const PROFILE_MAPPING: u32 = 3;
+const PROFILE_LOCATION: u32 = 4;
+const PROFILE_FUNCTION: u32 = 5;
+const PROFILE_STRING_TABLE: u32 = 6;
+const PROFILE_DURATION_NANOS: u32 = 10;
+const PROFILE_PERIOD_TYPE: u32 = 11;
+const PROFILE_PERIOD: u32 = 12;
+const PROFILE_DEFAULT_SAMPLE_TYPE: u32 = 14;
+
+/// Decode the binary `profile.proto` encoding.
+fn decode_proto(data: &[u8]) -> Result<PprofProfile, PprofParseError> {
+    let mut pprof = PprofProfile::default();
+    for field in Fields::new(data) {
+        match field? {
+            (PROFILE_SAMPLE_TYPE, Value::Bytes(m)) => pprof.sample_type.push(value_type(m)?),
+            (PROFILE_SAMPLE, Value::Bytes(m)) => {
+                let mut sample = PprofSample::default();
+                for field in Fields::new(m) {
+                    match field? {
+                        (1, v) => sample.location_id.extend(varints(v)?),
+                        (2, v) => sample
+                            .value
+                            .extend(varints(v)?.into_iter().map(|v| v as i64)),
+                        (3, Value::Bytes(m)) => {
+                            let mut label = PprofLabel::default();
+                            for field in Fields::new(m) {
+                                match field? {
+                                    (1, v) => label.key = v.as_u64().unwrap_or_default(),
+                                    (2, v) => label.str = v.as_u64().unwrap_or_default(),
+                                    _ => {}
+                                }
+                            }
+                            sample.label.push(label);
+                        }
+                        _ => {}
+                    }
+                }
+                pprof.samples.push(sample);
+            }
+            (PROFILE_MAPPING, Value::Bytes(m)) => {
+                let mut mapping = PprofMapping::default();
+                for field in Fields::new(m) {
+                    let (number, v) = field?;
+                    let v = v.as_u64().unwrap_or_default();
+                    match number {
+                        1 => mapping.id = v,
+                        2 => mapping.memory_start = v,
+                        3 => mapping.memory_limit = v,
+                        4 => mapping.file_offset = v,
+                        5 => mapping.filename = v,
+                        6 => mapping.build_id = v,
+                        _ => {}
+                    }
+                }
+                pprof.mappings.push(mapping);
+            }
+            (PROFILE_LOCATION, Value::Bytes(m)) => {
+                let mut location = PprofLocation::default();
+                for field in Fields::new(m) {
+                    match field? {
+                        (1, v) => location.id = v.as_u64().unwrap_or_default(),
+                        (2, v) => location.mapping_id = v.as_u64().unwrap_or_default(),
+                        (3, v) => location.address = v.as_u64().unwrap_or_default(),
+                        (4, Value::Bytes(m)) => {
+                            let mut line = PprofLine::default();
+                            for field in Fields::new(m) {
+                                if let (1, v) = field? {
+                                    line.function_id = v.as_u64().unwrap_or_default();
+                                }
+                            }
+                            location.line.push(line);
+                        }
+                        _ => {}
+                    }
+                }
+                pprof.locations.push(location);
+            }
+            (PROFILE_FUNCTION, Value::Bytes(m)) => {
+                let mut function = PprofFunction::default();
+                for field in Fields::new(m) {
+                    match field? {
+                        (1, v) => function.id = v.as_u64().unwrap_or_default(),
+                        (2, v) => function.name = v.as_u64().unwrap_or_default(),
+                        (4, v) => function.file_name = v.as_u64(),
+                        _ => {}
+                    }
+                }
+                pprof.functions.push(function);
+            }
+            (PROFILE_STRING_TABLE, v) => pprof.string_table.push(
+                v.as_str()
+                    .ok_or(PprofParseError::Protobuf("string is not UTF-8"))?
+                    .to_string(),
+            ),
+            (PROFILE_DURATION_NANOS, v) => pprof.duration_nanos = v.as_u64(),
+            (PROFILE_PERIOD_TYPE, Value::Bytes(m)) => pprof.period_type = Some(value_type(m)?),
+            (PROFILE_PERIOD, v) => pprof.period = v.as_u64().unwrap_or_default() as i64,
+            (PROFILE_DEFAULT_SAMPLE_TYPE, v) => {
+                pprof.default_sample_type = v.as_u64().unwrap_or_default();
+            }
+            _ => {}
+        }
+    }
+    Ok(pprof)
+}
+
+fn value_type(data: &[u8]) -> Result<PprofValueType, WireError> {
+    let mut value_type = PprofValueType::default();
+    for field in Fields::new(data) {
+        match field? {
+            (1, v) => value_type.kind = v.as_u64().unwrap_or_default(),
+            (2, v) => value_type.unit = v.as_u64().unwrap_or_default(),
+            _ => {}
+        }
+    }
+    Ok(value_type)
+}
 
+fn build(pprof: &PprofProfile) -> Result<Profile, PprofParseError> {
     if pprof.samples.is_empty() {
         return Err(PprofParseError::NoSamples);
     }
@@ -73,33 +296,130 @@ pub fn parse_pprof(data: &[u8]) -> Result<Profile, PprofParseError> {
     let loc_map: std::collections::HashMap<u64, &PprofLocation> =
         pprof.locations.iter().map(|l| (l.id, l)).collect();
 
-    let resolve_name = |loc_id: u64| -> String {
-        if let Some(loc) = loc_map.get(&loc_id)
-            && let Some(line) = loc.line.first()
-            && let Some(func) = func_map.get(&line.function_id)
-            && let Some(name) = pprof.string_table.get(func.name as usize)
-            && !name.is_empty()
+    let mapping_map: std::collections::HashMap<u64, &PprofMapping> =
+        pprof.mappings.iter().map(|m| (m.id, m)).collect();
+    let string = |idx: u64| pprof.string_table.get(idx as usize).map(String::as_str);
+
+    // One binary per file and build id; the segments of a file share it.
+    let mut binaries: Vec<Binary> = Vec::new();
+    let mut mapping_binary: std::collections::HashMap<u64, u32> = std::collections::HashMap::new();
+    for mapping in &pprof.mappings {
+        let Some(name) = string(mapping.filename).filter(|n| !n.is_empty()) else {
+            continue;
+        };
+        // Index 0 is the empty string by spec, i.e. no build id.
+        let build_id = (mapping.build_id != 0)
+            .then(|| string(mapping.build_id))
+            .flatten()
+            .filter(|b| !b.is_empty());
+        let index = match binaries
+            .iter()
+            .position(|b| b.name.as_ref() == name && b.build_id.as_deref() == build_id)
         {
+            Some(index) => index,
+            None => {
+                binaries.push(Binary {
+                    name: name.into(),
+                    build_id: build_id.map(Into::into),
+                });
+                binaries.len() - 1
+            }
+        };
+        mapping_binary.insert(mapping.id, index as u32);
+    }
+
+    let function_name = |line: &PprofLine| -> Option<&String> {
+        let func = func_map.get(&line.function_id)?;
+        pprof
+            .string_table
+            .get(func.name as usize)
+            .filter(|n| !n.is_empty())
+    };
+
+    // A location's lines run from the innermost inlined function to the
+    // one the code was compiled into, which names the frame. Unsymbolized
+    // locations keep their address, module-relative when the mapping is
+    // known, so `symbolicate` can resolve them later.
+    let resolve_name = |loc_id: u64| -> String {
+        let Some(loc) = loc_map.get(&loc_id) else {
+            return format!("loc-{loc_id}");
+        };
+        if let Some(name) = loc.line.last().and_then(function_name) {
             return name.clone();
         }
-        format!("loc-{loc_id}")
+        if loc.address == 0 {
+            return format!("loc-{loc_id}");
+        }
+        if let Some(mapping) = mapping_map.get(&loc.mapping_id)
+            && (mapping.memory_start..mapping.memory_limit).contains(&loc.address)
+            && let Some(file) = pprof.string_table.get(mapping.filename as usize)
+            && !file.is_empty()
+        {
+            return crate::symbolicate::module_frame_name(
+                file,
+                loc.address - mapping.memory_start + mapping.file_offset,
+            );
+        }
+        format!("{:#x}", loc.address)
     };
 
     let resolve_file = |loc_id: u64| -> Option<String> {
         let loc = loc_map.get(&loc_id)?;
-        let line = loc.line.first()?;
+        let line = loc.line.last()?;
         let func = func_map.get(&line.function_id)?;
         let file_idx = func.file_name? as usize;
         pprof.string_table.get(file_idx).cloned()
     };
 
+    // Inlined callers first, as `Frame::inlined` lists them.
+    let resolve_inlined = |loc_id: u64| -> Vec<String> {
+        let Some((_, inlined)) = loc_map.get(&loc_id).and_then(|loc| loc.line.split_last()) else {
+            return Vec::new();
+        };
+        inlined
+            .iter()
+            .rev()
+            .filter_map(function_name)
+            .cloned()
+            .collect()
+    };
+
+    // Profiles with several sample types (Go heap profiles record
+    // allocations and in-use memory, by count and by bytes) name the one to
+    // show; otherwise it's the first.
+    let value_index = string(pprof.default_sample_type)
+        .filter(|_| pprof.default_sample_type != 0)
+        .and_then(|kind| {
+            pprof
+                .sample_type
+                .iter()
+                .position(|t| string(t.kind) == Some(kind))
+        })
+        .unwrap_or(0);
+
+    let lane = |sample: &PprofSample| -> Option<String> {
+        let labels: Vec<String> = sample
+            .label
+            .iter()
+            .filter(|l| l.str != 0)
+            .filter_map(|l| Some(format!("{}={}", string(l.key)?, string(l.str)?)))
+            .collect();
+        (!labels.is_empty()).then(|| labels.join(", "))
+    };
+
     let mut frames: Vec<Frame> = Vec::new();
     let mut next_id: u64 = 0;
-    let mut offset: f64 = 0.0;
+    // Each lane's samples are laid out end to end.
+    let mut offsets: std::collections::HashMap<Option<String>, f64> =
+        std::collections::HashMap::new();
 
     for sample in &pprof.samples {
-        let weight = sample.value.first().copied().unwrap_or(1) as f64;
-        let sample_end = offset + weight;
+        let weight = sample.value.get(value_index).copied().unwrap_or(1) as f64;
+        let thread = lane(sample);
+        let offset = offsets.entry(thread.clone()).or_default();
+        let sample_start = *offset;
+        let sample_end = sample_start + weight;
+        *offset = sample_end;
 
         // pprof stacks are leaf-first; reverse to get root-first.
         let stack: Vec<u64> = sample.location_id.iter().copied().rev().collect();
@@ -108,6 +428,10 @@ pub fn parse_pprof(data: &[u8]) -> Result<Profile, PprofParseError> {
         for (depth, &loc_id) in stack.iter().enumerate() {
             let name = resolve_name(loc_id);
             let category = resolve_file(loc_id);
+            let binary = loc_map
+                .get(&loc_id)
+                .and_then(|loc| mapping_binary.get(&loc.mapping_id))
+                .copied();
             let is_leaf = depth == stack.len() - 1;
 
             let id = next_id;
@@ -116,42 +440,50 @@ pub fn parse_pprof(data: &[u8]) -> Result<Profile, PprofParseError> {
             frames.push(Frame {
                 id,
                 name,
-                start: offset,
+                start: sample_start,
                 end: sample_end,
                 depth: depth as u32,
                 category,
                 parent: parent_id,
                 self_time: if is_leaf { weight } else { 0.0 },
-                thread: None,
+                thread: thread.clone(),
+                truncated: false,
+                pid: None,
+                tid: None,
+                source: None,
+                binary,
+                inlined: resolve_inlined(loc_id),
             });
 
             parent_id = Some(id);
         }
-
-        offset = sample_end;
     }
+    let offset = offsets.into_values().fold(0.0, f64::max);
 
-    // Recompute self_time properly.
-    let child_time = {
-        let mut map = std::collections::HashMap::<u64, f64>::new();
-        for f in &frames {
-            if let Some(pid) = f.parent {
-                *map.entry(pid).or_default() += f.duration();
-            }
-        }
-        map
-    };
-    for f in &mut frames {
-        let children_total = child_time.get(&f.id).copied().unwrap_or(0.0);
-        f.self_time = (f.duration() - children_total).max(0.0);
-    }
+    // Count-weighted profiles are sample counts; the period (if recorded in
+    // a time unit) gives the time each sample stands for.
+    let unit = pprof
+        .sample_type
+        .get(value_index)
+        .and_then(|t| string(t.unit));
+    let counts_samples = matches!(unit, Some("count" | "samples"));
+    let sample_interval_us = pprof
+        .period_type
+        .as_ref()
+        .filter(|_| counts_samples && pprof.period > 0)
+        .and_then(|t| match string(t.unit)? {
+            "nanoseconds" => Some(pprof.period as f64 / 1000.0),
+            "microseconds" => Some(pprof.period as f64),
+            "milliseconds" => Some(pprof.period as f64 * 1000.0),
+            _ => None,
+        });
 
     let duration_us = pprof
         .duration_nanos
         .map(|ns| ns as f64 / 1000.0)
         .unwrap_or(offset);
 
-    Ok(Profile::new(
+    let mut profile = Profile::new(
         ProfileMetadata {
             name: None,
             start_time: 0.0,
@@ -162,9 +494,17 @@ pub fn parse_pprof(data: &[u8]) -> Result<Profile, PprofParseError> {
             },
             format: "pprof".to_string(),
             time_domain: None,
+            value_unit: match unit {
+                Some("count" | "samples") => Some(ValueUnit::Samples),
+                Some("bytes") => Some(ValueUnit::Bytes),
+                _ => None,
+            },
+            sample_interval_us,
         },
         frames,
-    ))
+    );
+    profile.binaries = binaries;
+    Ok(profile)
 }
 
 #[cfg(test)]
@@ -202,9 +542,213 @@ mod tests {
         assert_eq!(main_f.depth, 0);
     }
 
+    #[test]
+    fn unsymbolized_locations_keep_module_offsets() {
+        let json = r#"{
+            "samples": [{"locationId": [2, 1], "value": [1]}],
+            "mappings": [{"id": 1, "memoryStart": 4096, "memoryLimit": 8192, "fileOffset": 0, "filename": 0}],
+            "locations": [
+                {"id": 1, "mappingId": 1, "address": 4352},
+                {"id": 2, "address": 65536}
+            ],
+            "stringTable": ["/usr/lib/libgame.so"]
+        }"#;
+
+        let profile = parse_pprof(json.as_bytes()).unwrap();
+        let names: Vec<_> = profile.frames.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(names, ["libgame.so+0x100", "0x10000"]);
+    }
+
+    #[test]
+    fn frames_carry_their_mapped_binary() {
+        let json = r#"{
+            "samples": [{"locationId": [3, 2, 1], "value": [1]}],
+            "mappings": [
+                {"id": 1, "memoryStart": 0, "memoryLimit": 4096, "filename": 2, "buildId": 3},
+                {"id": 2, "memoryStart": 4096, "memoryLimit": 8192, "filename": 2, "buildId": 3},
+                {"id": 3, "memoryStart": 65536, "memoryLimit": 131072, "filename": 4}
+            ],
+            "locations": [
+                {"id": 1, "mappingId": 1, "line": [{"functionId": 1}]},
+                {"id": 2, "mappingId": 2, "line": [{"functionId": 1}]},
+                {"id": 3, "mappingId": 3, "line": [{"functionId": 2}]}
+            ],
+            "functions": [{"id": 1, "name": 0}, {"id": 2, "name": 1}],
+            "stringTable": ["main", "memcpy", "/srv/app", "f00d", "/usr/lib/libc.so.6"]
+        }"#;
+
+        let profile = parse_pprof(json.as_bytes()).unwrap().into_visual_profile();
+        assert_eq!(
+            profile.binaries,
+            [
+                Binary {
+                    name: "/srv/app".into(),
+                    build_id: Some("f00d".into()),
+                },
+                Binary {
+                    name: "/usr/lib/libc.so.6".into(),
+                    build_id: None,
+                },
+            ]
+        );
+        let binaries: Vec<_> = profile
+            .all_spans()
+            .map(|s| profile.binary_of(s).map(|b| b.name.as_ref()))
+            .collect();
+        assert_eq!(
+            binaries,
+            [
+                Some("/srv/app"),
+                Some("/srv/app"),
+                Some("/usr/lib/libc.so.6")
+            ]
+        );
+    }
+
+    #[test]
+    fn inlined_lines_fold_into_their_frame() {
+        let json = r#"{
+            "samples": [{"locationId": [2, 1], "value": [10]}],
+            "locations": [
+                {"id": 1, "line": [{"functionId": 1}]},
+                {"id": 2, "line": [{"functionId": 4}, {"functionId": 3}, {"functionId": 2}]}
+            ],
+            "functions": [
+                {"id": 1, "name": 0}, {"id": 2, "name": 1},
+                {"id": 3, "name": 2}, {"id": 4, "name": 3}
+            ],
+            "stringTable": ["main", "run", "Vec::push", "grow"]
+        }"#;
+
+        let profile = parse_pprof(json.as_bytes()).unwrap();
+        let frames: Vec<(&str, u32, Vec<&str>)> = profile
+            .frames
+            .iter()
+            .map(|f| {
+                (
+                    f.name.as_str(),
+                    f.depth,
+                    f.inlined.iter().map(String::as_str).collect(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            frames,
+            [("main", 0, vec![]), ("run", 1, vec!["Vec::push", "grow"])]
+        );
+    }
+
+    #[test]
+    fn count_weighted_samples_carry_interval() {
+        let json = r#"{
+            "sampleType": [{"type": 1, "unit": 2}],
+            "periodType": {"type": 3, "unit": 4},
+            "period": 10000000,
+            "samples": [{"locationId": [1], "value": [5]}],
+            "locations": [{"id": 1, "line": [{"functionId": 1}]}],
+            "functions": [{"id": 1, "name": 0}],
+            "stringTable": ["main", "samples", "count", "cpu", "nanoseconds"]
+        }"#;
+
+        let profile = parse_pprof(json.as_bytes()).unwrap().into_visual_profile();
+        assert_eq!(profile.meta.value_unit, ValueUnit::Samples);
+        assert_eq!(profile.meta.sample_interval_us, Some(10_000.0));
+        assert_eq!(profile.meta.format_value(5.0), "5 samples (~50.0ms)");
+    }
+
     #[test]
     fn empty_samples_errors() {
         let json = r#"{"samples":[],"locations":[],"functions":[],"stringTable":[]}"#;
         assert!(parse_pprof(json.as_bytes()).is_err());
     }
+
+    /// A gzipped heap profile: `alloc_space` is the default of its two
+    /// sample types, and `Vec::push` is inlined into `main.run`.
+    fn heap_profile() -> Vec<u8> {
+        use crate::parsers::protobuf::Msg;
+        use std::io::Write;
+
+        let strings = [
+            "",
+            "alloc_objects",
+            "count",
+            "alloc_space",
+            "bytes",
+            "main",
+            "main.run",
+            "Vec::push",
+            "handler",
+            "/api",
+            "/srv/app",
+        ];
+        let value_type = |kind, unit| Msg::default().varint(1, kind).varint(2, unit);
+        let sample = |locations: &[u64], values: &[u64]| {
+            Msg::default().packed(1, locations).packed(2, values)
+        };
+        let location = |id, functions: &[u64]| {
+            functions
+                .iter()
+                .fold(Msg::default().varint(1, id).varint(2, 1), |m, &f| {
+                    m.msg(4, Msg::default().varint(1, f))
+                })
+        };
+        let function = |id, name| Msg::default().varint(1, id).varint(2, name);
+        let profile = strings.iter().fold(
+            Msg::default()
+                .msg(1, value_type(1, 2))
+                .msg(1, value_type(3, 4))
+                .msg(2, sample(&[2, 1], &[1, 64]))
+                .msg(
+                    2,
+                    sample(&[2, 1], &[3, 512]).msg(3, Msg::default().varint(1, 8).varint(2, 9)),
+                )
+                .msg(
+                    3,
+                    Msg::default().varint(1, 1).varint(3, 0x1000).varint(5, 10),
+                )
+                .msg(4, location(1, &[1]))
+                .msg(4, location(2, &[3, 2]))
+                .msg(5, function(1, 5))
+                .msg(5, function(2, 6))
+                .msg(5, function(3, 7))
+                .varint(14, 3),
+            |m, s| m.str(6, s),
+        );
+
+        let mut gz = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::fast());
+        gz.write_all(&profile.0).unwrap();
+        gz.finish().unwrap()
+    }
+
+    #[test]
+    fn parse_gzipped_proto() {
+        let data = heap_profile();
+        assert!(is_gzip(&data));
+        let profile = parse_pprof(&data).unwrap();
+
+        let frames: Vec<_> = profile
+            .frames
+            .iter()
+            .map(|f| (f.name.as_str(), f.thread.as_deref(), f.start, f.end))
+            .collect();
+        assert_eq!(
+            frames,
+            [
+                ("main", None, 0.0, 64.0),
+                ("main.run", None, 0.0, 64.0),
+                ("main", Some("handler=/api"), 0.0, 512.0),
+                ("main.run", Some("handler=/api"), 0.0, 512.0),
+            ]
+        );
+        assert_eq!(profile.frames[1].inlined, ["Vec::push"]);
+        assert_eq!(profile.metadata.value_unit, Some(ValueUnit::Bytes));
+        assert_eq!(profile.binaries[0].name.as_ref(), "/srv/app");
+    }
+
+    #[test]
+    fn truncated_proto_is_an_error() {
+        let mut data = heap_profile();
+        data.truncate(data.len() / 2);
+        assert!(parse_pprof(&data).is_err());
+    }
 }
@@ -0,0 +1,173 @@
+//! Protobuf wire format reading for the binary parsers. Messages are
+//! walked field by field without a schema; callers pick the field numbers
+//! they know and skip the rest.
+
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+#[error("{0}")]
+pub(super) struct WireError(pub(super) &'static str);
+
+/// A decoded protobuf field value.
+#[derive(Debug, Clone, Copy)]
+pub(super) enum Value<'a> {
+    Varint(u64),
+    Fixed64(u64),
+    Bytes(&'a [u8]),
+    Fixed32(u32),
+}
+
+impl<'a> Value<'a> {
+    pub(super) fn as_u64(self) -> Option<u64> {
+        match self {
+            Self::Varint(v) | Self::Fixed64(v) => Some(v),
+            Self::Fixed32(v) => Some(u64::from(v)),
+            Self::Bytes(_) => None,
+        }
+    }
+
+    pub(super) fn as_str(self) -> Option<&'a str> {
+        match self {
+            Self::Bytes(b) => std::str::from_utf8(b).ok(),
+            _ => None,
+        }
+    }
+}
+
+/// The fields of one protobuf message, in wire order.
+pub(super) struct Fields<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Fields<'a> {
+    pub(super) fn new(data: &'a [u8]) -> Self {
+        Self { data }
+    }
+
+    fn varint(&mut self) -> Result<u64, WireError> {
+        let mut value = 0_u64;
+        for shift in (0..64).step_by(7) {
+            let (&byte, rest) = self
+                .data
+                .split_first()
+                .ok_or(WireError("truncated varint"))?;
+            self.data = rest;
+            value |= u64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(WireError("varint too long"))
+    }
+
+    fn take(&mut self, n: usize) -> Result<&'a [u8], WireError> {
+        if n > self.data.len() {
+            return Err(WireError("field runs past its message"));
+        }
+        let (head, rest) = self.data.split_at(n);
+        self.data = rest;
+        Ok(head)
+    }
+
+    fn field(&mut self) -> Result<(u32, Value<'a>), WireError> {
+        let key = self.varint()?;
+        let number = u32::try_from(key >> 3).map_err(|_| WireError("field number out of range"))?;
+        let value = match key & 7 {
+            0 => Value::Varint(self.varint()?),
+            1 => Value::Fixed64(u64::from_le_bytes(
+                self.take(8)?.try_into().unwrap_or_default(),
+            )),
+            2 => {
+                let len = usize::try_from(self.varint()?)
+                    .map_err(|_| WireError("length out of range"))?;
+                Value::Bytes(self.take(len)?)
+            }
+            5 => Value::Fixed32(u32::from_le_bytes(
+                self.take(4)?.try_into().unwrap_or_default(),
+            )),
+            _ => return Err(WireError("unsupported wire type")),
+        };
+        Ok((number, value))
+    }
+}
+
+impl<'a> Iterator for Fields<'a> {
+    type Item = Result<(u32, Value<'a>), WireError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.data.is_empty() {
+            return None;
+        }
+        let field = self.field();
+        if field.is_err() {
+            // Stop after the first error rather than misreading the rest.
+            self.data = &[];
+        }
+        Some(field)
+    }
+}
+
+/// Packed repeated varints, or one unpacked element.
+pub(super) fn varints(value: Value<'_>) -> Result<Vec<u64>, WireError> {
+    match value {
+        Value::Bytes(data) => {
+            let mut fields = Fields::new(data);
+            let mut out = Vec::new();
+            while !fields.data.is_empty() {
+                out.push(fields.varint()?);
+            }
+            Ok(out)
+        }
+        v => Ok(v.as_u64().into_iter().collect()),
+    }
+}
+
+/// Minimal protobuf writer for building messages by hand.
+#[cfg(test)]
+#[derive(Default)]
+pub(super) struct Msg(pub(super) Vec<u8>);
+
+#[cfg(test)]
+impl Msg {
+    fn raw_varint(&mut self, mut v: u64) {
+        while v >= 0x80 {
+            self.0.push((v & 0x7f) as u8 | 0x80);
+            v >>= 7;
+        }
+        self.0.push(v as u8);
+    }
+
+    pub(super) fn varint(mut self, number: u32, v: u64) -> Self {
+        self.raw_varint(u64::from(number) << 3);
+        self.raw_varint(v);
+        self
+    }
+
+    pub(super) fn double(mut self, number: u32, v: f64) -> Self {
+        self.raw_varint(u64::from(number) << 3 | 1);
+        self.0.extend_from_slice(&v.to_bits().to_le_bytes());
+        self
+    }
+
+    pub(super) fn str(mut self, number: u32, s: &str) -> Self {
+        self.raw_varint(u64::from(number) << 3 | 2);
+        self.raw_varint(s.len() as u64);
+        self.0.extend_from_slice(s.as_bytes());
+        self
+    }
+
+    pub(super) fn packed(self, number: u32, values: &[u64]) -> Self {
+        let mut body = Msg::default();
+        for &v in values {
+            body.raw_varint(v);
+        }
+        self.msg(number, body)
+    }
+
+    pub(super) fn msg(mut self, number: u32, m: Msg) -> Self {
+        self.raw_varint(u64::from(number) << 3 | 2);
+        self.raw_varint(m.0.len() as u64);
+        self.0.extend(m.0);
+        self
+    }
+}
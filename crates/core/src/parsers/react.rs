@@ -322,6 +322,12 @@ impl FiberTree {
                 parent: parent_frame_id,
                 self_time,
                 thread: Some("React Components".to_string()),
+                truncated: false,
+                pid: None,
+                tid: None,
+                source: None,
+                binary: None,
+                inlined: vec![],
             });
 
             // Queue children in reverse order so first child is processed first.
@@ -444,11 +450,15 @@ pub fn parse_react_profile(data: &[u8]) -> Result<Profile, ReactParseError> {
                         .values()
                         .flat_map(|n| n.children.iter().copied())
                         .collect();
-                    tree.nodes
+                    let mut roots: Vec<u64> = tree
+                        .nodes
                         .keys()
                         .copied()
                         .filter(|id| !child_set.contains(id))
-                        .collect::<Vec<_>>()
+                        .collect();
+                    // Map order would change span ids between loads.
+                    roots.sort_unstable();
+                    roots
                 } else {
                     initial_root_ids.clone()
                 };
@@ -493,6 +503,12 @@ pub fn parse_react_profile(data: &[u8]) -> Result<Profile, ReactParseError> {
                         parent: None,
                         self_time,
                         thread: Some("React Components".to_string()),
+                        truncated: false,
+                        pid: None,
+                        tid: None,
+                        source: None,
+                        binary: None,
+                        inlined: vec![],
                     });
 
                     offset += actual_us;
@@ -523,6 +539,8 @@ pub fn parse_react_profile(data: &[u8]) -> Result<Profile, ReactParseError> {
                 origin_label: Some("React DevTools (performance.now)".into()),
                 navigation_start_us: None,
             }),
+            value_unit: None,
+            sample_interval_us: None,
         },
         frames,
     ))
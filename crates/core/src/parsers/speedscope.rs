@@ -1,6 +1,8 @@
 use serde::Deserialize;
 use thiserror::Error;
 
+use flame_cat_protocol::ValueUnit;
+
 use crate::model::{Frame, Profile, ProfileMetadata};
 
 #[derive(Debug, Error)]
@@ -34,9 +36,19 @@ struct SpeedscopeFrame {
     file: Option<String>,
 }
 
+#[derive(Debug, Deserialize)]
+struct SpeedscopeProfile {
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    unit: Option<String>,
+    #[serde(flatten)]
+    data: ProfileData,
+}
+
 #[derive(Debug, Deserialize)]
 #[serde(tag = "type")]
-enum SpeedscopeProfile {
+enum ProfileData {
     #[serde(rename = "evented")]
     Evented {
         #[serde(rename = "startValue")]
@@ -64,157 +76,204 @@ struct SpeedscopeEvent {
     at: f64,
 }
 
-/// Parse a speedscope JSON file into a `Profile`.
+/// Value unit of a speedscope `unit`, plus the factor that converts values
+/// into it (there is no seconds unit, so seconds become milliseconds).
+fn value_unit(unit: Option<&str>) -> (Option<ValueUnit>, f64) {
+    match unit {
+        Some("nanoseconds") => (Some(ValueUnit::Nanoseconds), 1.0),
+        Some("microseconds") => (Some(ValueUnit::Microseconds), 1.0),
+        Some("milliseconds") => (Some(ValueUnit::Milliseconds), 1.0),
+        Some("seconds") => (Some(ValueUnit::Milliseconds), 1000.0),
+        Some("bytes") => (Some(ValueUnit::Bytes), 1.0),
+        Some("none") => (Some(ValueUnit::Weight), 1.0),
+        _ => (None, 1.0),
+    }
+}
+
+/// Parse a speedscope JSON file into a `Profile`, with one thread per
+/// speedscope profile. Values are read in the first profile's unit.
 pub fn parse_speedscope(data: &[u8]) -> Result<Profile, SpeedscopeParseError> {
     let file: SpeedscopeFile = serde_json::from_slice(data)?;
-
-    let shared_frames = file.shared.as_ref().map(|s| &s.frames[..]).unwrap_or(&[]);
-
     if file.profiles.is_empty() {
         return Err(SpeedscopeParseError::Unsupported);
     }
+    let shared_frames = file.shared.as_ref().map(|s| &s.frames[..]).unwrap_or(&[]);
+    let (unit, scale) = value_unit(file.profiles[0].unit.as_deref());
 
     let mut frames: Vec<Frame> = Vec::new();
     let mut next_id: u64 = 0;
     let mut global_start = f64::INFINITY;
     let mut global_end = f64::NEG_INFINITY;
+    let mut thread_names: Vec<String> = Vec::new();
+    for (index, profile) in file.profiles.iter().enumerate() {
+        // Threads are grouped by name, so repeated names need telling apart.
+        let mut thread = profile_name(profile, index);
+        if thread_names.contains(&thread) {
+            thread = format!("{thread} #{}", index + 1);
+        }
+        let (start, end) = convert_profile(
+            profile,
+            shared_frames,
+            scale,
+            &thread,
+            &mut frames,
+            &mut next_id,
+        );
+        thread_names.push(thread);
+        global_start = global_start.min(start);
+        global_end = global_end.max(end);
+    }
+    Ok(build_profile(
+        file.name,
+        unit,
+        global_start,
+        global_end,
+        frames,
+    ))
+}
 
-    for profile in &file.profiles {
-        match profile {
-            SpeedscopeProfile::Evented {
-                start_value,
-                end_value,
-                events,
-                ..
-            } => {
-                global_start = global_start.min(*start_value);
-                global_end = global_end.max(*end_value);
-
-                // Process open/close events using a stack.
-                let mut stack: Vec<usize> = Vec::new(); // indices into frames vec
-
-                for event in events {
-                    match event.event_type.as_str() {
-                        "O" => {
-                            let name = shared_frames
-                                .get(event.frame)
-                                .map(|f| f.name.clone())
-                                .unwrap_or_else(|| format!("frame-{}", event.frame));
-                            let category =
-                                shared_frames.get(event.frame).and_then(|f| f.file.clone());
+/// Parse each profile of a speedscope file into its own `Profile`, named
+/// after it, for loading as separate session profiles.
+pub fn parse_speedscope_profiles(data: &[u8]) -> Result<Vec<Profile>, SpeedscopeParseError> {
+    let file: SpeedscopeFile = serde_json::from_slice(data)?;
+    if file.profiles.is_empty() {
+        return Err(SpeedscopeParseError::Unsupported);
+    }
+    let shared_frames = file.shared.as_ref().map(|s| &s.frames[..]).unwrap_or(&[]);
+    Ok(file
+        .profiles
+        .iter()
+        .enumerate()
+        .map(|(index, profile)| {
+            let (unit, scale) = value_unit(profile.unit.as_deref());
+            let mut frames = Vec::new();
+            let mut next_id = 0;
+            let (start, end) = convert_profile(
+                profile,
+                shared_frames,
+                scale,
+                "Main",
+                &mut frames,
+                &mut next_id,
+            );
+            build_profile(Some(profile_name(profile, index)), unit, start, end, frames)
+        })
+        .collect())
+}
 
-                            let parent_id = stack.last().map(|&idx| frames[idx].id);
-                            let depth = stack.len() as u32;
+fn profile_name(profile: &SpeedscopeProfile, index: usize) -> String {
+    profile
+        .name
+        .clone()
+        .unwrap_or_else(|| format!("Profile {}", index + 1))
+}
 
-                            let id = next_id;
-                            next_id += 1;
-                            let frame_idx = frames.len();
+fn build_profile(
+    name: Option<String>,
+    value_unit: Option<ValueUnit>,
+    start: f64,
+    end: f64,
+    frames: Vec<Frame>,
+) -> Profile {
+    Profile::new(
+        ProfileMetadata {
+            name,
+            start_time: if start.is_finite() { start } else { 0.0 },
+            end_time: if end.is_finite() { end } else { 0.0 },
+            format: "speedscope".to_string(),
+            time_domain: None,
+            value_unit,
+            sample_interval_us: None,
+        },
+        frames,
+    )
+}
 
-                            frames.push(Frame {
-                                id,
-                                name,
-                                start: event.at,
-                                end: event.at, // updated on close
-                                depth,
-                                category,
-                                parent: parent_id,
-                                self_time: 0.0,
-                                thread: None,
-                            });
+/// Append the spans of one speedscope profile to `frames` on `thread`,
+/// scaling values by `scale`. Returns the profile's scaled start and end.
+fn convert_profile(
+    profile: &SpeedscopeProfile,
+    shared_frames: &[SpeedscopeFrame],
+    scale: f64,
+    thread: &str,
+    frames: &mut Vec<Frame>,
+    next_id: &mut u64,
+) -> (f64, f64) {
+    let frame = |index: usize, id: u64, start: f64, depth: u32, parent: Option<u64>| Frame {
+        id,
+        name: shared_frames
+            .get(index)
+            .map(|f| f.name.clone())
+            .unwrap_or_else(|| format!("frame-{index}")),
+        start,
+        end: start,
+        depth,
+        category: shared_frames.get(index).and_then(|f| f.file.clone()),
+        parent,
+        self_time: 0.0,
+        thread: Some(thread.to_string()),
+        truncated: false,
+        pid: None,
+        tid: None,
+        source: None,
+        binary: None,
+        inlined: vec![],
+    };
 
-                            stack.push(frame_idx);
-                        }
-                        "C" => {
-                            if let Some(frame_idx) = stack.pop() {
-                                frames[frame_idx].end = event.at;
-                            }
+    match &profile.data {
+        ProfileData::Evented {
+            start_value,
+            end_value,
+            events,
+        } => {
+            // Process open/close events using a stack.
+            let mut stack: Vec<usize> = Vec::new(); // indices into frames vec
+            for event in events {
+                match event.event_type.as_str() {
+                    "O" => {
+                        let parent = stack.last().map(|&idx| frames[idx].id);
+                        stack.push(frames.len());
+                        frames.push(frame(
+                            event.frame,
+                            *next_id,
+                            event.at * scale,
+                            stack.len() as u32 - 1,
+                            parent,
+                        ));
+                        *next_id += 1;
+                    }
+                    "C" => {
+                        if let Some(frame_idx) = stack.pop() {
+                            frames[frame_idx].end = event.at * scale;
                         }
-                        _ => {}
                     }
+                    _ => {}
                 }
             }
-            SpeedscopeProfile::Sampled {
-                start_value,
-                end_value,
-                samples,
-                weights,
-                ..
-            } => {
-                global_start = global_start.min(*start_value);
-                global_end = global_end.max(*end_value);
-
-                let mut offset = *start_value;
-
-                for (i, sample) in samples.iter().enumerate() {
-                    let weight = weights.get(i).copied().unwrap_or(1.0);
-                    let sample_end = offset + weight;
-
-                    let mut parent_id: Option<u64> = None;
-                    for (depth, &frame_idx) in sample.iter().enumerate() {
-                        let name = shared_frames
-                            .get(frame_idx)
-                            .map(|f| f.name.clone())
-                            .unwrap_or_else(|| format!("frame-{frame_idx}"));
-                        let category = shared_frames.get(frame_idx).and_then(|f| f.file.clone());
-
-                        let id = next_id;
-                        next_id += 1;
-                        let is_leaf = depth == sample.len() - 1;
-
-                        frames.push(Frame {
-                            id,
-                            name,
-                            start: offset,
-                            end: sample_end,
-                            depth: depth as u32,
-                            category,
-                            parent: parent_id,
-                            self_time: if is_leaf { weight } else { 0.0 },
-                            thread: None,
-                        });
-
-                        parent_id = Some(id);
-                    }
-
-                    offset = sample_end;
+            (start_value * scale, end_value * scale)
+        }
+        ProfileData::Sampled {
+            start_value,
+            end_value,
+            samples,
+            weights,
+        } => {
+            let mut offset = start_value * scale;
+            for (i, sample) in samples.iter().enumerate() {
+                let sample_end = offset + weights.get(i).copied().unwrap_or(1.0) * scale;
+                let mut parent = None;
+                for (depth, &frame_idx) in sample.iter().enumerate() {
+                    let mut f = frame(frame_idx, *next_id, offset, depth as u32, parent);
+                    f.end = sample_end;
+                    frames.push(f);
+                    parent = Some(*next_id);
+                    *next_id += 1;
                 }
+                offset = sample_end;
             }
+            (start_value * scale, end_value * scale)
         }
     }
-
-    // Compute self times for evented profiles.
-    let child_time = {
-        let mut map = std::collections::HashMap::<u64, f64>::new();
-        for f in &frames {
-            if let Some(pid) = f.parent {
-                *map.entry(pid).or_default() += f.duration();
-            }
-        }
-        map
-    };
-    for f in &mut frames {
-        let children_total = child_time.get(&f.id).copied().unwrap_or(0.0);
-        f.self_time = (f.duration() - children_total).max(0.0);
-    }
-
-    Ok(Profile::new(
-        ProfileMetadata {
-            name: file.name,
-            start_time: if global_start.is_finite() {
-                global_start
-            } else {
-                0.0
-            },
-            end_time: if global_end.is_finite() {
-                global_end
-            } else {
-                0.0
-            },
-            format: "speedscope".to_string(),
-            time_domain: None,
-        },
-        frames,
-    ))
 }
 
 #[cfg(test)]
@@ -291,6 +350,48 @@ mod tests {
         assert_eq!(profile.frames.len(), 5); // 2+2+1
     }
 
+    #[test]
+    fn one_thread_or_profile_per_speedscope_profile() {
+        let json = r#"{
+            "shared": {"frames": [{"name": "main"}, {"name": "work"}]},
+            "profiles": [
+                {"type": "evented", "name": "worker", "unit": "seconds",
+                 "startValue": 0, "endValue": 2,
+                 "events": [{"type": "O", "frame": 0, "at": 0},
+                            {"type": "C", "frame": 0, "at": 2}]},
+                {"type": "sampled", "name": "worker", "unit": "seconds",
+                 "startValue": 0, "endValue": 3,
+                 "samples": [[0, 1], [0]], "weights": [1, 2]}
+            ]
+        }"#;
+
+        let profile = parse_speedscope(json.as_bytes())
+            .unwrap()
+            .into_visual_profile();
+        assert_eq!(profile.meta.value_unit, ValueUnit::Milliseconds);
+        let threads: Vec<(&str, usize)> = profile
+            .threads
+            .iter()
+            .map(|t| (&*t.name, t.spans.len()))
+            .collect();
+        assert_eq!(threads, [("worker", 1), ("worker #2", 3)]);
+        assert_eq!(profile.threads[1].spans[2].end, 3000.0);
+
+        let hint = crate::parsers::FormatHint {
+            split_profiles: true,
+            ..Default::default()
+        };
+        let split = crate::parsers::parse_with_hint(json.as_bytes(), &hint).unwrap();
+        assert_eq!(split.len(), 2);
+        assert_eq!(split[1].metadata.name.as_deref(), Some("worker"));
+        assert!(
+            split[1]
+                .frames
+                .iter()
+                .all(|f| f.thread.as_deref() == Some("Main"))
+        );
+    }
+
     #[test]
     fn empty_profiles_errors() {
         let json = r#"{"shared":{"frames":[]},"profiles":[]}"#;
@@ -0,0 +1,422 @@
+use std::collections::{BTreeMap, HashMap};
+
+use flame_cat_protocol::{
+    AsyncSpan, CounterSample, CounterTrack, CounterUnit, InstantEvent, MarkerScope, SharedStr,
+};
+use thiserror::Error;
+
+use crate::model::{Frame, Profile, ProfileMetadata};
+
+#[derive(Debug, Error)]
+pub enum SystraceParseError {
+    #[error("invalid UTF-8: {0}")]
+    Utf8(#[from] std::str::Utf8Error),
+    #[error("not a systrace or atrace capture")]
+    NotSystrace,
+    #[error("no atrace events found")]
+    Empty,
+}
+
+/// Opening tag of the `<script>` blocks systrace embeds trace data in.
+const TRACE_DATA: &str = "class=\"trace-data\"";
+
+/// Returns `true` if `text` is systrace HTML or raw atrace/ftrace text with
+/// `tracing_mark_write` events.
+pub fn is_systrace(text: &str) -> bool {
+    if is_html(text) {
+        return text.contains(TRACE_DATA) && text.contains("tracing_mark_write: ");
+    }
+    let mut head = text.len().min(64 * 1024);
+    while !text.is_char_boundary(head) {
+        head -= 1;
+    }
+    let head = &text[..head];
+    head.trim_start().starts_with("# tracer:")
+        || head.lines().any(|line| parse_line(line).is_some())
+}
+
+fn is_html(text: &str) -> bool {
+    let start = text.trim_start().as_bytes();
+    [&b"<!doctype html"[..], b"<html"].iter().any(|tag| {
+        start
+            .get(..tag.len())
+            .is_some_and(|s| s.eq_ignore_ascii_case(tag))
+    })
+}
+
+/// One `tracing_mark_write` line of an ftrace buffer.
+#[derive(Debug, PartialEq)]
+struct Line<'a> {
+    /// Thread name, as the kernel records it (at most 15 bytes).
+    task: &'a str,
+    tid: u64,
+    /// Process id, when the buffer was captured with `print-tgid`.
+    tgid: Option<u64>,
+    /// Microseconds since boot.
+    ts: f64,
+    event: Event<'a>,
+}
+
+/// The atrace payload written to `trace_marker`.
+#[derive(Debug, PartialEq)]
+enum Event<'a> {
+    /// `B|pid|name`: a slice begins on the writing thread.
+    Begin { pid: u64, name: &'a str },
+    /// `E` or `E|pid`: the innermost open slice of the thread ends.
+    End,
+    /// `S|pid|name|cookie`: an async slice begins.
+    AsyncBegin {
+        pid: u64,
+        name: &'a str,
+        cookie: &'a str,
+    },
+    /// `F|pid|name|cookie`: the async slice with that name and cookie ends.
+    AsyncEnd {
+        pid: u64,
+        name: &'a str,
+        cookie: &'a str,
+    },
+    /// `C|pid|name|value`: a counter changes.
+    Counter { pid: u64, name: &'a str, value: f64 },
+    /// `I|pid|name`: an instant on the writing thread.
+    Instant { pid: u64, name: &'a str },
+}
+
+/// Parse a line like
+/// `RenderThread-1234 ( 1200) [001] ...1  1234.567890: tracing_mark_write: B|1200|DrawFrame`,
+/// where the `( tgid)` and flags columns are optional. Other ftrace events
+/// and malformed markers yield `None`.
+fn parse_line(line: &str) -> Option<Line<'_>> {
+    let (head, rest) = line.split_once(": tracing_mark_write: ")?;
+    // The CPU column is the last `[nnn]` before the timestamp.
+    let cpu = head.rmatch_indices('[').find(|&(i, _)| {
+        head[i + 1..]
+            .split_once(']')
+            .is_some_and(|(cpu, _)| !cpu.is_empty() && cpu.bytes().all(|b| b.is_ascii_digit()))
+    })?;
+    let (task, columns) = head.split_at(cpu.0);
+    let ts = micros(columns.split_whitespace().last()?)?;
+
+    let mut task = task.trim_end();
+    let mut tgid = None;
+    if let Some(open) = task.strip_suffix(')').and_then(|t| t.rfind('(')) {
+        tgid = task[open + 1..task.len() - 1].trim().parse().ok();
+        task = task[..open].trim_end();
+    }
+    let (task, tid) = task.trim_start().rsplit_once('-')?;
+    let tid = tid.parse().ok()?;
+
+    Some(Line {
+        task,
+        tid,
+        tgid,
+        ts,
+        event: parse_event(rest.trim_end())?,
+    })
+}
+
+/// Microseconds in an ftrace `seconds.fraction` timestamp, read digit by
+/// digit so that equal intervals stay equal.
+pub(super) fn micros(ts: &str) -> Option<f64> {
+    let (secs, fraction) = ts.split_once('.').unwrap_or((ts, ""));
+    if fraction.len() > 9 || !fraction.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let nanos: u64 = format!("{fraction:0<9}").parse().ok()?;
+    let secs: u64 = secs.parse().ok()?;
+    Some((secs * 1_000_000_000 + nanos) as f64 / 1_000.0)
+}
+
+fn parse_event(payload: &str) -> Option<Event<'_>> {
+    let mut fields = payload.splitn(3, '|');
+    let kind = fields.next()?;
+    if kind == "E" {
+        return Some(Event::End);
+    }
+    let pid = fields.next()?.trim().parse().ok()?;
+    let rest = fields.next()?;
+    Some(match kind {
+        "B" => Event::Begin { pid, name: rest },
+        "I" => Event::Instant { pid, name: rest },
+        "S" | "F" => {
+            let (name, cookie) = rest.rsplit_once('|')?;
+            if kind == "S" {
+                Event::AsyncBegin { pid, name, cookie }
+            } else {
+                Event::AsyncEnd { pid, name, cookie }
+            }
+        }
+        "C" => {
+            let (name, value) = rest.rsplit_once('|')?;
+            Event::Counter {
+                pid,
+                name,
+                value: value.trim().parse().ok()?,
+            }
+        }
+        _ => return None,
+    })
+}
+
+/// The ftrace text of a capture: the `trace-data` script blocks of
+/// systrace HTML, or the whole input otherwise.
+fn trace_text(text: &str) -> Result<String, SystraceParseError> {
+    if !is_html(text) {
+        return Ok(text.to_string());
+    }
+    let mut blocks = String::new();
+    let mut rest = text;
+    while let Some(tag) = rest.find(TRACE_DATA) {
+        rest = &rest[tag..];
+        let Some(body) = rest.find('>') else { break };
+        rest = &rest[body + 1..];
+        let end = rest.find("</script>").unwrap_or(rest.len());
+        blocks.push_str(&rest[..end]);
+        blocks.push('\n');
+        rest = &rest[end..];
+    }
+    if blocks.is_empty() {
+        return Err(SystraceParseError::NotSystrace);
+    }
+    Ok(blocks)
+}
+
+/// Parse Android systrace HTML or raw atrace text into a `Profile`.
+///
+/// Both carry an ftrace buffer in which apps and the framework write atrace
+/// markers through `tracing_mark_write`: `B|pid|name` and `E` bracket
+/// slices on the writing thread, `S`/`F` with a cookie bracket async slices,
+/// `C|pid|name|value` sets a counter and `I|pid|name` marks an instant.
+/// Slices become frames per thread; other ftrace events are skipped.
+pub fn parse_systrace(data: &[u8]) -> Result<Profile, SystraceParseError> {
+    let text = trace_text(std::str::from_utf8(data)?)?;
+    let lines: Vec<Line<'_>> = text.lines().filter_map(parse_line).collect();
+    let Some(origin) = lines.iter().map(|l| l.ts).reduce(f64::min) else {
+        return Err(SystraceParseError::Empty);
+    };
+    let end = lines.iter().map(|l| l.ts - origin).fold(0.0, f64::max);
+
+    let mut frames: Vec<Frame> = Vec::new();
+    // Indices into `frames` of the slices open on each thread.
+    let mut stacks: HashMap<u64, Vec<usize>> = HashMap::new();
+    let mut async_open: HashMap<(u64, &str, &str), (f64, u64)> = HashMap::new();
+    let mut async_spans = Vec::new();
+    let mut counters: BTreeMap<(u64, &str), Vec<CounterSample>> = BTreeMap::new();
+    let mut instant_events = Vec::new();
+    let mut process_names = BTreeMap::new();
+
+    for line in &lines {
+        let ts = line.ts - origin;
+        let pid = match line.event {
+            Event::Begin { pid, .. }
+            | Event::AsyncBegin { pid, .. }
+            | Event::AsyncEnd { pid, .. }
+            | Event::Counter { pid, .. }
+            | Event::Instant { pid, .. } => Some(pid),
+            Event::End => None,
+        }
+        .or(line.tgid);
+        if let Some(pid) = pid
+            && pid == line.tid
+        {
+            process_names.insert(pid, line.task.to_string());
+        }
+        match line.event {
+            Event::Begin { pid, name } => {
+                let stack = stacks.entry(line.tid).or_default();
+                let parent = stack.last().map(|&open| frames[open].id);
+                stack.push(frames.len());
+                frames.push(Frame {
+                    id: frames.len() as u64,
+                    name: name.to_string(),
+                    start: ts,
+                    end,
+                    depth: (stack.len() - 1) as u32,
+                    category: None,
+                    parent,
+                    self_time: 0.0,
+                    thread: Some(line.task.to_string()),
+                    truncated: true,
+                    pid: Some(pid),
+                    tid: Some(line.tid),
+                    source: None,
+                    binary: None,
+                    inlined: vec![],
+                });
+            }
+            Event::End => {
+                if let Some(open) = stacks.get_mut(&line.tid).and_then(Vec::pop) {
+                    let frame = &mut frames[open];
+                    frame.end = ts;
+                    frame.truncated = false;
+                }
+            }
+            Event::AsyncBegin { pid, name, cookie } => {
+                async_open.insert((pid, name, cookie), (ts, line.tid));
+            }
+            Event::AsyncEnd { pid, name, cookie } => {
+                if let Some((start, tid)) = async_open.remove(&(pid, name, cookie)) {
+                    async_spans.push(async_span(pid, tid, name, cookie, start, ts));
+                }
+            }
+            Event::Counter { pid, name, value } => {
+                counters
+                    .entry((pid, name))
+                    .or_default()
+                    .push(CounterSample { ts, value });
+            }
+            Event::Instant { pid, name } => instant_events.push(InstantEvent {
+                ts,
+                name: name.into(),
+                cat: None,
+                scope: MarkerScope::Thread,
+                pid,
+                tid: line.tid,
+            }),
+        }
+    }
+    // Async slices still open when the capture stopped end with it.
+    let mut unfinished: Vec<_> = async_open.into_iter().collect();
+    unfinished.sort_by(|a, b| a.1.0.total_cmp(&b.1.0));
+    for ((pid, name, cookie), (start, tid)) in unfinished {
+        async_spans.push(async_span(pid, tid, name, cookie, start, end));
+    }
+
+    if frames.is_empty() && async_spans.is_empty() && counters.is_empty() {
+        return Err(SystraceParseError::Empty);
+    }
+    for frame in &mut frames {
+        frame.self_time = frame.duration();
+    }
+
+    let mut profile = Profile::new(
+        ProfileMetadata {
+            name: None,
+            start_time: 0.0,
+            end_time: end,
+            format: "systrace".to_string(),
+            time_domain: None,
+            value_unit: None,
+            sample_interval_us: None,
+        },
+        frames,
+    );
+    profile.async_spans = async_spans;
+    profile.counters = counters
+        .into_iter()
+        .map(|((_, name), samples)| CounterTrack {
+            name: name.into(),
+            unit: CounterUnit::Count,
+            samples,
+        })
+        .collect();
+    profile.instant_events = instant_events;
+    profile.process_names = process_names;
+    Ok(profile)
+}
+
+fn async_span(pid: u64, tid: u64, name: &str, cookie: &str, start: f64, end: f64) -> AsyncSpan {
+    AsyncSpan {
+        id: SharedStr::from(format!("{pid}:{cookie}")),
+        name: name.into(),
+        cat: None,
+        start,
+        end,
+        pid,
+        tid,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ATRACE: &str = "\
+# tracer: nop
+#
+#           TASK-PID    TGID   CPU#  ||||    TIMESTAMP  FUNCTION
+#              | |        |      |   ||||       |         |
+   .example.app-1200  ( 1200) [001] ...1   100.000000: tracing_mark_write: B|1200|Choreographer#doFrame
+   .example.app-1200  ( 1200) [001] ...1   100.002000: tracing_mark_write: B|1200|traversal
+   .example.app-1200  ( 1200) [001] ...1   100.006000: tracing_mark_write: E|1200
+   .example.app-1200  ( 1200) [001] ...1   100.008000: tracing_mark_write: E|1200
+ RenderThread-1234  ( 1200) [002] ...1   100.007000: tracing_mark_write: B|1200|DrawFrame
+ RenderThread-1234  ( 1200) [002] ...1   100.007500: sched_switch: prev_comm=RenderThread
+ Binder:1200_2-1250  ( 1200) [003] ...1   100.001000: tracing_mark_write: S|1200|launching: app|7
+ Binder:1200_2-1250  ( 1200) [003] ...1   100.009000: tracing_mark_write: F|1200|launching: app|7
+ surfaceflinger-600   [000] d..2   100.003000: tracing_mark_write: C|600|VSYNC-app|1
+ surfaceflinger-600   [000] d..2   100.010000: tracing_mark_write: C|600|VSYNC-app|0
+";
+
+    #[test]
+    fn parses_atrace_lines() {
+        assert_eq!(
+            parse_line(
+                " Binder:1200_2-1250  ( 1200) [003] ...1   100.001000: tracing_mark_write: S|1200|launching: app|7"
+            ),
+            Some(Line {
+                task: "Binder:1200_2",
+                tid: 1250,
+                tgid: Some(1200),
+                ts: 100_001_000.0,
+                event: Event::AsyncBegin {
+                    pid: 1200,
+                    name: "launching: app",
+                    cookie: "7"
+                },
+            })
+        );
+        let old = parse_line("  surfaceflinger-600   [000] 100.5: tracing_mark_write: E").unwrap();
+        assert_eq!((old.task, old.tid, old.tgid), ("surfaceflinger", 600, None));
+        assert_eq!(parse_line("  app-1 [000] 1.0: sched_wakeup: comm=x"), None);
+    }
+
+    #[test]
+    fn slices_async_slices_and_counters() {
+        assert!(is_systrace(ATRACE));
+        let profile = parse_systrace(ATRACE.as_bytes()).unwrap();
+        assert_eq!(profile.metadata.format, "systrace");
+
+        let slices: Vec<_> = profile
+            .frames
+            .iter()
+            .map(|f| (f.name.as_str(), f.depth, f.start, f.end, f.truncated))
+            .collect();
+        assert_eq!(
+            slices,
+            [
+                ("Choreographer#doFrame", 0, 0.0, 8000.0, false),
+                ("traversal", 1, 2000.0, 6000.0, false),
+                // Never ended: lasts until the capture stops.
+                ("DrawFrame", 0, 7000.0, 10000.0, true),
+            ]
+        );
+        assert_eq!(profile.frames[1].parent, Some(0));
+        assert_eq!(profile.frames[2].thread.as_deref(), Some("RenderThread"));
+        assert_eq!(profile.process_names[&1200], ".example.app");
+
+        let launch = &profile.async_spans[0];
+        assert_eq!(
+            (&*launch.name, launch.start, launch.end),
+            ("launching: app", 1000.0, 9000.0)
+        );
+        assert_eq!(profile.counters.len(), 1);
+        assert_eq!(profile.counters[0].samples.len(), 2);
+    }
+
+    #[test]
+    fn reads_systrace_html() {
+        let html = format!(
+            "<!DOCTYPE html>\n<html><head></head><body>\n\
+             <script class=\"trace-data\" type=\"application/text\">\n{ATRACE}</script>\n\
+             </body></html>"
+        );
+        assert!(is_systrace(&html));
+        let profile = parse_systrace(html.as_bytes()).unwrap();
+        assert_eq!(profile.frames.len(), 3);
+        assert!(matches!(
+            parse_systrace(b"<html><body></body></html>"),
+            Err(SystraceParseError::NotSystrace)
+        ));
+    }
+}
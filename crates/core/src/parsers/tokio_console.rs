@@ -0,0 +1,399 @@
+use std::collections::HashMap;
+
+use flame_cat_protocol::{AsyncSpan, FlowArrow, SharedStr};
+use serde::Deserialize;
+use thiserror::Error;
+
+use crate::model::{Frame, Profile, ProfileMetadata};
+
+#[derive(Debug, Error)]
+pub enum TokioConsoleParseError {
+    #[error("invalid UTF-8: {0}")]
+    Utf8(#[from] std::str::Utf8Error),
+    #[error("line {line}: {source}")]
+    Json {
+        line: usize,
+        source: serde_json::Error,
+    },
+    #[error("no tasks found")]
+    NoTasks,
+}
+
+/// One line of a console-subscriber recording.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum Line {
+    Header {
+        #[serde(rename = "v")]
+        _version: u32,
+    },
+    Event(Event),
+}
+
+#[derive(Debug, Deserialize)]
+enum Event {
+    Spawn {
+        id: u64,
+        at: Timestamp,
+        #[serde(default)]
+        fields: serde_json::Map<String, serde_json::Value>,
+    },
+    Enter {
+        id: u64,
+        at: Timestamp,
+    },
+    Exit {
+        id: u64,
+        at: Timestamp,
+    },
+    Close {
+        id: u64,
+        at: Timestamp,
+    },
+    Waker {
+        id: u64,
+        op: serde_json::Value,
+        at: Timestamp,
+    },
+}
+
+/// Serialized `SystemTime`.
+#[derive(Debug, Clone, Copy, Deserialize)]
+struct Timestamp {
+    secs_since_epoch: u64,
+    nanos_since_epoch: u32,
+}
+
+impl Timestamp {
+    fn as_us(self) -> f64 {
+        self.secs_since_epoch as f64 * 1_000_000.0 + f64::from(self.nanos_since_epoch) / 1_000.0
+    }
+}
+
+/// Returns `true` if `text` looks like a console-subscriber recording.
+pub fn is_tokio_console(text: &str) -> bool {
+    let Some(first) = text.lines().find(|l| !l.trim().is_empty()) else {
+        return false;
+    };
+    let first = first.trim_start();
+    first.starts_with("{\"v\":") || first.starts_with("{\"Spawn\":")
+}
+
+struct Task {
+    name: String,
+    spawned: f64,
+    closed: Option<f64>,
+    /// Start of the poll in progress, if the task is entered.
+    entered: Option<f64>,
+    /// Nested enters of the same task only count once.
+    enter_depth: u32,
+}
+
+struct Poll {
+    task: u64,
+    start: f64,
+    end: f64,
+    lane: u64,
+}
+
+/// Parse a tokio console-subscriber recording (`recording_path` output) into
+/// a `Profile`.
+///
+/// The recording is newline-delimited JSON: a `{"v":1}` header followed by
+/// `Spawn`, `Enter`, `Exit`, `Close` and `Waker` events. Tasks become async
+/// spans from spawn to close. Polls (enter → exit) become spans on "Polls n"
+/// lanes, packed so that concurrent polls get separate lanes, since the
+/// recording does not say which worker thread ran them. Wakes become flow
+/// arrows from the wake to the woken task's next poll.
+pub fn parse_tokio_console(data: &[u8]) -> Result<Profile, TokioConsoleParseError> {
+    let text = std::str::from_utf8(data)?;
+
+    let mut tasks: HashMap<u64, Task> = HashMap::new();
+    let mut polls: Vec<Poll> = Vec::new();
+    let mut wakes: Vec<(u64, f64)> = Vec::new();
+    let mut origin = f64::INFINITY;
+    let mut last = f64::NEG_INFINITY;
+
+    for (i, line) in text.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let event = match serde_json::from_str::<Line>(line) {
+            Ok(Line::Header { .. }) => continue,
+            Ok(Line::Event(event)) => event,
+            Err(source) => {
+                return Err(TokioConsoleParseError::Json {
+                    line: i + 1,
+                    source,
+                });
+            }
+        };
+        let at = match &event {
+            Event::Spawn { at, .. }
+            | Event::Enter { at, .. }
+            | Event::Exit { at, .. }
+            | Event::Close { at, .. }
+            | Event::Waker { at, .. } => at.as_us(),
+        };
+        origin = origin.min(at);
+        last = last.max(at);
+
+        match event {
+            Event::Spawn { id, fields, .. } => {
+                tasks.insert(
+                    id,
+                    Task {
+                        name: task_name(id, &fields),
+                        spawned: at,
+                        closed: None,
+                        entered: None,
+                        enter_depth: 0,
+                    },
+                );
+            }
+            Event::Enter { id, .. } => {
+                if let Some(task) = tasks.get_mut(&id) {
+                    task.enter_depth += 1;
+                    task.entered.get_or_insert(at);
+                }
+            }
+            Event::Exit { id, .. } => {
+                if let Some(task) = tasks.get_mut(&id)
+                    && task.enter_depth > 0
+                {
+                    task.enter_depth -= 1;
+                    if task.enter_depth == 0
+                        && let Some(start) = task.entered.take()
+                    {
+                        polls.push(Poll {
+                            task: id,
+                            start,
+                            end: at,
+                            lane: 0,
+                        });
+                    }
+                }
+            }
+            Event::Close { id, .. } => {
+                if let Some(task) = tasks.get_mut(&id) {
+                    task.closed = Some(at);
+                }
+            }
+            Event::Waker { id, op, .. } => {
+                // Clones and drops of a waker are not wake-ups.
+                let op = op
+                    .as_str()
+                    .map(str::to_owned)
+                    .or_else(|| op.as_object()?.keys().next().cloned());
+                if matches!(op.as_deref(), Some("Wake" | "WakeByRef")) {
+                    wakes.push((id, at));
+                }
+            }
+        }
+    }
+
+    if tasks.is_empty() {
+        return Err(TokioConsoleParseError::NoTasks);
+    }
+
+    // Polls still running when the recording stopped end with it. Task
+    // order decides lanes on equal starts, so go by id.
+    let mut task_ids: Vec<u64> = tasks.keys().copied().collect();
+    task_ids.sort_unstable();
+    for &id in &task_ids {
+        if let Some(start) = tasks[&id].entered {
+            polls.push(Poll {
+                task: id,
+                start,
+                end: last,
+                lane: 0,
+            });
+        }
+    }
+
+    // Greedy interval packing: each poll takes the first lane free at its start.
+    polls.sort_by(|a, b| a.start.total_cmp(&b.start));
+    let mut lane_ends: Vec<f64> = Vec::new();
+    for poll in &mut polls {
+        let lane = match lane_ends.iter().position(|&end| end <= poll.start) {
+            Some(lane) => lane,
+            None => {
+                lane_ends.push(0.0);
+                lane_ends.len() - 1
+            }
+        };
+        lane_ends[lane] = poll.end;
+        poll.lane = lane as u64;
+    }
+
+    let frames: Vec<Frame> = polls
+        .iter()
+        .enumerate()
+        .map(|(i, poll)| Frame {
+            id: i as u64,
+            name: tasks[&poll.task].name.clone(),
+            start: poll.start - origin,
+            end: poll.end - origin,
+            depth: 0,
+            category: Some("poll".to_string()),
+            parent: None,
+            self_time: poll.end - poll.start,
+            thread: Some(format!("Polls {}", poll.lane + 1)),
+            truncated: false,
+            pid: Some(0),
+            tid: Some(poll.lane),
+            source: None,
+            binary: None,
+            inlined: vec![],
+        })
+        .collect();
+
+    let async_spans = task_ids
+        .iter()
+        .map(|id| {
+            let task = &tasks[id];
+            AsyncSpan {
+                id: SharedStr::from(id.to_string()),
+                name: SharedStr::from(task.name.as_str()),
+                cat: Some("task".into()),
+                start: task.spawned - origin,
+                end: task.closed.unwrap_or(last) - origin,
+                pid: 0,
+                tid: 0,
+            }
+        })
+        .collect();
+
+    let flow_arrows = wakes
+        .iter()
+        .filter_map(|&(id, at)| {
+            let next = polls.iter().find(|p| p.task == id && p.start >= at)?;
+            // The waker is whichever poll was running at the time, if any.
+            let from_lane = polls
+                .iter()
+                .find(|p| p.task != id && p.start <= at && at <= p.end)
+                .map_or(next.lane, |p| p.lane);
+            Some(FlowArrow {
+                name: "wake".into(),
+                id: SharedStr::from(id.to_string()),
+                from_ts: at - origin,
+                from_pid: 0,
+                from_tid: from_lane,
+                to_ts: next.start - origin,
+                to_pid: 0,
+                to_tid: next.lane,
+            })
+        })
+        .collect();
+
+    let mut profile = Profile::new(
+        ProfileMetadata {
+            name: None,
+            start_time: 0.0,
+            end_time: last - origin,
+            format: "tokio-console".to_string(),
+            time_domain: None,
+            value_unit: None,
+            sample_interval_us: None,
+        },
+        frames,
+    );
+    profile.async_spans = async_spans;
+    profile.flow_arrows = flow_arrows;
+    Ok(profile)
+}
+
+/// Display name from the task's spawn fields: `task.name`, else its kind and
+/// spawn location, else its id.
+fn task_name(id: u64, fields: &serde_json::Map<String, serde_json::Value>) -> String {
+    let field = |key: &str| {
+        let value = fields.get(key)?;
+        // Field values may be bare or wrapped in a one-key type tag ({"Str": ...}).
+        let value = match value.as_object() {
+            Some(tagged) if tagged.len() == 1 => tagged.values().next()?,
+            _ => value,
+        };
+        match value {
+            serde_json::Value::String(s) => Some(s.clone()),
+            serde_json::Value::Null => None,
+            other => Some(other.to_string()),
+        }
+    };
+    if let Some(name) = field("task.name") {
+        return name;
+    }
+    match (field("kind"), field("spawn.location")) {
+        (Some(kind), Some(location)) => format!("{kind} task @ {location}"),
+        (None, Some(location)) => format!("task @ {location}"),
+        _ => format!("task {id}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ts(us: u64) -> String {
+        format!(
+            r#"{{"secs_since_epoch":{},"nanos_since_epoch":{}}}"#,
+            1_700_000_000 + us / 1_000_000,
+            (us % 1_000_000) * 1_000
+        )
+    }
+
+    fn recording() -> String {
+        [
+            r#"{"v":1}"#.to_string(),
+            format!(
+                r#"{{"Spawn":{{"id":1,"at":{},"fields":{{"task.name":{{"Str":"server"}}}}}}}}"#,
+                ts(0)
+            ),
+            format!(
+                r#"{{"Spawn":{{"id":2,"at":{},"fields":{{"spawn.location":"src/main.rs:10:5"}}}}}}"#,
+                ts(5)
+            ),
+            format!(r#"{{"Enter":{{"id":1,"at":{}}}}}"#, ts(10)),
+            format!(r#"{{"Enter":{{"id":2,"at":{}}}}}"#, ts(20)),
+            format!(
+                r#"{{"Waker":{{"id":2,"op":{{"WakeByRef":{{"self_wake":false}}}},"at":{}}}}}"#,
+                ts(30)
+            ),
+            format!(r#"{{"Exit":{{"id":1,"at":{}}}}}"#, ts(40)),
+            format!(r#"{{"Exit":{{"id":2,"at":{}}}}}"#, ts(50)),
+            format!(r#"{{"Enter":{{"id":2,"at":{}}}}}"#, ts(60)),
+            format!(r#"{{"Exit":{{"id":2,"at":{}}}}}"#, ts(70)),
+            format!(r#"{{"Close":{{"id":2,"at":{}}}}}"#, ts(80)),
+        ]
+        .join("\n")
+    }
+
+    #[test]
+    fn tasks_polls_and_wakes() {
+        let data = recording();
+        assert!(is_tokio_console(&data));
+        let profile = parse_tokio_console(data.as_bytes()).unwrap();
+
+        assert_eq!(profile.async_spans.len(), 2);
+        assert_eq!(profile.async_spans[0].name, "server");
+        assert_eq!(profile.async_spans[1].name, "task @ src/main.rs:10:5");
+        assert_eq!(profile.async_spans[1].end, 80.0);
+        // Task 1 never closed: it lasts until the end of the recording.
+        assert_eq!(profile.async_spans[0].end, 80.0);
+
+        // The first two polls overlap, so they land on separate lanes.
+        assert_eq!(profile.frames.len(), 3);
+        let lanes: Vec<_> = profile.frames.iter().map(|f| f.tid).collect();
+        assert_eq!(lanes, vec![Some(0), Some(1), Some(0)]);
+
+        assert_eq!(profile.flow_arrows.len(), 1);
+        let wake = &profile.flow_arrows[0];
+        assert_eq!((wake.from_ts, wake.to_ts), (30.0, 60.0));
+        assert_eq!(wake.from_tid, 0);
+    }
+
+    #[test]
+    fn bad_line_reports_position() {
+        let err = parse_tokio_console(b"{\"v\":1}\n{\"Spawn\":").unwrap_err();
+        assert!(matches!(err, TokioConsoleParseError::Json { line: 2, .. }));
+    }
+}
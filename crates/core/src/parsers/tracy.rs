@@ -36,6 +36,9 @@ struct TracyInfo {
 struct TracyThread {
     #[serde(default)]
     name: Option<String>,
+    /// A GPU context rather than a CPU thread.
+    #[serde(default)]
+    gpu: bool,
     #[serde(default)]
     zones: Vec<TracyZone>,
 }
@@ -56,8 +59,12 @@ pub fn parse_tracy(data: &[u8]) -> Result<Profile, TracyParseError> {
 
     let mut frames: Vec<Frame> = Vec::new();
     let mut next_id: u64 = 0;
+    let mut gpu_threads = std::collections::BTreeSet::new();
 
     for thread in &export.threads {
+        if let (true, Some(name)) = (thread.gpu, &thread.name) {
+            gpu_threads.insert(name.clone());
+        }
         for zone in &thread.zones {
             flatten_zone(
                 zone,
@@ -74,15 +81,13 @@ pub fn parse_tracy(data: &[u8]) -> Result<Profile, TracyParseError> {
         return Err(TracyParseError::NoZones);
     }
 
-    compute_self_times(&mut frames);
-
     let start_time = frames.iter().map(|f| f.start).fold(f64::INFINITY, f64::min);
     let end_time = frames
         .iter()
         .map(|f| f.end)
         .fold(f64::NEG_INFINITY, f64::max);
 
-    Ok(Profile::new(
+    let mut profile = Profile::new(
         ProfileMetadata {
             name: export.info.and_then(|i| i.app_name),
             start_time: if start_time.is_finite() {
@@ -93,9 +98,13 @@ pub fn parse_tracy(data: &[u8]) -> Result<Profile, TracyParseError> {
             end_time: if end_time.is_finite() { end_time } else { 0.0 },
             format: "tracy".to_string(),
             time_domain: None,
+            value_unit: None,
+            sample_interval_us: None,
         },
         frames,
-    ))
+    );
+    profile.gpu_threads = gpu_threads;
+    Ok(profile)
 }
 
 fn flatten_zone(
@@ -118,7 +127,13 @@ fn flatten_zone(
         category: thread_name.map(ToString::to_string),
         parent: parent_id,
         self_time: 0.0,
-        thread: None,
+        thread: thread_name.map(ToString::to_string),
+        truncated: false,
+        pid: None,
+        tid: None,
+        source: None,
+        binary: None,
+        inlined: vec![],
     });
 
     for child in &zone.children {
@@ -126,22 +141,6 @@ fn flatten_zone(
     }
 }
 
-fn compute_self_times(frames: &mut [Frame]) {
-    let child_time = {
-        let mut map = std::collections::HashMap::<u64, f64>::new();
-        for f in frames.iter() {
-            if let Some(pid) = f.parent {
-                *map.entry(pid).or_default() += f.duration();
-            }
-        }
-        map
-    };
-    for f in frames.iter_mut() {
-        let children_total = child_time.get(&f.id).copied().unwrap_or(0.0);
-        f.self_time = (f.duration() - children_total).max(0.0);
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -179,6 +178,24 @@ mod tests {
         assert_eq!(draw.depth, 2);
     }
 
+    #[test]
+    fn gpu_contexts_become_gpu_tracks() {
+        let json = r#"{"threads":[
+            {"name": "Main", "zones": [{"name": "Submit", "start": 0, "end": 10}]},
+            {"name": "Vulkan context", "gpu": true, "zones": [{"name": "Shadows", "start": 12, "end": 30}]}
+        ]}"#;
+        let profile = parse_tracy(json.as_bytes()).unwrap().into_visual_profile();
+        let kinds: Vec<(&str, flame_cat_protocol::TrackKind)> =
+            profile.threads.iter().map(|t| (&*t.name, t.kind)).collect();
+        assert_eq!(
+            kinds,
+            [
+                ("Main", flame_cat_protocol::TrackKind::Cpu),
+                ("Vulkan context", flame_cat_protocol::TrackKind::Gpu),
+            ]
+        );
+    }
+
     #[test]
     fn empty_zones_errors() {
         let json = r#"{"threads":[{"name":"t","zones":[]}]}"#;
@@ -0,0 +1,437 @@
+//! Windows Performance Analyzer (WPA) table exports of ETW traces.
+//!
+//! WPA exports a table as CSV: a header row of column names, then one row
+//! per event. Two tables are read, alone or in one file:
+//!
+//! - **CPU Usage (Sampled)** rows carry `Process` (`name.exe (pid)`),
+//!   `Thread ID`, `TimeStamp` in seconds and `Stack`, the call chain as
+//!   `module!function` frames root first, separated by `/`. An optional
+//!   `Weight` in ms gives the sampling interval.
+//! - **Generic Events** rows carry `Provider Name`, `Task Name` or
+//!   `Event Name`, and `Time` or `TimeStamp` in seconds.
+//!
+//! Column names match case-insensitively, ignoring a unit in parentheses
+//! (`TimeStamp (s)`), and columns may come in any order.
+
+use std::collections::{BTreeMap, HashMap};
+
+use flame_cat_protocol::{Binary, Marker, MarkerScope};
+use thiserror::Error;
+
+use super::ebpf::category;
+use super::perf::{Sample, stitch_samples};
+use crate::model::{Frame, Profile, ProfileMetadata};
+
+#[derive(Debug, Error)]
+pub enum WpaParseError {
+    #[error("invalid UTF-8: {0}")]
+    Utf8(#[from] std::str::Utf8Error),
+    #[error("not a WPA table export")]
+    NotWpa,
+    #[error("line {line}: {message}")]
+    Malformed { line: usize, message: String },
+    #[error("no samples or events found")]
+    Empty,
+}
+
+/// Root pseudo-frame WPA starts every stack with.
+const ROOT_FRAME: &str = "[Root]";
+
+/// Whether `text` starts with the header of a sampled CPU or generic
+/// events table export.
+pub fn is_wpa_csv(text: &str) -> bool {
+    let Some(header) = text.trim_start_matches('\u{feff}').lines().next() else {
+        return false;
+    };
+    let columns = Columns::new(&split_record(header));
+    columns.time.is_some() && (columns.stack.is_some() || columns.provider.is_some())
+}
+
+/// Parse a WPA CSV export into a `Profile`.
+///
+/// Sampled stacks become frames on a lane per thread, stitched like other
+/// sampled formats, with each frame's module recorded as its binary and
+/// frames in `ntoskrnl.exe`, `hal.dll` or a driver (`.sys`) given the
+/// [`SpanCategory::KERNEL`] category. Generic events become markers in
+/// their provider's category. Times are µs from the start of the trace.
+///
+/// [`SpanCategory::KERNEL`]: flame_cat_protocol::SpanCategory::KERNEL
+pub fn parse_wpa(data: &[u8]) -> Result<Profile, WpaParseError> {
+    let text = std::str::from_utf8(data)?.trim_start_matches('\u{feff}');
+    let mut records = records(text);
+    let Some((_, header)) = records.next() else {
+        return Err(WpaParseError::NotWpa);
+    };
+    let columns = Columns::new(&header);
+    let Some(time) = columns.time else {
+        return Err(WpaParseError::NotWpa);
+    };
+    if columns.stack.is_none() && columns.provider.is_none() {
+        return Err(WpaParseError::NotWpa);
+    }
+
+    let mut trace = Trace::default();
+    for (line, record) in records {
+        let field = |i: Option<usize>| {
+            i.and_then(|i| record.get(i))
+                .map(|s| s.trim())
+                .filter(|s| !s.is_empty())
+        };
+        let Some(ts) = field(Some(time)) else {
+            continue;
+        };
+        let ts = seconds(ts).ok_or_else(|| WpaParseError::Malformed {
+            line,
+            message: format!("bad timestamp {ts:?}"),
+        })? * 1e6;
+
+        if let Some(stack) = field(columns.stack) {
+            let (process, pid) = field(columns.process).map_or(("", 0), process_and_pid);
+            let tid = field(columns.thread)
+                .and_then(|t| t.parse().ok())
+                .ok_or_else(|| WpaParseError::Malformed {
+                    line,
+                    message: "sample without a thread id".to_string(),
+                })?;
+            if !process.is_empty() {
+                trace.process_names.insert(pid, process.to_string());
+            }
+            if trace.interval_us.is_none() {
+                trace.interval_us = field(columns.weight)
+                    .and_then(seconds)
+                    .map(|ms| ms * 1e3)
+                    .filter(|us| *us > 0.0);
+            }
+            let stack = trace.stack(stack);
+            trace
+                .threads
+                .entry((pid, tid))
+                .or_default()
+                .push(Sample { ts, stack });
+        } else if let Some(provider) = field(columns.provider) {
+            let name = field(columns.task)
+                .or(field(columns.event))
+                .unwrap_or(provider);
+            trace.markers.push(Marker {
+                ts,
+                name: name.into(),
+                scope: MarkerScope::Global,
+                category: Some(provider.into()),
+            });
+        }
+    }
+    trace.finish()
+}
+
+/// Positions of the columns read, by header.
+#[derive(Default)]
+struct Columns {
+    time: Option<usize>,
+    process: Option<usize>,
+    thread: Option<usize>,
+    stack: Option<usize>,
+    weight: Option<usize>,
+    provider: Option<usize>,
+    task: Option<usize>,
+    event: Option<usize>,
+}
+
+impl Columns {
+    fn new(header: &[String]) -> Self {
+        let mut columns = Self::default();
+        for (i, name) in header.iter().enumerate() {
+            let name = name.split('(').next().unwrap_or("").trim().to_lowercase();
+            let slot = match name.as_str() {
+                "timestamp" | "time" => &mut columns.time,
+                "process" | "process name" => &mut columns.process,
+                "thread id" | "tid" => &mut columns.thread,
+                "stack" => &mut columns.stack,
+                "weight" | "weight in view" => &mut columns.weight,
+                "provider name" | "provider" => &mut columns.provider,
+                "task name" => &mut columns.task,
+                "event name" => &mut columns.event,
+                _ => continue,
+            };
+            slot.get_or_insert(i);
+        }
+        columns
+    }
+}
+
+#[derive(Default)]
+struct Trace {
+    /// Frame names by location id.
+    locations: Vec<String>,
+    location_index: HashMap<String, u32>,
+    binaries: Vec<Binary>,
+    binary_index: HashMap<String, u32>,
+    /// Binary of each location, if the frame names a module.
+    location_binaries: Vec<Option<u32>>,
+    threads: BTreeMap<(u64, u64), Vec<Sample>>,
+    process_names: BTreeMap<u64, String>,
+    interval_us: Option<f64>,
+    markers: Vec<Marker>,
+}
+
+impl Trace {
+    /// Location ids of a `/`-separated stack, root first.
+    fn stack(&mut self, stack: &str) -> Vec<u32> {
+        stack
+            .split('/')
+            .map(str::trim)
+            .filter(|f| !f.is_empty() && *f != ROOT_FRAME)
+            .map(|frame| self.location(frame))
+            .collect()
+    }
+
+    fn location(&mut self, frame: &str) -> u32 {
+        if let Some(&id) = self.location_index.get(frame) {
+            return id;
+        }
+        let binary = frame.split_once('!').map(|(module, _)| {
+            *self
+                .binary_index
+                .entry(module.to_string())
+                .or_insert_with(|| {
+                    self.binaries.push(Binary {
+                        name: module.into(),
+                        build_id: None,
+                    });
+                    self.binaries.len() as u32 - 1
+                })
+        });
+        let id = self.locations.len() as u32;
+        self.locations.push(frame.to_string());
+        self.location_binaries.push(binary);
+        self.location_index.insert(frame.to_string(), id);
+        id
+    }
+
+    fn finish(self) -> Result<Profile, WpaParseError> {
+        if self.threads.is_empty() && self.markers.is_empty() {
+            return Err(WpaParseError::Empty);
+        }
+        let mut frames: Vec<Frame> = Vec::new();
+        for ((pid, tid), mut samples) in self.threads {
+            let thread = format!("Thread {tid}");
+            stitch_samples(&mut frames, &mut samples, self.interval_us, |location| {
+                let binary = self.location_binaries[location as usize];
+                let kernel = binary.is_some_and(|b| is_kernel(&self.binaries[b as usize].name));
+                Frame {
+                    name: self.locations[location as usize].clone(),
+                    category: category(kernel),
+                    thread: Some(thread.clone()),
+                    pid: Some(pid),
+                    tid: Some(tid),
+                    binary,
+                    ..Frame::default()
+                }
+            });
+        }
+
+        let start_time = frames
+            .iter()
+            .map(|f| f.start)
+            .chain(self.markers.iter().map(|m| m.ts))
+            .fold(f64::INFINITY, f64::min);
+        let end_time = frames
+            .iter()
+            .map(|f| f.end)
+            .chain(self.markers.iter().map(|m| m.ts))
+            .fold(f64::NEG_INFINITY, f64::max);
+        let mut profile = Profile::new(
+            ProfileMetadata {
+                name: None,
+                start_time,
+                end_time,
+                format: "wpa".to_string(),
+                time_domain: None,
+                value_unit: None,
+                sample_interval_us: self.interval_us,
+            },
+            frames,
+        );
+        profile.markers = self.markers;
+        profile.binaries = self.binaries;
+        profile.process_names = self.process_names;
+        Ok(profile)
+    }
+}
+
+/// Whether a module runs in the Windows kernel.
+fn is_kernel(module: &str) -> bool {
+    let module = module.to_ascii_lowercase();
+    module == "ntoskrnl.exe" || module == "hal.dll" || module.ends_with(".sys")
+}
+
+/// Image name and pid of a WPA process cell, `name.exe (1234)`.
+fn process_and_pid(cell: &str) -> (&str, u64) {
+    cell.strip_suffix(')')
+        .and_then(|rest| rest.rsplit_once(" ("))
+        .and_then(|(name, pid)| Some((name.trim(), pid.trim().parse().ok()?)))
+        .unwrap_or((cell, 0))
+}
+
+/// A number as WPA writes it: digits grouped by commas when there is a
+/// decimal point, else a comma as the decimal mark.
+fn seconds(cell: &str) -> Option<f64> {
+    let number = if cell.contains('.') {
+        cell.replace([',', '\u{a0}', ' '], "")
+    } else {
+        cell.replace(',', ".")
+    };
+    number.parse().ok().filter(|v: &f64| v.is_finite())
+}
+
+/// CSV records of `text` with the line each starts on; quoted fields may
+/// hold commas, doubled quotes and line breaks.
+fn records(text: &str) -> impl Iterator<Item = (usize, Vec<String>)> + '_ {
+    let mut rest = text;
+    let mut line = 1;
+    std::iter::from_fn(move || {
+        while rest.starts_with(['\r', '\n']) {
+            if rest.starts_with('\n') {
+                line += 1;
+            }
+            rest = &rest[1..];
+        }
+        if rest.is_empty() {
+            return None;
+        }
+        let start = line;
+        let mut fields = Vec::new();
+        let mut field = String::new();
+        let mut quoted = false;
+        let mut chars = rest.char_indices().peekable();
+        let mut end = rest.len();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '"' if quoted && chars.peek().is_some_and(|&(_, n)| n == '"') => {
+                    chars.next();
+                    field.push('"');
+                }
+                '"' => quoted = !quoted,
+                ',' if !quoted => fields.push(std::mem::take(&mut field)),
+                '\n' if !quoted => {
+                    end = i;
+                    break;
+                }
+                '\r' if !quoted => {}
+                _ => {
+                    if c == '\n' {
+                        line += 1;
+                    }
+                    field.push(c);
+                }
+            }
+        }
+        fields.push(field);
+        rest = &rest[end..];
+        Some((start, fields))
+    })
+}
+
+/// Fields of a single-line CSV record.
+fn split_record(line: &str) -> Vec<String> {
+    records(line).next().map(|(_, r)| r).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use flame_cat_protocol::SpanCategory;
+
+    use super::*;
+
+    const SAMPLED: &str = "\u{feff}Line,Process,Thread ID,TimeStamp (s),Stack,Weight (ms)
+1,app.exe (4321),17,1.000000,[Root]/ntdll.dll!RtlUserThreadStart/app.exe!main/app.exe!work,250.000000
+2,app.exe (4321),17,1.250000,[Root]/ntdll.dll!RtlUserThreadStart/app.exe!main/ntoskrnl.exe!KiPageFault,250.000000
+3,app.exe (4321),17,1.500000,[Root]/ntdll.dll!RtlUserThreadStart/app.exe!main,250.000000
+4,app.exe (4321),23,\"1,001.500000\",\"[Root]/ntdll.dll!RtlUserThreadStart/app.exe!\"\"io\"\"\",250.000000
+";
+
+    #[test]
+    fn parses_sampled_cpu_usage() {
+        let text = SAMPLED.replace("\"1,001.500000\"", "1.125");
+        assert!(is_wpa_csv(&text));
+        let profile = parse_wpa(text.as_bytes()).unwrap();
+        assert_eq!(profile.metadata.format, "wpa");
+        assert_eq!(profile.metadata.sample_interval_us, Some(250_000.0));
+        assert_eq!(profile.process_names[&4321], "app.exe");
+
+        let lane: Vec<_> = profile
+            .frames
+            .iter()
+            .filter(|f| f.tid == Some(17))
+            .map(|f| (f.name.as_str(), f.depth, f.start, f.end))
+            .collect();
+        assert_eq!(
+            lane,
+            [
+                ("ntdll.dll!RtlUserThreadStart", 0, 1e6, 1_750_000.0),
+                ("app.exe!main", 1, 1e6, 1_750_000.0),
+                ("app.exe!work", 2, 1e6, 1_250_000.0),
+                ("ntoskrnl.exe!KiPageFault", 2, 1_250_000.0, 1_500_000.0),
+            ]
+        );
+        let fault = profile
+            .frames
+            .iter()
+            .find(|f| f.name.starts_with("ntoskrnl"))
+            .unwrap();
+        assert_eq!(fault.category.as_deref(), Some(SpanCategory::KERNEL));
+        assert_eq!(
+            profile.binaries[fault.binary.unwrap() as usize]
+                .name
+                .as_ref(),
+            "ntoskrnl.exe"
+        );
+        let io = profile
+            .frames
+            .iter()
+            .find(|f| f.tid == Some(23) && f.depth == 1);
+        assert_eq!(io.unwrap().name, "app.exe!\"io\"");
+    }
+
+    #[test]
+    fn parses_quoted_numbers_and_generic_events() {
+        let profile = parse_wpa(SAMPLED.as_bytes()).unwrap();
+        let io = profile.frames.iter().find(|f| f.tid == Some(23)).unwrap();
+        assert_eq!(io.start, 1_001_500_000.0);
+
+        let events = "Provider Name,Task Name,Event Name,Time (s)
+Microsoft-Windows-Kernel-Process,ProcessStart,,0.5
+MyApp,,FrameDone,\"0,75\"
+";
+        assert!(is_wpa_csv(events));
+        let profile = parse_wpa(events.as_bytes()).unwrap();
+        let markers: Vec<_> = profile
+            .markers
+            .iter()
+            .map(|m| (m.name.as_ref(), m.ts, m.category.as_deref()))
+            .collect();
+        assert_eq!(
+            markers,
+            [
+                (
+                    "ProcessStart",
+                    500_000.0,
+                    Some("Microsoft-Windows-Kernel-Process")
+                ),
+                ("FrameDone", 750_000.0, Some("MyApp")),
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_other_csv() {
+        assert!(!is_wpa_csv("name,value\na,1\n"));
+        assert!(matches!(
+            parse_wpa(b"name,value\na,1\n"),
+            Err(WpaParseError::NotWpa)
+        ));
+        assert!(matches!(
+            parse_wpa(b"Process,Thread ID,TimeStamp,Stack\napp.exe (1),x,1.0,a!b\n"),
+            Err(WpaParseError::Malformed { line: 2, .. })
+        ));
+    }
+}
@@ -0,0 +1,200 @@
+//! Loading profiles over HTTP.
+//!
+//! A [`RemoteRequest`] describes what to fetch: the URL plus headers such as
+//! `Authorization`. The transport is platform specific. Native builds with
+//! the `http` feature fetch with a blocking client through
+//! [`parse_from_url`]; the wasm viewer uses the browser's `fetch` and hands
+//! the body to [`decode_body`]. Either way, gzip bodies are decompressed
+//! before parsing — whether the server sent `Content-Encoding: gzip` or the
+//! artifact itself is a `.json.gz` — within the parse memory budget.
+
+use thiserror::Error;
+
+use crate::parsers::Limits;
+use crate::parsers::limits::{DecompressError, decompress_within};
+
+#[derive(Debug, Error)]
+pub enum RemoteError {
+    #[error("request to {url} failed: {message}")]
+    Transport { url: String, message: String },
+    #[error("HTTP {status} from {url}")]
+    Status { status: u16, url: String },
+    #[error("unsupported content encoding {0:?}")]
+    UnsupportedEncoding(String),
+    #[error("decompression: {0}")]
+    Decompress(#[from] DecompressError),
+}
+
+/// A profile to fetch over HTTP.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RemoteRequest {
+    pub url: String,
+    /// Extra request headers, sent in order.
+    pub headers: Vec<(String, String)>,
+}
+
+impl RemoteRequest {
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            headers: Vec::new(),
+        }
+    }
+
+    /// Add a request header.
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Add an `Authorization: Bearer …` header.
+    pub fn bearer_token(self, token: &str) -> Self {
+        self.header("Authorization", format!("Bearer {token}"))
+    }
+}
+
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// Undo any compression on a fetched body.
+///
+/// `content_encoding` is the response's `Content-Encoding` header. Clients
+/// that already decoded the body (browsers always do) may still report it,
+/// so gzip and deflate are only decompressed when the bytes actually are
+/// compressed. Gzip is also recognised by its magic bytes alone, which
+/// covers `.gz` artifacts served as opaque binaries. Bodies that inflate
+/// past the memory budget of `limits` are refused.
+pub fn decode_body(
+    body: Vec<u8>,
+    content_encoding: Option<&str>,
+    limits: &Limits,
+) -> Result<Vec<u8>, RemoteError> {
+    let encoding = content_encoding
+        .map(|e| e.trim().to_ascii_lowercase())
+        .unwrap_or_default();
+    match encoding.as_str() {
+        "" | "identity" | "gzip" | "x-gzip" => {
+            if body.starts_with(&GZIP_MAGIC) {
+                let gz = flate2::read::MultiGzDecoder::new(body.as_slice());
+                Ok(decompress_within(gz, limits)?)
+            } else {
+                Ok(body)
+            }
+        }
+        // zlib streams start with 0x78 for the default window size.
+        "deflate" if body.first() == Some(&0x78) => {
+            let zlib = flate2::read::ZlibDecoder::new(body.as_slice());
+            Ok(decompress_within(zlib, limits)?)
+        }
+        "deflate" => Ok(body),
+        other => Err(RemoteError::UnsupportedEncoding(other.to_string())),
+    }
+}
+
+/// Fetch `request` and return the body, decoded within `limits`.
+#[cfg(all(feature = "http", not(target_arch = "wasm32")))]
+pub fn fetch(request: &RemoteRequest, limits: &Limits) -> Result<Vec<u8>, RemoteError> {
+    use std::io::Read;
+
+    let transport = |message: String| RemoteError::Transport {
+        url: request.url.clone(),
+        message,
+    };
+    let mut call = ureq::get(&request.url).set("Accept-Encoding", "gzip, deflate");
+    for (name, value) in &request.headers {
+        call = call.set(name, value);
+    }
+    let response = match call.call() {
+        Ok(response) => response,
+        Err(ureq::Error::Status(status, _)) => {
+            return Err(RemoteError::Status {
+                status,
+                url: request.url.clone(),
+            });
+        }
+        Err(e) => return Err(transport(e.to_string())),
+    };
+    let encoding = response.header("Content-Encoding").map(str::to_owned);
+    let mut body = Vec::new();
+    response
+        .into_reader()
+        .read_to_end(&mut body)
+        .map_err(|e| transport(e.to_string()))?;
+    decode_body(body, encoding.as_deref(), limits)
+}
+
+/// Fetch `request` and parse the body within `limits`, auto-detecting the
+/// format. Remote bodies are untrusted input like any dropped file.
+#[cfg(all(feature = "http", not(target_arch = "wasm32")))]
+pub fn parse_from_url(
+    request: &RemoteRequest,
+    limits: &Limits,
+) -> Result<flame_cat_protocol::VisualProfile, crate::parsers::ParseError> {
+    let body = fetch(request, limits)?;
+    crate::parsers::parse_auto_with_limits(&body, limits)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use super::*;
+
+    const TRACE: &[u8] =
+        br#"{"traceEvents":[{"name":"main","ph":"X","ts":0,"dur":10,"pid":1,"tid":1}]}"#;
+
+    #[test]
+    fn gzip_is_detected_with_or_without_header() {
+        let mut gz = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::fast());
+        gz.write_all(TRACE).unwrap();
+        let gz = gz.finish().unwrap();
+
+        assert_eq!(decode_body(gz.clone(), None, &Limits::NONE).unwrap(), TRACE);
+        assert_eq!(decode_body(gz, Some("gzip"), &Limits::NONE).unwrap(), TRACE);
+        // Already decoded by the client, header still present.
+        assert_eq!(
+            decode_body(TRACE.to_vec(), Some("gzip"), &Limits::NONE).unwrap(),
+            TRACE
+        );
+    }
+
+    #[test]
+    fn deflate_and_unknown_encodings() {
+        let mut z = flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::fast());
+        z.write_all(TRACE).unwrap();
+        assert_eq!(
+            decode_body(z.finish().unwrap(), Some("deflate"), &Limits::NONE).unwrap(),
+            TRACE
+        );
+        assert!(matches!(
+            decode_body(TRACE.to_vec(), Some("br"), &Limits::NONE),
+            Err(RemoteError::UnsupportedEncoding(_))
+        ));
+    }
+
+    #[test]
+    fn gzip_bombs_stop_at_the_budget() {
+        let mut gz = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::best());
+        gz.write_all(&vec![0; 1 << 20]).unwrap();
+        let bomb = gz.finish().unwrap();
+        assert!(bomb.len() < 4096);
+        let limits = Limits {
+            max_memory: 64 * 1024,
+            ..Limits::default()
+        };
+        assert!(matches!(
+            decode_body(bomb, None, &limits),
+            Err(RemoteError::Decompress(DecompressError::TooLarge {
+                limit: 8192
+            }))
+        ));
+    }
+
+    #[test]
+    fn request_headers() {
+        let req = RemoteRequest::new("https://ci/trace.json.gz").bearer_token("abc");
+        assert_eq!(
+            req.headers,
+            vec![("Authorization".to_string(), "Bearer abc".to_string())]
+        );
+    }
+}
@@ -0,0 +1,390 @@
+//! Applying JavaScript source maps to profiles of minified code.
+//!
+//! V8 CPU profiles and Chrome traces record each JS frame's script URL and
+//! the position of its function in the generated code. Parsers keep that as
+//! a `url:line:column` source location (on a span's category, or on a
+//! [`CpuNode`](flame_cat_protocol::CpuNode)). Given the source map of one
+//! script, [`SourceMap::apply`] rewrites matching frames to their original
+//! function names and source positions.
+
+use flame_cat_protocol::{SharedStr, VisualProfile};
+use serde::Deserialize;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum SourceMapError {
+    #[error("invalid JSON: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("unsupported source map version {0}")]
+    UnsupportedVersion(u32),
+    #[error("indexed source maps (with `sections`) are not supported")]
+    Indexed,
+    #[error("invalid mappings on generated line {line}")]
+    InvalidMappings { line: usize },
+}
+
+#[derive(Debug, Deserialize)]
+struct RawSourceMap {
+    version: u32,
+    #[serde(default)]
+    file: Option<String>,
+    #[serde(default, rename = "sourceRoot")]
+    source_root: Option<String>,
+    #[serde(default)]
+    sources: Vec<Option<String>>,
+    #[serde(default)]
+    names: Vec<String>,
+    #[serde(default)]
+    mappings: String,
+    #[serde(default)]
+    sections: Option<serde_json::Value>,
+}
+
+/// One mapping segment: a generated column and, optionally, where it came from.
+#[derive(Debug, Clone, Copy)]
+struct Segment {
+    column: u32,
+    original: Option<Original>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Original {
+    source: u32,
+    line: u32,
+    column: u32,
+    name: Option<u32>,
+}
+
+/// A position in the original sources. Lines and columns are 0-based.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OriginalPosition<'a> {
+    pub source: &'a str,
+    pub line: u32,
+    pub column: u32,
+    pub name: Option<&'a str>,
+}
+
+/// A decoded version 3 source map.
+#[derive(Debug, Clone)]
+pub struct SourceMap {
+    file: Option<String>,
+    sources: Vec<String>,
+    names: Vec<String>,
+    /// Segments per generated line, sorted by column.
+    lines: Vec<Vec<Segment>>,
+}
+
+fn base64_value(b: u8) -> Option<i64> {
+    Some(match b {
+        b'A'..=b'Z' => b - b'A',
+        b'a'..=b'z' => b - b'a' + 26,
+        b'0'..=b'9' => b - b'0' + 52,
+        b'+' => 62,
+        b'/' => 63,
+        _ => return None,
+    } as i64)
+}
+
+/// Decode the base64 VLQ fields of one segment.
+fn decode_vlq(segment: &str) -> Option<Vec<i64>> {
+    let mut fields = Vec::with_capacity(5);
+    let mut value = 0i64;
+    let mut shift = 0;
+    for b in segment.bytes() {
+        let digit = base64_value(b)?;
+        if shift > 60 {
+            return None;
+        }
+        value |= (digit & 31) << shift;
+        if digit & 32 != 0 {
+            shift += 5;
+            continue;
+        }
+        let negative = value & 1 != 0;
+        value >>= 1;
+        fields.push(if negative { -value } else { value });
+        value = 0;
+        shift = 0;
+    }
+    // A trailing continuation digit leaves a field unfinished.
+    (shift == 0).then_some(fields)
+}
+
+impl SourceMap {
+    pub fn parse(data: &[u8]) -> Result<Self, SourceMapError> {
+        let raw: RawSourceMap = serde_json::from_slice(data)?;
+        if raw.sections.is_some() {
+            return Err(SourceMapError::Indexed);
+        }
+        if raw.version != 3 {
+            return Err(SourceMapError::UnsupportedVersion(raw.version));
+        }
+
+        let root = raw
+            .source_root
+            .filter(|r| !r.is_empty())
+            .map(|r| r.trim_end_matches('/').to_string());
+        let sources = raw
+            .sources
+            .into_iter()
+            .map(|s| {
+                let s = s.unwrap_or_default();
+                match &root {
+                    Some(root) => format!("{root}/{s}"),
+                    None => s,
+                }
+            })
+            .collect();
+
+        // Source, original line/column and name are deltas across the whole
+        // map; the generated column resets on every line.
+        let (mut source, mut line, mut column, mut name) = (0i64, 0i64, 0i64, 0i64);
+        let mut lines = Vec::new();
+        for (i, encoded) in raw.mappings.split(';').enumerate() {
+            let invalid = || SourceMapError::InvalidMappings { line: i + 1 };
+            let mut segments = Vec::new();
+            let mut generated = 0i64;
+            for segment in encoded.split(',').filter(|s| !s.is_empty()) {
+                let fields = decode_vlq(segment).ok_or_else(invalid)?;
+                generated += fields[0];
+                let original = match fields.len() {
+                    1 => None,
+                    4 | 5 => {
+                        source += fields[1];
+                        line += fields[2];
+                        column += fields[3];
+                        let has_name = fields.len() == 5;
+                        if has_name {
+                            name += fields[4];
+                        }
+                        Some(Original {
+                            source: u32::try_from(source).map_err(|_| invalid())?,
+                            line: u32::try_from(line).map_err(|_| invalid())?,
+                            column: u32::try_from(column).map_err(|_| invalid())?,
+                            name: if has_name {
+                                Some(u32::try_from(name).map_err(|_| invalid())?)
+                            } else {
+                                None
+                            },
+                        })
+                    }
+                    _ => return Err(invalid()),
+                };
+                segments.push(Segment {
+                    column: u32::try_from(generated).map_err(|_| invalid())?,
+                    original,
+                });
+            }
+            segments.sort_by_key(|s| s.column);
+            lines.push(segments);
+        }
+
+        Ok(Self {
+            file: raw.file,
+            sources,
+            names: raw.names,
+            lines,
+        })
+    }
+
+    /// The generated file this map describes, if the map names it.
+    pub fn file(&self) -> Option<&str> {
+        self.file.as_deref()
+    }
+
+    fn original(&self, segment: &Segment) -> Option<OriginalPosition<'_>> {
+        let original = segment.original?;
+        Some(OriginalPosition {
+            source: self.sources.get(original.source as usize)?,
+            line: original.line,
+            column: original.column,
+            name: original
+                .name
+                .and_then(|n| self.names.get(n as usize))
+                .map(String::as_str),
+        })
+    }
+
+    /// Original position of a generated (0-based) line and column: the
+    /// closest mapping at or before it on the same line.
+    pub fn lookup(&self, line: u32, column: u32) -> Option<OriginalPosition<'_>> {
+        let segments = self.lines.get(line as usize)?;
+        let idx = segments.partition_point(|s| s.column <= column);
+        self.original(&segments[idx.checked_sub(1)?])
+    }
+
+    /// Original name of the function starting at a generated position: the
+    /// name on the mapping covering it or, failing that, on the mapping just
+    /// before it (for `function name(` that is the name).
+    fn function_name(&self, line: u32, column: u32) -> Option<&str> {
+        let segments = self.lines.get(line as usize)?;
+        let idx = segments.partition_point(|s| s.column <= column);
+        segments[..idx]
+            .iter()
+            .rev()
+            .take(2)
+            .find_map(|s| self.original(s)?.name)
+    }
+
+    /// Resolve a `url:line:column` source location (1-based) in
+    /// `script_url`. Returns the original function name, if known, and the
+    /// original location.
+    fn resolve(&self, script_url: &str, location: &str) -> Option<(Option<&str>, String)> {
+        let (url, line, column) = split_location(location)?;
+        if !script_matches(url, script_url) {
+            return None;
+        }
+        let (line, column) = (line.checked_sub(1)?, column.checked_sub(1)?);
+        let original = self.lookup(line, column)?;
+        Some((
+            self.function_name(line, column),
+            format!(
+                "{}:{}:{}",
+                original.source,
+                original.line + 1,
+                original.column + 1
+            ),
+        ))
+    }
+
+    /// Rewrite frames of `script_url` in `profile` — span names and source
+    /// locations, and CPU sample nodes — to their original names and
+    /// positions. Returns how many frames were rewritten.
+    ///
+    /// `script_url` matches a frame's URL exactly, or by trailing path
+    /// segments (`bundle.js` matches `https://cdn/app/bundle.js?v=3`).
+    pub fn apply(&self, script_url: &str, profile: &mut VisualProfile) -> usize {
+        let mut rewritten = 0;
+        for span in profile.threads.iter_mut().flat_map(|t| &mut t.spans) {
+            let Some(category) = &mut span.category else {
+                continue;
+            };
+            let Some((name, source)) = category
+                .source
+                .as_deref()
+                .and_then(|location| self.resolve(script_url, location))
+            else {
+                continue;
+            };
+            if let Some(name) = name {
+                span.name = SharedStr::from(name);
+            }
+            category.source = Some(SharedStr::from(source));
+            rewritten += 1;
+        }
+        if let Some(samples) = &mut profile.cpu_samples {
+            for node in &mut samples.nodes {
+                let Some((name, source)) = node
+                    .source
+                    .as_deref()
+                    .and_then(|location| self.resolve(script_url, location))
+                else {
+                    continue;
+                };
+                if let Some(name) = name {
+                    node.function_name = SharedStr::from(name);
+                }
+                node.source = Some(SharedStr::from(source));
+                rewritten += 1;
+            }
+        }
+        rewritten
+    }
+}
+
+/// Format a V8 call frame position (0-based line and column) as a 1-based
+/// `url:line:column` source location. `None` for frames without a script
+/// URL or position, such as `(program)` and native functions.
+pub(crate) fn source_location(url: &str, line: i64, column: i64) -> Option<String> {
+    (!url.is_empty() && line >= 0 && column >= 0)
+        .then(|| format!("{url}:{}:{}", line + 1, column + 1))
+}
+
+fn split_location(location: &str) -> Option<(&str, u32, u32)> {
+    let (rest, column) = location.rsplit_once(':')?;
+    let (url, line) = rest.rsplit_once(':')?;
+    Some((url, line.parse().ok()?, column.parse().ok()?))
+}
+
+fn script_matches(url: &str, script_url: &str) -> bool {
+    let url = url.split(['?', '#']).next().unwrap_or(url);
+    let script_url = script_url.split(['?', '#']).next().unwrap_or(script_url);
+    url == script_url
+        || url
+            .strip_suffix(script_url)
+            .is_some_and(|prefix| prefix.ends_with('/'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // bundle.js: `function a(b){return b}` on line 1, generated from
+    // src/util.ts where the function is `identity` on line 3, column 0.
+    // Segments: col 0 -> util.ts 2:0; col 9 -> 2:9 named "identity";
+    // col 10 -> 2:18.
+    const MAP: &str = r#"{
+        "version": 3,
+        "file": "bundle.js",
+        "sourceRoot": "webpack://app/",
+        "sources": ["src/util.ts"],
+        "names": ["identity"],
+        "mappings": "AAEA,SAASA,CAAS"
+    }"#;
+
+    #[test]
+    fn vlq_fields() {
+        assert_eq!(decode_vlq("AAEA"), Some(vec![0, 0, 2, 0]));
+        assert_eq!(decode_vlq("2HDgB"), Some(vec![123, -1, 16]));
+        assert_eq!(decode_vlq("g"), None);
+    }
+
+    #[test]
+    fn lookup_and_function_names() {
+        let map = SourceMap::parse(MAP.as_bytes()).unwrap();
+        assert_eq!(map.file(), Some("bundle.js"));
+        let pos = map.lookup(0, 9).unwrap();
+        assert_eq!(pos.source, "webpack://app/src/util.ts");
+        assert_eq!((pos.line, pos.column, pos.name), (2, 9, Some("identity")));
+        // V8 reports the function at its parameter list, just after the name.
+        assert_eq!(map.function_name(0, 10), Some("identity"));
+        assert_eq!(map.function_name(0, 0), None);
+        assert!(map.lookup(1, 0).is_none());
+    }
+
+    #[test]
+    fn rejects_unsupported_maps() {
+        assert!(matches!(
+            SourceMap::parse(br#"{"version":2,"mappings":""}"#),
+            Err(SourceMapError::UnsupportedVersion(2))
+        ));
+        assert!(matches!(
+            SourceMap::parse(br#"{"version":3,"sections":[]}"#),
+            Err(SourceMapError::Indexed)
+        ));
+    }
+
+    #[test]
+    fn applies_to_cpuprofile_frames() {
+        let cpuprofile = br#"{
+            "nodes": [
+                {"id": 1, "callFrame": {"functionName": "(root)", "url": "", "lineNumber": -1, "columnNumber": -1}, "children": [2]},
+                {"id": 2, "callFrame": {"functionName": "a", "url": "https://cdn.example/bundle.js?v=2", "lineNumber": 0, "columnNumber": 10}}
+            ],
+            "startTime": 0, "endTime": 100
+        }"#;
+        let mut profile = crate::parsers::cpuprofile::parse_cpuprofile(cpuprofile)
+            .unwrap()
+            .into_visual_profile();
+        let map = SourceMap::parse(MAP.as_bytes()).unwrap();
+
+        assert_eq!(map.apply("other.js", &mut profile), 0);
+        assert_eq!(map.apply("bundle.js", &mut profile), 1);
+        let span = profile.all_spans().find(|s| s.depth == 1).unwrap();
+        assert_eq!(span.name, "identity");
+        assert_eq!(
+            span.category.as_ref().and_then(|c| c.source.as_deref()),
+            Some("webpack://app/src/util.ts:3:19")
+        );
+    }
+}
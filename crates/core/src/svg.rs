@@ -1,33 +1,131 @@
 //! SVG renderer: converts `RenderCommand` lists into standalone SVG strings.
 
-use flame_cat_protocol::{RenderCommand, ThemeToken};
+use std::collections::HashMap;
+use std::sync::LazyLock;
+
+use flame_cat_protocol::{DurationFormat, RenderCommand, ThemeToken, heat_rgb};
+use serde::Deserialize;
+
+use crate::views::text::{FixedWidth, LABEL_PADDING, fit_label};
+use crate::views::time_axis::nice_interval;
+
+/// Height of the title block above the rendered view.
+const TITLE_HEIGHT: f64 = 28.0;
+/// Height of the scale bar strip below the rendered view.
+const SCALE_BAR_HEIGHT: f64 = 24.0;
+
+/// How an exported image looks, beyond the render commands themselves.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct SvgOptions {
+    /// Dark palette rather than the light one. Hosts set this from their
+    /// theme mode.
+    #[serde(skip)]
+    pub dark: bool,
+    /// CSS colors replacing the theme's for individual tokens.
+    pub palette: HashMap<ThemeToken, String>,
+    /// Leave out the background so the image sits on the page's own.
+    pub transparent: bool,
+    /// Heading drawn in a block above the view.
+    pub title: Option<String>,
+    /// Microseconds spanned by the view's width; draws a scale bar below it.
+    #[serde(skip)]
+    pub visible_us: Option<f64>,
+    /// Format of the scale bar's length.
+    #[serde(skip)]
+    pub duration_format: DurationFormat,
+}
 
 /// Render a list of commands as an SVG document string.
 ///
 /// `width` and `height` define the SVG viewBox dimensions.
 /// `dark` selects the color palette.
 pub fn render_svg(commands: &[RenderCommand], width: f64, height: f64, dark: bool) -> String {
+    render_svg_with(
+        commands,
+        width,
+        height,
+        &SvgOptions {
+            dark,
+            ..SvgOptions::default()
+        },
+    )
+}
+
+/// Render like [`render_svg`], styled by `options`. The title block and
+/// scale bar extend the document beyond `height`.
+pub fn render_svg_with(
+    commands: &[RenderCommand],
+    width: f64,
+    height: f64,
+    options: &SvgOptions,
+) -> String {
+    let palette = Palette::new(options);
+    let top = if options.title.is_some() {
+        TITLE_HEIGHT
+    } else {
+        0.0
+    };
+    let scale_bar = options
+        .visible_us
+        .filter(|us| *us > 0.0 && width > 0.0)
+        .map(|us| scale_bar(us, width));
+    let bottom = if scale_bar.is_some() {
+        SCALE_BAR_HEIGHT
+    } else {
+        0.0
+    };
+    let total_height = top + height + bottom;
+
     let mut svg = String::with_capacity(commands.len() * 200);
     let mut clip_counter = 0_u32;
     let mut clip_depth = 0_u32;
     let mut group_depth = 0_u32;
     svg.push_str(&format!(
-        r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 {width} {height}" width="{width}" height="{height}" style="font-family:system-ui,-apple-system,sans-serif;font-size:11px">"#,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 {width} {total_height}" width="{width}" height="{total_height}" style="font-family:system-ui,-apple-system,sans-serif;font-size:11px">"#,
     ));
 
-    let bg = resolve_color(ThemeToken::Background, dark);
-    svg.push_str(&format!(
-        r#"<rect width="{width}" height="{height}" fill="{bg}"/>"#,
-    ));
+    if !options.transparent {
+        let bg = palette.color(ThemeToken::Background);
+        svg.push_str(&format!(
+            r#"<rect width="{width}" height="{total_height}" fill="{bg}"/>"#,
+        ));
+    }
+
+    if let Some(title) = &options.title {
+        let fill = palette.color(ThemeToken::TextPrimary);
+        let border = palette.color(ThemeToken::Border);
+        svg.push_str(&format!(
+            r#"<text x="{LABEL_PADDING}" y="{}" fill="{fill}" font-size="14" font-weight="600">{}</text><line x1="0" y1="{TITLE_HEIGHT}" x2="{width}" y2="{TITLE_HEIGHT}" stroke="{border}"/>"#,
+            TITLE_HEIGHT * 0.65,
+            escape_xml(title),
+        ));
+    }
+    if top > 0.0 {
+        svg.push_str(&format!(r#"<g transform="translate(0 {top})">"#));
+    }
 
     for cmd in commands {
         match cmd {
             RenderCommand::DrawRect {
-                rect, color, label, ..
+                rect,
+                color,
+                label,
+                dimmed,
+                ..
             } => {
-                let fill = resolve_color(*color, dark);
+                let fill = palette.color(*color);
+                // Same fade as the canvas renderers (alpha 40/255).
+                let opacity = if *dimmed {
+                    r#" fill-opacity="0.16""#
+                } else if *color == ThemeToken::OverlaySpanFill {
+                    // Overlaid runs stay see-through, as on the canvas.
+                    r#" fill-opacity="0.2""#
+                } else {
+                    ""
+                };
                 svg.push_str(&format!(
-                    r#"<rect x="{}" y="{}" width="{}" height="{}" fill="{fill}" rx="1">"#,
+                    r#"<rect x="{}" y="{}" width="{}" height="{}" fill="{fill}"{opacity} rx="1">"#,
                     rect.x, rect.y, rect.w, rect.h,
                 ));
                 if let Some(label) = label {
@@ -35,23 +133,17 @@ pub fn render_svg(commands: &[RenderCommand], width: f64, height: f64, dark: boo
                 }
                 svg.push_str("</rect>");
 
-                // Render text label if rect is wide enough
-                if let Some(label) = label
-                    && rect.w > 30.0
+                if let Some(fitted) = label
+                    .as_deref()
+                    .and_then(|l| fit_label(l, rect.w, rect.h, &FixedWidth::default()))
                 {
-                    let text_color = resolve_color(ThemeToken::TextPrimary, dark);
-                    let tx = rect.x + 3.0;
+                    let text_color = palette.color(ThemeToken::TextPrimary);
+                    let tx = rect.x + LABEL_PADDING;
                     let ty = rect.y + rect.h * 0.75;
-                    let max_chars = (rect.w / 7.0) as usize;
-                    let text = if label.chars().count() > max_chars && max_chars > 2 {
-                        let truncated: String = label.chars().take(max_chars - 1).collect();
-                        format!("{truncated}…")
-                    } else {
-                        label.to_string()
-                    };
                     svg.push_str(&format!(
-                        r#"<text x="{tx}" y="{ty}" fill="{text_color}" style="pointer-events:none">{}</text>"#,
-                        escape_xml(&text),
+                        r#"<text x="{tx}" y="{ty}" fill="{text_color}" font-size="{}" style="pointer-events:none">{}</text>"#,
+                        fitted.font_size,
+                        escape_xml(&fitted.text),
                     ));
                 }
             }
@@ -61,7 +153,7 @@ pub fn render_svg(commands: &[RenderCommand], width: f64, height: f64, dark: boo
                 color,
                 width: line_width,
             } => {
-                let stroke = resolve_color(*color, dark);
+                let stroke = palette.color(*color);
                 svg.push_str(&format!(
                     r#"<line x1="{}" y1="{}" x2="{}" y2="{}" stroke="{stroke}" stroke-width="{line_width}"/>"#,
                     from.x, from.y, to.x, to.y,
@@ -73,7 +165,7 @@ pub fn render_svg(commands: &[RenderCommand], width: f64, height: f64, dark: boo
                 color,
                 ..
             } => {
-                let fill = resolve_color(*color, dark);
+                let fill = palette.color(*color);
                 svg.push_str(&format!(
                     r#"<text x="{}" y="{}" fill="{fill}">{}</text>"#,
                     position.x,
@@ -96,8 +188,14 @@ pub fn render_svg(commands: &[RenderCommand], width: f64, height: f64, dark: boo
                     clip_depth -= 1;
                 }
             }
-            RenderCommand::BeginGroup { .. } => {
-                svg.push_str("<g>");
+            RenderCommand::BeginGroup { label, .. } => {
+                match label {
+                    Some(label) => svg.push_str(&format!(
+                        r#"<g role="group" aria-label="{}">"#,
+                        escape_xml(label)
+                    )),
+                    None => svg.push_str("<g>"),
+                }
                 group_depth += 1;
             }
             RenderCommand::EndGroup => {
@@ -117,13 +215,64 @@ pub fn render_svg(commands: &[RenderCommand], width: f64, height: f64, dark: boo
     for _ in 0..group_depth {
         svg.push_str("</g>");
     }
+    if top > 0.0 {
+        svg.push_str("</g>");
+    }
+
+    if let Some((length_us, length_px)) = scale_bar {
+        let stroke = palette.color(ThemeToken::TextSecondary);
+        let x = LABEL_PADDING;
+        let y = top + height + SCALE_BAR_HEIGHT / 2.0;
+        svg.push_str(&format!(
+            r#"<g role="img" aria-label="scale bar"><path d="M{x} {} V{} H{} V{}" fill="none" stroke="{stroke}" stroke-width="1.5"/><text x="{}" y="{}" fill="{stroke}">{}</text></g>"#,
+            y - 4.0,
+            y + 4.0,
+            x + length_px,
+            y - 4.0,
+            x + length_px + 6.0,
+            y + 4.0,
+            escape_xml(&options.duration_format.format_us(length_us)),
+        ));
+    }
 
     svg.push_str("</svg>");
     svg
 }
 
+/// A round length of time for a scale bar across a view spanning
+/// `visible_us` over `width` pixels, and its length in pixels.
+fn scale_bar(visible_us: f64, width: f64) -> (f64, f64) {
+    let (length_us, _) = nice_interval(visible_us, width);
+    (length_us, length_us / visible_us * width)
+}
+
+/// Theme colors with the caller's overrides, escaped for attributes.
+struct Palette {
+    dark: bool,
+    overrides: HashMap<ThemeToken, String>,
+}
+
+impl Palette {
+    fn new(options: &SvgOptions) -> Self {
+        Self {
+            dark: options.dark,
+            overrides: options
+                .palette
+                .iter()
+                .map(|(token, color)| (*token, escape_xml(color)))
+                .collect(),
+        }
+    }
+
+    fn color(&self, token: ThemeToken) -> &str {
+        self.overrides
+            .get(&token)
+            .map_or_else(|| resolve_color(token, self.dark), String::as_str)
+    }
+}
+
 /// Map ThemeToken to hex color string, matching crates/ui/src/theme.rs exactly.
-fn resolve_color(token: ThemeToken, dark: bool) -> &'static str {
+pub(crate) fn resolve_color(token: ThemeToken, dark: bool) -> &'static str {
     if dark {
         // Catppuccin Mocha palette — must match theme.rs resolve_dark()
         match token {
@@ -162,14 +311,20 @@ fn resolve_color(token: ThemeToken, dark: bool) -> &'static str {
             ThemeToken::CounterFill => "#74c7ec",
             ThemeToken::CounterLine => "#74c7ec",
             ThemeToken::CounterText => "#bac2de",
+            ThemeToken::CounterBudgetExceeded => "#4a2f3c",
+            ThemeToken::CounterBudgetLine => "#f38ba8",
             ThemeToken::MarkerLine => "#f9e2af",
             ThemeToken::MarkerText => "#f9e2af",
             ThemeToken::AsyncSpanFill => "#94e2d5",
             ThemeToken::AsyncSpanBorder => "#74c7ec",
+            ThemeToken::GpuSpanBorder => "#fab387",
+            ThemeToken::KernelSpan => "#cba6f7",
+            ThemeToken::OverlaySpanFill | ThemeToken::OverlaySpanBorder => "#94e2d5",
             ThemeToken::FrameGood => "#a6e3a1",
             ThemeToken::FrameWarning => "#f9e2af",
             ThemeToken::FrameDropped => "#f38ba8",
             ThemeToken::FlowArrow | ThemeToken::FlowArrowHead => "#6c7086",
+            ThemeToken::Heat(level) => heat_hex(level),
         }
     } else {
         // Light palette — must match theme.rs resolve_light()
@@ -209,19 +364,38 @@ fn resolve_color(token: ThemeToken, dark: bool) -> &'static str {
             ThemeToken::CounterFill => "#326edc",
             ThemeToken::CounterLine => "#326edc",
             ThemeToken::CounterText => "#505064",
+            ThemeToken::CounterBudgetExceeded => "#f8dde0",
+            ThemeToken::CounterBudgetLine => "#d2283c",
             ThemeToken::MarkerLine => "#c89614",
             ThemeToken::MarkerText => "#96640a",
             ThemeToken::AsyncSpanFill => "#508cc8",
             ThemeToken::AsyncSpanBorder => "#326eb4",
+            ThemeToken::GpuSpanBorder => "#dc6e1e",
+            ThemeToken::KernelSpan => "#c8aaf0",
+            ThemeToken::OverlaySpanFill | ThemeToken::OverlaySpanBorder => "#179299",
             ThemeToken::FrameGood => "#388e3c",
             ThemeToken::FrameWarning => "#e6aa00",
             ThemeToken::FrameDropped => "#d32f2f",
             ThemeToken::FlowArrow | ThemeToken::FlowArrowHead => "#3278dc",
+            ThemeToken::Heat(level) => heat_hex(level),
         }
     }
 }
 
-fn escape_xml(s: &str) -> String {
+/// Hex color of a [`ThemeToken::Heat`] level.
+fn heat_hex(level: u8) -> &'static str {
+    static HEX: LazyLock<Vec<String>> = LazyLock::new(|| {
+        (0..=255)
+            .map(|level| {
+                let (r, g, b) = heat_rgb(level);
+                format!("#{r:02x}{g:02x}{b:02x}")
+            })
+            .collect()
+    });
+    &HEX[usize::from(level)]
+}
+
+pub(crate) fn escape_xml(s: &str) -> String {
     s.replace('&', "&amp;")
         .replace('<', "&lt;")
         .replace('>', "&gt;")
@@ -242,6 +416,8 @@ mod tests {
             border_color: None,
             label: Some("main".into()),
             frame_id: Some(1),
+            dimmed: false,
+            span_name: None,
         }];
         let svg = render_svg(&commands, 800.0, 400.0, true);
         assert!(svg.starts_with("<svg"));
@@ -258,8 +434,38 @@ mod tests {
             border_color: None,
             label: Some("fn<T>(&self)".into()),
             frame_id: None,
+            dimmed: false,
+            span_name: None,
         }];
         let svg = render_svg(&commands, 400.0, 100.0, false);
         assert!(svg.contains("fn&lt;T&gt;(&amp;self)"));
     }
+
+    #[test]
+    fn options_style_the_export() {
+        let commands = vec![RenderCommand::DrawRect {
+            rect: Rect::new(0.0, 0.0, 200.0, 18.0),
+            color: ThemeToken::FlameHot,
+            border_color: None,
+            label: None,
+            frame_id: None,
+            dimmed: false,
+            span_name: None,
+        }];
+        let mut options: SvgOptions = serde_json::from_str(
+            r##"{"palette":{"FlameHot":"#123456"},"transparent":true,"title":"A & B"}"##,
+        )
+        .unwrap();
+        options.dark = true;
+        options.visible_us = Some(1_000.0);
+        let svg = render_svg_with(&commands, 800.0, 100.0, &options);
+
+        assert!(svg.contains(r##"fill="#123456""##));
+        assert!(!svg.contains("#11111b"), "background left out");
+        assert!(svg.contains("A &amp; B"));
+        assert!(svg.contains(r#"height="152""#));
+        assert!(svg.contains(r#"<g transform="translate(0 28)">"#));
+        assert!(svg.contains("aria-label=\"scale bar\""));
+        assert!(svg.contains(">100µs</text>"));
+    }
 }
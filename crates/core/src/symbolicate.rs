@@ -0,0 +1,536 @@
+//! Resolving address-only frames to function names.
+//!
+//! perf, eBPF and pprof profiles of stripped or JIT-compiled code often
+//! contain frames that are just addresses. Parsers keep those addresses in
+//! the frame name, in one of two forms:
+//!
+//! - `0x7f3a1b2c` (or bare hex, as perf prints it): an absolute address in
+//!   the profiled process, resolved against perf map files
+//!   (`/tmp/perf-<pid>.map`).
+//! - `libfoo.so+0x1a2b`: an address relative to a module's load address
+//!   (from pprof mappings), resolved against that module's symbols — a
+//!   Breakpad `.sym` file or, with the `dwarf` feature, the binary's debug
+//!   info.
+//!
+//! A [`Symbolicator`] collects symbol sources and rewrites matching frame
+//! names in a loaded [`Profile`] or [`VisualProfile`]; frames it cannot
+//! resolve are left unchanged.
+
+use std::collections::HashMap;
+
+use flame_cat_protocol::{SharedStr, VisualProfile};
+use thiserror::Error;
+
+use crate::model::Profile;
+
+#[derive(Debug, Error)]
+pub enum SymbolicateError {
+    #[error("{format} line {line}: {message}")]
+    Syntax {
+        format: &'static str,
+        line: usize,
+        message: String,
+    },
+    #[error("Breakpad symbol file has no MODULE record")]
+    MissingModule,
+    #[error("io: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("reading debug info from {path}: {message}")]
+    DebugInfo { path: String, message: String },
+}
+
+/// A named address range.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Symbol {
+    pub address: u64,
+    /// Length in bytes. `None` means the symbol extends to the next one.
+    pub size: Option<u64>,
+    pub name: String,
+}
+
+/// Symbols of one address space, sorted for lookup.
+#[derive(Debug, Clone, Default)]
+pub struct SymbolTable {
+    symbols: Vec<Symbol>,
+}
+
+impl SymbolTable {
+    pub fn new(mut symbols: Vec<Symbol>) -> Self {
+        symbols.sort_by_key(|s| s.address);
+        Self { symbols }
+    }
+
+    /// The symbol covering `address`, if any.
+    pub fn lookup(&self, address: u64) -> Option<&Symbol> {
+        let idx = self.symbols.partition_point(|s| s.address <= address);
+        let symbol = &self.symbols[idx.checked_sub(1)?];
+        match symbol.size {
+            Some(size) => (address - symbol.address < size).then_some(symbol),
+            None => Some(symbol),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.symbols.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.symbols.is_empty()
+    }
+}
+
+fn parse_hex(s: &str) -> Option<u64> {
+    let digits = s
+        .strip_prefix("0x")
+        .or_else(|| s.strip_prefix("0X"))
+        .unwrap_or(s);
+    u64::from_str_radix(digits, 16).ok()
+}
+
+/// Display name for a perf map symbol. V8 (Node, Deno, Chrome) prefixes JS
+/// functions with a log tag and a tier marker, as in `LazyCompile:*render
+/// app.js:10` or `JS:~render app.js:10`; those are dropped. Other tags,
+/// such as `Builtin:`, say what the code is and are kept.
+fn jit_symbol_name(name: &str) -> &str {
+    let name = name.trim();
+    ["LazyCompile:", "Function:", "JS:"]
+        .iter()
+        .find_map(|tag| name.strip_prefix(tag))
+        .map_or(name, |rest| rest.trim_start_matches(['*', '~', '^', '+']))
+}
+
+/// Parse a perf map file: one `START SIZE name` line per symbol, with
+/// `START` and `SIZE` in hex. JITs (V8, the JVM agent, .NET) write these
+/// to `/tmp/perf-<pid>.map`.
+pub fn parse_perf_map(text: &str) -> Result<SymbolTable, SymbolicateError> {
+    let mut symbols = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let syntax = |message: &str| SymbolicateError::Syntax {
+            format: "perf map",
+            line: i + 1,
+            message: message.to_string(),
+        };
+        let mut parts = line.splitn(3, char::is_whitespace);
+        let (Some(start), Some(size), Some(name)) = (parts.next(), parts.next(), parts.next())
+        else {
+            return Err(syntax("expected `START SIZE name`"));
+        };
+        symbols.push(Symbol {
+            address: parse_hex(start).ok_or_else(|| syntax("invalid start address"))?,
+            size: Some(parse_hex(size).ok_or_else(|| syntax("invalid size"))?),
+            name: jit_symbol_name(name).to_string(),
+        });
+    }
+    Ok(SymbolTable::new(symbols))
+}
+
+/// Symbols from a Breakpad `.sym` file, relative to the module's load address.
+#[derive(Debug, Clone)]
+pub struct BreakpadSymbols {
+    /// Module file name from the `MODULE` record, e.g. `libfoo.so`.
+    pub module: String,
+    pub table: SymbolTable,
+}
+
+/// Parse a Breakpad text symbol file.
+///
+/// Only `MODULE`, `FUNC` and `PUBLIC` records are used; line, inline and
+/// stack records are skipped.
+pub fn parse_breakpad(text: &str) -> Result<BreakpadSymbols, SymbolicateError> {
+    let mut module = None;
+    let mut symbols = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let syntax = |message: &str| SymbolicateError::Syntax {
+            format: "Breakpad",
+            line: i + 1,
+            message: message.to_string(),
+        };
+        let Some((record, rest)) = line.trim_end().split_once(' ') else {
+            continue;
+        };
+        match record {
+            "MODULE" => {
+                // MODULE <os> <arch> <id> <name>
+                let name = rest.splitn(4, ' ').nth(3);
+                module = Some(name.ok_or_else(|| syntax("expected `MODULE os arch id name`"))?);
+            }
+            "FUNC" => {
+                // FUNC [m] <address> <size> <param_size> <name>
+                let rest = rest.strip_prefix("m ").unwrap_or(rest);
+                let mut parts = rest.splitn(4, ' ');
+                let (Some(address), Some(size), Some(_), Some(name)) =
+                    (parts.next(), parts.next(), parts.next(), parts.next())
+                else {
+                    return Err(syntax("expected `FUNC address size param_size name`"));
+                };
+                symbols.push(Symbol {
+                    address: parse_hex(address).ok_or_else(|| syntax("invalid address"))?,
+                    size: Some(parse_hex(size).ok_or_else(|| syntax("invalid size"))?),
+                    name: name.to_string(),
+                });
+            }
+            "PUBLIC" => {
+                // PUBLIC [m] <address> <param_size> <name>
+                let rest = rest.strip_prefix("m ").unwrap_or(rest);
+                let mut parts = rest.splitn(3, ' ');
+                let (Some(address), Some(_), Some(name)) =
+                    (parts.next(), parts.next(), parts.next())
+                else {
+                    return Err(syntax("expected `PUBLIC address param_size name`"));
+                };
+                symbols.push(Symbol {
+                    address: parse_hex(address).ok_or_else(|| syntax("invalid address"))?,
+                    size: None,
+                    name: name.to_string(),
+                });
+            }
+            _ => {}
+        }
+    }
+    Ok(BreakpadSymbols {
+        module: module.ok_or(SymbolicateError::MissingModule)?.to_string(),
+        table: SymbolTable::new(symbols),
+    })
+}
+
+/// An address parsed out of a frame name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FrameAddress<'a> {
+    Absolute(u64),
+    Module { module: &'a str, offset: u64 },
+}
+
+fn file_name(path: &str) -> &str {
+    path.rsplit(['/', '\\']).next().unwrap_or(path)
+}
+
+/// Frame name for an unsymbolized address inside `module` (a path or file
+/// name), in the form [`Symbolicator`] resolves.
+pub(crate) fn module_frame_name(module: &str, offset: u64) -> String {
+    format!("{}+{offset:#x}", file_name(module))
+}
+
+fn parse_frame_address(name: &str) -> Option<FrameAddress<'_>> {
+    let name = name.trim();
+    if let Some(digits) = name.strip_prefix("0x").or_else(|| name.strip_prefix("0X")) {
+        return u64::from_str_radix(digits, 16)
+            .ok()
+            .map(FrameAddress::Absolute);
+    }
+    // perf prints addresses without a prefix; require a full-width-looking
+    // value so short identifiers like `add` or `cafe` are not mistaken for one.
+    if name.len() >= 8 && name.chars().all(|c| c.is_ascii_hexdigit()) {
+        return u64::from_str_radix(name, 16)
+            .ok()
+            .map(FrameAddress::Absolute);
+    }
+    let (module, offset) = name.rsplit_once("+0x")?;
+    if module.is_empty() {
+        return None;
+    }
+    Some(FrameAddress::Module {
+        module: file_name(module),
+        offset: u64::from_str_radix(offset, 16).ok()?,
+    })
+}
+
+enum ModuleSymbols {
+    Table(SymbolTable),
+    #[cfg(all(feature = "dwarf", not(target_arch = "wasm32")))]
+    Dwarf(Box<addr2line::Loader>),
+}
+
+impl ModuleSymbols {
+    fn lookup(&self, offset: u64) -> Option<String> {
+        match self {
+            Self::Table(table) => table.lookup(offset).map(|s| s.name.clone()),
+            #[cfg(all(feature = "dwarf", not(target_arch = "wasm32")))]
+            Self::Dwarf(loader) => {
+                let probe = offset.checked_add(loader.relative_address_base())?;
+                // Name the frame after the physical function, not whatever
+                // was inlined into it at this address.
+                let mut outermost = None;
+                if let Ok(mut frames) = loader.find_frames(probe) {
+                    while let Ok(Some(frame)) = frames.next() {
+                        if let Some(function) = frame.function
+                            && let Ok(name) = function.demangle()
+                        {
+                            outermost = Some(name.into_owned());
+                        }
+                    }
+                }
+                outermost.or_else(|| {
+                    let name = loader.find_symbol(probe)?;
+                    Some(addr2line::demangle_auto(name.into(), None).into_owned())
+                })
+            }
+        }
+    }
+}
+
+/// Collected symbol sources, applied to profiles after they are loaded.
+#[derive(Default)]
+pub struct Symbolicator {
+    /// Tables of absolute addresses (perf maps), searched in order.
+    absolute: Vec<SymbolTable>,
+    /// Per-module symbols keyed by module file name.
+    modules: HashMap<String, ModuleSymbols>,
+}
+
+impl Symbolicator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a perf map file's symbols.
+    pub fn add_perf_map(&mut self, text: &str) -> Result<(), SymbolicateError> {
+        self.absolute.push(parse_perf_map(text)?);
+        Ok(())
+    }
+
+    /// Add a Breakpad symbol file, keyed by its `MODULE` name.
+    pub fn add_breakpad(&mut self, text: &str) -> Result<(), SymbolicateError> {
+        let symbols = parse_breakpad(text)?;
+        self.add_module(&symbols.module, symbols.table);
+        Ok(())
+    }
+
+    /// Add symbols for `module` (a path or file name), with addresses
+    /// relative to its load address.
+    pub fn add_module(&mut self, module: &str, table: SymbolTable) {
+        self.modules
+            .insert(file_name(module).to_string(), ModuleSymbols::Table(table));
+    }
+
+    /// Use the DWARF debug info (or symbol table) of the binary at `path` for
+    /// frames in the module with the same file name.
+    #[cfg(all(feature = "dwarf", not(target_arch = "wasm32")))]
+    pub fn add_debug_file(
+        &mut self,
+        path: impl AsRef<std::path::Path>,
+    ) -> Result<(), SymbolicateError> {
+        let path = path.as_ref();
+        let loader = addr2line::Loader::new(path).map_err(|e| SymbolicateError::DebugInfo {
+            path: path.display().to_string(),
+            message: e.to_string(),
+        })?;
+        let module = path
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        self.modules
+            .insert(module, ModuleSymbols::Dwarf(Box::new(loader)));
+        Ok(())
+    }
+
+    /// Add a symbol file, picking the format from its contents: Breakpad
+    /// files start with a `MODULE` record, anything else textual is read as
+    /// a perf map, and (with the `dwarf` feature) binaries are read for
+    /// debug info.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn add_file(&mut self, path: impl AsRef<std::path::Path>) -> Result<(), SymbolicateError> {
+        let path = path.as_ref();
+        let data = std::fs::read(path)?;
+        match std::str::from_utf8(&data) {
+            Ok(text) if text.starts_with("MODULE ") => self.add_breakpad(text),
+            Ok(text) => self.add_perf_map(text),
+            #[cfg(feature = "dwarf")]
+            Err(_) => self.add_debug_file(path),
+            #[cfg(not(feature = "dwarf"))]
+            Err(_) => Err(SymbolicateError::DebugInfo {
+                path: path.display().to_string(),
+                message: "binary symbol files need the `dwarf` feature".to_string(),
+            }),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.absolute.is_empty() && self.modules.is_empty()
+    }
+
+    /// Resolve an address-only frame name, or `None` if `name` is not an
+    /// address or no source covers it.
+    pub fn resolve(&self, name: &str) -> Option<String> {
+        match parse_frame_address(name)? {
+            FrameAddress::Absolute(address) => self
+                .absolute
+                .iter()
+                .find_map(|table| table.lookup(address))
+                .map(|s| s.name.clone()),
+            FrameAddress::Module { module, offset } => self.modules.get(module)?.lookup(offset),
+        }
+    }
+
+    /// Rename resolvable frames in `profile`. Returns how many were renamed.
+    pub fn apply(&self, profile: &mut Profile) -> usize {
+        let mut resolved: HashMap<String, Option<String>> = HashMap::new();
+        let mut renamed = 0;
+        for frame in &mut profile.frames {
+            let name = resolved
+                .entry(frame.name.clone())
+                .or_insert_with_key(|name| self.resolve(name));
+            if let Some(name) = name {
+                frame.name.clone_from(name);
+                renamed += 1;
+            }
+        }
+        renamed
+    }
+
+    /// Rename resolvable spans in an already converted profile. Returns how
+    /// many were renamed.
+    pub fn apply_visual(&self, profile: &mut VisualProfile) -> usize {
+        let mut resolved: HashMap<SharedStr, Option<SharedStr>> = HashMap::new();
+        let mut renamed = 0;
+        for span in profile.threads.iter_mut().flat_map(|t| &mut t.spans) {
+            let name = resolved
+                .entry(span.name.clone())
+                .or_insert_with_key(|name| self.resolve(name).map(SharedStr::from));
+            if let Some(name) = name {
+                span.name = name.clone();
+                renamed += 1;
+            }
+        }
+        renamed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{Frame, ProfileMetadata};
+
+    const PERF_MAP: &str = "7f0000001000 40 LazyCompile:~render app.js:10\n\
+                            7f0000002000 0x10 Builtin:ArrayMap\n";
+
+    const BREAKPAD: &str = "MODULE Linux x86_64 0123456789ABCDEF libgame.so\n\
+                            FILE 0 src/game.cc\n\
+                            FUNC 1000 20 0 Game::update(float)\n\
+                            1000 10 42 0\n\
+                            PUBLIC m 2000 0 physics_step\n";
+
+    #[test]
+    fn perf_map_lookup() {
+        let table = parse_perf_map(PERF_MAP).unwrap();
+        assert_eq!(table.len(), 2);
+        let hit = table.lookup(0x7f00_0000_1010).unwrap();
+        assert_eq!(hit.name, "render app.js:10");
+        assert!(table.lookup(0x7f00_0000_1040).is_none());
+        assert!(table.lookup(0x10).is_none());
+        assert!(matches!(
+            parse_perf_map("zz 10 f"),
+            Err(SymbolicateError::Syntax { line: 1, .. })
+        ));
+    }
+
+    #[test]
+    fn v8_tags_and_tiers_are_dropped() {
+        assert_eq!(jit_symbol_name("JS:*render app.js:10"), "render app.js:10");
+        assert_eq!(
+            jit_symbol_name("Function:^tick node:internal/timers:1"),
+            "tick node:internal/timers:1"
+        );
+        assert_eq!(jit_symbol_name("Builtin:ArrayMap"), "Builtin:ArrayMap");
+        assert_eq!(
+            jit_symbol_name("Lcom/example/Server;::handle"),
+            "Lcom/example/Server;::handle"
+        );
+    }
+
+    #[test]
+    fn breakpad_records() {
+        let symbols = parse_breakpad(BREAKPAD).unwrap();
+        assert_eq!(symbols.module, "libgame.so");
+        assert_eq!(symbols.table.len(), 2);
+        assert_eq!(
+            symbols.table.lookup(0x1008).unwrap().name,
+            "Game::update(float)"
+        );
+        // PUBLIC symbols have no size and run to the next symbol.
+        assert_eq!(symbols.table.lookup(0x2fff).unwrap().name, "physics_step");
+        assert!(matches!(
+            parse_breakpad("FUNC 1000 20 0 f"),
+            Err(SymbolicateError::MissingModule)
+        ));
+    }
+
+    #[test]
+    fn frame_address_forms() {
+        assert_eq!(
+            parse_frame_address("0x7f01"),
+            Some(FrameAddress::Absolute(0x7f01))
+        );
+        assert_eq!(
+            parse_frame_address("ffffffff810a"),
+            Some(FrameAddress::Absolute(0xffff_ffff_810a))
+        );
+        assert_eq!(
+            parse_frame_address("/usr/lib/libgame.so+0x1a"),
+            Some(FrameAddress::Module {
+                module: "libgame.so",
+                offset: 0x1a
+            })
+        );
+        assert_eq!(parse_frame_address("cafe"), None);
+        assert_eq!(parse_frame_address("main"), None);
+        assert_eq!(parse_frame_address("func_a+0x10zz"), None);
+    }
+
+    #[test]
+    fn applies_to_profile_and_visual_profile() {
+        let mut symbolicator = Symbolicator::new();
+        symbolicator.add_perf_map(PERF_MAP).unwrap();
+        symbolicator.add_breakpad(BREAKPAD).unwrap();
+
+        let frame = |id, name: &str| Frame {
+            id,
+            name: name.to_string(),
+            start: id as f64,
+            end: id as f64 + 1.0,
+            depth: 0,
+            category: None,
+            parent: None,
+            self_time: 1.0,
+            thread: None,
+            truncated: false,
+            pid: None,
+            tid: None,
+            source: None,
+            binary: None,
+            inlined: vec![],
+        };
+        let profile = Profile::new(
+            ProfileMetadata {
+                name: None,
+                start_time: 0.0,
+                end_time: 4.0,
+                format: "ebpf-perf".to_string(),
+                time_domain: None,
+                value_unit: None,
+                sample_interval_us: None,
+            },
+            vec![
+                frame(0, "0x7f0000002004"),
+                frame(1, "libgame.so+0x1004"),
+                frame(2, "main"),
+                frame(3, "0xdead"),
+            ],
+        );
+
+        let mut raw = profile.clone();
+        assert_eq!(symbolicator.apply(&mut raw), 2);
+        let names: Vec<_> = raw.frames.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(
+            names,
+            ["Builtin:ArrayMap", "Game::update(float)", "main", "0xdead"]
+        );
+
+        let mut visual = profile.into_visual_profile();
+        assert_eq!(symbolicator.apply_visual(&mut visual), 2);
+        assert!(visual.all_spans().any(|s| s.name == "Game::update(float)"));
+    }
+}
@@ -0,0 +1,100 @@
+//! Small hand-built profiles for unit tests.
+//!
+//! Tests list their spans as `(id, name, start, end, parent)` and leave the
+//! rest to [`thread`] and [`profile`]: depths follow the parent links, and
+//! self times and the profile's extent follow the spans.
+
+use std::collections::HashMap;
+
+use flame_cat_protocol::{
+    ProfileMeta, SharedStr, SourceFormat, Span, SpanKind, ThreadGroup, ValueUnit, VisualProfile,
+};
+
+/// An event span; its depth is set by [`thread`].
+pub(crate) fn span(id: u64, name: &str, start: f64, end: f64, parent: Option<u64>) -> Span {
+    Span {
+        id,
+        name: SharedStr::from(name),
+        start,
+        end,
+        depth: 0,
+        parent,
+        self_value: 0.0,
+        kind: SpanKind::Event,
+        category: None,
+        truncated: false,
+        binary: None,
+        inlined: vec![],
+    }
+}
+
+/// Thread `id`, named `T{id}`, with each span one level below its parent.
+pub(crate) fn thread(id: u32, mut spans: Vec<Span>) -> ThreadGroup {
+    let parents: HashMap<u64, Option<u64>> = spans.iter().map(|s| (s.id, s.parent)).collect();
+    for span in &mut spans {
+        let mut parent = span.parent;
+        span.depth = 0;
+        while let Some(id) = parent {
+            span.depth += 1;
+            parent = parents.get(&id).copied().flatten();
+        }
+    }
+    let mut thread = ThreadGroup {
+        id,
+        name: format!("T{id}").into(),
+        kind: Default::default(),
+        sort_key: 0,
+        max_depth: 0,
+        spans,
+        pid: None,
+        tid: None,
+        process_name: None,
+    };
+    thread.compute_max_depth();
+    thread
+}
+
+/// A profile of `threads` in `unit`, covering their spans, with self times
+/// computed.
+pub(crate) fn profile(unit: ValueUnit, threads: Vec<ThreadGroup>) -> VisualProfile {
+    let spans = || threads.iter().flat_map(|t| &t.spans);
+    let start = spans().map(|s| s.start).fold(f64::INFINITY, f64::min);
+    let end = spans().map(|s| s.end).fold(f64::NEG_INFINITY, f64::max);
+    let (start, end) = if start <= end {
+        (start, end)
+    } else {
+        (0.0, 0.0)
+    };
+    let mut profile = VisualProfile {
+        meta: ProfileMeta {
+            name: None,
+            source_format: SourceFormat::Unknown,
+            value_unit: unit,
+            total_value: end - start,
+            start_time: start,
+            end_time: end,
+            time_domain: None,
+            truncated_spans: 0,
+            repairs: Default::default(),
+            limits_hit: Default::default(),
+            partial: None,
+            sample_interval_us: None,
+            description: None,
+            tags: vec![],
+        },
+        threads,
+        frames: vec![],
+        counters: vec![],
+        async_spans: vec![],
+        flow_arrows: vec![],
+        markers: vec![],
+        instant_events: vec![],
+        object_events: vec![],
+        cpu_samples: None,
+        network_requests: vec![],
+        screenshots: vec![],
+        binaries: vec![],
+    };
+    crate::transform::compute_self_times(&mut profile);
+    profile
+}
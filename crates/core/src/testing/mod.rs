@@ -0,0 +1,5 @@
+//! Workloads for benchmarks and stress tests, and fixtures for unit tests.
+
+#[cfg(test)]
+pub(crate) mod fixtures;
+pub mod synth;
@@ -0,0 +1,321 @@
+//! Synthetic profiles of any size.
+//!
+//! [`generate`] builds a profile from a [`SynthConfig`] and a seed, so a
+//! benchmark or stress test gets the same workload on every run and every
+//! machine: spans laid out in time order with a chosen depth distribution,
+//! plus optional counters and cross-thread flows.
+
+use flame_cat_protocol::{
+    CounterSample, CounterTrack, CounterUnit, FlowArrow, ProfileMeta, SharedStr, SourceFormat,
+    Span, SpanKind, ThreadGroup, ValueUnit, VisualProfile,
+};
+
+/// How deep each generated span sits.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DepthDistribution {
+    /// Every root opens a chain down to the deepest level, like a
+    /// recursive call, then the next root starts.
+    Chain,
+    /// Each span goes to any level from the root down to one below the
+    /// previous span, with equal odds.
+    Uniform,
+    /// Level `k` with odds shrinking by `decay` per level, so most spans
+    /// are shallow; `decay` is in `0..1`.
+    Geometric { decay: f64 },
+}
+
+/// Shape of a synthetic profile.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SynthConfig {
+    pub threads: u32,
+    pub spans_per_thread: usize,
+    /// Number of stack levels; spans go no deeper than `levels - 1`.
+    pub levels: u32,
+    pub depth: DepthDistribution,
+    /// Distinct span names, drawn uniformly.
+    pub names: usize,
+    pub counters: usize,
+    pub samples_per_counter: usize,
+    /// Flow arrows between spans of different threads.
+    pub flows: usize,
+    pub seed: u64,
+}
+
+impl Default for SynthConfig {
+    fn default() -> Self {
+        Self {
+            threads: 4,
+            spans_per_thread: 10_000,
+            levels: 16,
+            depth: DepthDistribution::Uniform,
+            names: 256,
+            counters: 0,
+            samples_per_counter: 1_000,
+            flows: 0,
+            seed: 0,
+        }
+    }
+}
+
+/// SplitMix64: tiny, fast and identical on every platform.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform in `0..n`; `n` must not be zero.
+    fn below(&mut self, n: u64) -> u64 {
+        self.next() % n
+    }
+
+    /// Uniform in `0.0..1.0`.
+    fn unit(&mut self) -> f64 {
+        (self.next() >> 11) as f64 / (1_u64 << 53) as f64
+    }
+}
+
+/// A span still waiting for its end.
+struct Open {
+    index: usize,
+    /// Time covered by its finished children.
+    children: f64,
+}
+
+/// Spans of one thread in time order, ids counting up from `next_id`.
+/// Returns them with the time the last one ends.
+fn thread_spans(
+    config: &SynthConfig,
+    names: &[SharedStr],
+    rng: &mut Rng,
+    next_id: &mut u64,
+) -> (Vec<Span>, f64) {
+    let levels = config.levels.max(1) as usize;
+    let mut spans: Vec<Span> = Vec::with_capacity(config.spans_per_thread);
+    let mut open: Vec<Open> = Vec::with_capacity(levels);
+    let mut now = 0.0;
+    let close = |spans: &mut [Span], open: &mut Vec<Open>, now: &mut f64, rng: &mut Rng| {
+        if let Some(done) = open.pop() {
+            *now += 1.0 + rng.below(8) as f64;
+            let span = &mut spans[done.index];
+            span.end = *now;
+            span.self_value = span.duration() - done.children;
+            if let Some(parent) = open.last_mut() {
+                parent.children += span.duration();
+            }
+        }
+    };
+    for _ in 0..config.spans_per_thread {
+        let depth = match config.depth {
+            DepthDistribution::Chain if open.len() < levels => open.len(),
+            DepthDistribution::Chain => 0,
+            DepthDistribution::Uniform => rng.below((open.len() + 1).min(levels) as u64) as usize,
+            DepthDistribution::Geometric { decay } => {
+                let mut depth = 0;
+                while depth < open.len().min(levels - 1) && rng.unit() < decay {
+                    depth += 1;
+                }
+                depth
+            }
+        };
+        while open.len() > depth {
+            close(&mut spans, &mut open, &mut now, rng);
+        }
+        now += 1.0 + rng.below(8) as f64;
+        spans.push(Span {
+            id: *next_id,
+            name: names[rng.below(names.len() as u64) as usize].clone(),
+            start: now,
+            end: now,
+            depth: depth as u32,
+            parent: open.last().map(|o| spans[o.index].id),
+            self_value: 0.0,
+            kind: SpanKind::Event,
+            category: None,
+            truncated: false,
+            binary: None,
+            inlined: vec![],
+        });
+        open.push(Open {
+            index: spans.len() - 1,
+            children: 0.0,
+        });
+        *next_id += 1;
+    }
+    while !open.is_empty() {
+        close(&mut spans, &mut open, &mut now, rng);
+    }
+    (spans, now)
+}
+
+/// Generate the profile `config` describes, in microseconds from zero.
+pub fn generate(config: &SynthConfig) -> VisualProfile {
+    let mut rng = Rng(config.seed);
+    let names: Vec<SharedStr> = (0..config.names.max(1))
+        .map(|i| format!("fn_{i}").into())
+        .collect();
+    let mut next_id = 0;
+    let mut end_time: f64 = 0.0;
+    let threads: Vec<ThreadGroup> = (0..config.threads)
+        .map(|tid| {
+            let (spans, end) = thread_spans(config, &names, &mut rng, &mut next_id);
+            end_time = end_time.max(end);
+            let mut thread = ThreadGroup {
+                id: tid,
+                name: format!("Thread {tid}").into(),
+                kind: Default::default(),
+                sort_key: i64::from(tid),
+                max_depth: 0,
+                spans,
+                pid: Some(1),
+                tid: Some(u64::from(tid) + 1),
+                process_name: None,
+            };
+            thread.compute_max_depth();
+            thread
+        })
+        .collect();
+
+    let counters = (0..config.counters)
+        .map(|c| {
+            let count = config.samples_per_counter.max(1);
+            let step = end_time / count as f64;
+            let mut value = 1_000.0;
+            CounterTrack {
+                name: format!("counter_{c}").into(),
+                unit: CounterUnit::Count,
+                samples: (0..count)
+                    .map(|i| {
+                        value = (value + rng.unit() * 200.0 - 100.0).max(0.0);
+                        CounterSample {
+                            ts: i as f64 * step,
+                            value,
+                        }
+                    })
+                    .collect(),
+            }
+        })
+        .collect();
+
+    let mut flow_arrows = Vec::with_capacity(config.flows);
+    if threads.len() > 1 && config.spans_per_thread > 0 {
+        for f in 0..config.flows {
+            let from = rng.below(threads.len() as u64) as usize;
+            let to = (from + 1 + rng.below(threads.len() as u64 - 1) as usize) % threads.len();
+            let from_span =
+                &threads[from].spans[rng.below(threads[from].spans.len() as u64) as usize];
+            let to_span = &threads[to].spans[rng.below(threads[to].spans.len() as u64) as usize];
+            flow_arrows.push(FlowArrow {
+                name: "flow".into(),
+                id: f.to_string().into(),
+                from_ts: from_span.start,
+                from_pid: 1,
+                from_tid: threads[from].tid.unwrap_or_default(),
+                to_ts: to_span.start.max(from_span.start),
+                to_pid: 1,
+                to_tid: threads[to].tid.unwrap_or_default(),
+            });
+        }
+    }
+
+    VisualProfile {
+        meta: ProfileMeta {
+            name: Some(format!("synthetic (seed {})", config.seed).into()),
+            source_format: SourceFormat::Unknown,
+            value_unit: ValueUnit::Microseconds,
+            total_value: end_time,
+            start_time: 0.0,
+            end_time,
+            time_domain: None,
+            truncated_spans: 0,
+            repairs: Default::default(),
+            limits_hit: Default::default(),
+            partial: None,
+            sample_interval_us: None,
+            description: None,
+            tags: vec![],
+        },
+        threads,
+        frames: vec![],
+        counters,
+        async_spans: vec![],
+        flow_arrows,
+        markers: vec![],
+        instant_events: vec![],
+        object_events: vec![],
+        cpu_samples: None,
+        network_requests: vec![],
+        screenshots: vec![],
+        binaries: vec![],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transform::compute_self_times_checked;
+
+    fn config() -> SynthConfig {
+        SynthConfig {
+            threads: 3,
+            spans_per_thread: 500,
+            levels: 6,
+            counters: 2,
+            samples_per_counter: 50,
+            flows: 20,
+            seed: 7,
+            ..Default::default()
+        }
+    }
+
+    fn shape(profile: &VisualProfile) -> Vec<(u64, f64, f64, u32)> {
+        profile
+            .all_spans()
+            .map(|s| (s.id, s.start, s.end, s.depth))
+            .collect()
+    }
+
+    #[test]
+    fn same_seed_same_profile() {
+        let a = generate(&config());
+        assert_eq!(shape(&a), shape(&generate(&config())));
+        let other = generate(&SynthConfig {
+            seed: 8,
+            ..config()
+        });
+        assert_ne!(shape(&a), shape(&other));
+    }
+
+    #[test]
+    fn spans_nest_with_consistent_self_times() {
+        for depth in [
+            DepthDistribution::Chain,
+            DepthDistribution::Uniform,
+            DepthDistribution::Geometric { decay: 0.7 },
+        ] {
+            let mut profile = generate(&SynthConfig { depth, ..config() });
+            assert_eq!(profile.span_count(), 1_500);
+            assert!(profile.threads.iter().all(|t| t.max_depth <= 5));
+            assert!(compute_self_times_checked(&mut profile).is_empty());
+        }
+        let chain = generate(&SynthConfig {
+            depth: DepthDistribution::Chain,
+            ..config()
+        });
+        assert!(chain.threads.iter().all(|t| t.max_depth == 5));
+    }
+
+    #[test]
+    fn adds_counters_and_cross_thread_flows() {
+        let profile = generate(&config());
+        assert_eq!(profile.counters.len(), 2);
+        assert!(profile.counters.iter().all(|c| c.samples.len() == 50));
+        assert_eq!(profile.flow_arrows.len(), 20);
+        assert!(profile.flow_arrows.iter().all(|f| f.from_tid != f.to_tid));
+    }
+}
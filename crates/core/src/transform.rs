@@ -0,0 +1,637 @@
+//! Whole-profile passes run on a [`VisualProfile`] after parsing.
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use flame_cat_protocol::{SharedStr, VisualProfile};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// An inconsistency found by [`compute_self_times_checked`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SelfTimeIssue {
+    /// Children cover more time than the span itself; `self_value` was
+    /// clamped to zero from `raw`.
+    NegativeSelfTime { span_id: u64, raw: f64 },
+    /// A child span lasts longer than its parent.
+    ParentShorterThanChild { parent_id: u64, child_id: u64 },
+}
+
+/// Set every span's `self_value` to its duration minus the durations of its
+/// direct children, clamped at zero. Parsers leave self time to this pass so
+/// every format agrees on what it means.
+pub fn compute_self_times(profile: &mut VisualProfile) {
+    compute(profile, None);
+}
+
+/// [`compute_self_times`], also reporting spans whose tree does not add up.
+/// The span tree has normally been sanitized already, so any issue here
+/// points at a parser bug; useful in debug builds and tests.
+pub fn compute_self_times_checked(profile: &mut VisualProfile) -> Vec<SelfTimeIssue> {
+    let mut issues = Vec::new();
+    compute(profile, Some(&mut issues));
+    issues
+}
+
+fn compute(profile: &mut VisualProfile, mut issues: Option<&mut Vec<SelfTimeIssue>>) {
+    let mut child_time: HashMap<u64, f64> = HashMap::new();
+    let durations: HashMap<u64, f64> = profile.all_spans().map(|s| (s.id, s.duration())).collect();
+    for span in profile.all_spans() {
+        let Some(parent_id) = span.parent else {
+            continue;
+        };
+        *child_time.entry(parent_id).or_default() += span.duration();
+        if let Some(issues) = issues.as_deref_mut()
+            && durations
+                .get(&parent_id)
+                .is_some_and(|&d| d < span.duration())
+        {
+            issues.push(SelfTimeIssue::ParentShorterThanChild {
+                parent_id,
+                child_id: span.id,
+            });
+        }
+    }
+    for span in profile.threads.iter_mut().flat_map(|t| &mut t.spans) {
+        let raw = span.duration() - child_time.get(&span.id).copied().unwrap_or(0.0);
+        if raw < 0.0
+            && let Some(issues) = issues.as_deref_mut()
+        {
+            issues.push(SelfTimeIssue::NegativeSelfTime {
+                span_id: span.id,
+                raw,
+            });
+        }
+        span.self_value = raw.max(0.0);
+    }
+}
+
+/// Gaps longer than this many sampling intervals split stitched samples.
+pub const DEFAULT_GAP_FACTOR: f64 = 10.0;
+
+/// How far apart two samples may be and still be stitched into one span.
+///
+/// A sample normally lasts until the next one. When the next sample comes
+/// much later than usual, the thread was most likely asleep in between, so
+/// the sample is given one interval instead of the whole gap.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SampleGaps {
+    /// Typical time between samples: the median of the gaps.
+    pub interval: f64,
+    /// Longest gap still treated as continuous.
+    pub max_gap: f64,
+}
+
+impl SampleGaps {
+    /// Gaps for samples taken every `interval`, split at gaps over
+    /// `factor` intervals.
+    pub fn new(interval: f64, factor: f64) -> Self {
+        Self {
+            interval,
+            max_gap: interval * factor,
+        }
+    }
+
+    /// [`SampleGaps::new`] with the recorded `interval` when there is one,
+    /// else [`SampleGaps::measure`].
+    pub fn for_samples(timestamps: &[f64], interval: Option<f64>, factor: f64) -> Option<Self> {
+        match interval.filter(|&i| i > 0.0) {
+            Some(interval) => Some(Self::new(interval, factor)),
+            None => Self::measure(timestamps, factor),
+        }
+    }
+
+    /// Measure sorted sample `timestamps`, splitting at gaps over `factor`
+    /// intervals. `None` with fewer than two distinct timestamps.
+    pub fn measure(timestamps: &[f64], factor: f64) -> Option<Self> {
+        let mut deltas: Vec<f64> = timestamps
+            .windows(2)
+            .map(|w| w[1] - w[0])
+            .filter(|d| *d > 0.0)
+            .collect();
+        if deltas.is_empty() {
+            return None;
+        }
+        let mid = deltas.len() / 2;
+        let (_, &mut interval, _) = deltas.select_nth_unstable_by(mid, f64::total_cmp);
+        Some(Self::new(interval, factor))
+    }
+
+    /// End of a sample taken at `ts` when the next one is at `next`.
+    pub fn sample_end(&self, ts: f64, next: f64) -> f64 {
+        if next - ts > self.max_gap {
+            ts + self.interval
+        } else {
+            next
+        }
+    }
+}
+
+/// A rule rewriting span names that match `pattern`, a regex, with
+/// `replacement`, which may refer to capture groups as `$1` or `$name`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NameRewrite {
+    pub pattern: String,
+    pub replacement: String,
+}
+
+#[derive(Debug, Error)]
+pub enum NameRewriteError {
+    #[error("invalid rewrite pattern {pattern:?}: {source}")]
+    Pattern {
+        pattern: String,
+        source: regex::Error,
+    },
+}
+
+/// Compiled [`NameRewrite`] rules, applied in order so later rules see
+/// the names earlier ones produced.
+#[derive(Debug, Clone, Default)]
+pub struct NameRewriter {
+    rules: Vec<(Regex, String)>,
+}
+
+impl NameRewriter {
+    pub fn new(rules: &[NameRewrite]) -> Result<Self, NameRewriteError> {
+        let rules = rules
+            .iter()
+            .map(|rule| {
+                let regex =
+                    Regex::new(&rule.pattern).map_err(|source| NameRewriteError::Pattern {
+                        pattern: rule.pattern.clone(),
+                        source,
+                    })?;
+                Ok((regex, rule.replacement.clone()))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self { rules })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// `name` with every rule applied.
+    pub fn rewrite<'a>(&self, name: &'a str) -> Cow<'a, str> {
+        let mut name = Cow::Borrowed(name);
+        for (regex, replacement) in &self.rules {
+            if let Cow::Owned(rewritten) = regex.replace_all(&name, replacement.as_str()) {
+                name = Cow::Owned(rewritten);
+            }
+        }
+        name
+    }
+}
+
+/// Rewrite every span name of `profile` with `rewriter`, so spans whose
+/// names differ only in noise (template arguments, lambda numbers,
+/// addresses) aggregate together. Returns how many names changed.
+pub fn rewrite_names(profile: &mut VisualProfile, rewriter: &NameRewriter) -> usize {
+    if rewriter.is_empty() {
+        return 0;
+    }
+    let mut rewritten: HashMap<SharedStr, Option<SharedStr>> = HashMap::new();
+    let mut changed = 0;
+    for span in profile.threads.iter_mut().flat_map(|t| t.spans.iter_mut()) {
+        let name = rewritten
+            .entry(span.name.clone())
+            .or_insert_with_key(|name| match rewriter.rewrite(name) {
+                Cow::Owned(new) if new != **name => Some(SharedStr::from(new)),
+                _ => None,
+            });
+        if let Some(name) = name {
+            span.name = name.clone();
+            changed += 1;
+        }
+    }
+    changed
+}
+
+/// What [`anonymize`] does with one kind of sensitive text.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Scrub {
+    /// Leave it as recorded.
+    Keep,
+    /// Replace it with a token derived from its hash, so equal values
+    /// still match each other across the profile.
+    #[default]
+    Hash,
+    /// Replace it with a placeholder naming its kind.
+    Strip,
+}
+
+impl Scrub {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "keep" => Some(Self::Keep),
+            "hash" => Some(Self::Hash),
+            "strip" => Some(Self::Strip),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Error, PartialEq)]
+pub enum AnonymizeRulesError {
+    #[error("rule {0:?} is not of the form `urls|paths|args=keep|hash|strip`")]
+    Malformed(String),
+}
+
+/// What [`anonymize`] scrubs from a profile before it is shared.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AnonymizeRules {
+    /// `scheme://…` URLs.
+    pub urls: Scrub,
+    /// Absolute and relative file paths, Unix or Windows.
+    pub paths: Scrub,
+    /// Argument payloads: quoted string literals in names, and screenshot
+    /// images, which are dropped unless kept.
+    pub args: Scrub,
+    /// Mixed into every hash, so tokens cannot be matched against hashes
+    /// of guessed values without it.
+    pub salt: String,
+}
+
+impl FromStr for AnonymizeRules {
+    type Err = AnonymizeRulesError;
+
+    /// Parse a comma-separated list such as `urls=strip, args=keep`; kinds
+    /// not named are hashed.
+    fn from_str(spec: &str) -> Result<Self, Self::Err> {
+        let mut rules = Self::default();
+        for rule in spec.split(',').map(str::trim).filter(|r| !r.is_empty()) {
+            let malformed = || AnonymizeRulesError::Malformed(rule.to_string());
+            let (kind, scrub) = rule.split_once('=').ok_or_else(malformed)?;
+            let scrub = Scrub::from_name(scrub.trim()).ok_or_else(malformed)?;
+            match kind.trim() {
+                "urls" => rules.urls = scrub,
+                "paths" => rules.paths = scrub,
+                "args" => rules.args = scrub,
+                _ => return Err(malformed()),
+            }
+        }
+        Ok(rules)
+    }
+}
+
+/// Scrub URLs, file paths and argument payloads from every name, source
+/// and label of `profile` as `rules` say, so it can be shared outside the
+/// team that recorded it. Timing and structure are untouched.
+pub fn anonymize(profile: &mut VisualProfile, rules: &AnonymizeRules) {
+    let mut scrubber = Scrubber {
+        rules,
+        cache: HashMap::new(),
+    };
+    let meta = &mut profile.meta;
+    scrubber.option(&mut meta.name);
+    scrubber.option(&mut meta.description);
+    if let Some(partial) = &mut meta.partial {
+        scrubber.text(&mut partial.error);
+    }
+    for thread in &mut profile.threads {
+        scrubber.text(&mut thread.name);
+        scrubber.option(&mut thread.process_name);
+        for span in &mut thread.spans {
+            scrubber.text(&mut span.name);
+            span.inlined.iter_mut().for_each(|name| scrubber.text(name));
+            if let Some(category) = &mut span.category {
+                scrubber.option(&mut category.source);
+            }
+        }
+    }
+    for binary in &mut profile.binaries {
+        scrubber.text(&mut binary.name);
+    }
+    for counter in &mut profile.counters {
+        scrubber.text(&mut counter.name);
+    }
+    for span in &mut profile.async_spans {
+        scrubber.text(&mut span.name);
+    }
+    for arrow in &mut profile.flow_arrows {
+        scrubber.text(&mut arrow.name);
+    }
+    for marker in &mut profile.markers {
+        scrubber.text(&mut marker.name);
+    }
+    for event in &mut profile.instant_events {
+        scrubber.text(&mut event.name);
+    }
+    for event in &mut profile.object_events {
+        scrubber.text(&mut event.name);
+    }
+    if let Some(samples) = &mut profile.cpu_samples {
+        for node in &mut samples.nodes {
+            scrubber.text(&mut node.function_name);
+            scrubber.option(&mut node.source);
+        }
+    }
+    for request in &mut profile.network_requests {
+        scrubber.text(&mut request.url);
+        scrubber.option(&mut request.initiator);
+    }
+    if rules.args != Scrub::Keep {
+        profile.screenshots.clear();
+    }
+}
+
+/// Rewrites text for [`anonymize`], remembering each distinct input.
+struct Scrubber<'a> {
+    rules: &'a AnonymizeRules,
+    cache: HashMap<SharedStr, SharedStr>,
+}
+
+impl Scrubber<'_> {
+    fn text(&mut self, text: &mut SharedStr) {
+        if let Some(scrubbed) = self.cache.get(text) {
+            *text = scrubbed.clone();
+            return;
+        }
+        let scrubbed = SharedStr::from(self.scrub(text));
+        self.cache.insert(text.clone(), scrubbed.clone());
+        *text = scrubbed;
+    }
+
+    fn option(&mut self, text: &mut Option<SharedStr>) {
+        if let Some(text) = text {
+            self.text(text);
+        }
+    }
+
+    fn scrub(&self, text: &str) -> String {
+        let mut out = String::with_capacity(text.len());
+        let mut rest = text;
+        while let Some(c) = rest.chars().next() {
+            if matches!(c, '"' | '\'')
+                && self.rules.args != Scrub::Keep
+                && let Some(end) = rest[1..].find(c)
+            {
+                let literal = &rest[..end + 2];
+                out.push(c);
+                out.push_str(&self.replace("arg", self.rules.args, &literal[1..literal.len() - 1]));
+                out.push(c);
+                rest = &rest[literal.len()..];
+                continue;
+            }
+            let token_len = rest
+                .find(|c: char| c.is_whitespace() || "\"'()[]{}<>,;".contains(c))
+                .unwrap_or(rest.len());
+            if token_len == 0 {
+                out.push(c);
+                rest = &rest[c.len_utf8()..];
+                continue;
+            }
+            let token = &rest[..token_len];
+            // Keep `:line:column` suffixes readable.
+            let location = token
+                .trim_end_matches(|c: char| c.is_ascii_digit() || c == ':')
+                .len();
+            let (body, suffix) = token.split_at(location.max(1));
+            let kind = if is_url(body) {
+                Some(("url", self.rules.urls))
+            } else if is_path(body) {
+                Some(("path", self.rules.paths))
+            } else {
+                None
+            };
+            match kind {
+                Some((kind, scrub)) if scrub != Scrub::Keep => {
+                    out.push_str(&self.replace(kind, scrub, body));
+                    out.push_str(suffix);
+                }
+                _ => out.push_str(token),
+            }
+            rest = &rest[token_len..];
+        }
+        out
+    }
+
+    /// Hash token or placeholder for `value`, a `kind` of text scrubbed
+    /// by `scrub`.
+    fn replace(&self, kind: &str, scrub: Scrub, value: &str) -> String {
+        if scrub == Scrub::Strip {
+            return format!("<{kind}>");
+        }
+        // FNV-1a: stable across runs and platforms, unlike `DefaultHasher`.
+        let hash = self
+            .rules
+            .salt
+            .bytes()
+            .chain([0])
+            .chain(value.bytes())
+            .fold(0xcbf2_9ce4_8422_2325_u64, |h, b| {
+                (h ^ u64::from(b)).wrapping_mul(0x0100_0000_01b3)
+            });
+        format!("<{kind}:{:08x}>", hash as u32)
+    }
+}
+
+/// Whether `token` is a `scheme://…` URL.
+fn is_url(token: &str) -> bool {
+    token.split_once("://").is_some_and(|(scheme, rest)| {
+        !scheme.is_empty()
+            && !rest.is_empty()
+            && scheme
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || "+-.".contains(c))
+    })
+}
+
+/// Whether `token` looks like a file path of at least two components.
+fn is_path(token: &str) -> bool {
+    let bytes = token.as_bytes();
+    let windows = bytes.len() > 3
+        && bytes[0].is_ascii_alphabetic()
+        && bytes[1] == b':'
+        && matches!(bytes[2], b'\\' | b'/');
+    let unix = ["/", "~/", "./", "../"]
+        .iter()
+        .any(|prefix| token.starts_with(prefix))
+        && token.trim_start_matches(['.', '~', '/']).contains('/');
+    windows || unix
+}
+
+#[cfg(test)]
+mod tests {
+    use flame_cat_protocol::{Span, SpanKind, ThreadGroup};
+
+    use super::*;
+    use crate::model::{Profile, ProfileMetadata};
+
+    fn span(id: u64, start: f64, end: f64, parent: Option<u64>) -> Span {
+        Span {
+            id,
+            name: format!("s{id}").into(),
+            start,
+            end,
+            depth: u32::from(parent.is_some()),
+            parent,
+            self_value: 0.0,
+            kind: SpanKind::Event,
+            category: None,
+            truncated: false,
+            binary: None,
+            inlined: vec![],
+        }
+    }
+
+    #[test]
+    fn computes_and_reports() {
+        let meta = ProfileMetadata {
+            name: None,
+            start_time: 0.0,
+            end_time: 100.0,
+            format: "chrome".to_string(),
+            time_domain: None,
+            value_unit: None,
+            sample_interval_us: None,
+        };
+        let mut profile = Profile::new(meta, vec![]).into_visual_profile();
+        profile.threads = vec![ThreadGroup {
+            id: 0,
+            name: "Main".into(),
+            kind: Default::default(),
+            sort_key: 0,
+            max_depth: 1,
+            spans: vec![
+                span(1, 0.0, 100.0, None),
+                span(2, 10.0, 40.0, Some(1)),
+                span(3, 0.0, 10.0, None),
+                // Both children are longer than their 10µs parent.
+                span(4, 0.0, 15.0, Some(3)),
+                span(5, 0.0, 12.0, Some(3)),
+            ],
+            pid: None,
+            tid: None,
+            process_name: None,
+        }];
+
+        compute_self_times(&mut profile);
+        let self_values: Vec<f64> = profile.all_spans().map(|s| s.self_value).collect();
+        assert_eq!(self_values, [70.0, 30.0, 0.0, 15.0, 12.0]);
+
+        let issues = compute_self_times_checked(&mut profile);
+        assert_eq!(
+            issues,
+            [
+                SelfTimeIssue::ParentShorterThanChild {
+                    parent_id: 3,
+                    child_id: 4
+                },
+                SelfTimeIssue::ParentShorterThanChild {
+                    parent_id: 3,
+                    child_id: 5
+                },
+                SelfTimeIssue::NegativeSelfTime {
+                    span_id: 3,
+                    raw: -17.0
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn sample_gaps_split_long_pauses() {
+        let gaps = SampleGaps::measure(&[0.0, 10.0, 20.0, 30.0, 500.0, 510.0], 10.0).unwrap();
+        assert_eq!(gaps.interval, 10.0);
+        assert_eq!(gaps.sample_end(20.0, 30.0), 30.0);
+        assert_eq!(gaps.sample_end(30.0, 500.0), 40.0);
+        assert_eq!(SampleGaps::measure(&[5.0, 5.0], 10.0), None);
+    }
+
+    #[test]
+    fn sample_gaps_prefer_recorded_interval() {
+        let ts = [0.0, 10.0, 20.0, 30.0, 500.0];
+        let gaps = SampleGaps::for_samples(&ts, Some(100.0), 10.0).unwrap();
+        assert_eq!(gaps.interval, 100.0);
+        assert_eq!(gaps.sample_end(30.0, 500.0), 500.0);
+        let measured = SampleGaps::for_samples(&ts, Some(0.0), 10.0).unwrap();
+        assert_eq!(measured.interval, 10.0);
+    }
+
+    #[test]
+    fn anonymize_scrubs_urls_paths_and_args() {
+        let rules: AnonymizeRules = "paths=strip, args=keep".parse().unwrap();
+        let scrubber = Scrubber {
+            rules: &rules,
+            cache: HashMap::new(),
+        };
+        let name = "load (/home/ana/app/src/main.rs:12:4) https://intra.example/api?q=1";
+        let scrubbed = scrubber.scrub(name);
+        assert!(
+            scrubbed.starts_with("load (<path>:12:4) <url:"),
+            "{scrubbed}"
+        );
+        assert_eq!(scrubbed, scrubber.scrub(name));
+        assert_eq!(
+            scrubber.scrub("query \"secret\" a/b"),
+            "query \"secret\" a/b"
+        );
+        assert_eq!(scrubber.scrub(r"C:\Users\ana\x.dll"), "<path>");
+
+        let hashed = AnonymizeRules::default();
+        let scrubber = Scrubber {
+            rules: &hashed,
+            cache: HashMap::new(),
+        };
+        let scrubbed = scrubber.scrub("fetch('token-1')");
+        assert!(scrubbed.starts_with("fetch('<arg:") && scrubbed.ends_with(">')"));
+        let salted = AnonymizeRules {
+            salt: "pepper".into(),
+            ..AnonymizeRules::default()
+        };
+        let salted = Scrubber {
+            rules: &salted,
+            cache: HashMap::new(),
+        };
+        assert_ne!(scrubbed, salted.scrub("fetch('token-1')"));
+
+        assert_eq!(
+            "urls=bogus".parse::<AnonymizeRules>(),
+            Err(AnonymizeRulesError::Malformed("urls=bogus".into()))
+        );
+    }
+
+    #[test]
+    fn anonymize_rewrites_profile_text() {
+        let meta = ProfileMetadata {
+            name: Some("/srv/traces/run.json".into()),
+            start_time: 0.0,
+            end_time: 10.0,
+            format: "chrome".to_string(),
+            time_domain: None,
+            value_unit: None,
+            sample_interval_us: None,
+        };
+        let mut profile = Profile::new(meta, vec![]).into_visual_profile();
+        let mut root = span(1, 0.0, 10.0, None);
+        root.name = "GET https://example.com/a".into();
+        profile.threads = vec![ThreadGroup {
+            id: 0,
+            name: "Main".into(),
+            kind: Default::default(),
+            sort_key: 0,
+            max_depth: 0,
+            spans: vec![root, span(2, 0.0, 5.0, Some(1))],
+            pid: None,
+            tid: None,
+            process_name: Some("/usr/bin/app".into()),
+        }];
+
+        let rules = AnonymizeRules {
+            urls: Scrub::Strip,
+            paths: Scrub::Strip,
+            ..AnonymizeRules::default()
+        };
+        anonymize(&mut profile, &rules);
+        assert_eq!(profile.meta.name.as_deref(), Some("<path>"));
+        let thread = &profile.threads[0];
+        assert_eq!(thread.process_name.as_deref(), Some("<path>"));
+        assert_eq!(&*thread.spans[0].name, "GET <url>");
+        assert_eq!(&*thread.spans[1].name, "s2");
+    }
+}
@@ -0,0 +1,238 @@
+//! Accessibility tree over rendered commands.
+//!
+//! A canvas is opaque to screen readers. [`accessibility_tree`] turns the
+//! groups and labelled rects of a render into nested nodes (view, lane,
+//! flame row, span) that a web host can mirror as ARIA elements or walk
+//! with the keyboard.
+
+use std::collections::HashMap;
+
+use flame_cat_protocol::{GroupRole, Rect, RenderCommand, VisualProfile};
+use serde::Serialize;
+
+/// What an [`AccessNode`] stands for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AccessRole {
+    View,
+    Lane,
+    /// Spans of one stack depth within a lane.
+    Row,
+    /// One labelled rect, usually a span.
+    Item,
+}
+
+impl From<GroupRole> for AccessRole {
+    fn from(role: GroupRole) -> Self {
+        match role {
+            GroupRole::View => Self::View,
+            GroupRole::Lane => Self::Lane,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AccessNode {
+    pub role: AccessRole,
+    pub label: String,
+    /// Span behind an item, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub frame_id: Option<u64>,
+    /// Where an item was drawn, in viewport pixels.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rect: Option<Rect>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<AccessNode>,
+}
+
+impl AccessNode {
+    fn group(role: AccessRole, label: String) -> Self {
+        Self {
+            role,
+            label,
+            frame_id: None,
+            rect: None,
+            children: Vec::new(),
+        }
+    }
+}
+
+/// A group still collecting children, with its flame rows by depth.
+struct OpenGroup {
+    node: AccessNode,
+    rows: Vec<(u32, AccessNode)>,
+}
+
+impl OpenGroup {
+    fn finish(mut self) -> AccessNode {
+        self.rows.sort_by_key(|&(depth, _)| depth);
+        self.node
+            .children
+            .extend(self.rows.into_iter().map(|(_, row)| row));
+        self.node
+    }
+}
+
+/// Nest the groups of `commands` into a tree, top-level groups first.
+///
+/// Labelled rects become items of the innermost open group. Items whose
+/// `frame_id` is a span of `profile` are gathered into one
+/// "flame row depth N" node per stack depth, shallowest first; other items
+/// stay in drawing order ahead of the rows. Unlabelled rects, text and
+/// other decorations are left out.
+pub fn accessibility_tree(commands: &[RenderCommand], profile: &VisualProfile) -> Vec<AccessNode> {
+    let depths: HashMap<u64, u32> = profile.all_spans().map(|s| (s.id, s.depth)).collect();
+    let mut stack = vec![OpenGroup {
+        node: AccessNode::group(AccessRole::View, String::new()),
+        rows: Vec::new(),
+    }];
+    for cmd in commands {
+        match cmd {
+            RenderCommand::BeginGroup { id, label, role } => stack.push(OpenGroup {
+                node: AccessNode::group((*role).into(), label.as_deref().unwrap_or(id).to_string()),
+                rows: Vec::new(),
+            }),
+            RenderCommand::EndGroup if stack.len() > 1 => {
+                let done = stack.pop().map(OpenGroup::finish);
+                if let (Some(done), Some(parent)) = (done, stack.last_mut()) {
+                    parent.node.children.push(done);
+                }
+            }
+            RenderCommand::DrawRect {
+                rect,
+                label: Some(label),
+                frame_id,
+                ..
+            } => {
+                let Some(group) = stack.last_mut() else {
+                    continue;
+                };
+                let item = AccessNode {
+                    role: AccessRole::Item,
+                    label: label.to_string(),
+                    frame_id: *frame_id,
+                    rect: Some(*rect),
+                    children: Vec::new(),
+                };
+                match frame_id.and_then(|id| depths.get(&id)) {
+                    Some(&depth) => {
+                        let at = match group.rows.iter().position(|(d, _)| *d == depth) {
+                            Some(at) => at,
+                            None => {
+                                group.rows.push((
+                                    depth,
+                                    AccessNode::group(
+                                        AccessRole::Row,
+                                        format!("flame row depth {depth}"),
+                                    ),
+                                ));
+                                group.rows.len() - 1
+                            }
+                        };
+                        group.rows[at].1.children.push(item);
+                    }
+                    None => group.node.children.push(item),
+                }
+            }
+            _ => {}
+        }
+    }
+    // Close groups a truncated command list left open.
+    while stack.len() > 1 {
+        if let Some(done) = stack.pop().map(OpenGroup::finish)
+            && let Some(parent) = stack.last_mut()
+        {
+            parent.node.children.push(done);
+        }
+    }
+    stack
+        .pop()
+        .map(|root| root.finish().children)
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use flame_cat_protocol::{ThemeToken, Viewport};
+
+    use super::*;
+    use crate::parsers::chrome::parse_chrome_trace;
+    use crate::views::time_order::render_time_order;
+
+    fn profile() -> VisualProfile {
+        let json = r#"{"traceEvents":[
+            {"name":"thread_name","ph":"M","ts":0,"pid":1,"tid":1,"args":{"name":"Main"}},
+            {"name":"main","ph":"X","ts":0,"dur":100,"pid":1,"tid":1},
+            {"name":"parse","ph":"X","ts":10,"dur":30,"pid":1,"tid":1},
+            {"name":"layout","ph":"X","ts":50,"dur":30,"pid":1,"tid":1}
+        ]}"#;
+        parse_chrome_trace(json.as_bytes())
+            .unwrap()
+            .into_visual_profile()
+    }
+
+    #[test]
+    fn nests_lanes_rows_and_spans() {
+        let profile = profile();
+        let viewport = Viewport {
+            x: 0.0,
+            y: 0.0,
+            width: 800.0,
+            height: 600.0,
+            dpr: 1.0,
+        };
+        let commands = render_time_order(&profile, &viewport, 0.0, 100.0, None);
+        let tree = accessibility_tree(&commands, &profile);
+
+        assert_eq!(tree.len(), 1);
+        assert_eq!(
+            (tree[0].role, tree[0].label.as_str()),
+            (AccessRole::View, "Time Order")
+        );
+        let lane = &tree[0].children[0];
+        assert_eq!((lane.role, lane.label.as_str()), (AccessRole::Lane, "Main"));
+        let rows: Vec<(&str, Vec<&str>)> = lane
+            .children
+            .iter()
+            .map(|row| {
+                assert_eq!(row.role, AccessRole::Row);
+                (
+                    row.label.as_str(),
+                    row.children.iter().map(|i| i.label.as_str()).collect(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            rows,
+            [
+                ("flame row depth 0", vec!["main"]),
+                ("flame row depth 1", vec!["parse", "layout"]),
+            ]
+        );
+        assert!(lane.children[1].children[0].frame_id.is_some());
+    }
+
+    #[test]
+    fn keeps_unmatched_items_and_closes_open_groups() {
+        let commands = [
+            RenderCommand::BeginGroup {
+                id: "ranked".into(),
+                label: None,
+                role: GroupRole::View,
+            },
+            RenderCommand::DrawRect {
+                rect: Rect::new(0.0, 0.0, 10.0, 10.0),
+                color: ThemeToken::FlameHot,
+                border_color: None,
+                label: Some("row".into()),
+                frame_id: Some(700),
+                dimmed: false,
+                span_name: None,
+            },
+        ];
+        let tree = accessibility_tree(&commands, &profile());
+        assert_eq!(tree[0].label, "ranked");
+        assert_eq!(tree[0].children[0].role, AccessRole::Item);
+        assert_eq!(tree[0].children[0].frame_id, Some(700));
+    }
+}
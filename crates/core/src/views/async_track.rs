@@ -1,5 +1,5 @@
 use flame_cat_protocol::{
-    AsyncSpan, Point, Rect, RenderCommand, SharedStr, TextAlign, ThemeToken, Viewport,
+    AsyncSpan, GroupRole, Point, Rect, RenderCommand, SharedStr, TextAlign, ThemeToken, Viewport,
 };
 use std::collections::HashMap;
 
@@ -10,9 +10,12 @@ const FONT_SIZE: f64 = 10.0;
 /// Render async spans as horizontal bars grouped by category.
 ///
 /// Each category gets its own row. Spans within a category are laid out
-/// in parallel rows to avoid overlapping (swimlane packing).
+/// in parallel rows to avoid overlapping (swimlane packing). `executing`
+/// is index-aligned with `spans` (see [`crate::model::AsyncLinks`]); linked
+/// spans carry their executing span's id as the rect's `frame_id`.
 pub fn render_async_track(
     spans: &[AsyncSpan],
+    executing: &[Option<u64>],
     viewport: &Viewport,
     view_start: f64,
     view_end: f64,
@@ -28,6 +31,7 @@ pub fn render_async_track(
     commands.push(RenderCommand::BeginGroup {
         id: "async-track".into(),
         label: Some("Async Spans".into()),
+        role: GroupRole::Lane,
     });
 
     // Background
@@ -37,16 +41,18 @@ pub fn render_async_track(
         border_color: Some(ThemeToken::LaneBorder),
         label: None,
         frame_id: None,
+        dimmed: false,
+        span_name: None,
     });
 
     // Group spans by category
-    let mut groups: HashMap<SharedStr, Vec<&AsyncSpan>> = HashMap::new();
-    for span in spans {
+    let mut groups: HashMap<SharedStr, Vec<(usize, &AsyncSpan)>> = HashMap::new();
+    for (index, span) in spans.iter().enumerate() {
         if span.end < view_start || span.start > view_end {
             continue;
         }
         let key = span.cat.clone().unwrap_or_else(|| "uncategorized".into());
-        groups.entry(key).or_default().push(span);
+        groups.entry(key).or_default().push((index, span));
     }
 
     // Sort groups by name for stable ordering
@@ -72,30 +78,30 @@ pub fn render_async_track(
 
         // Swimlane packing: assign each span to the first row where it fits
         let mut row_ends: Vec<f64> = Vec::new();
-        let mut assignments: Vec<(usize, &AsyncSpan)> = Vec::new();
+        let mut assignments: Vec<(usize, usize, &AsyncSpan)> = Vec::new();
 
         // Sort by start time
-        let mut sorted: Vec<&AsyncSpan> = group_spans.clone();
-        sorted.sort_by(|a, b| a.start.total_cmp(&b.start));
+        let mut sorted: Vec<(usize, &AsyncSpan)> = group_spans.clone();
+        sorted.sort_by(|a, b| a.1.start.total_cmp(&b.1.start));
 
-        for span in sorted {
+        for (index, span) in sorted {
             let mut placed = false;
             for (row_idx, row_end) in row_ends.iter_mut().enumerate() {
                 if span.start >= *row_end {
                     *row_end = span.end;
-                    assignments.push((row_idx, span));
+                    assignments.push((row_idx, index, span));
                     placed = true;
                     break;
                 }
             }
             if !placed {
                 row_ends.push(span.end);
-                assignments.push((row_ends.len() - 1, span));
+                assignments.push((row_ends.len() - 1, index, span));
             }
         }
 
         // Render spans
-        for (row, span) in &assignments {
+        for (row, index, span) in &assignments {
             let x = (span.start - view_start) * x_scale;
             let w = (span.end - span.start) * x_scale;
             let y = current_y + *row as f64 * (ROW_HEIGHT + ROW_GAP);
@@ -116,7 +122,11 @@ pub fn render_async_track(
                 color: ThemeToken::AsyncSpanFill,
                 border_color: Some(ThemeToken::AsyncSpanBorder),
                 label: Some(span.name.clone()),
-                frame_id: None,
+                // Hovering or selecting an async span acts on the span
+                // that executed it.
+                frame_id: executing.get(*index).copied().flatten(),
+                dimmed: false,
+                span_name: None,
             });
         }
 
@@ -161,7 +171,7 @@ mod tests {
             height: 100.0,
             dpr: 1.0,
         };
-        let cmds = render_async_track(&spans, &vp, 0.0, 100.0);
+        let cmds = render_async_track(&spans, &[None, Some(7)], &vp, 0.0, 100.0);
         assert!(!cmds.is_empty());
 
         let rects: Vec<_> = cmds
@@ -170,6 +180,14 @@ mod tests {
             .collect();
         // bg + 2 span rects
         assert!(rects.len() >= 3);
+        let ids: Vec<_> = rects
+            .iter()
+            .filter_map(|c| match c {
+                RenderCommand::DrawRect { frame_id, .. } => *frame_id,
+                _ => None,
+            })
+            .collect();
+        assert_eq!(ids, [7]);
     }
 
     #[test]
@@ -181,7 +199,7 @@ mod tests {
             height: 100.0,
             dpr: 1.0,
         };
-        let cmds = render_async_track(&[], &vp, 0.0, 100.0);
+        let cmds = render_async_track(&[], &[], &vp, 0.0, 100.0);
         assert!(cmds.is_empty());
     }
 }
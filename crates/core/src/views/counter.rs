@@ -1,5 +1,6 @@
 use flame_cat_protocol::{
-    CounterTrack, Point, Rect, RenderCommand, SharedStr, TextAlign, ThemeToken, Viewport,
+    CounterTrack, CounterUnit, GroupRole, Point, Rect, RenderCommand, SharedStr, TextAlign,
+    ThemeToken, Viewport,
 };
 
 const COUNTER_TRACK_HEIGHT: f64 = 60.0;
@@ -9,12 +10,15 @@ const FONT_SIZE: f64 = 10.0;
 /// Render a single counter track as an area chart.
 ///
 /// Returns render commands that draw a filled area chart of the counter's
-/// time-series samples within the given time window.
+/// time-series samples within the given time window. With a `budget`, the
+/// limit is drawn as a line and samples that break it are shaded (see
+/// [`CounterUnit::exceeds_budget`]).
 pub fn render_counter_track(
     counter: &CounterTrack,
     viewport: &Viewport,
     view_start: f64,
     view_end: f64,
+    budget: Option<f64>,
 ) -> Vec<RenderCommand> {
     let duration = view_end - view_start;
     if duration <= 0.0 || counter.samples.is_empty() {
@@ -43,6 +47,11 @@ pub fn render_counter_track(
             lo = lo.min(after.value);
             hi = hi.max(after.value);
         }
+        // Keep the budget line on screen.
+        if let Some(limit) = budget {
+            lo = lo.min(limit);
+            hi = hi.max(limit);
+        }
         if lo == hi {
             (lo - 1.0, hi + 1.0) // avoid zero range
         } else {
@@ -61,6 +70,7 @@ pub fn render_counter_track(
     commands.push(RenderCommand::BeginGroup {
         id: SharedStr::from(format!("counter-{}", counter.name).as_str()),
         label: Some(counter.name.clone()),
+        role: GroupRole::Lane,
     });
 
     // Background
@@ -70,6 +80,8 @@ pub fn render_counter_track(
         border_color: Some(ThemeToken::LaneBorder),
         label: None,
         frame_id: None,
+        dimmed: false,
+        span_name: None,
     });
 
     // Draw area chart as a series of filled rectangles (step chart)
@@ -97,6 +109,18 @@ pub fn render_counter_track(
             continue;
         }
 
+        if budget.is_some_and(|limit| counter.unit.exceeds_budget(sample.value, limit)) {
+            commands.push(RenderCommand::DrawRect {
+                rect: Rect::new(x, 0.0, w, height),
+                color: ThemeToken::CounterBudgetExceeded,
+                border_color: None,
+                label: None,
+                frame_id: None,
+                dimmed: false,
+                span_name: None,
+            });
+        }
+
         // Area fill
         commands.push(RenderCommand::DrawRect {
             rect: Rect::new(x, y, w, bar_height),
@@ -104,6 +128,8 @@ pub fn render_counter_track(
             border_color: None,
             label: None,
             frame_id: None,
+            dimmed: false,
+            span_name: None,
         });
 
         // Top edge line
@@ -115,6 +141,16 @@ pub fn render_counter_track(
         });
     }
 
+    if let Some(limit) = budget {
+        let y = height - (limit - min_val) * y_scale;
+        commands.push(RenderCommand::DrawLine {
+            from: Point::new(0.0, y),
+            to: Point::new(viewport.width, y),
+            color: ThemeToken::CounterBudgetLine,
+            width: 1.0,
+        });
+    }
+
     // Title label
     commands.push(RenderCommand::DrawText {
         position: Point::new(LABEL_PADDING, FONT_SIZE + LABEL_PADDING),
@@ -139,8 +175,7 @@ pub fn render_counter_track(
 }
 
 /// Format a counter value with appropriate units.
-fn format_counter_value(value: f64, unit: &flame_cat_protocol::CounterUnit) -> String {
-    use flame_cat_protocol::CounterUnit;
+pub(crate) fn format_counter_value(value: f64, unit: &CounterUnit) -> String {
     match unit {
         CounterUnit::Bytes => {
             if value >= 1_073_741_824.0 {
@@ -170,6 +205,15 @@ fn format_counter_value(value: f64, unit: &flame_cat_protocol::CounterUnit) -> S
                 format!("{:.1}ms", value)
             }
         }
+        CounterUnit::Milliwatts => {
+            if value.abs() >= 1000.0 {
+                format!("{:.2} W", value / 1000.0)
+            } else {
+                format!("{:.0} mW", value)
+            }
+        }
+        CounterUnit::Celsius => format!("{:.1} °C", value),
+        CounterUnit::BatteryPercent => format!("{:.0}%", value),
         CounterUnit::Count | CounterUnit::None => {
             if value >= 1_000_000.0 {
                 format!("{:.1}M", value / 1_000_000.0)
@@ -185,7 +229,7 @@ fn format_counter_value(value: f64, unit: &flame_cat_protocol::CounterUnit) -> S
 #[cfg(test)]
 mod tests {
     use super::*;
-    use flame_cat_protocol::{CounterSample, CounterUnit};
+    use flame_cat_protocol::CounterSample;
 
     #[test]
     fn renders_counter_area_chart() {
@@ -214,7 +258,7 @@ mod tests {
             height: 60.0,
             dpr: 1.0,
         };
-        let cmds = render_counter_track(&counter, &vp, 0.0, 100.0);
+        let cmds = render_counter_track(&counter, &vp, 0.0, 100.0, None);
 
         // Should have: BeginGroup, Background, 3 area rects, 3 lines, 2 texts, EndGroup
         assert!(!cmds.is_empty());
@@ -239,10 +283,71 @@ mod tests {
             height: 60.0,
             dpr: 1.0,
         };
-        let cmds = render_counter_track(&counter, &vp, 0.0, 100.0);
+        let cmds = render_counter_track(&counter, &vp, 0.0, 100.0, None);
         assert!(cmds.is_empty());
     }
 
+    #[test]
+    fn shades_samples_over_budget() {
+        let counter = CounterTrack {
+            name: "power.rails.cpu".into(),
+            unit: CounterUnit::Milliwatts,
+            samples: [(0.0, 500.0), (25.0, 1500.0), (50.0, 800.0), (75.0, 2000.0)]
+                .into_iter()
+                .map(|(ts, value)| CounterSample { ts, value })
+                .collect(),
+        };
+        let vp = Viewport {
+            x: 0.0,
+            y: 0.0,
+            width: 100.0,
+            height: 60.0,
+            dpr: 1.0,
+        };
+        let shaded = |cmds: &[RenderCommand]| -> Vec<f64> {
+            cmds.iter()
+                .filter_map(|c| match c {
+                    RenderCommand::DrawRect {
+                        rect,
+                        color: ThemeToken::CounterBudgetExceeded,
+                        ..
+                    } => Some(rect.x),
+                    _ => None,
+                })
+                .collect()
+        };
+        let cmds = render_counter_track(&counter, &vp, 0.0, 100.0, Some(1000.0));
+        assert_eq!(shaded(&cmds), [25.0, 75.0]);
+        assert!(cmds.iter().any(|c| matches!(
+            c,
+            RenderCommand::DrawLine {
+                color: ThemeToken::CounterBudgetLine,
+                ..
+            }
+        )));
+        assert!(shaded(&render_counter_track(&counter, &vp, 0.0, 100.0, None)).is_empty());
+
+        // Battery charge breaks its budget by dropping below it.
+        assert!(CounterUnit::BatteryPercent.exceeds_budget(15.0, 20.0));
+        assert!(!CounterUnit::Celsius.exceeds_budget(15.0, 20.0));
+    }
+
+    #[test]
+    fn format_power_and_temperature() {
+        assert_eq!(
+            format_counter_value(850.0, &CounterUnit::Milliwatts),
+            "850 mW"
+        );
+        assert_eq!(
+            format_counter_value(2_500.0, &CounterUnit::Milliwatts),
+            "2.50 W"
+        );
+        assert_eq!(
+            format_counter_value(41.26, &CounterUnit::Celsius),
+            "41.3 °C"
+        );
+    }
+
     #[test]
     fn format_bytes() {
         assert_eq!(format_counter_value(500.0, &CounterUnit::Bytes), "500 B");
@@ -1,6 +1,10 @@
-use flame_cat_protocol::{CpuSamples, Rect, RenderCommand, SharedStr, ThemeToken, Viewport};
+use flame_cat_protocol::{
+    CpuSamples, GroupRole, Rect, RenderCommand, SharedStr, ThemeToken, Viewport,
+};
 use std::collections::HashMap;
 
+use crate::transform::{DEFAULT_GAP_FACTOR, SampleGaps};
+
 const ROW_HEIGHT: f64 = 18.0;
 const ROW_GAP: f64 = 1.0;
 
@@ -30,9 +34,43 @@ fn build_stack(
     stack
 }
 
+/// Function names of the sample running at `ts`, leaf first, as in a
+/// debugger's call stack. Empty before the first sample, past the last one
+/// or in a gap where the profiler was not sampling.
+pub fn stack_at(samples: &CpuSamples, ts: f64) -> Vec<SharedStr> {
+    let i = samples.timestamps.partition_point(|&t| t <= ts);
+    let Some(i) = i.checked_sub(1) else {
+        return Vec::new();
+    };
+    let gaps = SampleGaps::for_samples(
+        &samples.timestamps,
+        samples.sample_interval_us,
+        DEFAULT_GAP_FACTOR,
+    );
+    let start = samples.timestamps[i];
+    let end = match samples.timestamps.get(i + 1) {
+        Some(&next) => gaps.map_or(next, |g| g.sample_end(start, next)),
+        None => start + gaps.map_or(1000.0, |g| g.interval),
+    };
+    let Some(&node_id) = samples.samples.get(i).filter(|_| ts < end) else {
+        return Vec::new();
+    };
+    let node_map: HashMap<u32, (Option<u32>, SharedStr)> = samples
+        .nodes
+        .iter()
+        .map(|n| (n.id, (n.parent, n.function_name.clone())))
+        .collect();
+    build_stack(node_id, &node_map)
+        .into_iter()
+        .rev()
+        .map(|(_, name)| name)
+        .collect()
+}
+
 /// Render CPU samples as a flame chart.
 ///
-/// Consecutive samples with the same leaf node are merged into bars.
+/// Consecutive samples with the same leaf node are merged into bars, except
+/// across gaps much longer than the sampling interval (see [`SampleGaps`]).
 /// Each stack frame depth gets its own row, with the deepest frame at top.
 pub fn render_cpu_samples(
     samples: &CpuSamples,
@@ -61,6 +99,11 @@ pub fn render_cpu_samples(
         node_id: u32,
     }
 
+    let gaps = SampleGaps::for_samples(
+        &samples.timestamps,
+        samples.sample_interval_us,
+        DEFAULT_GAP_FACTOR,
+    );
     let mut runs: Vec<Run> = Vec::new();
     for (i, &node_id) in samples.samples.iter().enumerate() {
         let ts = samples.timestamps[i];
@@ -68,10 +111,9 @@ pub fn render_cpu_samples(
             break;
         }
         // Estimate sample end from next sample timestamp
-        let next_ts = if i + 1 < samples.timestamps.len() {
-            samples.timestamps[i + 1]
-        } else {
-            ts + 1000.0 // 1ms fallback
+        let next_ts = match samples.timestamps.get(i + 1) {
+            Some(&next) => gaps.map_or(next, |g| g.sample_end(ts, next)),
+            None => ts + gaps.map_or(1000.0, |g| g.interval), // 1ms fallback
         };
         if next_ts < view_start {
             continue;
@@ -79,6 +121,7 @@ pub fn render_cpu_samples(
 
         if let Some(last) = runs.last_mut()
             && last.node_id == node_id
+            && last.end >= ts
         {
             last.end = next_ts;
             continue;
@@ -95,6 +138,7 @@ pub fn render_cpu_samples(
     commands.push(RenderCommand::BeginGroup {
         id: "cpu-samples".into(),
         label: Some("CPU Samples".into()),
+        role: GroupRole::Lane,
     });
 
     // Background
@@ -104,6 +148,8 @@ pub fn render_cpu_samples(
         border_color: Some(ThemeToken::LaneBorder),
         label: None,
         frame_id: None,
+        dimmed: false,
+        span_name: None,
     });
 
     // Color palette for depth
@@ -143,6 +189,8 @@ pub fn render_cpu_samples(
                 border_color: Some(ThemeToken::LaneBorder),
                 label: Some(name.clone()),
                 frame_id: None,
+                dimmed: false,
+                span_name: None,
             });
         }
     }
@@ -164,22 +212,26 @@ mod tests {
                     parent: None,
                     function_name: "(root)".into(),
                     script_id: 0,
+                    source: None,
                 },
                 CpuNode {
                     id: 2,
                     parent: Some(1),
                     function_name: "main".into(),
                     script_id: 1,
+                    source: None,
                 },
                 CpuNode {
                     id: 3,
                     parent: Some(2),
                     function_name: "compute".into(),
                     script_id: 1,
+                    source: None,
                 },
             ],
             samples: vec![2, 3, 3, 2],
             timestamps: vec![0.0, 1000.0, 2000.0, 3000.0],
+            sample_interval_us: None,
         }
     }
 
@@ -228,12 +280,28 @@ mod tests {
         assert_eq!(labeled_rects.len(), 4);
     }
 
+    #[test]
+    fn reconstructs_stack_at_time() {
+        let samples = test_samples();
+        let names = |ts| {
+            stack_at(&samples, ts)
+                .iter()
+                .map(|n| n.to_string())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(names(1_500.0), ["compute", "main"]);
+        assert_eq!(names(500.0), ["main"]);
+        assert!(names(-1.0).is_empty());
+        assert!(names(9_000.0).is_empty());
+    }
+
     #[test]
     fn empty_samples_returns_empty() {
         let samples = CpuSamples {
             nodes: vec![],
             samples: vec![],
             timestamps: vec![],
+            sample_interval_us: None,
         };
         let vp = Viewport {
             x: 0.0,
@@ -1,5 +1,5 @@
 use flame_cat_protocol::{
-    FrameTiming, Point, Rect, RenderCommand, SharedStr, TextAlign, ThemeToken, Viewport,
+    FrameTiming, GroupRole, Point, Rect, RenderCommand, SharedStr, TextAlign, ThemeToken, Viewport,
 };
 
 const FRAME_TRACK_HEIGHT: f64 = 30.0;
@@ -44,6 +44,7 @@ pub fn render_frame_track(
     commands.push(RenderCommand::BeginGroup {
         id: "frames".into(),
         label: Some("Frame Cost".into()),
+        role: GroupRole::Lane,
     });
 
     // Background
@@ -53,6 +54,8 @@ pub fn render_frame_track(
         border_color: Some(ThemeToken::LaneBorder),
         label: None,
         frame_id: None,
+        dimmed: false,
+        span_name: None,
     });
 
     // 60fps budget line
@@ -91,6 +94,8 @@ pub fn render_frame_track(
             border_color: None,
             label: None,
             frame_id: None,
+            dimmed: false,
+            span_name: None,
         });
 
         // Duration label on wide frames
@@ -0,0 +1,172 @@
+//! Heat coloring: span rects colored by duration or self time.
+//!
+//! Applied to time-order output after layout, replacing each span rect's
+//! depth token with a [`ThemeToken::Heat`] level so every host shows the
+//! same ramp.
+
+use std::collections::{HashMap, HashSet};
+
+use flame_cat_protocol::{ColorMode, HeatScale, RenderCommand, Span, ThemeToken, VisualProfile};
+
+/// Decades of the absolute scale, from 1µs to 1s.
+const ABSOLUTE_DECADES: f64 = 6.0;
+
+/// Value of `span` a heat mode colors by.
+fn heat_value(span: &Span, mode: ColorMode) -> f64 {
+    match mode {
+        ColorMode::BySelfTime => span.self_value,
+        _ => span.duration(),
+    }
+}
+
+/// Recolor every span rect in `commands` by the heat `mode` on `scale`.
+/// No-op unless `mode` is a heat mode.
+pub fn apply_heat(
+    commands: &mut [RenderCommand],
+    profile: &VisualProfile,
+    mode: ColorMode,
+    scale: HeatScale,
+) {
+    if !mode.is_heat() {
+        return;
+    }
+    let wanted: HashSet<u64> = commands
+        .iter()
+        .filter_map(|cmd| match cmd {
+            RenderCommand::DrawRect {
+                frame_id: Some(id), ..
+            } => Some(*id),
+            _ => None,
+        })
+        .collect();
+    if wanted.is_empty() {
+        return;
+    }
+
+    let mut levels: HashMap<u64, f64> = HashMap::with_capacity(wanted.len());
+    match scale {
+        HeatScale::Siblings => {
+            // Largest value per (thread, parent): roots compare with the
+            // other roots of their thread.
+            for thread in &profile.threads {
+                let mut max: HashMap<Option<u64>, f64> = HashMap::new();
+                for span in &thread.spans {
+                    let m = max.entry(span.parent).or_default();
+                    *m = m.max(heat_value(span, mode));
+                }
+                for span in thread.spans.iter().filter(|s| wanted.contains(&s.id)) {
+                    let m = max.get(&span.parent).copied().unwrap_or_default();
+                    let t = if m > 0.0 {
+                        heat_value(span, mode) / m
+                    } else {
+                        0.0
+                    };
+                    levels.insert(span.id, t);
+                }
+            }
+        }
+        HeatScale::Absolute => {
+            let factor = profile.meta.value_unit.to_microseconds_factor();
+            let total = profile.meta.total_value;
+            for span in profile.all_spans().filter(|s| wanted.contains(&s.id)) {
+                let value = heat_value(span, mode);
+                let t = match factor {
+                    Some(factor) => (value * factor).max(1.0).log10() / ABSOLUTE_DECADES,
+                    None if total > 0.0 => value.max(0.0).ln_1p() / total.ln_1p(),
+                    None => 0.0,
+                };
+                levels.insert(span.id, t);
+            }
+        }
+    }
+
+    for cmd in commands {
+        if let RenderCommand::DrawRect {
+            frame_id: Some(id),
+            color,
+            ..
+        } = cmd
+            && let Some(&t) = levels.get(id)
+        {
+            *color = ThemeToken::Heat((t.clamp(0.0, 1.0) * 255.0).round() as u8);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use flame_cat_protocol::{Rect, ValueUnit};
+
+    use super::*;
+    use crate::testing::fixtures::{profile, span, thread};
+
+    fn levels(mode: ColorMode, scale: HeatScale) -> Vec<ThemeToken> {
+        let profile = profile(
+            ValueUnit::Milliseconds,
+            vec![thread(
+                0,
+                vec![
+                    span(1, "f", 0.0, 1_000.0, None),
+                    span(2, "f", 0.0, 100.0, Some(1)),
+                    span(3, "f", 100.0, 400.0, Some(1)),
+                ],
+            )],
+        );
+        let mut commands: Vec<RenderCommand> = (1..=3)
+            .map(|id| RenderCommand::DrawRect {
+                rect: Rect::new(0.0, 0.0, 1.0, 1.0),
+                color: ThemeToken::FlameHot,
+                border_color: None,
+                label: None,
+                frame_id: Some(id),
+                dimmed: false,
+                span_name: None,
+            })
+            .collect();
+        apply_heat(&mut commands, &profile, mode, scale);
+        commands
+            .iter()
+            .filter_map(|cmd| match cmd {
+                RenderCommand::DrawRect { color, .. } => Some(*color),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn scales_against_siblings() {
+        assert_eq!(
+            levels(ColorMode::ByDuration, HeatScale::Siblings),
+            [
+                ThemeToken::Heat(255),
+                ThemeToken::Heat(85),
+                ThemeToken::Heat(255)
+            ]
+        );
+        assert_eq!(
+            levels(ColorMode::BySelfTime, HeatScale::Siblings),
+            [
+                ThemeToken::Heat(255),
+                ThemeToken::Heat(85),
+                ThemeToken::Heat(255)
+            ]
+        );
+        assert_eq!(
+            levels(ColorMode::ByName, HeatScale::Siblings),
+            [ThemeToken::FlameHot; 3]
+        );
+    }
+
+    #[test]
+    fn absolute_scale_is_logarithmic() {
+        // 1s, 100ms and 300ms on a 1µs–1s scale.
+        assert_eq!(
+            levels(ColorMode::ByDuration, HeatScale::Absolute),
+            [
+                ThemeToken::Heat(255),
+                ThemeToken::Heat(213),
+                ThemeToken::Heat(233)
+            ]
+        );
+    }
+}
@@ -0,0 +1,282 @@
+//! Span label templates.
+//!
+//! A template such as `{name} ({dur})` rewrites the label of every rect that
+//! stands for a single span after a view has rendered, so every host draws
+//! the same enriched labels. Aggregate rows (merged stacks, ranked entries)
+//! have no single span behind them and keep their labels.
+
+use std::collections::{HashMap, HashSet};
+
+use flame_cat_protocol::{DurationFormat, RenderCommand, SharedStr, Span, VisualProfile};
+use thiserror::Error;
+
+#[derive(Debug, Error, PartialEq)]
+pub enum LabelTemplateError {
+    #[error("unknown label field {{{0}}}")]
+    UnknownField(String),
+    #[error("unclosed {{ in label template")]
+    Unclosed,
+}
+
+/// Span property a template placeholder stands for.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Field {
+    Name,
+    Duration,
+    SelfTime,
+    Category,
+    /// Full `url:line:column` source location.
+    Source,
+    /// Source location without line and column.
+    Url,
+    Depth,
+}
+
+impl Field {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "name" => Some(Self::Name),
+            "dur" => Some(Self::Duration),
+            "self" => Some(Self::SelfTime),
+            "category" => Some(Self::Category),
+            "source" => Some(Self::Source),
+            "url" => Some(Self::Url),
+            "depth" => Some(Self::Depth),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Part {
+    Text(String),
+    Field(Field),
+}
+
+/// A parsed label template.
+///
+/// Placeholders are `{name}`, `{dur}`, `{self}`, `{category}`, `{source}`,
+/// `{url}` and `{depth}`; `{{` and `}}` are literal braces. Fields a span
+/// lacks, like the URL of a span with no source location, expand to nothing.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LabelTemplate {
+    parts: Vec<Part>,
+}
+
+impl LabelTemplate {
+    pub fn parse(template: &str) -> Result<Self, LabelTemplateError> {
+        let mut parts = Vec::new();
+        let mut text = String::new();
+        let mut chars = template.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    text.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    text.push('}');
+                }
+                '{' => {
+                    let mut name = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(c) => name.push(c),
+                            None => return Err(LabelTemplateError::Unclosed),
+                        }
+                    }
+                    let field = Field::from_name(name.trim())
+                        .ok_or(LabelTemplateError::UnknownField(name))?;
+                    if !text.is_empty() {
+                        parts.push(Part::Text(std::mem::take(&mut text)));
+                    }
+                    parts.push(Part::Field(field));
+                }
+                c => text.push(c),
+            }
+        }
+        if !text.is_empty() {
+            parts.push(Part::Text(text));
+        }
+        Ok(Self { parts })
+    }
+
+    /// Label of `span` in `profile`, with times written as `format` asks.
+    pub fn format(&self, span: &Span, profile: &VisualProfile, format: &DurationFormat) -> String {
+        let unit = profile.meta.value_unit;
+        let source = span.category.as_ref().and_then(|c| c.source.as_deref());
+        let mut label = String::new();
+        for part in &self.parts {
+            match part {
+                Part::Text(text) => label.push_str(text),
+                Part::Field(Field::Name) => label.push_str(&span.name),
+                Part::Field(Field::Duration) => {
+                    label.push_str(&unit.format_value_with(span.duration(), format))
+                }
+                Part::Field(Field::SelfTime) => {
+                    label.push_str(&unit.format_value_with(span.self_value, format))
+                }
+                Part::Field(Field::Category) => {
+                    label.push_str(span.category.as_ref().map_or("", |c| &c.name));
+                }
+                Part::Field(Field::Source) => label.push_str(source.unwrap_or_default()),
+                Part::Field(Field::Url) => label.push_str(source.map_or("", strip_position)),
+                Part::Field(Field::Depth) => label.push_str(&span.depth.to_string()),
+            }
+        }
+        label
+    }
+}
+
+/// `url` of a `url:line:column` or `url:line` source location.
+fn strip_position(source: &str) -> &str {
+    let mut url = source;
+    for _ in 0..2 {
+        match url.rsplit_once(':') {
+            Some((head, tail)) if !tail.is_empty() && tail.bytes().all(|b| b.is_ascii_digit()) => {
+                url = head;
+            }
+            _ => break,
+        }
+    }
+    url
+}
+
+/// Relabel every rect that has a span behind it with `template`, keeping
+/// the span's name in `span_name` for coloring and category filters. Rows
+/// of expanded inlined functions, whose `span_name` is not the span's, keep
+/// their label.
+pub fn apply_label_template(
+    commands: &mut [RenderCommand],
+    profile: &VisualProfile,
+    template: &LabelTemplate,
+    format: &DurationFormat,
+) {
+    let wanted: HashSet<u64> = commands
+        .iter()
+        .filter_map(|cmd| match cmd {
+            RenderCommand::DrawRect {
+                label: Some(_),
+                frame_id: Some(id),
+                ..
+            } => Some(*id),
+            _ => None,
+        })
+        .collect();
+    if wanted.is_empty() {
+        return;
+    }
+    let spans: HashMap<u64, &Span> = profile
+        .all_spans()
+        .filter(|s| wanted.contains(&s.id))
+        .map(|s| (s.id, s))
+        .collect();
+    for cmd in commands {
+        if let RenderCommand::DrawRect {
+            label: Some(label),
+            frame_id: Some(id),
+            span_name,
+            ..
+        } = cmd
+            && let Some(span) = spans.get(id)
+            && span_name.as_ref().is_none_or(|name| *name == span.name)
+        {
+            *span_name = Some(span.name.clone());
+            *label = SharedStr::from(template.format(span, profile, format));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use flame_cat_protocol::{Rect, SpanCategory, ThemeToken, ValueUnit};
+
+    use super::*;
+    use crate::testing::fixtures::{self, span, thread};
+
+    fn profile() -> VisualProfile {
+        let script = Span {
+            category: Some(SpanCategory {
+                name: "devtools.timeline".into(),
+                source: Some("https://example.com/app.js:12:4".into()),
+            }),
+            ..span(7, "EvaluateScript", 0.0, 1_500.0, None)
+        };
+        fixtures::profile(
+            ValueUnit::Microseconds,
+            vec![thread(
+                0,
+                vec![script, span(8, "Compile", 500.0, 1_500.0, Some(7))],
+            )],
+        )
+    }
+
+    #[test]
+    fn expands_fields_and_escapes() {
+        let profile = profile();
+        let span = &profile.threads[0].spans[0];
+        let template =
+            LabelTemplate::parse("{name} ({dur}, self {self}) {{{url}}}").expect("valid template");
+        assert_eq!(
+            template.format(span, &profile, &DurationFormat::default()),
+            "EvaluateScript (1.5ms, self 500µs) {https://example.com/app.js}"
+        );
+        let ms = DurationFormat {
+            decimals: Some(2),
+            unit: flame_cat_protocol::DurationUnit::Milliseconds,
+            separators: flame_cat_protocol::Separators::PointComma,
+        };
+        assert_eq!(
+            template.format(span, &profile, &ms),
+            "EvaluateScript (1,50ms, self 0,50ms) {https://example.com/app.js}"
+        );
+        assert_eq!(
+            LabelTemplate::parse("{args.url}"),
+            Err(LabelTemplateError::UnknownField("args.url".into()))
+        );
+        assert_eq!(
+            LabelTemplate::parse("{name"),
+            Err(LabelTemplateError::Unclosed)
+        );
+    }
+
+    #[test]
+    fn relabels_rects_with_a_span() {
+        let profile = profile();
+        let rect = |frame_id| RenderCommand::DrawRect {
+            rect: Rect::new(0.0, 0.0, 10.0, 10.0),
+            color: ThemeToken::FlameHot,
+            border_color: None,
+            label: Some("EvaluateScript".into()),
+            frame_id,
+            dimmed: false,
+            span_name: None,
+        };
+        let mut commands = vec![rect(Some(7)), rect(None)];
+        let template = LabelTemplate::parse("{name} ({dur})").expect("valid template");
+        apply_label_template(
+            &mut commands,
+            &profile,
+            &template,
+            &DurationFormat::default(),
+        );
+        let labels: Vec<(Option<&str>, Option<&str>)> = commands
+            .iter()
+            .map(|cmd| match cmd {
+                RenderCommand::DrawRect {
+                    label, span_name, ..
+                } => (label.as_deref(), span_name.as_deref()),
+                _ => unreachable!(),
+            })
+            .collect();
+        assert_eq!(
+            labels,
+            [
+                (Some("EvaluateScript (1.5ms)"), Some("EvaluateScript")),
+                (Some("EvaluateScript"), None),
+            ]
+        );
+    }
+}
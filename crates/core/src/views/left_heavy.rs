@@ -1,7 +1,8 @@
 use std::collections::HashMap;
 
 use flame_cat_protocol::{
-    Rect, RenderCommand, SharedStr, Span, ThemeToken, Viewport, VisualProfile,
+    GroupRole, Rect, RenderCommand, RenderLimits, SharedStr, Span, ThemeToken, Viewport,
+    VisualProfile,
 };
 
 const FRAME_HEIGHT: f64 = 20.0;
@@ -20,7 +21,37 @@ pub fn render_left_heavy(
     viewport: &Viewport,
     thread_id: Option<u32>,
 ) -> Vec<RenderCommand> {
-    render_left_heavy_inner(profile, viewport, thread_id, false)
+    let mut commands = Vec::new();
+    render_left_heavy_inner(
+        &mut commands,
+        profile,
+        viewport,
+        thread_id.as_slice(),
+        &RenderLimits::default(),
+        false,
+        0.0,
+    );
+    commands
+}
+
+/// Like [`render_left_heavy`], but writes into a caller-owned buffer.
+///
+/// `commands` is cleared first and its allocation reused.
+pub fn render_left_heavy_into(
+    commands: &mut Vec<RenderCommand>,
+    profile: &VisualProfile,
+    viewport: &Viewport,
+    thread_id: Option<u32>,
+) {
+    render_left_heavy_inner(
+        commands,
+        profile,
+        viewport,
+        thread_id.as_slice(),
+        &RenderLimits::default(),
+        false,
+        0.0,
+    );
 }
 
 /// Render an inverted (icicle) view: roots at the top, callees growing downward,
@@ -30,27 +61,65 @@ pub fn render_icicle(
     viewport: &Viewport,
     thread_id: Option<u32>,
 ) -> Vec<RenderCommand> {
-    render_left_heavy_inner(profile, viewport, thread_id, true)
+    let mut commands = Vec::new();
+    render_left_heavy_inner(
+        &mut commands,
+        profile,
+        viewport,
+        thread_id.as_slice(),
+        &RenderLimits::default(),
+        true,
+        0.0,
+    );
+    commands
 }
 
-fn render_left_heavy_inner(
+/// Like [`render_icicle`], but writes into a caller-owned buffer.
+///
+/// `commands` is cleared first and its allocation reused.
+pub fn render_icicle_into(
+    commands: &mut Vec<RenderCommand>,
     profile: &VisualProfile,
     viewport: &Viewport,
     thread_id: Option<u32>,
+) {
+    render_left_heavy_inner(
+        commands,
+        profile,
+        viewport,
+        thread_id.as_slice(),
+        &RenderLimits::default(),
+        true,
+        0.0,
+    );
+}
+
+/// Merged call tree of the threads in `threads` (all when empty). Nodes past
+/// `limits` are counted into an "N more…" note instead of drawn, and
+/// subtrees under `prune_percent` of the total are collapsed (see
+/// [`prune`]).
+pub(super) fn render_left_heavy_inner(
+    commands: &mut Vec<RenderCommand>,
+    profile: &VisualProfile,
+    viewport: &Viewport,
+    threads: &[u32],
+    limits: &RenderLimits,
     inverted: bool,
-) -> Vec<RenderCommand> {
-    let spans: Vec<&Span> = if let Some(tid) = thread_id {
+    prune_percent: f64,
+) {
+    commands.clear();
+    let spans: Vec<&Span> = if threads.is_empty() {
+        profile.all_spans().collect()
+    } else {
         profile
             .threads
             .iter()
-            .filter(|t| t.id == tid)
+            .filter(|t| threads.contains(&t.id))
             .flat_map(|t| t.spans.iter())
             .collect()
-    } else {
-        profile.all_spans().collect()
     };
     if spans.is_empty() {
-        return Vec::new();
+        return;
     }
 
     // Build parent → children index for O(1) lookup
@@ -62,12 +131,21 @@ fn render_left_heavy_inner(
     let roots = merge_children(&spans, &children_index, None);
     let total_time: f64 = roots.iter().map(|n| n.total_time).sum();
     if total_time <= 0.0 {
-        return Vec::new();
+        return;
     }
+    let roots = if prune_percent > 0.0 {
+        prune(roots, total_time * prune_percent / 100.0)
+    } else {
+        roots
+    };
 
     let x_scale = viewport.width / total_time;
 
-    let max_depth = if inverted { tree_depth(&roots, 0) } else { 0 };
+    let max_depth = if inverted {
+        tree_depth(&roots, 0).min(limits.max_depth)
+    } else {
+        0
+    };
 
     let group_id = if inverted { "icicle" } else { "left-heavy" };
     let group_label = if inverted { "Icicle" } else { "Left Heavy" };
@@ -77,18 +155,21 @@ fn render_left_heavy_inner(
         viewport,
         inverted,
         max_depth,
+        limits,
     };
 
-    let mut commands = Vec::with_capacity(profile.span_count());
+    commands.reserve(spans.len().min(limits.max_commands) + 2);
     commands.push(RenderCommand::BeginGroup {
         id: group_id.into(),
         label: Some(group_label.into()),
+        role: GroupRole::View,
     });
 
-    layout_nodes(&roots, 0, 0.0, &ctx, &mut commands);
+    let mut hidden = 0;
+    layout_nodes(&roots, 0, 0.0, &ctx, commands, &mut hidden);
 
+    super::push_hidden_indicator(commands, viewport, hidden);
     commands.push(RenderCommand::EndGroup);
-    commands
 }
 
 fn merge_children(
@@ -100,54 +181,111 @@ fn merge_children(
         return Vec::new();
     };
 
-    let mut groups: HashMap<&str, (SharedStr, f64, Vec<u64>)> = HashMap::new();
+    // Spans merge with the same function and the same functions inlined
+    // into it, each of which becomes a node of its own.
+    let mut groups: HashMap<_, (f64, Vec<u64>)> = HashMap::new();
     for &idx in child_indices {
         let child = spans[idx];
         let entry = groups
-            .entry(&child.name)
-            .or_insert_with(|| (child.name.clone(), 0.0, Vec::new()));
-        entry.1 += child.duration();
-        entry.2.push(child.id);
+            .entry((&child.name, &child.inlined))
+            .or_insert_with(|| (0.0, Vec::new()));
+        entry.0 += child.duration();
+        entry.1.push(child.id);
     }
+    let inlined = groups.keys().any(|(_, chain)| !chain.is_empty());
 
     let mut nodes: Vec<MergedNode> = groups
         .into_iter()
-        .map(|(_, (name, total_time, ids))| {
+        .map(|((name, chain), (total_time, ids))| {
             let mut merged_children = Vec::new();
             for id in &ids {
                 let mut sub = merge_children(spans, children_index, Some(*id));
                 merged_children.append(&mut sub);
             }
-            let merged_children = re_merge(merged_children);
-
-            MergedNode {
-                name,
+            let mut node = MergedNode {
+                name: chain.last().unwrap_or(name).clone(),
                 total_time,
-                children: merged_children,
+                children: re_merge(merged_children),
+            };
+            for caller in std::iter::once(name.clone())
+                .chain(chain.iter().cloned())
+                .rev()
+                .skip(1)
+            {
+                node = MergedNode {
+                    name: caller,
+                    total_time,
+                    children: vec![node],
+                };
             }
+            node
         })
         .collect();
 
+    // Chains sharing their outer functions still need merging.
+    if inlined {
+        return re_merge(nodes);
+    }
     nodes.sort_by(|a, b| b.total_time.total_cmp(&a.total_time));
     nodes
 }
 
+/// Merge same-named siblings, and in turn the children of those that merged.
 fn re_merge(nodes: Vec<MergedNode>) -> Vec<MergedNode> {
-    let mut groups: HashMap<SharedStr, MergedNode> = HashMap::with_capacity(nodes.len());
+    let mut groups: HashMap<SharedStr, (MergedNode, usize)> = HashMap::with_capacity(nodes.len());
     for node in nodes {
-        let entry = groups.entry(node.name.clone()).or_insert(MergedNode {
-            name: node.name.clone(),
-            total_time: 0.0,
-            children: Vec::new(),
-        });
+        let (entry, merged) = groups.entry(node.name.clone()).or_insert((
+            MergedNode {
+                name: node.name.clone(),
+                total_time: 0.0,
+                children: Vec::new(),
+            },
+            0,
+        ));
         entry.total_time += node.total_time;
         entry.children.extend(node.children);
+        *merged += 1;
     }
-    let mut result: Vec<MergedNode> = groups.into_values().collect();
+    let mut result: Vec<MergedNode> = groups
+        .into_values()
+        .map(|(mut node, merged)| {
+            if merged > 1 {
+                node.children = re_merge(node.children);
+            }
+            node
+        })
+        .collect();
     result.sort_by(|a, b| b.total_time.total_cmp(&a.total_time));
     result
 }
 
+/// Collapse the siblings lighter than `min_time` at each level into one
+/// "other (N frames)" node after the heavier ones, N counting every frame
+/// of the collapsed subtrees. A lone light leaf is kept as it is.
+fn prune(nodes: Vec<MergedNode>, min_time: f64) -> Vec<MergedNode> {
+    let (mut kept, light): (Vec<MergedNode>, Vec<MergedNode>) =
+        nodes.into_iter().partition(|n| n.total_time >= min_time);
+    for node in &mut kept {
+        node.children = prune(std::mem::take(&mut node.children), min_time);
+    }
+    let frames = node_count(&light);
+    if frames == 1 {
+        kept.extend(light);
+    } else if frames > 1 {
+        kept.push(MergedNode {
+            name: format!("other ({frames} frames)").into(),
+            total_time: light.iter().map(|n| n.total_time).sum(),
+            children: Vec::new(),
+        });
+    }
+    kept
+}
+
+/// Number of nodes in the merged forest.
+fn node_count(nodes: &[MergedNode]) -> usize {
+    nodes.iter().map(|n| 1 + node_count(&n.children)).sum()
+}
+
 /// Compute the maximum depth of the merged tree.
 fn tree_depth(nodes: &[MergedNode], depth: u32) -> u32 {
     let mut max = depth;
@@ -162,6 +300,7 @@ struct LayoutCtx<'a> {
     viewport: &'a Viewport,
     inverted: bool,
     max_depth: u32,
+    limits: &'a RenderLimits,
 }
 
 fn layout_nodes(
@@ -170,7 +309,12 @@ fn layout_nodes(
     mut x_offset: f64,
     ctx: &LayoutCtx<'_>,
     commands: &mut Vec<RenderCommand>,
+    hidden: &mut usize,
 ) {
+    if depth > ctx.limits.max_depth {
+        *hidden += node_count(nodes);
+        return;
+    }
     let y = if ctx.inverted {
         f64::from(ctx.max_depth - depth) * FRAME_HEIGHT
     } else {
@@ -191,16 +335,22 @@ fn layout_nodes(
                 _ => ThemeToken::FlameNeutral,
             };
 
-            commands.push(RenderCommand::DrawRect {
-                rect: Rect::new(x_offset, y, w, FRAME_HEIGHT - 1.0),
-                color,
-                border_color: Some(ThemeToken::Border),
-                label: Some(node.name.clone()),
-                frame_id: None,
-            });
+            if commands.len() < ctx.limits.max_commands {
+                commands.push(RenderCommand::DrawRect {
+                    rect: Rect::new(x_offset, y, w, FRAME_HEIGHT - 1.0),
+                    color,
+                    border_color: Some(ThemeToken::Border),
+                    label: Some(node.name.clone()),
+                    frame_id: None,
+                    dimmed: false,
+                    span_name: None,
+                });
+            } else {
+                *hidden += 1;
+            }
         }
 
-        layout_nodes(&node.children, depth + 1, x_offset, ctx, commands);
+        layout_nodes(&node.children, depth + 1, x_offset, ctx, commands, hidden);
         x_offset += w;
     }
 }
@@ -221,10 +371,18 @@ mod tests {
                 start_time: 0.0,
                 end_time: 100.0,
                 time_domain: None,
+                truncated_spans: 0,
+                repairs: Default::default(),
+                limits_hit: Default::default(),
+                partial: None,
+                sample_interval_us: None,
+                description: None,
+                tags: vec![],
             },
             threads: vec![ThreadGroup {
                 id: 0,
                 name: "Main".into(),
+                kind: Default::default(),
                 sort_key: 0,
                 max_depth: 0,
                 spans: vec![
@@ -238,6 +396,9 @@ mod tests {
                         self_value: 50.0,
                         kind: SpanKind::Event,
                         category: None,
+                        truncated: false,
+                        binary: None,
+                        inlined: vec![],
                     },
                     Span {
                         id: 1,
@@ -249,8 +410,14 @@ mod tests {
                         self_value: 50.0,
                         kind: SpanKind::Event,
                         category: None,
+                        truncated: false,
+                        binary: None,
+                        inlined: vec![],
                     },
                 ],
+                pid: None,
+                tid: None,
+                process_name: None,
             }],
             frames: vec![],
             counters: vec![],
@@ -262,6 +429,7 @@ mod tests {
             cpu_samples: None,
             network_requests: vec![],
             screenshots: vec![],
+            binaries: vec![],
         };
         let vp = Viewport {
             x: 0.0,
@@ -279,6 +447,112 @@ mod tests {
         assert_eq!(rects.len(), 1);
     }
 
+    #[test]
+    fn prunes_light_subtrees_into_other() {
+        let span = |id, name: &str, start, end, parent: Option<u64>| Span {
+            id,
+            name: name.into(),
+            start,
+            end,
+            depth: u32::from(parent.is_some()),
+            parent,
+            self_value: 0.0,
+            kind: SpanKind::Event,
+            category: None,
+            truncated: false,
+            binary: None,
+            inlined: vec![],
+        };
+        let mut profile = crate::testing::synth::generate(&crate::testing::synth::SynthConfig {
+            threads: 1,
+            spans_per_thread: 0,
+            ..Default::default()
+        });
+        profile.threads[0].spans = vec![
+            span(0, "main", 0.0, 100.0, None),
+            span(1, "a", 0.0, 90.0, Some(0)),
+            span(2, "b", 90.0, 94.0, Some(0)),
+            span(3, "c", 94.0, 97.0, Some(0)),
+            span(4, "d", 97.0, 100.0, Some(0)),
+        ];
+        let vp = Viewport {
+            x: 0.0,
+            y: 0.0,
+            width: 1000.0,
+            height: 600.0,
+            dpr: 1.0,
+        };
+        let labels = |prune_percent| {
+            let mut commands = Vec::new();
+            render_left_heavy_inner(
+                &mut commands,
+                &profile,
+                &vp,
+                &[],
+                &RenderLimits::default(),
+                false,
+                prune_percent,
+            );
+            commands
+                .iter()
+                .filter_map(|c| match c {
+                    RenderCommand::DrawRect {
+                        label: Some(l),
+                        rect,
+                        ..
+                    } => Some((l.to_string(), rect.w)),
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(labels(0.0).len(), 5);
+        assert_eq!(
+            labels(5.0),
+            [
+                ("main".to_string(), 1000.0),
+                ("a".to_string(), 900.0),
+                ("other (3 frames)".to_string(), 100.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn inlined_functions_become_merged_nodes() {
+        let profile = crate::parsers::collapsed::parse_collapsed(
+            b"main;run;push_[i];alloc 3\nmain;run 1\nmain;run;push_[i];grow_[i] 4\n",
+        )
+        .unwrap()
+        .into_visual_profile();
+        let vp = Viewport {
+            x: 0.0,
+            y: 0.0,
+            width: 800.0,
+            height: 600.0,
+            dpr: 1.0,
+        };
+        let rows: Vec<(String, f64, f64)> = render_left_heavy(&profile, &vp, None)
+            .iter()
+            .filter_map(|c| match c {
+                RenderCommand::DrawRect {
+                    label: Some(l),
+                    rect,
+                    ..
+                } => Some((l.to_string(), rect.y, rect.w)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            rows,
+            [
+                ("main".into(), 0.0, 800.0),
+                ("run".into(), 20.0, 800.0),
+                ("push".into(), 40.0, 700.0),
+                ("grow".into(), 60.0, 400.0),
+                ("alloc".into(), 60.0, 300.0),
+            ]
+        );
+    }
+
     #[test]
     fn empty_profile_returns_empty() {
         let profile = VisualProfile {
@@ -290,6 +564,13 @@ mod tests {
                 start_time: 0.0,
                 end_time: 0.0,
                 time_domain: None,
+                truncated_spans: 0,
+                repairs: Default::default(),
+                limits_hit: Default::default(),
+                partial: None,
+                sample_interval_us: None,
+                description: None,
+                tags: vec![],
             },
             threads: vec![],
             frames: vec![],
@@ -302,6 +583,7 @@ mod tests {
             cpu_samples: None,
             network_requests: vec![],
             screenshots: vec![],
+            binaries: vec![],
         };
         let vp = Viewport {
             x: 0.0,
@@ -1,4 +1,6 @@
-use flame_cat_protocol::{Marker, Point, RenderCommand, TextAlign, ThemeToken, Viewport};
+use flame_cat_protocol::{
+    GroupRole, Marker, Point, RenderCommand, TextAlign, ThemeToken, Viewport,
+};
 
 const FONT_SIZE: f64 = 10.0;
 const LABEL_OFFSET_Y: f64 = 12.0;
@@ -24,6 +26,7 @@ pub fn render_markers(
     commands.push(RenderCommand::BeginGroup {
         id: "markers".into(),
         label: Some("Markers".into()),
+        role: GroupRole::Lane,
     });
 
     // Track label positions to avoid overlap
@@ -1,4 +1,9 @@
-use flame_cat_protocol::{Point, Rect, RenderCommand, ThemeToken, Viewport, VisualProfile};
+use std::collections::BTreeMap;
+
+use flame_cat_protocol::{
+    GroupRole, Point, Rect, RenderCommand, ThemeToken, ThreadGroup, Viewport, VisualProfile,
+};
+use serde::Serialize;
 
 const ROW_HEIGHT: f64 = 4.0;
 const CELL_WIDTH: f64 = 4.0;
@@ -31,6 +36,7 @@ pub fn render_minimap(
     commands.push(RenderCommand::BeginGroup {
         id: "minimap".into(),
         label: Some("Minimap".into()),
+        role: GroupRole::View,
     });
 
     // Background
@@ -40,6 +46,8 @@ pub fn render_minimap(
         border_color: None,
         label: None,
         frame_id: None,
+        dimmed: false,
+        span_name: None,
     });
 
     // Build density grid: rows = depth, cols = time buckets
@@ -121,6 +129,8 @@ pub fn render_minimap(
                 border_color: None,
                 label: None,
                 frame_id: None,
+                dimmed: false,
+                span_name: None,
             });
 
             // If high density, overlay with the hot color
@@ -131,6 +141,8 @@ pub fn render_minimap(
                     border_color: None,
                     label: None,
                     frame_id: None,
+                    dimmed: false,
+                    span_name: None,
                 });
             }
         }
@@ -148,6 +160,8 @@ pub fn render_minimap(
             border_color: None,
             label: None,
             frame_id: None,
+            dimmed: false,
+            span_name: None,
         });
     }
 
@@ -160,6 +174,8 @@ pub fn render_minimap(
             border_color: None,
             label: None,
             frame_id: None,
+            dimmed: false,
+            span_name: None,
         });
     }
 
@@ -170,6 +186,8 @@ pub fn render_minimap(
         border_color: Some(ThemeToken::Border),
         label: None,
         frame_id: None,
+        dimmed: false,
+        span_name: None,
     });
 
     // Left handle
@@ -192,6 +210,243 @@ pub fn render_minimap(
     commands
 }
 
+/// How close (px) the pointer must be to a viewport edge to grab its handle.
+const HANDLE_GRAB_PX: f64 = 2.0 * HANDLE_WIDTH;
+/// Narrowest viewport, as a fraction of the profile, that resizing leaves.
+const MIN_VISIBLE_FRAC: f64 = 0.001;
+
+/// Part of the minimap under the pointer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MinimapHit {
+    LeftHandle,
+    RightHandle,
+    /// Inside the viewport, away from its handles.
+    Viewport,
+    /// Outside the viewport, where a drag draws a new range.
+    Outside,
+}
+
+impl MinimapHit {
+    /// Name used by the JS API.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::LeftHandle => "left_handle",
+            Self::RightHandle => "right_handle",
+            Self::Viewport => "viewport",
+            Self::Outside => "outside",
+        }
+    }
+}
+
+/// What is under `x` (px from the left edge) on a minimap `width` px wide
+/// showing the viewport `visible_start..visible_end` (fractions of the
+/// profile). The nearer handle wins when a narrow viewport puts both in
+/// reach.
+pub fn minimap_hit_test(x: f64, width: f64, visible_start: f64, visible_end: f64) -> MinimapHit {
+    let to_left = (x - visible_start * width).abs();
+    let to_right = (x - visible_end * width).abs();
+    if to_left.min(to_right) < HANDLE_GRAB_PX {
+        if to_left <= to_right {
+            MinimapHit::LeftHandle
+        } else {
+            MinimapHit::RightHandle
+        }
+    } else if (visible_start * width..=visible_end * width).contains(&x) {
+        MinimapHit::Viewport
+    } else {
+        MinimapHit::Outside
+    }
+}
+
+/// A press-drag-release gesture on the minimap, fixed by where it started.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MinimapDrag {
+    /// Moving the viewport, grabbed `offset` (fraction) right of its start.
+    Pan {
+        offset: f64,
+    },
+    ResizeStart,
+    ResizeEnd,
+    /// Drawing a new range from `anchor` (fraction).
+    Create {
+        anchor: f64,
+    },
+}
+
+impl MinimapDrag {
+    /// The gesture a press at `x` starts; arguments as for
+    /// [`minimap_hit_test`].
+    pub fn begin(x: f64, width: f64, visible_start: f64, visible_end: f64) -> Self {
+        let frac = (x / width).clamp(0.0, 1.0);
+        match minimap_hit_test(x, width, visible_start, visible_end) {
+            MinimapHit::LeftHandle => Self::ResizeStart,
+            MinimapHit::RightHandle => Self::ResizeEnd,
+            MinimapHit::Viewport => Self::Pan {
+                offset: frac - visible_start,
+            },
+            MinimapHit::Outside => Self::Create { anchor: frac },
+        }
+    }
+
+    /// Visible range once the pointer has moved to `x`, starting from the
+    /// viewport `visible_start..visible_end`. For [`MinimapDrag::Create`]
+    /// this is the range drawn so far, which may be empty.
+    pub fn update(self, x: f64, width: f64, visible_start: f64, visible_end: f64) -> (f64, f64) {
+        let frac = (x / width).clamp(0.0, 1.0);
+        match self {
+            Self::Pan { offset } => {
+                let span = visible_end - visible_start;
+                let start = (frac - offset).clamp(0.0, 1.0 - span);
+                (start, start + span)
+            }
+            Self::ResizeStart => (frac.min(visible_end - MIN_VISIBLE_FRAC), visible_end),
+            Self::ResizeEnd => (visible_start, frac.max(visible_start + MIN_VISIBLE_FRAC)),
+            Self::Create { anchor } => (anchor.min(frac), anchor.max(frac)),
+        }
+    }
+}
+
+/// Finest resolution of a [`DensityPyramid`], in time buckets.
+const PYRAMID_BASE_BINS: usize = 4096;
+/// Minimum pyramid bins per sampled column.
+const PYRAMID_OVERSAMPLE: usize = 8;
+
+/// Multi-resolution span-density histogram over a profile's time range.
+///
+/// Built once per profile; [`DensityPyramid::sample`] then resamples it to
+/// any column count without touching the spans again, so resizing the
+/// minimap costs O(bins) instead of O(spans).
+#[derive(Debug, Clone)]
+pub struct DensityPyramid {
+    /// `levels[0]` is the finest; each next level halves the bin count,
+    /// keeping the peak of each pair so short bursts survive downsampling.
+    levels: Vec<Vec<u32>>,
+}
+
+impl DensityPyramid {
+    /// Count overlapping spans per time bucket across all threads.
+    pub fn build(profile: &VisualProfile) -> Self {
+        Self::from_intervals(
+            profile.meta.start_time,
+            profile.meta.end_time,
+            profile.all_spans().map(|s| (s.start, s.end)),
+        )
+    }
+
+    /// Count overlapping spans of one thread, over the whole profile's time
+    /// range so that the pyramids of different threads line up.
+    pub fn build_thread(profile: &VisualProfile, thread: &ThreadGroup) -> Self {
+        Self::from_intervals(
+            profile.meta.start_time,
+            profile.meta.end_time,
+            thread.spans.iter().map(|s| (s.start, s.end)),
+        )
+    }
+
+    /// Build from raw `(start, end)` intervals within `[start, end]`.
+    pub fn from_intervals(
+        start: f64,
+        end: f64,
+        intervals: impl IntoIterator<Item = (f64, f64)>,
+    ) -> Self {
+        let bins = PYRAMID_BASE_BINS;
+        let duration = end - start;
+        if duration <= 0.0 {
+            return Self {
+                levels: vec![vec![0; bins]],
+            };
+        }
+        let bin_dur = duration / bins as f64;
+
+        // Difference array: +1 where an interval enters, -1 past where it leaves.
+        let mut diff = vec![0i64; bins + 1];
+        for (s, e) in intervals {
+            let rel_start = (s - start) / bin_dur;
+            let rel_end = (e - start) / bin_dur;
+            if rel_end < 0.0 || rel_start >= bins as f64 {
+                continue;
+            }
+            let c0 = (rel_start.max(0.0) as usize).min(bins);
+            let c1 = (rel_end.ceil() as usize).min(bins);
+            if c0 < c1 {
+                diff[c0] += 1;
+                diff[c1] -= 1;
+            }
+        }
+        let mut running = 0i64;
+        let base: Vec<u32> = diff[..bins]
+            .iter()
+            .map(|d| {
+                running += d;
+                u32::try_from(running.max(0)).unwrap_or(u32::MAX)
+            })
+            .collect();
+
+        let mut levels = vec![base];
+        while let Some(prev) = levels.last()
+            && prev.len() > 1
+        {
+            let next = prev
+                .chunks(2)
+                .map(|pair| pair.iter().copied().max().unwrap_or(0))
+                .collect();
+            levels.push(next);
+        }
+        Self { levels }
+    }
+
+    /// Peak density across the whole profile.
+    pub fn max(&self) -> u32 {
+        self.levels
+            .last()
+            .and_then(|l| l.first())
+            .copied()
+            .unwrap_or(0)
+    }
+
+    /// Resample to `cols` columns, taking the peak of the bins under each.
+    pub fn sample(&self, cols: usize) -> Vec<u32> {
+        if cols == 0 {
+            return Vec::new();
+        }
+        // Coarsest level that still has several bins per column, so that
+        // column edges don't bleed a whole coarse bin into their neighbours.
+        let level = self
+            .levels
+            .iter()
+            .rev()
+            .find(|l| l.len() >= cols.saturating_mul(PYRAMID_OVERSAMPLE))
+            .unwrap_or(&self.levels[0]);
+        let bins = level.len();
+        (0..cols)
+            .map(|c| {
+                let b0 = c * bins / cols;
+                let b1 = ((c + 1) * bins).div_ceil(cols).clamp(b0 + 1, bins);
+                level[b0.min(bins - 1)..b1]
+                    .iter()
+                    .copied()
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect()
+    }
+}
+
+/// Columns of a lane thumbnail in the sidebar.
+pub const THUMBNAIL_COLS: usize = 48;
+
+/// A `cols` wide density strip per thread, keyed by thread id, each
+/// sampled from that thread's own [`DensityPyramid`]. Cheap enough to keep
+/// per profile, unlike the pyramids themselves.
+pub fn lane_thumbnails(profile: &VisualProfile, cols: usize) -> BTreeMap<u32, Vec<u32>> {
+    profile
+        .threads
+        .iter()
+        .map(|t| (t.id, DensityPyramid::build_thread(profile, t).sample(cols)))
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -208,10 +463,18 @@ mod tests {
                 start_time: 0.0,
                 end_time: 100.0,
                 time_domain: None,
+                truncated_spans: 0,
+                repairs: Default::default(),
+                limits_hit: Default::default(),
+                partial: None,
+                sample_interval_us: None,
+                description: None,
+                tags: vec![],
             },
             threads: vec![ThreadGroup {
                 id: 0,
                 name: "Main".into(),
+                kind: Default::default(),
                 sort_key: 0,
                 max_depth: 0,
                 spans: vec![Span {
@@ -224,7 +487,13 @@ mod tests {
                     self_value: 100.0,
                     kind: SpanKind::Event,
                     category: None,
+                    truncated: false,
+                    binary: None,
+                    inlined: vec![],
                 }],
+                pid: None,
+                tid: None,
+                process_name: None,
             }],
             frames: vec![],
             counters: vec![],
@@ -236,6 +505,7 @@ mod tests {
             cpu_samples: None,
             network_requests: vec![],
             screenshots: vec![],
+            binaries: vec![],
         };
         let vp = Viewport {
             x: 0.0,
@@ -252,4 +522,117 @@ mod tests {
         // Background + frame + viewport indicator
         assert!(rects.len() >= 3);
     }
+
+    #[test]
+    fn pyramid_counts_overlaps() {
+        let pyramid =
+            DensityPyramid::from_intervals(0.0, 100.0, [(0.0, 100.0), (0.0, 50.0), (12.0, 18.0)]);
+        assert_eq!(pyramid.max(), 3);
+        let cols = pyramid.sample(10);
+        assert_eq!(cols, vec![2, 3, 2, 2, 2, 1, 1, 1, 1, 1]);
+    }
+
+    #[test]
+    fn pyramid_samples_any_width() {
+        let pyramid = DensityPyramid::from_intervals(0.0, 100.0, [(99.0, 100.0)]);
+        for cols in [1, 3, 640, 1921, 5000] {
+            let d = pyramid.sample(cols);
+            assert_eq!(d.len(), cols);
+            assert_eq!(d.last().copied(), Some(1), "burst lost at {cols} columns");
+        }
+        assert!(pyramid.sample(0).is_empty());
+    }
+
+    #[test]
+    fn pyramid_empty_range() {
+        let pyramid = DensityPyramid::from_intervals(5.0, 5.0, [(5.0, 5.0)]);
+        assert_eq!(pyramid.max(), 0);
+        assert_eq!(pyramid.sample(4), vec![0; 4]);
+    }
+
+    #[test]
+    fn thumbnails_share_the_profile_time_range() {
+        let mut profile = VisualProfile {
+            meta: ProfileMeta {
+                name: None,
+                source_format: SourceFormat::Unknown,
+                value_unit: ValueUnit::Microseconds,
+                total_value: 100.0,
+                start_time: 0.0,
+                end_time: 100.0,
+                time_domain: None,
+                truncated_spans: 0,
+                repairs: Default::default(),
+                limits_hit: Default::default(),
+                partial: None,
+                sample_interval_us: None,
+                description: None,
+                tags: vec![],
+            },
+            threads: vec![ThreadGroup {
+                id: 0,
+                name: "Main".into(),
+                kind: Default::default(),
+                sort_key: 0,
+                max_depth: 0,
+                spans: vec![Span {
+                    id: 0,
+                    name: "main".into(),
+                    start: 0.0,
+                    end: 100.0,
+                    depth: 0,
+                    parent: None,
+                    self_value: 100.0,
+                    kind: SpanKind::Event,
+                    category: None,
+                    truncated: false,
+                    binary: None,
+                    inlined: vec![],
+                }],
+                pid: None,
+                tid: None,
+                process_name: None,
+            }],
+            frames: vec![],
+            counters: vec![],
+            async_spans: vec![],
+            flow_arrows: vec![],
+            markers: vec![],
+            instant_events: vec![],
+            object_events: vec![],
+            cpu_samples: None,
+            network_requests: vec![],
+            screenshots: vec![],
+            binaries: vec![],
+        };
+        let mut late = profile.threads[0].clone();
+        late.id = 1;
+        late.spans[0].start = 75.0;
+        profile.threads.push(late);
+        let thumbs = lane_thumbnails(&profile, 4);
+        assert_eq!(thumbs[&0], vec![1, 1, 1, 1]);
+        assert_eq!(thumbs[&1], vec![0, 0, 0, 1]);
+    }
+
+    #[test]
+    fn drag_outside_the_viewport_draws_a_new_range() {
+        // 1000px minimap showing 40%–60%.
+        let hit = |x| minimap_hit_test(x, 1000.0, 0.4, 0.6);
+        assert_eq!(hit(395.0), MinimapHit::LeftHandle);
+        assert_eq!(hit(607.0), MinimapHit::RightHandle);
+        assert_eq!(hit(500.0), MinimapHit::Viewport);
+        assert_eq!(hit(100.0), MinimapHit::Outside);
+
+        let drag = MinimapDrag::begin(800.0, 1000.0, 0.4, 0.6);
+        assert_eq!(drag, MinimapDrag::Create { anchor: 0.8 });
+        assert_eq!(drag.update(700.0, 1000.0, 0.4, 0.6), (0.7, 0.8));
+        assert_eq!(drag.update(1200.0, 1000.0, 0.4, 0.6), (0.8, 1.0));
+
+        let pan = MinimapDrag::begin(450.0, 1000.0, 0.4, 0.6);
+        let (start, end) = pan.update(950.0, 1000.0, 0.4, 0.6);
+        assert!((start - 0.8).abs() < 1e-9 && (end - 1.0).abs() < 1e-9);
+
+        let resize = MinimapDrag::begin(601.0, 1000.0, 0.4, 0.6);
+        assert_eq!(resize.update(100.0, 1000.0, 0.4, 0.6), (0.4, 0.401));
+    }
 }
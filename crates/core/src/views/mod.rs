@@ -1,12 +1,252 @@
+pub mod accessibility;
 pub mod async_track;
 pub mod counter;
 pub mod cpu_samples;
 pub mod frame_track;
+pub mod heat;
+pub mod labels;
 pub mod left_heavy;
 pub mod markers;
 pub mod minimap;
 pub mod object_track;
+pub mod overlay;
 pub mod ranked;
 pub mod sandwich;
+pub mod task_strip;
+pub mod text;
 pub mod time_axis;
 pub mod time_order;
+pub mod utilization;
+
+use std::collections::{HashMap, HashSet};
+
+use flame_cat_protocol::{
+    Point, RenderCommand, RenderOptions, SearchOptions, SharedStr, Span, TextAlign, ThemeToken,
+    TimeRange, ViewType, Viewport, VisualProfile, color_key,
+};
+
+/// Render `profile` as configured by `options`.
+pub fn render(profile: &VisualProfile, options: &RenderOptions) -> Vec<RenderCommand> {
+    let mut commands = Vec::new();
+    render_into(&mut commands, profile, options);
+    commands
+}
+
+/// Like [`render`], but writes into a caller-owned buffer, which is cleared
+/// first.
+///
+/// Name and depth coloring are left to the host's painter; the heat modes
+/// are baked into time-order output with [`heat::apply_heat`]. The view's
+/// label template, if any, is applied with
+/// [`labels::apply_label_template`], then an active search with
+/// [`apply_search`], so searches match the templated labels.
+pub fn render_into(
+    commands: &mut Vec<RenderCommand>,
+    profile: &VisualProfile,
+    options: &RenderOptions,
+) {
+    render_compared_into(commands, profile, options, None);
+}
+
+/// Like [`render_into`], with the ranked view showing each row's change
+/// from `baseline`.
+pub fn render_compared_into(
+    commands: &mut Vec<RenderCommand>,
+    profile: &VisualProfile,
+    options: &RenderOptions,
+    baseline: Option<&ranked::Baseline>,
+) {
+    let viewport = &options.viewport;
+    let threads = options.threads.as_slice();
+    match (options.view, options.selected_frame) {
+        (ViewType::LeftHeavy, _) => {
+            left_heavy::render_left_heavy_inner(
+                commands,
+                profile,
+                viewport,
+                threads,
+                &options.limits,
+                false,
+                options.prune_percent,
+            );
+        }
+        (ViewType::Icicle, _) => {
+            left_heavy::render_left_heavy_inner(
+                commands,
+                profile,
+                viewport,
+                threads,
+                &options.limits,
+                true,
+                options.prune_percent,
+            );
+        }
+        (ViewType::Sandwich, Some(frame_id)) => {
+            *commands =
+                sandwich::render_sandwich_inner(profile, frame_id, viewport, options.prune_percent);
+        }
+        (ViewType::Ranked, _) => {
+            *commands = ranked::render_ranked(
+                profile,
+                viewport,
+                ranked::RankedSort::SelfTime,
+                false,
+                baseline,
+                &options.format,
+            );
+        }
+        // Without a selected span the sandwich view has nothing to center on.
+        (ViewType::TimeOrder | ViewType::Sandwich, _) => {
+            let range = options.range.unwrap_or(TimeRange {
+                start: profile.meta.start_time,
+                end: profile.meta.end_time,
+            });
+            time_order::render_threads_into(
+                commands,
+                profile,
+                viewport,
+                range,
+                threads,
+                &options.lod,
+                &options.limits,
+            );
+            heat::apply_heat(commands, profile, options.color_mode, options.heat_scale);
+        }
+    }
+    if options.hide_kernel {
+        hide_kernel_frames(commands, profile);
+    }
+    if let Some(template) = options
+        .label_templates
+        .get(&options.view)
+        .and_then(|t| labels::LabelTemplate::parse(t).ok())
+    {
+        labels::apply_label_template(commands, profile, &template, &options.format);
+    }
+    apply_search(commands, profile, &options.search);
+}
+
+/// Note in the top-right corner that `hidden` spans were left out by the
+/// render's `RenderLimits`.
+fn push_hidden_indicator(commands: &mut Vec<RenderCommand>, viewport: &Viewport, hidden: usize) {
+    if hidden == 0 {
+        return;
+    }
+    commands.push(RenderCommand::DrawText {
+        position: Point {
+            x: viewport.width - 6.0,
+            y: 12.0,
+        },
+        text: SharedStr::from(format!("{hidden} more… (render limits)")),
+        color: ThemeToken::TextMuted,
+        font_size: 11.0,
+        align: TextAlign::Right,
+    });
+}
+
+/// Drop the rects of kernel spans of `profile` from `commands`, for
+/// [`RenderOptions::hide_kernel`]. Rects with no span id, as in the
+/// aggregate views, are matched by span name.
+pub fn hide_kernel_frames(commands: &mut Vec<RenderCommand>, profile: &VisualProfile) {
+    let kernel: Vec<&Span> = profile.all_spans().filter(|s| s.is_kernel()).collect();
+    if kernel.is_empty() {
+        return;
+    }
+    let ids: HashSet<u64> = kernel.iter().map(|s| s.id).collect();
+    let names: HashSet<&str> = kernel.iter().map(|s| s.name.as_ref()).collect();
+    commands.retain(|cmd| match cmd {
+        RenderCommand::DrawRect {
+            frame_id: Some(id), ..
+        } => !ids.contains(id),
+        RenderCommand::DrawRect {
+            label: Some(label),
+            span_name,
+            ..
+        } => !names.contains(span_name.as_deref().unwrap_or(label)),
+        _ => true,
+    });
+}
+
+/// Flag every rect whose label does not match `search` as dimmed, as hosts
+/// show search results: the label must contain the query and, with a
+/// category set, the span name (the label when untemplated) must have that
+/// color key; with a binary set, some span of that name in `profile` must
+/// have run in it, and with a code class set, some span of that name must
+/// be of that class under the search's class rules. Unlabeled rects never
+/// match. No-op without a query, category, binary or code class.
+pub fn apply_search(
+    commands: &mut [RenderCommand],
+    profile: &VisualProfile,
+    search: &SearchOptions,
+) {
+    if search.query.is_empty()
+        && search.category.is_none()
+        && search.binary.is_none()
+        && search.code_class.is_none()
+    {
+        return;
+    }
+    let query = if search.case_sensitive {
+        search.query.clone()
+    } else {
+        search.query.to_lowercase()
+    };
+    // Aggregate views draw merged nodes with no span id, so binaries are
+    // matched by span name.
+    let binary_names: Option<HashSet<&str>> = search.binary.as_deref().map(|binary| {
+        profile
+            .all_spans()
+            .filter(|s| {
+                profile
+                    .binary_of(s)
+                    .is_some_and(|b| b.name.as_ref() == binary)
+            })
+            .map(|s| s.name.as_ref())
+            .collect()
+    });
+    let class_names: Option<HashSet<&str>> = search.code_class.map(|class| {
+        profile
+            .all_spans()
+            .filter(|s| search.class_rules.classify(s, profile.binary_of(s)) == class)
+            .map(|s| s.name.as_ref())
+            .collect()
+    });
+    // Far fewer distinct names than rects, so match each name once.
+    let mut matched: HashMap<(SharedStr, Option<SharedStr>), bool> = HashMap::new();
+    for cmd in commands {
+        if let RenderCommand::DrawRect {
+            label,
+            dimmed,
+            span_name,
+            ..
+        } = cmd
+        {
+            *dimmed = match label {
+                Some(label) => !*matched
+                    .entry((label.clone(), span_name.clone()))
+                    .or_insert_with(|| {
+                        let name = span_name.as_deref().unwrap_or(label);
+                        let in_category = search
+                            .category
+                            .as_deref()
+                            .is_none_or(|c| color_key(name) == c);
+                        let in_binary = binary_names
+                            .as_ref()
+                            .is_none_or(|names| names.contains(name));
+                        let in_class = class_names
+                            .as_ref()
+                            .is_none_or(|names| names.contains(name));
+                        in_category
+                            && in_binary
+                            && in_class
+                            && if search.case_sensitive {
+                                label.contains(&query)
+                            } else {
+                                label.to_lowercase().contains(&query)
+                            }
+                    }),
+                None => true,
+            };
+        }
+    }
+}
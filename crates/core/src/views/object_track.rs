@@ -1,5 +1,5 @@
 use flame_cat_protocol::{
-    ObjectEvent, ObjectPhase, Rect, RenderCommand, SharedStr, ThemeToken, Viewport,
+    GroupRole, ObjectEvent, ObjectPhase, Rect, RenderCommand, SharedStr, ThemeToken, Viewport,
 };
 use std::collections::HashMap;
 
@@ -59,6 +59,7 @@ pub fn render_object_track(
     commands.push(RenderCommand::BeginGroup {
         id: "object-track".into(),
         label: Some("Object Lifecycle".into()),
+        role: GroupRole::Lane,
     });
 
     // Background
@@ -68,6 +69,8 @@ pub fn render_object_track(
         border_color: Some(ThemeToken::LaneBorder),
         label: None,
         frame_id: None,
+        dimmed: false,
+        span_name: None,
     });
 
     // Swimlane packing
@@ -111,6 +114,8 @@ pub fn render_object_track(
             border_color: Some(ThemeToken::AsyncSpanBorder),
             label: Some(life.name.clone()),
             frame_id: None,
+            dimmed: false,
+            span_name: None,
         });
 
         // Snapshot markers
@@ -130,6 +135,8 @@ pub fn render_object_track(
                 border_color: None,
                 label: None,
                 frame_id: None,
+                dimmed: false,
+                span_name: None,
             });
         }
     }
@@ -0,0 +1,182 @@
+//! Overlay comparison: one run drawn over another.
+//!
+//! Stacking two runs of the same scenario makes the eye jump between lanes.
+//! [`render_overlay`] draws a thread of the base profile as usual and the
+//! same-named thread of a second profile as translucent outlines on top,
+//! both mapped through the session alignment, so a span that got slower
+//! shows as an outline running past its base rect.
+
+use flame_cat_protocol::{
+    GroupRole, RenderCommand, RenderOptions, SharedStr, ThemeToken, TimeRange, ViewType,
+};
+
+use crate::model::session::ProfileEntry;
+
+/// Time-order render of `thread` in `base`, with the spans of the thread of
+/// the same name in `other` laid over it.
+///
+/// `options.range` is in `base`'s own clock, the whole base profile when
+/// absent; the overlay covers the same stretch of the session timeline.
+/// `options.threads` and `options.view` are ignored. Overlaid rects carry
+/// no label or span id, so they neither hide the base labels nor take its
+/// clicks. Without a matching thread the base is drawn alone.
+pub fn render_overlay(
+    base: &ProfileEntry,
+    other: &ProfileEntry,
+    thread: u32,
+    options: &RenderOptions,
+) -> Vec<RenderCommand> {
+    let range = options.range.unwrap_or(TimeRange {
+        start: base.profile.meta.start_time,
+        end: base.profile.meta.end_time,
+    });
+    let mut base_options = options.clone();
+    base_options.view = ViewType::TimeOrder;
+    base_options.range = Some(range);
+    base_options.threads = vec![thread];
+    let mut commands = super::render(&base.profile, &base_options);
+
+    let Some(name) = base
+        .profile
+        .threads
+        .iter()
+        .find(|t| t.id == thread)
+        .map(|t| &t.name)
+    else {
+        return commands;
+    };
+    let Some(other_thread) = other.profile.threads.iter().find(|t| &t.name == name) else {
+        return commands;
+    };
+    let mut other_commands = Vec::new();
+    super::time_order::render_threads_into(
+        &mut other_commands,
+        &other.profile,
+        &options.viewport,
+        TimeRange {
+            start: other.to_local_time(base.to_session_time(range.start)),
+            end: other.to_local_time(base.to_session_time(range.end)),
+        },
+        &[other_thread.id],
+        &options.lod,
+        &options.limits,
+    );
+    let ghosts: Vec<RenderCommand> = other_commands
+        .into_iter()
+        .filter_map(|cmd| match cmd {
+            RenderCommand::DrawRect {
+                rect,
+                frame_id: Some(_),
+                ..
+            } => Some(RenderCommand::DrawRect {
+                rect,
+                color: ThemeToken::OverlaySpanFill,
+                border_color: Some(ThemeToken::OverlaySpanBorder),
+                label: None,
+                frame_id: None,
+                dimmed: false,
+                span_name: None,
+            }),
+            _ => None,
+        })
+        .collect();
+    if ghosts.is_empty() {
+        return commands;
+    }
+
+    // Inside the base view's group, after everything it draws.
+    let closing = commands.pop();
+    commands.push(RenderCommand::BeginGroup {
+        id: SharedStr::from(format!("overlay-{}", other_thread.id)),
+        label: Some(SharedStr::from(format!(
+            "{} ({})",
+            other_thread.name, other.label
+        ))),
+        role: GroupRole::Lane,
+    });
+    commands.extend(ghosts);
+    commands.push(RenderCommand::EndGroup);
+    commands.extend(closing);
+    commands
+}
+
+#[cfg(test)]
+mod tests {
+    use flame_cat_protocol::{Rect, Viewport};
+
+    use super::*;
+    use crate::model::Session;
+    use crate::parsers::chrome::parse_chrome_trace;
+
+    fn profile(dur: u32) -> flame_cat_protocol::VisualProfile {
+        let json = format!(
+            r#"{{"traceEvents":[
+            {{"name":"thread_name","ph":"M","ts":0,"pid":1,"tid":1,"args":{{"name":"Main"}}}},
+            {{"name":"main","ph":"X","ts":0,"dur":{dur},"pid":1,"tid":1}}
+        ]}}"#
+        );
+        parse_chrome_trace(json.as_bytes())
+            .unwrap()
+            .into_visual_profile()
+    }
+
+    fn ghost_rects(commands: &[RenderCommand]) -> Vec<Rect> {
+        commands
+            .iter()
+            .filter_map(|c| match c {
+                RenderCommand::DrawRect {
+                    rect,
+                    color: ThemeToken::OverlaySpanFill,
+                    ..
+                } => Some(*rect),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn lays_the_other_run_over_the_base() {
+        let mut session = Session::from_profile(profile(100), "before");
+        session.add_profile(profile(150), "after");
+        session.profiles_mut()[1].offset_us = 1_000.0;
+        let [base, other] = session.profiles() else {
+            panic!("two profiles");
+        };
+        let options = RenderOptions {
+            viewport: Viewport {
+                x: 0.0,
+                y: 0.0,
+                width: 400.0,
+                height: 100.0,
+                dpr: 1.0,
+            },
+            range: Some(TimeRange {
+                start: 0.0,
+                end: 200.0,
+            }),
+            ..Default::default()
+        };
+        let thread = base.profile.threads[0].id;
+
+        // Placed after the window: nothing to lay over it.
+        let commands = render_overlay(base, other, thread, &options);
+        assert!(ghost_rects(&commands).is_empty());
+
+        // Aligned at the same start: the slower run reaches further.
+        let mut aligned = other.clone();
+        aligned.offset_us = base.offset_us;
+        let commands = render_overlay(base, &aligned, thread, &options);
+        let ghosts = ghost_rects(&commands);
+        assert_eq!(ghosts.len(), 1);
+        assert_eq!((ghosts[0].x, ghosts[0].w.round()), (0.0, 300.0));
+        assert!(matches!(commands.last(), Some(RenderCommand::EndGroup)));
+        assert!(commands.iter().any(|c| matches!(
+            c,
+            RenderCommand::DrawRect { label: Some(l), .. } if l.as_ref() == "main"
+        )));
+
+        // Unknown threads draw no overlay.
+        let commands = render_overlay(base, &aligned, 99, &options);
+        assert!(ghost_rects(&commands).is_empty());
+    }
+}
@@ -1,11 +1,16 @@
 use std::collections::HashMap;
 
 use flame_cat_protocol::{
-    Point, Rect, RenderCommand, SharedStr, TextAlign, ThemeToken, Viewport, VisualProfile,
+    ClassRules, DurationFormat, GroupRole, Point, ProfileMeta, Rect, RenderCommand, SharedStr,
+    TextAlign, ThemeToken, ValueUnit, Viewport, VisualProfile,
 };
 
+use crate::model::{DiffNormalization, Session};
+
 const ROW_HEIGHT: f64 = 24.0;
 const HEADER_ROW_HEIGHT: f64 = 28.0;
+/// Slowest spans kept per row by [`get_ranked_entries`].
+pub const SLOWEST_INSTANCES: usize = 5;
 
 /// A single row in the ranked table.
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
@@ -14,6 +19,150 @@ pub struct RankedEntry {
     pub self_time: f64,
     pub total_time: f64,
     pub count: u32,
+    /// Longest spans with this name, slowest first.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub slowest: Vec<SpanInstance>,
+}
+
+/// One occurrence of a ranked name, to jump to from its row.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct SpanInstance {
+    pub frame_id: u64,
+    pub start: f64,
+    pub duration: f64,
+    /// Id of the thread group the span is on.
+    pub thread: u32,
+}
+
+impl RankedEntry {
+    /// Average duration of one span with this name.
+    pub fn mean_duration(&self) -> f64 {
+        if self.count == 0 {
+            0.0
+        } else {
+            self.total_time / f64::from(self.count)
+        }
+    }
+}
+
+/// Per-name totals of the session's baseline profile, which ranked rows and
+/// tooltips are compared against.
+#[derive(Debug, Clone)]
+pub struct Baseline {
+    unit: ValueUnit,
+    entries: HashMap<SharedStr, RankedEntry>,
+    /// Factor from [`DiffNormalization`] applied to totals, which are then
+    /// in `scaled_unit`.
+    scale: f64,
+    scaled_unit: ValueUnit,
+}
+
+impl Baseline {
+    /// Baseline the primary profile of `session` is compared against,
+    /// normalized the way the session asks. `None` without a baseline, or
+    /// when the primary profile is it.
+    pub fn for_session(session: &Session) -> Option<Self> {
+        let index = session.baseline_index().filter(|&index| index != 0)?;
+        let primary = &session.profiles().first()?.profile;
+        Some(Self::normalized(
+            &session.profiles().get(index)?.profile,
+            primary,
+            session.diff_normalization(),
+        ))
+    }
+
+    pub fn new(profile: &VisualProfile) -> Self {
+        let entries = aggregate_spans(profile, RankedGroup::Name, RankedSort::Name, false, 0)
+            .into_iter()
+            .map(|e| (e.name.clone(), e))
+            .collect();
+        Self {
+            unit: profile.meta.value_unit,
+            entries,
+            scale: 1.0,
+            scaled_unit: profile.meta.value_unit,
+        }
+    }
+
+    /// Baseline `profile` scaled for comparison with `primary`. Values stay
+    /// as recorded when `normalization` needs something a profile lacks,
+    /// like the length of a sample-count recording with no known interval.
+    pub fn normalized(
+        profile: &VisualProfile,
+        primary: &VisualProfile,
+        normalization: DiffNormalization,
+    ) -> Self {
+        let mut baseline = Self::new(profile);
+        let ratio = match normalization {
+            DiffNormalization::PerSample => None,
+            DiffNormalization::PerSecond => recording_us(primary).zip(recording_us(profile)),
+            DiffNormalization::PerTotal => {
+                Some((primary.meta.total_value, profile.meta.total_value))
+            }
+        };
+        if let Some((primary_norm, baseline_norm)) = ratio
+            && primary_norm > 0.0
+            && baseline_norm > 0.0
+        {
+            baseline.scale = primary_norm / baseline_norm;
+            // Shares of the total carry over into the primary's unit.
+            if normalization == DiffNormalization::PerTotal {
+                baseline.scaled_unit = primary.meta.value_unit;
+            }
+        }
+        baseline
+    }
+
+    /// Normalized baseline totals for `name`, converted to `unit`. `None`
+    /// when the baseline has no such span or its values cannot be converted.
+    pub fn entry(&self, name: &str, unit: ValueUnit) -> Option<RankedEntry> {
+        let scale = self.scale * conversion(self.scaled_unit, unit)?;
+        let entry = self.entries.get(name)?;
+        Some(RankedEntry {
+            self_time: entry.self_time * scale,
+            total_time: entry.total_time * scale,
+            ..entry.clone()
+        })
+    }
+
+    /// Mean duration of one `name` span in the baseline, converted to
+    /// `unit`. Not normalized: a single call takes as long however long
+    /// the recording ran.
+    pub fn mean_duration(&self, name: &str, unit: ValueUnit) -> Option<f64> {
+        Some(self.entries.get(name)?.mean_duration() * conversion(self.unit, unit)?)
+    }
+}
+
+/// Factor converting values in `from` to `to`.
+fn conversion(from: ValueUnit, to: ValueUnit) -> Option<f64> {
+    if from == to {
+        Some(1.0)
+    } else {
+        Some(from.to_microseconds_factor()? / to.to_microseconds_factor()?)
+    }
+}
+
+/// Wall-clock length of `profile`'s recording in µs, if known.
+fn recording_us(profile: &VisualProfile) -> Option<f64> {
+    let meta = &profile.meta;
+    let factor = match (meta.value_unit, meta.sample_interval_us) {
+        (ValueUnit::Samples, Some(interval)) => interval,
+        (unit, _) => unit.to_microseconds_factor()?,
+    };
+    Some(profile.duration() * factor)
+}
+
+/// Format `value` with its change from `baseline`, if there is one.
+pub fn format_compared(
+    meta: &ProfileMeta,
+    value: f64,
+    baseline: Option<f64>,
+    format: &DurationFormat,
+) -> String {
+    match baseline {
+        Some(baseline) => meta.format_delta_with(value, baseline, format),
+        None => meta.format_value_with(value, format),
+    }
 }
 
 /// Sort field for the ranked view.
@@ -25,14 +174,46 @@ pub enum RankedSort {
     Count,
 }
 
+/// What ranked rows total spans by.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RankedGroup<'a> {
+    /// One row per span name.
+    #[default]
+    Name,
+    /// One row per mapped binary (see [`VisualProfile::binaries`]), with
+    /// spans of no known binary under [`UNMAPPED_BINARY`].
+    Binary,
+    /// One row per [`CodeClass`](flame_cat_protocol::CodeClass) the rules
+    /// put spans in, named by its `name()`.
+    Class(&'a ClassRules),
+}
+
+impl<'a> RankedGroup<'a> {
+    /// Parse a JS API name: `name`, `binary` or `class` (sorted by `rules`).
+    pub fn from_name(name: &str, rules: &'a ClassRules) -> Option<Self> {
+        match name {
+            "name" => Some(Self::Name),
+            "binary" => Some(Self::Binary),
+            "class" => Some(Self::Class(rules)),
+            _ => None,
+        }
+    }
+}
+
+/// Row name of spans with no recorded binary under [`RankedGroup::Binary`].
+pub const UNMAPPED_BINARY: &str = "(unmapped)";
+
 /// Aggregate all spans by name and produce render commands for a table layout.
+/// With a `baseline`, the time columns also show each name's change from it.
 pub fn render_ranked(
     profile: &VisualProfile,
     viewport: &Viewport,
     sort: RankedSort,
     ascending: bool,
+    baseline: Option<&Baseline>,
+    format: &DurationFormat,
 ) -> Vec<RenderCommand> {
-    let entries = aggregate_spans(profile, sort, ascending);
+    let entries = aggregate_spans(profile, RankedGroup::Name, sort, ascending, 0);
     let total_duration = profile.duration();
     if total_duration <= 0.0 {
         return Vec::new();
@@ -42,20 +223,23 @@ pub fn render_ranked(
     commands.push(RenderCommand::BeginGroup {
         id: "ranked".into(),
         label: Some("Ranked".into()),
+        role: GroupRole::View,
     });
 
     // Column layout: Symbol Name | Self | Total | Count
-    let col_self_x = viewport.width * 0.5;
-    let col_total_x = viewport.width * 0.68;
-    let col_count_x = viewport.width * 0.86;
+    let col_self_x = viewport.snap(viewport.width * 0.5);
+    let col_total_x = viewport.snap(viewport.width * 0.68);
+    let col_count_x = viewport.snap(viewport.width * 0.86);
 
     // Header row
     commands.push(RenderCommand::DrawRect {
-        rect: Rect::new(0.0, 0.0, viewport.width, HEADER_ROW_HEIGHT),
+        rect: viewport.snap_rect(Rect::new(0.0, 0.0, viewport.width, HEADER_ROW_HEIGHT)),
         color: ThemeToken::TableHeaderBackground,
         border_color: Some(ThemeToken::TableBorder),
         label: None,
         frame_id: None,
+        dimmed: false,
+        span_name: None,
     });
 
     let header_y = HEADER_ROW_HEIGHT / 2.0;
@@ -92,14 +276,17 @@ pub fn render_ranked(
 
         // Row background
         commands.push(RenderCommand::DrawRect {
-            rect: Rect::new(0.0, y, viewport.width, ROW_HEIGHT),
+            rect: viewport.snap_rect(Rect::new(0.0, y, viewport.width, ROW_HEIGHT)),
             color: row_color,
             border_color: None,
             label: None,
             frame_id: None,
+            dimmed: false,
+            span_name: None,
         });
 
         let text_y = y + ROW_HEIGHT / 2.0;
+        let compared = baseline.and_then(|b| b.entry(&entry.name, profile.meta.value_unit));
 
         // Symbol name
         commands.push(RenderCommand::DrawText {
@@ -114,23 +301,31 @@ pub fn render_ranked(
         let self_pct = entry.self_time / total_duration;
         let bar_max_w = viewport.width * 0.16;
         commands.push(RenderCommand::DrawRect {
-            rect: Rect::new(
+            rect: viewport.snap_rect(Rect::new(
                 col_self_x + 2.0,
                 y + ROW_HEIGHT - 4.0,
                 bar_max_w * self_pct,
                 2.0,
-            ),
+            )),
             color: ThemeToken::BarFill,
             border_color: None,
             label: None,
             frame_id: None,
+            dimmed: false,
+            span_name: None,
         });
         commands.push(RenderCommand::DrawText {
             position: Point {
                 x: col_self_x + 4.0,
                 y: text_y,
             },
-            text: format_time(entry.self_time).into(),
+            text: format_compared(
+                &profile.meta,
+                entry.self_time,
+                compared.as_ref().map(|b| b.self_time),
+                format,
+            )
+            .into(),
             color: ThemeToken::TextSecondary,
             font_size: 11.0,
             align: TextAlign::Left,
@@ -139,23 +334,31 @@ pub fn render_ranked(
         // Total time + bar
         let total_pct = entry.total_time / total_duration;
         commands.push(RenderCommand::DrawRect {
-            rect: Rect::new(
+            rect: viewport.snap_rect(Rect::new(
                 col_total_x + 2.0,
                 y + ROW_HEIGHT - 4.0,
                 bar_max_w * total_pct,
                 2.0,
-            ),
+            )),
             color: ThemeToken::BarFill,
             border_color: None,
             label: None,
             frame_id: None,
+            dimmed: false,
+            span_name: None,
         });
         commands.push(RenderCommand::DrawText {
             position: Point {
                 x: col_total_x + 4.0,
                 y: text_y,
             },
-            text: format_time(entry.total_time).into(),
+            text: format_compared(
+                &profile.meta,
+                entry.total_time,
+                compared.as_ref().map(|b| b.total_time),
+                format,
+            )
+            .into(),
             color: ThemeToken::TextSecondary,
             font_size: 11.0,
             align: TextAlign::Left,
@@ -167,7 +370,7 @@ pub fn render_ranked(
                 x: col_count_x + 4.0,
                 y: text_y,
             },
-            text: SharedStr::from(entry.count.to_string()),
+            text: SharedStr::from(format.number(f64::from(entry.count), 0)),
             color: ThemeToken::TextMuted,
             font_size: 11.0,
             align: TextAlign::Left,
@@ -181,35 +384,80 @@ pub fn render_ranked(
 /// Compute ranked entries from WASM for the table/detail views.
 pub fn get_ranked_entries(
     profile: &VisualProfile,
+    group: RankedGroup,
     sort: RankedSort,
     ascending: bool,
 ) -> Vec<RankedEntry> {
-    aggregate_spans(profile, sort, ascending)
+    aggregate_spans(profile, group, sort, ascending, SLOWEST_INSTANCES)
 }
 
-fn aggregate_spans(profile: &VisualProfile, sort: RankedSort, ascending: bool) -> Vec<RankedEntry> {
-    let mut by_name: HashMap<&str, (SharedStr, f64, f64, u32)> = HashMap::new();
+/// Totals per row of `group`, each with up to `instances` of its slowest
+/// spans.
+fn aggregate_spans(
+    profile: &VisualProfile,
+    group: RankedGroup,
+    sort: RankedSort,
+    ascending: bool,
+    instances: usize,
+) -> Vec<RankedEntry> {
+    let mut by_name: HashMap<&str, RankedEntry> = HashMap::new();
 
-    for span in profile.all_spans() {
-        let entry = by_name
-            .entry(&span.name)
-            .or_insert_with(|| (span.name.clone(), 0.0, 0.0, 0));
-        entry.1 += span.self_value;
-        entry.2 += span.duration();
-        entry.3 += 1;
+    for thread in &profile.threads {
+        for span in &thread.spans {
+            let group_name = match group {
+                RankedGroup::Name => None,
+                RankedGroup::Binary => Some(
+                    profile
+                        .binary_of(span)
+                        .map_or(UNMAPPED_BINARY, |b| b.name.as_ref()),
+                ),
+                RankedGroup::Class(rules) => {
+                    Some(rules.classify(span, profile.binary_of(span)).name())
+                }
+            };
+            // By name, a span counts for its function and for each function
+            // inlined into it; the innermost of them spent its self time.
+            let chain = match group {
+                RankedGroup::Name => span.inlined.as_slice(),
+                RankedGroup::Binary | RankedGroup::Class(_) => &[],
+            };
+            let duration = span.duration();
+            for (i, name) in std::iter::once(&span.name).chain(chain).enumerate() {
+                let entry = by_name
+                    .entry(group_name.unwrap_or(name))
+                    .or_insert_with(|| RankedEntry {
+                        name: group_name.map_or_else(|| name.clone(), SharedStr::from),
+                        self_time: 0.0,
+                        total_time: 0.0,
+                        count: 0,
+                        slowest: Vec::new(),
+                    });
+                if i == chain.len() {
+                    entry.self_time += span.self_value;
+                }
+                entry.total_time += duration;
+                entry.count += 1;
+                if instances > 0
+                    && (entry.slowest.len() < instances
+                        || entry.slowest.last().is_some_and(|s| duration > s.duration))
+                {
+                    let at = entry.slowest.partition_point(|s| s.duration >= duration);
+                    entry.slowest.insert(
+                        at,
+                        SpanInstance {
+                            frame_id: span.id,
+                            start: span.start,
+                            duration,
+                            thread: thread.id,
+                        },
+                    );
+                    entry.slowest.truncate(instances);
+                }
+            }
+        }
     }
 
-    let mut entries: Vec<RankedEntry> = Vec::with_capacity(by_name.len());
-    entries.extend(
-        by_name
-            .into_values()
-            .map(|(name, self_time, total_time, count)| RankedEntry {
-                name,
-                self_time,
-                total_time,
-                count,
-            }),
-    );
+    let mut entries: Vec<RankedEntry> = by_name.into_values().collect();
 
     match sort {
         RankedSort::SelfTime => entries.sort_by(|a, b| b.self_time.total_cmp(&a.self_time)),
@@ -225,16 +473,6 @@ fn aggregate_spans(profile: &VisualProfile, sort: RankedSort, ascending: bool) -
     entries
 }
 
-fn format_time(us: f64) -> String {
-    if us >= 1_000_000.0 {
-        format!("{:.2}s", us / 1_000_000.0)
-    } else if us >= 1_000.0 {
-        format!("{:.1}ms", us / 1_000.0)
-    } else {
-        format!("{:.0}µs", us)
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -253,10 +491,18 @@ mod tests {
                 start_time: 0.0,
                 end_time: 100.0,
                 time_domain: None,
+                truncated_spans: 0,
+                repairs: Default::default(),
+                limits_hit: Default::default(),
+                partial: None,
+                sample_interval_us: None,
+                description: None,
+                tags: vec![],
             },
             threads: vec![ThreadGroup {
                 id: 0,
                 name: "Main".into(),
+                kind: Default::default(),
                 sort_key: 0,
                 max_depth: 0,
                 spans: vec![
@@ -270,6 +516,9 @@ mod tests {
                         self_value: 30.0,
                         kind: SpanKind::Event,
                         category: None,
+                        truncated: false,
+                        binary: None,
+                        inlined: vec![],
                     },
                     Span {
                         id: 1,
@@ -281,6 +530,9 @@ mod tests {
                         self_value: 20.0,
                         kind: SpanKind::Event,
                         category: None,
+                        truncated: false,
+                        binary: None,
+                        inlined: vec![],
                     },
                     Span {
                         id: 2,
@@ -292,8 +544,14 @@ mod tests {
                         self_value: 30.0,
                         kind: SpanKind::Event,
                         category: None,
+                        truncated: false,
+                        binary: None,
+                        inlined: vec![],
                     },
                 ],
+                pid: None,
+                tid: None,
+                process_name: None,
             }],
             frames: vec![],
             counters: vec![],
@@ -305,13 +563,20 @@ mod tests {
             cpu_samples: None,
             network_requests: vec![],
             screenshots: vec![],
+            binaries: vec![],
         };
 
-        let entries = get_ranked_entries(&profile, RankedSort::SelfTime, false);
+        let entries = get_ranked_entries(&profile, RankedGroup::Name, RankedSort::SelfTime, false);
         assert_eq!(entries.len(), 2);
         assert_eq!(entries[0].name, "foo");
         assert_eq!(entries[0].self_time, 50.0);
         assert_eq!(entries[0].count, 2);
+        let slowest: Vec<(u64, f64)> = entries[0]
+            .slowest
+            .iter()
+            .map(|s| (s.frame_id, s.duration))
+            .collect();
+        assert_eq!(slowest, [(0, 50.0), (1, 30.0)]);
         assert_eq!(entries[1].name, "bar");
 
         let vp = Viewport {
@@ -321,7 +586,14 @@ mod tests {
             height: 600.0,
             dpr: 1.0,
         };
-        let cmds = render_ranked(&profile, &vp, RankedSort::SelfTime, false);
+        let cmds = render_ranked(
+            &profile,
+            &vp,
+            RankedSort::SelfTime,
+            false,
+            None,
+            &DurationFormat::default(),
+        );
         let texts: Vec<_> = cmds
             .iter()
             .filter_map(|c| {
@@ -335,4 +607,249 @@ mod tests {
         assert!(texts.contains(&SharedStr::from("foo")));
         assert!(texts.contains(&SharedStr::from("bar")));
     }
+
+    #[test]
+    fn groups_rows_by_binary() {
+        let json = r#"{
+            "samples": [
+                {"locationId": [2, 1], "value": [3]},
+                {"locationId": [3], "value": [1]}
+            ],
+            "mappings": [{"id": 1, "memoryStart": 0, "memoryLimit": 4096, "filename": 2}],
+            "locations": [
+                {"id": 1, "mappingId": 1, "line": [{"functionId": 1}]},
+                {"id": 2, "mappingId": 1, "line": [{"functionId": 2}]},
+                {"id": 3, "line": [{"functionId": 2}]}
+            ],
+            "functions": [{"id": 1, "name": 0}, {"id": 2, "name": 1}],
+            "stringTable": ["main", "work", "/srv/app"]
+        }"#;
+        let profile = crate::parsers::pprof::parse_pprof(json.as_bytes())
+            .unwrap()
+            .into_visual_profile();
+        let entries =
+            get_ranked_entries(&profile, RankedGroup::Binary, RankedSort::SelfTime, false);
+        let rows: Vec<(&str, f64, u32)> = entries
+            .iter()
+            .map(|e| (e.name.as_ref(), e.self_time, e.count))
+            .collect();
+        assert_eq!(rows, [("/srv/app", 3.0, 2), (UNMAPPED_BINARY, 1.0, 1)]);
+    }
+
+    #[test]
+    fn groups_rows_by_code_class() {
+        let profile = crate::parsers::collapsed::parse_collapsed(
+            b"main;std::sync::Mutex::lock 2\nmain;serde::de::parse 1\n",
+        )
+        .unwrap()
+        .into_visual_profile();
+        let rules = ClassRules {
+            dependencies: vec!["serde::".into()],
+            ..ClassRules::default()
+        };
+        let entries = get_ranked_entries(
+            &profile,
+            RankedGroup::Class(&rules),
+            RankedSort::SelfTime,
+            false,
+        );
+        let rows: Vec<(&str, f64)> = entries
+            .iter()
+            .map(|e| (e.name.as_ref(), e.self_time))
+            .collect();
+        assert_eq!(
+            rows,
+            [("runtime", 2.0), ("dependency", 1.0), ("first_party", 0.0)]
+        );
+    }
+
+    #[test]
+    fn inlined_functions_get_rows_of_their_own() {
+        let profile = crate::parsers::collapsed::parse_collapsed(
+            b"main;run;push_[i];grow_[i] 3\nmain;run;push_[i];alloc 1\n",
+        )
+        .unwrap()
+        .into_visual_profile();
+        let entries = get_ranked_entries(&profile, RankedGroup::Name, RankedSort::Name, false);
+        let rows: Vec<(&str, f64, f64, u32)> = entries
+            .iter()
+            .map(|e| (e.name.as_ref(), e.self_time, e.total_time, e.count))
+            .collect();
+        assert_eq!(
+            rows,
+            [
+                ("alloc", 1.0, 1.0, 1),
+                ("grow", 3.0, 3.0, 1),
+                ("main", 0.0, 4.0, 2),
+                ("push", 0.0, 4.0, 2),
+                ("run", 0.0, 4.0, 2),
+            ]
+        );
+    }
+
+    #[test]
+    fn bars_land_on_device_pixels() {
+        let profile = crate::parsers::collapsed::parse_collapsed(b"main;work 1\nmain 2\n")
+            .unwrap()
+            .into_visual_profile();
+        for dpr in [1.0, 2.0, 3.0] {
+            let vp = Viewport {
+                x: 0.0,
+                y: 0.0,
+                width: 333.0,
+                height: 600.0,
+                dpr,
+            };
+            for cmd in render_ranked(
+                &profile,
+                &vp,
+                RankedSort::SelfTime,
+                false,
+                None,
+                &DurationFormat::default(),
+            ) {
+                if let RenderCommand::DrawRect { rect, .. } = cmd {
+                    for edge in [rect.x, rect.y, rect.x + rect.w, rect.y + rect.h] {
+                        let device = edge * dpr;
+                        assert!((device - device.round()).abs() < 1e-9, "{edge} at {dpr}x");
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn sample_profiles_show_sample_counts() {
+        let profile = crate::parsers::collapsed::parse_collapsed(b"main;work 3\nmain 2\n")
+            .unwrap()
+            .into_visual_profile();
+        let vp = Viewport {
+            x: 0.0,
+            y: 0.0,
+            width: 800.0,
+            height: 600.0,
+            dpr: 1.0,
+        };
+        let cmds = render_ranked(
+            &profile,
+            &vp,
+            RankedSort::TotalTime,
+            false,
+            None,
+            &DurationFormat::default(),
+        );
+        let has_text = |t: &str| {
+            cmds.iter()
+                .any(|c| matches!(c, RenderCommand::DrawText { text, .. } if &**text == t))
+        };
+        assert!(has_text("5 samples"));
+        assert!(has_text("3 samples"));
+        assert!(
+            !cmds
+                .iter()
+                .any(|c| matches!(c, RenderCommand::DrawText { text, .. } if text.ends_with("µs")))
+        );
+    }
+
+    #[test]
+    fn shows_deltas_vs_baseline() {
+        let parse = |data: &[u8]| {
+            crate::parsers::collapsed::parse_collapsed(data)
+                .unwrap()
+                .into_visual_profile()
+        };
+        let profile = parse(b"main;work 3\nmain 2\n");
+        let baseline = Baseline::new(&parse(b"main;work 6\nmain;idle 4\n"));
+        let work = baseline.entry("work", ValueUnit::Samples).unwrap();
+        assert_eq!((work.total_time, work.mean_duration()), (6.0, 6.0));
+        assert!(baseline.entry("work", ValueUnit::Microseconds).is_none());
+
+        let vp = Viewport {
+            x: 0.0,
+            y: 0.0,
+            width: 800.0,
+            height: 600.0,
+            dpr: 1.0,
+        };
+        let cmds = render_ranked(
+            &profile,
+            &vp,
+            RankedSort::TotalTime,
+            false,
+            Some(&baseline),
+            &DurationFormat::default(),
+        );
+        let has_text = |t: &str| {
+            cmds.iter()
+                .any(|c| matches!(c, RenderCommand::DrawText { text, .. } if &**text == t))
+        };
+        assert!(has_text("5 samples (-5 samples vs baseline)"));
+        assert!(has_text("3 samples (-3 samples vs baseline)"));
+        assert!(has_text("2 samples (+2 samples vs baseline)"));
+    }
+
+    #[test]
+    fn normalizes_baseline_totals() {
+        let parse = |data: &[u8]| {
+            crate::parsers::collapsed::parse_collapsed(data)
+                .unwrap()
+                .into_visual_profile()
+        };
+        // Twice the samples, same split: no change once normalized.
+        let profile = parse(b"main;work 3\nmain 2\n");
+        let mut doubled = parse(b"main;work 6\nmain 4\n");
+        let total = |normalization| {
+            Baseline::normalized(&doubled, &profile, normalization)
+                .entry("work", ValueUnit::Samples)
+                .unwrap()
+                .total_time
+        };
+        assert_eq!(total(DiffNormalization::PerSample), 6.0);
+        assert_eq!(total(DiffNormalization::PerTotal), 3.0);
+        // No sampling interval, so the recording length is unknown.
+        assert_eq!(total(DiffNormalization::PerSecond), 6.0);
+
+        // At the same rate, the baseline simply recorded twice as long.
+        doubled.meta.sample_interval_us = Some(1_000.0);
+        let mut profile = profile;
+        profile.meta.sample_interval_us = Some(1_000.0);
+        let baseline = Baseline::normalized(&doubled, &profile, DiffNormalization::PerSecond);
+        assert_eq!(
+            baseline
+                .entry("work", ValueUnit::Samples)
+                .unwrap()
+                .total_time,
+            3.0
+        );
+        assert_eq!(
+            baseline.mean_duration("work", ValueUnit::Samples),
+            Some(6.0)
+        );
+    }
+
+    #[test]
+    fn keeps_the_slowest_instances_of_each_name() {
+        let profile = crate::testing::synth::generate(&crate::testing::synth::SynthConfig {
+            threads: 2,
+            spans_per_thread: 400,
+            names: 8,
+            ..Default::default()
+        });
+        for entry in get_ranked_entries(&profile, RankedGroup::Name, RankedSort::Name, false) {
+            let mut durations: Vec<f64> = profile
+                .all_spans()
+                .filter(|s| s.name == entry.name)
+                .map(|s| s.duration())
+                .collect();
+            durations.sort_by(|a, b| b.total_cmp(a));
+            durations.truncate(SLOWEST_INSTANCES);
+            let kept: Vec<f64> = entry.slowest.iter().map(|s| s.duration).collect();
+            assert_eq!(kept, durations);
+            for instance in &entry.slowest {
+                let span = profile.span(instance.frame_id).unwrap();
+                assert_eq!(span.start, instance.start);
+                assert_eq!(profile.thread_of_span(span.id), Some(instance.thread));
+            }
+        }
+    }
 }
@@ -1,4 +1,6 @@
-use flame_cat_protocol::{Rect, RenderCommand, SharedStr, ThemeToken, Viewport, VisualProfile};
+use flame_cat_protocol::{
+    GroupRole, Rect, RenderCommand, SharedStr, ThemeToken, Viewport, VisualProfile,
+};
 
 const FRAME_HEIGHT: f64 = 20.0;
 const SEPARATOR_HEIGHT: f64 = 4.0;
@@ -9,11 +11,23 @@ pub fn render_sandwich(
     profile: &VisualProfile,
     selected_frame_id: u64,
     viewport: &Viewport,
+) -> Vec<RenderCommand> {
+    render_sandwich_inner(profile, selected_frame_id, viewport, 0.0)
+}
+
+/// [`render_sandwich`] with the callers and callees under `prune_percent`
+/// of the selected frame's time collapsed into one "other (N frames)" row.
+pub(super) fn render_sandwich_inner(
+    profile: &VisualProfile,
+    selected_frame_id: u64,
+    viewport: &Viewport,
+    prune_percent: f64,
 ) -> Vec<RenderCommand> {
     let mut commands = Vec::with_capacity(32);
     commands.push(RenderCommand::BeginGroup {
         id: "sandwich".into(),
         label: Some("Sandwich".into()),
+        role: GroupRole::View,
     });
 
     // Build O(1) span index for parent-chain traversal.
@@ -40,6 +54,7 @@ pub fn render_sandwich(
     }
 
     let total_time: f64 = matching.iter().map(|s| s.duration()).sum();
+    let min_time = total_time * prune_percent / 100.0;
     let x_scale = viewport.width / total_time.max(1.0);
 
     // === Callers section (walk upward) ===
@@ -61,6 +76,7 @@ pub fn render_sandwich(
 
     let mut callers: Vec<_> = caller_time.into_iter().collect();
     callers.sort_by(|a, b| b.1.total_cmp(&a.1));
+    let callers = prune_rows(callers, min_time);
 
     for (i, (name, time)) in callers.iter().enumerate() {
         let w = time * x_scale;
@@ -78,6 +94,8 @@ pub fn render_sandwich(
             border_color: Some(ThemeToken::Border),
             label: Some(name.clone()),
             frame_id: None,
+            dimmed: false,
+            span_name: None,
         });
     }
 
@@ -90,6 +108,8 @@ pub fn render_sandwich(
         border_color: Some(ThemeToken::Border),
         label: Some(selected_name.clone()),
         frame_id: Some(selected_frame_id),
+        dimmed: false,
+        span_name: None,
     });
 
     // === Callees section (walk downward) ===
@@ -115,6 +135,7 @@ pub fn render_sandwich(
 
     let mut callees: Vec<_> = callee_time.into_iter().collect();
     callees.sort_by(|a, b| b.1.total_cmp(&a.1));
+    let callees = prune_rows(callees, min_time);
 
     for (i, (name, time)) in callees.iter().enumerate() {
         let w = time * x_scale;
@@ -132,6 +153,8 @@ pub fn render_sandwich(
             border_color: Some(ThemeToken::Border),
             label: Some(name.clone()),
             frame_id: None,
+            dimmed: false,
+            span_name: None,
         });
     }
 
@@ -139,6 +162,18 @@ pub fn render_sandwich(
     commands
 }
 
+/// Replace the rows, heaviest first, lighter than `min_time` with one
+/// "other (N frames)" row when there is more than one of them.
+fn prune_rows(mut rows: Vec<(SharedStr, f64)>, min_time: f64) -> Vec<(SharedStr, f64)> {
+    let light = rows.partition_point(|(_, time)| *time >= min_time);
+    if rows.len() - light > 1 {
+        let frames = rows.len() - light;
+        let time = rows.drain(light..).map(|(_, time)| time).sum();
+        rows.push((format!("other ({frames} frames)").into(), time));
+    }
+    rows
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -157,10 +192,18 @@ mod tests {
                 start_time: 0.0,
                 end_time: 100.0,
                 time_domain: None,
+                truncated_spans: 0,
+                repairs: Default::default(),
+                limits_hit: Default::default(),
+                partial: None,
+                sample_interval_us: None,
+                description: None,
+                tags: vec![],
             },
             threads: vec![ThreadGroup {
                 id: 0,
                 name: "Main".into(),
+                kind: Default::default(),
                 sort_key: 0,
                 max_depth: 0,
                 spans: vec![
@@ -174,6 +217,9 @@ mod tests {
                         self_value: 0.0,
                         kind: SpanKind::Event,
                         category: None,
+                        truncated: false,
+                        binary: None,
+                        inlined: vec![],
                     },
                     Span {
                         id: 1,
@@ -185,6 +231,9 @@ mod tests {
                         self_value: 0.0,
                         kind: SpanKind::Event,
                         category: None,
+                        truncated: false,
+                        binary: None,
+                        inlined: vec![],
                     },
                     Span {
                         id: 2,
@@ -196,8 +245,14 @@ mod tests {
                         self_value: 60.0,
                         kind: SpanKind::Event,
                         category: None,
+                        truncated: false,
+                        binary: None,
+                        inlined: vec![],
                     },
                 ],
+                pid: None,
+                tid: None,
+                process_name: None,
             }],
             frames: vec![],
             counters: vec![],
@@ -209,6 +264,7 @@ mod tests {
             cpu_samples: None,
             network_requests: vec![],
             screenshots: vec![],
+            binaries: vec![],
         };
         let vp = Viewport {
             x: 0.0,
@@ -256,10 +312,18 @@ mod tests {
                 start_time: 0.0,
                 end_time: 100.0,
                 time_domain: None,
+                truncated_spans: 0,
+                repairs: Default::default(),
+                limits_hit: Default::default(),
+                partial: None,
+                sample_interval_us: None,
+                description: None,
+                tags: vec![],
             },
             threads: vec![ThreadGroup {
                 id: 0,
                 name: "Main".into(),
+                kind: Default::default(),
                 sort_key: 0,
                 max_depth: 0,
                 spans: vec![Span {
@@ -272,7 +336,13 @@ mod tests {
                     self_value: 100.0,
                     kind: SpanKind::Event,
                     category: None,
+                    truncated: false,
+                    binary: None,
+                    inlined: vec![],
                 }],
+                pid: None,
+                tid: None,
+                process_name: None,
             }],
             frames: vec![],
             counters: vec![],
@@ -284,6 +354,7 @@ mod tests {
             cpu_samples: None,
             network_requests: vec![],
             screenshots: vec![],
+            binaries: vec![],
         };
         let vp = Viewport {
             x: 0.0,
@@ -298,4 +369,27 @@ mod tests {
         assert!(matches!(cmds[0], RenderCommand::BeginGroup { .. }));
         assert!(matches!(cmds[1], RenderCommand::EndGroup));
     }
+
+    #[test]
+    fn prunes_light_rows() {
+        let rows = |times: &[f64]| -> Vec<(SharedStr, f64)> {
+            times
+                .iter()
+                .enumerate()
+                .map(|(i, &t)| (format!("f{i}").into(), t))
+                .collect()
+        };
+        let pruned = prune_rows(rows(&[50.0, 30.0, 2.0, 1.0]), 5.0);
+        assert_eq!(
+            pruned,
+            [
+                ("f0".into(), 50.0),
+                ("f1".into(), 30.0),
+                ("other (2 frames)".into(), 3.0)
+            ]
+        );
+        // A single light row stays as it is.
+        assert_eq!(prune_rows(rows(&[50.0, 2.0]), 5.0).len(), 2);
+        assert_eq!(prune_rows(rows(&[50.0, 2.0, 1.0]), 0.0).len(), 3);
+    }
 }
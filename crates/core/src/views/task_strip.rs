@@ -0,0 +1,125 @@
+use flame_cat_protocol::{GroupRole, Rect, RenderCommand, SharedStr, ThemeToken, Viewport};
+
+use crate::analysis::tasks::Task;
+
+/// Tasks narrower than this many pixels are skipped unless they are long.
+const MIN_TASK_PX: f64 = 0.5;
+
+/// Render a thread's top-level tasks as a strip of bars, one per task.
+///
+/// Tasks of at least `long_task` (in the profile's unit) are highlighted.
+/// Each bar is labeled with the task and its dominant child, and carries
+/// the task's span id so hosts can select it.
+pub fn render_task_strip(
+    tasks: &[Task],
+    viewport: &Viewport,
+    view_start: f64,
+    view_end: f64,
+    long_task: Option<f64>,
+) -> Vec<RenderCommand> {
+    let duration = view_end - view_start;
+    if duration <= 0.0 || tasks.is_empty() {
+        return Vec::new();
+    }
+    let x_scale = viewport.width / duration;
+
+    let mut commands = Vec::with_capacity(tasks.len() + 3);
+    commands.push(RenderCommand::BeginGroup {
+        id: "tasks".into(),
+        label: Some("Tasks".into()),
+        role: GroupRole::Lane,
+    });
+    commands.push(RenderCommand::DrawRect {
+        rect: Rect::new(0.0, 0.0, viewport.width, viewport.height),
+        color: ThemeToken::LaneBackground,
+        border_color: Some(ThemeToken::LaneBorder),
+        label: None,
+        frame_id: None,
+        dimmed: false,
+        span_name: None,
+    });
+
+    for task in tasks {
+        if task.end < view_start || task.start > view_end {
+            continue;
+        }
+        let is_long = long_task.is_some_and(|t| task.duration >= t);
+        let w = task.duration * x_scale;
+        if w < MIN_TASK_PX && !is_long {
+            continue;
+        }
+        let label = match &task.dominant_child {
+            Some(child) => format!("{} ({})", task.name, child.name),
+            None => task.name.to_string(),
+        };
+        commands.push(RenderCommand::DrawRect {
+            rect: Rect::new(
+                (task.start - view_start) * x_scale,
+                0.0,
+                w.max(1.0),
+                viewport.height,
+            ),
+            color: if is_long {
+                ThemeToken::FrameDropped
+            } else {
+                ThemeToken::FlameNeutral
+            },
+            border_color: None,
+            label: Some(SharedStr::from(label)),
+            frame_id: Some(task.span_id),
+            dimmed: false,
+            span_name: None,
+        });
+    }
+
+    commands.push(RenderCommand::EndGroup);
+    commands
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn task(span_id: u64, start: f64, end: f64) -> Task {
+        Task {
+            span_id,
+            name: "RunTask".into(),
+            start,
+            end,
+            duration: end - start,
+            dominant_child: None,
+        }
+    }
+
+    #[test]
+    fn highlights_long_tasks() {
+        let vp = Viewport {
+            x: 0.0,
+            y: 0.0,
+            width: 1000.0,
+            height: 12.0,
+            dpr: 1.0,
+        };
+        let tasks = [
+            task(1, 0.0, 10.0),
+            task(2, 20.0, 80.0),
+            task(3, 90.0, 90.01),
+        ];
+        let cmds = render_task_strip(&tasks, &vp, 0.0, 100.0, Some(50.0));
+        let bars: Vec<(u64, ThemeToken)> = cmds
+            .iter()
+            .filter_map(|c| match c {
+                RenderCommand::DrawRect {
+                    frame_id: Some(id),
+                    color,
+                    ..
+                } => Some((*id, *color)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            bars,
+            [(1, ThemeToken::FlameNeutral), (2, ThemeToken::FrameDropped)]
+        );
+    }
+}
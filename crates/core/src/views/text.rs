@@ -0,0 +1,136 @@
+//! Span label fitting.
+//!
+//! Every host draws rect labels the same way: inset by [`LABEL_PADDING`],
+//! sized by [`label_font_size`], and cut with an ellipsis when too wide.
+//! Only measuring text differs between hosts, so that part is a
+//! [`TextMeasure`] each renderer plugs in; the decision of what to show is
+//! made here once.
+
+use std::borrow::Cow;
+
+/// Horizontal inset of a label from each side of its rect.
+pub const LABEL_PADDING: f64 = 3.0;
+/// Rects shorter than this get no label.
+pub const MIN_LABEL_HEIGHT: f64 = 8.0;
+const LABEL_FONT_MIN: f64 = 6.0;
+const LABEL_FONT_MAX: f64 = 11.0;
+/// Vertical room left around a label's glyphs.
+const LABEL_FONT_PADDING: f64 = 4.0;
+const ELLIPSIS: char = '…';
+
+/// Width of a string as a host will draw it.
+pub trait TextMeasure {
+    /// Width of `text` at `font_size`, in the same units as rect widths.
+    fn width(&self, text: &str, font_size: f64) -> f64;
+}
+
+/// Measures every character as `em` times the font size wide. A stand-in
+/// for hosts that cannot measure glyphs, such as SVG export.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FixedWidth {
+    pub em: f64,
+}
+
+impl Default for FixedWidth {
+    fn default() -> Self {
+        Self { em: 0.6 }
+    }
+}
+
+impl TextMeasure for FixedWidth {
+    fn width(&self, text: &str, font_size: f64) -> f64 {
+        text.chars().count() as f64 * self.em * font_size
+    }
+}
+
+/// Font size of the label of a rect `rect_height` tall.
+pub fn label_font_size(rect_height: f64) -> f64 {
+    (rect_height - LABEL_FONT_PADDING).clamp(LABEL_FONT_MIN, LABEL_FONT_MAX)
+}
+
+/// A label as it fits its rect.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FittedLabel<'a> {
+    /// The whole label, or its longest prefix that fits followed by `…`.
+    pub text: Cow<'a, str>,
+    pub font_size: f64,
+}
+
+/// What of `label` to draw in a `rect_width` × `rect_height` rect, or
+/// `None` when not even one character and the ellipsis fit.
+pub fn fit_label<'a>(
+    label: &'a str,
+    rect_width: f64,
+    rect_height: f64,
+    measure: &dyn TextMeasure,
+) -> Option<FittedLabel<'a>> {
+    let available = rect_width - LABEL_PADDING * 2.0;
+    if label.is_empty() || available <= 0.0 || rect_height < MIN_LABEL_HEIGHT {
+        return None;
+    }
+    let font_size = label_font_size(rect_height);
+    if measure.width(label, font_size) <= available {
+        return Some(FittedLabel {
+            text: Cow::Borrowed(label),
+            font_size,
+        });
+    }
+
+    // Byte offsets after each character; widths grow with the prefix, so
+    // binary-search the longest one that fits with the ellipsis.
+    let ends: Vec<usize> = label
+        .char_indices()
+        .map(|(i, c)| i + c.len_utf8())
+        .collect();
+    let cut = |chars: usize| format!("{}{ELLIPSIS}", &label[..ends[chars - 1]]);
+    let (mut fits, mut too_wide) = (0, ends.len());
+    while too_wide - fits > 1 {
+        let mid = (fits + too_wide) / 2;
+        if measure.width(&cut(mid), font_size) <= available {
+            fits = mid;
+        } else {
+            too_wide = mid;
+        }
+    }
+    (fits > 0).then(|| FittedLabel {
+        text: Cow::Owned(cut(fits)),
+        font_size,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// One unit per character, whatever the font size, like terminal cells.
+    struct Cells;
+
+    impl TextMeasure for Cells {
+        fn width(&self, text: &str, _font_size: f64) -> f64 {
+            text.chars().count() as f64
+        }
+    }
+
+    #[test]
+    fn keeps_cuts_or_drops_labels() {
+        let fit = |label, width| fit_label(label, width, 20.0, &Cells).map(|f| f.text);
+        assert_eq!(fit("render", 12.0).as_deref(), Some("render"));
+        assert_eq!(fit("render", 11.0).as_deref(), Some("rend…"));
+        assert_eq!(fit("ünïcode", 9.0).as_deref(), Some("ün…"));
+        assert_eq!(fit("render", 7.0).as_deref(), None);
+        assert_eq!(fit("", 100.0), None);
+        assert_eq!(fit_label("render", 100.0, 6.0, &Cells), None);
+    }
+
+    #[test]
+    fn font_size_follows_rect_height() {
+        assert_eq!(label_font_size(19.0), 11.0);
+        assert_eq!(label_font_size(12.0), 8.0);
+        let fitted = fit_label("main", 100.0, 19.0, &FixedWidth::default()).unwrap();
+        assert_eq!((fitted.text.as_ref(), fitted.font_size), ("main", 11.0));
+        // 0.6 em at 11px: 6.6px per character, 94px of room after padding.
+        let fitted = fit_label("a".repeat(20).as_str(), 100.0, 19.0, &FixedWidth::default())
+            .map(|f| f.text.into_owned());
+        assert_eq!(fitted, Some(format!("{}…", "a".repeat(13))));
+    }
+}
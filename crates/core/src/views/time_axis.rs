@@ -1,4 +1,6 @@
-use flame_cat_protocol::{Point, Rect, RenderCommand, SharedStr, TextAlign, ThemeToken, Viewport};
+use flame_cat_protocol::{
+    DurationFormat, Point, Rect, RenderCommand, SharedStr, TextAlign, ThemeToken, Viewport,
+};
 
 const AXIS_HEIGHT: f64 = 24.0;
 const MAJOR_TICK_HEIGHT: f64 = 10.0;
@@ -10,14 +12,17 @@ const MIN_MAJOR_SPACING_PX: f64 = 80.0;
 
 /// Render a time axis ruler with major/medium/minor ticks and labels.
 ///
-/// `view_start` and `view_end` are in microseconds (absolute timestamps).
+/// `view_start` and `view_end` are in microseconds on whatever clock the
+/// labels should read, e.g. relative to the session start.
 /// Returns render commands for the axis bar + ticks + labels, plus
-/// vertical gridlines extending `grid_height` below the axis.
+/// vertical gridlines extending `grid_height` below the axis. Labels are
+/// written as `format` asks.
 pub fn render_time_axis(
     viewport: &Viewport,
     view_start: f64,
     view_end: f64,
     grid_height: f64,
+    format: &DurationFormat,
 ) -> Vec<RenderCommand> {
     let duration = view_end - view_start;
     if duration <= 0.0 {
@@ -35,6 +40,8 @@ pub fn render_time_axis(
         border_color: Some(ThemeToken::LaneBorder),
         label: None,
         frame_id: None,
+        dimmed: false,
+        span_name: None,
     });
 
     // Calculate tick spacing: find a "nice" interval in microseconds
@@ -100,7 +107,7 @@ pub fn render_time_axis(
             });
 
             // Time label
-            let label = format_time_label(t, major_interval);
+            let label = format_time_label(t, major_interval, format);
             commands.push(RenderCommand::DrawText {
                 position: Point::new(x + 3.0, LABEL_Y - 8.0),
                 text: SharedStr::from(label.as_str()),
@@ -133,7 +140,10 @@ fn is_aligned(t: f64, interval: f64, base: f64) -> bool {
 
 /// Choose a "nice" major tick interval in microseconds given the visible duration
 /// and pixel width. Returns (major_interval_us, subdivisions).
-fn nice_interval(duration_us: f64, width_px: f64) -> (f64, u32) {
+///
+/// Hosts drawing gridlines of their own use this to line them up with the
+/// axis' major ticks.
+pub fn nice_interval(duration_us: f64, width_px: f64) -> (f64, u32) {
     // Target: roughly one major tick per MIN_MAJOR_SPACING_PX pixels
     let target_count = (width_px / MIN_MAJOR_SPACING_PX).max(2.0);
     let raw_interval = duration_us / target_count;
@@ -181,21 +191,13 @@ fn nice_interval(duration_us: f64, width_px: f64) -> (f64, u32) {
     (magnitude, 2)
 }
 
-/// Format a timestamp in microseconds as a human-readable label.
-fn format_time_label(us: f64, _interval: f64) -> String {
-    let abs = us.abs();
-    if abs >= 60_000_000.0 {
-        let mins = (us / 60_000_000.0).floor();
-        let secs = (us - mins * 60_000_000.0) / 1_000_000.0;
-        format!("{:.0}m{:.1}s", mins, secs)
-    } else if abs >= 1_000_000.0 {
-        format!("{:.3}s", us / 1_000_000.0)
-    } else if abs >= 1_000.0 {
-        format!("{:.2}ms", us / 1_000.0)
-    } else if abs >= 1.0 {
-        format!("{:.1}µs", us)
+/// Format a timestamp in microseconds as a human-readable label. Ticks at
+/// the clock's origin read `0` rather than a rounding error in nanoseconds.
+fn format_time_label(us: f64, interval: f64, format: &DurationFormat) -> String {
+    if us.abs() < interval * 1e-6 {
+        "0".to_string()
     } else {
-        format!("{:.0}ns", us * 1000.0)
+        format.format_tick(us)
     }
 }
 
@@ -222,7 +224,7 @@ mod tests {
             height: 24.0,
             dpr: 1.0,
         };
-        let cmds = render_time_axis(&vp, 0.0, 1_000_000.0, 400.0);
+        let cmds = render_time_axis(&vp, 0.0, 1_000_000.0, 400.0, &DurationFormat::default());
         assert!(!cmds.is_empty());
 
         // Should have background rect
@@ -249,8 +251,18 @@ mod tests {
 
     #[test]
     fn format_labels() {
-        assert_eq!(format_time_label(500.0, 100.0), "500.0µs");
-        assert_eq!(format_time_label(1_500.0, 1000.0), "1.50ms");
-        assert_eq!(format_time_label(1_500_000.0, 1_000_000.0), "1.500s");
+        let auto = DurationFormat::default();
+        assert_eq!(format_time_label(500.0, 100.0, &auto), "500.0µs");
+        assert_eq!(format_time_label(1_500.0, 1000.0, &auto), "1.50ms");
+        assert_eq!(format_time_label(1_500_000.0, 1_000_000.0, &auto), "1.500s");
+        assert_eq!(format_time_label(1e-9, 1_000.0, &auto), "0");
+        let ms = DurationFormat {
+            unit: flame_cat_protocol::DurationUnit::Milliseconds,
+            ..auto
+        };
+        assert_eq!(
+            format_time_label(1_500_000.0, 1_000_000.0, &ms),
+            "1500.00ms"
+        );
     }
 }
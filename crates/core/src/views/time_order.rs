@@ -1,10 +1,16 @@
+use std::collections::HashMap;
+
 use flame_cat_protocol::{
-    Point, Rect, RenderCommand, SharedStr, TextAlign, ThemeToken, Viewport, VisualProfile,
+    GroupRole, LodOptions, Point, Rect, RenderCommand, RenderLimits, SharedStr, Span, TextAlign,
+    ThemeToken, ThreadGroup, TimeRange, TrackKind, Viewport, VisualProfile,
 };
 
-const FRAME_HEIGHT: f64 = 20.0;
+/// Height of one stack row; hosts scale and scroll lanes in multiples of it.
+pub const FRAME_HEIGHT: f64 = 20.0;
 const THREAD_HEADER_HEIGHT: f64 = 22.0;
 const THREAD_GAP: f64 = 4.0;
+/// Horizontal depth of the zigzag drawn on truncated spans' right edge.
+const RAGGED_EDGE_DEPTH: f64 = 3.0;
 
 /// Render a profile in time-order view: frames are laid out chronologically,
 /// X-axis = wall time, Y-axis = stack depth.
@@ -20,38 +26,120 @@ pub fn render_time_order(
     view_end: f64,
     thread_id: Option<u32>,
 ) -> Vec<RenderCommand> {
+    let mut commands = Vec::new();
+    render_time_order_into(
+        &mut commands,
+        profile,
+        viewport,
+        view_start,
+        view_end,
+        thread_id,
+    );
+    commands
+}
+
+/// Like [`render_time_order`], but writes into a caller-owned buffer.
+///
+/// `commands` is cleared first; its allocation is kept, so a caller that
+/// re-renders every pan/zoom tick stops paying for a fresh `Vec` each frame.
+pub fn render_time_order_into(
+    commands: &mut Vec<RenderCommand>,
+    profile: &VisualProfile,
+    viewport: &Viewport,
+    view_start: f64,
+    view_end: f64,
+    thread_id: Option<u32>,
+) {
+    render_threads_into(
+        commands,
+        profile,
+        viewport,
+        TimeRange {
+            start: view_start,
+            end: view_end,
+        },
+        thread_id.as_slice(),
+        &LodOptions::default(),
+        &RenderLimits::default(),
+    );
+}
+
+/// Time-order layout of the threads in `threads` (all when empty), skipping
+/// spans narrower than `lod.min_span_px`. Thread headers are drawn unless
+/// exactly one thread is selected. Spans deeper than `limits.max_depth` get
+/// an "N more…" row per thread instead. Spans with functions inlined into
+/// them are labelled "name (+N inlined)", or with `lod.expand_inlined` get
+/// a row per inlined function, named after it but standing for the span.
+pub(super) fn render_threads_into(
+    commands: &mut Vec<RenderCommand>,
+    profile: &VisualProfile,
+    viewport: &Viewport,
+    range: TimeRange,
+    threads: &[u32],
+    lod: &LodOptions,
+    limits: &RenderLimits,
+) {
+    commands.clear();
+    let TimeRange {
+        start: view_start,
+        end: view_end,
+    } = range;
     let visible_duration = view_end - view_start;
     if visible_duration <= 0.0 {
-        return Vec::new();
+        return;
     }
 
     let x_scale = viewport.width / visible_duration;
+    // Rows are separated by one device pixel, whatever the display density.
+    let gap = viewport.hairline();
 
-    let mut commands = Vec::with_capacity(profile.span_count() + 2);
+    let included = |id: u32| threads.is_empty() || threads.contains(&id);
+    let span_count: usize = profile
+        .threads
+        .iter()
+        .filter(|t| included(t.id))
+        .map(|t| t.spans.len())
+        .sum();
+    commands.reserve(span_count.min(limits.max_commands) + 2 * profile.threads.len() + 2);
 
     commands.push(RenderCommand::BeginGroup {
         id: "time-order".into(),
         label: Some("Time Order".into()),
+        role: GroupRole::View,
     });
 
     let mut y_offset: f64 = 0.0;
+    // Spans left out once `limits.max_commands` was reached.
+    let mut hidden = 0_usize;
 
     for thread in &profile.threads {
         // Skip threads not matching the filter
-        if thread_id.is_some_and(|tid| thread.id != tid) {
+        if !included(thread.id) {
             continue;
         }
+        commands.push(RenderCommand::BeginGroup {
+            id: SharedStr::from(format!("thread-{}", thread.id)),
+            label: Some(thread.name.clone()),
+            role: GroupRole::Lane,
+        });
 
         // Thread header (skip when rendering a single thread — the caller provides the header)
-        if thread_id.is_none() {
+        if threads.len() != 1 {
             let header_y = y_offset - viewport.y;
             if header_y + THREAD_HEADER_HEIGHT >= 0.0 && header_y <= viewport.height {
                 commands.push(RenderCommand::DrawRect {
-                    rect: Rect::new(0.0, header_y, viewport.width, THREAD_HEADER_HEIGHT - 1.0),
+                    rect: viewport.snap_rect(Rect::new(
+                        0.0,
+                        header_y,
+                        viewport.width,
+                        THREAD_HEADER_HEIGHT - gap,
+                    )),
                     color: ThemeToken::LaneHeaderBackground,
                     border_color: Some(ThemeToken::LaneBorder),
                     label: None,
                     frame_id: None,
+                    dimmed: false,
+                    span_name: None,
                 });
                 commands.push(RenderCommand::DrawText {
                     position: Point {
@@ -71,43 +159,178 @@ pub fn render_time_order(
             y_offset += THREAD_HEADER_HEIGHT;
         }
 
-        // Use cached max_depth (computed at parse time)
-        let max_depth = thread.max_depth;
+        // Rows the expanded inline chains above each span push it down by.
+        let shifts = if lod.expand_inlined {
+            inline_shifts(thread)
+        } else {
+            HashMap::new()
+        };
+        // Use cached max_depth (computed at parse time) unless chains are expanded.
+        let max_depth = if shifts.is_empty() {
+            thread.max_depth
+        } else {
+            deepest_row(thread, &shifts)
+        }
+        .min(limits.max_depth);
+        let mut too_deep = 0_usize;
+        // GPU queues get a distinct outline so they stand apart from CPU work.
+        let border = match thread.kind {
+            TrackKind::Gpu => ThemeToken::GpuSpanBorder,
+            TrackKind::Cpu => ThemeToken::Border,
+        };
 
         for span in &thread.spans {
             let x = (span.start - view_start) * x_scale;
             let w = span.duration() * x_scale;
-            let y = y_offset + f64::from(span.depth) * FRAME_HEIGHT - viewport.y;
 
             // Skip frames outside the visible area
             if x + w < 0.0 || x > viewport.width {
                 continue;
             }
-            if y + FRAME_HEIGHT < 0.0 || y > viewport.height {
-                continue;
-            }
+            let top = span.depth + shifts.get(&span.id).copied().unwrap_or(0);
+            // The span itself, then each function inlined into it a row lower.
+            let chain = if lod.expand_inlined {
+                span.inlined.as_slice()
+            } else {
+                &[]
+            };
+            for (row, inlined) in (top..).zip(std::iter::once(None).chain(chain.iter().map(Some))) {
+                let y = y_offset + f64::from(row) * FRAME_HEIGHT - viewport.y;
+                if row > limits.max_depth {
+                    too_deep += 1;
+                    continue;
+                }
+                if y + FRAME_HEIGHT < 0.0 || y > viewport.height {
+                    continue;
+                }
 
-            // Skip sub-pixel frames
-            if w < 0.5 {
-                continue;
-            }
+                // Skip sub-pixel frames
+                if w < lod.min_span_px {
+                    continue;
+                }
+                if commands.len() >= limits.max_commands {
+                    hidden += 1;
+                    continue;
+                }
 
-            let color = color_for_depth(span.depth);
+                let color = if span.is_kernel() {
+                    ThemeToken::KernelSpan
+                } else {
+                    color_for_depth(row)
+                };
+                let (label, span_name) = match inlined {
+                    Some(name) => (name.clone(), Some(name.clone())),
+                    None if span.inlined.is_empty() || lod.expand_inlined => {
+                        (span.name.clone(), None)
+                    }
+                    None => (
+                        SharedStr::from(format!("{} (+{} inlined)", span.name, span.inlined.len())),
+                        Some(span.name.clone()),
+                    ),
+                };
 
-            commands.push(RenderCommand::DrawRect {
-                rect: Rect::new(x, y, w, FRAME_HEIGHT - 1.0),
-                color,
-                border_color: Some(ThemeToken::Border),
-                label: Some(span.name.clone()),
-                frame_id: Some(span.id),
-            });
+                let rect = viewport.snap_rect(Rect::new(x, y, w, FRAME_HEIGHT - gap));
+                commands.push(RenderCommand::DrawRect {
+                    rect,
+                    color,
+                    border_color: Some(border),
+                    label: Some(label),
+                    frame_id: Some(span.id),
+                    dimmed: false,
+                    span_name,
+                });
+                if span.truncated && w > RAGGED_EDGE_DEPTH * 2.0 {
+                    push_ragged_edge(commands, rect.x + rect.w, rect.y, rect.h);
+                }
+            }
         }
 
-        y_offset += f64::from(max_depth + 1) * FRAME_HEIGHT + THREAD_GAP;
+        let mut rows = max_depth + 1;
+        if too_deep > 0 {
+            let y = y_offset + f64::from(rows) * FRAME_HEIGHT - viewport.y;
+            commands.push(RenderCommand::DrawText {
+                position: Point {
+                    x: 6.0,
+                    y: y + FRAME_HEIGHT / 2.0,
+                },
+                text: SharedStr::from(format!(
+                    "{too_deep} more… (deeper than level {})",
+                    limits.max_depth
+                )),
+                color: ThemeToken::TextMuted,
+                font_size: 11.0,
+                align: TextAlign::Left,
+            });
+            rows += 1;
+        }
+        y_offset += f64::from(rows) * FRAME_HEIGHT + THREAD_GAP;
+        commands.push(RenderCommand::EndGroup);
     }
 
+    super::push_hidden_indicator(commands, viewport, hidden);
     commands.push(RenderCommand::EndGroup);
-    commands
+}
+
+/// Deepest stack row of `thread` with the functions inlined into its spans
+/// drawn on rows of their own (see [`LodOptions::expand_inlined`]).
+pub fn expanded_max_depth(thread: &ThreadGroup) -> u32 {
+    deepest_row(thread, &inline_shifts(thread))
+}
+
+fn deepest_row(thread: &ThreadGroup, shifts: &HashMap<u64, u32>) -> u32 {
+    thread
+        .spans
+        .iter()
+        .map(|s| s.depth + shifts.get(&s.id).copied().unwrap_or(0) + s.inlined.len() as u32)
+        .max()
+        .unwrap_or(0)
+}
+
+/// Rows each span of `thread` moves down by when the inline chains of its
+/// ancestors are expanded; empty when no span has one.
+fn inline_shifts(thread: &ThreadGroup) -> HashMap<u64, u32> {
+    if thread.spans.iter().all(|s| s.inlined.is_empty()) {
+        return HashMap::new();
+    }
+    let by_id: HashMap<u64, &Span> = thread.spans.iter().map(|s| (s.id, s)).collect();
+    let mut shifts = HashMap::with_capacity(thread.spans.len());
+    for span in &thread.spans {
+        shift_of(span, &by_id, &mut shifts);
+    }
+    shifts
+}
+
+fn shift_of(span: &Span, by_id: &HashMap<u64, &Span>, shifts: &mut HashMap<u64, u32>) -> u32 {
+    if let Some(&shift) = shifts.get(&span.id) {
+        return shift;
+    }
+    let shift = match span.parent.and_then(|p| by_id.get(&p)) {
+        Some(parent) => shift_of(parent, by_id, shifts) + parent.inlined.len() as u32,
+        None => 0,
+    };
+    shifts.insert(span.id, shift);
+    shift
+}
+
+/// Cut a zigzag into the right edge at `x` so spans that ran past the end of
+/// the recording read as torn off rather than finished.
+fn push_ragged_edge(commands: &mut Vec<RenderCommand>, x: f64, y: f64, height: f64) {
+    const TEETH: usize = 3;
+    let step = height / (TEETH * 2) as f64;
+    let mut from = Point { x, y };
+    for i in 1..=TEETH * 2 {
+        let to = Point {
+            x: if i % 2 == 1 { x - RAGGED_EDGE_DEPTH } else { x },
+            y: y + step * i as f64,
+        };
+        commands.push(RenderCommand::DrawLine {
+            from,
+            to,
+            color: ThemeToken::LaneBackground,
+            width: 1.5,
+        });
+        from = to;
+    }
 }
 
 fn color_for_depth(depth: u32) -> ThemeToken {
@@ -122,7 +345,9 @@ fn color_for_depth(depth: u32) -> ThemeToken {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use flame_cat_protocol::{ProfileMeta, SourceFormat, Span, SpanKind, ThreadGroup, ValueUnit};
+    use flame_cat_protocol::{
+        ProfileMeta, SourceFormat, Span, SpanCategory, SpanKind, ThreadGroup, ValueUnit,
+    };
 
     fn test_profile() -> VisualProfile {
         VisualProfile {
@@ -134,10 +359,18 @@ mod tests {
                 start_time: 0.0,
                 end_time: 100.0,
                 time_domain: None,
+                truncated_spans: 0,
+                repairs: Default::default(),
+                limits_hit: Default::default(),
+                partial: None,
+                sample_interval_us: None,
+                description: None,
+                tags: vec![],
             },
             threads: vec![ThreadGroup {
                 id: 0,
                 name: "Main".into(),
+                kind: Default::default(),
                 sort_key: 0,
                 max_depth: 0,
                 spans: vec![
@@ -151,6 +384,9 @@ mod tests {
                         self_value: 50.0,
                         kind: SpanKind::Event,
                         category: None,
+                        truncated: false,
+                        binary: None,
+                        inlined: vec![],
                     },
                     Span {
                         id: 1,
@@ -162,8 +398,14 @@ mod tests {
                         self_value: 50.0,
                         kind: SpanKind::Event,
                         category: None,
+                        truncated: false,
+                        binary: None,
+                        inlined: vec![],
                     },
                 ],
+                pid: None,
+                tid: None,
+                process_name: None,
             }],
             frames: vec![],
             counters: vec![],
@@ -175,6 +417,7 @@ mod tests {
             cpu_samples: None,
             network_requests: vec![],
             screenshots: vec![],
+            binaries: vec![],
         }
     }
 
@@ -202,6 +445,296 @@ mod tests {
         assert_eq!(rects.len(), 2);
     }
 
+    #[test]
+    fn spans_are_aligned_to_device_pixels() {
+        let profile = test_profile();
+        for dpr in [1.0, 2.0, 3.0] {
+            let vp = Viewport {
+                x: 0.0,
+                y: 0.0,
+                width: 333.0,
+                height: 600.0,
+                dpr,
+            };
+            let rects: Vec<Rect> = render_time_order(&profile, &vp, 0.0, 100.0, None)
+                .into_iter()
+                .filter_map(|c| match c {
+                    RenderCommand::DrawRect { rect, .. } => Some(rect),
+                    _ => None,
+                })
+                .collect();
+            assert_eq!(rects.len(), 3);
+            for rect in &rects {
+                for edge in [rect.x, rect.y, rect.x + rect.w, rect.y + rect.h] {
+                    let device = edge * dpr;
+                    assert!((device - device.round()).abs() < 1e-9, "{edge} at {dpr}x");
+                }
+            }
+            // The child starts at 33.3 logical px; a row gap is one device pixel.
+            let child = rects[2];
+            assert_eq!((child.x * dpr).round(), (33.3 * dpr).round());
+            assert_eq!((child.h * dpr).round(), FRAME_HEIGHT * dpr - 1.0);
+        }
+    }
+
+    #[test]
+    fn truncated_spans_get_ragged_edge() {
+        let vp = Viewport {
+            x: 0.0,
+            y: 0.0,
+            width: 800.0,
+            height: 600.0,
+            dpr: 1.0,
+        };
+        let mut profile = test_profile();
+        let lines = |p: &VisualProfile| {
+            render_time_order(p, &vp, 0.0, 100.0, None)
+                .iter()
+                .filter(|c| matches!(c, RenderCommand::DrawLine { .. }))
+                .count()
+        };
+        assert_eq!(lines(&profile), 0);
+        profile.threads[0].spans[0].truncated = true;
+        assert_eq!(lines(&profile), 6);
+    }
+
+    #[test]
+    fn gpu_tracks_get_their_own_border() {
+        let vp = Viewport {
+            x: 0.0,
+            y: 0.0,
+            width: 800.0,
+            height: 600.0,
+            dpr: 1.0,
+        };
+        let mut profile = test_profile();
+        profile.threads[0].kind = TrackKind::Gpu;
+        let borders: Vec<_> = render_time_order(&profile, &vp, 0.0, 100.0, None)
+            .into_iter()
+            .filter_map(|c| match c {
+                RenderCommand::DrawRect {
+                    frame_id: Some(_),
+                    border_color,
+                    ..
+                } => border_color,
+                _ => None,
+            })
+            .collect();
+        assert_eq!(borders, [ThemeToken::GpuSpanBorder; 2]);
+    }
+
+    #[test]
+    fn kernel_spans_stand_apart_and_can_be_hidden() {
+        let mut profile = test_profile();
+        profile.threads[0].spans[1].category = Some(SpanCategory {
+            name: SpanCategory::KERNEL.into(),
+            source: None,
+        });
+        let mut options = flame_cat_protocol::RenderOptions {
+            viewport: Viewport {
+                x: 0.0,
+                y: 0.0,
+                width: 800.0,
+                height: 600.0,
+                dpr: 1.0,
+            },
+            ..Default::default()
+        };
+        let span_colors = |options: &flame_cat_protocol::RenderOptions| -> Vec<(u64, ThemeToken)> {
+            crate::views::render(&profile, options)
+                .into_iter()
+                .filter_map(|c| match c {
+                    RenderCommand::DrawRect {
+                        frame_id: Some(id),
+                        color,
+                        ..
+                    } => Some((id, color)),
+                    _ => None,
+                })
+                .collect()
+        };
+        assert_eq!(
+            span_colors(&options),
+            [(0, ThemeToken::FlameHot), (1, ThemeToken::KernelSpan)]
+        );
+        options.hide_kernel = true;
+        assert_eq!(span_colors(&options), [(0, ThemeToken::FlameHot)]);
+    }
+
+    #[test]
+    fn inlined_functions_fold_and_expand() {
+        let mut profile = test_profile();
+        profile.threads[0].spans[0].inlined = vec!["push".into(), "grow".into()];
+        let mut options = flame_cat_protocol::RenderOptions {
+            viewport: Viewport {
+                x: 0.0,
+                y: 0.0,
+                width: 800.0,
+                height: 600.0,
+                dpr: 1.0,
+            },
+            threads: vec![0],
+            ..Default::default()
+        };
+        options
+            .label_templates
+            .insert(flame_cat_protocol::ViewType::TimeOrder, "{name}!".into());
+        let rows = |options: &flame_cat_protocol::RenderOptions| -> Vec<(u64, f64, String)> {
+            crate::views::render(&profile, options)
+                .into_iter()
+                .filter_map(|c| match c {
+                    RenderCommand::DrawRect {
+                        frame_id: Some(id),
+                        rect,
+                        label: Some(label),
+                        ..
+                    } => Some((id, rect.y, label.to_string())),
+                    _ => None,
+                })
+                .collect()
+        };
+        assert_eq!(
+            rows(&options),
+            [(0, 0.0, "main!".into()), (1, 20.0, "child!".into())]
+        );
+        options.label_templates.clear();
+        assert_eq!(rows(&options)[0].2, "main (+2 inlined)");
+
+        options.lod.expand_inlined = true;
+        assert_eq!(
+            rows(&options),
+            [
+                (0, 0.0, "main".into()),
+                (0, 20.0, "push".into()),
+                (0, 40.0, "grow".into()),
+                (1, 60.0, "child".into()),
+            ]
+        );
+        assert_eq!(expanded_max_depth(&profile.threads[0]), 3);
+    }
+
+    #[test]
+    fn render_options_lod_and_thread_filter() {
+        let profile = test_profile();
+        let span_rects = |options: &flame_cat_protocol::RenderOptions| {
+            crate::views::render(&profile, options)
+                .iter()
+                .filter(|c| {
+                    matches!(
+                        c,
+                        RenderCommand::DrawRect {
+                            frame_id: Some(_),
+                            ..
+                        }
+                    )
+                })
+                .count()
+        };
+        let mut options = flame_cat_protocol::RenderOptions {
+            viewport: Viewport {
+                x: 0.0,
+                y: 0.0,
+                width: 800.0,
+                height: 600.0,
+                dpr: 1.0,
+            },
+            ..Default::default()
+        };
+        assert_eq!(span_rects(&options), 2);
+        // The child covers half the width (400 px).
+        options.lod.min_span_px = 500.0;
+        assert_eq!(span_rects(&options), 1);
+        options.threads = vec![7];
+        assert_eq!(span_rects(&options), 0);
+    }
+
+    #[test]
+    fn render_limits_summarize_hidden_spans() {
+        let profile = test_profile();
+        let render = |limits| {
+            let options = flame_cat_protocol::RenderOptions {
+                viewport: Viewport {
+                    x: 0.0,
+                    y: 0.0,
+                    width: 800.0,
+                    height: 600.0,
+                    dpr: 1.0,
+                },
+                threads: vec![0],
+                limits,
+                ..Default::default()
+            };
+            let commands = crate::views::render(&profile, &options);
+            let ids: Vec<u64> = commands
+                .iter()
+                .filter_map(|c| match c {
+                    RenderCommand::DrawRect { frame_id, .. } => *frame_id,
+                    _ => None,
+                })
+                .collect();
+            let notes: Vec<String> = commands
+                .iter()
+                .filter_map(|c| match c {
+                    RenderCommand::DrawText { text, .. } => Some(text.to_string()),
+                    _ => None,
+                })
+                .collect();
+            (ids, notes)
+        };
+
+        let (ids, notes) = render(RenderLimits {
+            max_depth: 0,
+            ..Default::default()
+        });
+        assert_eq!(ids, [0]);
+        assert_eq!(notes, ["1 more… (deeper than level 0)"]);
+
+        // Room for the view and lane group markers and one rect.
+        let (ids, notes) = render(RenderLimits {
+            max_commands: 3,
+            ..Default::default()
+        });
+        assert_eq!(ids, [0]);
+        assert_eq!(notes, ["1 more… (render limits)"]);
+    }
+
+    #[test]
+    fn search_dims_non_matching_spans() {
+        let profile = test_profile();
+        let mut options = flame_cat_protocol::RenderOptions {
+            viewport: Viewport {
+                x: 0.0,
+                y: 0.0,
+                width: 800.0,
+                height: 600.0,
+                dpr: 1.0,
+            },
+            threads: vec![0],
+            ..Default::default()
+        };
+        options.search.query = "CHILD".into();
+        let dimmed: Vec<(u64, bool)> = crate::views::render(&profile, &options)
+            .iter()
+            .filter_map(|c| match c {
+                RenderCommand::DrawRect {
+                    frame_id: Some(id),
+                    dimmed,
+                    ..
+                } => Some((*id, *dimmed)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(dimmed, [(0, true), (1, false)]);
+
+        options.search.case_sensitive = true;
+        let cmds = crate::views::render(&profile, &options);
+        assert!(cmds.iter().all(|c| match c {
+            RenderCommand::DrawRect { dimmed, .. } => *dimmed,
+            _ => true,
+        }));
+        assert!(crate::svg::render_svg(&cmds, 800.0, 600.0, true).contains("fill-opacity"));
+    }
+
     #[test]
     fn empty_profile() {
         let profile = VisualProfile {
@@ -213,6 +746,13 @@ mod tests {
                 start_time: 0.0,
                 end_time: 0.0,
                 time_domain: None,
+                truncated_spans: 0,
+                repairs: Default::default(),
+                limits_hit: Default::default(),
+                partial: None,
+                sample_interval_us: None,
+                description: None,
+                tags: vec![],
             },
             threads: vec![],
             frames: vec![],
@@ -225,6 +765,7 @@ mod tests {
             cpu_samples: None,
             network_requests: vec![],
             screenshots: vec![],
+            binaries: vec![],
         };
         let vp = Viewport {
             x: 0.0,
@@ -235,4 +776,27 @@ mod tests {
         };
         assert!(render_time_order(&profile, &vp, 0.0, 0.0, None).is_empty());
     }
+
+    #[test]
+    fn render_into_reuses_buffer() {
+        let vp = Viewport {
+            x: 0.0,
+            y: 0.0,
+            width: 800.0,
+            height: 600.0,
+            dpr: 1.0,
+        };
+        let profile = test_profile();
+        let mut buf = render_time_order(&profile, &vp, 0.0, 100.0, Some(0));
+        let expected = buf.len();
+        let capacity = buf.capacity();
+
+        render_time_order_into(&mut buf, &profile, &vp, 0.0, 100.0, Some(0));
+        assert_eq!(buf.len(), expected);
+        assert_eq!(buf.capacity(), capacity);
+
+        // An empty window clears stale commands from the previous frame.
+        render_time_order_into(&mut buf, &profile, &vp, 50.0, 50.0, Some(0));
+        assert!(buf.is_empty());
+    }
 }
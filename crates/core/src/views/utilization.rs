@@ -0,0 +1,44 @@
+use flame_cat_protocol::{GroupRole, Rect, RenderCommand, ThemeToken, Viewport};
+
+/// Render busy fractions (0–1, as from
+/// [`busy_fractions`](crate::analysis::utilization::busy_fractions)) as a
+/// compact bar strip spanning the viewport, one bar per bucket.
+pub fn render_utilization_strip(fractions: &[f64], viewport: &Viewport) -> Vec<RenderCommand> {
+    if fractions.is_empty() {
+        return Vec::new();
+    }
+    let bar_w = viewport.width / fractions.len() as f64;
+
+    let mut commands = Vec::with_capacity(fractions.len() + 3);
+    commands.push(RenderCommand::BeginGroup {
+        id: "utilization".into(),
+        label: Some("Utilization".into()),
+        role: GroupRole::Lane,
+    });
+    commands.push(RenderCommand::DrawRect {
+        rect: Rect::new(0.0, 0.0, viewport.width, viewport.height),
+        color: ThemeToken::LaneBackground,
+        border_color: None,
+        label: None,
+        frame_id: None,
+        dimmed: false,
+        span_name: None,
+    });
+    for (i, &fraction) in fractions.iter().enumerate() {
+        if fraction <= 0.0 {
+            continue;
+        }
+        let h = fraction * viewport.height;
+        commands.push(RenderCommand::DrawRect {
+            rect: Rect::new(i as f64 * bar_w, viewport.height - h, bar_w, h),
+            color: ThemeToken::CounterFill,
+            border_color: None,
+            label: None,
+            frame_id: None,
+            dimmed: false,
+            span_name: None,
+        });
+    }
+    commands.push(RenderCommand::EndGroup);
+    commands
+}
@@ -0,0 +1,28 @@
+//! Integration test: parsed fixtures come out with consistent self times.
+
+use flame_cat_core::parsers::parse_auto_visual;
+use flame_cat_core::transform::compute_self_times_checked;
+
+#[test]
+fn fixtures_have_consistent_self_times() {
+    for (name, data) in [
+        (
+            "chrome",
+            &include_bytes!("fixtures/chrome-trace-sample.json")[..],
+        ),
+        (
+            "react",
+            &include_bytes!("fixtures/react-devtools-metronome.json")[..],
+        ),
+    ] {
+        let mut profile = parse_auto_visual(data).expect("fixture parses");
+        let before: Vec<f64> = profile.all_spans().map(|s| s.self_value).collect();
+        let issues = compute_self_times_checked(&mut profile);
+        assert!(issues.is_empty(), "{name}: {issues:?}");
+        let after: Vec<f64> = profile.all_spans().map(|s| s.self_value).collect();
+        assert_eq!(
+            before, after,
+            "{name}: self times already computed on parse"
+        );
+    }
+}
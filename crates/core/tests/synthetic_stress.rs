@@ -0,0 +1,42 @@
+//! Stress test: a large synthetic profile renders within the render limits.
+
+use flame_cat_core::testing::synth::{self, SynthConfig};
+use flame_cat_core::views;
+use flame_cat_protocol::{RenderLimits, RenderOptions, ViewType, Viewport};
+
+#[test]
+fn large_profile_respects_render_limits() {
+    let profile = synth::generate(&SynthConfig {
+        threads: 8,
+        spans_per_thread: 50_000,
+        levels: 64,
+        counters: 4,
+        flows: 1_000,
+        ..Default::default()
+    });
+    for view in [ViewType::TimeOrder, ViewType::LeftHeavy, ViewType::Ranked] {
+        let commands = views::render(
+            &profile,
+            &RenderOptions {
+                view,
+                viewport: Viewport {
+                    x: 0.0,
+                    y: 0.0,
+                    width: 1920.0,
+                    height: 100_000.0,
+                    dpr: 1.0,
+                },
+                limits: RenderLimits {
+                    max_commands: 20_000,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+        );
+        assert!(!commands.is_empty(), "{view:?}");
+        if view != ViewType::Ranked {
+            // Begin/end groups and the "more…" note come on top of the cap.
+            assert!(commands.len() <= 20_010, "{view:?}: {}", commands.len());
+        }
+    }
+}
@@ -0,0 +1,221 @@
+//! Telling the profiled program's own code from its dependencies and the
+//! runtime under both.
+
+use serde::{Deserialize, Serialize};
+
+use crate::{Binary, Span};
+
+/// Whose code a span ran.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CodeClass {
+    /// The profiled program's own code.
+    FirstParty,
+    /// Libraries and frameworks it depends on.
+    Dependency,
+    /// The language runtime, standard library, system libraries and kernel.
+    Runtime,
+}
+
+impl CodeClass {
+    pub const ALL: [Self; 3] = [Self::FirstParty, Self::Dependency, Self::Runtime];
+
+    /// Stable snake_case name, as used by the JS API and session files.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::FirstParty => "first_party",
+            Self::Dependency => "dependency",
+            Self::Runtime => "runtime",
+        }
+    }
+
+    /// Inverse of [`CodeClass::name`].
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "first_party" => Some(Self::FirstParty),
+            "dependency" => Some(Self::Dependency),
+            "runtime" => Some(Self::Runtime),
+            _ => None,
+        }
+    }
+
+    /// Human-readable label.
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::FirstParty => "Your code",
+            Self::Dependency => "Dependencies",
+            Self::Runtime => "Runtime",
+        }
+    }
+}
+
+/// Where package managers put dependency sources.
+const DEPENDENCY_PATHS: &[&str] = &[
+    "node_modules/",
+    ".cargo/registry/",
+    ".cargo/git/",
+    "site-packages/",
+    "dist-packages/",
+    "vendor/",
+    "pkg/mod/",
+    ".m2/repository/",
+    ".gradle/caches/",
+];
+
+/// Name, source and binary prefixes of standard libraries, language
+/// runtimes and system libraries; `(` covers V8's `(garbage collector)`
+/// and `(program)`.
+const RUNTIME_PREFIXES: &[&str] = &[
+    "(",
+    "node:",
+    "native ",
+    "std::",
+    "core::",
+    "alloc::",
+    "java.",
+    "javax.",
+    "jdk.",
+    "sun.",
+    "System.",
+    "Microsoft.",
+    "runtime.",
+    "libc.",
+    "libc-",
+    "libm.",
+    "libpthread",
+    "libstdc++",
+    "libc++",
+    "libdl",
+    "ld-linux",
+    "libsystem_",
+    "libdyld",
+    "libobjc",
+    "[kernel",
+    "[vdso]",
+    "ntdll.dll",
+    "kernel32.dll",
+    "KernelBase.dll",
+    "ucrtbase.dll",
+];
+
+/// How spans are sorted into [`CodeClass`]es. Each list holds prefixes of
+/// span names (`tokio::`, `com.example.`), source paths (`src/`) or
+/// binaries (`libfoo.so`); a prefix also matches after any `/`, so path
+/// prefixes match within URLs and absolute paths.
+///
+/// The lists are tried first-party, dependency, runtime in turn; spans none
+/// of them match fall back to built-in rules for common package managers,
+/// runtimes and system libraries, and otherwise count as first-party.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ClassRules {
+    pub first_party: Vec<String>,
+    pub dependencies: Vec<String>,
+    pub runtime: Vec<String>,
+}
+
+impl ClassRules {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// The class of `span`, which ran in `binary` (see
+    /// [`VisualProfile::binary_of`](crate::VisualProfile::binary_of)).
+    pub fn classify(&self, span: &Span, binary: Option<&Binary>) -> CodeClass {
+        let source = span.category.as_ref().and_then(|c| c.source.as_deref());
+        let binary = binary.map(|b| b.name.as_ref());
+        let texts = [Some(span.name.as_ref()), source, binary];
+        if matches(&texts, &self.first_party) {
+            CodeClass::FirstParty
+        } else if matches(&texts, &self.dependencies) {
+            CodeClass::Dependency
+        } else if matches(&texts, &self.runtime)
+            // Built-in rules from here on.
+            || span.is_kernel()
+            || span
+                .category
+                .as_ref()
+                .is_some_and(|c| c.name.as_ref() == "gc")
+            || matches(&texts, RUNTIME_PREFIXES)
+        {
+            CodeClass::Runtime
+        } else if matches(&texts, DEPENDENCY_PATHS) {
+            CodeClass::Dependency
+        } else {
+            CodeClass::FirstParty
+        }
+    }
+}
+
+/// Whether any of `texts` has any of `prefixes`.
+fn matches(texts: &[Option<&str>], prefixes: &[impl AsRef<str>]) -> bool {
+    prefixes.iter().any(|prefix| {
+        texts
+            .iter()
+            .flatten()
+            .any(|text| has_prefix(text, prefix.as_ref()))
+    })
+}
+
+/// Whether `text` starts with `prefix`, or has it right after a `/`.
+fn has_prefix(text: &str, prefix: &str) -> bool {
+    !prefix.is_empty()
+        && (text.starts_with(prefix)
+            || text
+                .match_indices(prefix)
+                .any(|(i, _)| text[..i].ends_with('/')))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{SpanCategory, SpanKind};
+
+    fn span(name: &str, source: Option<&str>) -> Span {
+        Span {
+            id: 0,
+            name: name.into(),
+            start: 0.0,
+            end: 1.0,
+            depth: 0,
+            parent: None,
+            self_value: 1.0,
+            kind: SpanKind::Sample,
+            category: source.map(|source| SpanCategory {
+                name: "js".into(),
+                source: Some(source.into()),
+            }),
+            truncated: false,
+            binary: None,
+            inlined: vec![],
+        }
+    }
+
+    #[test]
+    fn rules_come_before_built_ins() {
+        let rules = ClassRules {
+            first_party: vec!["myapp::".into(), "vendor/mycorp/".into()],
+            dependencies: vec!["serde::".into()],
+            runtime: vec![],
+        };
+        let class = |name, source| rules.classify(&span(name, source), None);
+        let libc = Binary {
+            name: "/usr/lib/libc.so.6".into(),
+            build_id: None,
+        };
+
+        assert_eq!(class("myapp::run", None), CodeClass::FirstParty);
+        assert_eq!(class("serde::de::from_str", None), CodeClass::Dependency);
+        assert_eq!(
+            rules.classify(&span("memcpy", None), Some(&libc)),
+            CodeClass::Runtime
+        );
+        assert_eq!(class("(garbage collector)", None), CodeClass::Runtime);
+        let url = "https://app.example/node_modules/react-dom/index.js:1:2";
+        assert_eq!(class("render", Some(url)), CodeClass::Dependency);
+        let vendored = "/srv/app/vendor/mycorp/util.js";
+        assert_eq!(class("util", Some(vendored)), CodeClass::FirstParty);
+        let own = "webpack:///src/app.js";
+        assert_eq!(class("handleClick", Some(own)), CodeClass::FirstParty);
+    }
+}
@@ -4,6 +4,18 @@ use crate::shared_str::SharedStr;
 use crate::theme::ThemeToken;
 use crate::types::{Point, Rect};
 
+/// What a [`RenderCommand::BeginGroup`] stands for, so hosts can describe
+/// the canvas to assistive technology.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GroupRole {
+    /// A whole view, such as the time-order chart or the ranked table.
+    #[default]
+    View,
+    /// One track within a view: a thread, counter, marker row and so on.
+    Lane,
+}
+
 /// A single, stateless render instruction.
 ///
 /// The core emits a `Vec<RenderCommand>` for each view. Renderers consume
@@ -18,6 +30,14 @@ pub enum RenderCommand {
         border_color: Option<ThemeToken>,
         label: Option<SharedStr>,
         frame_id: Option<u64>,
+        /// Set on rects whose label does not match the active search, so
+        /// every renderer fades them the same way.
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        dimmed: bool,
+        /// Span name behind a label built from a label template. Color by
+        /// name and category filters key on it rather than on `label`.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        span_name: Option<SharedStr>,
     },
 
     /// Draw a text string at a position.
@@ -54,7 +74,10 @@ pub enum RenderCommand {
     /// batching, layer separation, or accessibility.
     BeginGroup {
         id: SharedStr,
+        /// Human-readable name, such as a thread or counter name.
         label: Option<SharedStr>,
+        #[serde(default)]
+        role: GroupRole,
     },
 
     /// End the current group.
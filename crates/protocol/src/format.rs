@@ -0,0 +1,263 @@
+//! How durations and numbers are written out.
+//!
+//! Tooltips, axis labels, ranked rows, the terminal and Markdown export all
+//! format times through one [`DurationFormat`], so a host that asks for
+//! milliseconds with three decimals and `1.234,5` separators gets exactly
+//! that on every surface.
+
+use serde::{Deserialize, Serialize};
+
+/// Unit durations are written in.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DurationUnit {
+    /// The largest unit the value has at least one of, e.g. `1.5ms`.
+    #[default]
+    Auto,
+    Seconds,
+    Milliseconds,
+    Microseconds,
+    Nanoseconds,
+}
+
+impl DurationUnit {
+    /// Parse a command-line unit: `auto`, `s`, `ms`, `us` (or `µs`) or `ns`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "auto" => Some(Self::Auto),
+            "s" => Some(Self::Seconds),
+            "ms" => Some(Self::Milliseconds),
+            "us" | "µs" => Some(Self::Microseconds),
+            "ns" => Some(Self::Nanoseconds),
+            _ => None,
+        }
+    }
+}
+
+/// Digit grouping and decimal mark.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Separators {
+    /// `1234.5`
+    #[default]
+    Plain,
+    /// `1,234.5`
+    CommaPoint,
+    /// `1.234,5`
+    PointComma,
+    /// `1 234,5`, grouped with a narrow no-break space.
+    SpaceComma,
+}
+
+impl Separators {
+    /// Parse a name as serialized, e.g. `comma_point`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "plain" => Some(Self::Plain),
+            "comma_point" => Some(Self::CommaPoint),
+            "point_comma" => Some(Self::PointComma),
+            "space_comma" => Some(Self::SpaceComma),
+            _ => None,
+        }
+    }
+
+    /// Thousands separator, if digits are grouped, and decimal mark.
+    fn marks(self) -> (Option<char>, char) {
+        match self {
+            Self::Plain => (None, '.'),
+            Self::CommaPoint => (Some(','), '.'),
+            Self::PointComma => (Some('.'), ','),
+            Self::SpaceComma => (Some('\u{202f}'), ','),
+        }
+    }
+}
+
+/// Time units from coarsest to finest, with their length in nanoseconds.
+const SCALES: [(DurationUnit, f64, &str); 4] = [
+    (DurationUnit::Seconds, 1e9, "s"),
+    (DurationUnit::Milliseconds, 1e6, "ms"),
+    (DurationUnit::Microseconds, 1e3, "µs"),
+    (DurationUnit::Nanoseconds, 1.0, "ns"),
+];
+
+/// Default digits after the decimal mark for each of [`SCALES`], per
+/// surface, as each wrote durations before they could be configured.
+/// Values in tables, labels and exports:
+const VALUE_DECIMALS: [usize; 4] = [2, 1, 0, 0];
+/// Tooltips and the detail panel:
+const DETAIL_DECIMALS: [usize; 4] = [2, 2, 1, 1];
+/// Time axis ticks:
+const TICK_DECIMALS: [usize; 4] = [3, 2, 1, 0];
+
+/// Display preferences for durations and other numbers.
+///
+/// The default writes every surface exactly as it was written before
+/// formats were configurable; setting `unit` or `decimals` applies the
+/// same choice everywhere.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DurationFormat {
+    /// Digits after the decimal mark. By default two for seconds, one for
+    /// milliseconds and none for finer units, with a digit more in
+    /// tooltips and on the time axis.
+    pub decimals: Option<u8>,
+    pub unit: DurationUnit,
+    pub separators: Separators,
+}
+
+impl DurationFormat {
+    /// A duration of `us` microseconds, e.g. `1.5ms`.
+    pub fn format_us(&self, us: f64) -> String {
+        self.format_ns(us * 1e3, DurationUnit::Microseconds)
+    }
+
+    /// A duration of `ns` nanoseconds. [`DurationUnit::Auto`] goes no finer
+    /// than `finest`, the resolution the value was recorded at.
+    pub fn format_ns(&self, ns: f64, finest: DurationUnit) -> String {
+        let scale = self.scale(ns, finest);
+        self.write(ns, scale, VALUE_DECIMALS)
+    }
+
+    /// A duration of `us` microseconds as tooltips and the detail panel
+    /// show it, with a digit more than [`format_us`](Self::format_us) by
+    /// default, e.g. `1.50ms`.
+    pub fn format_detail_us(&self, us: f64) -> String {
+        let ns = us * 1e3;
+        let scale = self.scale(ns, DurationUnit::Microseconds);
+        self.write(ns, scale, DETAIL_DECIMALS)
+    }
+
+    /// Time `us` as a time axis tick label, e.g. `1.500s`; from a minute
+    /// on, [`DurationUnit::Auto`] writes minutes and seconds (`2m5.0s`).
+    pub fn format_tick(&self, us: f64) -> String {
+        let ns = us * 1e3;
+        if self.unit == DurationUnit::Auto && ns.abs() >= 60e9 {
+            let mins = (us / 60e6).floor();
+            let secs = (us - mins * 60e6) / 1e6;
+            let decimals = self.decimals.map_or(1, usize::from);
+            return format!("{}m{}s", self.number(mins, 0), self.number(secs, decimals));
+        }
+        // Ticks either side of zero read alike, so pick the unit by size.
+        let scale = self.scale(ns.abs(), DurationUnit::Nanoseconds);
+        self.write(ns, scale, TICK_DECIMALS)
+    }
+
+    /// `ns` in the unit at `SCALES[scale]`, with `defaults` giving the
+    /// digits after the decimal mark when `decimals` is unset.
+    fn write(&self, ns: f64, scale: usize, defaults: [usize; 4]) -> String {
+        let (_, len, suffix) = SCALES[scale];
+        let decimals = self.decimals.map_or(defaults[scale], usize::from);
+        format!("{}{suffix}", self.number(ns / len, decimals))
+    }
+
+    /// `value` with `decimals` digits after the decimal mark, its integer
+    /// part grouped by thousands as the separators ask.
+    pub fn number(&self, value: f64, decimals: usize) -> String {
+        let plain = format!("{value:.decimals$}");
+        let (group, mark) = self.separators.marks();
+        if group.is_none() && mark == '.' {
+            return plain;
+        }
+        let (sign, digits) = match plain.strip_prefix('-') {
+            Some(digits) => ("-", digits),
+            None => ("", plain.as_str()),
+        };
+        let (int, frac) = digits.split_once('.').unwrap_or((digits, ""));
+        let mut out = String::from(sign);
+        for (i, c) in int.chars().enumerate() {
+            if let Some(group) = group
+                && i > 0
+                && (int.len() - i) % 3 == 0
+            {
+                out.push(group);
+            }
+            out.push(c);
+        }
+        if !frac.is_empty() {
+            out.push(mark);
+            out.push_str(frac);
+        }
+        out
+    }
+
+    /// Index into [`SCALES`] of the unit `ns` is shown in: the preferred
+    /// one, or the largest that `ns` has at least one of, down to `finest`.
+    fn scale(&self, ns: f64, finest: DurationUnit) -> usize {
+        let index = |unit| SCALES.iter().position(|&(u, ..)| u == unit);
+        index(self.unit).unwrap_or_else(|| {
+            let finest = index(finest).unwrap_or(SCALES.len() - 1);
+            SCALES[..finest]
+                .iter()
+                .position(|&(_, len, _)| ns >= len)
+                .unwrap_or(finest)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn picks_units_and_decimals() {
+        let auto = DurationFormat::default();
+        assert_eq!(auto.format_us(500.0), "500µs");
+        assert_eq!(auto.format_us(1_500.0), "1.5ms");
+        assert_eq!(auto.format_us(2_500_000.0), "2.50s");
+        assert_eq!(auto.format_us(0.25), "0µs");
+        assert_eq!(auto.format_ns(250.0, DurationUnit::Nanoseconds), "250ns");
+
+        let ms = DurationFormat {
+            decimals: Some(3),
+            unit: DurationUnit::Milliseconds,
+            ..Default::default()
+        };
+        assert_eq!(ms.format_us(500.0), "0.500ms");
+        assert_eq!(ms.format_us(2_500_000.0), "2500.000ms");
+    }
+
+    #[test]
+    fn groups_digits() {
+        let with = |separators| DurationFormat {
+            separators,
+            unit: DurationUnit::Microseconds,
+            decimals: Some(1),
+        };
+        assert_eq!(
+            with(Separators::Plain).format_us(1_234_567.5),
+            "1234567.5µs"
+        );
+        assert_eq!(
+            with(Separators::CommaPoint).format_us(1_234_567.5),
+            "1,234,567.5µs"
+        );
+        assert_eq!(
+            with(Separators::PointComma).format_us(-1_234.5),
+            "-1.234,5µs"
+        );
+        assert_eq!(with(Separators::SpaceComma).number(123.0, 0), "123");
+    }
+
+    #[test]
+    fn surfaces_keep_their_own_precision() {
+        let auto = DurationFormat::default();
+        assert_eq!(auto.format_detail_us(500.0), "500.0µs");
+        assert_eq!(auto.format_detail_us(1_500.0), "1.50ms");
+        assert_eq!(auto.format_detail_us(0.25), "0.2µs");
+
+        assert_eq!(auto.format_tick(500.0), "500.0µs");
+        assert_eq!(auto.format_tick(1_500.0), "1.50ms");
+        assert_eq!(auto.format_tick(-1_500.0), "-1.50ms");
+        assert_eq!(auto.format_tick(0.5), "500ns");
+        assert_eq!(auto.format_tick(1_500_000.0), "1.500s");
+        assert_eq!(auto.format_tick(90_000_000.0), "1m30.0s");
+
+        let ms = DurationFormat {
+            decimals: Some(1),
+            unit: DurationUnit::Milliseconds,
+            ..Default::default()
+        };
+        assert_eq!(ms.format_detail_us(500.0), "0.5ms");
+        assert_eq!(ms.format_tick(90_000_000.0), "90000.0ms");
+    }
+}
@@ -1,23 +1,33 @@
+pub mod code_class;
 pub mod commands;
+pub mod format;
+pub mod render_options;
 pub mod shared_str;
 pub mod theme;
 pub mod types;
 pub mod visual_profile;
 
-pub use commands::{RenderCommand, TextAlign};
+pub use code_class::{ClassRules, CodeClass};
+pub use commands::{GroupRole, RenderCommand, TextAlign};
+pub use format::{DurationFormat, DurationUnit, Separators};
+pub use render_options::{
+    ColorMode, HeatScale, LodOptions, RENDER_OPTIONS_VERSION, RenderLimits, RenderOptions,
+    SearchOptions, TimeRange, ViewType, color_hue, color_key, color_rgb,
+};
 pub use shared_str::SharedStr;
-pub use theme::ThemeToken;
+pub use theme::{ThemeToken, heat_rgb};
 pub use types::{ClockKind, Color, Point, Rect, TimeDomain};
 pub use visual_profile::{
-    AsyncSpan, CounterSample, CounterTrack, CounterUnit, CpuNode, CpuSamples, FlowArrow,
-    FrameTiming, InstantEvent, Marker, MarkerScope, NetworkRequest, ObjectEvent, ObjectPhase,
-    ProfileMeta, Screenshot, SourceFormat, Span, SpanCategory, SpanKind, ThreadGroup, ValueUnit,
+    AsyncSpan, Binary, CounterSample, CounterTrack, CounterUnit, CpuNode, CpuSamples, FlowArrow,
+    FrameTiming, InstantEvent, LimitsHit, Marker, MarkerScope, NetworkRequest, ObjectEvent,
+    ObjectPhase, PartialParse, ProfileMeta, ProfileMetaEdit, Screenshot, SourceFormat, Span,
+    SpanCategory, SpanFlow, SpanKind, SpanRepairs, ThreadGroup, TrackKind, ValueUnit,
     VisualProfile,
 };
 
 /// Viewport describing the visible region — passed to view transforms so
 /// they can cull off-screen frames and compute pixel-space coordinates.
-#[derive(Debug, Clone, Copy, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
 pub struct Viewport {
     pub x: f64,
     pub y: f64,
@@ -26,3 +36,80 @@ pub struct Viewport {
     /// Device pixel ratio (1.0, 2.0, 3.0 …)
     pub dpr: f64,
 }
+
+impl Viewport {
+    /// Device pixels per logical pixel; nonsensical ratios count as 1.
+    fn scale(&self) -> f64 {
+        if self.dpr.is_finite() && self.dpr > 0.0 {
+            self.dpr
+        } else {
+            1.0
+        }
+    }
+
+    /// Logical coordinate `v` moved onto the nearest device pixel edge.
+    pub fn snap(&self, v: f64) -> f64 {
+        let scale = self.scale();
+        (v * scale).round() / scale
+    }
+
+    /// Width of one device pixel in logical pixels, for crisp 1px lines
+    /// and gaps on high-density displays.
+    pub fn hairline(&self) -> f64 {
+        1.0 / self.scale()
+    }
+
+    /// `rect` with every edge snapped to a device pixel. A rect that
+    /// would collapse keeps one device pixel of width or height.
+    pub fn snap_rect(&self, rect: Rect) -> Rect {
+        let edges = |from: f64, len: f64| {
+            let start = self.snap(from);
+            let end = self.snap(from + len);
+            let len = if len > 0.0 {
+                (end - start).max(self.hairline())
+            } else {
+                0.0
+            };
+            (start, len)
+        };
+        let (x, w) = edges(rect.x, rect.w);
+        let (y, h) = edges(rect.y, rect.h);
+        Rect { x, y, w, h }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn viewport(dpr: f64) -> Viewport {
+        Viewport {
+            x: 0.0,
+            y: 0.0,
+            width: 800.0,
+            height: 600.0,
+            dpr,
+        }
+    }
+
+    #[test]
+    fn snaps_to_device_pixels() {
+        let rect = Rect::new(10.3, 4.6, 0.1, 19.0);
+        assert_eq!(
+            viewport(1.0).snap_rect(rect),
+            Rect::new(10.0, 5.0, 1.0, 19.0)
+        );
+        assert_eq!(
+            viewport(2.0).snap_rect(rect),
+            Rect::new(10.5, 4.5, 0.5, 19.0)
+        );
+        let third = viewport(3.0).snap_rect(rect);
+        assert_eq!((third.x * 3.0, third.w * 3.0), (31.0, 1.0));
+        assert_eq!(viewport(3.0).hairline(), 1.0 / 3.0);
+        assert_eq!(viewport(0.0).snap(2.4), 2.0);
+        assert_eq!(
+            viewport(1.0).snap_rect(Rect::new(1.0, 1.0, 0.0, 2.0)).w,
+            0.0
+        );
+    }
+}
@@ -0,0 +1,344 @@
+//! Render configuration shared by every view entry point.
+//!
+//! Hosts build one [`RenderOptions`] (natively or as JSON) instead of
+//! threading viewport, range, thread filter and styling through positional
+//! arguments. New fields must have a serde default so older payloads keep
+//! parsing; bump [`RENDER_OPTIONS_VERSION`] only when a field changes meaning.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{ClassRules, CodeClass, DurationFormat, Viewport};
+
+/// Newest [`RenderOptions::version`] this build understands.
+pub const RENDER_OPTIONS_VERSION: u32 = 1;
+
+/// Active visualization mode.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ViewType {
+    #[default]
+    TimeOrder,
+    LeftHeavy,
+    Sandwich,
+    Ranked,
+    Icicle,
+}
+
+impl ViewType {
+    /// Stable snake_case name, as used by the JS API and session files.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::TimeOrder => "time_order",
+            Self::LeftHeavy => "left_heavy",
+            Self::Sandwich => "sandwich",
+            Self::Ranked => "ranked",
+            Self::Icicle => "icicle",
+        }
+    }
+
+    /// Inverse of [`ViewType::name`].
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "time_order" => Some(Self::TimeOrder),
+            "left_heavy" => Some(Self::LeftHeavy),
+            "sandwich" => Some(Self::Sandwich),
+            "ranked" => Some(Self::Ranked),
+            "icicle" => Some(Self::Icicle),
+            _ => None,
+        }
+    }
+}
+
+/// How span rectangles are colored.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ColorMode {
+    /// Hash the span label into a consistent hue (color-by-package).
+    #[default]
+    #[serde(rename = "by_name")]
+    ByName,
+    /// Use theme token from render command (depth-based cycling).
+    #[serde(rename = "by_depth", alias = "theme")]
+    ByDepth,
+    /// Heat ramp by span duration, scaled by [`HeatScale`].
+    #[serde(rename = "by_duration")]
+    ByDuration,
+    /// Heat ramp by span self time, scaled by [`HeatScale`].
+    #[serde(rename = "by_self_time")]
+    BySelfTime,
+}
+
+impl ColorMode {
+    /// Name used by the JS API and session files.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::ByName => "by_name",
+            Self::ByDepth => "by_depth",
+            Self::ByDuration => "by_duration",
+            Self::BySelfTime => "by_self_time",
+        }
+    }
+
+    /// Parse a JS/session name, defaulting to [`ColorMode::ByName`].
+    pub fn from_name(name: &str) -> Self {
+        match name {
+            "by_depth" | "theme" => Self::ByDepth,
+            "by_duration" => Self::ByDuration,
+            "by_self_time" => Self::BySelfTime,
+            _ => Self::ByName,
+        }
+    }
+
+    /// Whether views bake the colors in as [`ThemeToken::Heat`](crate::ThemeToken::Heat).
+    pub fn is_heat(self) -> bool {
+        matches!(self, Self::ByDuration | Self::BySelfTime)
+    }
+}
+
+/// What the heat color modes compare a span's value with.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HeatScale {
+    /// The largest value among spans with the same parent, so the heaviest
+    /// sibling is hottest.
+    #[default]
+    Siblings,
+    /// A fixed logarithmic scale from 1µs to 1s (or up to the profile total
+    /// for values that are not times).
+    Absolute,
+}
+
+impl HeatScale {
+    /// Name used by the JS API.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Siblings => "siblings",
+            Self::Absolute => "absolute",
+        }
+    }
+
+    /// Inverse of [`HeatScale::name`].
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "siblings" => Some(Self::Siblings),
+            "absolute" => Some(Self::Absolute),
+            _ => None,
+        }
+    }
+}
+
+/// Part of a span label that [`ColorMode::ByName`] colors by: the package
+/// or module prefix before the first `:`, `.`, `/`, `@` or `\`.
+pub fn color_key(label: &str) -> &str {
+    label
+        .split([':', '.', '/', '@', '\\'])
+        .next()
+        .unwrap_or(label)
+}
+
+/// Hue in degrees (0–359) that [`ColorMode::ByName`] gives a color key.
+pub fn color_hue(key: &str) -> f32 {
+    let mut hash: u32 = 5381;
+    for b in key.bytes() {
+        hash = hash.wrapping_mul(33).wrapping_add(u32::from(b));
+    }
+    (hash % 360) as f32
+}
+
+/// Fill that [`ColorMode::ByName`] gives a color key, as RGB, with
+/// Perfetto-inspired saturation and lightness for the dark or light theme.
+pub fn color_rgb(key: &str, dark: bool) -> (u8, u8, u8) {
+    let (s, l) = if dark { (0.60, 0.50) } else { (0.55, 0.58) };
+    let h = color_hue(key);
+    let c = (1.0 - (2.0 * l - 1.0_f32).abs()) * s;
+    let x = c * (1.0 - ((h / 60.0) % 2.0 - 1.0).abs());
+    let m = l - c / 2.0;
+    let (r, g, b) = match (h as u32) / 60 {
+        0 => (c, x, 0.0),
+        1 => (x, c, 0.0),
+        2 => (0.0, c, x),
+        3 => (0.0, x, c),
+        4 => (x, 0.0, c),
+        _ => (c, 0.0, x),
+    };
+    let channel = |v: f32| ((v + m) * 255.0) as u8;
+    (channel(r), channel(g), channel(b))
+}
+
+/// Absolute time window in µs, in the profile's own clock.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct TimeRange {
+    pub start: f64,
+    pub end: f64,
+}
+
+/// Level-of-detail culling.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LodOptions {
+    /// Time-order spans narrower than this many pixels are not drawn.
+    pub min_span_px: f64,
+    /// Draw the functions inlined into a time-order span on rows of their
+    /// own beneath it, instead of folding them into its label.
+    pub expand_inlined: bool,
+}
+
+impl Default for LodOptions {
+    fn default() -> Self {
+        Self {
+            min_span_px: 0.5,
+            expand_inlined: false,
+        }
+    }
+}
+
+/// Guards that keep a pathological profile (say, a million spans hundreds
+/// of levels deep) from stalling the renderer. Spans past either limit are
+/// summarized by an "N more…" label instead of drawn.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RenderLimits {
+    /// Deepest stack level drawn; top-level spans are depth 0.
+    pub max_depth: u32,
+    /// Most render commands emitted by one render call.
+    pub max_commands: usize,
+}
+
+impl Default for RenderLimits {
+    fn default() -> Self {
+        Self {
+            max_depth: 256,
+            max_commands: 200_000,
+        }
+    }
+}
+
+/// Span search: rects whose label does not contain `query`
+/// (case-insensitively unless `case_sensitive`) are emitted `dimmed`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SearchOptions {
+    pub query: String,
+    pub case_sensitive: bool,
+    /// Also dim rects whose [`color_key`] differs from this one.
+    pub category: Option<String>,
+    /// Also dim rects of spans that did not run in the binary of this name
+    /// (see [`Binary`](crate::Binary)).
+    pub binary: Option<String>,
+    /// Also dim rects of spans not of this class under `class_rules`.
+    pub code_class: Option<CodeClass>,
+    pub class_rules: ClassRules,
+}
+
+/// Everything a view needs besides the profile itself.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RenderOptions {
+    /// Schema version the payload was written against.
+    pub version: u32,
+    pub view: ViewType,
+    pub viewport: Viewport,
+    /// Visible window; the whole profile when absent. Ignored by the
+    /// aggregate views (left heavy, icicle, sandwich, ranked).
+    pub range: Option<TimeRange>,
+    /// Thread ids to include; empty means all threads.
+    pub threads: Vec<u32>,
+    /// Span the sandwich view is centered on.
+    pub selected_frame: Option<u64>,
+    pub color_mode: ColorMode,
+    pub heat_scale: HeatScale,
+    pub lod: LodOptions,
+    pub limits: RenderLimits,
+    pub search: SearchOptions,
+    /// Span label template per view, e.g. `"{name} ({dur})"`; views
+    /// without one label spans by name.
+    pub label_templates: BTreeMap<ViewType, String>,
+    /// How times in labels and tables are written.
+    pub format: DurationFormat,
+    /// Merged subtrees under this share of the total, 0–100, collapse into
+    /// one "other (N frames)" block in the left-heavy, icicle and sandwich
+    /// views. 0 keeps every frame.
+    pub prune_percent: f64,
+    /// Leave out spans in the [`SpanCategory::KERNEL`](crate::SpanCategory::KERNEL)
+    /// category, as when only the program's own code is of interest.
+    pub hide_kernel: bool,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        Self {
+            version: RENDER_OPTIONS_VERSION,
+            view: ViewType::default(),
+            viewport: Viewport {
+                x: 0.0,
+                y: 0.0,
+                width: 0.0,
+                height: 0.0,
+                dpr: 1.0,
+            },
+            range: None,
+            threads: Vec::new(),
+            selected_frame: None,
+            color_mode: ColorMode::default(),
+            heat_scale: HeatScale::default(),
+            lod: LodOptions::default(),
+            limits: RenderLimits::default(),
+            search: SearchOptions::default(),
+            label_templates: BTreeMap::new(),
+            format: DurationFormat::default(),
+            prune_percent: 0.0,
+            hide_kernel: false,
+        }
+    }
+}
+
+impl RenderOptions {
+    /// Whether this build can honor the payload's schema version.
+    pub fn is_supported(&self) -> bool {
+        self.version <= RENDER_OPTIONS_VERSION
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn partial_json_fills_defaults() {
+        let opts: RenderOptions = serde_json::from_str(
+            r#"{"view":"icicle","viewport":{"x":0,"y":0,"width":800,"height":600,"dpr":2},
+                "threads":[3],"color_mode":"theme","search":{"query":"Paint"},
+                "label_templates":{"time_order":"{name} ({dur})"}}"#,
+        )
+        .expect("valid options");
+        assert_eq!(opts.version, RENDER_OPTIONS_VERSION);
+        assert!(opts.is_supported());
+        assert_eq!(opts.view, ViewType::Icicle);
+        assert_eq!(opts.color_mode, ColorMode::ByDepth);
+        assert_eq!(opts.lod.min_span_px, 0.5);
+        assert_eq!(opts.limits, RenderLimits::default());
+        assert_eq!(opts.threads, [3]);
+        assert_eq!(opts.search.query, "Paint");
+        assert_eq!(opts.search.category, None);
+        assert_eq!(opts.format, DurationFormat::default());
+        assert_eq!(
+            opts.label_templates
+                .get(&ViewType::TimeOrder)
+                .map(String::as_str),
+            Some("{name} ({dur})")
+        );
+
+        let future: RenderOptions = serde_json::from_str(r#"{"version":99}"#).expect("valid");
+        assert!(!future.is_supported());
+    }
+
+    #[test]
+    fn color_key_is_package_prefix() {
+        assert_eq!(color_key("react-dom.development.js"), "react-dom");
+        assert_eq!(color_key("std::vec::Vec::push"), "std");
+        assert_eq!(color_key("main"), "main");
+        assert_eq!(color_hue("std"), color_hue(color_key("std::fmt")));
+        assert!(color_hue("std") < 360.0);
+    }
+}
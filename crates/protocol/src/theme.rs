@@ -52,6 +52,9 @@ pub enum ThemeToken {
     CounterFill,
     CounterLine,
     CounterText,
+    /// Shading behind counter samples that break the counter's budget.
+    CounterBudgetExceeded,
+    CounterBudgetLine,
 
     // Markers / navigation timing
     MarkerLine,
@@ -61,6 +64,16 @@ pub enum ThemeToken {
     AsyncSpanFill,
     AsyncSpanBorder,
 
+    // GPU queue tracks
+    GpuSpanBorder,
+
+    /// Kernel frames of system profiles, kept apart from user code.
+    KernelSpan,
+
+    /// Translucent spans of a second run laid over the first.
+    OverlaySpanFill,
+    OverlaySpanBorder,
+
     // Frame cost track
     FrameGood,
     FrameWarning,
@@ -69,4 +82,32 @@ pub enum ThemeToken {
     // Flow arrows
     FlowArrow,
     FlowArrowHead,
+
+    /// Point on the heat ramp, from 0 (coldest) to 255 (hottest); see
+    /// [`heat_rgb`].
+    Heat(u8),
+}
+
+/// Viridis stops, evenly spaced from cold to hot.
+const HEAT_STOPS: [(u8, u8, u8); 9] = [
+    (0x44, 0x01, 0x54),
+    (0x47, 0x2d, 0x7b),
+    (0x3b, 0x52, 0x8b),
+    (0x2c, 0x72, 0x8e),
+    (0x21, 0x91, 0x8c),
+    (0x28, 0xae, 0x80),
+    (0x5e, 0xc9, 0x62),
+    (0xad, 0xdc, 0x30),
+    (0xfd, 0xe7, 0x25),
+];
+
+/// Color of [`ThemeToken::Heat`] `level` on a perceptually uniform ramp
+/// (viridis), the same in light and dark themes.
+pub fn heat_rgb(level: u8) -> (u8, u8, u8) {
+    let pos = f32::from(level) / 255.0 * (HEAT_STOPS.len() - 1) as f32;
+    let i = (pos as usize).min(HEAT_STOPS.len() - 2);
+    let t = pos - i as f32;
+    let (a, b) = (HEAT_STOPS[i], HEAT_STOPS[i + 1]);
+    let mix = |x: u8, y: u8| (f32::from(x) + (f32::from(y) - f32::from(x)) * t).round() as u8;
+    (mix(a.0, b.0), mix(a.1, b.1), mix(a.2, b.2))
 }
@@ -1,3 +1,4 @@
+use crate::format::{DurationFormat, DurationUnit};
 use crate::shared_str::SharedStr;
 use crate::types::TimeDomain;
 use serde::{Deserialize, Serialize};
@@ -59,6 +60,9 @@ pub struct VisualProfile {
     /// Screenshot snapshots for filmstrip view.
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub screenshots: Vec<Screenshot>,
+    /// Binaries spans were sampled in, indexed by [`Span::binary`].
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub binaries: Vec<Binary>,
 }
 
 /// Top-level metadata about the profile.
@@ -78,6 +82,179 @@ pub struct ProfileMeta {
     pub end_time: f64,
     /// Clock domain metadata for cross-profile alignment.
     pub time_domain: Option<TimeDomain>,
+    /// Number of spans that were still open when the trace ended.
+    #[serde(default)]
+    pub truncated_spans: usize,
+    /// Malformed spans repaired while building the profile.
+    #[serde(default)]
+    pub repairs: SpanRepairs,
+    /// Input left out because it broke the parse limits.
+    #[serde(default)]
+    pub limits_hit: LimitsHit,
+    /// Where parsing stopped, when a truncated or corrupt input was
+    /// recovered in part.
+    #[serde(default)]
+    pub partial: Option<PartialParse>,
+    /// Time between samples (µs) for sample-count profiles, when known.
+    /// Lets sample counts be shown as estimated time.
+    #[serde(default)]
+    pub sample_interval_us: Option<f64>,
+    /// Free-form note added by the user after loading.
+    #[serde(default)]
+    pub description: Option<SharedStr>,
+    /// User-assigned labels, e.g. to tell apart many loaded profiles.
+    #[serde(default)]
+    pub tags: Vec<SharedStr>,
+}
+
+impl ProfileMeta {
+    /// Apply a user edit. Blank names and descriptions clear the field;
+    /// tags are trimmed, and blank or repeated ones dropped. A sampling
+    /// interval that is not positive clears it.
+    pub fn apply_edit(&mut self, edit: &ProfileMetaEdit) {
+        fn non_blank(s: &str) -> Option<SharedStr> {
+            let s = s.trim();
+            (!s.is_empty()).then(|| SharedStr::from(s))
+        }
+        if let Some(name) = &edit.name {
+            self.name = non_blank(name);
+        }
+        if let Some(description) = &edit.description {
+            self.description = non_blank(description);
+        }
+        if let Some(tags) = &edit.tags {
+            self.tags.clear();
+            for tag in tags.iter().filter_map(|t| non_blank(t)) {
+                if !self.tags.contains(&tag) {
+                    self.tags.push(tag);
+                }
+            }
+        }
+        if let Some(interval) = edit.sample_interval_us {
+            self.sample_interval_us = (interval > 0.0).then_some(interval);
+        }
+    }
+
+    /// Format a span value for display. Sample counts are shown with an
+    /// estimated duration when the sampling interval is known.
+    pub fn format_value(&self, value: f64) -> String {
+        self.format_value_with(value, &DurationFormat::default())
+    }
+
+    /// [`format_value`](Self::format_value) as `format` asks.
+    pub fn format_value_with(&self, value: f64, format: &DurationFormat) -> String {
+        match (self.value_unit, self.sample_interval_us) {
+            (ValueUnit::Samples, Some(interval)) if interval > 0.0 => format!(
+                "{} (~{})",
+                self.value_unit.format_value_with(value, format),
+                format.format_us(value * interval)
+            ),
+            _ => self.value_unit.format_value_with(value, format),
+        }
+    }
+
+    /// [`format_value`](Self::format_value) followed by the change from
+    /// `baseline`, e.g. `12.3ms (+4.1ms vs baseline)`.
+    pub fn format_delta(&self, value: f64, baseline: f64) -> String {
+        self.format_delta_with(value, baseline, &DurationFormat::default())
+    }
+
+    /// [`format_delta`](Self::format_delta) as `format` asks.
+    pub fn format_delta_with(&self, value: f64, baseline: f64, format: &DurationFormat) -> String {
+        let delta = value - baseline;
+        let sign = if delta < 0.0 { '-' } else { '+' };
+        format!(
+            "{} ({sign}{} vs baseline)",
+            self.format_value_with(value, format),
+            self.value_unit.format_value_with(delta.abs(), format)
+        )
+    }
+}
+
+/// A change to a profile's user-editable metadata. Fields left `None` are
+/// not touched.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProfileMetaEdit {
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub tags: Option<Vec<String>>,
+    /// Time between samples (µs), for formats that don't record it.
+    pub sample_interval_us: Option<f64>,
+}
+
+/// Counts of malformed spans fixed up before layout.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SpanRepairs {
+    /// Spans whose end preceded their start; the end was clamped to the start.
+    pub negative_durations: usize,
+    /// Spans extending outside their parent, clamped to the parent's range.
+    pub clamped_to_parent: usize,
+    /// Spans overlapping an earlier sibling at the same depth: nested under
+    /// it when fully contained, otherwise trimmed to start where it ends.
+    pub overlapping_siblings: usize,
+    /// Spans whose recorded depth or parent did not match the span tree.
+    pub redepthed: usize,
+}
+
+impl SpanRepairs {
+    /// Total number of repairs of any kind.
+    pub fn total(&self) -> usize {
+        self.negative_durations
+            + self.clamped_to_parent
+            + self.overlapping_siblings
+            + self.redepthed
+    }
+}
+
+impl std::ops::AddAssign for SpanRepairs {
+    fn add_assign(&mut self, other: Self) {
+        self.negative_durations += other.negative_durations;
+        self.clamped_to_parent += other.clamped_to_parent;
+        self.overlapping_siblings += other.overlapping_siblings;
+        self.redepthed += other.redepthed;
+    }
+}
+
+/// What a limited parse left out; all zero when the whole input loaded.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LimitsHit {
+    /// Input bytes past the memory budget that were never parsed.
+    pub bytes_skipped: usize,
+    /// Spans dropped once the event limit was reached.
+    pub events_dropped: usize,
+    /// Spans dropped for sitting deeper than the depth limit.
+    pub too_deep: usize,
+    /// Span names cut to the string length limit.
+    pub strings_cut: usize,
+}
+
+impl LimitsHit {
+    /// Whether anything was left out, making the profile partial.
+    pub fn any(&self) -> bool {
+        *self != Self::default()
+    }
+}
+
+impl std::ops::AddAssign for LimitsHit {
+    fn add_assign(&mut self, other: Self) {
+        self.bytes_skipped += other.bytes_skipped;
+        self.events_dropped += other.events_dropped;
+        self.too_deep += other.too_deep;
+        self.strings_cut += other.strings_cut;
+    }
+}
+
+/// How far a recovering parse got into a truncated or corrupt input.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PartialParse {
+    /// Complete events kept from before the damage.
+    pub events: usize,
+    /// Latest timestamp among the kept events; nothing after it was read.
+    pub cut_off: f64,
+    /// Why parsing stopped.
+    pub error: SharedStr,
 }
 
 /// The original profiling format — informational only.
@@ -93,6 +270,33 @@ pub enum SourceFormat {
     Tracy,
     Pix,
     Ebpf,
+    /// Spans recorded from Rust `tracing` instrumentation.
+    Tracing,
+    TokioConsole,
+    Perfetto,
+    /// Linux `perf record` output.
+    Perf,
+    /// Java Flight Recorder recording.
+    Jfr,
+    /// .NET EventPipe trace (`dotnet-trace`).
+    Nettrace,
+    /// Windows Performance Analyzer table export of an ETW trace.
+    Wpa,
+    /// Instruments Time Profiler table from `xctrace export`.
+    Instruments,
+    /// Android systrace HTML or raw atrace text.
+    Systrace,
+    /// Kernel ftrace text or `trace-cmd report` output.
+    Ftrace,
+    /// Valgrind callgrind profile (`callgrind.out.<pid>`).
+    Callgrind,
+    /// Valgrind massif heap profile (`massif.out.<pid>`).
+    Massif,
+    /// Valgrind DHAT JSON output (`dhat.out.<pid>`).
+    Dhat,
+    /// heaptrack recording (`heaptrack.<app>.<pid>.gz`) or its
+    /// `heaptrack_print -F` flamegraph export.
+    Heaptrack,
     Unknown,
 }
 
@@ -109,6 +313,20 @@ impl std::fmt::Display for SourceFormat {
             Self::Tracy => write!(f, "Tracy"),
             Self::Pix => write!(f, "PIX"),
             Self::Ebpf => write!(f, "eBPF"),
+            Self::Tracing => write!(f, "Rust tracing"),
+            Self::TokioConsole => write!(f, "tokio-console"),
+            Self::Perfetto => write!(f, "Perfetto"),
+            Self::Perf => write!(f, "perf"),
+            Self::Jfr => write!(f, "JFR"),
+            Self::Nettrace => write!(f, "nettrace"),
+            Self::Wpa => write!(f, "WPA"),
+            Self::Instruments => write!(f, "Instruments"),
+            Self::Systrace => write!(f, "systrace"),
+            Self::Ftrace => write!(f, "ftrace"),
+            Self::Callgrind => write!(f, "callgrind"),
+            Self::Massif => write!(f, "massif"),
+            Self::Dhat => write!(f, "DHAT"),
+            Self::Heaptrack => write!(f, "heaptrack"),
             Self::Unknown => write!(f, "Unknown"),
         }
     }
@@ -129,63 +347,52 @@ pub enum ValueUnit {
     Bytes,
     /// Arbitrary weight (custom profilers).
     Weight,
+    /// Executed instructions (Valgrind callgrind).
+    Instructions,
 }
 
 impl ValueUnit {
     /// Format a value in this unit for display.
     pub fn format_value(&self, value: f64) -> String {
+        self.format_value_with(value, &DurationFormat::default())
+    }
+
+    /// [`format_value`](Self::format_value) as `format` asks. Times go
+    /// through [`DurationFormat::format_ns`]; other units keep their own
+    /// scale and take only the separators.
+    pub fn format_value_with(&self, value: f64, format: &DurationFormat) -> String {
         match self {
-            Self::Microseconds => {
-                if value >= 1_000_000.0 {
-                    format!("{:.2}s", value / 1_000_000.0)
-                } else if value >= 1_000.0 {
-                    format!("{:.1}ms", value / 1_000.0)
-                } else {
-                    format!("{:.0}µs", value)
-                }
-            }
-            Self::Milliseconds => {
-                if value >= 1_000.0 {
-                    format!("{:.2}s", value / 1_000.0)
-                } else {
-                    format!("{:.1}ms", value)
-                }
-            }
-            Self::Nanoseconds => {
-                if value >= 1_000_000_000.0 {
-                    format!("{:.2}s", value / 1_000_000_000.0)
-                } else if value >= 1_000_000.0 {
-                    format!("{:.1}ms", value / 1_000_000.0)
-                } else if value >= 1_000.0 {
-                    format!("{:.0}µs", value / 1_000.0)
-                } else {
-                    format!("{:.0}ns", value)
-                }
-            }
-            Self::Samples => format!("{} samples", value as u64),
+            Self::Microseconds => format.format_ns(value * 1e3, DurationUnit::Microseconds),
+            Self::Milliseconds => format.format_ns(value * 1e6, DurationUnit::Milliseconds),
+            Self::Nanoseconds => format.format_ns(value, DurationUnit::Nanoseconds),
+            Self::Samples => format!("{} samples", format.number((value as u64) as f64, 0)),
             Self::Bytes => {
                 if value >= 1_073_741_824.0 {
-                    format!("{:.1} GiB", value / 1_073_741_824.0)
+                    format!("{} GiB", format.number(value / 1_073_741_824.0, 1))
                 } else if value >= 1_048_576.0 {
-                    format!("{:.1} MiB", value / 1_048_576.0)
+                    format!("{} MiB", format.number(value / 1_048_576.0, 1))
                 } else if value >= 1_024.0 {
-                    format!("{:.1} KiB", value / 1_024.0)
+                    format!("{} KiB", format.number(value / 1_024.0, 1))
                 } else {
                     format!("{} B", value as u64)
                 }
             }
-            Self::Weight => format!("{:.0}", value),
+            Self::Weight => format.number(value, 0),
+            Self::Instructions => {
+                format!("{} instructions", format.number((value as u64) as f64, 0))
+            }
         }
     }
 
     /// Factor to multiply a value in this unit by to get microseconds.
-    /// Returns `None` for non-time units (Samples, Bytes, Weight).
+    /// Returns `None` for non-time units (Samples, Bytes, Weight,
+    /// Instructions).
     pub fn to_microseconds_factor(&self) -> Option<f64> {
         match self {
             Self::Microseconds => Some(1.0),
             Self::Milliseconds => Some(1_000.0),
             Self::Nanoseconds => Some(0.001),
-            Self::Samples | Self::Bytes | Self::Weight => None,
+            Self::Samples | Self::Bytes | Self::Weight | Self::Instructions => None,
         }
     }
 }
@@ -197,8 +404,20 @@ pub struct ThreadGroup {
     pub id: u32,
     /// Display name ("Main Thread", "Renderer", "GC", etc.).
     pub name: SharedStr,
+    /// Whether this is a CPU thread or a GPU queue timeline.
+    #[serde(default)]
+    pub kind: TrackKind,
     /// Process/thread identifiers from the source format.
     pub sort_key: i64,
+    /// Source process id, for formats that record one.
+    #[serde(default)]
+    pub pid: Option<u64>,
+    /// Source thread id, for formats that record one. Only unique within `pid`.
+    #[serde(default)]
+    pub tid: Option<u64>,
+    /// Name of the owning process (Chrome `process_name` metadata).
+    #[serde(default)]
+    pub process_name: Option<SharedStr>,
     /// All spans in this thread, ordered by start time.
     pub spans: Vec<Span>,
     /// Cached maximum span depth (0 if empty). Set by `compute_max_depth()`.
@@ -206,6 +425,31 @@ pub struct ThreadGroup {
     pub max_depth: u32,
 }
 
+/// What a [`ThreadGroup`]'s timeline records.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TrackKind {
+    /// Work on a CPU thread.
+    #[default]
+    Cpu,
+    /// Work executing on a GPU queue (PIX queues, Perfetto GPU tracks,
+    /// Tracy GPU contexts).
+    Gpu,
+}
+
+impl TrackKind {
+    /// Guess the kind of a track from its name: queue timelines are named
+    /// like "GPU", "Direct Queue" or "VkQueue 0". Thread names that merely
+    /// contain "Gpu", like Chrome's `CrGpuMain`, stay CPU threads.
+    pub fn from_track_name(name: &str) -> Self {
+        let gpu = name
+            .split(|c: char| !c.is_ascii_alphanumeric())
+            .map(str::to_ascii_lowercase)
+            .any(|word| word == "gpu" || word.ends_with("queue"));
+        if gpu { Self::Gpu } else { Self::Cpu }
+    }
+}
+
 impl ThreadGroup {
     /// Compute and cache `max_depth` from spans. Call after populating spans.
     pub fn compute_max_depth(&mut self) {
@@ -240,6 +484,19 @@ pub struct Span {
     pub kind: SpanKind,
     /// Optional semantic category for grouping and coloring.
     pub category: Option<SpanCategory>,
+    /// The span never ended in the source data (e.g. a `B` event with no
+    /// matching `E` in a cut-off trace) and was closed at the trace end.
+    #[serde(default)]
+    pub truncated: bool,
+    /// Index into [`VisualProfile::binaries`] of the executable or library
+    /// the code ran in, for formats that record mappings.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub binary: Option<u32>,
+    /// Functions the compiler inlined into this one, outermost first. The
+    /// span's `name` is the function the code physically lives in; the
+    /// last entry is where its self time was actually spent.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub inlined: Vec<SharedStr>,
 }
 
 impl Span {
@@ -247,6 +504,13 @@ impl Span {
     pub fn duration(&self) -> f64 {
         self.end - self.start
     }
+
+    /// Whether the span is a frame of the operating system kernel.
+    pub fn is_kernel(&self) -> bool {
+        self.category
+            .as_ref()
+            .is_some_and(|c| c.name.as_ref() == SpanCategory::KERNEL)
+    }
 }
 
 /// How a span was produced — affects how views interpret it.
@@ -260,6 +524,16 @@ pub enum SpanKind {
     Synthetic,
 }
 
+/// An executable or shared library mapped into the profiled process.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Binary {
+    /// File name as recorded, e.g. `/usr/lib/libc.so.6` or `[kernel.kallsyms]`.
+    pub name: SharedStr,
+    /// Build id, to tell two builds of one file apart.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub build_id: Option<SharedStr>,
+}
+
 /// Semantic categories for coloring and grouping.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SpanCategory {
@@ -269,6 +543,13 @@ pub struct SpanCategory {
     pub source: Option<SharedStr>,
 }
 
+impl SpanCategory {
+    /// Frames that ran in the kernel, in system profiles (perf, eBPF).
+    pub const KERNEL: &'static str = "kernel";
+    /// Frames that ran in user space, in profiles that tell them apart.
+    pub const USER: &'static str = "user";
+}
+
 /// A single rendering frame with timing information.
 ///
 /// Used to build a "frames track" showing per-frame cost and jank detection.
@@ -303,9 +584,26 @@ pub enum CounterUnit {
     Percent,
     Microseconds,
     Milliseconds,
+    /// Power draw, e.g. Perfetto power rails.
+    Milliwatts,
+    /// Temperature in degrees Celsius, e.g. thermal zones.
+    Celsius,
+    /// Battery charge level, 0–100.
+    BatteryPercent,
     None,
 }
 
+impl CounterUnit {
+    /// Whether `value` breaks a budget of `limit`. Battery charge breaks
+    /// its budget by falling below it; everything else by rising above.
+    pub fn exceeds_budget(self, value: f64, limit: f64) -> bool {
+        match self {
+            Self::BatteryPercent => value < limit,
+            _ => value > limit,
+        }
+    }
+}
+
 /// A single counter sample at a point in time.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CounterSample {
@@ -343,14 +641,47 @@ pub struct FlowArrow {
     pub id: SharedStr,
     /// Source timestamp.
     pub from_ts: f64,
+    /// Source process id.
+    #[serde(default)]
+    pub from_pid: u64,
     /// Source thread id.
     pub from_tid: u64,
     /// Destination timestamp.
     pub to_ts: f64,
+    /// Destination process id.
+    #[serde(default)]
+    pub to_pid: u64,
     /// Destination thread id.
     pub to_tid: u64,
 }
 
+/// A flow arrow touching a span, seen from that span's end.
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct SpanFlow<'a> {
+    pub arrow: &'a FlowArrow,
+    /// The arrow starts within the span (rather than ends there).
+    pub outgoing: bool,
+}
+
+impl SpanFlow<'_> {
+    /// `(pid, tid, ts)` of the arrow's end away from the span.
+    pub fn other_end(&self) -> (u64, u64, f64) {
+        let a = self.arrow;
+        if self.outgoing {
+            (a.to_pid, a.to_tid, a.to_ts)
+        } else {
+            (a.from_pid, a.from_tid, a.from_ts)
+        }
+    }
+}
+
+impl ThreadGroup {
+    /// `(pid, tid)` this group was recorded on, when the source has both.
+    pub fn source_thread(&self) -> Option<(u64, u64)> {
+        Some((self.pid?, self.tid?))
+    }
+}
+
 /// A marker (vertical line) at a point in time.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Marker {
@@ -420,6 +751,10 @@ pub struct CpuSamples {
     pub samples: Vec<u32>,
     /// Timestamps for each sample in the profile's value unit.
     pub timestamps: Vec<f64>,
+    /// Time between samples (µs) as recorded, which sample durations and
+    /// gaps are judged by; measured from the timestamps when `None`.
+    #[serde(default)]
+    pub sample_interval_us: Option<f64>,
 }
 
 /// A single node in the CPU profiler call tree.
@@ -433,6 +768,9 @@ pub struct CpuNode {
     pub function_name: SharedStr,
     /// Script id.
     pub script_id: u32,
+    /// Source location as `url:line:column` (1-based), if recorded.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<SharedStr>,
 }
 
 /// A network request with timing phases.
@@ -456,6 +794,9 @@ pub struct NetworkRequest {
     /// Whether the response was served from cache.
     #[serde(default)]
     pub from_cache: bool,
+    /// URL of the script or resource that triggered the request.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub initiator: Option<SharedStr>,
 }
 
 /// A screenshot snapshot captured during profiling.
@@ -483,6 +824,20 @@ impl VisualProfile {
             .find(|s| s.id == id)
     }
 
+    /// Binary `span` ran in, if recorded.
+    pub fn binary_of(&self, span: &Span) -> Option<&Binary> {
+        self.binaries.get(span.binary? as usize)
+    }
+
+    /// The last screenshot taken at or before `ts`, i.e. what was on screen
+    /// then.
+    pub fn screenshot_at(&self, ts: f64) -> Option<&Screenshot> {
+        self.screenshots
+            .iter()
+            .filter(|s| s.ts <= ts)
+            .max_by(|a, b| a.ts.total_cmp(&b.ts))
+    }
+
     /// Iterate all spans across all threads.
     pub fn all_spans(&self) -> impl Iterator<Item = &Span> {
         self.threads.iter().flat_map(|t| &t.spans)
@@ -532,6 +887,53 @@ impl VisualProfile {
             .find(|t| t.spans.iter().any(|s| s.id == span_id))
             .map(|t| t.id)
     }
+
+    /// Flow arrows starting or ending within the span's time on its
+    /// thread, ordered by when they touch the span.
+    pub fn flows_for_span(&self, span_id: u64) -> Vec<SpanFlow<'_>> {
+        let Some((thread, span)) = self.threads.iter().find_map(|t| {
+            let span = t.spans.iter().find(|s| s.id == span_id)?;
+            Some((t.source_thread()?, span))
+        }) else {
+            return vec![];
+        };
+        let within = |thread_key: (u64, u64), ts: f64| {
+            thread_key == thread && ts >= span.start && ts <= span.end
+        };
+        let mut flows: Vec<(f64, SpanFlow<'_>)> = Vec::new();
+        for arrow in &self.flow_arrows {
+            if within((arrow.from_pid, arrow.from_tid), arrow.from_ts) {
+                flows.push((
+                    arrow.from_ts,
+                    SpanFlow {
+                        arrow,
+                        outgoing: true,
+                    },
+                ));
+            }
+            if within((arrow.to_pid, arrow.to_tid), arrow.to_ts) {
+                flows.push((
+                    arrow.to_ts,
+                    SpanFlow {
+                        arrow,
+                        outgoing: false,
+                    },
+                ));
+            }
+        }
+        flows.sort_by(|a, b| a.0.total_cmp(&b.0));
+        flows.into_iter().map(|(_, flow)| flow).collect()
+    }
+
+    /// Deepest span recorded on `(pid, tid)` that covers `ts`.
+    pub fn span_at(&self, pid: u64, tid: u64, ts: f64) -> Option<&Span> {
+        self.threads
+            .iter()
+            .filter(|t| t.source_thread() == Some((pid, tid)))
+            .flat_map(|t| &t.spans)
+            .filter(|s| s.start <= ts && ts <= s.end)
+            .max_by_key(|s| s.depth)
+    }
 }
 
 #[cfg(test)]
@@ -548,11 +950,19 @@ mod tests {
                 start_time: 0.0,
                 end_time: 100.0,
                 time_domain: None,
+                truncated_spans: 0,
+                repairs: Default::default(),
+                limits_hit: Default::default(),
+                partial: None,
+                sample_interval_us: None,
+                description: None,
+                tags: vec![],
             },
             threads: vec![
                 ThreadGroup {
                     id: 0,
                     name: "Main".into(),
+                    kind: Default::default(),
                     sort_key: 0,
                     max_depth: 0,
                     spans: vec![
@@ -566,6 +976,9 @@ mod tests {
                             self_value: 40.0,
                             kind: SpanKind::Event,
                             category: None,
+                            truncated: false,
+                            binary: None,
+                            inlined: vec![],
                         },
                         Span {
                             id: 1,
@@ -580,12 +993,19 @@ mod tests {
                                 name: "js".into(),
                                 source: None,
                             }),
+                            truncated: false,
+                            binary: None,
+                            inlined: vec![],
                         },
                     ],
+                    pid: None,
+                    tid: None,
+                    process_name: None,
                 },
                 ThreadGroup {
                     id: 1,
                     name: "Worker".into(),
+                    kind: Default::default(),
                     sort_key: 1,
                     max_depth: 0,
                     spans: vec![Span {
@@ -598,7 +1018,13 @@ mod tests {
                         self_value: 30.0,
                         kind: SpanKind::Event,
                         category: None,
+                        truncated: false,
+                        binary: None,
+                        inlined: vec![],
                     }],
+                    pid: None,
+                    tid: None,
+                    process_name: None,
                 },
             ],
             frames: vec![],
@@ -611,6 +1037,17 @@ mod tests {
             cpu_samples: None,
             network_requests: vec![],
             screenshots: vec![],
+            binaries: vec![],
+        }
+    }
+
+    #[test]
+    fn guesses_gpu_tracks_from_names() {
+        for name in ["GPU", "GPU 0", "Direct Queue", "VkQueue 1", "gpu-compute"] {
+            assert_eq!(TrackKind::from_track_name(name), TrackKind::Gpu, "{name}");
+        }
+        for name in ["CrGpuMain", "Main", "ThreadPoolForegroundWorker"] {
+            assert_eq!(TrackKind::from_track_name(name), TrackKind::Cpu, "{name}");
         }
     }
 
@@ -620,6 +1057,20 @@ mod tests {
         assert!((p.duration() - 100.0).abs() < f64::EPSILON);
     }
 
+    #[test]
+    fn screenshot_on_screen_at_a_time() {
+        let mut p = sample_profile();
+        p.screenshots = [30.0, 10.0, 20.0]
+            .map(|ts| Screenshot {
+                ts,
+                data: format!("frame{ts}"),
+            })
+            .into();
+        assert!(p.screenshot_at(5.0).is_none());
+        assert_eq!(p.screenshot_at(25.0).map(|s| s.ts), Some(20.0));
+        assert_eq!(p.screenshot_at(99.0).map(|s| s.ts), Some(30.0));
+    }
+
     #[test]
     fn span_count_across_threads() {
         let p = sample_profile();
@@ -656,6 +1107,38 @@ mod tests {
         assert_eq!(names, vec!["root", "child", "task"]);
     }
 
+    #[test]
+    fn flows_for_span_and_far_end() {
+        let mut p = sample_profile();
+        p.threads[0].pid = Some(1);
+        p.threads[0].tid = Some(1);
+        p.threads[1].pid = Some(1);
+        p.threads[1].tid = Some(2);
+        p.flow_arrows = vec![FlowArrow {
+            name: "post".into(),
+            id: "7".into(),
+            from_ts: 15.0,
+            from_pid: 1,
+            from_tid: 1,
+            to_ts: 25.0,
+            to_pid: 1,
+            to_tid: 2,
+        }];
+
+        let flows = p.flows_for_span(1);
+        assert_eq!(flows.len(), 1);
+        assert!(flows[0].outgoing);
+        let (pid, tid, ts) = flows[0].other_end();
+        assert_eq!(p.span_at(pid, tid, ts).map(|s| s.id), Some(2));
+
+        let back = p.flows_for_span(2);
+        assert!(!back[0].outgoing);
+        let (pid, tid, ts) = back[0].other_end();
+        // The deepest span covering the source wins over its parent.
+        assert_eq!(p.span_at(pid, tid, ts).map(|s| s.id), Some(1));
+        assert!(p.flows_for_span(0).len() == 1 && p.flows_for_span(99).is_empty());
+    }
+
     #[test]
     fn span_duration() {
         let s = Span {
@@ -668,6 +1151,9 @@ mod tests {
             self_value: 20.0,
             kind: SpanKind::Event,
             category: None,
+            truncated: false,
+            binary: None,
+            inlined: vec![],
         };
         assert!((s.duration() - 20.0).abs() < f64::EPSILON);
     }
@@ -0,0 +1,447 @@
+//! A [`tracing_subscriber::Layer`] that records Rust `tracing` spans for
+//! flame.cat.
+//!
+//! Two modes are supported:
+//!
+//! - [`FlameLayer::with_file`] keeps every closed span in memory and writes a
+//!   `.flamecache` file (see `flame_cat_core::cache`) when the returned
+//!   [`FlushGuard`] is dropped.
+//! - [`FlameLayer::with_writer`] streams the begin and end of each span as
+//!   one-line Chrome trace event batches, e.g. over a `TcpStream` to a
+//!   bridge that feeds a viewer in live mode (`appendLiveEvents`). They are
+//!   async events paired by span id, as spans need not close in the order
+//!   they were created in.
+//!
+//! ```no_run
+//! use tracing_subscriber::prelude::*;
+//!
+//! let (layer, _guard) = flame_cat_tracing::FlameLayer::with_file("app.flamecache");
+//! tracing_subscriber::registry().with(layer).init();
+//! // Run instrumented code; the file is written when `_guard` drops.
+//! ```
+//!
+//! A span lasts from its creation to its close and is drawn on the thread it
+//! was created on. Spans whose parent lives on another thread become roots
+//! of their own thread.
+
+use std::cell::Cell;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::Instant;
+
+use flame_cat_core::model::{Frame, Profile, ProfileMetadata};
+use flame_cat_protocol::VisualProfile;
+use thiserror::Error;
+use tracing_core::Subscriber;
+use tracing_core::span::{Attributes, Id};
+use tracing_subscriber::Layer;
+use tracing_subscriber::layer::Context;
+use tracing_subscriber::registry::LookupSpan;
+
+#[derive(Debug, Error)]
+pub enum FlameLayerError {
+    #[error("io: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+/// Records spans into the flame.cat profile model.
+pub struct FlameLayer {
+    shared: Arc<Shared>,
+}
+
+/// Writes the recorded profile when dropped.
+///
+/// Errors cannot be reported from `drop`; call [`FlushGuard::flush`] to
+/// observe them.
+#[must_use = "the profile is written when the guard is dropped"]
+pub struct FlushGuard {
+    shared: Arc<Shared>,
+    path: PathBuf,
+}
+
+struct Shared {
+    origin: Instant,
+    next_id: AtomicU64,
+    sink: Mutex<Sink>,
+}
+
+enum Sink {
+    Memory(Vec<Frame>),
+    Stream {
+        /// `None` once a write has failed; streaming stops rather than retrying.
+        writer: Option<Box<dyn Write + Send>>,
+        /// Threads whose `thread_name` metadata event was already sent.
+        announced: Vec<u64>,
+    },
+}
+
+/// Per-span bookkeeping stored in the registry's span extensions.
+struct Timing {
+    id: u64,
+    start: f64,
+    depth: u32,
+    parent: Option<u64>,
+    /// Time covered by already-closed children, for self time.
+    child_time: f64,
+    tid: u64,
+    thread: String,
+}
+
+static NEXT_TID: AtomicU64 = AtomicU64::new(1);
+
+thread_local! {
+    /// Small stable per-thread id; `std::thread::ThreadId` has no stable integer form.
+    static TID: Cell<u64> = const { Cell::new(0) };
+}
+
+fn current_tid() -> u64 {
+    TID.with(|tid| {
+        if tid.get() == 0 {
+            tid.set(NEXT_TID.fetch_add(1, Ordering::Relaxed));
+        }
+        tid.get()
+    })
+}
+
+fn thread_name(tid: u64) -> String {
+    std::thread::current()
+        .name()
+        .map_or_else(|| format!("thread {tid}"), str::to_owned)
+}
+
+impl FlameLayer {
+    fn new(sink: Sink) -> Self {
+        Self {
+            shared: Arc::new(Shared {
+                origin: Instant::now(),
+                next_id: AtomicU64::new(0),
+                sink: Mutex::new(sink),
+            }),
+        }
+    }
+
+    /// Record spans in memory and write them to `path` as a `.flamecache`
+    /// file when the returned guard drops.
+    pub fn with_file(path: impl Into<PathBuf>) -> (Self, FlushGuard) {
+        let layer = Self::new(Sink::Memory(Vec::new()));
+        let guard = FlushGuard {
+            shared: layer.shared.clone(),
+            path: path.into(),
+        };
+        (layer, guard)
+    }
+
+    /// Stream each span to `writer` as a `b` event when it is created and an
+    /// `e` event with the same id when it closes, each a newline-terminated
+    /// JSON array of Chrome trace events. Events go out in time order, so a
+    /// viewer adds each batch to what it has without rebuilding. Nothing is
+    /// kept in memory.
+    pub fn with_writer<W: Write + Send + 'static>(writer: W) -> Self {
+        Self::new(Sink::Stream {
+            writer: Some(Box::new(writer)),
+            announced: Vec::new(),
+        })
+    }
+
+    /// Snapshot of the spans closed so far (empty in streaming mode).
+    pub fn profile(&self) -> VisualProfile {
+        self.shared.profile()
+    }
+}
+
+impl Shared {
+    fn now_us(&self) -> f64 {
+        self.origin.elapsed().as_secs_f64() * 1_000_000.0
+    }
+
+    fn profile(&self) -> VisualProfile {
+        let frames = match &*self.sink.lock().unwrap_or_else(PoisonError::into_inner) {
+            Sink::Memory(frames) => frames.clone(),
+            Sink::Stream { .. } => Vec::new(),
+        };
+        let end_time = frames.iter().map(|f| f.end).fold(0.0, f64::max);
+        Profile::new(
+            ProfileMetadata {
+                name: None,
+                start_time: 0.0,
+                end_time,
+                format: "tracing".to_string(),
+                time_domain: None,
+                value_unit: None,
+                sample_interval_us: None,
+            },
+            frames,
+        )
+        .into_visual_profile()
+    }
+
+    fn record(&self, frame: Frame) {
+        if let Sink::Memory(frames) = &mut *self.sink.lock().unwrap_or_else(PoisonError::into_inner)
+        {
+            frames.push(frame);
+        }
+    }
+
+    /// Stream a `b` or `e` event of span `id`, named `name`, on thread `tid`.
+    /// The event is stamped while the sink is held, so events from all
+    /// threads go out in time order.
+    fn stream(&self, ph: &str, id: u64, name: &str, category: &str, tid: u64, thread: &str) {
+        let mut sink = self.sink.lock().unwrap_or_else(PoisonError::into_inner);
+        let Sink::Stream { writer, announced } = &mut *sink else {
+            return;
+        };
+        let Some(w) = writer else {
+            return;
+        };
+        let pid = std::process::id();
+        let mut batch = Vec::with_capacity(2);
+        if !announced.contains(&tid) {
+            announced.push(tid);
+            batch.push(serde_json::json!({
+                "name": "thread_name", "ph": "M", "ts": 0, "pid": pid, "tid": tid,
+                "args": { "name": thread },
+            }));
+        }
+        batch.push(serde_json::json!({
+            "name": name, "cat": category, "ph": ph, "id": id,
+            "ts": self.now_us(), "pid": pid, "tid": tid,
+        }));
+        let written = serde_json::to_writer(&mut *w, &batch)
+            .map_err(std::io::Error::from)
+            .and_then(|()| w.write_all(b"\n"))
+            .and_then(|()| w.flush());
+        if written.is_err() {
+            *writer = None;
+        }
+    }
+}
+
+impl FlushGuard {
+    /// Write the profile recorded so far.
+    pub fn flush(&self) -> Result<(), FlameLayerError> {
+        flame_cat_core::cache::write_cache(&self.path, &self.shared.profile())?;
+        Ok(())
+    }
+}
+
+impl Drop for FlushGuard {
+    fn drop(&mut self) {
+        let _ = self.flush();
+    }
+}
+
+impl<S> Layer<S> for FlameLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, _attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else {
+            return;
+        };
+        let tid = current_tid();
+        let parent = span.parent().and_then(|p| {
+            p.extensions()
+                .get::<Timing>()
+                .filter(|t| t.tid == tid)
+                .map(|t| (t.id, t.depth))
+        });
+        let thread = thread_name(tid);
+        let metadata = span.metadata();
+        let span_id = self.shared.next_id.fetch_add(1, Ordering::Relaxed);
+        self.shared.stream(
+            "b",
+            span_id,
+            metadata.name(),
+            metadata.target(),
+            tid,
+            &thread,
+        );
+        span.extensions_mut().insert(Timing {
+            id: span_id,
+            start: self.shared.now_us(),
+            depth: parent.map_or(0, |(_, depth)| depth + 1),
+            parent: parent.map(|(id, _)| id),
+            child_time: 0.0,
+            tid,
+            thread,
+        });
+    }
+
+    fn on_close(&self, id: Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(&id) else {
+            return;
+        };
+        let end = self.shared.now_us();
+        let Some(timing) = span.extensions_mut().remove::<Timing>() else {
+            return;
+        };
+        let duration = end - timing.start;
+        if let Some(parent) = span.parent()
+            && let Some(pt) = parent.extensions_mut().get_mut::<Timing>()
+            && Some(pt.id) == timing.parent
+        {
+            pt.child_time += duration;
+        }
+
+        let metadata = span.metadata();
+        self.shared.stream(
+            "e",
+            timing.id,
+            metadata.name(),
+            metadata.target(),
+            timing.tid,
+            &timing.thread,
+        );
+        self.shared.record(Frame {
+            id: timing.id,
+            name: metadata.name().to_string(),
+            start: timing.start,
+            end,
+            depth: timing.depth,
+            category: Some(metadata.target().to_string()),
+            parent: timing.parent,
+            self_time: (duration - timing.child_time).max(0.0),
+            thread: Some(timing.thread),
+            truncated: false,
+            pid: Some(u64::from(std::process::id())),
+            tid: Some(timing.tid),
+            source: None,
+            binary: None,
+            inlined: vec![],
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tracing_subscriber::prelude::*;
+
+    fn run_workload() {
+        let outer = tracing::info_span!("outer");
+        let _outer = outer.enter();
+        for _ in 0..2 {
+            let inner = tracing::info_span!("inner");
+            let _inner = inner.enter();
+        }
+    }
+
+    #[test]
+    fn writes_nested_spans_on_drop() -> Result<(), Box<dyn std::error::Error>> {
+        let path = std::env::temp_dir().join(format!(
+            "flame-cat-tracing-{}.flamecache",
+            std::process::id()
+        ));
+        let (layer, guard) = FlameLayer::with_file(&path);
+        tracing::subscriber::with_default(tracing_subscriber::registry().with(layer), run_workload);
+        drop(guard);
+
+        let profile = flame_cat_core::cache::read_cache(&path)?;
+        std::fs::remove_file(&path)?;
+        assert_eq!(profile.meta.source_format.to_string(), "Rust tracing");
+        let spans: Vec<_> = profile.all_spans().collect();
+        assert_eq!(spans.len(), 3);
+        let outer = spans
+            .iter()
+            .find(|s| s.name == "outer")
+            .ok_or("no outer span")?;
+        assert_eq!(outer.depth, 0);
+        let inner: Vec<_> = spans.iter().filter(|s| s.name == "inner").collect();
+        assert!(
+            inner
+                .iter()
+                .all(|s| s.parent == Some(outer.id) && s.depth == 1)
+        );
+        assert!(outer.self_value <= outer.duration());
+        Ok(())
+    }
+
+    #[derive(Clone, Default)]
+    struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Buffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .write(buf)
+        }
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    /// Run `workload` streaming to a buffer, and read the batches back as
+    /// the viewer does.
+    fn stream_workload(
+        workload: impl FnOnce(),
+    ) -> Result<flame_cat_core::parsers::chrome::ChromeTraceStream, Box<dyn std::error::Error>>
+    {
+        let out = Buffer::default();
+        let layer = FlameLayer::with_writer(out.clone());
+        tracing::subscriber::with_default(tracing_subscriber::registry().with(layer), workload);
+
+        let data = out.0.lock().unwrap_or_else(PoisonError::into_inner).clone();
+        let mut stream = flame_cat_core::parsers::chrome::ChromeTraceStream::new(
+            flame_cat_core::parsers::Limits::default(),
+            None,
+        );
+        for line in data.split(|&b| b == b'\n').filter(|l| !l.is_empty()) {
+            stream.push_batch(line)?;
+        }
+        Ok(stream)
+    }
+
+    #[test]
+    fn streams_chrome_batches_the_viewer_can_parse() -> Result<(), Box<dyn std::error::Error>> {
+        let stream = stream_workload(run_workload)?;
+        // Every batch followed the one before it, so none was rebuilt.
+        assert_eq!(stream.events_processed(), stream.event_count());
+        assert_eq!(stream.event_count(), 6);
+        let profile = stream.profile();
+        let mut spans: Vec<_> = profile
+            .async_spans
+            .iter()
+            .map(|s| (s.name.as_ref(), s.cat.as_deref(), s.start <= s.end))
+            .collect();
+        spans.sort_unstable();
+        let target = Some(module_path!());
+        assert_eq!(
+            spans,
+            [
+                ("inner", target, true),
+                ("inner", target, true),
+                ("outer", target, true)
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn streamed_spans_keep_their_own_close() -> Result<(), Box<dyn std::error::Error>> {
+        let stream = stream_workload(|| {
+            let a = tracing::info_span!("a");
+            let b = tracing::info_span!("b");
+            std::thread::sleep(std::time::Duration::from_millis(2));
+            drop(a);
+            std::thread::sleep(std::time::Duration::from_millis(2));
+            drop(b);
+        })?;
+        let profile = stream.profile();
+        let span = |name: &str| {
+            profile
+                .async_spans
+                .iter()
+                .find(|s| s.name.as_ref() == name)
+                .map(|s| (s.start, s.end))
+                .ok_or("span not streamed")
+        };
+        let (a, b) = (span("a")?, span("b")?);
+        // `a` was created first and closed first.
+        assert!(a.0 <= b.0);
+        assert!(b.1 - a.1 >= 1_000.0);
+        Ok(())
+    }
+}
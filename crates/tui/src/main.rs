@@ -1,20 +1,358 @@
 mod renderer;
+mod serve;
 
-use std::path::PathBuf;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
 
-use anyhow::Result;
+use anyhow::{Result, bail};
+use flame_cat_core::analysis::{self, CategoryBudget};
+use flame_cat_core::comparison::{Comparison, ReportFormat};
+use flame_cat_core::model::{DiffNormalization, Session};
+use flame_cat_core::remote::{self, RemoteRequest};
+use flame_cat_core::symbolicate::Symbolicator;
+use flame_cat_core::transform::{self, AnonymizeRules, NameRewrite, NameRewriter};
+use flame_cat_core::views::ranked::{RankedGroup, RankedSort, get_ranked_entries};
+use flame_cat_protocol::{DurationFormat, DurationUnit, Separators, VisualProfile};
+
+/// Rows `top` prints unless `-n` says otherwise.
+const DEFAULT_TOP: usize = 20;
+
+/// What to do with the profile.
+#[derive(PartialEq)]
+enum Mode {
+    /// Open the interactive viewer.
+    View,
+    /// Print the heaviest functions.
+    Top,
+    /// Print category budgets and insights.
+    Report,
+    /// Compare a profile with a baseline.
+    Diff,
+    /// Write the parsed profile as JSON or a `.flamecache`.
+    Convert,
+    /// Render profiles posted over HTTP.
+    Serve,
+}
+
+/// Address `serve` listens on unless `--addr` says otherwise.
+const DEFAULT_ADDR: &str = "127.0.0.1:7878";
 
 fn main() -> Result<()> {
-    let args: Vec<String> = std::env::args().collect();
-    if args.len() < 2 {
-        eprintln!("Usage: flame-cat <profile.json>");
-        std::process::exit(1);
+    let mut args = std::env::args().skip(1).peekable();
+    let mode = match args
+        .next_if(|arg| ["top", "report", "diff", "convert", "serve"].contains(&arg.as_str()))
+        .as_deref()
+    {
+        Some("top") => Mode::Top,
+        Some("report") => Mode::Report,
+        Some("diff") => Mode::Diff,
+        Some("serve") => Mode::Serve,
+        Some(_) => Mode::Convert,
+        None => Mode::View,
+    };
+    let top = mode == Mode::Top;
+    let report = mode == Mode::Report;
+    let diff = mode == Mode::Diff;
+    let convert = mode == Mode::Convert;
+    let serve = mode == Mode::Serve;
+    let inputs = match mode {
+        Mode::Diff => 2,
+        Mode::Serve => 0,
+        _ => 1,
+    };
+    let mut addr = DEFAULT_ADDR.to_string();
+    let mut budgets: Vec<CategoryBudget> = Vec::new();
+    let mut check = false;
+    let mut paths = false;
+    let mut limit = DEFAULT_TOP;
+    let mut output = None;
+    let mut normalization = DiffNormalization::default();
+    let mut anonymize = None;
+    let mut rewrites = Vec::new();
+    let mut sources = Vec::new();
+    let mut symbolicator = Symbolicator::new();
+    let mut format = DurationFormat::default();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--symbols" => {
+                let Some(path) = args.next() else {
+                    usage();
+                };
+                symbolicator.add_file(&path)?;
+            }
+            "--jit-map" => {
+                let Some(path) = args.next() else {
+                    usage();
+                };
+                let map = std::fs::read(&path)?;
+                symbolicator.add_perf_map(&String::from_utf8_lossy(&map))?;
+            }
+            "--rewrite" => {
+                let (Some(pattern), Some(replacement)) = (args.next(), args.next()) else {
+                    usage();
+                };
+                rewrites.push(NameRewrite {
+                    pattern,
+                    replacement,
+                });
+            }
+            "--unit" => {
+                let Some(unit) = args.next().and_then(|u| DurationUnit::from_name(&u)) else {
+                    usage();
+                };
+                format.unit = unit;
+            }
+            "--decimals" => {
+                let Some(decimals) = args.next().and_then(|d| d.parse().ok()) else {
+                    usage();
+                };
+                format.decimals = Some(decimals);
+            }
+            "--separators" => {
+                let Some(separators) = args.next().and_then(|s| Separators::from_name(&s)) else {
+                    usage();
+                };
+                format.separators = separators;
+            }
+            "--budget" if report => {
+                let Some(spec) = args.next() else {
+                    usage();
+                };
+                budgets.extend(analysis::parse_budgets(&spec)?);
+            }
+            "--check" if report => check = true,
+            "--paths" if top => paths = true,
+            "-n" if top => {
+                let Some(n) = args.next().and_then(|n| n.parse().ok()) else {
+                    usage();
+                };
+                limit = n;
+            }
+            "--report" if diff => {
+                let Some(path) = args.next() else {
+                    usage();
+                };
+                output = Some(PathBuf::from(path));
+            }
+            "--normalize" if diff => {
+                let Some(n) = args.next().and_then(|n| DiffNormalization::from_name(&n)) else {
+                    usage();
+                };
+                normalization = n;
+            }
+            "-o" if convert => {
+                let Some(path) = args.next() else {
+                    usage();
+                };
+                output = Some(PathBuf::from(path));
+            }
+            "--anonymize" if convert => {
+                anonymize.get_or_insert_with(AnonymizeRules::default);
+            }
+            "--anonymize-rules" if convert => {
+                let Some(spec) = args.next() else {
+                    usage();
+                };
+                anonymize = Some(spec.parse()?);
+            }
+            "--addr" if serve => {
+                let Some(a) = args.next() else {
+                    usage();
+                };
+                addr = a;
+            }
+            _ if sources.len() < inputs => sources.push(arg),
+            _ => usage(),
+        }
+    }
+    if sources.len() < inputs {
+        usage();
+    }
+    if serve {
+        return serve::serve(&addr);
+    }
+
+    let rewriter = NameRewriter::new(&rewrites)?;
+    let mut profiles = Vec::with_capacity(inputs);
+    for source in &sources {
+        let mut profile = load(source)?;
+        if !symbolicator.is_empty() {
+            symbolicator.apply_visual(&mut profile);
+        }
+        transform::rewrite_names(&mut profile, &rewriter);
+        profiles.push(profile);
+    }
+
+    let mut profiles = profiles.into_iter();
+    let Some(profile) = profiles.next() else {
+        usage();
+    };
+    if let Some(target) = profiles.next() {
+        let mut session = Session::from_profile(target, label(&sources[1]));
+        session.add_profile(profile, label(&sources[0]));
+        session.set_baseline(Some(1))?;
+        session.set_diff_normalization(normalization);
+        return print_diff(&session, output.as_deref(), &format);
+    }
+    match mode {
+        Mode::Top => print_top(&profile, limit, paths, &format),
+        Mode::Report => print_report(&profile, &budgets, check, &format),
+        Mode::Convert => write_profile(profile, anonymize.as_ref(), output.as_deref()),
+        Mode::View | Mode::Diff | Mode::Serve => renderer::render_tui(&profile, &format),
     }
+}
 
-    let path = PathBuf::from(&args[1]);
-    let data = std::fs::read(&path)?;
-    let profile = flame_cat_core::parsers::parse_auto_visual(&data)?;
+/// Parse a local file or an `http(s)://` URL.
+fn load(source: &str) -> Result<VisualProfile> {
+    if source.starts_with("http://") || source.starts_with("https://") {
+        // Private artifact stores usually want a token.
+        let mut request = RemoteRequest::new(source);
+        if let Ok(token) = std::env::var("FLAME_CAT_TOKEN") {
+            request = request.bearer_token(&token);
+        }
+        Ok(remote::parse_from_url(
+            &request,
+            &flame_cat_core::parsers::Limits::default(),
+        )?)
+    } else {
+        Ok(flame_cat_core::input::parse_file_visual(PathBuf::from(
+            source,
+        ))?)
+    }
+}
 
-    renderer::render_tui(&profile)?;
+/// The file name of `source`, for report headings.
+fn label(source: &str) -> String {
+    source
+        .rsplit(['/', '\\'])
+        .find(|part| !part.is_empty())
+        .unwrap_or(source)
+        .to_owned()
+}
+
+/// Print the `limit` functions with the most self time, or with `paths`
+/// the hottest complete stacks as folded-stack lines.
+fn print_top(
+    profile: &VisualProfile,
+    limit: usize,
+    paths: bool,
+    format: &DurationFormat,
+) -> Result<()> {
+    let mut out = io::stdout().lock();
+    if paths {
+        for path in flame_cat_core::analysis::hot_paths(profile, limit) {
+            writeln!(out, "{path}")?;
+        }
+        return Ok(());
+    }
+    let meta = &profile.meta;
+    for entry in get_ranked_entries(profile, RankedGroup::Name, RankedSort::SelfTime, false)
+        .iter()
+        .take(limit)
+    {
+        writeln!(
+            out,
+            "{:>10}  {:>10}  {}",
+            meta.format_value_with(entry.self_time, format),
+            meta.format_value_with(entry.total_time, format),
+            entry.name
+        )?;
+    }
     Ok(())
 }
+
+/// Print how `profile` fares against `budgets`, then every insight. With
+/// `check`, fail when a budget is exceeded.
+fn print_report(
+    profile: &VisualProfile,
+    budgets: &[CategoryBudget],
+    check: bool,
+    format: &DurationFormat,
+) -> Result<()> {
+    let mut out = io::stdout().lock();
+    let results = analysis::evaluate_budgets(profile, budgets);
+    for result in &results {
+        writeln!(
+            out,
+            "{:<4}  {}: {}% ({})",
+            if result.exceeded() { "OVER" } else { "ok" },
+            result.budget,
+            format.number(result.percent, 1),
+            profile.meta.format_value_with(result.self_time, format)
+        )?;
+    }
+    let insights = analysis::insights(profile, &[]);
+    if !results.is_empty() && !insights.is_empty() {
+        writeln!(out)?;
+    }
+    for insight in insights {
+        writeln!(out, "{}\n  {}", insight.title, insight.detail)?;
+    }
+    let over = results.iter().filter(|r| r.exceeded()).count();
+    if check && over > 0 {
+        bail!("{over} of {} category budgets exceeded", results.len());
+    }
+    Ok(())
+}
+
+/// Write the comparison of the session's two profiles to `output`, as
+/// HTML or Markdown by its extension, or print it as Markdown.
+fn print_diff(session: &Session, output: Option<&Path>, format: &DurationFormat) -> Result<()> {
+    let comparison = Comparison::new(session)?;
+    match output {
+        Some(path) => {
+            std::fs::write(
+                path,
+                comparison.render(ReportFormat::for_path(path), format),
+            )?;
+        }
+        None => {
+            let markdown = comparison.render(ReportFormat::Markdown, format);
+            io::stdout().lock().write_all(markdown.as_bytes())?;
+        }
+    }
+    Ok(())
+}
+
+/// Write `profile`, scrubbed by `anonymize` if given, to `output` — a
+/// `.flamecache` when the name says so, JSON otherwise — or as JSON to
+/// stdout.
+fn write_profile(
+    mut profile: VisualProfile,
+    anonymize: Option<&AnonymizeRules>,
+    output: Option<&Path>,
+) -> Result<()> {
+    if let Some(rules) = anonymize {
+        transform::anonymize(&mut profile, rules);
+    }
+    let Some(path) = output else {
+        serde_json::to_writer(io::stdout().lock(), &profile)?;
+        return Ok(());
+    };
+    let bytes = if path.extension().is_some_and(|ext| ext == "flamecache") {
+        flame_cat_core::cache::encode(&profile)
+    } else {
+        serde_json::to_vec(&profile)?
+    };
+    std::fs::write(path, bytes)?;
+    Ok(())
+}
+
+fn usage() -> ! {
+    eprintln!(
+        "Usage: flame-cat [options] <profile.json | https://…>\n       \
+         flame-cat top [--paths] [-n <rows>] [options] <profile.json | https://…>\n       \
+         flame-cat report [--budget \"js < 50%, layout < 10%\"]... [--check] [options] <profile.json | https://…>\n       \
+         flame-cat diff [--report <out.md | out.html>] [--normalize <per_sample|per_second|per_total>] [options] <baseline> <profile>\n       \
+         flame-cat convert [--anonymize] [--anonymize-rules \"urls=strip, paths=hash, args=keep\"] [-o <out.json | out.flamecache>] [options] <profile.json | https://…>\n       \
+         flame-cat serve [--addr <host:port>]\n\n\
+         Options:\n  \
+         --symbols <perf map | .sym | binary>   resolve addresses (repeatable)\n  \
+         --jit-map <perf map>                   resolve JIT frames\n  \
+         --rewrite <regex> <replacement>        normalize span names (repeatable)\n  \
+         --unit <auto|s|ms|us|ns>               unit for durations\n  \
+         --decimals <n>                         digits after the decimal mark\n  \
+         --separators <plain|comma_point|point_comma|space_comma>"
+    );
+    std::process::exit(1);
+}
@@ -8,7 +8,11 @@ use crossterm::{
     execute,
     terminal::{EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode},
 };
-use flame_cat_protocol::{RenderCommand, ThemeToken, VisualProfile};
+use flame_cat_core::model::{Navigation, SelectionState};
+use flame_cat_core::views::text::{TextMeasure, fit_label};
+use flame_cat_protocol::{
+    DurationFormat, RenderCommand, RenderOptions, ThemeToken, TimeRange, VisualProfile, heat_rgb,
+};
 use ratatui::{
     Terminal,
     backend::CrosstermBackend,
@@ -17,6 +21,18 @@ use ratatui::{
     widgets::{Block, Borders},
 };
 
+/// Terminal cells as a text measure: every character takes one column of
+/// `column_width` viewport units.
+struct Cells {
+    column_width: f64,
+}
+
+impl TextMeasure for Cells {
+    fn width(&self, text: &str, _font_size: f64) -> f64 {
+        text.chars().count() as f64 * self.column_width
+    }
+}
+
 fn theme_to_color(token: &ThemeToken) -> Color {
     match token {
         ThemeToken::FlameHot => Color::Red,
@@ -50,10 +66,16 @@ fn theme_to_color(token: &ThemeToken) -> Color {
         ThemeToken::CounterFill => Color::Rgb(116, 199, 236),
         ThemeToken::CounterLine => Color::Rgb(116, 199, 236),
         ThemeToken::CounterText => Color::Gray,
+        ThemeToken::CounterBudgetExceeded => Color::Rgb(90, 40, 50),
+        ThemeToken::CounterBudgetLine => Color::Rgb(243, 139, 168),
         ThemeToken::MarkerLine => Color::Yellow,
         ThemeToken::MarkerText => Color::Yellow,
         ThemeToken::AsyncSpanFill => Color::Cyan,
         ThemeToken::AsyncSpanBorder => Color::Rgb(116, 199, 236),
+        ThemeToken::GpuSpanBorder => Color::Rgb(250, 179, 135),
+        ThemeToken::KernelSpan => Color::Rgb(203, 166, 247),
+        ThemeToken::OverlaySpanFill => Color::Rgb(40, 70, 72),
+        ThemeToken::OverlaySpanBorder => Color::Rgb(148, 226, 213),
         ThemeToken::FrameGood => Color::Green,
         ThemeToken::FrameWarning => Color::Yellow,
         ThemeToken::FrameDropped => Color::Red,
@@ -63,10 +85,14 @@ fn theme_to_color(token: &ThemeToken) -> Color {
         ThemeToken::InlineLabelBackground => Color::Rgb(30, 30, 46),
         ThemeToken::FlowArrow => Color::DarkGray,
         ThemeToken::FlowArrowHead => Color::Gray,
+        ThemeToken::Heat(level) => {
+            let (r, g, b) = heat_rgb(*level);
+            Color::Rgb(r, g, b)
+        }
     }
 }
 
-pub fn render_tui(profile: &VisualProfile) -> Result<()> {
+pub fn render_tui(profile: &VisualProfile, format: &DurationFormat) -> Result<()> {
     enable_raw_mode()?;
     let mut stdout = stdout();
     execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
@@ -76,6 +102,7 @@ pub fn render_tui(profile: &VisualProfile) -> Result<()> {
     let mut scroll_x: f64 = 0.0;
     let mut scroll_y: f64 = 0.0;
     let mut zoom: f64 = 1.0;
+    let mut selection = SelectionState::default();
 
     let duration = profile.duration();
 
@@ -93,8 +120,17 @@ pub fn render_tui(profile: &VisualProfile) -> Result<()> {
         let view_start = profile.meta.start_time + scroll_x;
         let view_end = (view_start + visible_duration).min(profile.meta.end_time);
 
-        let cmds = flame_cat_core::views::time_order::render_time_order(
-            profile, &viewport, view_start, view_end, None,
+        let cmds = flame_cat_core::views::render(
+            profile,
+            &RenderOptions {
+                viewport,
+                range: Some(TimeRange {
+                    start: view_start,
+                    end: view_end,
+                }),
+                format: *format,
+                ..RenderOptions::default()
+            },
         );
 
         terminal.draw(|frame| {
@@ -102,10 +138,21 @@ pub fn render_tui(profile: &VisualProfile) -> Result<()> {
 
             // Header
             let header_area = Rect::new(0, 0, area.width, 1);
+            let selected = selection
+                .selected()
+                .and_then(|id| profile.span(id))
+                .map(|s| {
+                    format!(
+                        " | {} {}",
+                        s.name,
+                        profile.meta.format_value_with(s.duration(), format)
+                    )
+                })
+                .unwrap_or_default();
             let header = Block::default()
                 .title(format!(
-                    " flame.cat — {} spans | ←→ scroll | +/- zoom | q quit ",
-                    profile.span_count()
+                    " flame.cat — {} spans | ←→ scroll | +/- zoom | [ ] {{ }} select | F flow | q quit{selected} ",
+                    format.number(profile.span_count() as f64, 0)
                 ))
                 .style(Style::default().fg(Color::White).bg(Color::DarkGray));
             frame.render_widget(header, header_area);
@@ -119,7 +166,11 @@ pub fn render_tui(profile: &VisualProfile) -> Result<()> {
 
             for cmd in &cmds {
                 if let RenderCommand::DrawRect {
-                    rect, color, label, ..
+                    rect,
+                    color,
+                    label,
+                    frame_id,
+                    ..
                 } = cmd
                 {
                     // Map floating-point coords to terminal cells
@@ -134,12 +185,22 @@ pub fn render_tui(profile: &VisualProfile) -> Result<()> {
                         continue;
                     }
 
-                    let fg = theme_to_color(color);
-                    let label_str = label.as_deref().unwrap_or("");
-                    let display: String = if (width as usize) >= label_str.len() + 2 {
-                        format!(" {label_str:<w$}", w = (width as usize).saturating_sub(2))
+                    let (fg, bg) = if frame_id.is_some() && *frame_id == selection.selected() {
+                        (Color::Black, Color::White)
                     } else {
-                        "█".repeat(width as usize)
+                        (theme_to_color(color), Color::Black)
+                    };
+                    let cells = Cells {
+                        column_width: 1.0 / col_scale,
+                    };
+                    let display: String = match label
+                        .as_deref()
+                        .and_then(|l| fit_label(l, rect.w, rect.h, &cells))
+                    {
+                        Some(fitted) => {
+                            format!(" {:<w$}", fitted.text, w = (width as usize).saturating_sub(1))
+                        }
+                        None => "█".repeat(width as usize),
                     };
 
                     let clamped_width = width.min(content_area.width.saturating_sub(col));
@@ -150,7 +211,7 @@ pub fn render_tui(profile: &VisualProfile) -> Result<()> {
                         if x < content_area.x + content_area.width
                             && y < content_area.y + content_area.height
                         {
-                            buf[(x, y)].set_char(ch).set_fg(fg).set_bg(Color::Black);
+                            buf[(x, y)].set_char(ch).set_fg(fg).set_bg(bg);
                         }
                     }
                 }
@@ -178,6 +239,21 @@ pub fn render_tui(profile: &VisualProfile) -> Result<()> {
                     KeyCode::Char('-') => {
                         zoom = (zoom / 1.3).max(1.0);
                     }
+                    KeyCode::Char('[') => {
+                        selection.navigate(profile, Navigation::Parent);
+                    }
+                    KeyCode::Char(']') => {
+                        selection.navigate(profile, Navigation::FirstChild);
+                    }
+                    KeyCode::Char('{') => {
+                        selection.navigate(profile, Navigation::PrevSibling);
+                    }
+                    KeyCode::Char('}') => {
+                        selection.navigate(profile, Navigation::NextSibling);
+                    }
+                    KeyCode::Char('F') => {
+                        selection.navigate(profile, Navigation::FollowFlow);
+                    }
                     _ => {}
                 },
                 Event::Mouse(mouse) => match mouse.kind {
@@ -0,0 +1,401 @@
+//! `flame-cat serve`: render profiles over HTTP, for dashboards and bots
+//! that want flame images without a browser.
+//!
+//! `POST /render` takes a profile in any supported format as the body and
+//! answers with its render:
+//!
+//! - `?format=json` (default) the render commands as JSON,
+//! - `?format=svg` an SVG document,
+//! - `?format=png` that document rasterized at the viewport's pixel ratio.
+//!
+//! `?theme=dark` picks the dark palette for images. Render options go in
+//! an `X-Render-Options` header as `RenderOptions` JSON; a zero viewport
+//! renders at [`DEFAULT_WIDTH`] × [`DEFAULT_HEIGHT`], and one with other
+//! non-positive sizes, larger than [`MAX_WIDTH`] × [`MAX_HEIGHT`] at
+//! [`MAX_DPR`], or over [`MAX_PIXELS`] device pixels is refused. Bodies
+//! are parsed within the default parse [`Limits`].
+
+use std::io::Read;
+use std::panic::AssertUnwindSafe;
+
+use anyhow::{Result, anyhow};
+use flame_cat_core::parsers::Limits;
+use flame_cat_core::svg::{SvgOptions, render_svg_with};
+use flame_cat_protocol::{RENDER_OPTIONS_VERSION, RenderOptions, Viewport};
+use tiny_http::{Header, Method, Request, Response, Server};
+
+/// Viewport width of requests that leave it at zero.
+const DEFAULT_WIDTH: f64 = 1200.0;
+/// Viewport height of requests that leave it at zero.
+const DEFAULT_HEIGHT: f64 = 600.0;
+/// Widest viewport rendered, in logical pixels.
+const MAX_WIDTH: f64 = 16_384.0;
+/// Tallest viewport rendered, in logical pixels.
+const MAX_HEIGHT: f64 = 16_384.0;
+/// Highest device pixel ratio rendered.
+const MAX_DPR: f64 = 4.0;
+/// Most device pixels (width × height × dpr²) rendered, 128 MiB as RGBA.
+const MAX_PIXELS: f64 = 8192.0 * 4096.0;
+/// Largest profile body accepted.
+const MAX_BODY: u64 = 512 * 1024 * 1024;
+
+/// Image formats and command JSON a render can be returned as.
+#[derive(Clone, Copy)]
+enum Format {
+    Json,
+    Svg,
+    Png,
+}
+
+impl Format {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "json" => Some(Self::Json),
+            "svg" => Some(Self::Svg),
+            "png" => Some(Self::Png),
+            _ => None,
+        }
+    }
+
+    fn content_type(self) -> &'static str {
+        match self {
+            Self::Json => "application/json",
+            Self::Svg => "image/svg+xml",
+            Self::Png => "image/png",
+        }
+    }
+}
+
+/// A failed request: status code and plain-text reason.
+type Failure = (u16, String);
+
+/// Answer render requests on `addr` until the process is stopped, one
+/// worker thread per core.
+pub fn serve(addr: &str) -> Result<()> {
+    let server = Server::http(addr).map_err(|e| anyhow!("cannot listen on {addr}: {e}"))?;
+    // Loading system fonts takes a while; do it once for all PNG renders.
+    let mut svg_options = resvg::usvg::Options::default();
+    svg_options.fontdb_mut().load_system_fonts();
+
+    let workers = std::thread::available_parallelism().map_or(1, usize::from);
+    std::thread::scope(|scope| {
+        for _ in 0..workers {
+            scope.spawn(|| {
+                while let Ok(request) = server.recv() {
+                    handle(request, &svg_options);
+                }
+            });
+        }
+    });
+    Ok(())
+}
+
+fn handle(mut request: Request, svg_options: &resvg::usvg::Options) {
+    let url = request.url().to_string();
+    let (path, query) = url.split_once('?').unwrap_or((&url, ""));
+    let result = if path != "/render" {
+        Err((404, "not found; POST profiles to /render".to_string()))
+    } else if *request.method() != Method::Post {
+        Err((405, "use POST".to_string()))
+    } else {
+        survive_panics(|| render(&mut request, query, MAX_BODY, svg_options))
+    };
+    let (status, content_type, body) = match result {
+        Ok((content_type, body)) => (200, content_type, body),
+        Err((status, reason)) => (status, "text/plain; charset=utf-8", reason.into_bytes()),
+    };
+    let mut response = Response::from_data(body).with_status_code(status);
+    if let Ok(header) = Header::from_bytes("Content-Type", content_type) {
+        response.add_header(header);
+    }
+    // The client may have hung up; there is no one left to tell.
+    let _ = request.respond(response);
+}
+
+/// Run `render`, answering 500 if it panics, so that a parser bug does not
+/// take the worker thread down with it.
+fn survive_panics(
+    render: impl FnOnce() -> Result<(&'static str, Vec<u8>), Failure>,
+) -> Result<(&'static str, Vec<u8>), Failure> {
+    std::panic::catch_unwind(AssertUnwindSafe(render))
+        .unwrap_or_else(|_| Err((500, "rendering the profile failed".to_string())))
+}
+
+/// Parse and render the profile in `request`'s body, refusing bodies
+/// over `max_body` bytes.
+fn render(
+    request: &mut Request,
+    query: &str,
+    max_body: u64,
+    svg_options: &resvg::usvg::Options,
+) -> Result<(&'static str, Vec<u8>), Failure> {
+    let mut format = Format::Json;
+    let mut dark = false;
+    for pair in query.split('&').filter(|p| !p.is_empty()) {
+        match pair.split_once('=').unwrap_or((pair, "")) {
+            ("format", name) => {
+                format = Format::from_name(name)
+                    .ok_or_else(|| (400, format!("unknown format {name:?}")))?;
+            }
+            ("theme", theme) => dark = theme == "dark",
+            _ => return Err((400, format!("unknown parameter {pair:?}"))),
+        }
+    }
+    let mut options = match request
+        .headers()
+        .iter()
+        .find(|h| h.field.equiv("X-Render-Options"))
+    {
+        Some(header) => serde_json::from_str::<RenderOptions>(header.value.as_str())
+            .map_err(|e| (400, format!("invalid render options: {e}")))?,
+        None => RenderOptions::default(),
+    };
+    if !options.is_supported() {
+        return Err((
+            400,
+            format!(
+                "render options version {} is newer than supported version {RENDER_OPTIONS_VERSION}",
+                options.version
+            ),
+        ));
+    }
+    if options.viewport.width == 0.0 && options.viewport.height == 0.0 {
+        options.viewport.width = DEFAULT_WIDTH;
+        options.viewport.height = DEFAULT_HEIGHT;
+    }
+    check_viewport(&options.viewport)?;
+
+    let mut body = Vec::new();
+    request
+        .as_reader()
+        .take(max_body + 1)
+        .read_to_end(&mut body)
+        .map_err(|e| (400, format!("cannot read body: {e}")))?;
+    if body.len() as u64 > max_body {
+        return Err((413, format!("profiles are limited to {max_body} bytes")));
+    }
+    let profile = flame_cat_core::parsers::parse_auto_with_limits(&body, &Limits::default())
+        .map_err(|e| (422, format!("cannot parse profile: {e}")))?;
+    let commands = flame_cat_core::views::render(&profile, &options);
+
+    let (width, height) = (options.viewport.width, options.viewport.height);
+    let svg = || {
+        let options = SvgOptions {
+            dark,
+            ..SvgOptions::default()
+        };
+        render_svg_with(&commands, width, height, &options)
+    };
+    let bytes = match format {
+        Format::Json => serde_json::to_vec(&commands).map_err(|e| (500, e.to_string()))?,
+        Format::Svg => svg().into_bytes(),
+        Format::Png => {
+            rasterize(&svg(), options.viewport.dpr, svg_options).map_err(|e| (500, e))?
+        }
+    };
+    Ok((format.content_type(), bytes))
+}
+
+/// Refuse viewports that are empty, not finite, or would need more than
+/// [`MAX_PIXELS`] device pixels to draw.
+fn check_viewport(viewport: &Viewport) -> Result<(), Failure> {
+    let Viewport {
+        width, height, dpr, ..
+    } = *viewport;
+    // `!(x > 0)` also refuses NaN.
+    if !(width > 0.0 && height > 0.0 && dpr > 0.0) {
+        return Err((
+            400,
+            "viewport width, height and pixel ratio must be positive".to_string(),
+        ));
+    }
+    // Infinity fails the caps, so everything past them is finite.
+    if !(width <= MAX_WIDTH
+        && height <= MAX_HEIGHT
+        && dpr <= MAX_DPR
+        && width * height * dpr * dpr <= MAX_PIXELS)
+    {
+        return Err((
+            400,
+            format!(
+                "viewports are limited to {MAX_WIDTH}×{MAX_HEIGHT} at a pixel ratio of \
+                 {MAX_DPR} and {MAX_PIXELS} device pixels"
+            ),
+        ));
+    }
+    Ok(())
+}
+
+/// Rasterize an SVG document as PNG, `dpr` device pixels to each of its
+/// own.
+fn rasterize(svg: &str, dpr: f64, options: &resvg::usvg::Options) -> Result<Vec<u8>, String> {
+    let tree = resvg::usvg::Tree::from_str(svg, options).map_err(|e| e.to_string())?;
+    let scale = dpr as f32;
+    let size = tree
+        .size()
+        .to_int_size()
+        .scale_by(scale)
+        .ok_or_else(|| format!("cannot draw an image at pixel ratio {dpr}"))?;
+    let mut pixmap = resvg::tiny_skia::Pixmap::new(size.width(), size.height())
+        .ok_or_else(|| format!("cannot draw a {}×{} image", size.width(), size.height()))?;
+    resvg::render(
+        &tree,
+        resvg::tiny_skia::Transform::from_scale(scale, scale),
+        &mut pixmap.as_mut(),
+    );
+    pixmap.encode_png().map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use tiny_http::TestRequest;
+
+    use super::*;
+
+    fn post(body: &'static str, options: Option<&str>) -> Result<Request> {
+        let mut request = TestRequest::new()
+            .with_method(Method::Post)
+            .with_path("/render")
+            .with_body(body);
+        if let Some(options) = options {
+            let header = Header::from_bytes("X-Render-Options", options)
+                .map_err(|()| anyhow!("invalid header {options:?}"))?;
+            request = request.with_header(header);
+        }
+        Ok(request.into())
+    }
+
+    fn status(result: Result<(&'static str, Vec<u8>), Failure>) -> u16 {
+        result.map_or_else(|(status, _)| status, |_| 200)
+    }
+
+    #[test]
+    fn renders_posted_profile() -> Result<()> {
+        let options = resvg::usvg::Options::default();
+        let (content_type, body) = render(
+            &mut post("main;work 5\n", None)?,
+            "format=svg",
+            MAX_BODY,
+            &options,
+        )
+        .map_err(|(status, reason)| anyhow!("{status}: {reason}"))?;
+        assert_eq!(content_type, "image/svg+xml");
+        let svg = String::from_utf8(body)?;
+        assert!(svg.starts_with("<svg") && svg.contains("work"));
+        Ok(())
+    }
+
+    #[test]
+    fn draws_png_in_device_pixels() -> Result<()> {
+        let options = resvg::usvg::Options::default();
+        let (content_type, png) = render(
+            &mut post(
+                "main;work 5\n",
+                Some(r#"{"viewport":{"x":0,"y":0,"width":300,"height":100,"dpr":2}}"#),
+            )?,
+            "format=png",
+            MAX_BODY,
+            &options,
+        )
+        .map_err(|(status, reason)| anyhow!("{status}: {reason}"))?;
+        assert_eq!(content_type, "image/png");
+        // Width and height open the IHDR chunk, after the signature.
+        let size = png.get(16..24).ok_or_else(|| anyhow!("truncated PNG"))?;
+        assert_eq!(size, [0, 0, 2, 88, 0, 0, 0, 200]);
+        Ok(())
+    }
+
+    #[test]
+    fn answers_500_when_rendering_panics() {
+        let panicked = survive_panics(|| std::panic::resume_unwind(Box::new("parser bug")));
+        assert_eq!(status(panicked), 500);
+        assert_eq!(status(survive_panics(|| Ok(("text/plain", vec![])))), 200);
+    }
+
+    #[test]
+    fn refuses_bad_requests() -> Result<()> {
+        let options = resvg::usvg::Options::default();
+        let malformed = render(
+            &mut post("{\"traceEvents\": 7}", None)?,
+            "",
+            MAX_BODY,
+            &options,
+        );
+        assert_eq!(status(malformed), 422);
+
+        let oversized = render(&mut post("main;work 5\n", None)?, "", 4, &options);
+        assert_eq!(status(oversized), 413);
+
+        for (viewport, reason) in [
+            (
+                r#"{"viewport":{"x":0,"y":0,"width":100000,"height":600,"dpr":1}}"#,
+                "viewports are limited",
+            ),
+            (
+                r#"{"viewport":{"x":0,"y":0,"width":1200,"height":100000,"dpr":1}}"#,
+                "viewports are limited",
+            ),
+            (
+                r#"{"viewport":{"x":0,"y":0,"width":1200,"height":600,"dpr":64}}"#,
+                "viewports are limited",
+            ),
+            // Within each cap, but a gigabyte of pixels.
+            (
+                r#"{"viewport":{"x":0,"y":0,"width":16384,"height":16384,"dpr":1}}"#,
+                "viewports are limited",
+            ),
+            (
+                r#"{"viewport":{"x":0,"y":0,"width":4096,"height":4096,"dpr":4}}"#,
+                "viewports are limited",
+            ),
+            (
+                r#"{"viewport":{"x":0,"y":0,"width":-1200,"height":600,"dpr":1}}"#,
+                "viewport width, height and pixel ratio must be positive",
+            ),
+            (
+                r#"{"viewport":{"x":0,"y":0,"width":1200,"height":0,"dpr":1}}"#,
+                "viewport width, height and pixel ratio must be positive",
+            ),
+            (
+                r#"{"viewport":{"x":0,"y":0,"width":1200,"height":600,"dpr":0}}"#,
+                "viewport width, height and pixel ratio must be positive",
+            ),
+            (
+                r#"{"viewport":{"x":0,"y":0,"width":1200,"height":600,"dpr":-2}}"#,
+                "viewport width, height and pixel ratio must be positive",
+            ),
+        ] {
+            let failure = render(
+                &mut post("main;work 5\n", Some(viewport))?,
+                "",
+                MAX_BODY,
+                &options,
+            )
+            .err();
+            assert_eq!(
+                failure.as_ref().map(|(status, _)| *status),
+                Some(400),
+                "{viewport}"
+            );
+            assert!(
+                failure.is_some_and(|(_, message)| message.starts_with(reason)),
+                "{viewport}"
+            );
+        }
+
+        // Non-finite sizes can't be written in JSON but can be built.
+        let mut viewport = RenderOptions::default().viewport;
+        viewport.width = f64::NAN;
+        viewport.height = 600.0;
+        assert_eq!(
+            check_viewport(&viewport).map_err(|(status, _)| status),
+            Err(400)
+        );
+        viewport.width = f64::INFINITY;
+        assert_eq!(
+            check_viewport(&viewport).map_err(|(status, _)| status),
+            Err(400)
+        );
+        Ok(())
+    }
+}
@@ -1,7 +1,37 @@
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+
 use eframe::egui;
-use flame_cat_core::model::Session;
+use flame_cat_core::analysis::breakdown::{
+    binary_breakdown, class_summary, BinaryEntry, CategoryTotal,
+};
+use flame_cat_core::analysis::gpu::{link_submissions, GpuSubmission};
+use flame_cat_core::analysis::tasks::{long_task_threshold, top_level_tasks, Task};
+use flame_cat_core::analysis::utilization::busy_fractions;
+use flame_cat_core::analysis::{span_info, ChildTotal, SpanInfo};
+use flame_cat_core::legend::{category_legend, LegendEntry};
+use flame_cat_core::model::session_file::{
+    self, LaneView, PinnedRow, PinnedSpan, SessionState, ViewRegion, ViewState,
+};
+use flame_cat_core::model::{
+    import_sidecar, Alignment, AsyncLinks, DiffNormalization, Navigation, SelectionState, Session,
+    ThreadOrder, ThreadSort,
+};
 use flame_cat_core::parsers;
-use flame_cat_protocol::{RenderCommand, Viewport, VisualProfile};
+use flame_cat_core::parsers::chrome::ChromeTraceStream;
+use flame_cat_core::parsers::FormatHint;
+use flame_cat_core::transform::NameRewrite;
+use flame_cat_core::views::minimap::{
+    lane_thumbnails, minimap_hit_test, DensityPyramid, MinimapDrag, MinimapHit, THUMBNAIL_COLS,
+};
+use flame_cat_core::views::ranked::Baseline;
+use flame_cat_core::views::time_axis::{nice_interval, render_time_axis};
+use flame_cat_core::views::time_order::{expanded_max_depth, FRAME_HEIGHT};
+use flame_cat_protocol::{
+    ClassRules, CodeClass, DurationFormat, HeatScale, LodOptions, Point, ProfileMetaEdit,
+    RenderCommand, RenderLimits, RenderOptions, SearchOptions, SharedStr, Span, TimeRange,
+    ValueUnit, Viewport, VisualProfile,
+};
 
 use crate::renderer;
 use crate::theme::{
@@ -14,23 +44,218 @@ const ANIM_EASE_FACTOR: f64 = 0.25;
 const ANIM_EASE_BOOST: f64 = 1.5;
 const ANIM_SNAP_EPSILON: f64 = 1e-4;
 const MIN_VIEW_SPAN: f64 = 1e-12;
+/// Replay speeds offered in the toolbar, as multiples of real time.
+const PLAYBACK_RATES: [f64; 3] = [1.0, 0.5, 0.1];
+/// Longest frame time replay advances by, so the first frame after a stall
+/// (a hidden tab) does not jump ahead.
+const MAX_PLAYBACK_STEP_S: f64 = 0.1;
+/// Least time between rebuilds of the live profile, so a burst of batches
+/// costs one rebuild.
+const LIVE_REFRESH_S: f64 = 0.25;
+/// Narrower minimap drags are treated as clicks rather than new ranges.
+const MIN_MINIMAP_RANGE_PX: f64 = 3.0;
 const MAX_BREADCRUMB_DEPTH: usize = 10;
+/// Row zoom bounds of thread lanes; zooming out also stops once the stack fits.
+const MIN_ROW_SCALE: f32 = 0.1;
+const MAX_ROW_SCALE: f32 = 2.0;
 const SIDEBAR_NAME_MAX_CHARS: usize = 24;
+/// Deepest frames listed in the CPU sample stack tooltip.
+const MAX_TOOLTIP_STACK: usize = 24;
+const LOAD_CANCELLED: &str = "Load cancelled";
+
+/// A parsed profile plus everything derived from it that is too expensive
+/// to compute on the UI thread.
+struct LoadedProfile {
+    session: Session,
+    /// Saved view state when the input was a `.flamecat` session file.
+    state: Option<SessionState>,
+    density: DensityPyramid,
+    /// Sidebar density strips of the primary profile's threads.
+    thumbnails: BTreeMap<u32, Vec<u32>>,
+    /// Auto-zoom bounds in absolute µs.
+    zoom_bounds: Option<(f64, f64)>,
+    /// Serialized profile for the JS export cache.
+    json: Option<String>,
+}
+
+/// An in-flight background parse (native only).
+#[cfg(not(target_arch = "wasm32"))]
+struct LoadJob {
+    result: std::sync::mpsc::Receiver<Result<LoadedProfile, String>>,
+    /// Set to make the worker stop at its next check.
+    cancel: std::sync::Arc<AtomicBool>,
+}
+
+/// Parse `data` and derive the per-load caches. Safe to run on any thread.
+///
+/// Accepts either a single profile in any supported format or a saved
+/// `.flamecat` session. Setting `cancel` stops the parse between events and
+/// the rest of the work between steps.
+fn prepare_profile(
+    data: &[u8],
+    hint: &FormatHint,
+    cancel: &AtomicBool,
+) -> Result<LoadedProfile, String> {
+    let cancelled = || cancel.load(Ordering::Relaxed);
+    #[cfg(target_arch = "wasm32")]
+    web_sys::console::log_1(&format!("flame.cat: parsing {} bytes...", data.len()).into());
+    if session_file::is_session_file(data) {
+        let (session, state) =
+            session_file::load_session(data).map_err(|e| format!("Failed to open session: {e}"))?;
+        let Some(entry) = session.profiles().first() else {
+            return Err("Session file contains no profiles".into());
+        };
+        return Ok(LoadedProfile {
+            zoom_bounds: None,
+            density: DensityPyramid::build(&entry.profile),
+            thumbnails: lane_thumbnails(&entry.profile, THUMBNAIL_COLS),
+            json: serde_json::to_string(&entry.profile).ok(),
+            state: Some(state),
+            session,
+        });
+    }
+    // Hostile or corrupt input loads partially instead of exhausting memory,
+    // and a trace cut short loads up to where it breaks off.
+    let limits = parsers::Limits::default();
+    let profiles = parsers::parse_visual_cancellable(data, hint, &limits, cancel)
+        .or_else(|e| {
+            let recovering = FormatHint {
+                recover: true,
+                ..hint.clone()
+            };
+            if hint.recover || matches!(e, parsers::ParseError::Cancelled) {
+                return Err(e);
+            }
+            parsers::parse_visual_cancellable(data, &recovering, &limits, cancel).map_err(|_| e)
+        })
+        .map_err(|e| format!("Failed to parse profile: {e}"))?;
+    let mut session = Session::new();
+    for mut profile in profiles {
+        if cancelled() {
+            return Err(LOAD_CANCELLED.into());
+        }
+        #[cfg(target_arch = "wasm32")]
+        web_sys::console::log_1(
+            &format!("flame.cat: loaded {} threads", profile.threads.len()).into(),
+        );
+
+        // Crop profile time bounds to actual span data range
+        let mut data_start = f64::INFINITY;
+        let mut data_end = f64::NEG_INFINITY;
+        for span in profile.all_spans() {
+            data_start = data_start.min(span.start);
+            data_end = data_end.max(span.end);
+        }
+        if data_start.is_finite() && data_end.is_finite() && data_start < data_end {
+            profile.meta.start_time = data_start;
+            profile.meta.end_time = data_end;
+        }
 
-/// Format a duration in µs to human-readable string.
-fn format_duration(us: f64) -> String {
-    if us < 1000.0 {
-        format!("{:.1}µs", us)
-    } else if us < 1_000_000.0 {
-        format!("{:.2}ms", us / 1000.0)
-    } else {
-        format!("{:.2}s", us / 1_000_000.0)
+        // Synthesize frame timings if none exist
+        if profile.frames.is_empty() {
+            profile.frames = synthesize_frame_timings(&profile);
+        }
+
+        // Split profiles are told apart by name.
+        let label = match &profile.meta.name {
+            Some(name) if hint.split_profiles => name.to_string(),
+            _ => "Profile".to_string(),
+        };
+        session.add_profile(profile, label);
+    }
+    let Some(entry) = session.profiles().first() else {
+        return Err("Failed to parse profile: no profiles found".into());
+    };
+    if cancelled() {
+        return Err(LOAD_CANCELLED.into());
+    }
+
+    Ok(LoadedProfile {
+        zoom_bounds: compute_auto_zoom(&entry.profile),
+        density: DensityPyramid::build(&entry.profile),
+        thumbnails: lane_thumbnails(&entry.profile, THUMBNAIL_COLS),
+        json: serde_json::to_string(&entry.profile).ok(),
+        state: None,
+        session,
+    })
+}
+
+/// Format a span value in its profile's own unit. Time-based values keep the
+/// usual duration formatting; sample counts are shown as samples.
+fn format_span_value(
+    meta: &flame_cat_protocol::ProfileMeta,
+    value: f64,
+    format: &DurationFormat,
+) -> String {
+    match meta.value_unit.to_microseconds_factor() {
+        Some(factor) => format.format_detail_us(value * factor),
+        None => meta.format_value_with(value, format),
     }
 }
 
+/// Stacked bar of self time per code class, with the filtered class
+/// outlined. Returns the class whose segment was clicked.
+fn class_bar(
+    ui: &mut egui::Ui,
+    classes: &[CategoryTotal],
+    meta: &flame_cat_protocol::ProfileMeta,
+    filter: Option<CodeClass>,
+    theme_mode: ThemeMode,
+    duration_format: &DurationFormat,
+) -> Option<CodeClass> {
+    let total: f64 = classes.iter().map(|c| c.self_time).sum();
+    let (bar, _) =
+        ui.allocate_exact_size(egui::vec2(ui.available_width(), 14.0), egui::Sense::hover());
+    let mut clicked = None;
+    let mut x = bar.left();
+    for item in classes {
+        let Some(class) = CodeClass::from_name(&item.name) else {
+            continue;
+        };
+        let width = if total > 0.0 {
+            (item.self_time / total) as f32 * bar.width()
+        } else {
+            0.0
+        };
+        let segment =
+            egui::Rect::from_min_size(egui::pos2(x, bar.top()), egui::vec2(width, bar.height()));
+        x += width;
+        if width <= 0.0 {
+            continue;
+        }
+        let color = renderer::category_color(item.name.as_str(), theme_mode);
+        ui.painter().rect_filled(segment, 0.0, color);
+        if filter == Some(class) {
+            ui.painter().rect_stroke(
+                segment,
+                0.0,
+                egui::Stroke::new(2.0, ui.visuals().strong_text_color()),
+                egui::StrokeKind::Inside,
+            );
+        }
+        let share = item.self_time / total * 100.0;
+        let response = ui
+            .interact(
+                segment,
+                ui.id().with(("code_class", item.name.as_str())),
+                egui::Sense::click(),
+            )
+            .on_hover_text(format!(
+                "{}  {} ({share:.1}%)\n{} spans",
+                class.label(),
+                format_span_value(meta, item.self_time, duration_format),
+                item.span_count
+            ));
+        if response.clicked() {
+            clicked = Some(class);
+        }
+    }
+    clicked
+}
+
 /// Main application state.
 pub struct FlameApp {
-    session: Option<Session>,
+    session: Option<std::sync::Arc<Session>>,
     /// Per-lane state.
     lanes: Vec<LaneState>,
     /// Fractional view window [0..1].
@@ -42,44 +267,116 @@ pub struct FlameApp {
     view_type: crate::ViewType,
     /// How span rects are colored.
     color_mode: crate::renderer::ColorMode,
-    /// Cached render commands per lane (invalidated on zoom/scroll/resize).
+    /// What the heat color modes compare spans with.
+    heat_scale: HeatScale,
+    /// Share of the total, in percent, under which merged subtrees collapse
+    /// into an "other" block in the aggregate views.
+    prune_percent: f64,
+    /// Session profile drawn over the first one in time-order thread lanes.
+    overlay_profile: Option<usize>,
+    /// Leave kernel frames out of thread lanes.
+    hide_kernel: bool,
+    /// Whether the primary profile has kernel frames, checked on first use
+    /// per session.
+    has_kernel_frames: Option<bool>,
+    /// Draw the functions inlined into a span on rows of their own.
+    expand_inlined: bool,
+    /// Stack depth, with inlined functions expanded, of each primary-profile
+    /// thread that has any; computed on first use per session.
+    inline_depths: Option<std::collections::HashMap<u32, u32>>,
+    /// Depth and command-count guards for thread lanes, tunable by the host.
+    render_limits: RenderLimits,
+    /// Span label template per view, set by the host.
+    label_templates: BTreeMap<crate::ViewType, String>,
+    /// How durations are written in tooltips, the axis and tables.
+    duration_format: DurationFormat,
+    /// Cached render commands per lane, regenerated only when the lane's key changes.
     lane_commands: Vec<Vec<RenderCommand>>,
+    /// Inputs each entry of `lane_commands` was built from (`None` = stale).
+    lane_keys: Vec<Option<LaneKey>>,
     /// Global vertical scroll offset in pixels.
     scroll_y: f32,
     /// Selected span for detail panel.
     selected_span: Option<SelectedSpan>,
-    /// Search query for filtering spans.
-    search_query: String,
+    /// The selected span with its children totalled by name, and the
+    /// session it was computed from.
+    selected_info: Option<(std::sync::Weak<Session>, SpanInfo)>,
+    /// Selected/hovered span ids and search query, shared with other frontends.
+    selection: SelectionState,
     /// Error message to display.
     error: Option<String>,
     /// Pending profile data from async load.
     pending_data: std::sync::Arc<std::sync::Mutex<Option<Vec<u8>>>>,
     /// Loading state.
     loading: bool,
-    /// Cached minimap density (invalidated on profile load only).
-    minimap_density: Option<Vec<u32>>,
+    /// In-flight background parse (native only).
+    #[cfg(not(target_arch = "wasm32"))]
+    load_job: Option<LoadJob>,
+    /// Minimap density pyramid, built once per profile load.
+    minimap_density: Option<DensityPyramid>,
+    /// Density strip per thread id for the sidebar, built once per profile
+    /// load.
+    lane_thumbnails: BTreeMap<u32, Vec<u32>>,
+    /// Minimap gesture in progress, from press to release.
+    minimap_drag: Option<MinimapDrag>,
+    /// Annotations, hidden functions and named regions of the open session,
+    /// written back on save; its `view` is superseded by the live view.
+    saved_state: SessionState,
+    /// Name typed for the next saved region.
+    region_name: String,
+    /// Async span ↔ executing span links, built on first use per session.
+    async_links: Option<AsyncLinks>,
+    /// Color legend of the primary profile, built on first use per session.
+    legend: Option<Vec<LegendEntry>>,
+    /// Mapped binaries of the primary profile, built on first use per
+    /// session.
+    binaries: Option<Vec<BinaryEntry>>,
+    /// Time per code class of the primary profile, built on first use per
+    /// session.
+    classes: Option<Vec<CategoryTotal>>,
+    /// Top-level tasks of the task strip's thread, built on first use per
+    /// session.
+    tasks: Option<Vec<Task>>,
+    /// CPU submit → GPU queue links of the primary profile, built on first
+    /// use per session.
+    gpu_submissions: Option<Vec<GpuSubmission>>,
+    /// Per-name totals of the session's baseline profile, built on first
+    /// use per session. Unset when the primary profile is the baseline.
+    baseline: Option<Baseline>,
+    /// Rows of the comparison tray and the pins they were built for.
+    pinned_rows: Option<(Vec<PinnedSpan>, Vec<PinnedRow>)>,
+    /// Show the color legend panel.
+    show_legend: bool,
+    /// Show the annotations panel.
+    show_annotations: bool,
+    /// Color key picked in the legend; spans of other keys are dimmed.
+    category_filter: Option<String>,
+    /// Binary picked in the legend; spans that ran elsewhere are dimmed.
+    binary_filter: Option<String>,
+    /// Code class picked in the legend; spans of other classes are dimmed.
+    class_filter: Option<CodeClass>,
     /// Show keyboard help overlay.
     show_help: bool,
     /// Animation targets for smooth viewport transitions.
     anim_target: Option<(f64, f64)>,
+    /// Replay speed, as a multiple of real time.
+    playback_rate: f64,
+    /// Replay is advancing the viewport over the trace.
+    playing: bool,
     /// Context menu state: span info + screen position.
     context_menu: Option<ContextMenu>,
+    /// Open "Profile details" dialog.
+    meta_editor: Option<MetaEditor>,
     /// Currently hovered span (for JS event hooks).
     hovered_span: Option<SelectedSpan>,
+    /// Bring the selected span on screen at the next central panel pass.
+    reveal_selection: bool,
     /// Drag-to-zoom selection: start X position in viewport fraction.
     drag_select_start: Option<f64>,
     /// Zoom history for back/forward navigation.
     zoom_history: Vec<(f64, f64)>,
     /// Current position in zoom_history (index of last applied entry).
     zoom_history_pos: usize,
-    /// Cache key for ensure_commands (viewport + view_type hash).
-    last_cache_key: Option<(
-        usize,
-        u64,
-        u64,
-        u32,
-        std::mem::Discriminant<crate::ViewType>,
-    )>,
     /// Generation counter — incremented on any state change, used to skip snapshot rebuild.
     state_gen: u64,
     /// Last generation that was emitted as a snapshot.
@@ -88,11 +385,67 @@ pub struct FlameApp {
     last_hovered_fid: Option<u64>,
     /// Pending initial view type from URL hash (applied once after first profile load).
     pending_initial_view_type: Option<crate::ViewType>,
+    /// Live recording being followed; event batches pushed from JS extend it.
+    live: Option<ChromeTraceStream>,
+    /// Batches arrived since the live profile was last rebuilt.
+    live_pending: bool,
+    /// `egui` time of the last live rebuild.
+    live_refreshed_at: f64,
+}
+
+/// Draft values of the open "Profile details" dialog.
+struct MetaEditor {
+    profile_index: usize,
+    name: String,
+    description: String,
+    /// Comma-separated.
+    tags: String,
+    /// Sampling interval in µs; blank when unknown.
+    interval: String,
+}
+
+impl MetaEditor {
+    fn for_profile(session: &Session, profile_index: usize) -> Option<Self> {
+        let entry = session.profiles().get(profile_index)?;
+        let meta = &entry.profile.meta;
+        Some(Self {
+            profile_index,
+            name: meta
+                .name
+                .as_ref()
+                .map_or_else(|| entry.label.clone(), ToString::to_string),
+            description: meta
+                .description
+                .as_ref()
+                .map(ToString::to_string)
+                .unwrap_or_default(),
+            tags: meta.tags.join(", "),
+            interval: meta
+                .sample_interval_us
+                .map(|us| us.to_string())
+                .unwrap_or_default(),
+        })
+    }
+
+    fn to_edit(&self) -> ProfileMetaEdit {
+        // Blank clears the interval; anything unparsable leaves it be.
+        let interval = self.interval.trim();
+        ProfileMetaEdit {
+            name: Some(self.name.clone()),
+            description: Some(self.description.clone()),
+            tags: Some(self.tags.split(',').map(str::to_string).collect()),
+            sample_interval_us: if interval.is_empty() {
+                Some(0.0)
+            } else {
+                interval.parse().ok()
+            },
+        }
+    }
 }
 
 #[derive(Clone)]
 struct ContextMenu {
-    span_name: String,
+    span_name: SharedStr,
     frame_id: u64,
     lane_index: usize,
     /// Viewport-fractional bounds for zoom-to-span.
@@ -103,7 +456,7 @@ struct ContextMenu {
 
 #[derive(Clone)]
 struct SelectedSpan {
-    name: String,
+    name: SharedStr,
     frame_id: u64,
     lane_index: usize,
     /// Time bounds for zoom-to-span (in session µs).
@@ -111,9 +464,14 @@ struct SelectedSpan {
     end_us: f64,
 }
 
+#[derive(PartialEq)]
 enum LaneKind {
     /// Flame chart for a thread (uses render_time_order).
     Thread(u32),
+    /// Top-level task strip of a thread, drawn above its flame chart.
+    Tasks(u32),
+    /// Busy-fraction strip of a thread, drawn above its flame chart.
+    Utilization(u32),
     /// Counter track (memory, CPU, etc.).
     Counter(usize),
     /// Async spans track.
@@ -134,6 +492,51 @@ struct LaneState {
     height: f32,
     visible: bool,
     span_count: usize,
+    /// Vertical zoom of a thread lane's stack rows; 1.0 is the core row height.
+    row_scale: f32,
+    /// How far a thread lane's stack is scrolled down, in unscaled pixels.
+    depth_scroll: f32,
+}
+
+impl LaneState {
+    /// Scroll the stack by `delta` unscaled pixels and zoom its rows by
+    /// `zoom`, keeping the stack position `anchor` pixels below the lane's
+    /// top in place. `stack_height` is the whole stack's unscaled height.
+    fn zoom_stack(&mut self, stack_height: f32, delta: f32, zoom: f32, anchor: f32) {
+        let fit = (self.height / stack_height).clamp(MIN_ROW_SCALE, 1.0);
+        let anchored = self.depth_scroll + anchor / self.row_scale;
+        self.row_scale = (self.row_scale * zoom).clamp(fit, MAX_ROW_SCALE);
+        let max_scroll = (stack_height - self.height / self.row_scale).max(0.0);
+        self.depth_scroll = (anchored - anchor / self.row_scale + delta).clamp(0.0, max_scroll);
+    }
+}
+
+/// Everything a lane's render commands depend on. A lane is regenerated
+/// only when its key differs from the one its cached commands were built with.
+#[derive(Clone, Copy, PartialEq, Eq)]
+struct LaneKey {
+    visible: bool,
+    view_type: crate::ViewType,
+    width: u32,
+    height: u32,
+    /// Stack zoom and scroll bits, which the lane's transform is built from.
+    /// A zoom anchored at the bottom leaves `height` as it was.
+    row_scale: u32,
+    depth_scroll: u32,
+    /// View window bits, or `None` for views that lay out the whole profile.
+    window: Option<(u64, u64)>,
+    /// Selected frame for sandwich lanes; whether this lane owns the ranked table.
+    focus: Option<u64>,
+    /// Hash of the search query; non-matching rects are baked in as dimmed.
+    search: u64,
+    /// Heat coloring, which is baked into the commands unlike the other modes.
+    heat: Option<(crate::renderer::ColorMode, HeatScale)>,
+    /// Prune threshold bits, for the views that collapse light subtrees.
+    prune: u64,
+    /// Profile laid over thread lanes in the time-order view.
+    overlay: Option<usize>,
+    hide_kernel: bool,
+    expand_inlined: bool,
 }
 
 impl FlameApp {
@@ -221,20 +624,54 @@ impl FlameApp {
             theme_mode: ThemeMode::Dark,
             view_type: initial_view_type,
             color_mode: crate::renderer::ColorMode::ByName,
+            heat_scale: HeatScale::default(),
+            prune_percent: 0.0,
+            overlay_profile: None,
+            hide_kernel: false,
+            has_kernel_frames: None,
+            expand_inlined: false,
+            inline_depths: None,
+            render_limits: RenderLimits::default(),
+            label_templates: BTreeMap::new(),
+            duration_format: DurationFormat::default(),
             lane_commands: Vec::new(),
+            lane_keys: Vec::new(),
             scroll_y: 0.0,
             selected_span: None,
-            search_query: String::new(),
+            selected_info: None,
+            selection: SelectionState::default(),
             error: None,
             pending_data,
             loading: false,
+            #[cfg(not(target_arch = "wasm32"))]
+            load_job: None,
             minimap_density: None,
+            lane_thumbnails: BTreeMap::new(),
+            minimap_drag: None,
+            saved_state: SessionState::default(),
+            region_name: String::new(),
+            async_links: None,
+            legend: None,
+            binaries: None,
+            classes: None,
+            tasks: None,
+            gpu_submissions: None,
+            baseline: None,
+            pinned_rows: None,
+            show_legend: false,
+            show_annotations: false,
+            category_filter: None,
+            binary_filter: None,
+            class_filter: None,
             show_help: false,
             anim_target: None,
+            playback_rate: PLAYBACK_RATES[0],
+            playing: false,
             context_menu: None,
+            meta_editor: None,
             hovered_span: None,
+            reveal_selection: false,
             drag_select_start: None,
-            last_cache_key: None,
             zoom_history: vec![(0.0, 1.0)],
             zoom_history_pos: 0,
             state_gen: 0,
@@ -245,6 +682,9 @@ impl FlameApp {
             } else {
                 None
             },
+            live: None,
+            live_pending: false,
+            live_refreshed_at: f64::NEG_INFINITY,
         }
     }
 
@@ -253,108 +693,643 @@ impl FlameApp {
         self.pending_data.clone()
     }
 
-    fn load_profile(&mut self, data: &[u8]) {
-        #[cfg(target_arch = "wasm32")]
-        web_sys::console::log_1(&format!("flame.cat: parsing {} bytes...", data.len()).into());
-        match parsers::parse_auto_visual(data) {
-            Ok(mut profile) => {
-                #[cfg(target_arch = "wasm32")]
-                web_sys::console::log_1(
-                    &format!("flame.cat: loaded {} threads", profile.threads.len()).into(),
-                );
-
-                // Crop profile time bounds to actual span data range
-                let mut data_start = f64::INFINITY;
-                let mut data_end = f64::NEG_INFINITY;
-                for span in profile.all_spans() {
-                    data_start = data_start.min(span.start);
-                    data_end = data_end.max(span.end);
-                }
-                if data_start.is_finite() && data_end.is_finite() && data_start < data_end {
-                    profile.meta.start_time = data_start;
-                    profile.meta.end_time = data_end;
-                }
+    /// Parse `data` (following `hint`) and install it as the current profile.
+    ///
+    /// The parse runs on a worker thread and the result is picked up in
+    /// `update`, so the UI keeps painting (spinner + cancel) while large
+    /// files parse.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn load_profile<D>(&mut self, ctx: &egui::Context, data: D, hint: FormatHint)
+    where
+        D: AsRef<[u8]> + Send + 'static,
+    {
+        let (tx, rx) = std::sync::mpsc::channel();
+        let cancel = std::sync::Arc::new(AtomicBool::new(false));
+        let ctx = ctx.clone();
+        let worker_cancel = cancel.clone();
+        let spawned = std::thread::Builder::new()
+            .name("flame-cat-parse".into())
+            .spawn(move || {
+                // A closed channel means the load was cancelled — drop the result.
+                let _ = tx.send(prepare_profile(data.as_ref(), &hint, &worker_cancel));
+                ctx.request_repaint();
+            });
+        match spawned {
+            Ok(_) => {
+                // Replacing an in-flight job cancels it.
+                self.cancel_load();
+                self.load_job = Some(LoadJob { result: rx, cancel });
+                self.loading = true;
+                self.error = None;
+            }
+            Err(e) => {
+                self.error = Some(format!("Failed to start parser thread: {e}"));
+            }
+        }
+    }
 
-                // Synthesize frame timings if none exist
-                if profile.frames.is_empty() {
-                    profile.frames = synthesize_frame_timings(&profile);
-                }
+    /// Parse `data` (following `hint`) and install it as the current profile.
+    /// There are no threads on wasm, so it runs inline.
+    #[cfg(target_arch = "wasm32")]
+    fn load_profile(&mut self, ctx: &egui::Context, data: &[u8], hint: &FormatHint) {
+        let _ = ctx;
+        let result = prepare_profile(data, hint, &AtomicBool::new(false));
+        self.install_profile(result);
+    }
 
-                self.setup_lanes(&profile);
+    /// Abandon an in-flight background parse and stop its worker.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn cancel_load(&mut self) {
+        if let Some(job) = self.load_job.take() {
+            job.cancel.store(true, Ordering::Relaxed);
+        }
+        self.loading = false;
+    }
 
-                // Cache serialized profile for export
-                crate::set_profile_json(serde_json::to_string(&profile).ok());
+    /// Pick up a finished background parse, if any.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn poll_load_job(&mut self) {
+        let Some(job) = &self.load_job else {
+            return;
+        };
+        match job.result.try_recv() {
+            Ok(result) => {
+                self.load_job = None;
+                self.loading = false;
+                self.install_profile(result);
+            }
+            Err(std::sync::mpsc::TryRecvError::Empty) => {}
+            Err(std::sync::mpsc::TryRecvError::Disconnected) => {
+                self.load_job = None;
+                self.loading = false;
+                self.error = Some("Parser thread exited without a result".into());
+            }
+        }
+    }
 
-                // Compute auto-zoom bounds before consuming profile
-                let zoom_bounds = compute_auto_zoom(&profile);
+    /// Install a prepared profile as the current session.
+    fn install_profile(&mut self, result: Result<LoadedProfile, String>) {
+        let loaded = match result {
+            Ok(loaded) => loaded,
+            Err(e) => {
+                self.error = Some(e);
+                return;
+            }
+        };
+        let LoadedProfile {
+            session,
+            state,
+            density,
+            thumbnails,
+            zoom_bounds,
+            json,
+        } = loaded;
+        self.live = None;
+
+        if let Some(entry) = session.profiles().first() {
+            self.setup_lanes(&entry.profile, session.thread_order());
+        }
 
-                let session = Session::from_profile(profile, "Profile");
-                let session_start = session.start_time();
-                let session_end = session.end_time();
-                let duration = session_end - session_start;
+        // Cache serialized profile for export
+        crate::set_profile_json(json);
 
-                if duration > 0.0 {
-                    if let Some((lo, hi)) = zoom_bounds {
-                        let pad = (hi - lo) * 0.15;
-                        self.view_start = ((lo - pad - session_start) / duration).clamp(0.0, 1.0);
-                        self.view_end = ((hi + pad - session_start) / duration).clamp(0.0, 1.0);
-                    }
-                } else {
-                    self.view_start = 0.0;
-                    self.view_end = 1.0;
-                }
+        let session = std::sync::Arc::new(session);
+        crate::set_session(Some(session.clone()));
+        let session_start = session.start_time();
+        let session_end = session.end_time();
+        let duration = session_end - session_start;
 
-                self.session = Some(session);
-                self.scroll_y = 0.0;
-                self.error = None;
-                self.selected_span = None;
-                self.minimap_density = None;
-                self.invalidate_commands();
+        if duration > 0.0 {
+            if let Some((lo, hi)) = zoom_bounds {
+                let pad = (hi - lo) * 0.15;
+                self.view_start = ((lo - pad - session_start) / duration).clamp(0.0, 1.0);
+                self.view_end = ((hi + pad - session_start) / duration).clamp(0.0, 1.0);
             }
-            Err(e) => {
-                self.error = Some(format!("Failed to parse profile: {e}"));
+        } else {
+            self.view_start = 0.0;
+            self.view_end = 1.0;
+        }
+
+        self.session = Some(session);
+        self.scroll_y = 0.0;
+        self.error = None;
+        self.set_selected_span(None);
+        self.minimap_density = Some(density);
+        self.lane_thumbnails = thumbnails;
+        self.reset_lane_cache();
+
+        // Apply hash-based view type preset after first profile load
+        if let Some(vt) = self.pending_initial_view_type.take() {
+            self.view_type = vt;
+            self.invalidate_commands();
+        }
+
+        match state {
+            Some(state) => {
+                self.apply_view_state(&state.view);
+                self.saved_state = state;
             }
+            None => self.saved_state = SessionState::default(),
         }
     }
 
-    fn setup_lanes(&mut self, profile: &VisualProfile) {
+    /// Start following a live recording. The current profile is dropped and
+    /// replaced by one built from the batches appended afterwards.
+    fn open_live_session(&mut self) {
+        // Batches are read on the UI thread as they arrive, so there is no
+        // parse in flight for anything else to cancel.
+        self.live = Some(ChromeTraceStream::new(parsers::Limits::default(), None));
+        self.live_pending = false;
+        self.live_refreshed_at = f64::NEG_INFINITY;
+        self.session = None;
+        crate::set_session(None);
+        crate::set_profile_json(None);
         self.lanes.clear();
+        self.minimap_density = None;
+        self.lane_thumbnails.clear();
+        self.set_selected_span(None);
+        self.view_start = 0.0;
+        self.view_end = 1.0;
+        self.error = None;
+        self.reset_lane_cache();
+    }
+
+    /// Stop following the live recording, keeping what was received so far
+    /// as an ordinary profile.
+    fn close_live_session(&mut self) {
+        if self.live_pending {
+            self.refresh_live();
+            self.live_pending = false;
+        }
+        if let Some(session) = &self.session {
+            crate::set_profile_json(
+                session
+                    .profiles()
+                    .first()
+                    .and_then(|e| serde_json::to_string(&e.profile).ok()),
+            );
+        }
+        self.live = None;
+    }
+
+    /// Rebuild the live profile after new batches arrived. The view keeps
+    /// the same absolute time range, or stays pinned to the end of the
+    /// recording if it was showing the end.
+    fn refresh_live(&mut self) {
+        let Some(stream) = &self.live else {
+            return;
+        };
+        let profile = stream.profile().into_visual_profile();
 
-        // Collect threads sorted by span count (densest first)
-        let mut thread_info: Vec<_> = profile
+        // New threads or tracks may have appeared.
+        let order = self
+            .session
+            .as_ref()
+            .map(|s| s.thread_order().clone())
+            .unwrap_or_default();
+        self.relayout_lanes(&profile, &order);
+        self.minimap_density = Some(DensityPyramid::build(&profile));
+        self.lane_thumbnails = lane_thumbnails(&profile, THUMBNAIL_COLS);
+
+        let mut session = Session::from_profile(profile, "Live");
+        session.set_thread_order(order);
+        let session = std::sync::Arc::new(session);
+        if let Some(old) = &self.session {
+            let (old_start, old_d) = (old.start_time(), old.end_time() - old.start_time());
+            let (new_start, new_end) = (session.start_time(), session.end_time());
+            let new_d = new_end - new_start;
+            let whole_view = self.view_start <= 0.0 && self.view_end >= 1.0;
+            if old_d > 0.0 && new_d > 0.0 && !whole_view {
+                let lo = old_start + self.view_start * old_d;
+                let hi = old_start + self.view_end * old_d;
+                let (lo, hi) = if self.view_end >= 1.0 {
+                    (new_end - (hi - lo), new_end)
+                } else {
+                    (lo, hi)
+                };
+                self.view_start = ((lo - new_start) / new_d).clamp(0.0, 1.0);
+                self.view_end = ((hi - new_start) / new_d).clamp(0.0, 1.0);
+            }
+        }
+        crate::set_session(Some(session.clone()));
+        self.session = Some(session);
+        self.reset_lane_cache();
+    }
+
+    /// Rebuild the lanes for `profile`, keeping the user's visibility and
+    /// height for lanes that already existed.
+    fn relayout_lanes(&mut self, profile: &VisualProfile, order: &ThreadOrder) {
+        let previous: Vec<(LaneKind, bool, f32)> = self
+            .lanes
+            .drain(..)
+            .map(|l| (l.kind, l.visible, l.height))
+            .collect();
+        self.setup_lanes(profile, order);
+        for lane in &mut self.lanes {
+            if let Some((_, visible, height)) = previous.iter().find(|(k, ..)| *k == lane.kind) {
+                lane.visible = *visible;
+                lane.height = *height;
+            }
+        }
+    }
+
+    /// Change the session's thread order and lay the lanes out again.
+    fn update_thread_order(&mut self, update: impl FnOnce(&mut ThreadOrder)) {
+        let Some(session) = &self.session else {
+            return;
+        };
+        let mut order = session.thread_order().clone();
+        update(&mut order);
+        if order == *session.thread_order() {
+            return;
+        }
+        let mut session = Session::clone(session);
+        session.set_thread_order(order);
+        if let Some(entry) = session.profiles().first() {
+            self.relayout_lanes(&entry.profile, session.thread_order());
+        }
+        let session = std::sync::Arc::new(session);
+        crate::set_session(Some(session.clone()));
+        self.session = Some(session);
+        self.reset_lane_cache();
+    }
+
+    /// Display name of the thread behind a thread lane.
+    fn lane_thread_name(&self, kind: &LaneKind) -> Option<SharedStr> {
+        let LaneKind::Thread(id) = kind else {
+            return None;
+        };
+        let entry = self.session.as_ref()?.profiles().first()?;
+        entry
+            .profile
             .threads
             .iter()
-            .map(|t| {
-                let span_count = t.spans.len();
-                let max_depth = t.spans.iter().map(|s| s.depth).max().unwrap_or(0);
-                (t, span_count, max_depth)
+            .find(|t| t.id == *id)
+            .map(|t| t.name.clone())
+    }
+
+    /// Largest sample of a counter lane's counter, the starting point when a
+    /// budget is first set.
+    fn lane_counter_peak(&self, kind: &LaneKind) -> Option<f64> {
+        let LaneKind::Counter(idx) = kind else {
+            return None;
+        };
+        let entry = self.session.as_ref()?.profiles().first()?;
+        let counter = entry.profile.counters.get(*idx)?;
+        Some(counter.samples.iter().map(|s| s.value).fold(0.0, f64::max))
+    }
+
+    /// Rewrite minified JS frames of every loaded profile using a source map.
+    fn apply_source_map(&mut self, script_url: Option<&str>, map: &[u8]) {
+        let Some(session) = &self.session else {
+            self.error = Some("Load a profile before applying a source map".into());
+            return;
+        };
+        let source_map = match flame_cat_core::sourcemap::SourceMap::parse(map) {
+            Ok(source_map) => source_map,
+            Err(e) => {
+                self.error = Some(format!("Invalid source map: {e}"));
+                return;
+            }
+        };
+        let Some(script_url) = script_url.or(source_map.file()) else {
+            self.error = Some("Source map does not name its script".into());
+            return;
+        };
+        let mut session = Session::clone(session);
+        let rewritten: usize = (0..session.profiles().len())
+            .filter_map(|index| {
+                session
+                    .rename_spans(index, |profile| source_map.apply(script_url, profile))
+                    .ok()
             })
-            .collect();
-        thread_info.sort_by(|a, b| b.1.cmp(&a.1));
-
-        // Split threads: dense (≥100 spans) go first, sparse go after specialty tracks
-        let dense_threshold = 100;
-        let (dense_threads, sparse_threads): (Vec<_>, Vec<_>) = thread_info
-            .into_iter()
-            .partition(|(_, count, _)| *count >= dense_threshold);
-
-        // Dense threads first
-        for (thread, span_count, max_depth) in &dense_threads {
-            let content_height = if *max_depth == 0 {
-                20.0_f32
+            .sum();
+        if rewritten == 0 {
+            self.error = Some(format!("No frames from {script_url} in this profile"));
+            return;
+        }
+        self.replace_renamed_session(session);
+    }
+
+    /// Resolve JIT frames of session profile `profile_index` with a perf map.
+    fn attach_jit_map(&mut self, profile_index: usize, map: &[u8]) {
+        let Some(session) = &self.session else {
+            self.error = Some("Load a profile before attaching a JIT map".into());
+            return;
+        };
+        let mut symbolicator = flame_cat_core::symbolicate::Symbolicator::new();
+        if let Err(e) = symbolicator.add_perf_map(&String::from_utf8_lossy(map)) {
+            self.error = Some(format!("Invalid JIT map: {e}"));
+            return;
+        }
+        let mut session = Session::clone(session);
+        let resolved = match session
+            .rename_spans(profile_index, |profile| symbolicator.apply_visual(profile))
+        {
+            Ok(resolved) => resolved,
+            Err(e) => {
+                self.error = Some(e.to_string());
+                return;
+            }
+        };
+        if resolved == 0 {
+            self.error = Some("No frames in the profile matched the JIT map".into());
+            return;
+        }
+        self.replace_renamed_session(session);
+    }
+
+    /// Add the notes of an annotation sidecar for session profile
+    /// `profile_index`. Notes naming spans the profile lacks are reported
+    /// and the rest are kept.
+    fn import_annotations(&mut self, profile_index: usize, data: &[u8]) {
+        let Some(session) = &self.session else {
+            self.error = Some("Load a profile before importing annotations".into());
+            return;
+        };
+        let import = match import_sidecar(data, session, profile_index) {
+            Ok(import) => import,
+            Err(e) => {
+                self.error = Some(e.to_string());
+                return;
+            }
+        };
+        if !import.unresolved.is_empty() {
+            self.error = Some(format!(
+                "No spans named {} for {} annotation(s)",
+                import.unresolved.join(", "),
+                import.unresolved.len()
+            ));
+        }
+        if self.saved_state.add_annotations(import.annotations) > 0 {
+            self.show_annotations = true;
+            self.state_gen += 1;
+        }
+    }
+
+    /// Rename, describe or tag session profile `profile_index`.
+    fn set_profile_meta(&mut self, profile_index: usize, edit: &ProfileMetaEdit) {
+        let Some(session) = &self.session else {
+            return;
+        };
+        let mut session = Session::clone(session);
+        let Some(entry) = session.profiles_mut().get_mut(profile_index) else {
+            self.error = Some(format!("No profile at index {profile_index}"));
+            return;
+        };
+        entry.edit_meta(edit);
+        if profile_index == 0 {
+            crate::set_profile_json(serde_json::to_string(&entry.profile).ok());
+        }
+        let session = std::sync::Arc::new(session);
+        crate::set_session(Some(session.clone()));
+        self.session = Some(session);
+        self.invalidate_commands();
+    }
+
+    /// Mark session profile `index` as the baseline, or clear it.
+    fn set_baseline(&mut self, index: Option<usize>) {
+        let Some(session) = &self.session else {
+            return;
+        };
+        let mut session = Session::clone(session);
+        if let Err(e) = session.set_baseline(index) {
+            self.error = Some(e.to_string());
+            return;
+        }
+        let session = std::sync::Arc::new(session);
+        crate::set_session(Some(session.clone()));
+        self.session = Some(session);
+        self.reset_lane_cache();
+    }
+
+    /// Choose how the baseline is scaled before comparison.
+    fn set_diff_normalization(&mut self, normalization: DiffNormalization) {
+        let Some(session) = &self.session else {
+            return;
+        };
+        let mut session = Session::clone(session);
+        session.set_diff_normalization(normalization);
+        let session = std::sync::Arc::new(session);
+        crate::set_session(Some(session.clone()));
+        self.session = Some(session);
+        self.reset_lane_cache();
+    }
+
+    /// Rewrite span names of every session profile with `rules`.
+    fn set_name_rewrites(&mut self, rules: Vec<NameRewrite>) {
+        let Some(session) = &self.session else {
+            self.error = Some("Load a profile before rewriting names".into());
+            return;
+        };
+        let mut session = Session::clone(session);
+        if let Err(e) = session.set_name_rewrites(rules) {
+            self.error = Some(e.to_string());
+            return;
+        }
+        self.replace_renamed_session(session);
+    }
+
+    /// Sort spans into first-party, dependency and runtime code with `rules`.
+    fn set_class_rules(&mut self, rules: ClassRules) {
+        let Some(session) = &self.session else {
+            return;
+        };
+        let mut session = Session::clone(session);
+        session.set_class_rules(rules);
+        let session = std::sync::Arc::new(session);
+        crate::set_session(Some(session.clone()));
+        self.session = Some(session);
+        self.reset_lane_cache();
+    }
+
+    /// Install a session whose span names were rewritten in place.
+    fn replace_renamed_session(&mut self, session: Session) {
+        crate::set_profile_json(
+            session
+                .profiles()
+                .first()
+                .and_then(|e| serde_json::to_string(&e.profile).ok()),
+        );
+        let session = std::sync::Arc::new(session);
+        crate::set_session(Some(session.clone()));
+        self.session = Some(session);
+        // The selection caches the old name.
+        self.set_selected_span(None);
+        self.error = None;
+        self.reset_lane_cache();
+    }
+
+    /// Capture the current view as saveable session state.
+    fn session_state(&self) -> SessionState {
+        SessionState {
+            view: ViewState {
+                view_type: Some(self.view_type.name().to_string()),
+                view_start: self.view_start,
+                view_end: self.view_end,
+                scroll_y: self.scroll_y,
+                search: self.selection.search.clone(),
+                selected_frame: self.selected_span.as_ref().map(|s| s.frame_id),
+                theme: Some(self.theme_mode.name().to_string()),
+                color_mode: Some(self.color_mode.name().to_string()),
+                lanes: self
+                    .lanes
+                    .iter()
+                    .map(|l| LaneView {
+                        name: l.name.clone(),
+                        visible: l.visible,
+                        height: l.height,
+                    })
+                    .collect(),
+            },
+            ..self.saved_state.clone()
+        }
+    }
+
+    /// Save the current viewport as a named region, replacing any region
+    /// of the same name.
+    fn save_region(&mut self, name: &str) {
+        let name = name.trim();
+        if name.is_empty() {
+            return;
+        }
+        self.saved_state.save_region(ViewRegion {
+            name: name.to_string(),
+            view_start: self.view_start,
+            view_end: self.view_end,
+        });
+        self.state_gen += 1;
+    }
+
+    /// Set or clear the budget of the counter called `name` and redraw its
+    /// lane.
+    fn set_counter_budget(&mut self, name: String, limit: Option<f64>) {
+        for (lane, key) in self.lanes.iter().zip(&mut self.lane_keys) {
+            if matches!(lane.kind, LaneKind::Counter(_)) && lane.name == name {
+                *key = None;
+            }
+        }
+        match limit {
+            Some(limit) => self.saved_state.counter_budgets.insert(name, limit),
+            None => self.saved_state.counter_budgets.remove(&name),
+        };
+        self.invalidate_commands();
+    }
+
+    /// Animate to a saved region, if there is one by that name.
+    fn goto_region(&mut self, name: &str) {
+        if let Some(region) = self.saved_state.region(name) {
+            let (start, end) = (region.view_start, region.view_end);
+            self.animate_to(start, end);
+        }
+    }
+
+    /// Restore view state saved in a session file.
+    fn apply_view_state(&mut self, view: &ViewState) {
+        // Theme changes need the egui context, so go through the command queue.
+        match view.theme.as_deref() {
+            Some("dark") => crate::push_command(crate::AppCommand::SetTheme(ThemeMode::Dark)),
+            Some("light") => crate::push_command(crate::AppCommand::SetTheme(ThemeMode::Light)),
+            _ => {}
+        }
+        if let Some(vt) = view
+            .view_type
+            .as_deref()
+            .and_then(crate::ViewType::from_name)
+        {
+            self.view_type = vt;
+        }
+        if let Some(mode) = view.color_mode.as_deref() {
+            self.color_mode = renderer::ColorMode::from_name(mode);
+        }
+        if view.view_start < view.view_end {
+            self.view_start = view.view_start.clamp(0.0, 1.0);
+            self.view_end = view.view_end.clamp(0.0, 1.0);
+            self.push_zoom();
+        }
+        self.scroll_y = view.scroll_y.max(0.0);
+        self.selection.search = view.search.clone();
+        for saved in &view.lanes {
+            if let Some(lane) = self.lanes.iter_mut().find(|l| l.name == saved.name) {
+                lane.visible = saved.visible;
+                lane.height = saved.height.clamp(16.0, 600.0);
+            }
+        }
+        self.set_selected_span(view.selected_frame.and_then(|fid| {
+            let profile = &self.session.as_ref()?.profiles().first()?.profile;
+            let span = profile.span(fid)?;
+            let tid = profile.thread_of_span(fid)?;
+            let lane_index = self
+                .lanes
+                .iter()
+                .position(|l| matches!(l.kind, LaneKind::Thread(t) if t == tid))?;
+            Some(SelectedSpan {
+                name: span.name.clone(),
+                frame_id: fid,
+                lane_index,
+                start_us: span.start,
+                end_us: span.end,
+            })
+        }));
+        self.invalidate_commands();
+    }
+
+    fn setup_lanes(&mut self, profile: &VisualProfile, order: &ThreadOrder) {
+        self.lanes.clear();
+
+        // Threads first, in the session's order, with the first (main)
+        // thread's task strip on top.
+        let threads = order.arrange(&profile.threads);
+        if let Some(main) = threads.first() {
+            let count = top_level_tasks(main).len();
+            if count > 0 {
+                self.lanes.push(LaneState {
+                    kind: LaneKind::Tasks(main.id),
+                    name: format!("Tasks ({count})"),
+                    height: 14.0,
+                    visible: true,
+                    span_count: count,
+                    row_scale: 1.0,
+                    depth_scroll: 0.0,
+                });
+            }
+        }
+        for thread in threads {
+            let span_count = thread.spans.len();
+            let max_depth = thread.spans.iter().map(|s| s.depth).max().unwrap_or(0);
+            // Busy threads get taller lanes; nearly empty ones start hidden.
+            let (flat, cap) = if span_count >= 100 {
+                (20.0_f32, 180.0_f32)
             } else {
-                ((*max_depth + 1) as f32 * 18.0 + 4.0).min(180.0)
+                (16.0, 120.0)
             };
+            let content_height = if max_depth == 0 {
+                flat
+            } else {
+                ((max_depth + 1) as f32 * 18.0 + 4.0).min(cap)
+            };
+            let visible = span_count >= 3 || order.is_pinned(&thread.name);
+            self.lanes.push(LaneState {
+                kind: LaneKind::Utilization(thread.id),
+                name: format!("{} utilization", thread.name),
+                height: 8.0,
+                visible,
+                span_count,
+                row_scale: 1.0,
+                depth_scroll: 0.0,
+            });
             self.lanes.push(LaneState {
                 kind: LaneKind::Thread(thread.id),
                 name: format!("{} ({span_count} spans)", thread.name),
                 height: content_height,
-                visible: true,
-                span_count: *span_count,
+                visible,
+                span_count,
+                row_scale: 1.0,
+                depth_scroll: 0.0,
             });
         }
 
-        // Specialty tracks (between dense and sparse threads)
+        // Specialty tracks
         if !profile.async_spans.is_empty() {
             let count = profile.async_spans.len();
             self.lanes.push(LaneState {
@@ -363,6 +1338,8 @@ impl FlameApp {
                 height: 60.0,
                 visible: true,
                 span_count: count,
+                row_scale: 1.0,
+                depth_scroll: 0.0,
             });
         }
 
@@ -373,6 +1350,8 @@ impl FlameApp {
                 height: 80.0,
                 visible: true,
                 span_count: counter.samples.len(),
+                row_scale: 1.0,
+                depth_scroll: 0.0,
             });
         }
 
@@ -384,6 +1363,8 @@ impl FlameApp {
                 height: 30.0,
                 visible: true,
                 span_count: count,
+                row_scale: 1.0,
+                depth_scroll: 0.0,
             });
         }
 
@@ -397,6 +1378,8 @@ impl FlameApp {
                     .cpu_samples
                     .as_ref()
                     .map_or(0, |s| s.timestamps.len()),
+                row_scale: 1.0,
+                depth_scroll: 0.0,
             });
         }
 
@@ -408,6 +1391,8 @@ impl FlameApp {
                 height: 40.0,
                 visible: true,
                 span_count: count,
+                row_scale: 1.0,
+                depth_scroll: 0.0,
             });
         }
 
@@ -419,32 +1404,225 @@ impl FlameApp {
                 height: 60.0,
                 visible: true,
                 span_count: count,
-            });
-        }
-
-        // Sparse threads after specialty tracks
-        for (thread, span_count, max_depth) in &sparse_threads {
-            let content_height = if *max_depth == 0 {
-                16.0_f32
-            } else {
-                ((*max_depth + 1) as f32 * 18.0 + 4.0).min(120.0)
-            };
-            self.lanes.push(LaneState {
-                kind: LaneKind::Thread(thread.id),
-                name: format!("{} ({span_count} spans)", thread.name),
-                height: content_height,
-                visible: *span_count >= 3,
-                span_count: *span_count,
+                row_scale: 1.0,
+                depth_scroll: 0.0,
             });
         }
     }
 
+    /// Note a view-state change. Only lanes whose [`LaneKey`] changed are
+    /// regenerated on the next `ensure_commands`.
     fn invalidate_commands(&mut self) {
-        self.lane_commands.clear();
-        self.last_cache_key = None;
         self.state_gen += 1;
     }
 
+    /// Drop every cached lane, e.g. when a new profile replaces the lanes.
+    fn reset_lane_cache(&mut self) {
+        self.async_links = None;
+        self.legend = None;
+        self.binaries = None;
+        self.classes = None;
+        self.has_kernel_frames = None;
+        self.inline_depths = None;
+        self.tasks = None;
+        self.gpu_submissions = None;
+        self.baseline = None;
+        self.pinned_rows = None;
+        self.lane_commands.clear();
+        self.lane_keys.clear();
+        self.invalidate_commands();
+    }
+
+    /// Lane the global ranked table is drawn in: the first visible thread
+    /// lane.
+    fn ranked_lane(&self) -> Option<usize> {
+        self.lanes
+            .iter()
+            .position(|l| l.visible && matches!(l.kind, LaneKind::Thread(_)))
+    }
+
+    /// Stack rows a thread lane lays out in the current view, or `None` for
+    /// lanes and views without a stack to scroll.
+    fn lane_depth_rows(&self, lane: &LaneState) -> Option<u32> {
+        let LaneKind::Thread(tid) = lane.kind else {
+            return None;
+        };
+        let sandwich = self.view_type == crate::ViewType::Sandwich && self.selected_span.is_some();
+        if sandwich || self.view_type == crate::ViewType::Ranked {
+            return None;
+        }
+        let profile = &self.session.as_ref()?.profiles().first()?.profile;
+        let thread = profile.threads.iter().find(|t| t.id == tid)?;
+        // Merged views always give inlined functions nodes of their own.
+        let expanded = self.expand_inlined
+            || matches!(
+                self.view_type,
+                crate::ViewType::LeftHeavy | crate::ViewType::Icicle
+            );
+        let depth = self
+            .inline_depths
+            .as_ref()
+            .filter(|_| expanded)
+            .and_then(|depths| depths.get(&tid))
+            .copied()
+            .unwrap_or(thread.max_depth);
+        let max_depth = self.render_limits.max_depth;
+        // Plus the "N more…" row of stacks deeper than the limit.
+        Some(depth.min(max_depth) + 1 + u32::from(depth > max_depth))
+    }
+
+    /// Height of stack `lane` renders, in unscaled pixels: everything from
+    /// the top of the stack to the bottom of its scrolled, zoomed window.
+    fn lane_render_height(&self, lane: &LaneState) -> f32 {
+        match self.lane_depth_rows(lane) {
+            Some(_) => lane.height / lane.row_scale + lane.depth_scroll,
+            None => lane.height,
+        }
+    }
+
+    /// Scroll and zoom lane `idx`'s stack, as [`LaneState::zoom_stack`].
+    fn adjust_lane_depth(&mut self, idx: usize, delta: f32, zoom: f32, anchor: f32) {
+        let Some(rows) = self.lanes.get(idx).and_then(|l| self.lane_depth_rows(l)) else {
+            return;
+        };
+        let stack_height = rows as f32 * FRAME_HEIGHT as f32;
+        self.lanes[idx].zoom_stack(stack_height, delta, zoom, anchor);
+        self.invalidate_commands();
+    }
+
+    /// Header control of a deep lane: fit its whole stack, or back to 1:1.
+    fn toggle_lane_fit(&mut self, idx: usize) {
+        let Some(lane) = self.lanes.get_mut(idx) else {
+            return;
+        };
+        if lane.row_scale != 1.0 || lane.depth_scroll != 0.0 {
+            lane.row_scale = 1.0;
+            lane.depth_scroll = 0.0;
+            self.invalidate_commands();
+        } else {
+            // Zooming all the way out stops at the scale that fits.
+            self.adjust_lane_depth(idx, 0.0, 0.0, 0.0);
+        }
+    }
+
+    /// Compute the key for `lane` under the current view state.
+    fn lane_key(
+        &self,
+        lane_idx: usize,
+        lane: &LaneState,
+        canvas_width: f32,
+        first_visible: Option<usize>,
+    ) -> LaneKey {
+        let window = Some((self.view_start.to_bits(), self.view_end.to_bits()));
+        let height = self.lane_render_height(lane);
+        let (window, focus, height) = match (&lane.kind, self.view_type) {
+            (LaneKind::Thread(_), crate::ViewType::LeftHeavy | crate::ViewType::Icicle) => {
+                (None, None, height)
+            }
+            (LaneKind::Thread(_), crate::ViewType::Sandwich) => match self.selected_span.as_ref() {
+                Some(sel) => (None, Some(sel.frame_id), height),
+                None => (window, None, height),
+            },
+            (LaneKind::Thread(_), crate::ViewType::Ranked) => {
+                (None, Some(u64::from(Some(lane_idx) == first_visible)), 0.0)
+            }
+            _ => (window, None, height),
+        };
+        LaneKey {
+            visible: lane.visible,
+            view_type: self.view_type,
+            width: canvas_width.to_bits(),
+            height: height.to_bits(),
+            row_scale: lane.row_scale.to_bits(),
+            depth_scroll: lane.depth_scroll.to_bits(),
+            window,
+            focus,
+            search: {
+                use std::hash::{Hash, Hasher};
+                let mut hasher = std::collections::hash_map::DefaultHasher::new();
+                self.selection.search.hash(&mut hasher);
+                self.category_filter.hash(&mut hasher);
+                self.binary_filter.hash(&mut hasher);
+                self.class_filter.hash(&mut hasher);
+                hasher.finish()
+            },
+            heat: self
+                .color_mode
+                .is_heat()
+                .then_some((self.color_mode, self.heat_scale)),
+            prune: self.prune_percent.to_bits(),
+            overlay: self.overlay_profile,
+            hide_kernel: self.hide_kernel,
+            expand_inlined: self.expand_inlined,
+        }
+    }
+
+    /// Format a value on the session timeline. Sample-count sessions are not
+    /// in µs, so they are shown in the primary profile's unit instead.
+    fn format_session_value(&self, value: f64) -> String {
+        match self.session.as_ref().and_then(|s| s.profiles().first()) {
+            Some(entry)
+                if entry
+                    .profile
+                    .meta
+                    .value_unit
+                    .to_microseconds_factor()
+                    .is_none() =>
+            {
+                entry
+                    .profile
+                    .meta
+                    .format_value_with(value, &self.duration_format)
+            }
+            _ => self.duration_format.format_detail_us(value),
+        }
+    }
+
+    /// Reconstructed CPU sample stack, leaf first, under a pointer `frac` of
+    /// the way across the canvas.
+    fn cpu_stack_at(&self, frac: f64) -> Vec<SharedStr> {
+        let Some(session) = &self.session else {
+            return Vec::new();
+        };
+        let Some(entry) = session.profiles().first() else {
+            return Vec::new();
+        };
+        let Some(samples) = &entry.profile.cpu_samples else {
+            return Vec::new();
+        };
+        let start = session.start_time();
+        let at = self.view_start + frac * (self.view_end - self.view_start);
+        let ts = entry.to_local_time(start + at * (session.end_time() - start));
+        flame_cat_core::views::cpu_samples::stack_at(samples, ts)
+    }
+
+    /// Format the duration of a span named `name` on the session timeline,
+    /// with its change from the mean duration of that name in the baseline.
+    fn format_span_duration(&self, name: &str, value: f64) -> String {
+        let formatted = self.format_session_value(value);
+        let Some(entry) = self.session.as_ref().and_then(|s| s.profiles().first()) else {
+            return formatted;
+        };
+        // Same unit as `format_session_value`.
+        let unit = match entry.profile.meta.value_unit {
+            unit if unit.to_microseconds_factor().is_some() => ValueUnit::Microseconds,
+            unit => unit,
+        };
+        let Some(mean) = self
+            .baseline
+            .as_ref()
+            .and_then(|b| b.mean_duration(name, unit))
+        else {
+            return formatted;
+        };
+        let delta = value - mean;
+        format!(
+            "{formatted} ({}{} vs baseline)",
+            if delta < 0.0 { '-' } else { '+' },
+            self.format_session_value(delta.abs())
+        )
+    }
+
     /// Push a zoom entry to history (truncate any forward history).
     fn push_zoom(&mut self) {
         let entry = (self.view_start, self.view_end);
@@ -471,19 +1649,6 @@ impl FlameApp {
             return;
         };
 
-        // Check if cached commands are still valid
-        let cache_key = (
-            self.lanes.len(),
-            self.view_start.to_bits(),
-            self.view_end.to_bits(),
-            canvas_width.to_bits(),
-            std::mem::discriminant(&self.view_type),
-        );
-        if self.lane_commands.len() == self.lanes.len() && self.last_cache_key == Some(cache_key) {
-            return; // Already cached with same parameters
-        }
-        self.last_cache_key = Some(cache_key);
-
         let session_start = session.start_time();
         let session_end = session.end_time();
         let duration = session_end - session_start;
@@ -491,14 +1656,69 @@ impl FlameApp {
             return;
         }
 
-        let abs_start = session_start + self.view_start * duration;
-        let abs_end = session_start + self.view_end * duration;
-
-        self.lane_commands.clear();
-        let first_visible = self.lanes.iter().position(|l| l.visible);
+        // Views work in the profile's own clock; map the session window back
+        // through the entry's offset/drift transform.
+        let abs_start = entry.to_local_time(session_start + self.view_start * duration);
+        let abs_end = entry.to_local_time(session_start + self.view_end * duration);
+
+        // Reuse each lane's command buffer across rebuilds instead of
+        // reallocating every pan/zoom tick.
+        self.lane_commands.resize_with(self.lanes.len(), Vec::new);
+        self.lane_keys.resize(self.lanes.len(), None);
+        let first_visible = self.ranked_lane();
+        let async_lane_visible = self
+            .lanes
+            .iter()
+            .any(|l| l.visible && l.kind == LaneKind::AsyncSpans);
+        if async_lane_visible && self.async_links.is_none() {
+            self.async_links = Some(AsyncLinks::build(&entry.profile));
+        }
+        if self.baseline.is_none() {
+            self.baseline = Baseline::for_session(session);
+        }
+        if self.gpu_submissions.is_none() {
+            self.gpu_submissions = Some(link_submissions(&entry.profile));
+        }
+        let task_thread = self.lanes.iter().find_map(|l| match l.kind {
+            LaneKind::Tasks(tid) if l.visible => Some(tid),
+            _ => None,
+        });
+        if let (Some(tid), None) = (task_thread, &self.tasks) {
+            self.tasks = entry
+                .profile
+                .threads
+                .iter()
+                .find(|t| t.id == tid)
+                .map(top_level_tasks);
+        }
+        let search = SearchOptions {
+            query: self.selection.search.clone(),
+            category: self.category_filter.clone(),
+            binary: self.binary_filter.clone(),
+            code_class: self.class_filter,
+            class_rules: session.class_rules().clone(),
+            ..SearchOptions::default()
+        };
+        let mut rebuilt = false;
         for (lane_idx, lane) in self.lanes.iter().enumerate() {
+            let key = self.lane_key(lane_idx, lane, canvas_width, first_visible);
+            if self.lane_keys[lane_idx] == Some(key) {
+                continue;
+            }
+            self.lane_keys[lane_idx] = Some(key);
+            rebuilt = true;
+
+            let render_height = self.lane_render_height(lane);
+            // Scrolled or zoomed stacks are drawn through a transform.
+            let stack_transform = (self.lane_depth_rows(lane).is_some()
+                && (lane.row_scale != 1.0 || lane.depth_scroll != 0.0))
+                .then(|| RenderCommand::PushTransform {
+                    translate: Point::new(0.0, -f64::from(lane.depth_scroll * lane.row_scale)),
+                    scale: Point::new(1.0, f64::from(lane.row_scale)),
+                });
+            let buf = &mut self.lane_commands[lane_idx];
             if !lane.visible {
-                self.lane_commands.push(Vec::new());
+                buf.clear();
                 continue;
             }
             let viewport = Viewport {
@@ -509,113 +1729,153 @@ impl FlameApp {
                 height: if self.view_type == crate::ViewType::Ranked {
                     2000.0
                 } else {
-                    lane.height as f64
+                    render_height as f64
                 },
                 dpr: 1.0,
             };
-            let cmds = match &lane.kind {
-                LaneKind::Thread(tid) => match self.view_type {
-                    crate::ViewType::TimeOrder => {
-                        flame_cat_core::views::time_order::render_time_order(
-                            &entry.profile,
-                            &viewport,
-                            abs_start,
-                            abs_end,
-                            Some(*tid),
-                        )
+            match &lane.kind {
+                LaneKind::Thread(tid) => {
+                    // Ranked table is global — only render for the first visible lane
+                    if self.view_type == crate::ViewType::Ranked && Some(lane_idx) != first_visible
+                    {
+                        buf.clear();
+                        continue;
                     }
-                    crate::ViewType::LeftHeavy => {
-                        flame_cat_core::views::left_heavy::render_left_heavy(
+                    let options = RenderOptions {
+                        view: self.view_type,
+                        viewport,
+                        range: Some(TimeRange {
+                            start: abs_start,
+                            end: abs_end,
+                        }),
+                        threads: vec![*tid],
+                        selected_frame: self.selected_span.as_ref().map(|s| s.frame_id),
+                        color_mode: self.color_mode,
+                        heat_scale: self.heat_scale,
+                        prune_percent: self.prune_percent,
+                        hide_kernel: self.hide_kernel,
+                        lod: LodOptions {
+                            expand_inlined: self.expand_inlined,
+                            ..LodOptions::default()
+                        },
+                        limits: self.render_limits,
+                        label_templates: self.label_templates.clone(),
+                        format: self.duration_format,
+                        ..RenderOptions::default()
+                    };
+                    let overlay = self
+                        .overlay_profile
+                        .filter(|_| self.view_type == crate::ViewType::TimeOrder)
+                        .and_then(|i| session.profiles().get(i));
+                    match overlay {
+                        Some(other) => {
+                            *buf = flame_cat_core::views::overlay::render_overlay(
+                                entry, other, *tid, &options,
+                            );
+                        }
+                        None => flame_cat_core::views::render_compared_into(
+                            buf,
                             &entry.profile,
-                            &viewport,
-                            Some(*tid),
-                        )
+                            &options,
+                            self.baseline.as_ref(),
+                        ),
                     }
-                    crate::ViewType::Sandwich => {
-                        if let Some(ref sel) = self.selected_span {
-                            flame_cat_core::views::sandwich::render_sandwich(
-                                &entry.profile,
-                                sel.frame_id,
-                                &viewport,
-                            )
-                        } else {
-                            // No span selected — show time order as fallback
-                            flame_cat_core::views::time_order::render_time_order(
-                                &entry.profile,
-                                &viewport,
-                                abs_start,
-                                abs_end,
-                                Some(*tid),
-                            )
-                        }
+                    if let Some(transform) = stack_transform {
+                        buf.insert(0, transform);
+                        buf.push(RenderCommand::PopTransform);
                     }
-                    crate::ViewType::Ranked => {
-                        // Ranked table is global — only render for the first visible lane
-                        if Some(lane_idx) == first_visible {
-                            flame_cat_core::views::ranked::render_ranked(
-                                &entry.profile,
+                }
+                LaneKind::Tasks(_) => {
+                    *buf = flame_cat_core::views::task_strip::render_task_strip(
+                        self.tasks.as_deref().unwrap_or_default(),
+                        &viewport,
+                        abs_start,
+                        abs_end,
+                        long_task_threshold(entry.profile.meta.value_unit),
+                    );
+                }
+                LaneKind::Utilization(tid) => {
+                    *buf = match entry.profile.threads.iter().find(|t| t.id == *tid) {
+                        Some(thread) => {
+                            // About one bar per two pixels.
+                            let bucket =
+                                (abs_end - abs_start) / (f64::from(canvas_width) / 2.0).max(1.0);
+                            flame_cat_core::views::utilization::render_utilization_strip(
+                                &busy_fractions(thread, abs_start, abs_end, bucket),
                                 &viewport,
-                                flame_cat_core::views::ranked::RankedSort::SelfTime,
-                                false,
                             )
-                        } else {
-                            Vec::new()
                         }
-                    }
-                    crate::ViewType::Icicle => flame_cat_core::views::left_heavy::render_icicle(
-                        &entry.profile,
-                        &viewport,
-                        Some(*tid),
-                    ),
-                },
+                        None => Vec::new(),
+                    };
+                }
                 LaneKind::Counter(idx) => {
-                    if let Some(counter) = entry.profile.counters.get(*idx) {
+                    *buf = if let Some(counter) = entry.profile.counters.get(*idx) {
                         flame_cat_core::views::counter::render_counter_track(
-                            counter, &viewport, abs_start, abs_end,
+                            counter,
+                            &viewport,
+                            abs_start,
+                            abs_end,
+                            self.saved_state
+                                .counter_budgets
+                                .get(&*counter.name)
+                                .copied(),
                         )
                     } else {
                         Vec::new()
-                    }
+                    };
+                }
+                LaneKind::AsyncSpans => {
+                    *buf = flame_cat_core::views::async_track::render_async_track(
+                        &entry.profile.async_spans,
+                        self.async_links
+                            .as_ref()
+                            .map_or(&[], AsyncLinks::executing_spans),
+                        &viewport,
+                        abs_start,
+                        abs_end,
+                    );
+                }
+                LaneKind::Markers => {
+                    *buf = flame_cat_core::views::markers::render_markers(
+                        &entry.profile.markers,
+                        &viewport,
+                        abs_start,
+                        abs_end,
+                    );
                 }
-                LaneKind::AsyncSpans => flame_cat_core::views::async_track::render_async_track(
-                    &entry.profile.async_spans,
-                    &viewport,
-                    abs_start,
-                    abs_end,
-                ),
-                LaneKind::Markers => flame_cat_core::views::markers::render_markers(
-                    &entry.profile.markers,
-                    &viewport,
-                    abs_start,
-                    abs_end,
-                ),
                 LaneKind::CpuSamples => {
-                    if let Some(ref samples) = entry.profile.cpu_samples {
+                    *buf = if let Some(ref samples) = entry.profile.cpu_samples {
                         flame_cat_core::views::cpu_samples::render_cpu_samples(
                             samples, &viewport, abs_start, abs_end,
                         )
                     } else {
                         Vec::new()
-                    }
+                    };
                 }
-                LaneKind::FrameTrack => flame_cat_core::views::frame_track::render_frame_track(
-                    &entry.profile.frames,
-                    &viewport,
-                    abs_start,
-                    abs_end,
-                ),
-                LaneKind::ObjectTrack => flame_cat_core::views::object_track::render_object_track(
-                    &entry.profile.object_events,
-                    &viewport,
-                    abs_start,
-                    abs_end,
-                ),
-            };
-            self.lane_commands.push(cmds);
+                LaneKind::FrameTrack => {
+                    *buf = flame_cat_core::views::frame_track::render_frame_track(
+                        &entry.profile.frames,
+                        &viewport,
+                        abs_start,
+                        abs_end,
+                    );
+                }
+                LaneKind::ObjectTrack => {
+                    *buf = flame_cat_core::views::object_track::render_object_track(
+                        &entry.profile.object_events,
+                        &viewport,
+                        abs_start,
+                        abs_end,
+                    );
+                }
+            }
+            flame_cat_core::views::apply_search(buf, &entry.profile, &search);
         }
 
         // Update SVG export cache (only when commands were rebuilt)
-        crate::set_lane_commands(self.lane_commands.clone());
+        if rebuilt {
+            crate::set_lane_commands(&self.lane_commands, canvas_width);
+        }
     }
 
     #[cfg(target_arch = "wasm32")]
@@ -658,85 +1918,96 @@ impl FlameApp {
         Ok(uint8.to_vec())
     }
 
-    /// Draw the time axis ruler showing tick marks and time labels.
+    /// Draw the time axis ruler from the core `time_axis` view, labelled
+    /// relative to the session start.
     fn draw_time_axis(&self, ui: &egui::Ui, rect: egui::Rect) {
         let Some(session) = &self.session else {
             return;
         };
-        let session_start = session.start_time();
-        let session_end = session.end_time();
-        let duration = session_end - session_start;
+        let duration = session.end_time() - session.start_time();
         if duration <= 0.0 {
             return;
         }
-
-        let painter = ui.painter_at(rect);
-
-        // Background
-        let bg = crate::theme::resolve(
-            flame_cat_protocol::ThemeToken::LaneHeaderBackground,
+        let viewport = flame_cat_protocol::Viewport {
+            x: 0.0,
+            y: 0.0,
+            width: f64::from(rect.width()),
+            height: f64::from(rect.height()),
+            dpr: f64::from(ui.ctx().pixels_per_point()),
+        };
+        let commands = render_time_axis(
+            &viewport,
+            self.view_start * duration,
+            self.view_end * duration,
+            0.0,
+            &self.duration_format,
+        );
+        let mut painter = ui.painter_at(rect);
+        renderer::render_commands(
+            &mut painter,
+            &commands,
+            rect.min,
             self.theme_mode,
+            self.color_mode,
         );
-        painter.rect_filled(rect, egui::CornerRadius::ZERO, bg);
+    }
 
-        // Visible time window in µs
-        let vis_start_us = session_start + self.view_start * duration;
-        let vis_end_us = session_start + self.view_end * duration;
-        let vis_duration = vis_end_us - vis_start_us;
-        if vis_duration <= 0.0 {
+    /// Mark the session's notes on the time axis: a pin at each note's start,
+    /// with its range shaded, and the text on hover.
+    fn draw_annotation_pins(&self, ui: &egui::Ui, resp: egui::Response) {
+        let rect = resp.rect;
+        let Some(session) = &self.session else {
+            return;
+        };
+        let duration = session.duration();
+        let window = (self.view_end - self.view_start) * duration;
+        if self.saved_state.annotations.is_empty() || window <= 0.0 {
             return;
         }
-
-        // Compute nice tick interval
-        let target_tick_count = (rect.width() / 100.0).max(2.0) as usize;
-        let tick_interval = nice_tick_interval(vis_duration, target_tick_count);
-
-        let text_color = crate::theme::resolve(
-            flame_cat_protocol::ThemeToken::TextSecondary,
-            self.theme_mode,
-        );
-        let tick_color =
-            crate::theme::resolve(flame_cat_protocol::ThemeToken::LaneBorder, self.theme_mode);
-
-        // First tick aligned to interval (relative to session start)
-        let rel_start = vis_start_us - session_start;
-        let first_tick = (rel_start / tick_interval).ceil() * tick_interval;
-
-        let mut tick = first_tick;
-        while tick <= vis_end_us - session_start {
-            let frac = (tick - rel_start) / vis_duration;
-            let x = rect.left() + frac as f32 * rect.width();
-
-            // Tick mark
+        let origin = session.start_time() + self.view_start * duration;
+        let to_x = |us: f64| rect.left() + ((us - origin) / window) as f32 * rect.width();
+        let color =
+            crate::theme::resolve(flame_cat_protocol::ThemeToken::MarkerLine, self.theme_mode);
+        let painter = ui.painter_at(rect);
+        let pointer = resp.hover_pos();
+        let mut hovered = Vec::new();
+        for note in &self.saved_state.annotations {
+            let x = to_x(note.start_us);
+            let end_x = note.end_us.map_or(x, to_x);
+            if end_x < rect.left() || x > rect.right() {
+                continue;
+            }
+            if end_x > x {
+                painter.rect_filled(
+                    egui::Rect::from_x_y_ranges(x..=end_x, rect.y_range()),
+                    egui::CornerRadius::ZERO,
+                    color.gamma_multiply(0.15),
+                );
+            }
             painter.line_segment(
-                [
-                    egui::pos2(x, rect.bottom() - 6.0),
-                    egui::pos2(x, rect.bottom()),
-                ],
-                egui::Stroke::new(1.0, tick_color),
-            );
-
-            // Time label
-            let label = format_tick_label(tick, tick_interval);
-            painter.text(
-                egui::pos2(x, rect.center().y),
-                egui::Align2::CENTER_CENTER,
-                &label,
-                egui::FontId::proportional(FONT_CAPTION),
-                text_color,
+                [egui::pos2(x, rect.top()), egui::pos2(x, rect.bottom())],
+                egui::Stroke::new(1.0, color),
             );
-
-            tick += tick_interval;
+            painter.add(egui::Shape::convex_polygon(
+                vec![
+                    egui::pos2(x, rect.top()),
+                    egui::pos2(x + 6.0, rect.top() + 3.0),
+                    egui::pos2(x, rect.top() + 6.0),
+                ],
+                color,
+                egui::Stroke::NONE,
+            ));
+            if pointer.is_some_and(|p| p.x >= x - 3.0 && p.x <= end_x.max(x + 6.0)) {
+                hovered.push(note.text.as_str());
+            }
+        }
+        if !hovered.is_empty() {
+            resp.on_hover_ui_at_pointer(|ui| {
+                for text in hovered {
+                    ui.label(text);
+                }
+            });
         }
-
-        // Bottom border
-        painter.line_segment(
-            [
-                egui::pos2(rect.left(), rect.bottom()),
-                egui::pos2(rect.right(), rect.bottom()),
-            ],
-            egui::Stroke::new(1.0, tick_color),
-        );
     }
 
     /// Draw an interactive minimap with density heatmap and draggable viewport.
@@ -772,32 +2043,12 @@ impl FlameApp {
             egui::Stroke::new(1.0, border_color),
         );
 
-        // Build or reuse cached density per column
+        // Resample the prebuilt density pyramid to the current width
         let cols = (rect.width() as usize).max(1);
-        let density = match &self.minimap_density {
-            Some(cached) if cached.len() == cols => cached,
-            _ => {
-                let start = profile.meta.start_time;
-                let col_dur = duration / cols as f64;
-                let mut d = vec![0u32; cols];
-                for span in profile.all_spans() {
-                    let rel_start = (span.start - start) / col_dur;
-                    let rel_end = (span.end - start) / col_dur;
-                    if rel_end < 0.0 || rel_start >= cols as f64 {
-                        continue;
-                    }
-                    let c0 = (rel_start.max(0.0) as usize).min(cols);
-                    let c1 = (rel_end.ceil() as usize).min(cols);
-                    for item in d.iter_mut().take(c1).skip(c0) {
-                        *item += 1;
-                    }
-                }
-                self.minimap_density = Some(d);
-                // We just assigned Some above, so unwrap is safe
-                #[allow(clippy::expect_used)]
-                self.minimap_density.as_ref().expect("just assigned")
-            }
-        };
+        let density = self
+            .minimap_density
+            .as_ref()
+            .map_or_else(|| vec![0; cols], |pyramid| pyramid.sample(cols));
         let max_d = *density.iter().max().unwrap_or(&1).max(&1);
 
         // Draw density bars (batch adjacent columns into rects for performance)
@@ -905,31 +2156,51 @@ impl FlameApp {
             egui::Stroke::new(1.0, border_color),
         );
 
-        // Interactive: drag to pan/resize viewport
-        let handle_w = 6.0_f32;
-
-        if resp.dragged() {
-            if let Some(pos) = resp.interact_pointer_pos() {
+        // Interactive: press-drag-release pans or resizes the viewport, or
+        // draws a new range when it starts outside it.
+        let width = f64::from(rect.width());
+        let pointer_x = |pos: egui::Pos2| f64::from(pos.x - rect.left());
+        if resp.drag_started() {
+            if let Some(origin) = ui.input(|i| i.pointer.press_origin()) {
                 self.anim_target = None;
-                let frac = ((pos.x - rect.left()) / rect.width()) as f64;
-                let frac = frac.clamp(0.0, 1.0);
-                let delta_x = resp.drag_delta().x;
-
-                let drag_start = pos.x - delta_x;
-                let on_left_handle = (drag_start - vp_left).abs() < handle_w * 2.0;
-                let on_right_handle = (drag_start - vp_right).abs() < handle_w * 2.0;
-
-                if on_left_handle {
-                    self.view_start = frac.min(self.view_end - 0.001);
-                } else if on_right_handle {
-                    self.view_end = frac.max(self.view_start + 0.001);
+                self.minimap_drag = Some(MinimapDrag::begin(
+                    pointer_x(origin),
+                    width,
+                    self.view_start,
+                    self.view_end,
+                ));
+            }
+        }
+        if let (Some(drag), Some(pos)) = (self.minimap_drag, ui.input(|i| i.pointer.latest_pos())) {
+            let (start, end) = drag.update(pointer_x(pos), width, self.view_start, self.view_end);
+            if let MinimapDrag::Create { .. } = drag {
+                if resp.drag_stopped() {
+                    self.minimap_drag = None;
+                    if (end - start) * width >= MIN_MINIMAP_RANGE_PX {
+                        self.animate_to(start, end);
+                    }
                 } else {
-                    let dx_frac = (delta_x as f64) / rect.width() as f64;
-                    let span = self.view_end - self.view_start;
-                    self.view_start = (self.view_start + dx_frac).clamp(0.0, 1.0 - span);
-                    self.view_end = self.view_start + span;
+                    let draft = egui::Rect::from_min_max(
+                        egui::pos2(rect.left() + (start * width) as f32, rect.top()),
+                        egui::pos2(rect.left() + (end * width) as f32, rect.bottom()),
+                    );
+                    painter.rect_filled(draft, egui::CornerRadius::ZERO, vp_color);
+                    painter.rect_stroke(
+                        draft,
+                        egui::CornerRadius::ZERO,
+                        egui::Stroke::new(1.0, handle_color),
+                        egui::StrokeKind::Inside,
+                    );
+                }
+            } else {
+                if resp.dragged() {
+                    self.view_start = start;
+                    self.view_end = end;
+                    self.invalidate_commands();
+                }
+                if resp.drag_stopped() {
+                    self.minimap_drag = None;
                 }
-                self.invalidate_commands();
             }
         }
 
@@ -948,21 +2219,25 @@ impl FlameApp {
         }
 
         // Cursor hint
-        if resp.hovered() {
+        if resp.hovered() && self.minimap_drag.is_none() {
             if let Some(pos) = ui.input(|i| i.pointer.hover_pos()) {
-                let on_left = (pos.x - vp_left).abs() < handle_w * 2.0;
-                let on_right = (pos.x - vp_right).abs() < handle_w * 2.0;
-                if on_left || on_right {
-                    ui.ctx().set_cursor_icon(egui::CursorIcon::ResizeHorizontal);
-                } else if pos.x > vp_left && pos.x < vp_right {
-                    ui.ctx().set_cursor_icon(egui::CursorIcon::Grab);
-                }
+                let icon =
+                    match minimap_hit_test(pointer_x(pos), width, self.view_start, self.view_end) {
+                        MinimapHit::LeftHandle | MinimapHit::RightHandle => {
+                            egui::CursorIcon::ResizeHorizontal
+                        }
+                        MinimapHit::Viewport => egui::CursorIcon::Grab,
+                        MinimapHit::Outside => egui::CursorIcon::Crosshair,
+                    };
+                ui.ctx().set_cursor_icon(icon);
             }
         }
     }
 
-    /// Start an animated transition to the given viewport.
+    /// Start an animated transition to the given viewport. Jumping
+    /// elsewhere stops replay.
     fn animate_to(&mut self, start: f64, end: f64) {
+        self.playing = false;
         let mut lo = start.clamp(0.0, 1.0);
         let mut hi = end.clamp(0.0, 1.0);
         if hi < lo {
@@ -1015,6 +2290,56 @@ impl FlameApp {
         true
     }
 
+    /// Whether the primary profile is on a time axis replay can follow.
+    fn can_play(&self) -> bool {
+        self.session
+            .as_ref()
+            .and_then(|s| s.profiles().first())
+            .is_some_and(|e| e.profile.meta.value_unit.to_microseconds_factor().is_some())
+    }
+
+    /// Replay at `rate` times real time, rewinding first when the view is
+    /// already at the end; `None` pauses.
+    fn set_playback(&mut self, rate: Option<f64>) {
+        match rate.filter(|r| r.is_finite() && *r > 0.0) {
+            Some(rate) => {
+                self.playback_rate = rate;
+                if self.can_play() {
+                    if self.view_end >= 1.0 {
+                        self.view_end -= self.view_start;
+                        self.view_start = 0.0;
+                    }
+                    self.anim_target = None;
+                    self.playing = true;
+                }
+            }
+            None => self.playing = false,
+        }
+        self.invalidate_commands();
+    }
+
+    /// Slide the viewport along by one frame's worth of trace time,
+    /// stopping at the end of the trace.
+    fn tick_playback(&mut self, ctx: &egui::Context) {
+        if !self.playing {
+            return;
+        }
+        let duration = self.session.as_ref().map_or(0.0, |s| s.duration());
+        if duration <= 0.0 || !self.can_play() {
+            self.playing = false;
+            return;
+        }
+        let dt = f64::from(ctx.input(|i| i.unstable_dt)).min(MAX_PLAYBACK_STEP_S);
+        let span = self.view_end - self.view_start;
+        self.view_end = (self.view_end + dt * 1e6 * self.playback_rate / duration).min(1.0);
+        self.view_start = self.view_end - span;
+        if self.view_end >= 1.0 {
+            self.playing = false;
+        }
+        self.invalidate_commands();
+        ctx.request_repaint();
+    }
+
     fn render_toolbar(&mut self, ctx: &egui::Context) {
         // Top toolbar
         egui::TopBottomPanel::top("toolbar").show(ctx, |ui| {
@@ -1030,15 +2355,55 @@ impl FlameApp {
                 );
                 ui.separator();
 
+                if let Some(entry) = self.session.as_ref().and_then(|s| s.profiles().first()) {
+                    let name = entry.profile.meta.name.as_deref().unwrap_or(&entry.label);
+                    if ui
+                        .button(format!("✏ {name}"))
+                        .on_hover_text("Rename, describe or tag profiles")
+                        .clicked()
+                    {
+                        self.meta_editor = self
+                            .session
+                            .as_ref()
+                            .and_then(|s| MetaEditor::for_profile(s, 0));
+                    }
+                    ui.separator();
+                }
+
                 if ui.button("Open…").clicked() {
                     #[cfg(not(target_arch = "wasm32"))]
                     {
                         if let Some(path) = rfd::FileDialog::new()
-                            .add_filter("Profile", &["json", "cpuprofile", "speedscope"])
+                            .add_filter(
+                                "Profile",
+                                &[
+                                    "json",
+                                    "cpuprofile",
+                                    "speedscope",
+                                    "perfetto-trace",
+                                    "pftrace",
+                                    "data",
+                                    "jfr",
+                                    "nettrace",
+                                    "csv",
+                                    "xml",
+                                    "html",
+                                    "txt",
+                                    "trace",
+                                    "pprof",
+                                    "pb",
+                                    "gz",
+                                    "flamecache",
+                                    "flamecat",
+                                ],
+                            )
+                            // Valgrind's callgrind.out.<pid> and massif.out.<pid> have no
+                            // usable extension.
+                            .add_filter("All files", &["*"])
                             .pick_file()
                         {
-                            match std::fs::read(&path) {
-                                Ok(data) => self.load_profile(&data),
+                            match flame_cat_core::input::MappedFile::open(&path) {
+                                Ok(data) => self.load_profile(ctx, data, FormatHint::default()),
                                 Err(e) => {
                                     self.error = Some(format!("Failed to read file: {e}"));
                                 }
@@ -1060,6 +2425,86 @@ impl FlameApp {
                     }
                 }
 
+                #[cfg(not(target_arch = "wasm32"))]
+                if let Some(session) = self.session.clone() {
+                    if ui
+                        .button("Save session…")
+                        .on_hover_text("Save profiles and view state to a .flamecat file")
+                        .clicked()
+                    {
+                        if let Some(path) = rfd::FileDialog::new()
+                            .add_filter("flame.cat session", &["flamecat"])
+                            .set_file_name("session.flamecat")
+                            .save_file()
+                        {
+                            let written =
+                                session_file::save_session(&session, &self.session_state())
+                                    .map_err(|e| e.to_string())
+                                    .and_then(|json| {
+                                        std::fs::write(&path, json).map_err(|e| e.to_string())
+                                    });
+                            if let Err(e) = written {
+                                self.error = Some(format!("Failed to save session: {e}"));
+                            }
+                        }
+                    }
+                }
+
+                #[cfg(not(target_arch = "wasm32"))]
+                if let Some(session) = self.session.as_ref().filter(|s| s.profiles().len() > 1) {
+                    if ui
+                        .button("Export comparison…")
+                        .on_hover_text(
+                            "Write how the primary profile differs from the baseline as HTML or Markdown",
+                        )
+                        .clicked()
+                    {
+                        if let Some(path) = rfd::FileDialog::new()
+                            .add_filter("HTML", &["html"])
+                            .add_filter("Markdown", &["md"])
+                            .set_file_name("comparison.html")
+                            .save_file()
+                        {
+                            use flame_cat_core::comparison::{Comparison, ReportFormat};
+                            let written = Comparison::new(session)
+                                .map_err(|e| e.to_string())
+                                .and_then(|comparison| {
+                                    let report = comparison.render(
+                                        ReportFormat::for_path(&path),
+                                        &self.duration_format,
+                                    );
+                                    std::fs::write(&path, report).map_err(|e| e.to_string())
+                                });
+                            if let Err(e) = written {
+                                self.error = Some(format!("Failed to export comparison: {e}"));
+                            }
+                        }
+                    }
+                }
+
+                // Import once: save the parsed profile as a columnar cache that
+                // reopens without re-parsing.
+                #[cfg(not(target_arch = "wasm32"))]
+                if let Some(entry) = self.session.as_ref().and_then(|s| s.profiles().first()) {
+                    if ui
+                        .button("Save cache…")
+                        .on_hover_text("Save a .flamecache file that reopens instantly")
+                        .clicked()
+                    {
+                        if let Some(path) = rfd::FileDialog::new()
+                            .add_filter("flame.cat cache", &["flamecache"])
+                            .set_file_name("profile.flamecache")
+                            .save_file()
+                        {
+                            if let Err(e) =
+                                flame_cat_core::cache::write_cache(&path, &entry.profile)
+                            {
+                                self.error = Some(format!("Failed to write cache: {e}"));
+                            }
+                        }
+                    }
+                }
+
                 ui.separator();
 
                 let theme_label = match self.theme_mode {
@@ -1084,17 +2529,57 @@ impl FlameApp {
                 // Color mode toggle
                 let color_label = match self.color_mode {
                     renderer::ColorMode::ByName => "By Name",
-                    renderer::ColorMode::Theme => "By Depth",
+                    renderer::ColorMode::ByDepth => "By Depth",
+                    renderer::ColorMode::ByDuration => "By Duration",
+                    renderer::ColorMode::BySelfTime => "By Self Time",
                 };
-                if ui
-                    .button(color_label)
-                    .on_hover_text("Toggle span coloring mode")
-                    .clicked()
+                egui::ComboBox::from_id_salt("color_mode")
+                    .selected_text(color_label)
+                    .show_ui(ui, |ui| {
+                        for (mode, label) in [
+                            (renderer::ColorMode::ByName, "By Name"),
+                            (renderer::ColorMode::ByDepth, "By Depth"),
+                            (renderer::ColorMode::ByDuration, "By Duration"),
+                            (renderer::ColorMode::BySelfTime, "By Self Time"),
+                        ] {
+                            ui.selectable_value(&mut self.color_mode, mode, label);
+                        }
+                        if self.color_mode.is_heat() {
+                            ui.separator();
+                            ui.selectable_value(
+                                &mut self.heat_scale,
+                                HeatScale::Siblings,
+                                "Relative to siblings",
+                            );
+                            ui.selectable_value(
+                                &mut self.heat_scale,
+                                HeatScale::Absolute,
+                                "Absolute (1µs–1s)",
+                            );
+                        }
+                    })
+                    .response
+                    .on_hover_text("How spans are colored");
+                if self.session.is_some()
+                    && ui
+                        .selectable_label(self.show_legend, "Legend")
+                        .on_hover_text("What the span colors mean")
+                        .clicked()
                 {
-                    self.color_mode = match self.color_mode {
-                        renderer::ColorMode::ByName => renderer::ColorMode::Theme,
-                        renderer::ColorMode::Theme => renderer::ColorMode::ByName,
-                    };
+                    self.show_legend = !self.show_legend;
+                }
+                if self.session.is_some()
+                    && ui
+                        .selectable_label(
+                            self.show_annotations,
+                            format!("Notes ({})", self.saved_state.annotations.len()),
+                        )
+                        .on_hover_text(
+                            "Notes on the timeline; drop a .annotations.json file to add more",
+                        )
+                        .clicked()
+                {
+                    self.show_annotations = !self.show_annotations;
                 }
 
                 ui.separator();
@@ -1114,6 +2599,114 @@ impl FlameApp {
                             self.invalidate_commands();
                         }
                     }
+                    if let Some(session) = self
+                        .session
+                        .as_ref()
+                        .filter(|s| s.profiles().len() > 1)
+                        .filter(|_| self.view_type == crate::ViewType::TimeOrder)
+                    {
+                        let selected = self
+                            .overlay_profile
+                            .and_then(|i| session.profiles().get(i))
+                            .map_or("None", |e| e.label.as_str());
+                        egui::ComboBox::from_id_salt("overlay_profile")
+                            .selected_text(format!("Overlay: {selected}"))
+                            .show_ui(ui, |ui| {
+                                ui.selectable_value(&mut self.overlay_profile, None, "None");
+                                for (i, entry) in session.profiles().iter().enumerate().skip(1) {
+                                    ui.selectable_value(
+                                        &mut self.overlay_profile,
+                                        Some(i),
+                                        &entry.label,
+                                    );
+                                }
+                            })
+                            .response
+                            .on_hover_text(
+                                "Draw another profile's spans over the same threads, aligned in time",
+                            );
+                    }
+                    if self.view_type == crate::ViewType::TimeOrder && self.can_play() {
+                        let (icon, hint) = if self.playing {
+                            ("⏸", "Pause replay")
+                        } else {
+                            ("▶", "Replay: slide the view along the trace in real time")
+                        };
+                        if ui.button(icon).on_hover_text(hint).clicked() {
+                            let rate = (!self.playing).then_some(self.playback_rate);
+                            self.set_playback(rate);
+                        }
+                        for rate in PLAYBACK_RATES {
+                            if ui
+                                .selectable_label(self.playback_rate == rate, format!("{rate}×"))
+                                .on_hover_text("Replay speed")
+                                .clicked()
+                            {
+                                self.playback_rate = rate;
+                                self.invalidate_commands();
+                            }
+                        }
+                    }
+                    if matches!(
+                        self.view_type,
+                        crate::ViewType::LeftHeavy
+                            | crate::ViewType::Icicle
+                            | crate::ViewType::Sandwich
+                    ) {
+                        ui.add(
+                            egui::DragValue::new(&mut self.prune_percent)
+                                .range(0.0..=20.0)
+                                .speed(0.1)
+                                .prefix("Prune < ")
+                                .suffix("%"),
+                        )
+                        .on_hover_text(
+                            "Collapse subtrees under this share of the total into one \"other\" block",
+                        );
+                    }
+                    let has_kernel = *self.has_kernel_frames.get_or_insert_with(|| {
+                        self.session
+                            .as_ref()
+                            .and_then(|s| s.profiles().first())
+                            .is_some_and(|e| e.profile.all_spans().any(Span::is_kernel))
+                    });
+                    if has_kernel
+                        && ui
+                            .selectable_label(self.hide_kernel, "Hide kernel")
+                            .on_hover_text("Leave out frames that ran in the operating system kernel")
+                            .clicked()
+                    {
+                        self.hide_kernel = !self.hide_kernel;
+                        self.invalidate_commands();
+                    }
+                    let has_inlined = !self
+                        .inline_depths
+                        .get_or_insert_with(|| {
+                            self.session
+                                .as_ref()
+                                .and_then(|s| s.profiles().first())
+                                .map(|e| {
+                                    e.profile
+                                        .threads
+                                        .iter()
+                                        .filter(|t| t.spans.iter().any(|s| !s.inlined.is_empty()))
+                                        .map(|t| (t.id, expanded_max_depth(t)))
+                                        .collect()
+                                })
+                                .unwrap_or_default()
+                        })
+                        .is_empty();
+                    if has_inlined
+                        && ui
+                            .selectable_label(self.expand_inlined, "Expand inlined")
+                            .on_hover_text(
+                                "Draw functions the compiler inlined on rows of their own instead of folding them into their caller",
+                            )
+                            .clicked()
+                    {
+                        self.expand_inlined = !self.expand_inlined;
+                        self.invalidate_commands();
+                    }
 
                     ui.separator();
 
@@ -1142,6 +2735,47 @@ impl FlameApp {
                         self.view_end = e;
                         self.invalidate_commands();
                     }
+
+                    // Named regions: jump to one, or save the current view.
+                    let mut goto = None;
+                    let mut save = false;
+                    egui::ComboBox::from_id_salt("regions")
+                        .selected_text("Regions")
+                        .show_ui(ui, |ui| {
+                            for region in &self.saved_state.regions {
+                                if ui.selectable_label(false, &region.name).clicked() {
+                                    goto = Some(region.name.clone());
+                                }
+                            }
+                            if !self.saved_state.regions.is_empty() {
+                                ui.separator();
+                            }
+                            ui.horizontal(|ui| {
+                                let name = ui.add(
+                                    egui::TextEdit::singleline(&mut self.region_name)
+                                        .hint_text("Region name")
+                                        .desired_width(120.0),
+                                );
+                                let enter = name.lost_focus()
+                                    && ui.input(|i| i.key_pressed(egui::Key::Enter));
+                                save = ui
+                                    .add_enabled(
+                                        !self.region_name.trim().is_empty(),
+                                        egui::Button::new("Save view"),
+                                    )
+                                    .clicked()
+                                    || enter;
+                            });
+                        })
+                        .response
+                        .on_hover_text("Saved zoom regions");
+                    if let Some(name) = goto {
+                        self.goto_region(&name);
+                    }
+                    if save {
+                        let name = std::mem::take(&mut self.region_name);
+                        self.save_region(&name);
+                    }
                 }
 
                 ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
@@ -1168,7 +2802,7 @@ impl FlameApp {
 
                     // Search box
                     let search_response = ui.add(
-                        egui::TextEdit::singleline(&mut self.search_query)
+                        egui::TextEdit::singleline(&mut self.selection.search)
                             .hint_text("Search spans...")
                             .desired_width(150.0),
                     );
@@ -1176,7 +2810,7 @@ impl FlameApp {
                         self.invalidate_commands();
                     }
                     // Show match count when search is active
-                    if !self.search_query.is_empty() {
+                    if !self.selection.search.is_empty() {
                         let count = self.count_search_matches();
                         let label = if count == 1 {
                             "1 match".to_string()
@@ -1196,6 +2830,7 @@ impl FlameApp {
                                 )
                             },
                         ));
+                        self.render_session_matches(ui);
                     }
                 });
             });
@@ -1239,9 +2874,15 @@ impl FlameApp {
                         .sum();
                     let thread_count: usize =
                         profiles.iter().map(|p| p.profile.threads.len()).sum();
-                    ui.label(format!("Duration: {}", format_duration(duration_us)));
+                    ui.label(format!(
+                        "Duration: {}",
+                        self.format_session_value(duration_us)
+                    ));
                     ui.separator();
-                    ui.label(format!("Viewing: {}", format_duration(vis_duration_us)));
+                    ui.label(format!(
+                        "Viewing: {}",
+                        self.format_session_value(vis_duration_us)
+                    ));
                     ui.separator();
                     ui.label(format!(
                         "Zoom: {:.0}%",
@@ -1257,6 +2898,107 @@ impl FlameApp {
                         ))
                         .weak(),
                     );
+                    if let Some(stream) = &self.live {
+                        ui.separator();
+                        ui.label(
+                            egui::RichText::new(format!(
+                                "● Live · {} events",
+                                stream.event_count()
+                            ))
+                            .strong(),
+                        );
+                    }
+                    let truncated: usize = profiles
+                        .iter()
+                        .map(|p| p.profile.meta.truncated_spans)
+                        .sum();
+                    if truncated > 0 {
+                        ui.separator();
+                        ui.label(
+                            egui::RichText::new(format!("⚠ {truncated} truncated")).color(
+                                crate::theme::resolve(
+                                    flame_cat_protocol::ThemeToken::FrameWarning,
+                                    self.theme_mode,
+                                ),
+                            ),
+                        )
+                        .on_hover_text(
+                            "Spans with no end event (trace cut off mid-recording). \
+                             They are closed at the trace end and drawn with a ragged edge.",
+                        );
+                    }
+                    let mut repairs = flame_cat_protocol::SpanRepairs::default();
+                    for p in profiles {
+                        repairs += p.profile.meta.repairs;
+                    }
+                    if repairs.total() > 0 {
+                        ui.separator();
+                        ui.label(
+                            egui::RichText::new(format!("⚠ {} repaired", repairs.total())).color(
+                                crate::theme::resolve(
+                                    flame_cat_protocol::ThemeToken::FrameWarning,
+                                    self.theme_mode,
+                                ),
+                            ),
+                        )
+                        .on_hover_text(format!(
+                            "Malformed spans fixed before layout:\n\
+                             {} ended before they started\n\
+                             {} extended past their parent\n\
+                             {} overlapped a sibling\n\
+                             {} had an inconsistent depth or parent",
+                            repairs.negative_durations,
+                            repairs.clamped_to_parent,
+                            repairs.overlapping_siblings,
+                            repairs.redepthed,
+                        ));
+                    }
+                    let mut limits_hit = flame_cat_protocol::LimitsHit::default();
+                    for p in profiles {
+                        limits_hit += p.profile.meta.limits_hit;
+                    }
+                    for (partial, p) in profiles
+                        .iter()
+                        .filter_map(|p| Some((p.profile.meta.partial.as_ref()?, p)))
+                    {
+                        ui.separator();
+                        ui.label(
+                            egui::RichText::new("⚠ cut short").color(crate::theme::resolve(
+                                flame_cat_protocol::ThemeToken::FrameWarning,
+                                self.theme_mode,
+                            )),
+                        )
+                        .on_hover_text(format!(
+                            "The input is truncated or corrupt; {} events were recovered, \
+                             up to {}.\n{}",
+                            partial.events,
+                            p.profile.meta.format_value_with(
+                                partial.cut_off - p.profile.meta.start_time,
+                                &self.duration_format,
+                            ),
+                            partial.error,
+                        ));
+                    }
+                    if limits_hit.any() {
+                        ui.separator();
+                        ui.label(
+                            egui::RichText::new("⚠ partial").color(crate::theme::resolve(
+                                flame_cat_protocol::ThemeToken::FrameWarning,
+                                self.theme_mode,
+                            )),
+                        )
+                        .on_hover_text(format!(
+                            "The input broke the parse limits and was loaded in part:\n\
+                             {} bytes past the memory budget\n\
+                             {} spans past the event limit\n\
+                             {} spans past the depth limit\n\
+                             {} names shortened",
+                            limits_hit.bytes_skipped,
+                            limits_hit.events_dropped,
+                            limits_hit.too_deep,
+                            limits_hit.strings_cut,
+                        ));
+                    }
                 } else {
                     ui.label("No profile loaded — click Open or drag & drop a file");
                 }
@@ -1264,7 +3006,27 @@ impl FlameApp {
         });
     }
 
+    /// Recompute [`Self::selected_info`] when the selection or the session
+    /// has changed since it was last computed.
+    fn refresh_selected_info(&mut self) {
+        let (Some(selected), Some(session)) = (&self.selected_span, &self.session) else {
+            self.selected_info = None;
+            return;
+        };
+        let fresh = self.selected_info.as_ref().is_some_and(|(from, info)| {
+            info.id == selected.frame_id && from.as_ptr() == std::sync::Arc::as_ptr(session)
+        });
+        if !fresh {
+            self.selected_info = session
+                .profiles()
+                .first()
+                .and_then(|entry| span_info(&entry.profile, selected.frame_id))
+                .map(|info| (std::sync::Arc::downgrade(session), info));
+        }
+    }
+
     fn render_detail_panel(&mut self, ctx: &egui::Context) {
+        self.refresh_selected_info();
         // Detail panel: show selected span info
         if let Some(selected) = &self.selected_span {
             let selected_clone = selected.clone();
@@ -1281,7 +3043,7 @@ impl FlameApp {
                                 .on_hover_text("Close (Esc)")
                                 .clicked()
                             {
-                                self.selected_span = None;
+                                self.set_selected_span(None);
                             }
                         });
                     });
@@ -1292,7 +3054,7 @@ impl FlameApp {
                     if let Some(session) = &self.session {
                         if let Some(entry) = session.profiles().first() {
                             if let Some(lane) = self.lanes.get(selected_clone.lane_index) {
-                                if let LaneKind::Thread(tid) = &lane.kind {
+                                if let LaneKind::Thread(tid) | LaneKind::Tasks(tid) = &lane.kind {
                                     if let Some(thread) =
                                         entry.profile.threads.iter().find(|t| t.id == *tid)
                                     {
@@ -1303,9 +3065,11 @@ impl FlameApp {
                                         {
                                             ui.horizontal(|ui| {
                                                 ui.label(
-                                                    egui::RichText::new(&selected_clone.name)
-                                                        .strong()
-                                                        .size(FONT_EMPHASIS),
+                                                    egui::RichText::new(
+                                                        selected_clone.name.as_str(),
+                                                    )
+                                                    .strong()
+                                                    .size(FONT_EMPHASIS),
                                                 );
                                             });
                                             ui.horizontal(|ui| {
@@ -1323,7 +3087,11 @@ impl FlameApp {
                                                 ui.label(
                                                     egui::RichText::new(format!(
                                                         "{} ({:.1}%)",
-                                                        format_duration(span.duration()),
+                                                        format_span_value(
+                                                            &entry.profile.meta,
+                                                            span.duration(),
+                                                            &self.duration_format,
+                                                        ),
                                                         pct,
                                                     ))
                                                     .size(FONT_BODY)
@@ -1332,7 +3100,11 @@ impl FlameApp {
                                                 ui.label(
                                                     egui::RichText::new(format!(
                                                         "Self: {} ({:.1}%)",
-                                                        format_duration(span.self_value),
+                                                        format_span_value(
+                                                            &entry.profile.meta,
+                                                            span.self_value,
+                                                            &self.duration_format,
+                                                        ),
                                                         self_pct,
                                                     ))
                                                     .size(FONT_CAPTION)
@@ -1349,6 +3121,13 @@ impl FlameApp {
                                             });
                                             if let Some(cat) = &span.category {
                                                 ui.label(format!("Category: {}", cat.name));
+                                                if let Some(source) = &cat.source {
+                                                    ui.label(
+                                                        egui::RichText::new(source.as_str())
+                                                            .size(FONT_CAPTION)
+                                                            .weak(),
+                                                    );
+                                                }
                                             }
                                             // Ancestor breadcrumbs
                                             if span.parent.is_some() {
@@ -1400,18 +3179,30 @@ impl FlameApp {
                                                         );
                                                     }
                                                     ui.label(
-                                                        egui::RichText::new(&selected_clone.name)
-                                                            .size(FONT_TINY)
-                                                            .strong(),
+                                                        egui::RichText::new(
+                                                            selected_clone.name.as_str(),
+                                                        )
+                                                        .size(FONT_TINY)
+                                                        .strong(),
                                                     );
                                                 });
                                             }
+                                            // Where the span's time went, by child name
+                                            if let Some((_, info)) = &self.selected_info {
+                                                if !info.children.is_empty() {
+                                                    self.render_child_breakdown(
+                                                        ui,
+                                                        &entry.profile.meta,
+                                                        &info.children,
+                                                    );
+                                                }
+                                            }
                                         }
                                     }
                                 } else {
                                     // Non-thread lanes: just show the name
                                     ui.label(
-                                        egui::RichText::new(&selected_clone.name)
+                                        egui::RichText::new(selected_clone.name.as_str())
                                             .strong()
                                             .size(FONT_EMPHASIS),
                                     );
@@ -1428,15 +3219,78 @@ impl FlameApp {
         }
     }
 
+    /// Table of a span's direct children totalled by name.
+    fn render_child_breakdown(
+        &self,
+        ui: &mut egui::Ui,
+        meta: &flame_cat_protocol::ProfileMeta,
+        children: &[ChildTotal],
+    ) {
+        ui.add_space(4.0);
+        egui::ScrollArea::vertical()
+            .id_salt("detail_children")
+            .show(ui, |ui| {
+                egui::Grid::new("detail_children_grid")
+                    .striped(true)
+                    .num_columns(4)
+                    .show(ui, |ui| {
+                        for heading in ["Child", "Time", "%", "Count"] {
+                            ui.label(egui::RichText::new(heading).size(FONT_CAPTION).weak());
+                        }
+                        ui.end_row();
+                        for child in children {
+                            ui.label(egui::RichText::new(&child.name).size(FONT_CAPTION));
+                            ui.label(
+                                egui::RichText::new(format_span_value(
+                                    meta,
+                                    child.total_time,
+                                    &self.duration_format,
+                                ))
+                                .size(FONT_CAPTION)
+                                .monospace(),
+                            );
+                            ui.label(
+                                egui::RichText::new(format!("{:.1}%", child.percent))
+                                    .size(FONT_CAPTION)
+                                    .monospace(),
+                            );
+                            ui.label(
+                                egui::RichText::new(format!("×{}", child.span_count))
+                                    .size(FONT_CAPTION)
+                                    .monospace(),
+                            );
+                            ui.end_row();
+                        }
+                    });
+            });
+    }
+
     fn render_sidebar(&mut self, ctx: &egui::Context) {
         // Sidebar: lane visibility toggles
         if self.session.is_some() {
             egui::SidePanel::left("lane_sidebar")
-                .default_width(180.0)
+                .default_width(220.0)
                 .min_width(120.0)
                 .resizable(true)
                 .show(ctx, |ui| {
                     ui.label(egui::RichText::new("Lanes").size(FONT_BODY).strong());
+                    let thread_order = self
+                        .session
+                        .as_ref()
+                        .map(|s| s.thread_order().clone())
+                        .unwrap_or_default();
+                    egui::ComboBox::from_id_salt("thread_sort")
+                        .selected_text(format!("Threads by {}", thread_order.sort.label()))
+                        .show_ui(ui, |ui| {
+                            for sort in ThreadSort::ALL {
+                                if ui
+                                    .selectable_label(thread_order.sort == sort, sort.label())
+                                    .clicked()
+                                {
+                                    crate::push_command(crate::AppCommand::SetThreadSort(sort));
+                                }
+                            }
+                        });
                     ui.separator();
                     egui::ScrollArea::vertical().show(ui, |ui| {
                         let mut changed = false;
@@ -1445,10 +3299,18 @@ impl FlameApp {
                             let lane_visible = self.lanes[idx].visible;
                             let mut vis = lane_visible;
                             let full_name = self.lanes[idx].name.clone();
+                            let thread_name = self.lane_thread_name(&self.lanes[idx].kind);
+                            let counter_peak = self.lane_counter_peak(&self.lanes[idx].kind);
+                            let budget = self.saved_state.counter_budgets.get(&full_name).copied();
+                            let thumbnail = match self.lanes[idx].kind {
+                                LaneKind::Thread(id) => self.lane_thumbnails.get(&id),
+                                _ => None,
+                            };
                             ui.horizontal(|ui| {
                                 if ui.checkbox(&mut vis, "").changed() {
                                     changed = true;
                                 }
+                                self.draw_lane_thumbnail(ui, thumbnail.map(Vec::as_slice), vis);
                                 let display_name =
                                     if full_name.chars().count() > SIDEBAR_NAME_MAX_CHARS {
                                         let end = full_name
@@ -1468,8 +3330,45 @@ impl FlameApp {
                                         },
                                     ),
                                 );
-                                if display_name.len() < full_name.len() {
-                                    resp.on_hover_text(&full_name);
+                                let resp = if display_name.len() < full_name.len() {
+                                    resp.on_hover_text(&full_name)
+                                } else {
+                                    resp
+                                };
+                                if let Some(thread) = &thread_name {
+                                    let pinned = thread_order.is_pinned(thread);
+                                    resp.context_menu(|ui| {
+                                        let label = if pinned { "Unpin" } else { "Pin to top" };
+                                        if ui.button(label).clicked() {
+                                            crate::push_command(crate::AppCommand::PinThread {
+                                                name: thread.to_string(),
+                                                pinned: !pinned,
+                                            });
+                                            ui.close();
+                                        }
+                                    });
+                                } else if let Some(peak) = counter_peak {
+                                    resp.context_menu(|ui| {
+                                        let mut enabled = budget.is_some();
+                                        let mut limit = budget.unwrap_or(peak);
+                                        let mut changed =
+                                            ui.checkbox(&mut enabled, "Budget").changed();
+                                        changed |= ui
+                                            .add_enabled(
+                                                enabled,
+                                                egui::DragValue::new(&mut limit)
+                                                    .speed((peak.abs() / 200.0).max(0.01)),
+                                            )
+                                            .changed();
+                                        if changed {
+                                            crate::push_command(
+                                                crate::AppCommand::SetCounterBudget {
+                                                    name: full_name.clone(),
+                                                    limit: enabled.then_some(limit),
+                                                },
+                                            );
+                                        }
+                                    });
                                 }
                             });
                             if vis != lane_visible {
@@ -1484,9 +3383,355 @@ impl FlameApp {
         }
     }
 
+    /// Tiny density strip of a thread lane, so lanes can be told apart
+    /// before they are shown. Other lanes get the same room left blank to
+    /// keep the names aligned.
+    fn draw_lane_thumbnail(&self, ui: &mut egui::Ui, density: Option<&[u32]>, visible: bool) {
+        let (rect, _) = ui.allocate_exact_size(
+            egui::vec2(THUMBNAIL_COLS as f32, FONT_CAPTION),
+            egui::Sense::hover(),
+        );
+        let Some(density) = density else {
+            return;
+        };
+        let painter = ui.painter();
+        painter.rect_filled(
+            rect,
+            egui::CornerRadius::ZERO,
+            crate::theme::resolve(
+                flame_cat_protocol::ThemeToken::MinimapBackground,
+                self.theme_mode,
+            ),
+        );
+        let max_d = density.iter().copied().max().unwrap_or(0).max(1);
+        let mut color = crate::theme::resolve(
+            flame_cat_protocol::ThemeToken::MinimapDensity,
+            self.theme_mode,
+        );
+        if !visible {
+            color = color.gamma_multiply(0.4);
+        }
+        let col_w = rect.width() / density.len().max(1) as f32;
+        for (c, &d) in density.iter().enumerate() {
+            if d == 0 {
+                continue;
+            }
+            // Same sqrt scaling as the minimap.
+            let h = ((d as f32).sqrt() / (max_d as f32).sqrt() * rect.height()).max(1.0);
+            let x = rect.left() + c as f32 * col_w;
+            painter.rect_filled(
+                egui::Rect::from_min_max(
+                    egui::pos2(x, rect.bottom() - h),
+                    egui::pos2(x + col_w, rect.bottom()),
+                ),
+                egui::CornerRadius::ZERO,
+                color,
+            );
+        }
+    }
+
+    /// Right-hand panel listing the session's notes by time. Clicking a note
+    /// zooms to it; ✕ deletes it.
+    fn render_annotations_panel(&mut self, ctx: &egui::Context) {
+        if !self.show_annotations {
+            return;
+        }
+        let Some(session) = self.session.clone() else {
+            return;
+        };
+        let mut order: Vec<usize> = (0..self.saved_state.annotations.len()).collect();
+        order.sort_by(|&a, &b| {
+            let notes = &self.saved_state.annotations;
+            notes[a].start_us.total_cmp(&notes[b].start_us)
+        });
+        let origin = session.start_time();
+        let mut zoom = None;
+        let mut remove = None;
+        egui::SidePanel::right("annotations")
+            .default_width(220.0)
+            .resizable(true)
+            .show(ctx, |ui| {
+                ui.label(egui::RichText::new("Notes").size(FONT_BODY).strong());
+                ui.separator();
+                if order.is_empty() {
+                    ui.label(
+                        egui::RichText::new("Drop a .annotations.json file to add notes")
+                            .size(FONT_CAPTION)
+                            .weak(),
+                    );
+                }
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    for index in order {
+                        let note = &self.saved_state.annotations[index];
+                        ui.horizontal(|ui| {
+                            let at = self.duration_format.format_us(note.start_us - origin);
+                            let hover = match &note.lane {
+                                Some(lane) => format!("{}\nLane: {lane}", note.text),
+                                None => note.text.clone(),
+                            };
+                            if ui
+                                .link(
+                                    egui::RichText::new(format!("{at}  {}", note.text))
+                                        .size(FONT_CAPTION),
+                                )
+                                .on_hover_text(hover)
+                                .clicked()
+                            {
+                                zoom = Some((note.start_us, note.end_us));
+                            }
+                            if ui.small_button("✕").on_hover_text("Delete").clicked() {
+                                remove = Some(index);
+                            }
+                        });
+                    }
+                });
+            });
+        if let Some(index) = remove {
+            self.saved_state.annotations.remove(index);
+            self.state_gen += 1;
+        }
+        let duration = session.duration();
+        if let Some((start, end)) = zoom.filter(|_| duration > 0.0) {
+            let start = (start - origin) / duration;
+            match end {
+                Some(end) => self.animate_to(start, (end - origin) / duration),
+                None => {
+                    let width = self.view_end - self.view_start;
+                    let lo = (start - width / 2.0).clamp(0.0, 1.0 - width);
+                    self.animate_to(lo, lo + width);
+                }
+            }
+        }
+    }
+
+    /// Bottom tray comparing the pinned spans of every profile side by side.
+    /// Clicking a span zooms to it; ✕ unpins it.
+    fn render_pin_tray(&mut self, ctx: &egui::Context) {
+        let Some(session) = self.session.clone() else {
+            return;
+        };
+        if self.saved_state.pins.is_empty() {
+            return;
+        }
+        if self
+            .pinned_rows
+            .as_ref()
+            .is_none_or(|(pins, _)| *pins != self.saved_state.pins)
+        {
+            let rows = self.saved_state.pinned_spans(&session);
+            self.pinned_rows = Some((self.saved_state.pins.clone(), rows));
+        }
+        let Some((_, rows)) = &self.pinned_rows else {
+            return;
+        };
+        let mut zoom = None;
+        let mut unpin = None;
+        let mut clear = false;
+        egui::TopBottomPanel::bottom("pin_tray")
+            .max_height(160.0)
+            .resizable(true)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label(egui::RichText::new("Pinned").size(FONT_BODY).strong());
+                    ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                        clear = ui.small_button("Clear").clicked();
+                    });
+                });
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    egui::Grid::new("pin_tray_grid")
+                        .striped(true)
+                        .num_columns(6)
+                        .show(ui, |ui| {
+                            for heading in ["Profile", "Span", "Duration", "Self", "vs first", ""] {
+                                ui.label(egui::RichText::new(heading).size(FONT_CAPTION).weak());
+                            }
+                            ui.end_row();
+                            for row in rows {
+                                let Some(entry) = session.profiles().get(row.pin.profile) else {
+                                    continue;
+                                };
+                                let meta = &entry.profile.meta;
+                                ui.label(egui::RichText::new(&row.label).size(FONT_CAPTION));
+                                if ui
+                                    .link(egui::RichText::new(row.name.as_str()).size(FONT_CAPTION))
+                                    .on_hover_text("Zoom to span")
+                                    .clicked()
+                                {
+                                    zoom = Some((row.start_us, row.end_us));
+                                }
+                                for value in [row.duration, row.self_value] {
+                                    ui.label(
+                                        egui::RichText::new(format_span_value(
+                                            meta,
+                                            value,
+                                            &self.duration_format,
+                                        ))
+                                        .size(FONT_CAPTION)
+                                        .monospace(),
+                                    );
+                                }
+                                let ratio = row.ratio.map_or_else(
+                                    || "—".to_string(),
+                                    |r| format!("{}×", self.duration_format.number(r, 2)),
+                                );
+                                ui.label(egui::RichText::new(ratio).size(FONT_CAPTION).monospace());
+                                if ui.small_button("✕").on_hover_text("Unpin").clicked() {
+                                    unpin = Some(row.pin);
+                                }
+                                ui.end_row();
+                            }
+                        });
+                });
+            });
+        if clear {
+            self.saved_state.pins.clear();
+            self.state_gen += 1;
+        } else if let Some(pin) = unpin {
+            self.saved_state.toggle_pin(pin);
+            self.state_gen += 1;
+        }
+        let duration = session.duration();
+        if let Some((start, end)) = zoom.filter(|_| duration > 0.0) {
+            let origin = session.start_time();
+            self.animate_to((start - origin) / duration, (end - origin) / duration);
+        }
+    }
+
+    /// Right-hand panel with a bar splitting self time between first-party,
+    /// dependency and runtime code, each color key with its swatch and total
+    /// self time, then the profile's mapped binaries if it has any. Clicking
+    /// a bar segment or row dims every other class, key or binary; clicking
+    /// it again clears the filter.
+    fn render_legend_panel(&mut self, ctx: &egui::Context) {
+        if !self.show_legend {
+            return;
+        }
+        let Some(session) = &self.session else {
+            return;
+        };
+        let Some(entry) = session.profiles().first() else {
+            return;
+        };
+        let classes = self
+            .classes
+            .get_or_insert_with(|| class_summary(&entry.profile, session.class_rules()));
+        let legend = self
+            .legend
+            .get_or_insert_with(|| category_legend(&entry.profile));
+        let binaries = self
+            .binaries
+            .get_or_insert_with(|| binary_breakdown(&entry.profile));
+        let meta = &entry.profile.meta;
+        let mut clicked = None;
+        let mut clicked_binary = None;
+        let mut clicked_class = None;
+        egui::SidePanel::right("category_legend")
+            .default_width(200.0)
+            .resizable(true)
+            .show(ctx, |ui| {
+                ui.label(egui::RichText::new("Legend").size(FONT_BODY).strong());
+                if self.color_mode != renderer::ColorMode::ByName {
+                    ui.label(
+                        egui::RichText::new("Colors apply in By Name mode")
+                            .size(FONT_CAPTION)
+                            .weak(),
+                    );
+                }
+                ui.separator();
+                let class_bar = class_bar(
+                    ui,
+                    classes,
+                    meta,
+                    self.class_filter,
+                    self.theme_mode,
+                    &self.duration_format,
+                );
+                if let Some(class) = class_bar {
+                    clicked_class = Some((self.class_filter != Some(class)).then_some(class));
+                }
+                ui.separator();
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    for item in legend.iter() {
+                        let active = self.category_filter.as_deref() == Some(&item.category);
+                        ui.horizontal(|ui| {
+                            let (swatch, _) = ui
+                                .allocate_exact_size(egui::vec2(12.0, 12.0), egui::Sense::hover());
+                            ui.painter().rect_filled(
+                                swatch,
+                                2.0,
+                                renderer::category_color(&item.category, self.theme_mode),
+                            );
+                            let name = if item.category.is_empty() {
+                                "(unnamed)"
+                            } else {
+                                &item.category
+                            };
+                            let label = format!(
+                                "{name}  {}",
+                                format_span_value(meta, item.total_time, &self.duration_format,)
+                            );
+                            if ui
+                                .selectable_label(
+                                    active,
+                                    egui::RichText::new(label).size(FONT_CAPTION),
+                                )
+                                .on_hover_text(format!("{} spans", item.span_count))
+                                .clicked()
+                            {
+                                clicked = Some((!active).then(|| item.category.clone()));
+                            }
+                        });
+                    }
+                    if !binaries.is_empty() {
+                        ui.separator();
+                        ui.label(egui::RichText::new("Binaries").size(FONT_CAPTION).strong());
+                    }
+                    for item in binaries.iter() {
+                        let active = self.binary_filter.as_deref() == Some(&item.name);
+                        let file = item.name.rsplit(['/', '\\']).next().unwrap_or(&item.name);
+                        let label = format!(
+                            "{file}  {}",
+                            format_span_value(meta, item.total_time, &self.duration_format)
+                        );
+                        let hover = match &item.build_id {
+                            Some(id) => {
+                                format!("{}\nbuild id {id}\n{} spans", item.name, item.span_count)
+                            }
+                            None => format!("{}\n{} spans", item.name, item.span_count),
+                        };
+                        if ui
+                            .selectable_label(active, egui::RichText::new(label).size(FONT_CAPTION))
+                            .on_hover_text(hover)
+                            .clicked()
+                        {
+                            clicked_binary = Some((!active).then(|| item.name.clone()));
+                        }
+                    }
+                });
+            });
+        if let Some(category) = clicked {
+            self.category_filter = category;
+            self.invalidate_commands();
+        }
+        if let Some(binary) = clicked_binary {
+            self.binary_filter = binary;
+            self.invalidate_commands();
+        }
+        if let Some(class) = clicked_class {
+            self.class_filter = class;
+            self.invalidate_commands();
+        }
+    }
+
     fn render_central_panel(&mut self, ctx: &egui::Context) {
+        // Last frame's hover also outlines the same span in other lanes,
+        // e.g. an async span and the thread span that executed it.
+        let linked_hover = self
+            .hovered_span
+            .as_ref()
+            .map(|h| (h.frame_id, h.lane_index));
         // Clear hover state each frame
-        self.hovered_span = None;
+        self.set_hovered_span(None);
 
         // Central panel: flame chart
         egui::CentralPanel::default().show(ctx, |ui| {
@@ -1496,6 +3741,10 @@ impl FlameApp {
                         ui.add_space(ui.available_height() / 3.0);
                         ui.spinner();
                         ui.label("Parsing profile…");
+                        #[cfg(not(target_arch = "wasm32"))]
+                        if ui.button("Cancel").clicked() {
+                            self.cancel_load();
+                        }
                     });
                 });
                 return;
@@ -1570,11 +3819,12 @@ impl FlameApp {
                 );
             }
 
-            let (time_rect, _) = ui.allocate_exact_size(
+            let (time_rect, time_resp) = ui.allocate_exact_size(
                 egui::vec2(ui.available_width(), time_axis_height),
                 egui::Sense::hover(),
             );
             self.draw_time_axis(ui, time_rect);
+            self.draw_annotation_pins(ui, time_resp);
 
             // Minimap overview strip (interactive range slider)
             let minimap_height = 48.0_f32;
@@ -1584,7 +3834,9 @@ impl FlameApp {
             );
             self.draw_minimap(ui, minimap_rect, &minimap_resp);
             minimap_resp
-                .on_hover_text("Drag viewport edges to zoom, or drag the middle to pan the view");
+                .on_hover_text(
+                "Drag viewport edges to zoom, the middle to pan, or outside it to select a new range",
+            );
 
             let available = ui.available_rect_before_wrap();
 
@@ -1689,12 +3941,14 @@ impl FlameApp {
                 }
             }
 
-            // Scroll wheel: Ctrl/Cmd+scroll = zoom, plain scroll = vertical pan
+            // Scroll wheel: Ctrl/Cmd+scroll = zoom, plain scroll = vertical pan.
+            // Alt+scroll is handled per lane below.
             let scroll = ui.input(|i| i.smooth_scroll_delta);
             let ctrl_held = ui.input(|i| i.modifiers.ctrl || i.modifiers.command);
             let shift_held = ui.input(|i| i.modifiers.shift);
+            let alt_held = ui.input(|i| i.modifiers.alt);
 
-            if ctrl_held && scroll.y.abs() > 0.1 {
+            if ctrl_held && !alt_held && scroll.y.abs() > 0.1 {
                 // Ctrl+scroll = zoom (like Chrome DevTools / Perfetto)
                 self.push_zoom(); // Save position before zoom starts
                 self.anim_target = None;
@@ -1712,7 +3966,7 @@ impl FlameApp {
                 self.view_start = (cursor_time - mouse_frac * new_span).max(0.0);
                 self.view_end = (self.view_start + new_span).min(1.0);
                 self.invalidate_commands();
-            } else if !ctrl_held && scroll.y.abs() > 0.1 {
+            } else if !ctrl_held && !alt_held && scroll.y.abs() > 0.1 {
                 if shift_held {
                     // Shift+scroll = horizontal pan (for mouse wheel users)
                     self.anim_target = None;
@@ -1761,20 +4015,20 @@ impl FlameApp {
             ui.input(|i| {
                 let view_span = self.view_end - self.view_start;
                 let pan_step = view_span * 0.1;
-                if i.key_pressed(egui::Key::A) || i.key_pressed(egui::Key::ArrowLeft) {
+                if i.key_pressed(egui::Key::A) || (i.key_pressed(egui::Key::ArrowLeft) && !i.modifiers.alt) {
                     self.view_start = (self.view_start - pan_step).max(0.0);
                     self.view_end = self.view_start + view_span;
                     self.invalidate_commands();
                 }
-                if i.key_pressed(egui::Key::D) || i.key_pressed(egui::Key::ArrowRight) {
+                if i.key_pressed(egui::Key::D) || (i.key_pressed(egui::Key::ArrowRight) && !i.modifiers.alt) {
                     self.view_end = (self.view_end + pan_step).min(1.0);
                     self.view_start = self.view_end - view_span;
                     self.invalidate_commands();
                 }
-                if i.key_pressed(egui::Key::W) || i.key_pressed(egui::Key::ArrowUp) {
+                if i.key_pressed(egui::Key::W) || (i.key_pressed(egui::Key::ArrowUp) && !i.modifiers.alt) {
                     self.scroll_y = (self.scroll_y - 50.0).max(0.0);
                 }
-                if i.key_pressed(egui::Key::S) || i.key_pressed(egui::Key::ArrowDown) {
+                if i.key_pressed(egui::Key::S) || (i.key_pressed(egui::Key::ArrowDown) && !i.modifiers.alt) {
                     self.scroll_y += 50.0;
                 }
                 // +/= key = zoom in, - key = zoom out, 0 = reset
@@ -1799,16 +4053,20 @@ impl FlameApp {
                     self.scroll_y = 0.0;
                 }
                 if i.key_pressed(egui::Key::Escape) {
-                    self.selected_span = None;
+                    self.set_selected_span(None);
                 }
             });
 
-            // Span hierarchy navigation: [ ] { } keys
+            // Span hierarchy navigation: [ ] { } and Alt+arrow keys
             self.handle_span_navigation(ui);
 
             // Search result navigation: Enter / Shift+Enter
             self.handle_search_navigation(ui);
 
+            if std::mem::take(&mut self.reveal_selection) {
+                self.reveal_selected_span(available.height());
+            }
+
             // Generate render commands AFTER all input (so invalidations are resolved)
             self.ensure_commands(available.width());
 
@@ -1836,8 +4094,10 @@ impl FlameApp {
                     let vis_start = session_start + self.view_start * session_duration;
                     let vis_end = session_start + self.view_end * session_duration;
                     let vis_dur = vis_end - vis_start;
-                    let interval = nice_tick_interval(vis_dur, 8);
-                    let first_tick = (vis_start / interval).ceil() * interval;
+                    // Same major interval as the axis above, so lines meet its ticks.
+                    let (interval, _) = nice_interval(vis_dur, f64::from(available.width()));
+                    let first_tick = session_start
+                        + ((vis_start - session_start) / interval).ceil() * interval;
                     let grid_color = crate::theme::resolve(
                         flame_cat_protocol::ThemeToken::Border,
                         self.theme_mode,
@@ -1862,10 +4122,31 @@ impl FlameApp {
             let mut y_offset = available.top() - self.scroll_y;
             let mut deferred_zoom: Option<(f64, f64)> = None;
             // Collect tid → y_center for flow arrow rendering
-            let mut tid_to_y: std::collections::HashMap<u64, f32> =
+            // Flow arrows name threads by source (pid, tid); lanes by group id.
+            let group_to_source: std::collections::HashMap<u32, (u64, u64)> = self
+                .session
+                .as_ref()
+                .and_then(|s| s.profiles().first())
+                .map(|entry| {
+                    entry
+                        .profile
+                        .threads
+                        .iter()
+                        .filter_map(|t| Some((t.id, t.source_thread()?)))
+                        .collect()
+                })
+                .unwrap_or_default();
+            let mut thread_to_y: std::collections::HashMap<(u64, u64), f32> =
+                std::collections::HashMap::new();
+            // Thread group id → lane y-center, for GPU submission links.
+            let mut group_to_y: std::collections::HashMap<u32, f32> =
                 std::collections::HashMap::new();
             // Deferred lane labels — drawn last, on top of everything
             let mut deferred_labels: Vec<(String, f32, f32)> = Vec::new(); // (name, x, y)
+            // Deep thread lanes get a fit/1:1 control next to their label.
+            let mut depth_controls: Vec<(usize, f32, bool)> = Vec::new(); // (lane, y, zoomed)
+            // Alt+scroll target: (lane, pointer offset below the stack's top).
+            let mut depth_wheel: Option<(usize, f32)> = None;
 
             for (i, lane) in self.lanes.iter().enumerate() {
                 if !lane.visible {
@@ -1873,16 +4154,15 @@ impl FlameApp {
                 }
 
                 // Ranked view: single global table in the first visible lane only
-                if self.view_type == crate::ViewType::Ranked {
-                    let first_visible = self.lanes.iter().position(|l| l.visible).unwrap_or(0);
-                    if i != first_visible {
-                        continue;
-                    }
+                if self.view_type == crate::ViewType::Ranked && Some(i) != self.ranked_lane() {
+                    continue;
                 }
 
                 let self_labeled = matches!(
                     lane.kind,
-                    LaneKind::Counter(_)
+                    LaneKind::Tasks(_)
+                        | LaneKind::Utilization(_)
+                        | LaneKind::Counter(_)
                         | LaneKind::Markers
                         | LaneKind::CpuSamples
                         | LaneKind::FrameTrack
@@ -1908,7 +4188,10 @@ impl FlameApp {
 
                 // Record lane y-center for flow arrows
                 if let LaneKind::Thread(tid) = &lane.kind {
-                    tid_to_y.insert(*tid as u64, lane_top + total_height / 2.0);
+                    group_to_y.insert(*tid, lane_top + total_height / 2.0);
+                    if let Some(&source) = group_to_source.get(tid) {
+                        thread_to_y.insert(source, lane_top + total_height / 2.0);
+                    }
                 }
 
                 // Skip if completely off-screen
@@ -1943,7 +4226,6 @@ impl FlameApp {
                         cmds,
                         egui::pos2(available.left(), lane_top + label_reserve),
                         self.theme_mode,
-                        &self.search_query,
                         self.color_mode,
                     );
 
@@ -1978,8 +4260,10 @@ impl FlameApp {
                                             };
 
                                         // Update hovered span for JS hooks
+                                        // (fields set directly: `self.lanes` is borrowed)
+                                        self.selection.hover(Some(hit.frame_id));
                                         self.hovered_span = Some(SelectedSpan {
-                                            name: name.to_string(),
+                                            name: name.clone(),
                                             frame_id: hit.frame_id,
                                             lane_index: i,
                                             start_us: hit_start_us,
@@ -2007,7 +4291,7 @@ impl FlameApp {
                                             .current_pos(hover_pos + egui::vec2(12.0, 12.0))
                                             .show(ui.ctx(), |ui| {
                                                 egui::Frame::popup(ui.style()).show(ui, |ui| {
-                                                    ui.label(egui::RichText::new(&name).strong());
+                                                    ui.label(egui::RichText::new(name.as_str()).strong());
                                                     let dur = hit_end_us - hit_start_us;
                                                     let total = self
                                                         .session
@@ -2023,6 +4307,12 @@ impl FlameApp {
                                                         LaneKind::Thread(tid) => {
                                                             format!("Thread #{tid}")
                                                         }
+                                                        LaneKind::Tasks(tid) => {
+                                                            format!("Tasks of thread #{tid}")
+                                                        }
+                                                        LaneKind::Utilization(tid) => {
+                                                            format!("Utilization of thread #{tid}")
+                                                        }
                                                         LaneKind::Counter(_) => "Counter".to_string(),
                                                         LaneKind::AsyncSpans => "Async spans".to_string(),
                                                         LaneKind::Markers => "Markers".to_string(),
@@ -2037,8 +4327,10 @@ impl FlameApp {
                                                         }
                                                     };
                                                     ui.label(
-                                                        egui::RichText::new(format_duration(dur))
-                                                            .weak(),
+                                                        egui::RichText::new(
+                                                            self.format_span_duration(&name, dur),
+                                                        )
+                                                        .weak(),
                                                     );
                                                     ui.label(
                                                         egui::RichText::new(format!(
@@ -2059,6 +4351,7 @@ impl FlameApp {
                                             });
                                         if clicked {
                                             self.context_menu = None;
+                                            self.selection.select(Some(hit.frame_id));
                                             self.selected_span = Some(SelectedSpan {
                                                 name,
                                                 frame_id: hit.frame_id,
@@ -2094,6 +4387,52 @@ impl FlameApp {
                         }
                     }
 
+                    // CPU sample bars carry no span id; show the whole stack instead.
+                    if self.lanes[i].kind == LaneKind::CpuSamples {
+                        if let Some(hover_pos) = ui
+                            .input(|i| i.pointer.hover_pos())
+                            .filter(|p| content_rect.contains(*p))
+                        {
+                            let frac = f64::from(
+                                (hover_pos.x - available.left()) / available.width(),
+                            );
+                            let stack = self.cpu_stack_at(frac);
+                            if !stack.is_empty() {
+                                egui::Area::new(egui::Id::new("cpu_stack_tooltip"))
+                                    .order(egui::Order::Tooltip)
+                                    .current_pos(hover_pos + egui::vec2(12.0, 12.0))
+                                    .show(ui.ctx(), |ui| {
+                                        egui::Frame::popup(ui.style()).show(ui, |ui| {
+                                            ui.label(
+                                                egui::RichText::new(format!(
+                                                    "Sampled stack ({} frames)",
+                                                    stack.len()
+                                                ))
+                                                .strong(),
+                                            );
+                                            for name in stack.iter().take(MAX_TOOLTIP_STACK) {
+                                                ui.label(
+                                                    egui::RichText::new(name.as_ref())
+                                                        .monospace()
+                                                        .size(FONT_TINY),
+                                                );
+                                            }
+                                            if stack.len() > MAX_TOOLTIP_STACK {
+                                                ui.label(
+                                                    egui::RichText::new(format!(
+                                                        "… {} more",
+                                                        stack.len() - MAX_TOOLTIP_STACK
+                                                    ))
+                                                    .size(FONT_TINY)
+                                                    .weak(),
+                                                );
+                                            }
+                                        });
+                                    });
+                            }
+                        }
+                    }
+
                     // Selected span highlight
                     if let Some(sel) = &self.selected_span {
                         if sel.lane_index == i {
@@ -2115,6 +4454,24 @@ impl FlameApp {
                         }
                     }
 
+                    // Linked hover highlight
+                    if let Some((frame_id, hover_lane)) = linked_hover {
+                        if hover_lane != i {
+                            let hover_color = crate::theme::resolve(
+                                flame_cat_protocol::ThemeToken::HoverHighlight,
+                                self.theme_mode,
+                            );
+                            for hit in result.hit_regions.iter().filter(|h| h.frame_id == frame_id) {
+                                painter.rect_stroke(
+                                    hit.rect,
+                                    egui::CornerRadius::ZERO,
+                                    egui::Stroke::new(1.0, hover_color),
+                                    egui::StrokeKind::Outside,
+                                );
+                            }
+                        }
+                    }
+
                     // Double-click to zoom to span
                     if response.double_clicked() {
                         if let Some(hover_pos) = ui.input(|i| i.pointer.hover_pos()) {
@@ -2142,12 +4499,28 @@ impl FlameApp {
                     }
                 }
 
+                if let Some(rows) = self.lane_depth_rows(lane) {
+                    let stack_top = lane_top + label_reserve;
+                    if let Some(pos) = ui.input(|i| i.pointer.hover_pos()) {
+                        if alt_held && content_rect.contains(pos) {
+                            depth_wheel = Some((i, (pos.y - stack_top).max(0.0)));
+                        }
+                    }
+                    self.draw_hidden_depth(&painter, lane, rows, content_rect, stack_top);
+                    let zoomed = lane.row_scale != 1.0 || lane.depth_scroll != 0.0;
+                    if zoomed || rows as f32 * FRAME_HEIGHT as f32 > lane.height {
+                        depth_controls.push((i, lane_top, zoomed));
+                    }
+                }
+
                 painter.set_clip_rect(prev_clip);
 
                 // Defer lane label for top-of-everything rendering
                 let self_labeled = matches!(
                     lane.kind,
-                    LaneKind::Counter(_)
+                    LaneKind::Tasks(_)
+                        | LaneKind::Utilization(_)
+                        | LaneKind::Counter(_)
                         | LaneKind::Markers
                         | LaneKind::CpuSamples
                         | LaneKind::FrameTrack
@@ -2210,17 +4583,19 @@ impl FlameApp {
                             if drawn >= max_arrows {
                                 break;
                             }
-                            let from_y = tid_to_y.get(&arrow.from_tid);
-                            let to_y = tid_to_y.get(&arrow.to_tid);
+                            let from_y = thread_to_y.get(&(arrow.from_pid, arrow.from_tid));
+                            let to_y = thread_to_y.get(&(arrow.to_pid, arrow.to_tid));
                             let (Some(&from_y), Some(&to_y)) = (from_y, to_y) else {
                                 continue;
                             };
 
                             // Convert timestamps to fractional viewport position
-                            let from_frac = ((arrow.from_ts - session_start) / session_duration
+                            let from_frac = ((entry.to_session_time(arrow.from_ts) - session_start)
+                                / session_duration
                                 - self.view_start)
                                 / view_span;
-                            let to_frac = ((arrow.to_ts - session_start) / session_duration
+                            let to_frac = ((entry.to_session_time(arrow.to_ts) - session_start)
+                                / session_duration
                                 - self.view_start)
                                 / view_span;
 
@@ -2234,34 +4609,70 @@ impl FlameApp {
                             let from_x = available.left() + from_frac as f32 * available.width();
                             let to_x = available.left() + to_frac as f32 * available.width();
 
-                            let p1 = egui::pos2(from_x, from_y);
-                            let p4 = egui::pos2(to_x, to_y);
-
-                            // Cubic Bézier with horizontal control points
-                            let dx = (to_x - from_x).abs() * 0.4;
-                            let p2 = egui::pos2(from_x + dx, from_y);
-                            let p3 = egui::pos2(to_x - dx, to_y);
-
-                            let bezier = egui::epaint::CubicBezierShape::from_points_stroke(
-                                [p1, p2, p3, p4],
-                                false,
-                                egui::Color32::TRANSPARENT,
-                                egui::Stroke::new(1.0, arrow_color),
+                            draw_link_arrow(
+                                &painter,
+                                egui::pos2(from_x, from_y),
+                                egui::pos2(to_x, to_y),
+                                arrow_color,
+                                head_color,
                             );
-                            painter.add(bezier);
-
-                            // Small arrowhead triangle at destination
-                            let arrow_size = 4.0_f32;
-                            let dir = (p4 - p3).normalized();
-                            let perp = egui::vec2(-dir.y, dir.x);
-                            let tip = p4;
-                            let left = tip - dir * arrow_size + perp * arrow_size * 0.5;
-                            let right = tip - dir * arrow_size - perp * arrow_size * 0.5;
-                            painter.add(egui::epaint::PathShape::convex_polygon(
-                                vec![tip, left, right],
+                            drawn += 1;
+                        }
+                    }
+                }
+            }
+
+            // Link GPU queue work to the CPU submits that produced it
+            if let (Some(session), Some(links)) = (&self.session, &self.gpu_submissions) {
+                if let Some(entry) = session.profiles().first() {
+                    let session_start = session.start_time();
+                    let session_duration = session.end_time() - session_start;
+                    let view_span = self.view_end - self.view_start;
+                    if session_duration > 0.0 && view_span > 0.0 {
+                        let c = crate::theme::resolve(
+                            flame_cat_protocol::ThemeToken::GpuSpanBorder,
+                            self.theme_mode,
+                        );
+                        let line_color =
+                            egui::Color32::from_rgba_unmultiplied(c.r(), c.g(), c.b(), 70);
+                        let head_color =
+                            egui::Color32::from_rgba_unmultiplied(c.r(), c.g(), c.b(), 110);
+                        let frac = |ts: f64| {
+                            ((entry.to_session_time(ts) - session_start) / session_duration
+                                - self.view_start)
+                                / view_span
+                        };
+                        painter.set_clip_rect(available);
+                        let mut drawn = 0_usize;
+                        for link in links {
+                            if drawn >= 50 {
+                                break;
+                            }
+                            let from_y = group_to_y.get(&link.submit_thread);
+                            let to_y = group_to_y.get(&link.gpu_thread);
+                            let (Some(&from_y), Some(&to_y)) = (from_y, to_y) else {
+                                continue;
+                            };
+                            let (from_frac, to_frac) =
+                                (frac(link.submit_start), frac(link.gpu_start));
+                            if (from_frac < -0.1 && to_frac < -0.1)
+                                || (from_frac > 1.1 && to_frac > 1.1)
+                            {
+                                continue;
+                            }
+                            draw_link_arrow(
+                                &painter,
+                                egui::pos2(
+                                    available.left() + from_frac as f32 * available.width(),
+                                    from_y,
+                                ),
+                                egui::pos2(
+                                    available.left() + to_frac as f32 * available.width(),
+                                    to_y,
+                                ),
+                                line_color,
                                 head_color,
-                                egui::Stroke::NONE,
-                            ));
+                            );
                             drawn += 1;
                         }
                     }
@@ -2308,6 +4719,49 @@ impl FlameApp {
                 }
             }
 
+            // Fit/1:1 controls of deep lanes, in the header row's right end
+            let mut fit_toggle = None;
+            for &(idx, lane_top, zoomed) in &depth_controls {
+                let rect = egui::Rect::from_min_size(
+                    egui::pos2(available.right() - 44.0, lane_top + 1.0),
+                    egui::vec2(40.0, 14.0),
+                );
+                if rect.top() < available.top() || rect.bottom() > available.bottom() {
+                    continue;
+                }
+                let (text, hint) = if zoomed {
+                    ("1:1", "Back to full-size rows")
+                } else {
+                    ("Fit", "Fit the whole stack into the lane")
+                };
+                let button = egui::Button::new(egui::RichText::new(text).size(FONT_TINY)).small();
+                if ui
+                    .put(rect, button)
+                    .on_hover_text(format!(
+                        "{hint}. Alt+scroll scrolls the stack, Alt+Ctrl+scroll zooms its rows."
+                    ))
+                    .clicked()
+                {
+                    fit_toggle = Some(idx);
+                }
+            }
+            if let Some(idx) = fit_toggle {
+                self.toggle_lane_fit(idx);
+            }
+
+            // Alt+scroll scrolls the lane's stack; Alt+Ctrl+scroll zooms its rows
+            if let Some((idx, anchor)) = depth_wheel {
+                if scroll.y.abs() > 0.1 {
+                    let row_scale = self.lanes[idx].row_scale;
+                    if ctrl_held {
+                        let zoom = 2.0_f32.powf(scroll.y * 0.01);
+                        self.adjust_lane_depth(idx, 0.0, zoom, anchor);
+                    } else {
+                        self.adjust_lane_depth(idx, -scroll.y / row_scale, 1.0, anchor);
+                    }
+                }
+            }
+
             // Apply deferred double-click zoom (animated)
             if let Some((new_start, new_end)) = deferred_zoom {
                 self.animate_to(new_start, new_end);
@@ -2315,6 +4769,52 @@ impl FlameApp {
         });
     }
 
+    /// Note how many stack levels of `lane` are scrolled out above and
+    /// cut off below its window.
+    fn draw_hidden_depth(
+        &self,
+        painter: &egui::Painter,
+        lane: &LaneState,
+        rows: u32,
+        content_rect: egui::Rect,
+        stack_top: f32,
+    ) {
+        let row = FRAME_HEIGHT as f32;
+        let above = (lane.depth_scroll / row).ceil() as u32;
+        let shown_end = ((lane.depth_scroll + lane.height / lane.row_scale) / row).floor() as u32;
+        let below = rows.saturating_sub(shown_end);
+        let color = crate::theme::resolve(
+            flame_cat_protocol::ThemeToken::TextSecondary,
+            self.theme_mode,
+        );
+        let bg = crate::theme::resolve(
+            flame_cat_protocol::ThemeToken::InlineLabelBackground,
+            self.theme_mode,
+        );
+        let font = egui::FontId::proportional(FONT_TINY);
+        let levels = |n: u32| if n == 1 { "level" } else { "levels" };
+        let badge = |text: String, anchor: egui::Align2, pos: egui::Pos2| {
+            let galley = painter.layout_no_wrap(text, font.clone(), color);
+            let rect = anchor.anchor_size(pos, galley.size());
+            painter.rect_filled(rect.expand(2.0), egui::CornerRadius::same(3), bg);
+            painter.galley(rect.min, galley, color);
+        };
+        if above > 0 {
+            badge(
+                format!("▲ {above} {}", levels(above)),
+                egui::Align2::RIGHT_TOP,
+                egui::pos2(content_rect.right() - 6.0, stack_top + 2.0),
+            );
+        }
+        if below > 0 {
+            badge(
+                format!("▼ {below} more {}", levels(below)),
+                egui::Align2::RIGHT_BOTTOM,
+                egui::pos2(content_rect.right() - 6.0, content_rect.bottom() - 2.0),
+            );
+        }
+    }
+
     fn handle_file_drop(&mut self, ctx: &egui::Context) {
         // Handle file drop
         ctx.input(|i| {
@@ -2323,6 +4823,29 @@ impl FlameApp {
                     if let Some(bytes) = &file.bytes {
                         // bytes is Arc<[u8]>
                         let data: Vec<u8> = bytes.to_vec();
+                        // `perf-<pid>.map` is a JIT symbol map; other `.map`
+                        // files are source maps.
+                        if file.name.starts_with("perf-") && file.name.ends_with(".map") {
+                            crate::push_command(crate::AppCommand::AttachJitMap {
+                                profile_index: 0,
+                                map: data,
+                            });
+                            return;
+                        }
+                        if file.name.ends_with(".annotations.json") {
+                            crate::push_command(crate::AppCommand::ImportAnnotations {
+                                profile_index: 0,
+                                data,
+                            });
+                            return;
+                        }
+                        if file.name.ends_with(".map") {
+                            crate::push_command(crate::AppCommand::ApplySourceMap {
+                                script_url: None,
+                                map: data,
+                            });
+                            return;
+                        }
                         // We need to defer this to avoid borrow issues
                         ctx.memory_mut(|mem| {
                             mem.data.insert_temp(egui::Id::new("pending_file"), data);
@@ -2339,7 +4862,10 @@ impl FlameApp {
             ctx.memory_mut(|mem| {
                 mem.data.remove::<Vec<u8>>(egui::Id::new("pending_file"));
             });
-            self.load_profile(&data);
+            #[cfg(not(target_arch = "wasm32"))]
+            self.load_profile(ctx, data, FormatHint::default());
+            #[cfg(target_arch = "wasm32")]
+            self.load_profile(ctx, &data, &FormatHint::default());
         }
     }
 
@@ -2397,6 +4923,8 @@ impl FlameApp {
                             ("Pinch", "Pinch zoom"),
                             ("Alt+Drag", "Drag to zoom selection"),
                             ("Double-click", "Zoom to span"),
+                            ("Alt+Scroll", "Scroll a lane's stack"),
+                            ("Alt+Ctrl+Scroll", "Zoom a lane's rows"),
                         ];
                         for (key, desc) in zoom {
                             ui.horizontal(|ui| {
@@ -2419,10 +4947,11 @@ impl FlameApp {
                         let selection = [
                             ("Click", "Select span"),
                             ("Right-click", "Context menu"),
-                            ("[", "Select parent span"),
-                            ("]", "Select first child"),
-                            ("Shift+[", "Previous sibling"),
-                            ("Shift+]", "Next sibling"),
+                            ("[  /  Alt+↑", "Select parent span"),
+                            ("]  /  Alt+↓", "Select first child"),
+                            ("Shift+[  /  Alt+←", "Previous sibling"),
+                            ("Shift+]  /  Alt+→", "Next sibling"),
+                            ("Shift+F", "Follow flow arrow"),
                             ("Enter", "Next search result"),
                             ("Shift+Enter", "Previous search result"),
                             ("Esc", "Deselect / close help"),
@@ -2447,24 +4976,132 @@ impl FlameApp {
             });
     }
 
+    /// "Profile details" dialog for renaming, describing and tagging the
+    /// loaded profiles.
+    fn render_meta_editor(&mut self, ctx: &egui::Context) {
+        let (Some(editor), Some(session)) = (&mut self.meta_editor, &self.session) else {
+            return;
+        };
+        let mut open = true;
+        let mut save = false;
+        let mut cancel = false;
+        let mut switch_to = None;
+        let was_baseline = session.baseline_index() == Some(editor.profile_index);
+        let mut is_baseline = was_baseline;
+        let was_normalization = session.diff_normalization();
+        let mut normalization = was_normalization;
+        egui::Window::new("Profile details")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                if session.len() > 1 {
+                    egui::ComboBox::from_id_salt("meta_profile")
+                        .selected_text(&session.profiles()[editor.profile_index].label)
+                        .show_ui(ui, |ui| {
+                            for (index, entry) in session.profiles().iter().enumerate() {
+                                if ui
+                                    .selectable_label(index == editor.profile_index, &entry.label)
+                                    .clicked()
+                                {
+                                    switch_to = Some(index);
+                                }
+                            }
+                        });
+                }
+                egui::Grid::new("meta_fields")
+                    .num_columns(2)
+                    .spacing([8.0, 6.0])
+                    .show(ui, |ui| {
+                        ui.label("Name");
+                        ui.text_edit_singleline(&mut editor.name);
+                        ui.end_row();
+                        ui.label("Description");
+                        ui.text_edit_multiline(&mut editor.description);
+                        ui.end_row();
+                        ui.label("Tags");
+                        ui.add(
+                            egui::TextEdit::singleline(&mut editor.tags)
+                                .hint_text("comma, separated"),
+                        );
+                        ui.end_row();
+                        ui.label("Sampling interval");
+                        ui.add(
+                            egui::TextEdit::singleline(&mut editor.interval)
+                                .hint_text("µs, if not recorded"),
+                        )
+                        .on_hover_text(
+                            "Time between samples. Sample counts are shown as time with it, \
+                             and gaps of many intervals split stitched samples",
+                        );
+                        ui.end_row();
+                        ui.label("Baseline");
+                        ui.checkbox(&mut is_baseline, "Compare against this profile");
+                        ui.end_row();
+                        if session.len() > 1 {
+                            ui.label("Aligned");
+                            ui.label(alignment_label(session, editor.profile_index));
+                            ui.end_row();
+                        }
+                        if session.baseline_index().is_some() {
+                            ui.label("Normalize");
+                            egui::ComboBox::from_id_salt("diff_normalization")
+                                .selected_text(normalization.label())
+                                .show_ui(ui, |ui| {
+                                    for n in DiffNormalization::ALL {
+                                        ui.selectable_value(&mut normalization, n, n.label());
+                                    }
+                                })
+                                .response
+                                .on_hover_text(
+                                    "Scale the baseline by recording length or total so \
+                                     profiles with different sample counts compare fairly",
+                                );
+                            ui.end_row();
+                        }
+                    });
+                ui.horizontal(|ui| {
+                    save = ui.button("Save").clicked();
+                    cancel = ui.button("Cancel").clicked();
+                });
+            });
+        if is_baseline != was_baseline {
+            let index = editor.profile_index;
+            self.set_baseline(is_baseline.then_some(index));
+        } else if normalization != was_normalization {
+            self.set_diff_normalization(normalization);
+        } else if let Some(index) = switch_to {
+            self.meta_editor = MetaEditor::for_profile(session, index);
+        } else if save {
+            let (index, edit) = (editor.profile_index, editor.to_edit());
+            self.meta_editor = None;
+            self.set_profile_meta(index, &edit);
+        } else if cancel || !open {
+            self.meta_editor = None;
+        }
+    }
+
     fn render_context_menu(&mut self, ctx: &egui::Context) {
         let Some(menu) = self.context_menu.clone() else {
             return;
         };
 
-        // Look up span timing for "Copy Timing" and parent info for "Go to Parent"
-        let (timing_text, has_parent) = self
+        // Look up span timing for "Copy Timing" and parent/flow info for the
+        // navigation entries
+        let (timing_text, has_parent, has_flows) = self
             .session
             .as_ref()
             .and_then(|s| s.profiles().first())
-            .and_then(|e| {
-                let span = e.profile.span(menu.frame_id)?;
+            .and_then(|entry| {
+                let span = entry.profile.span(menu.frame_id)?;
                 let timing = format!(
                     "{} (self: {})",
-                    format_duration(span.duration()),
-                    format_duration(span.self_value),
+                    format_span_value(&entry.profile.meta, span.duration(), &self.duration_format),
+                    format_span_value(&entry.profile.meta, span.self_value, &self.duration_format),
                 );
-                Some((timing, span.parent.is_some()))
+                let has_flows = !entry.profile.flows_for_span(span.id).is_empty();
+                Some((timing, span.parent.is_some(), has_flows))
             })
             .unwrap_or_default();
 
@@ -2475,7 +5112,7 @@ impl FlameApp {
                 egui::Frame::popup(ui.style()).show(ui, |ui| {
                     ui.set_min_width(180.0);
                     ui.label(
-                        egui::RichText::new(&menu.span_name)
+                        egui::RichText::new(menu.span_name.as_str())
                             .strong()
                             .size(FONT_BODY),
                     );
@@ -2484,7 +5121,7 @@ impl FlameApp {
                     }
                     ui.separator();
                     if ui.button("Copy Name").clicked() {
-                        ui.ctx().copy_text(menu.span_name.clone());
+                        ui.ctx().copy_text(menu.span_name.to_string());
                         self.context_menu = None;
                     }
                     if ui.button("Copy Timing").clicked() {
@@ -2492,16 +5129,61 @@ impl FlameApp {
                             .copy_text(format!("{}: {}", menu.span_name, timing_text));
                         self.context_menu = None;
                     }
+                    if ui.button("Copy as Markdown").clicked() {
+                        ui.ctx().copy_text(self.evidence_markdown(&[menu.frame_id]));
+                        self.context_menu = None;
+                    }
+                    let bookmarked = self.saved_state.bookmarks.contains(&menu.frame_id);
+                    let bookmark_label = if bookmarked {
+                        "★ Remove Bookmark"
+                    } else {
+                        "☆ Bookmark"
+                    };
+                    if ui.button(bookmark_label).clicked() {
+                        self.saved_state.toggle_bookmark(menu.frame_id);
+                        self.state_gen += 1;
+                        self.context_menu = None;
+                    }
+                    if !self.saved_state.bookmarks.is_empty()
+                        && ui
+                            .button(format!(
+                                "Copy Bookmarks as Markdown ({})",
+                                self.saved_state.bookmarks.len()
+                            ))
+                            .clicked()
+                    {
+                        let bookmarks = self.saved_state.bookmarks.clone();
+                        ui.ctx().copy_text(self.evidence_markdown(&bookmarks));
+                        self.context_menu = None;
+                    }
+                    let pin = PinnedSpan {
+                        profile: 0,
+                        span: menu.frame_id,
+                    };
+                    let pin_label = if self.saved_state.pins.contains(&pin) {
+                        "Unpin"
+                    } else {
+                        "📌 Pin for Comparison"
+                    };
+                    if ui.button(pin_label).clicked() {
+                        self.saved_state.toggle_pin(pin);
+                        self.state_gen += 1;
+                        self.context_menu = None;
+                    }
                     if ui.button("Zoom to Span").clicked() {
                         self.animate_to(menu.zoom_start, menu.zoom_end);
                         self.context_menu = None;
                     }
                     if ui.button("Find Similar").clicked() {
-                        self.search_query = menu.span_name.clone();
+                        self.selection.search = menu.span_name.to_string();
                         self.context_menu = None;
                     }
                     if has_parent && ui.button("⬆ Go to Parent").clicked() {
-                        self.navigate_to_parent(menu.frame_id, menu.lane_index);
+                        self.navigate_from(menu.frame_id, menu.lane_index, Navigation::Parent);
+                        self.context_menu = None;
+                    }
+                    if has_flows && ui.button("➜ Follow Flow").clicked() {
+                        self.navigate_from(menu.frame_id, menu.lane_index, Navigation::FollowFlow);
                         self.context_menu = None;
                     }
                 });
@@ -2521,78 +5203,127 @@ impl FlameApp {
         }
     }
 
-    /// Navigate to the parent of the given span, selecting it.
-    fn navigate_to_parent(&mut self, frame_id: u64, lane_index: usize) {
-        let Some(session) = &self.session else { return };
-        let Some(entry) = session.profiles().first() else {
+    /// `span_ids` and the session's annotations as a Markdown snippet for
+    /// review comments, linking to the host's permalink if it set one.
+    fn evidence_markdown(&self, span_ids: &[u64]) -> String {
+        let Some(session) = &self.session else {
+            return String::new();
+        };
+        flame_cat_core::markdown::evidence_markdown(
+            session,
+            span_ids,
+            &self.saved_state.annotations,
+            crate::permalink().as_deref(),
+            &self.duration_format,
+        )
+    }
+
+    /// Update the selected span, keeping the shared selection state in step.
+    fn set_selected_span(&mut self, span: Option<SelectedSpan>) {
+        self.selection.select(span.as_ref().map(|s| s.frame_id));
+        self.selected_span = span;
+    }
+
+    fn set_hovered_span(&mut self, span: Option<SelectedSpan>) {
+        self.selection.hover(span.as_ref().map(|s| s.frame_id));
+        self.hovered_span = span;
+    }
+
+    /// Move the selection through the call tree, staying in the same lane.
+    fn navigate_selection(&mut self, navigation: Navigation) {
+        if let Some(sel) = &self.selected_span {
+            self.navigate_from(sel.frame_id, sel.lane_index, navigation);
+        }
+    }
+
+    /// Select the span one `navigation` step away from `frame_id`, if any.
+    /// The selection moves to the target's thread lane when it has one.
+    fn navigate_from(&mut self, frame_id: u64, lane_index: usize, navigation: Navigation) {
+        let Some(session) = self.session.clone() else {
             return;
         };
-        let Some(span) = entry.profile.span(frame_id) else {
+        let Some(entry) = session.profiles().first() else {
             return;
         };
-        let Some(parent_id) = span.parent else { return };
-        let Some(parent) = entry.profile.span(parent_id) else {
+        let previous = self.selection.selected();
+        self.selection.select(Some(frame_id));
+        let Some(span) = self.selection.navigate(&entry.profile, navigation) else {
+            self.selection.select(previous);
             return;
         };
+        let lane_index = entry
+            .profile
+            .thread_of_span(span.id)
+            .and_then(|tid| {
+                self.lanes
+                    .iter()
+                    .position(|l| matches!(l.kind, LaneKind::Thread(id) if id == tid))
+            })
+            .unwrap_or(lane_index);
         self.selected_span = Some(SelectedSpan {
-            name: parent.name.to_string(),
-            frame_id: parent_id,
+            name: span.name.clone(),
+            frame_id: span.id,
             lane_index,
-            start_us: parent.start,
-            end_us: parent.end,
+            start_us: span.start,
+            end_us: span.end,
         });
+        self.reveal_selection = true;
         self.invalidate_commands();
     }
 
-    /// Navigate to the first child of the selected span.
-    fn navigate_to_first_child(&mut self) {
-        let Some(sel) = self.selected_span.clone() else {
-            return;
-        };
-        let Some(session) = &self.session else { return };
-        let Some(entry) = session.profiles().first() else {
+    /// Scroll the selected span's lane into view and, in the time-order
+    /// view, pan (or zoom out, if it is wider than the view) to the span.
+    fn reveal_selected_span(&mut self, canvas_height: f32) {
+        let Some((frame_id, lane_index)) = self
+            .selected_span
+            .as_ref()
+            .map(|s| (s.frame_id, s.lane_index))
+        else {
             return;
         };
-        let children = entry.profile.children(Some(sel.frame_id));
-        if let Some(child) = children.first() {
-            self.selected_span = Some(SelectedSpan {
-                name: child.name.to_string(),
-                frame_id: child.id,
-                lane_index: sel.lane_index,
-                start_us: child.start,
-                end_us: child.end,
-            });
-            self.invalidate_commands();
+        if let Some(lane) = self.lanes.get(lane_index).filter(|l| l.visible) {
+            let lane_top: f32 = self.lanes[..lane_index]
+                .iter()
+                .filter(|l| l.visible)
+                .map(|l| l.height + 1.0)
+                .sum();
+            let lane_bottom = lane_top + lane.height.min(canvas_height);
+            if lane_top < self.scroll_y {
+                self.scroll_y = lane_top;
+            } else if lane_bottom > self.scroll_y + canvas_height {
+                self.scroll_y = lane_bottom - canvas_height;
+            }
         }
-    }
 
-    /// Navigate to the next or previous sibling of the selected span.
-    fn navigate_to_sibling(&mut self, forward: bool) {
-        let Some(sel) = self.selected_span.clone() else {
+        if self.view_type != crate::ViewType::TimeOrder {
+            return;
+        }
+        let Some(session) = self.session.clone() else {
             return;
         };
-        let Some(session) = &self.session else { return };
         let Some(entry) = session.profiles().first() else {
             return;
         };
-        let siblings = entry.profile.siblings(sel.frame_id);
-        let pos = siblings.iter().position(|s| s.id == sel.frame_id);
-        let next = pos.and_then(|p| {
-            if forward {
-                siblings.get(p + 1)
-            } else {
-                p.checked_sub(1).and_then(|i| siblings.get(i))
-            }
-        });
-        if let Some(sib) = next {
-            self.selected_span = Some(SelectedSpan {
-                name: sib.name.to_string(),
-                frame_id: sib.id,
-                lane_index: sel.lane_index,
-                start_us: sib.start,
-                end_us: sib.end,
-            });
-            self.invalidate_commands();
+        let Some(span) = entry.profile.span(frame_id) else {
+            return;
+        };
+        let session_start = session.start_time();
+        let duration = session.end_time() - session_start;
+        if duration <= 0.0 {
+            return;
+        }
+        let lo = (entry.to_session_time(span.start) - session_start) / duration;
+        let hi = (entry.to_session_time(span.end) - session_start) / duration;
+        if lo >= self.view_start && hi <= self.view_end {
+            return;
+        }
+        let view_span = self.view_end - self.view_start;
+        if hi - lo <= view_span {
+            let start = ((lo + hi - view_span) / 2.0).clamp(0.0, 1.0 - view_span);
+            self.animate_to(start, start + view_span);
+        } else {
+            let pad = (hi - lo) * 0.05;
+            self.animate_to(lo - pad, hi + pad);
         }
     }
 
@@ -2601,27 +5332,37 @@ impl FlameApp {
         if self.selected_span.is_none() {
             return;
         }
-        let go_parent = ui.input(|i| i.key_pressed(egui::Key::OpenBracket));
-        let go_child = ui.input(|i| i.key_pressed(egui::Key::CloseBracket));
-        let go_prev = ui.input(|i| i.key_pressed(egui::Key::OpenBracket) && i.modifiers.shift);
-        let go_next = ui.input(|i| i.key_pressed(egui::Key::CloseBracket) && i.modifiers.shift);
+        let alt_key = |i: &egui::InputState, key| i.modifiers.alt && i.key_pressed(key);
+        let go_parent =
+            ui.input(|i| i.key_pressed(egui::Key::OpenBracket) || alt_key(i, egui::Key::ArrowUp));
+        let go_child = ui
+            .input(|i| i.key_pressed(egui::Key::CloseBracket) || alt_key(i, egui::Key::ArrowDown));
+        let go_prev = ui.input(|i| {
+            (i.key_pressed(egui::Key::OpenBracket) && i.modifiers.shift)
+                || alt_key(i, egui::Key::ArrowLeft)
+        });
+        let go_next = ui.input(|i| {
+            (i.key_pressed(egui::Key::CloseBracket) && i.modifiers.shift)
+                || alt_key(i, egui::Key::ArrowRight)
+        });
+        let follow_flow = ui.input(|i| i.key_pressed(egui::Key::F) && i.modifiers.shift_only());
 
         if go_prev {
-            self.navigate_to_sibling(false);
+            self.navigate_selection(Navigation::PrevSibling);
         } else if go_next {
-            self.navigate_to_sibling(true);
+            self.navigate_selection(Navigation::NextSibling);
         } else if go_parent {
-            if let Some(sel) = self.selected_span.clone() {
-                self.navigate_to_parent(sel.frame_id, sel.lane_index);
-            }
+            self.navigate_selection(Navigation::Parent);
         } else if go_child {
-            self.navigate_to_first_child();
+            self.navigate_selection(Navigation::FirstChild);
+        } else if follow_flow {
+            self.navigate_selection(Navigation::FollowFlow);
         }
     }
 
     /// Handle search result navigation with Enter / Shift+Enter.
     fn handle_search_navigation(&mut self, ui: &egui::Ui) {
-        if self.search_query.is_empty() {
+        if self.selection.search.is_empty() {
             return;
         }
         let enter = ui.input(|i| i.key_pressed(egui::Key::Enter));
@@ -2632,86 +5373,101 @@ impl FlameApp {
         self.advance_search_result(!shift);
     }
 
+    /// Threads of the visible thread lanes; search only looks at these.
+    fn visible_thread_ids(&self) -> Vec<u32> {
+        self.lanes
+            .iter()
+            .filter(|l| l.visible)
+            .filter_map(|l| match l.kind {
+                LaneKind::Thread(tid) => Some(tid),
+                _ => None,
+            })
+            .collect()
+    }
+
     /// Count how many spans match the current search query.
     fn count_search_matches(&self) -> usize {
-        let Some(session) = &self.session else {
+        let Some(entry) = self.session.as_ref().and_then(|s| s.profiles().first()) else {
             return 0;
         };
-        let Some(entry) = session.profiles().first() else {
+        let threads = self.visible_thread_ids();
+        if threads.is_empty() {
             return 0;
+        }
+        self.selection.matches(&entry.profile, &threads).len()
+    }
+
+    /// Match counts per profile when the session holds several; picking a
+    /// profile centers the view on its earliest match.
+    fn render_session_matches(&mut self, ui: &mut egui::Ui) {
+        let Some(session) = self.session.clone() else {
+            return;
         };
-        let query_lower = self.search_query.to_lowercase();
-        let mut count = 0;
-        for lane in &self.lanes {
-            if !lane.visible {
-                continue;
-            }
-            if let LaneKind::Thread(tid) = &lane.kind {
-                for thread in &entry.profile.threads {
-                    if thread.id == *tid {
-                        count += thread
-                            .spans
-                            .iter()
-                            .filter(|s| s.name.to_lowercase().contains(&query_lower))
-                            .count();
-                    }
+        if session.len() < 2 {
+            return;
+        }
+        let matches = self.selection.session_matches(&session);
+        if matches.is_empty() {
+            return;
+        }
+        let text = if matches.len() == 1 {
+            "in 1 profile".to_string()
+        } else {
+            format!("in {} profiles", matches.len())
+        };
+        let mut jump = None;
+        ui.menu_button(egui::RichText::new(text).size(FONT_CAPTION), |ui| {
+            for found in &matches {
+                if ui
+                    .button(format!("{} · {}", found.label, found.count))
+                    .on_hover_text("Show the first match")
+                    .clicked()
+                {
+                    jump = Some(found.first_match_us);
+                    ui.close();
                 }
             }
+        });
+        let duration = session.duration();
+        if let Some(at) = jump.filter(|_| duration > 0.0) {
+            let width = self.view_end - self.view_start;
+            let center = (at - session.start_time()) / duration;
+            self.view_start = (center - width / 2.0).clamp(0.0, 1.0 - width);
+            self.view_end = self.view_start + width;
+            self.push_zoom();
+            self.invalidate_commands();
         }
-        count
     }
 
     /// Advance to the next (forward=true) or previous (forward=false) search result.
     fn advance_search_result(&mut self, forward: bool) {
-        let Some(session) = &self.session else { return };
+        let Some(session) = self.session.clone() else {
+            return;
+        };
         let Some(entry) = session.profiles().first() else {
             return;
         };
-
-        let query_lower = self.search_query.to_lowercase();
-        let mut matches: Vec<(u64, &str, usize, f64, f64)> = Vec::new();
-
-        for (lane_idx, lane) in self.lanes.iter().enumerate() {
-            if !lane.visible {
-                continue;
-            }
-            if let LaneKind::Thread(tid) = &lane.kind {
-                for thread in &entry.profile.threads {
-                    if thread.id == *tid {
-                        for span in &thread.spans {
-                            if span.name.to_lowercase().contains(&query_lower) {
-                                matches.push((span.id, &span.name, lane_idx, span.start, span.end));
-                            }
-                        }
-                    }
-                }
-            }
-        }
-
-        if matches.is_empty() {
+        let threads = self.visible_thread_ids();
+        if threads.is_empty() {
             return;
         }
-
-        matches.sort_by(|a, b| a.3.partial_cmp(&b.3).unwrap_or(std::cmp::Ordering::Equal));
-
-        let current_id = self.selected_span.as_ref().map(|s| s.frame_id);
-        let current_pos = current_id.and_then(|id| matches.iter().position(|m| m.0 == id));
-
-        let next_idx = match (current_pos, forward) {
-            (Some(pos), true) => (pos + 1) % matches.len(),
-            (Some(pos), false) => (pos + matches.len() - 1) % matches.len(),
-            (None, true) => 0,
-            (None, false) => matches.len() - 1,
+        let Some(span) = self.selection.next_match(&entry.profile, &threads, forward) else {
+            return;
         };
-
-        let (id, name, lane_idx, start, end) = matches[next_idx];
+        let tid = entry.profile.thread_of_span(span.id);
+        let lane_index = self
+            .lanes
+            .iter()
+            .position(|l| Some(&l.kind) == tid.map(LaneKind::Thread).as_ref())
+            .unwrap_or(0);
         self.selected_span = Some(SelectedSpan {
-            name: name.to_string(),
-            frame_id: id,
-            lane_index: lane_idx,
-            start_us: start,
-            end_us: end,
+            name: span.name.clone(),
+            frame_id: span.id,
+            lane_index,
+            start_us: span.start,
+            end_us: span.end,
         });
+        self.reveal_selection = true;
         self.invalidate_commands();
     }
 }
@@ -2727,18 +5483,72 @@ impl eframe::App for FlameApp {
             lock.take()
         };
         if let Some(data) = pending {
-            self.load_profile(&data);
-            self.loading = false;
-            // Apply hash-based view type preset after first profile load
-            if let Some(vt) = self.pending_initial_view_type.take() {
-                self.view_type = vt;
-                self.invalidate_commands();
-            }
+            #[cfg(not(target_arch = "wasm32"))]
+            self.load_profile(ctx, data, FormatHint::default());
+            #[cfg(target_arch = "wasm32")]
+            self.load_profile(ctx, &data, &FormatHint::default());
         }
+        #[cfg(not(target_arch = "wasm32"))]
+        self.poll_load_job();
 
         // Process commands from JS API
+        let mut live_updated = false;
         for cmd in crate::drain_commands() {
             match cmd {
+                crate::AppCommand::OpenLiveSession => {
+                    self.open_live_session();
+                }
+                crate::AppCommand::AppendLiveEvents(batch) => {
+                    if let Some(stream) = &mut self.live {
+                        match stream.push_batch(&batch) {
+                            Ok(()) => live_updated = true,
+                            Err(e) => self.error = Some(format!("Live batch rejected: {e}")),
+                        }
+                    }
+                }
+                crate::AppCommand::CloseLiveSession => {
+                    self.close_live_session();
+                }
+                crate::AppCommand::ApplySourceMap { script_url, map } => {
+                    self.apply_source_map(script_url.as_deref(), &map);
+                }
+                crate::AppCommand::LoadProfileWithHint { data, hint } => {
+                    #[cfg(not(target_arch = "wasm32"))]
+                    self.load_profile(ctx, data, hint);
+                    #[cfg(target_arch = "wasm32")]
+                    self.load_profile(ctx, &data, &hint);
+                }
+                crate::AppCommand::AttachJitMap { profile_index, map } => {
+                    self.attach_jit_map(profile_index, &map);
+                }
+                crate::AppCommand::ImportAnnotations {
+                    profile_index,
+                    data,
+                } => self.import_annotations(profile_index, &data),
+                crate::AppCommand::SetProfileMeta {
+                    profile_index,
+                    edit,
+                } => {
+                    self.set_profile_meta(profile_index, &edit);
+                }
+                crate::AppCommand::SetDiffNormalization(normalization) => {
+                    self.set_diff_normalization(normalization);
+                }
+                crate::AppCommand::SetNameRewrites(rules) => {
+                    self.set_name_rewrites(rules);
+                }
+                crate::AppCommand::SetClassRules(rules) => {
+                    self.set_class_rules(rules);
+                }
+                crate::AppCommand::SetBaseline(index) => {
+                    self.set_baseline(index);
+                }
+                crate::AppCommand::SetThreadSort(sort) => {
+                    self.update_thread_order(|order| order.sort = sort);
+                }
+                crate::AppCommand::PinThread { name, pinned } => {
+                    self.update_thread_order(|order| order.set_pinned(&name, pinned));
+                }
                 crate::AppCommand::SetTheme(mode) => {
                     self.theme_mode = mode;
                     match mode {
@@ -2752,7 +5562,7 @@ impl eframe::App for FlameApp {
                     self.invalidate_commands();
                 }
                 crate::AppCommand::SetSearch(query) => {
-                    self.search_query = query;
+                    self.selection.search = query;
                     self.invalidate_commands();
                 }
                 crate::AppCommand::ResetZoom => {
@@ -2762,6 +5572,7 @@ impl eframe::App for FlameApp {
                     self.push_zoom();
                     self.invalidate_commands();
                 }
+                crate::AppCommand::SetPlayback(rate) => self.set_playback(rate),
                 crate::AppCommand::SetViewport(start, end) => {
                     self.view_start = start.max(0.0);
                     self.view_end = end.min(1.0);
@@ -2786,11 +5597,13 @@ impl eframe::App for FlameApp {
                         let lane = self.lanes.remove(from);
                         self.lanes.insert(to, lane);
                         // Also reorder cached commands
-                        if self.lane_commands.len() == len {
+                        if self.lane_commands.len() == len && self.lane_keys.len() == len {
                             let cmds = self.lane_commands.remove(from);
                             self.lane_commands.insert(to, cmds);
+                            let key = self.lane_keys.remove(from);
+                            self.lane_keys.insert(to, key);
                         } else {
-                            self.invalidate_commands();
+                            self.reset_lane_cache();
                         }
                     }
                 }
@@ -2805,11 +5618,12 @@ impl eframe::App for FlameApp {
                                 if let RenderCommand::DrawRect {
                                     label: Some(label),
                                     frame_id: Some(id),
+                                    span_name,
                                     ..
                                 } = cmd
                                 {
                                     if *id == fid {
-                                        Some(label.clone())
+                                        Some(span_name.as_ref().unwrap_or(label).clone())
                                     } else {
                                         None
                                     }
@@ -2818,16 +5632,16 @@ impl eframe::App for FlameApp {
                                 }
                             });
                         if let Some(name) = label {
-                            self.selected_span = Some(SelectedSpan {
-                                name: name.to_string(),
+                            self.set_selected_span(Some(SelectedSpan {
+                                name,
                                 frame_id: fid,
                                 lane_index: 0,
                                 start_us: 0.0,
                                 end_us: 0.0,
-                            });
+                            }));
                         }
                     } else {
-                        self.selected_span = None;
+                        self.set_selected_span(None);
                     }
                 }
                 crate::AppCommand::SetViewType(vt) => {
@@ -2853,49 +5667,118 @@ impl eframe::App for FlameApp {
                     }
                 }
                 crate::AppCommand::SetColorMode(mode) => {
-                    self.color_mode = match mode.as_str() {
-                        "by_name" => renderer::ColorMode::ByName,
-                        "by_depth" | "theme" => renderer::ColorMode::Theme,
-                        _ => renderer::ColorMode::ByName,
-                    };
+                    self.color_mode = renderer::ColorMode::from_name(&mode);
+                    self.state_gen += 1;
+                }
+                crate::AppCommand::SaveRegion(name) => self.save_region(&name),
+                crate::AppCommand::GotoRegion(name) => self.goto_region(&name),
+                crate::AppCommand::ToggleBookmark(frame_id) => {
+                    self.saved_state.toggle_bookmark(frame_id);
+                    self.state_gen += 1;
+                }
+                crate::AppCommand::TogglePin(pin) => {
+                    self.saved_state.toggle_pin(pin);
                     self.state_gen += 1;
                 }
+                crate::AppCommand::SetCounterBudget { name, limit } => {
+                    self.set_counter_budget(name, limit);
+                }
+                crate::AppCommand::SetHeatScale(scale) => {
+                    self.heat_scale = scale;
+                    self.state_gen += 1;
+                }
+                crate::AppCommand::SetCategoryFilter(category) => {
+                    self.category_filter = category;
+                    self.invalidate_commands();
+                }
+                crate::AppCommand::SetBinaryFilter(binary) => {
+                    self.binary_filter = binary;
+                    self.invalidate_commands();
+                }
+                crate::AppCommand::SetClassFilter(class) => {
+                    self.class_filter = class;
+                    self.invalidate_commands();
+                }
+                crate::AppCommand::SetHideKernel(hide) => {
+                    self.hide_kernel = hide;
+                    self.invalidate_commands();
+                }
+                crate::AppCommand::SetExpandInlined(expand) => {
+                    self.expand_inlined = expand;
+                    self.invalidate_commands();
+                }
+                crate::AppCommand::SetRenderLimits(limits) => {
+                    self.render_limits = limits;
+                    self.reset_lane_cache();
+                }
+                crate::AppCommand::SetDurationFormat(format) => {
+                    self.duration_format = format;
+                    crate::set_shared_duration_format(format);
+                    self.reset_lane_cache();
+                }
+                crate::AppCommand::SetLabelTemplate(view, template) => {
+                    match template {
+                        Some(template) => self.label_templates.insert(view, template),
+                        None => self.label_templates.remove(&view),
+                    };
+                    self.reset_lane_cache();
+                }
                 crate::AppCommand::NavigateToParent => {
-                    if let Some(sel) = self.selected_span.clone() {
-                        self.navigate_to_parent(sel.frame_id, sel.lane_index);
-                    }
+                    self.navigate_selection(Navigation::Parent);
                 }
                 crate::AppCommand::NavigateToChild => {
-                    self.navigate_to_first_child();
+                    self.navigate_selection(Navigation::FirstChild);
                 }
                 crate::AppCommand::NavigateToNextSibling => {
-                    self.navigate_to_sibling(true);
+                    self.navigate_selection(Navigation::NextSibling);
                 }
                 crate::AppCommand::NavigateToPrevSibling => {
-                    self.navigate_to_sibling(false);
+                    self.navigate_selection(Navigation::PrevSibling);
+                }
+                crate::AppCommand::FollowFlow => {
+                    self.navigate_selection(Navigation::FollowFlow);
                 }
                 crate::AppCommand::NextSearchResult => {
                     // Simulate Enter search navigation
-                    if !self.search_query.is_empty() {
+                    if !self.selection.search.is_empty() {
                         self.advance_search_result(true);
                     }
                 }
                 crate::AppCommand::PrevSearchResult => {
-                    if !self.search_query.is_empty() {
+                    if !self.selection.search.is_empty() {
                         self.advance_search_result(false);
                     }
                 }
             }
         }
+        // Batches only extend the stream; the profile is rebuilt from it at
+        // most every `LIVE_REFRESH_S`.
+        self.live_pending |= live_updated;
+        if self.live_pending {
+            let now = ctx.input(|i| i.time);
+            let wait = self.live_refreshed_at + LIVE_REFRESH_S - now;
+            if wait <= 0.0 {
+                self.refresh_live();
+                self.live_pending = false;
+                self.live_refreshed_at = now;
+            } else {
+                ctx.request_repaint_after(std::time::Duration::from_secs_f64(wait));
+            }
+        }
 
         self.tick_animation(ctx);
+        self.tick_playback(ctx);
 
         self.render_toolbar(ctx);
         self.render_status_bar(ctx);
         self.render_detail_panel(ctx);
+        self.render_pin_tray(ctx);
         self.render_sidebar(ctx);
+        self.render_legend_panel(ctx);
+        self.render_annotations_panel(ctx);
         self.render_central_panel(ctx);
         self.render_help_overlay(ctx);
+        self.render_meta_editor(ctx);
         self.render_context_menu(ctx);
         self.handle_file_drop(ctx);
 
@@ -2920,6 +5803,7 @@ impl eframe::App for FlameApp {
 
 impl FlameApp {
     fn emit_snapshot(&self) {
+        crate::set_session_state(self.session_state());
         let profile = self.session.as_ref().map(|s| {
             let profiles = s.profiles();
             let thread_count: usize = profiles.iter().map(|p| p.profile.threads.len()).sum();
@@ -2930,17 +5814,51 @@ impl FlameApp {
                 .sum();
             crate::ProfileSnapshot {
                 name: profiles.first().map(|p| p.label.clone()),
+                baseline: s.baseline_index(),
+                diff_normalization: s.diff_normalization().name(),
+                description: profiles
+                    .first()
+                    .and_then(|p| p.profile.meta.description.as_ref())
+                    .map(ToString::to_string),
+                tags: profiles
+                    .first()
+                    .map(|p| {
+                        p.profile
+                            .meta
+                            .tags
+                            .iter()
+                            .map(ToString::to_string)
+                            .collect()
+                    })
+                    .unwrap_or_default(),
                 format: profiles
                     .first()
                     .map(|p| format!("{:?}", p.profile.meta.source_format))
                     .unwrap_or_default(),
+                sample_interval_us: profiles
+                    .first()
+                    .and_then(|p| p.profile.meta.sample_interval_us),
                 duration_us: s.duration(),
                 start_time: s.start_time(),
                 end_time: s.end_time(),
                 span_count,
                 thread_count,
+                truncated_spans: profiles
+                    .iter()
+                    .map(|p| p.profile.meta.truncated_spans)
+                    .sum(),
+                repaired_spans: profiles
+                    .iter()
+                    .map(|p| p.profile.meta.repairs.total())
+                    .sum(),
+                limits_hit: profiles.iter().fold(Default::default(), |mut hit, p| {
+                    hit += p.profile.meta.limits_hit;
+                    hit
+                }),
+                partial: profiles.iter().find_map(|p| p.profile.meta.partial.clone()),
             }
         });
+        let thread_order = self.session.as_ref().map(|s| s.thread_order());
         let lanes = self
             .lanes
             .iter()
@@ -2948,17 +5866,21 @@ impl FlameApp {
                 name: l.name.clone(),
                 kind: match &l.kind {
                     LaneKind::Thread(_) => "thread",
+                    LaneKind::Tasks(_) => "tasks",
+                    LaneKind::Utilization(_) => "utilization",
                     LaneKind::Counter(_) => "counter",
                     LaneKind::AsyncSpans => "async",
                     LaneKind::Markers => "markers",
                     LaneKind::CpuSamples => "cpu_samples",
                     LaneKind::FrameTrack => "frame_track",
                     LaneKind::ObjectTrack => "object_track",
-                }
-                .to_string(),
+                },
                 height: l.height,
                 visible: l.visible,
                 span_count: l.span_count,
+                pinned: self
+                    .lane_thread_name(&l.kind)
+                    .is_some_and(|name| thread_order.is_some_and(|o| o.is_pinned(&name))),
             })
             .collect();
         let viewport = crate::ViewportSnapshot {
@@ -2986,78 +5908,103 @@ impl FlameApp {
                 start_us: s.start_us,
                 end_us: s.end_us,
             });
-        let theme = match self.theme_mode {
-            ThemeMode::Dark => "dark",
-            ThemeMode::Light => "light",
-        }
-        .to_string();
         crate::write_snapshot(crate::StateSnapshot {
             profile,
             lanes,
             viewport,
             selected,
             hovered,
-            search: self.search_query.clone(),
-            theme,
+            search: self.selection.search.clone(),
+            theme: self.theme_mode.name(),
             view_type: self.view_type,
-            color_mode: match self.color_mode {
-                renderer::ColorMode::ByName => "by_name",
-                renderer::ColorMode::Theme => "by_depth",
-            }
-            .to_string(),
+            color_mode: self.color_mode.name(),
+            heat_scale: self.heat_scale.name(),
+            thread_sort: thread_order
+                .map_or(ThreadSort::default(), |o| o.sort)
+                .name(),
             can_go_back: self.zoom_history_pos > 0,
             can_go_forward: self.zoom_history_pos + 1 < self.zoom_history.len(),
+            playback_rate: self.playback_rate,
+            playing: self.playing,
         });
     }
 }
 
-/// Find the label for a span by its frame_id in the render commands.
-fn find_span_label(cmds: &[RenderCommand], frame_id: u64) -> Option<String> {
+/// Find the name of a span by its frame_id in the render commands.
+/// Cubic Bézier from `from` to `to` with horizontal control points and a
+/// small arrowhead at `to`.
+/// How session profile `index` was placed on the timeline, for the profile
+/// details window.
+fn alignment_label(session: &Session, index: usize) -> String {
+    let Some(alignment) = session
+        .profiles()
+        .get(index)
+        .and_then(|e| e.alignment.as_ref())
+    else {
+        return "Unknown".into();
+    };
+    match alignment {
+        Alignment::Origin => "Defines the session clock".into(),
+        Alignment::SameClock => "Same clock".into(),
+        Alignment::NavigationStart => "By navigationStart".into(),
+        Alignment::Marker { name, reference } => {
+            let label = session
+                .profiles()
+                .get(*reference)
+                .map_or("?", |e| e.label.as_str());
+            format!("By marker {name} in {label}")
+        }
+        Alignment::SessionStart => "By start time".into(),
+        Alignment::Drift => "By drift correction".into(),
+    }
+}
+
+fn draw_link_arrow(
+    painter: &egui::Painter,
+    from: egui::Pos2,
+    to: egui::Pos2,
+    line_color: egui::Color32,
+    head_color: egui::Color32,
+) {
+    let dx = (to.x - from.x).abs() * 0.4;
+    let p2 = egui::pos2(from.x + dx, from.y);
+    let p3 = egui::pos2(to.x - dx, to.y);
+    painter.add(egui::epaint::CubicBezierShape::from_points_stroke(
+        [from, p2, p3, to],
+        false,
+        egui::Color32::TRANSPARENT,
+        egui::Stroke::new(1.0, line_color),
+    ));
+
+    let arrow_size = 4.0_f32;
+    let dir = (to - p3).normalized();
+    let perp = egui::vec2(-dir.y, dir.x);
+    let left = to - dir * arrow_size + perp * arrow_size * 0.5;
+    let right = to - dir * arrow_size - perp * arrow_size * 0.5;
+    painter.add(egui::epaint::PathShape::convex_polygon(
+        vec![to, left, right],
+        head_color,
+        egui::Stroke::NONE,
+    ));
+}
+
+fn find_span_label(cmds: &[RenderCommand], frame_id: u64) -> Option<SharedStr> {
     for cmd in cmds {
         if let RenderCommand::DrawRect {
             label: Some(label),
             frame_id: Some(fid),
+            span_name,
             ..
         } = cmd
         {
             if *fid == frame_id {
-                return Some(label.to_string());
+                return Some(span_name.as_ref().unwrap_or(label).clone());
             }
         }
     }
     None
 }
 
-/// Compute a "nice" tick interval for the time axis.
-/// Returns interval in µs.
-fn nice_tick_interval(visible_duration_us: f64, target_ticks: usize) -> f64 {
-    let raw = visible_duration_us / target_ticks as f64;
-    // Find the nearest "nice" number: 1, 2, 5, 10, 20, 50, ...
-    let mag = 10.0_f64.powf(raw.log10().floor());
-    let residual = raw / mag;
-    let nice = if residual <= 1.5 {
-        1.0
-    } else if residual <= 3.5 {
-        2.0
-    } else if residual <= 7.5 {
-        5.0
-    } else {
-        10.0
-    };
-    nice * mag
-}
-
-/// Format a tick label (time in µs relative to session start) with appropriate units.
-fn format_tick_label(us: f64, interval: f64) -> String {
-    if interval >= 1_000_000.0 {
-        format!("{:.1}s", us / 1_000_000.0)
-    } else if interval >= 1_000.0 {
-        format!("{:.1}ms", us / 1_000.0)
-    } else {
-        format!("{:.0}µs", us)
-    }
-}
-
 fn synthesize_frame_timings(
     profile: &flame_cat_protocol::VisualProfile,
 ) -> Vec<flame_cat_protocol::FrameTiming> {
@@ -3158,7 +6105,9 @@ async fn pick_file_wasm() -> Result<Vec<u8>, String> {
         .dyn_into()
         .map_err(|_| "not an input")?;
     input.set_type("file");
-    input.set_accept(".json,.cpuprofile,.speedscope,.pprof,.tracy");
+    input.set_accept(
+        ".json,.cpuprofile,.speedscope,.pprof,.tracy,.perfetto-trace,.pftrace,.data,.jfr,.nettrace,.csv,.xml,.html,.txt,.trace,.pb,.gz",
+    );
 
     // Create a promise that resolves when a file is selected
     let (tx, rx) = futures_channel::oneshot::channel::<Vec<u8>>();
@@ -3201,3 +6150,30 @@ async fn pick_file_wasm() -> Result<Vec<u8>, String> {
 
     rx.await.map_err(|_| "file pick cancelled".to_string())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bottom_anchored_zoom_changes_the_stack_key() {
+        let mut lane = LaneState {
+            kind: LaneKind::Thread(1),
+            name: "main".into(),
+            height: 200.0,
+            visible: true,
+            span_count: 0,
+            row_scale: 1.0,
+            depth_scroll: 100.0,
+        };
+        let bottom = |l: &LaneState| l.height / l.row_scale + l.depth_scroll;
+        let before = (bottom(&lane), lane.row_scale, lane.depth_scroll);
+
+        lane.zoom_stack(1000.0, 0.0, 2.0, lane.height);
+        // The stack's bottom edge stays put, so the render height alone
+        // would leave the lane's stale transform cached.
+        assert_eq!(bottom(&lane), before.0);
+        assert_eq!((lane.row_scale, lane.depth_scroll), (2.0, 200.0));
+        assert_ne!((lane.row_scale, lane.depth_scroll), (before.1, before.2));
+    }
+}
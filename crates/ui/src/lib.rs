@@ -4,17 +4,7 @@ mod theme;
 
 pub use app::FlameApp;
 
-/// Active visualization mode.
-#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize)]
-#[serde(rename_all = "snake_case")]
-pub enum ViewType {
-    #[default]
-    TimeOrder,
-    LeftHeavy,
-    Sandwich,
-    Ranked,
-    Icicle,
-}
+pub use flame_cat_protocol::ViewType;
 
 /// Commands that can be sent from JS to the egui app.
 #[derive(Debug)]
@@ -23,6 +13,8 @@ pub enum AppCommand {
     SetSearch(String),
     ResetZoom,
     SetViewport(f64, f64),
+    /// Replay the trace at this multiple of real time; `None` pauses.
+    SetPlayback(Option<f64>),
     SetLaneVisibility(usize, bool),
     SetLaneHeight(usize, f32),
     ReorderLanes(usize, usize),
@@ -31,12 +23,86 @@ pub enum AppCommand {
     NavigateBack,
     NavigateForward,
     SetColorMode(String),
+    SetHeatScale(flame_cat_protocol::HeatScale),
+    /// Save the current viewport under a name.
+    SaveRegion(String),
+    GotoRegion(String),
+    /// Bookmark a span for Markdown export, or remove its bookmark.
+    ToggleBookmark(u64),
+    /// Pin a span to the comparison tray, or unpin it.
+    TogglePin(flame_cat_core::model::PinnedSpan),
+    /// Shade a counter's samples that break a limit; `None` clears it.
+    SetCounterBudget {
+        name: String,
+        limit: Option<f64>,
+    },
+    SetRenderLimits(flame_cat_protocol::RenderLimits),
+    SetDurationFormat(flame_cat_protocol::DurationFormat),
+    /// Label spans of a view with a template; `None` restores plain names.
+    SetLabelTemplate(ViewType, Option<String>),
+    /// Dim every span whose color key differs from this one; `None` clears.
+    SetCategoryFilter(Option<String>),
+    /// Dim every span that did not run in the binary of this name; `None`
+    /// clears.
+    SetBinaryFilter(Option<String>),
+    /// Dim every span not of this code class; `None` clears.
+    SetClassFilter(Option<flame_cat_protocol::CodeClass>),
+    /// Leave kernel frames out of thread lanes.
+    SetHideKernel(bool),
+    /// Draw functions inlined into a span on rows of their own.
+    SetExpandInlined(bool),
     NavigateToParent,
     NavigateToChild,
     NavigateToNextSibling,
     NavigateToPrevSibling,
+    /// Select the span at the other end of the selection's first flow arrow.
+    FollowFlow,
     NextSearchResult,
     PrevSearchResult,
+    /// Start a live session fed by `AppendLiveEvents`.
+    OpenLiveSession,
+    /// A batch of Chrome trace events (JSON) for the live session.
+    AppendLiveEvents(Vec<u8>),
+    CloseLiveSession,
+    /// Apply a source map (JSON) to the frames of a script. Without a script
+    /// URL, the map's own `file` is used.
+    ApplySourceMap {
+        script_url: Option<String>,
+        map: Vec<u8>,
+    },
+    /// Load a profile, parsing it as `hint` says instead of by detection
+    /// alone.
+    LoadProfileWithHint {
+        data: Vec<u8>,
+        hint: flame_cat_core::parsers::FormatHint,
+    },
+    /// Resolve JIT frames of a session profile with a perf map
+    /// (`/tmp/perf-<pid>.map`).
+    AttachJitMap {
+        profile_index: usize,
+        map: Vec<u8>,
+    },
+    /// Add notes from an annotation sidecar (JSON) of a session profile.
+    ImportAnnotations {
+        profile_index: usize,
+        data: Vec<u8>,
+    },
+    /// Rename, describe or tag a session profile.
+    SetProfileMeta {
+        profile_index: usize,
+        edit: flame_cat_protocol::ProfileMetaEdit,
+    },
+    /// Compare against this session profile; `None` clears the baseline.
+    SetBaseline(Option<usize>),
+    SetDiffNormalization(flame_cat_core::model::DiffNormalization),
+    SetNameRewrites(Vec<flame_cat_core::transform::NameRewrite>),
+    SetClassRules(flame_cat_protocol::ClassRules),
+    SetThreadSort(flame_cat_core::model::ThreadSort),
+    /// Pin a thread (by display name) above the sorted ones, or unpin it.
+    PinThread {
+        name: String,
+        pinned: bool,
+    },
 }
 
 /// Global command queue drained by the app each frame.
@@ -65,31 +131,55 @@ pub struct StateSnapshot {
     pub selected: Option<SelectedSpanSnapshot>,
     pub hovered: Option<SelectedSpanSnapshot>,
     pub search: String,
-    pub theme: String,
+    pub theme: &'static str,
     pub view_type: ViewType,
-    pub color_mode: String,
+    pub color_mode: &'static str,
+    pub heat_scale: &'static str,
+    pub thread_sort: &'static str,
     pub can_go_back: bool,
     pub can_go_forward: bool,
+    /// Replay speed, as a multiple of real time.
+    pub playback_rate: f64,
+    /// Replay is sliding the viewport along the trace.
+    pub playing: bool,
 }
 
 #[derive(serde::Serialize)]
 pub struct ProfileSnapshot {
     pub name: Option<String>,
+    /// Session index of the baseline profile.
+    pub baseline: Option<usize>,
+    /// How the baseline is scaled for comparison (see `DiffNormalization`).
+    pub diff_normalization: &'static str,
+    pub description: Option<String>,
+    pub tags: Vec<String>,
     pub format: String,
+    /// Time between samples (µs), recorded or set by the user.
+    pub sample_interval_us: Option<f64>,
     pub duration_us: f64,
     pub start_time: f64,
     pub end_time: f64,
     pub span_count: usize,
     pub thread_count: usize,
+    /// Spans closed at the trace end because their end event was missing.
+    pub truncated_spans: usize,
+    /// Malformed spans repaired before layout (see `SpanRepairs`).
+    pub repaired_spans: usize,
+    /// Input left out by the parse limits; nonzero means a partial load.
+    pub limits_hit: flame_cat_protocol::LimitsHit,
+    /// Where a truncated or corrupt input stopped parsing.
+    pub partial: Option<flame_cat_protocol::PartialParse>,
 }
 
 #[derive(serde::Serialize)]
 pub struct LaneSnapshot {
     pub name: String,
-    pub kind: String,
+    pub kind: &'static str,
     pub height: f32,
     pub visible: bool,
     pub span_count: usize,
+    /// Thread lane pinned above the sorted threads.
+    pub pinned: bool,
 }
 
 #[derive(Default, serde::Serialize)]
@@ -101,7 +191,7 @@ pub struct ViewportSnapshot {
 
 #[derive(serde::Serialize)]
 pub struct SelectedSpanSnapshot {
-    pub name: String,
+    pub name: flame_cat_protocol::SharedStr,
     pub frame_id: u64,
     pub lane_index: usize,
     pub start_us: f64,
@@ -119,13 +209,26 @@ static STATE: std::sync::Mutex<StateSnapshot> = std::sync::Mutex::new(StateSnaps
     selected: None,
     hovered: None,
     search: String::new(),
-    theme: String::new(),
+    theme: "",
     view_type: ViewType::TimeOrder,
-    color_mode: String::new(),
+    color_mode: "",
+    heat_scale: "",
+    thread_sort: "",
     can_go_back: false,
     can_go_forward: false,
+    playback_rate: 1.0,
+    playing: false,
 });
 
+/// `getState` JSON, re-serialized only after the snapshot changes. The
+/// buffer is kept across calls so per-frame polling from JS neither
+/// re-encodes nor reallocates.
+#[cfg(target_arch = "wasm32")]
+static STATE_JSON: std::sync::Mutex<Vec<u8>> = std::sync::Mutex::new(Vec::new());
+
+/// Set by `write_snapshot`; cleared once `STATE_JSON` is rebuilt.
+static STATE_JSON_STALE: std::sync::atomic::AtomicBool = std::sync::atomic::AtomicBool::new(true);
+
 /// Cached serialized profile for export (set when profile loads).
 static PROFILE_JSON: std::sync::Mutex<Option<String>> = std::sync::Mutex::new(None);
 
@@ -135,13 +238,74 @@ pub fn set_profile_json(json: Option<String>) {
     }
 }
 
+/// Current session, shared with the app for `saveSessionState`.
+static SESSION: std::sync::Mutex<Option<std::sync::Arc<flame_cat_core::model::Session>>> =
+    std::sync::Mutex::new(None);
+
+pub fn set_session(session: Option<std::sync::Arc<flame_cat_core::model::Session>>) {
+    if let Ok(mut s) = SESSION.lock() {
+        *s = session;
+    }
+}
+
+/// View state mirrored from the app alongside each state snapshot.
+static SESSION_STATE: std::sync::Mutex<Option<flame_cat_core::model::SessionState>> =
+    std::sync::Mutex::new(None);
+
+pub fn set_session_state(state: flame_cat_core::model::SessionState) {
+    if let Ok(mut s) = SESSION_STATE.lock() {
+        *s = Some(state);
+    }
+}
+
+/// Duration format mirrored from the app for the JSON and Markdown exports.
+static DURATION_FORMAT: std::sync::Mutex<flame_cat_protocol::DurationFormat> =
+    std::sync::Mutex::new(flame_cat_protocol::DurationFormat {
+        decimals: None,
+        unit: flame_cat_protocol::DurationUnit::Auto,
+        separators: flame_cat_protocol::Separators::Plain,
+    });
+
+pub fn set_shared_duration_format(format: flame_cat_protocol::DurationFormat) {
+    if let Ok(mut f) = DURATION_FORMAT.lock() {
+        *f = format;
+    }
+}
+
+pub fn duration_format() -> flame_cat_protocol::DurationFormat {
+    DURATION_FORMAT.lock().map(|f| *f).unwrap_or_default()
+}
+
+/// Category budgets set by the host, reported by `getInsights`.
+#[cfg(target_arch = "wasm32")]
+static CATEGORY_BUDGETS: std::sync::Mutex<Vec<flame_cat_core::analysis::CategoryBudget>> =
+    std::sync::Mutex::new(Vec::new());
+
+/// Where the host saved the session, linked from Markdown exports.
+static PERMALINK: std::sync::Mutex<Option<String>> = std::sync::Mutex::new(None);
+
+pub fn permalink() -> Option<String> {
+    PERMALINK.lock().ok().and_then(|p| p.clone())
+}
+
 /// Cached lane render commands for SVG export (set on demand by app).
 static LANE_COMMANDS: std::sync::Mutex<Vec<Vec<flame_cat_protocol::RenderCommand>>> =
     std::sync::Mutex::new(Vec::new());
 
-pub fn set_lane_commands(cmds: Vec<Vec<flame_cat_protocol::RenderCommand>>) {
+/// Canvas width the cached lane commands were laid out for.
+static LANE_COMMANDS_WIDTH: std::sync::Mutex<f32> = std::sync::Mutex::new(0.0);
+
+/// Mirror the app's per-lane commands into the export cache, reusing the
+/// cache's existing lane buffers rather than reallocating them.
+pub fn set_lane_commands(cmds: &[Vec<flame_cat_protocol::RenderCommand>], canvas_width: f32) {
+    if let Ok(mut w) = LANE_COMMANDS_WIDTH.lock() {
+        *w = canvas_width;
+    }
     if let Ok(mut lc) = LANE_COMMANDS.lock() {
-        *lc = cmds;
+        lc.resize_with(cmds.len(), Vec::new);
+        for (dst, src) in lc.iter_mut().zip(cmds) {
+            dst.clone_from(src);
+        }
     }
 }
 
@@ -158,6 +322,7 @@ pub fn write_snapshot(snap: StateSnapshot) {
             || s.lanes.len() != snap.lanes.len()
             || std::mem::discriminant(&s.view_type) != std::mem::discriminant(&snap.view_type);
         *s = snap;
+        STATE_JSON_STALE.store(true, std::sync::atomic::Ordering::Relaxed);
         changed
     } else {
         false
@@ -266,6 +431,236 @@ pub fn load_profile(data: &[u8]) -> Result<(), JsValue> {
     Ok(())
 }
 
+/// Like `loadProfile`, with a JSON `FormatHint`
+/// (`{format?, split_profiles?, recover?}`) naming the format, asking for a
+/// multi-profile file (speedscope) to be loaded as separate session
+/// profiles, or for a truncated Chrome trace to load up to the damage.
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen(js_name = "loadProfileWithHint")]
+pub fn load_profile_with_hint(data: &[u8], hint: &str) -> Result<(), JsValue> {
+    let hint: flame_cat_core::parsers::FormatHint = serde_json::from_str(hint)
+        .map_err(|e| JsValue::from_str(&format!("invalid format hint: {e}")))?;
+    push_command(AppCommand::LoadProfileWithHint {
+        data: data.to_vec(),
+        hint,
+    });
+    request_repaint();
+    Ok(())
+}
+
+/// Fetch a profile from `url` and load it. `headers` is an optional object
+/// of extra request headers, e.g. `{ Authorization: "Bearer …" }`. Gzip
+/// artifacts such as `trace.json.gz` are decompressed. The promise resolves
+/// once the body is queued for parsing and rejects on network/HTTP errors.
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen(js_name = "loadProfileFromUrl")]
+pub fn load_profile_from_url(url: String, headers: Option<js_sys::Object>) -> js_sys::Promise {
+    wasm_bindgen_futures::future_to_promise(async move {
+        let mut request = flame_cat_core::remote::RemoteRequest::new(url);
+        if let Some(headers) = headers {
+            for entry in js_sys::Object::entries(&headers).iter() {
+                let pair = js_sys::Array::from(&entry);
+                if let (Some(name), Some(value)) =
+                    (pair.get(0).as_string(), pair.get(1).as_string())
+                {
+                    request = request.header(name, value);
+                }
+            }
+        }
+        let body = fetch_remote(&request)
+            .await
+            .map_err(|e| JsValue::from_str(&e))?;
+        load_profile(&body)?;
+        Ok(JsValue::UNDEFINED)
+    })
+}
+
+/// Browser counterpart of `flame_cat_core::remote::fetch`.
+#[cfg(target_arch = "wasm32")]
+async fn fetch_remote(request: &flame_cat_core::remote::RemoteRequest) -> Result<Vec<u8>, String> {
+    use wasm_bindgen_futures::JsFuture;
+
+    let window = web_sys::window().ok_or("no window")?;
+    let headers = web_sys::Headers::new().map_err(|e| format!("{e:?}"))?;
+    for (name, value) in &request.headers {
+        headers.set(name, value).map_err(|e| format!("{e:?}"))?;
+    }
+    let init = web_sys::RequestInit::new();
+    init.set_method("GET");
+    init.set_headers(&headers);
+    let req = web_sys::Request::new_with_str_and_init(&request.url, &init)
+        .map_err(|e| format!("{e:?}"))?;
+    let resp: web_sys::Response = JsFuture::from(window.fetch_with_request(&req))
+        .await
+        .map_err(|e| format!("{e:?}"))?
+        .dyn_into()
+        .map_err(|_| "not a Response")?;
+    if !resp.ok() {
+        return Err(format!("HTTP {} from {}", resp.status(), request.url));
+    }
+    // The browser has already undone any Content-Encoding; `decode_body`
+    // only inflates bodies that are still compressed (e.g. `.gz` files).
+    let encoding = resp.headers().get("content-encoding").ok().flatten();
+    let buf = JsFuture::from(resp.array_buffer().map_err(|e| format!("{e:?}"))?)
+        .await
+        .map_err(|e| format!("{e:?}"))?;
+    let body = js_sys::Uint8Array::new(&buf).to_vec();
+    let limits = flame_cat_core::parsers::Limits::default();
+    flame_cat_core::remote::decode_body(body, encoding.as_deref(), &limits)
+        .map_err(|e| e.to_string())
+}
+
+/// Apply a source map to the loaded profile, rewriting minified names and
+/// positions of frames from `script_url` (matched exactly or by trailing
+/// path, so `"bundle.js"` works). Pass `undefined` to use the map's `file`.
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen(js_name = "applySourceMap")]
+pub fn apply_source_map(script_url: Option<String>, map_bytes: &[u8]) {
+    push_command(AppCommand::ApplySourceMap {
+        script_url,
+        map: map_bytes.to_vec(),
+    });
+    request_repaint();
+}
+
+/// Fetch a source map from `map_url` and apply it like `applySourceMap`.
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen(js_name = "applySourceMapFromUrl")]
+pub fn apply_source_map_from_url(script_url: Option<String>, map_url: String) -> js_sys::Promise {
+    wasm_bindgen_futures::future_to_promise(async move {
+        let request = flame_cat_core::remote::RemoteRequest::new(map_url);
+        let map = fetch_remote(&request)
+            .await
+            .map_err(|e| JsValue::from_str(&e))?;
+        apply_source_map(script_url, &map);
+        Ok(JsValue::UNDEFINED)
+    })
+}
+
+/// Resolve address-only JIT frames (Node, Deno, JVM) of the session profile
+/// at `profile_index` using the contents of a `/tmp/perf-<pid>.map` file.
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen(js_name = "attachJitMap")]
+pub fn attach_jit_map(profile_index: usize, bytes: &[u8]) {
+    push_command(AppCommand::AttachJitMap {
+        profile_index,
+        map: bytes.to_vec(),
+    });
+    request_repaint();
+}
+
+/// Add the notes of an annotation sidecar to the session, placing its
+/// timestamps and span names within the profile at `profile_index`. Notes
+/// are stored in saved session files.
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen(js_name = "importAnnotations")]
+pub fn import_annotations(profile_index: usize, bytes: &[u8]) {
+    push_command(AppCommand::ImportAnnotations {
+        profile_index,
+        data: bytes.to_vec(),
+    });
+    request_repaint();
+}
+
+/// The session's notes as JSON `Annotation[]`, in the order they were added.
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen(js_name = "listAnnotations")]
+pub fn list_annotations() -> String {
+    let annotations = SESSION_STATE
+        .lock()
+        .ok()
+        .and_then(|s| s.as_ref().map(|s| s.annotations.clone()))
+        .unwrap_or_default();
+    serde_json::to_string(&annotations).unwrap_or_else(|_| "[]".into())
+}
+
+/// Rename, describe or tag the session profile at `profile_index` from a
+/// JSON `{name?, description?, tags?}`. Omitted fields are kept; blank ones
+/// are cleared.
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen(js_name = "setProfileMeta")]
+pub fn set_profile_meta(profile_index: usize, edit: &str) -> Result<(), JsValue> {
+    let edit: flame_cat_protocol::ProfileMetaEdit = serde_json::from_str(edit)
+        .map_err(|e| JsValue::from_str(&format!("invalid profile metadata: {e}")))?;
+    push_command(AppCommand::SetProfileMeta {
+        profile_index,
+        edit,
+    });
+    request_repaint();
+    Ok(())
+}
+
+/// Compare ranked rows and tooltips against the session profile at
+/// `profile_index`; `null` clears the baseline.
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen(js_name = "setBaseline")]
+pub fn set_baseline(profile_index: Option<usize>) {
+    push_command(AppCommand::SetBaseline(profile_index));
+    request_repaint();
+}
+
+/// Rewrite span names with JSON `[{pattern, replacement}]` regex rules,
+/// replacing earlier rules; `[]` restores the names as loaded. Saved with
+/// the session.
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen(js_name = "setNameRewrites")]
+pub fn set_name_rewrites(rules: &str) -> Result<(), JsValue> {
+    let rules: Vec<flame_cat_core::transform::NameRewrite> = serde_json::from_str(rules)
+        .map_err(|e| JsValue::from_str(&format!("invalid rewrite rules: {e}")))?;
+    flame_cat_core::transform::NameRewriter::new(&rules)
+        .map_err(|e| JsValue::from_str(&e.to_string()))?;
+    push_command(AppCommand::SetNameRewrites(rules));
+    request_repaint();
+    Ok(())
+}
+
+/// Sort spans into first-party, dependency and runtime code with JSON
+/// `{first_party, dependencies, runtime}` lists of name, source path or
+/// binary prefixes, checked before the built-in rules. Saved with the
+/// session.
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen(js_name = "setClassRules")]
+pub fn set_class_rules(rules: &str) -> Result<(), JsValue> {
+    let rules: flame_cat_protocol::ClassRules = serde_json::from_str(rules)
+        .map_err(|e| JsValue::from_str(&format!("invalid class rules: {e}")))?;
+    push_command(AppCommand::SetClassRules(rules));
+    request_repaint();
+    Ok(())
+}
+
+/// Scale the baseline before comparing: `"per_sample"` (as recorded),
+/// `"per_second"` (by recording length) or `"per_total"` (by share of each
+/// profile's total).
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen(js_name = "setDiffNormalization")]
+pub fn set_diff_normalization(normalization: &str) -> Result<(), JsValue> {
+    let normalization = flame_cat_core::model::DiffNormalization::from_name(normalization)
+        .ok_or_else(|| JsValue::from_str(&format!("unknown normalization: {normalization}")))?;
+    push_command(AppCommand::SetDiffNormalization(normalization));
+    request_repaint();
+    Ok(())
+}
+
+/// Order thread lanes by `"span_count"`, `"name"`, `"busy_time"` or
+/// `"first_activity"`. Pinned threads stay on top.
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen(js_name = "setThreadSort")]
+pub fn set_thread_sort(sort: &str) -> Result<(), JsValue> {
+    let sort = flame_cat_core::model::ThreadSort::from_name(sort)
+        .ok_or_else(|| JsValue::from_str(&format!("unknown thread sort: {sort}")))?;
+    push_command(AppCommand::SetThreadSort(sort));
+    request_repaint();
+    Ok(())
+}
+
+/// Pin a thread lane (by thread name) to the top, or unpin it.
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen(js_name = "pinThread")]
+pub fn pin_thread(name: String, pinned: bool) {
+    push_command(AppCommand::PinThread { name, pinned });
+    request_repaint();
+}
+
 #[cfg(target_arch = "wasm32")]
 #[wasm_bindgen(js_name = "setTheme")]
 pub fn set_theme(mode: &str) -> Result<(), JsValue> {
@@ -300,6 +695,15 @@ pub fn set_viewport(start: f64, end: f64) {
     request_repaint();
 }
 
+/// Replay the trace: slide the viewport along at `rate` times real time
+/// (the playhead is its right edge). A rate of 0 or less pauses.
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen(js_name = "setPlayback")]
+pub fn set_playback(rate: f64) {
+    push_command(AppCommand::SetPlayback((rate > 0.0).then_some(rate)));
+    request_repaint();
+}
+
 #[cfg(target_arch = "wasm32")]
 #[wasm_bindgen(js_name = "setLaneVisibility")]
 pub fn set_lane_visibility(index: usize, visible: bool) {
@@ -324,17 +728,11 @@ pub fn reorder_lanes(from_index: usize, to_index: usize) {
 #[cfg(target_arch = "wasm32")]
 #[wasm_bindgen(js_name = "setViewType")]
 pub fn set_view_type(view_type: &str) -> Result<(), JsValue> {
-    let vt =
-        match view_type {
-            "time_order" => ViewType::TimeOrder,
-            "left_heavy" => ViewType::LeftHeavy,
-            "sandwich" => ViewType::Sandwich,
-            "ranked" => ViewType::Ranked,
-            "icicle" => ViewType::Icicle,
-            _ => return Err(JsValue::from_str(
-                "view_type must be 'time_order', 'left_heavy', 'sandwich', 'ranked', or 'icicle'",
-            )),
-        };
+    let vt = ViewType::from_name(view_type).ok_or_else(|| {
+        JsValue::from_str(
+            "view_type must be 'time_order', 'left_heavy', 'sandwich', 'ranked', or 'icicle'",
+        )
+    })?;
     push_command(AppCommand::SetViewType(vt));
     request_repaint();
     Ok(())
@@ -354,6 +752,33 @@ pub fn navigate_forward() {
     request_repaint();
 }
 
+/// Start following a live recording. The current profile is replaced by
+/// one built from the event batches passed to `appendLiveEvents`, typically
+/// forwarded from a WebSocket opened on the JS side.
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen(js_name = "openLiveSession")]
+pub fn open_live_session() {
+    push_command(AppCommand::OpenLiveSession);
+    request_repaint();
+}
+
+/// Append a batch of Chrome trace events (an event array or an object with
+/// `traceEvents`) to the live session. Batches may split `B`/`E` pairs.
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen(js_name = "appendLiveEvents")]
+pub fn append_live_events(batch: &[u8]) {
+    push_command(AppCommand::AppendLiveEvents(batch.to_vec()));
+    request_repaint();
+}
+
+/// Stop following the live recording, keeping what was received.
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen(js_name = "closeLiveSession")]
+pub fn close_live_session() {
+    push_command(AppCommand::CloseLiveSession);
+    request_repaint();
+}
+
 #[cfg(target_arch = "wasm32")]
 #[wasm_bindgen(js_name = "setColorMode")]
 pub fn set_color_mode(mode: &str) {
@@ -361,6 +786,641 @@ pub fn set_color_mode(mode: &str) {
     request_repaint();
 }
 
+/// What the duration and self-time color modes compare a span with:
+/// `"siblings"` (spans with the same parent) or `"absolute"` (1µs–1s).
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen(js_name = "setHeatScale")]
+pub fn set_heat_scale(scale: &str) -> Result<(), JsValue> {
+    let scale = flame_cat_protocol::HeatScale::from_name(scale)
+        .ok_or_else(|| JsValue::from_str("heat scale must be 'siblings' or 'absolute'"))?;
+    push_command(AppCommand::SetHeatScale(scale));
+    request_repaint();
+    Ok(())
+}
+
+/// Save the current viewport as a named region, replacing any region of
+/// the same name. Regions are stored in saved session files.
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen(js_name = "saveRegion")]
+pub fn save_region(name: &str) -> Result<(), JsValue> {
+    if name.trim().is_empty() {
+        return Err(JsValue::from_str("region name must not be empty"));
+    }
+    push_command(AppCommand::SaveRegion(name.to_string()));
+    request_repaint();
+    Ok(())
+}
+
+/// Saved regions as a JSON array of `{ name, view_start, view_end }`.
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen(js_name = "listRegions")]
+pub fn list_regions() -> String {
+    let regions = SESSION_STATE
+        .lock()
+        .ok()
+        .and_then(|s| s.as_ref().map(|s| s.regions.clone()))
+        .unwrap_or_default();
+    serde_json::to_string(&regions).unwrap_or_else(|_| "[]".into())
+}
+
+/// Animate the viewport to a saved region. Unknown names are ignored.
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen(js_name = "gotoRegion")]
+pub fn goto_region(name: &str) {
+    push_command(AppCommand::GotoRegion(name.to_string()));
+    request_repaint();
+}
+
+/// Bookmark span `frame_id` for `exportMarkdown`, or remove its bookmark.
+/// Bookmarks are stored in saved session files.
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen(js_name = "toggleBookmark")]
+pub fn toggle_bookmark(frame_id: u64) {
+    push_command(AppCommand::ToggleBookmark(frame_id));
+    request_repaint();
+}
+
+/// Bookmarked span ids, oldest first, as a JSON array.
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen(js_name = "listBookmarks")]
+pub fn list_bookmarks() -> String {
+    let bookmarks = SESSION_STATE
+        .lock()
+        .ok()
+        .and_then(|s| s.as_ref().map(|s| s.bookmarks.clone()))
+        .unwrap_or_default();
+    serde_json::to_string(&bookmarks).unwrap_or_else(|_| "[]".into())
+}
+
+/// Where each session profile sits on the timeline as JSON `SessionInfo`,
+/// including how its offset was chosen (same clock, `navigationStart`, a
+/// shared marker, …).
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen(js_name = "getSessionInfo")]
+pub fn get_session_info() -> Result<String, JsValue> {
+    let session = SESSION
+        .lock()
+        .ok()
+        .and_then(|s| s.clone())
+        .ok_or_else(|| JsValue::from_str("no profile loaded"))?;
+    serde_json::to_string(&session.info()).map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
+/// Pin span `frame_id` of profile `profile` to the comparison tray, or
+/// unpin it. Pins are stored in saved session files.
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen(js_name = "togglePin")]
+pub fn toggle_pin(profile: usize, frame_id: u64) {
+    push_command(AppCommand::TogglePin(flame_cat_core::model::PinnedSpan {
+        profile,
+        span: frame_id,
+    }));
+    request_repaint();
+}
+
+/// Pinned spans side by side as a JSON array of `PinnedRow`, in pin order.
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen(js_name = "getPinnedSpans")]
+pub fn get_pinned_spans() -> Result<String, JsValue> {
+    let session = SESSION
+        .lock()
+        .ok()
+        .and_then(|s| s.clone())
+        .ok_or_else(|| JsValue::from_str("no profile loaded"))?;
+    let rows = SESSION_STATE
+        .lock()
+        .ok()
+        .and_then(|s| s.as_ref().map(|s| s.pinned_spans(&session)))
+        .unwrap_or_default();
+    serde_json::to_string(&rows).map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
+/// Link Markdown exports to where the host saved the session; `null`
+/// leaves the link out.
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen(js_name = "setPermalink")]
+pub fn set_permalink(url: Option<String>) {
+    if let Ok(mut p) = PERMALINK.lock() {
+        *p = url;
+    }
+}
+
+/// Bookmarked spans and annotations as a Markdown snippet for review
+/// comments: a table of function, `file:line`, duration and share of the
+/// total, the notes, and the permalink.
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen(js_name = "exportMarkdown")]
+pub fn export_markdown() -> Result<String, JsValue> {
+    let session = SESSION
+        .lock()
+        .ok()
+        .and_then(|s| s.clone())
+        .ok_or_else(|| JsValue::from_str("no profile loaded"))?;
+    let state = SESSION_STATE
+        .lock()
+        .ok()
+        .and_then(|s| s.clone())
+        .unwrap_or_default();
+    Ok(flame_cat_core::markdown::evidence_markdown(
+        &session,
+        &state.bookmarks,
+        &state.annotations,
+        permalink().as_deref(),
+        &duration_format(),
+    ))
+}
+
+/// How the primary profile differs from the baseline (or the second
+/// profile) as a `"markdown"` or `"html"` report: biggest function changes,
+/// new and removed functions, counters and frame timing.
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen(js_name = "exportComparison")]
+pub fn export_comparison(format: &str) -> Result<String, JsValue> {
+    use flame_cat_core::comparison::{Comparison, ReportFormat};
+    let report = ReportFormat::from_name(format)
+        .ok_or_else(|| JsValue::from_str(&format!("unknown report format: {format}")))?;
+    let session = SESSION
+        .lock()
+        .ok()
+        .and_then(|s| s.clone())
+        .ok_or_else(|| JsValue::from_str("no profile loaded"))?;
+    let comparison = Comparison::new(&session).map_err(|e| JsValue::from_str(&e.to_string()))?;
+    Ok(comparison.render(report, &duration_format()))
+}
+
+/// Shade the samples of counter `name` that break `limit`: above it, or
+/// below it for battery charge. `null` clears the budget. Budgets are
+/// stored in saved session files.
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen(js_name = "setCounterBudget")]
+pub fn set_counter_budget(name: &str, limit: Option<f64>) -> Result<(), JsValue> {
+    if limit.is_some_and(|l| !l.is_finite()) {
+        return Err(JsValue::from_str("counter budget must be a finite number"));
+    }
+    push_command(AppCommand::SetCounterBudget {
+        name: name.to_string(),
+        limit,
+    });
+    request_repaint();
+    Ok(())
+}
+
+/// Tune the depth and command-count guards from a JSON `RenderLimits`;
+/// omitted fields take their defaults.
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen(js_name = "setRenderLimits")]
+pub fn set_render_limits(limits: &str) -> Result<(), JsValue> {
+    let limits: flame_cat_protocol::RenderLimits = serde_json::from_str(limits)
+        .map_err(|e| JsValue::from_str(&format!("invalid render limits: {e}")))?;
+    push_command(AppCommand::SetRenderLimits(limits));
+    request_repaint();
+    Ok(())
+}
+
+/// Set how durations are written everywhere from a JSON `DurationFormat`
+/// (`{decimals, unit, separators}`); omitted fields take their defaults.
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen(js_name = "setDurationFormat")]
+pub fn set_duration_format(format: &str) -> Result<(), JsValue> {
+    let format: flame_cat_protocol::DurationFormat = serde_json::from_str(format)
+        .map_err(|e| JsValue::from_str(&format!("invalid duration format: {e}")))?;
+    push_command(AppCommand::SetDurationFormat(format));
+    request_repaint();
+    Ok(())
+}
+
+/// Label spans of `view_type` with a template such as `"{name} ({dur})"`;
+/// `null` restores plain names. See `LabelTemplate` for the fields.
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen(js_name = "setLabelTemplate")]
+pub fn set_label_template(view_type: &str, template: Option<String>) -> Result<(), JsValue> {
+    let vt = ViewType::from_name(view_type)
+        .ok_or_else(|| JsValue::from_str(&format!("unknown view type {view_type:?}")))?;
+    if let Some(template) = &template {
+        flame_cat_core::views::labels::LabelTemplate::parse(template)
+            .map_err(|e| JsValue::from_str(&e.to_string()))?;
+    }
+    push_command(AppCommand::SetLabelTemplate(vt, template));
+    request_repaint();
+    Ok(())
+}
+
+/// Color keys of the session profile at `profile_index`, heaviest first, as
+/// JSON `[{category, hue, color, total_time, span_count}]`. `color` is the
+/// `#rrggbb` fill for the current theme.
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen(js_name = "getCategoryLegend")]
+pub fn get_category_legend(profile_index: usize) -> Result<String, JsValue> {
+    #[derive(serde::Serialize)]
+    struct Entry {
+        #[serde(flatten)]
+        entry: flame_cat_core::legend::LegendEntry,
+        color: String,
+    }
+
+    let session = SESSION
+        .lock()
+        .ok()
+        .and_then(|s| s.clone())
+        .ok_or_else(|| JsValue::from_str("no profile loaded"))?;
+    let profile = session
+        .profiles()
+        .get(profile_index)
+        .ok_or_else(|| JsValue::from_str(&format!("no profile at index {profile_index}")))?;
+    let mode = match STATE.lock() {
+        Ok(s) if s.theme == "light" => theme::ThemeMode::Light,
+        _ => theme::ThemeMode::Dark,
+    };
+    let entries: Vec<Entry> = flame_cat_core::legend::category_legend(&profile.profile)
+        .into_iter()
+        .map(|entry| {
+            let c = renderer::category_color(&entry.category, mode);
+            Entry {
+                color: format!("#{:02x}{:02x}{:02x}", c.r(), c.g(), c.b()),
+                entry,
+            }
+        })
+        .collect();
+    serde_json::to_string(&entries).map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
+/// Mapped binaries of the session profile at `profile_index` that spans
+/// ran in, heaviest first, as JSON `[{name, build_id, total_time,
+/// span_count}]`. Empty for formats that record no mappings.
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen(js_name = "getBinaryBreakdown")]
+pub fn get_binary_breakdown(profile_index: usize) -> Result<String, JsValue> {
+    let session = SESSION
+        .lock()
+        .ok()
+        .and_then(|s| s.clone())
+        .ok_or_else(|| JsValue::from_str("no profile loaded"))?;
+    let profile = session
+        .profiles()
+        .get(profile_index)
+        .ok_or_else(|| JsValue::from_str(&format!("no profile at index {profile_index}")))?;
+    let breakdown = flame_cat_core::analysis::breakdown::binary_breakdown(&profile.profile);
+    serde_json::to_string(&breakdown).map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
+/// Time of the session profile at `profile_index` per span category and
+/// per span kind, as JSON `{categories, kinds}` of `[{name, total_time,
+/// self_time, span_count}]`, heaviest self time first.
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen(js_name = "getCategorySummary")]
+pub fn get_category_summary(profile_index: usize) -> Result<String, JsValue> {
+    let session = SESSION
+        .lock()
+        .ok()
+        .and_then(|s| s.clone())
+        .ok_or_else(|| JsValue::from_str("no profile loaded"))?;
+    let profile = session
+        .profiles()
+        .get(profile_index)
+        .ok_or_else(|| JsValue::from_str(&format!("no profile at index {profile_index}")))?;
+    let summary = flame_cat_core::analysis::breakdown::category_summary(&profile.profile);
+    serde_json::to_string(&summary).map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
+/// Span `frame_id` of the session profile at `profile_index` with its
+/// direct children totalled by name, as JSON `{id, name, start, end, depth,
+/// self_time, children}`, `children` being `[{name, total_time, percent,
+/// span_count}]` heaviest first; `undefined` if there is no such span.
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen(js_name = "getSpanInfo")]
+pub fn get_span_info(profile_index: usize, frame_id: u64) -> Result<Option<String>, JsValue> {
+    let session = SESSION
+        .lock()
+        .ok()
+        .and_then(|s| s.clone())
+        .ok_or_else(|| JsValue::from_str("no profile loaded"))?;
+    let profile = session
+        .profiles()
+        .get(profile_index)
+        .ok_or_else(|| JsValue::from_str(&format!("no profile at index {profile_index}")))?;
+    flame_cat_core::analysis::span_info(&profile.profile, frame_id)
+        .map(|info| serde_json::to_string(&info).map_err(|e| JsValue::from_str(&e.to_string())))
+        .transpose()
+}
+
+/// Time of the session profile at `profile_index` per code class under the
+/// session's class rules (see `setClassRules`), as JSON `[{name,
+/// total_time, self_time, span_count}]` for `first_party`, `dependency`
+/// and `runtime` in that order.
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen(js_name = "getClassSummary")]
+pub fn get_class_summary(profile_index: usize) -> Result<String, JsValue> {
+    let session = SESSION
+        .lock()
+        .ok()
+        .and_then(|s| s.clone())
+        .ok_or_else(|| JsValue::from_str("no profile loaded"))?;
+    let profile = session
+        .profiles()
+        .get(profile_index)
+        .ok_or_else(|| JsValue::from_str(&format!("no profile at index {profile_index}")))?;
+    let summary =
+        flame_cat_core::analysis::breakdown::class_summary(&profile.profile, session.class_rules());
+    serde_json::to_string(&summary).map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
+/// Ranked rows of the primary profile, heaviest self time first, as JSON
+/// `[{name, self_time, total_time, count, slowest, baseline, self_label,
+/// total_label}]`. `slowest` lists the longest spans with the name
+/// (`{frame_id, start, duration, thread}`) to jump to; `baseline` holds the
+/// same name's totals in the baseline profile, or null; the labels show each
+/// time with its change from it. `group` is `"name"` (the default),
+/// `"binary"` for one row per mapped binary or `"class"` for one row per
+/// code class; only rows by name have a baseline.
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen(js_name = "getRankedEntries")]
+pub fn get_ranked_entries(group: Option<String>) -> Result<String, JsValue> {
+    use flame_cat_core::views::ranked::{self, Baseline, RankedEntry, RankedGroup, RankedSort};
+
+    #[derive(serde::Serialize)]
+    struct Entry {
+        #[serde(flatten)]
+        entry: RankedEntry,
+        baseline: Option<RankedEntry>,
+        self_label: String,
+        total_label: String,
+    }
+
+    let session = SESSION
+        .lock()
+        .ok()
+        .and_then(|s| s.clone())
+        .ok_or_else(|| JsValue::from_str("no profile loaded"))?;
+    let primary = session
+        .profiles()
+        .first()
+        .ok_or_else(|| JsValue::from_str("no profile loaded"))?;
+    let group = group
+        .map(|name| {
+            RankedGroup::from_name(&name, session.class_rules())
+                .ok_or_else(|| JsValue::from_str(&format!("unknown ranked group {name:?}")))
+        })
+        .transpose()?
+        .unwrap_or(RankedGroup::Name);
+    let meta = &primary.profile.meta;
+    // Baseline totals are kept per span name.
+    let baseline = (group == RankedGroup::Name)
+        .then(|| Baseline::for_session(&session))
+        .flatten();
+    let format = duration_format();
+    let entries: Vec<Entry> =
+        ranked::get_ranked_entries(&primary.profile, group, RankedSort::SelfTime, false)
+            .into_iter()
+            .map(|entry| {
+                let baseline = baseline
+                    .as_ref()
+                    .and_then(|b| b.entry(&entry.name, meta.value_unit));
+                Entry {
+                    self_label: ranked::format_compared(
+                        meta,
+                        entry.self_time,
+                        baseline.as_ref().map(|b| b.self_time),
+                        &format,
+                    ),
+                    total_label: ranked::format_compared(
+                        meta,
+                        entry.total_time,
+                        baseline.as_ref().map(|b| b.total_time),
+                        &format,
+                    ),
+                    entry,
+                    baseline,
+                }
+            })
+            .collect();
+    serde_json::to_string(&entries).map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
+/// Top-level tasks of thread `thread_id` in the primary profile, in start
+/// order, as JSON `[{span_id, name, start, end, duration, dominant_child}]`.
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen(js_name = "getTopLevelTasks")]
+pub fn get_top_level_tasks(thread_id: u32) -> Result<String, JsValue> {
+    let session = SESSION
+        .lock()
+        .ok()
+        .and_then(|s| s.clone())
+        .ok_or_else(|| JsValue::from_str("no profile loaded"))?;
+    let thread = session
+        .profiles()
+        .first()
+        .and_then(|e| e.profile.threads.iter().find(|t| t.id == thread_id))
+        .ok_or_else(|| JsValue::from_str(&format!("no thread with id {thread_id}")))?;
+    let tasks = flame_cat_core::analysis::tasks::top_level_tasks(thread);
+    serde_json::to_string(&tasks).map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
+/// Busy percentage of every thread of the session profile at
+/// `profile_index` in `bucket_us` buckets, then of all threads summed, as
+/// JSON `[{thread_id, counter: {name, unit, samples: [{ts, value}]}}]`.
+/// The total has a null `thread_id`.
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen(js_name = "getThreadUtilization")]
+pub fn get_thread_utilization(profile_index: usize, bucket_us: f64) -> Result<String, JsValue> {
+    if bucket_us.is_nan() || bucket_us <= 0.0 {
+        return Err(JsValue::from_str("bucket_us must be positive"));
+    }
+    let session = SESSION
+        .lock()
+        .ok()
+        .and_then(|s| s.clone())
+        .ok_or_else(|| JsValue::from_str("no profile loaded"))?;
+    let entry = session
+        .profiles()
+        .get(profile_index)
+        .ok_or_else(|| JsValue::from_str(&format!("no profile at index {profile_index}")))?;
+    let tracks =
+        flame_cat_core::analysis::utilization::thread_utilization(&entry.profile, bucket_us);
+    serde_json::to_string(&tracks).map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
+/// Event-loop time of the primary profile per scheduling API, as JSON
+/// `{turns, turn_time, by_api: [{api, total_time, callbacks}]}`.
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen(js_name = "getEventLoopAttribution")]
+pub fn get_event_loop_attribution() -> Result<String, JsValue> {
+    let session = SESSION
+        .lock()
+        .ok()
+        .and_then(|s| s.clone())
+        .ok_or_else(|| JsValue::from_str("no profile loaded"))?;
+    let entry = session
+        .profiles()
+        .first()
+        .ok_or_else(|| JsValue::from_str("no profile loaded"))?;
+    let attribution = flame_cat_core::analysis::initiators::attribute_event_loop(&entry.profile);
+    serde_json::to_string(&attribution).map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
+/// Spans that led to span `frame_id` running, following flow arrows back,
+/// nearest first, as JSON `[{span_id, name, ts, api}]`.
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen(js_name = "getTaskInitiators")]
+pub fn get_task_initiators(frame_id: u64) -> Result<String, JsValue> {
+    let session = SESSION
+        .lock()
+        .ok()
+        .and_then(|s| s.clone())
+        .ok_or_else(|| JsValue::from_str("no profile loaded"))?;
+    let entry = session
+        .profiles()
+        .first()
+        .ok_or_else(|| JsValue::from_str("no profile loaded"))?;
+    let chain = flame_cat_core::analysis::initiators::get_task_initiators(&entry.profile, frame_id);
+    serde_json::to_string(&chain).map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
+/// Set the category budgets `getInsights` checks, from JSON
+/// `[{category, max_percent}]` or a list such as `"js < 50%, layout < 10%"`.
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen(js_name = "setCategoryBudgets")]
+pub fn set_category_budgets(budgets: &str) -> Result<(), JsValue> {
+    let budgets = match serde_json::from_str(budgets) {
+        Ok(budgets) => budgets,
+        Err(_) => flame_cat_core::analysis::parse_budgets(budgets)
+            .map_err(|e| JsValue::from_str(&e.to_string()))?,
+    };
+    if let Ok(mut b) = CATEGORY_BUDGETS.lock() {
+        *b = budgets;
+    }
+    Ok(())
+}
+
+/// Findings about the primary profile, such as leak suspects and broken
+/// category budgets, as JSON `[{kind, title, detail, links}]`.
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen(js_name = "getInsights")]
+pub fn get_insights() -> Result<String, JsValue> {
+    let session = SESSION
+        .lock()
+        .ok()
+        .and_then(|s| s.clone())
+        .ok_or_else(|| JsValue::from_str("no profile loaded"))?;
+    let entry = session
+        .profiles()
+        .first()
+        .ok_or_else(|| JsValue::from_str("no profile loaded"))?;
+    let budgets = CATEGORY_BUDGETS
+        .lock()
+        .map(|b| b.clone())
+        .unwrap_or_default();
+    let insights = flame_cat_core::analysis::insights(&entry.profile, &budgets);
+    serde_json::to_string(&insights).map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
+/// GPU queue spans of the primary profile linked to the CPU submits that
+/// produced them, as JSON `[{submit_span, submit_thread, submit_start,
+/// gpu_span, gpu_thread, gpu_start, latency}]`.
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen(js_name = "getGpuSubmissions")]
+pub fn get_gpu_submissions() -> Result<String, JsValue> {
+    let session = SESSION
+        .lock()
+        .ok()
+        .and_then(|s| s.clone())
+        .ok_or_else(|| JsValue::from_str("no profile loaded"))?;
+    let entry = session
+        .profiles()
+        .first()
+        .ok_or_else(|| JsValue::from_str("no profile loaded"))?;
+    let links = flame_cat_core::analysis::gpu::link_submissions(&entry.profile);
+    serde_json::to_string(&links).map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
+/// The `n` complete stacks of the primary profile with the most self time,
+/// as JSON `HotPath[]` whose `stack` is a folded-stack line.
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen(js_name = "getHotPaths")]
+pub fn get_hot_paths(n: usize) -> Result<String, JsValue> {
+    let session = SESSION
+        .lock()
+        .ok()
+        .and_then(|s| s.clone())
+        .ok_or_else(|| JsValue::from_str("no profile loaded"))?;
+    let entry = session
+        .profiles()
+        .first()
+        .ok_or_else(|| JsValue::from_str("no profile loaded"))?;
+    let paths = flame_cat_core::analysis::hot_paths(&entry.profile, n);
+    serde_json::to_string(&paths).map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
+/// Spans whose name contains `query` (case-insensitively) in every loaded
+/// profile, as JSON `ProfileMatches[]`: one entry per profile with a match,
+/// holding the match count and where the earliest match starts on the
+/// session timeline.
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen(js_name = "searchSession")]
+pub fn search_session(query: &str) -> Result<String, JsValue> {
+    let session = SESSION
+        .lock()
+        .ok()
+        .and_then(|s| s.clone())
+        .ok_or_else(|| JsValue::from_str("no profile loaded"))?;
+    let mut selection = flame_cat_core::model::SelectionState::default();
+    selection.search = query.to_string();
+    let matches = selection.session_matches(&session);
+    serde_json::to_string(&matches).map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
+/// Dim every span whose color key differs from `category`; `null` clears
+/// the filter.
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen(js_name = "setCategoryFilter")]
+pub fn set_category_filter(category: Option<String>) {
+    push_command(AppCommand::SetCategoryFilter(category));
+    request_repaint();
+}
+
+/// Dim every span that did not run in the binary named `binary` (see
+/// `getBinaryBreakdown`); `null` clears the filter.
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen(js_name = "setBinaryFilter")]
+pub fn set_binary_filter(binary: Option<String>) {
+    push_command(AppCommand::SetBinaryFilter(binary));
+    request_repaint();
+}
+
+/// Dim every span not of code class `class` (`"first_party"`,
+/// `"dependency"` or `"runtime"`, see `setClassRules`); `null` clears the
+/// filter.
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen(js_name = "setClassFilter")]
+pub fn set_class_filter(class: Option<String>) -> Result<(), JsValue> {
+    let class = class
+        .map(|name| {
+            flame_cat_protocol::CodeClass::from_name(&name)
+                .ok_or_else(|| JsValue::from_str(&format!("unknown code class {name:?}")))
+        })
+        .transpose()?;
+    push_command(AppCommand::SetClassFilter(class));
+    request_repaint();
+    Ok(())
+}
+
+/// Leave frames that ran in the operating system kernel out of thread
+/// lanes, for perf, eBPF and annotated collapsed-stack profiles.
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen(js_name = "setHideKernel")]
+pub fn set_hide_kernel(hide: bool) {
+    push_command(AppCommand::SetHideKernel(hide));
+    request_repaint();
+}
+
+/// Draw the functions the compiler inlined into a frame on rows of their
+/// own beneath it, instead of folding them into its label.
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen(js_name = "setExpandInlined")]
+pub fn set_expand_inlined(expand: bool) {
+    push_command(AppCommand::SetExpandInlined(expand));
+    request_repaint();
+}
+
 #[cfg(target_arch = "wasm32")]
 #[wasm_bindgen(js_name = "selectSpan")]
 pub fn select_span(frame_id: Option<u64>) {
@@ -396,6 +1456,125 @@ pub fn navigate_to_prev_sibling() {
     request_repaint();
 }
 
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen(js_name = "followFlow")]
+pub fn follow_flow() {
+    push_command(AppCommand::FollowFlow);
+    request_repaint();
+}
+
+/// Spans of thread `thread_id` at `depth` in profile `profile_index` that
+/// overlap `view_start..view_end` (in the profile's own time), as a JSON
+/// array of `RowSpan` ordered by start.
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen(js_name = "getRowSpans")]
+pub fn get_row_spans(
+    profile_index: usize,
+    thread_id: u32,
+    depth: u32,
+    view_start: f64,
+    view_end: f64,
+) -> Result<String, JsValue> {
+    let session = SESSION
+        .lock()
+        .ok()
+        .and_then(|s| s.clone())
+        .ok_or_else(|| JsValue::from_str("no profile loaded"))?;
+    let entry = session
+        .profiles()
+        .get(profile_index)
+        .ok_or_else(|| JsValue::from_str(&format!("no profile at index {profile_index}")))?;
+    let row =
+        flame_cat_core::model::row_spans(&entry.profile, thread_id, depth, view_start, view_end);
+    serde_json::to_string(&row).map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
+/// Function names of the CPU sample running at `ts` (in the profile's own
+/// time) in profile `profile_index`, leaf first, as a JSON array. Empty when
+/// the profile has no CPU samples or nothing was sampled at `ts`.
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen(js_name = "getStackAt")]
+pub fn get_stack_at(profile_index: usize, ts: f64) -> Result<String, JsValue> {
+    let session = SESSION
+        .lock()
+        .ok()
+        .and_then(|s| s.clone())
+        .ok_or_else(|| JsValue::from_str("no profile loaded"))?;
+    let entry = session
+        .profiles()
+        .get(profile_index)
+        .ok_or_else(|| JsValue::from_str(&format!("no profile at index {profile_index}")))?;
+    let stack = entry
+        .profile
+        .cpu_samples
+        .as_ref()
+        .map(|samples| flame_cat_core::views::cpu_samples::stack_at(samples, ts))
+        .unwrap_or_default();
+    serde_json::to_string(&stack).map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
+/// The screenshot on screen at `ts` (in the profile's own time, like
+/// `getStackAt`) as JSON `{ ts, data }`, or `undefined` before the first
+/// one. Lets a filmstrip follow replay.
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen(js_name = "getScreenshotAt")]
+pub fn get_screenshot_at(profile_index: usize, ts: f64) -> Result<Option<String>, JsValue> {
+    let session = SESSION
+        .lock()
+        .ok()
+        .and_then(|s| s.clone())
+        .ok_or_else(|| JsValue::from_str("no profile loaded"))?;
+    let entry = session
+        .profiles()
+        .get(profile_index)
+        .ok_or_else(|| JsValue::from_str(&format!("no profile at index {profile_index}")))?;
+    entry
+        .profile
+        .screenshot_at(ts)
+        .map(|shot| serde_json::to_string(shot).map_err(|e| JsValue::from_str(&e.to_string())))
+        .transpose()
+}
+
+/// The span one arrow-key step (`"left"`, `"right"`, `"up"` or `"down"`)
+/// from `frame_id` in the first profile's flame chart, as JSON `RowSpan`,
+/// or `undefined` at the edge of the chart.
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen(js_name = "findAdjacentSpan")]
+pub fn find_adjacent_span(frame_id: u64, direction: &str) -> Result<Option<String>, JsValue> {
+    let step = flame_cat_core::model::SpanStep::from_name(direction)
+        .ok_or_else(|| JsValue::from_str(&format!("unknown direction: {direction}")))?;
+    let session = SESSION
+        .lock()
+        .ok()
+        .and_then(|s| s.clone())
+        .ok_or_else(|| JsValue::from_str("no profile loaded"))?;
+    let entry = session
+        .profiles()
+        .first()
+        .ok_or_else(|| JsValue::from_str("no profile loaded"))?;
+    flame_cat_core::model::adjacent_span(&entry.profile, frame_id, step)
+        .map(|span| serde_json::to_string(&span).map_err(|e| JsValue::from_str(&e.to_string())))
+        .transpose()
+}
+
+/// Flow arrows starting or ending within a span of the first loaded
+/// profile, as JSON `[{arrow, outgoing}]`.
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen(js_name = "getFlowsForSpan")]
+pub fn get_flows_for_span(frame_id: u64) -> Result<String, JsValue> {
+    let session = SESSION
+        .lock()
+        .ok()
+        .and_then(|s| s.clone())
+        .ok_or_else(|| JsValue::from_str("no profile loaded"))?;
+    let entry = session
+        .profiles()
+        .first()
+        .ok_or_else(|| JsValue::from_str("no profile loaded"))?;
+    serde_json::to_string(&entry.profile.flows_for_span(frame_id))
+        .map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
 #[cfg(target_arch = "wasm32")]
 #[wasm_bindgen(js_name = "nextSearchResult")]
 pub fn next_search_result() {
@@ -418,43 +1597,278 @@ pub fn on_state_change(callback: js_sys::Function) {
     });
 }
 
+/// The loaded profile as JSON, first scrubbed by JSON `AnonymizeRules`
+/// when `anonymize` is given.
 #[cfg(target_arch = "wasm32")]
 #[wasm_bindgen(js_name = "exportProfile")]
-pub fn export_profile() -> Option<String> {
-    if let Ok(p) = PROFILE_JSON.lock() {
-        p.clone()
-    } else {
-        None
-    }
+pub fn export_profile(anonymize: Option<String>) -> Result<Option<String>, JsValue> {
+    let json = PROFILE_JSON.lock().ok().and_then(|p| p.clone());
+    let (Some(json), Some(rules)) = (&json, anonymize) else {
+        return Ok(json);
+    };
+    let rules: flame_cat_core::transform::AnonymizeRules = serde_json::from_str(&rules)
+        .map_err(|e| JsValue::from_str(&format!("invalid anonymize rules: {e}")))?;
+    let mut profile: flame_cat_protocol::VisualProfile =
+        serde_json::from_str(json).map_err(|e| JsValue::from_str(&e.to_string()))?;
+    flame_cat_core::transform::anonymize(&mut profile, &rules);
+    serde_json::to_string(&profile)
+        .map(Some)
+        .map_err(|e| JsValue::from_str(&e.to_string()))
 }
 
+/// Serialize the loaded profiles and current view state as a `.flamecat`
+/// session file. Reopen it with `loadProfile`.
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen(js_name = "saveSessionState")]
+pub fn save_session_state() -> Result<String, JsValue> {
+    let session = SESSION
+        .lock()
+        .ok()
+        .and_then(|s| s.clone())
+        .ok_or_else(|| JsValue::from_str("no profile loaded"))?;
+    let state = SESSION_STATE
+        .lock()
+        .ok()
+        .and_then(|s| s.clone())
+        .unwrap_or_default();
+    flame_cat_core::model::save_session(&session, &state)
+        .map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
+/// Image export options from JS: `theme` (`"dark"` or `"light"`, the app's
+/// by default), `scale_bar` (time views only) and the remaining
+/// [`flame_cat_core::svg::SvgOptions`].
+#[cfg(target_arch = "wasm32")]
+#[derive(Default, serde::Deserialize)]
+#[serde(default)]
+struct ImageExportOptions {
+    theme: Option<String>,
+    scale_bar: bool,
+    #[serde(flatten)]
+    svg: flame_cat_core::svg::SvgOptions,
+}
+
+/// Render the current view as SVG. `options` is JSON
+/// `{theme?, palette?, transparent?, title?, scale_bar?}`; without it the
+/// export follows the app's theme on an opaque background.
 #[cfg(target_arch = "wasm32")]
 #[wasm_bindgen(js_name = "exportSVG")]
-pub fn export_svg(width: f64, height: f64) -> Option<String> {
-    let dark = if let Ok(s) = STATE.lock() {
-        s.theme == "dark"
-    } else {
-        true
+pub fn export_svg(
+    width: f64,
+    height: f64,
+    options: Option<String>,
+) -> Result<Option<String>, JsValue> {
+    let options: ImageExportOptions = match options {
+        Some(json) => serde_json::from_str(&json)
+            .map_err(|e| JsValue::from_str(&format!("invalid export options: {e}")))?,
+        None => ImageExportOptions::default(),
     };
-    if let Ok(lc) = LANE_COMMANDS.lock() {
-        let all_cmds: Vec<_> = lc.iter().flatten().cloned().collect();
-        if all_cmds.is_empty() {
-            return None;
+    let mut svg_options = options.svg;
+    if let Ok(s) = STATE.lock() {
+        svg_options.dark = options.theme.as_deref().unwrap_or(s.theme) != "light";
+        let canvas_width = LANE_COMMANDS_WIDTH
+            .lock()
+            .map(|w| f64::from(*w))
+            .unwrap_or(0.0);
+        if options.scale_bar && s.view_type == ViewType::TimeOrder && canvas_width > 0.0 {
+            svg_options.visible_us = s.profile.as_ref().map(|p| {
+                (s.viewport.end - s.viewport.start) * p.duration_us * width / canvas_width
+            });
         }
-        Some(flame_cat_core::svg::render_svg(
-            &all_cmds, width, height, dark,
-        ))
-    } else {
-        None
     }
+    svg_options.duration_format = duration_format();
+    let Ok(lc) = LANE_COMMANDS.lock() else {
+        return Ok(None);
+    };
+    let all_cmds: Vec<_> = lc.iter().flatten().cloned().collect();
+    if all_cmds.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(flame_cat_core::svg::render_svg_with(
+        &all_cmds,
+        width,
+        height,
+        &svg_options,
+    )))
+}
+
+/// Render the first loaded profile headlessly from a JSON `RenderOptions`
+/// and return the render commands as JSON.
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen(js_name = "renderView")]
+pub fn render_view(options: &str) -> Result<String, JsValue> {
+    let (_, commands) = render_loaded(options)?;
+    serde_json::to_string(&commands).map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
+/// Render like [`render_view`] and return the rects as GPU-ready instance
+/// buffers: `{count, rects: Float32Array, colors: Uint8Array,
+/// borderColors: Uint8Array, labelIds: Uint32Array, frameIds:
+/// Float64Array, labels: string[], overlay: RenderCommand[]}`. Colors
+/// follow the options' color mode in the `theme` (`"dark"` or `"light"`,
+/// default the app's).
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen(js_name = "renderGeometry")]
+pub fn render_geometry(options: &str, theme: Option<String>) -> Result<JsValue, JsValue> {
+    use js_sys::{Array, Float32Array, Float64Array, Object, Reflect, Uint32Array, Uint8Array};
+
+    let (_, commands) = render_loaded(options)?;
+    let color_mode = serde_json::from_str::<flame_cat_protocol::RenderOptions>(options)
+        .map(|o| o.color_mode)
+        .unwrap_or_default();
+    let dark = match theme {
+        Some(theme) => theme != "light",
+        None => STATE.lock().map(|s| s.theme != "light").unwrap_or(true),
+    };
+    let batch = flame_cat_core::geometry::batch_geometry(&commands, color_mode, dark);
+    let overlay =
+        serde_json::to_string(&batch.overlay).map_err(|e| JsValue::from_str(&e.to_string()))?;
+
+    let out = Object::new();
+    let labels: Array = batch.labels.iter().map(|l| JsValue::from_str(l)).collect();
+    for (key, value) in [
+        ("count", JsValue::from(batch.len() as u32)),
+        ("rects", Float32Array::from(batch.rects.as_slice()).into()),
+        ("colors", Uint8Array::from(batch.colors.as_slice()).into()),
+        (
+            "borderColors",
+            Uint8Array::from(batch.border_colors.as_slice()).into(),
+        ),
+        (
+            "labelIds",
+            Uint32Array::from(batch.label_ids.as_slice()).into(),
+        ),
+        (
+            "frameIds",
+            Float64Array::from(batch.frame_ids.as_slice()).into(),
+        ),
+        ("labels", labels.into()),
+        ("overlay", js_sys::JSON::parse(&overlay)?),
+    ] {
+        Reflect::set(&out, &JsValue::from_str(key), &value)?;
+    }
+    Ok(out.into())
+}
+
+/// Render like [`render_view`] and return the result as a nested
+/// accessibility tree of views, lanes, flame rows and spans.
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen(js_name = "getAccessibilityTree")]
+pub fn get_accessibility_tree(options: &str) -> Result<String, JsValue> {
+    let (session, commands) = render_loaded(options)?;
+    let profile = &session.profiles()[0].profile;
+    let tree = flame_cat_core::views::accessibility::accessibility_tree(&commands, profile);
+    serde_json::to_string(&tree).map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
+/// Time-order render of thread `thread_id` of profile `base_idx` with the
+/// same-named thread of profile `other_idx` laid over it, aligned on the
+/// session timeline, as JSON render commands. `options` is a JSON
+/// `RenderOptions` whose range is in the base profile's own time.
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen(js_name = "renderOverlay")]
+pub fn render_overlay(
+    base_idx: usize,
+    other_idx: usize,
+    thread_id: u32,
+    options: &str,
+) -> Result<String, JsValue> {
+    let options: flame_cat_protocol::RenderOptions = serde_json::from_str(options)
+        .map_err(|e| JsValue::from_str(&format!("invalid render options: {e}")))?;
+    let session = SESSION
+        .lock()
+        .ok()
+        .and_then(|s| s.clone())
+        .ok_or_else(|| JsValue::from_str("no profile loaded"))?;
+    let entry = |index: usize| {
+        session
+            .profiles()
+            .get(index)
+            .ok_or_else(|| JsValue::from_str(&format!("no profile at index {index}")))
+    };
+    let commands = flame_cat_core::views::overlay::render_overlay(
+        entry(base_idx)?,
+        entry(other_idx)?,
+        thread_id,
+        &options,
+    );
+    serde_json::to_string(&commands).map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
+/// Render the first profile of the loaded session with JSON `options`.
+#[cfg(target_arch = "wasm32")]
+fn render_loaded(
+    options: &str,
+) -> Result<
+    (
+        std::sync::Arc<flame_cat_core::model::Session>,
+        Vec<flame_cat_protocol::RenderCommand>,
+    ),
+    JsValue,
+> {
+    let options: flame_cat_protocol::RenderOptions = serde_json::from_str(options)
+        .map_err(|e| JsValue::from_str(&format!("invalid render options: {e}")))?;
+    if !options.is_supported() {
+        return Err(JsValue::from_str(&format!(
+            "render options version {} is newer than supported version {}",
+            options.version,
+            flame_cat_protocol::RENDER_OPTIONS_VERSION
+        )));
+    }
+    let session = SESSION
+        .lock()
+        .ok()
+        .and_then(|s| s.clone())
+        .ok_or_else(|| JsValue::from_str("no profile loaded"))?;
+    let entry = session
+        .profiles()
+        .first()
+        .ok_or_else(|| JsValue::from_str("no profile loaded"))?;
+    let commands = flame_cat_core::views::render(&entry.profile, &options);
+    Ok((session, commands))
+}
+
+/// What a minimap pointer at `x` of a `width`-wide minimap lands on, given
+/// the visible fractions: `"left_handle"`, `"right_handle"`, `"viewport"` or
+/// `"outside"`. Lets hosts drawing their own minimap pick cursors and drags.
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen(js_name = "minimapHitTest")]
+pub fn minimap_hit_test(x: f64, width: f64, view_start: f64, view_end: f64) -> String {
+    flame_cat_core::views::minimap::minimap_hit_test(x, width, view_start, view_end)
+        .name()
+        .to_string()
+}
+
+/// Visible `[start, end]` fractions for a minimap drag pressed at `press_x`
+/// and now at `x`: a pan or handle resize inside the viewport, or a new
+/// range when pressed outside it.
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen(js_name = "minimapDragRange")]
+pub fn minimap_drag_range(
+    press_x: f64,
+    x: f64,
+    width: f64,
+    view_start: f64,
+    view_end: f64,
+) -> Vec<f64> {
+    let drag =
+        flame_cat_core::views::minimap::MinimapDrag::begin(press_x, width, view_start, view_end);
+    let (start, end) = drag.update(x, width, view_start, view_end);
+    vec![start, end]
 }
 
 #[cfg(target_arch = "wasm32")]
 #[wasm_bindgen(js_name = "getState")]
-pub fn get_state() -> String {
-    if let Ok(s) = STATE.lock() {
-        serde_json::to_string(&*s).unwrap_or_default()
-    } else {
-        "{}".to_string()
+pub fn get_state() -> JsValue {
+    let (Ok(state), Ok(mut buf)) = (STATE.lock(), STATE_JSON.lock()) else {
+        return JsValue::from_str("{}");
+    };
+    if STATE_JSON_STALE.swap(false, std::sync::atomic::Ordering::Relaxed) {
+        buf.clear();
+        if serde_json::to_writer(&mut *buf, &*state).is_err() {
+            buf.clear();
+        }
     }
+    // Copied straight into a JS string; no intermediate Rust `String`.
+    JsValue::from_str(std::str::from_utf8(&buf).unwrap_or("{}"))
 }
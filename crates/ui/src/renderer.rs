@@ -1,17 +1,22 @@
 use egui::{Align2, CornerRadius, FontId, Pos2, Rect, Stroke, StrokeKind};
+use flame_cat_core::views::text::{fit_label, TextMeasure, LABEL_PADDING};
+pub use flame_cat_protocol::ColorMode;
 use flame_cat_protocol::{RenderCommand, TextAlign, ThemeToken};
 
 use crate::theme::{self, ThemeMode};
 
-/// Minimum span width (px) to attempt rendering a text label.
-const MIN_LABEL_WIDTH: f32 = 6.0;
-/// Minimum span height (px) to attempt rendering a text label.
-const MIN_LABEL_HEIGHT: f32 = 8.0;
-/// Font size bounds for span labels (px).
-const LABEL_FONT_MIN: f32 = 6.0;
-const LABEL_FONT_MAX: f32 = 11.0;
-/// Vertical padding subtracted from span height to compute font size.
-const LABEL_FONT_PADDING: f32 = 4.0;
+/// Measures labels with the glyph advances of egui's proportional font.
+struct GlyphMeasure<'a>(&'a egui::Painter);
+
+impl TextMeasure for GlyphMeasure<'_> {
+    fn width(&self, text: &str, font_size: f64) -> f64 {
+        let font = FontId::proportional(font_size as f32);
+        let width: f32 = self
+            .0
+            .fonts_mut(|fonts| text.chars().map(|c| fonts.glyph_width(&font, c)).sum());
+        f64::from(width)
+    }
+}
 
 /// Transform state for PushTransform/PopTransform.
 #[derive(Debug, Clone, Copy)]
@@ -63,49 +68,19 @@ pub struct RenderResult {
 /// Render a list of `RenderCommand` into an egui `Painter`.
 ///
 /// `offset` is the top-left pixel position of the rendering area.
-/// `search` is an optional search filter — non-matching spans are dimmed.
+/// Rects flagged `dimmed` (search non-matches) are drawn faded.
 /// Returns hit regions for click/hover interaction.
-/// How span rectangles are colored.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum ColorMode {
-    /// Use theme token from render command (depth-based cycling).
-    Theme,
-    /// Hash the span label into a consistent hue (color-by-package).
-    ByName,
-}
-
 pub fn render_commands(
     painter: &mut egui::Painter,
     commands: &[RenderCommand],
     offset: Pos2,
     mode: ThemeMode,
-    search: &str,
     color_mode: ColorMode,
 ) -> RenderResult {
     let mut transform_stack: Vec<Transform> = vec![Transform::identity()];
     let mut clip_stack: Vec<Rect> = Vec::new();
     let mut hit_regions: Vec<HitRegion> = Vec::with_capacity(commands.len());
 
-    let search_lower = search.to_lowercase();
-
-    // Pre-compute matching unique label strings to avoid per-command to_lowercase().
-    // Most profiles have <500 unique names but >5000 commands.
-    let search_active = !search_lower.is_empty();
-    let matching_names: std::collections::HashSet<&str> = if search_active {
-        let mut unique: std::collections::HashSet<&str> = std::collections::HashSet::new();
-        for cmd in commands {
-            if let RenderCommand::DrawRect { label: Some(l), .. } = cmd {
-                unique.insert(l.as_ref());
-            }
-        }
-        unique
-            .into_iter()
-            .filter(|name| name.to_lowercase().contains(&search_lower))
-            .collect()
-    } else {
-        std::collections::HashSet::new()
-    };
-
     for cmd in commands {
         let tf = transform_stack
             .last()
@@ -118,6 +93,8 @@ pub fn render_commands(
                 border_color,
                 label,
                 frame_id,
+                dimmed,
+                span_name,
             } => {
                 let x = (tf.apply_x(rect.x) + offset.x).round();
                 let y = (tf.apply_y(rect.y) + offset.y).round();
@@ -136,25 +113,21 @@ pub fn render_commands(
                 }
 
                 let fill = match color_mode {
-                    ColorMode::ByName => {
-                        if let Some(label_text) = label {
-                            name_to_color(label_text, mode)
-                        } else {
-                            theme::resolve(*color, mode)
-                        }
+                    ColorMode::ByName => match span_name.as_ref().or(label.as_ref()) {
+                        // Kernel frames keep their own color so they stand
+                        // apart from user code.
+                        Some(name) if *color != ThemeToken::KernelSpan => name_to_color(name, mode),
+                        _ => theme::resolve(*color, mode),
+                    },
+                    ColorMode::ByDepth | ColorMode::ByDuration | ColorMode::BySelfTime => {
+                        theme::resolve(*color, mode)
                     }
-                    ColorMode::Theme => theme::resolve(*color, mode),
                 };
 
-                // Dim non-matching spans when search is active
-                let search_match = !search_active
-                    || label
-                        .as_ref()
-                        .is_some_and(|l| matching_names.contains(l.as_ref()));
-                let fill = if search_match {
-                    fill
-                } else {
+                let fill = if *dimmed {
                     egui::Color32::from_rgba_unmultiplied(fill.r(), fill.g(), fill.b(), 40)
+                } else {
+                    fill
                 };
 
                 painter.rect_filled(egui_rect, CornerRadius::ZERO, fill);
@@ -170,61 +143,22 @@ pub fn render_commands(
                 }
 
                 // Draw label text inside the rect
-                if let Some(label_text) = label {
-                    let label_str: &str = label_text;
-                    // Skip text layout entirely for rects too narrow to show even 1 char
-                    if !label_str.is_empty()
-                        && w > MIN_LABEL_WIDTH
-                        && h > MIN_LABEL_HEIGHT
-                        && w > 14.0
-                    {
-                        let font_size =
-                            (h - LABEL_FONT_PADDING).clamp(LABEL_FONT_MIN, LABEL_FONT_MAX);
-                        // WCAG: choose text color based on fill luminance
-                        let text_color = contrast_text_color(fill);
-                        let text_rect = egui_rect.shrink2(egui::vec2(2.0, 0.0));
-                        let galley = painter.layout_no_wrap(
-                            label_str.to_string(),
-                            FontId::proportional(font_size),
-                            text_color,
-                        );
-                        let text_pos = Pos2::new(
-                            text_rect.left(),
-                            text_rect.center().y - galley.size().y / 2.0,
-                        );
-                        if galley.size().x <= text_rect.width() + 2.0 {
-                            painter.galley(text_pos, galley, text_color);
-                        } else if text_rect.width() > 20.0 && galley.size().x > 0.0 {
-                            // Estimate how many characters fit using measured average width
-                            let avail = text_rect.width() - 2.0;
-                            let char_count = label_str.chars().count();
-                            let avg_char_w = galley.size().x / char_count as f32;
-                            let ellipsis_w = avg_char_w * 1.5; // rough ellipsis width
-                            let take =
-                                ((avail - ellipsis_w) / avg_char_w).floor().max(1.0) as usize;
-                            let take = take.min(char_count);
-                            let truncated: String = label_str.chars().take(take).collect();
-                            let ellipsis_galley = painter.layout_no_wrap(
-                                format!("{truncated}…"),
-                                FontId::proportional(font_size),
-                                text_color,
-                            );
-                            if ellipsis_galley.size().x <= avail + 2.0 {
-                                painter.galley(text_pos, ellipsis_galley, text_color);
-                            } else if take > 1 {
-                                // Fallback: try one fewer char
-                                let shorter: String = label_str.chars().take(take - 1).collect();
-                                let g = painter.layout_no_wrap(
-                                    format!("{shorter}…"),
-                                    FontId::proportional(font_size),
-                                    text_color,
-                                );
-                                if g.size().x <= avail + 2.0 {
-                                    painter.galley(text_pos, g, text_color);
-                                }
-                            }
-                        }
-                    }
+                if let Some(fitted) = label
+                    .as_deref()
+                    .and_then(|l| fit_label(l, f64::from(w), f64::from(h), &GlyphMeasure(painter)))
+                {
+                    // WCAG: choose text color based on fill luminance
+                    let text_color = contrast_text_color(fill);
+                    let galley = painter.layout_no_wrap(
+                        fitted.text.into_owned(),
+                        FontId::proportional(fitted.font_size as f32),
+                        text_color,
+                    );
+                    let text_pos = Pos2::new(
+                        egui_rect.left() + LABEL_PADDING as f32,
+                        egui_rect.center().y - galley.size().y / 2.0,
+                    );
+                    painter.galley(text_pos, galley, text_color);
                 }
 
                 if let Some(fid) = frame_id {
@@ -332,47 +266,16 @@ pub fn render_commands(
     RenderResult { hit_regions }
 }
 
-/// Generate a consistent color from a span name by hashing the "package" prefix.
-/// Extracts the first segment before common separators (::, ., /, @) and hashes it.
+/// Generate a consistent color from a span name by hashing its "package"
+/// prefix (see [`flame_cat_protocol::color_key`]).
 fn name_to_color(name: &str, mode: ThemeMode) -> egui::Color32 {
-    // Extract package/module prefix
-    let prefix = name
-        .split([':', '.', '/', '@', '\\'])
-        .next()
-        .unwrap_or(name);
-
-    // Simple hash → hue
-    let mut hash: u32 = 5381;
-    for b in prefix.bytes() {
-        hash = hash.wrapping_mul(33).wrapping_add(u32::from(b));
-    }
-    let hue = (hash % 360) as f32;
-
-    // HSL → RGB with Perfetto-inspired saturation/lightness
-    let (s, l) = match mode {
-        ThemeMode::Dark => (0.60, 0.50), // Vibrant on dark bg
-        ThemeMode::Light => (0.55, 0.58),
-    };
-    hsl_to_color32(hue, s, l)
+    category_color(flame_cat_protocol::color_key(name), mode)
 }
 
-fn hsl_to_color32(h: f32, s: f32, l: f32) -> egui::Color32 {
-    let c = (1.0 - (2.0 * l - 1.0).abs()) * s;
-    let x = c * (1.0 - ((h / 60.0) % 2.0 - 1.0).abs());
-    let m = l - c / 2.0;
-    let (r, g, b) = match (h as u32) / 60 {
-        0 => (c, x, 0.0),
-        1 => (x, c, 0.0),
-        2 => (0.0, c, x),
-        3 => (0.0, x, c),
-        4 => (x, 0.0, c),
-        _ => (c, 0.0, x),
-    };
-    egui::Color32::from_rgb(
-        ((r + m) * 255.0) as u8,
-        ((g + m) * 255.0) as u8,
-        ((b + m) * 255.0) as u8,
-    )
+/// Fill of spans whose color key is `key` in by-name mode.
+pub fn category_color(key: &str, mode: ThemeMode) -> egui::Color32 {
+    let (r, g, b) = flame_cat_protocol::color_rgb(key, mode == ThemeMode::Dark);
+    egui::Color32::from_rgb(r, g, b)
 }
 
 /// Choose white or dark text based on background luminance (WCAG).
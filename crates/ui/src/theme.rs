@@ -14,6 +14,11 @@ impl ResolvedColor {
         Self { r, g, b, a: 255 }
     }
 
+    fn heat(level: u8) -> Self {
+        let (r, g, b) = flame_cat_protocol::heat_rgb(level);
+        Self::rgb(r, g, b)
+    }
+
     const fn rgba(r: u8, g: u8, b: u8, a: u8) -> Self {
         Self { r, g, b, a }
     }
@@ -29,6 +34,16 @@ pub enum ThemeMode {
     Light,
 }
 
+impl ThemeMode {
+    /// Name used by the JS API and session files.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Dark => "dark",
+            Self::Light => "light",
+        }
+    }
+}
+
 pub fn resolve(token: ThemeToken, mode: ThemeMode) -> egui::Color32 {
     match mode {
         ThemeMode::Dark => resolve_dark(token),
@@ -85,12 +100,18 @@ fn resolve_dark(token: ThemeToken) -> ResolvedColor {
         CounterFill => ResolvedColor::rgba(0x74, 0xc7, 0xec, 50), // Sapphire
         CounterLine => ResolvedColor::rgb(0x74, 0xc7, 0xec),
         CounterText => ResolvedColor::rgb(0xba, 0xc2, 0xde), // Subtext1
+        CounterBudgetExceeded => ResolvedColor::rgba(0xf3, 0x8b, 0xa8, 40), // Red
+        CounterBudgetLine => ResolvedColor::rgb(0xf3, 0x8b, 0xa8),
 
         MarkerLine => ResolvedColor::rgb(0xf9, 0xe2, 0xaf), // Yellow
         MarkerText => ResolvedColor::rgb(0xf9, 0xe2, 0xaf),
 
         AsyncSpanFill => ResolvedColor::rgb(0x94, 0xe2, 0xd5), // Teal
         AsyncSpanBorder => ResolvedColor::rgb(0x74, 0xc7, 0xec), // Sapphire
+        GpuSpanBorder => ResolvedColor::rgb(0xfa, 0xb3, 0x87), // Peach
+        KernelSpan => ResolvedColor::rgb(0xcb, 0xa6, 0xf7),    // Mauve
+        OverlaySpanFill => ResolvedColor::rgba(0x94, 0xe2, 0xd5, 50), // Teal
+        OverlaySpanBorder => ResolvedColor::rgb(0x94, 0xe2, 0xd5),
 
         FrameGood => ResolvedColor::rgb(0xa6, 0xe3, 0xa1), // Green
         FrameWarning => ResolvedColor::rgb(0xf9, 0xe2, 0xaf), // Yellow
@@ -98,6 +119,8 @@ fn resolve_dark(token: ThemeToken) -> ResolvedColor {
 
         FlowArrow => ResolvedColor::rgba(0x6c, 0x70, 0x86, 80), // Overlay0
         FlowArrowHead => ResolvedColor::rgba(0x6c, 0x70, 0x86, 120),
+
+        Heat(level) => ResolvedColor::heat(level),
     }
 }
 fn resolve_light(token: ThemeToken) -> ResolvedColor {
@@ -147,12 +170,18 @@ fn resolve_light(token: ThemeToken) -> ResolvedColor {
         CounterFill => ResolvedColor::rgba(50, 110, 220, 40),
         CounterLine => ResolvedColor::rgb(50, 110, 220),
         CounterText => ResolvedColor::rgb(80, 80, 100),
+        CounterBudgetExceeded => ResolvedColor::rgba(210, 40, 60, 30),
+        CounterBudgetLine => ResolvedColor::rgb(210, 40, 60),
 
         MarkerLine => ResolvedColor::rgb(200, 150, 20),
         MarkerText => ResolvedColor::rgb(150, 100, 10),
 
         AsyncSpanFill => ResolvedColor::rgb(80, 140, 200),
         AsyncSpanBorder => ResolvedColor::rgb(50, 110, 180),
+        GpuSpanBorder => ResolvedColor::rgb(220, 110, 30),
+        KernelSpan => ResolvedColor::rgb(200, 170, 240),
+        OverlaySpanFill => ResolvedColor::rgba(23, 146, 153, 45),
+        OverlaySpanBorder => ResolvedColor::rgb(23, 146, 153),
 
         FrameGood => ResolvedColor::rgb(56, 142, 60),
         FrameWarning => ResolvedColor::rgb(230, 170, 0),
@@ -160,6 +189,8 @@ fn resolve_light(token: ThemeToken) -> ResolvedColor {
 
         FlowArrow => ResolvedColor::rgba(50, 120, 220, 140),
         FlowArrowHead => ResolvedColor::rgba(50, 120, 220, 180),
+
+        Heat(level) => ResolvedColor::heat(level),
     }
 }
 
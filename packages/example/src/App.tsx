@@ -61,7 +61,7 @@ function Toolbar() {
   const { viewType, setViewType } = useViewType();
   const { canGoBack, canGoForward, back, forward } = useNavigation();
   const { resetZoom } = useViewport();
-  const { exportJSON, exportSVG } = useExport();
+  const { exportJSON, exportSVG, exportPNG } = useExport();
   const profile = useProfile();
   const searchRef = useRef<HTMLInputElement>(null);
 
@@ -89,6 +89,16 @@ function Toolbar() {
     downloadBlob(svg, "flamegraph.svg", "image/svg+xml");
   };
 
+  const handleExportPNG = async () => {
+    const png = await exportPNG(1200, 600, {
+      title: profile?.name ?? undefined,
+      transparent: true,
+      scale_bar: true,
+    });
+    if (!png) return;
+    downloadBlob(png, "flamegraph.png", "image/png");
+  };
+
   return (
     <div style={{
       display: "flex", alignItems: "center", gap: 8, flexWrap: "wrap",
@@ -105,7 +115,7 @@ function Toolbar() {
         background: mode === "dark" ? "#2a2a4a" : "#e9ecef",
       }}>
         📂 Open
-        <input type="file" accept=".json,.cpuprofile,.speedscope" onChange={handleFile} style={{ display: "none" }} />
+        <input type="file" accept=".json,.cpuprofile,.speedscope,.perfetto-trace,.pftrace,.data,.jfr,.nettrace,.csv,.xml,.html,.txt,.trace,.pprof,.pb,.gz" onChange={handleFile} style={{ display: "none" }} />
       </label>
 
       <Sep />
@@ -155,6 +165,7 @@ function Toolbar() {
 
       <button onClick={handleExportJSON} disabled={!profile} title="Export JSON" style={navBtn(mode)}>💾 JSON</button>
       <button onClick={handleExportSVG} disabled={!profile} title="Export SVG" style={navBtn(mode)}>🖼 SVG</button>
+      <button onClick={handleExportPNG} disabled={!profile} title="Export PNG" style={navBtn(mode)}>🖼 PNG</button>
       <button onClick={toggleColor} disabled={!profile} title="Toggle color mode" style={navBtn(mode)}>
         {colorMode === "by_name" ? "🎨 Color" : "🔢 Value"}
       </button>
@@ -370,7 +381,7 @@ function formatDuration(us: number): string {
   return `${(us / 1_000_000).toFixed(2)}s`;
 }
 
-function downloadBlob(content: string, filename: string, mimeType: string) {
+function downloadBlob(content: BlobPart, filename: string, mimeType: string) {
   const blob = new Blob([content], { type: mimeType });
   const url = URL.createObjectURL(blob);
   const a = document.createElement("a");
@@ -354,6 +354,34 @@ describe("hooks integration", () => {
     expect(wasm.exportSVG).toHaveBeenCalledWith(800, 400);
   });
 
+  it("useExport passes image options as JSON", () => {
+    const { result } = renderHook(() => useExport(), {
+      wrapper: createWrapper(store),
+    });
+    result.current.exportSVG(800, 400, { theme: "light", transparent: true, title: "Load" });
+    expect(wasm.exportSVG).toHaveBeenCalledWith(
+      800,
+      400,
+      '{"theme":"light","transparent":true,"title":"Load"}',
+    );
+  });
+
+  it("useExport passes anonymize rules as JSON", () => {
+    const { result } = renderHook(() => useExport(), {
+      wrapper: createWrapper(store),
+    });
+    result.current.exportJSON({ urls: "strip", salt: "team" });
+    expect(wasm.exportProfile).toHaveBeenCalledWith('{"urls":"strip","salt":"team"}');
+  });
+
+  it("useExport resolves no PNG without a view", async () => {
+    vi.mocked(wasm.exportSVG).mockReturnValueOnce(undefined);
+    const { result } = renderHook(() => useExport(), {
+      wrapper: createWrapper(store),
+    });
+    await expect(result.current.exportPNG()).resolves.toBeNull();
+  });
+
   // ── useColorMode ────────────────────────────────────────────────────
 
   it("useColorMode reads and toggles color mode", () => {
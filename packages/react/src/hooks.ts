@@ -1,7 +1,16 @@
 import { useSyncExternalStore, useCallback, useEffect } from "react";
 import { useFlameCatStore } from "./FlameCatProvider";
 import type { FlameCatStatus } from "./store";
-import type { ProfileInfo, LaneInfo, ViewportInfo, SelectedSpanInfo, ViewType } from "./types";
+import type {
+  ColorMode,
+  AnonymizeRules,
+  ImageExportOptions,
+  ProfileInfo,
+  LaneInfo,
+  ViewportInfo,
+  SelectedSpanInfo,
+  ViewType,
+} from "./types";
 
 // ── useFlameGraph ──────────────────────────────────────────────────────
 
@@ -288,7 +297,7 @@ export function useTheme(): ThemeState {
 
 // ── useColorMode ───────────────────────────────────────────────────────
 
-export type ColorMode = "by_name" | "by_depth";
+export type { ColorMode };
 
 export interface ColorModeState {
   /** Current color mode. */
@@ -299,7 +308,7 @@ export interface ColorModeState {
   toggle(): void;
 }
 
-/** Span coloring mode: by package/name or by stack depth. */
+/** Span coloring mode: by package/name, stack depth, duration or self time. */
 export function useColorMode(): ColorModeState {
   const store = useFlameCatStore();
 
@@ -367,6 +376,8 @@ export interface SpanNavigationState {
   goToNextSibling(): void;
   /** Navigate to the previous sibling of the currently selected span. */
   goToPrevSibling(): void;
+  /** Select the span at the other end of the selected span's first flow arrow. */
+  followFlow(): void;
   /** Jump to the next search result. */
   nextSearchResult(): void;
   /** Jump to the previous search result. */
@@ -393,6 +404,10 @@ export function useSpanNavigation(): SpanNavigationState {
     store.exec((w) => w.navigateToPrevSibling());
   }, [store]);
 
+  const followFlow = useCallback(() => {
+    store.exec((w) => w.followFlow());
+  }, [store]);
+
   const nextSearchResult = useCallback(() => {
     store.exec((w) => w.nextSearchResult());
   }, [store]);
@@ -401,7 +416,15 @@ export function useSpanNavigation(): SpanNavigationState {
     store.exec((w) => w.prevSearchResult());
   }, [store]);
 
-  return { goToParent, goToChild, goToNextSibling, goToPrevSibling, nextSearchResult, prevSearchResult };
+  return {
+    goToParent,
+    goToChild,
+    goToNextSibling,
+    goToPrevSibling,
+    followFlow,
+    nextSearchResult,
+    prevSearchResult,
+  };
 }
 
 // ── useHoveredSpan ─────────────────────────────────────────────────────
@@ -459,38 +482,86 @@ export function useNavigation(): NavigationState {
 // ── useExport ──────────────────────────────────────────────────────────
 
 export interface ExportState {
-  /** Export the loaded profile as a JSON string (VisualProfile format). */
-  exportJSON(): string | null;
+  /**
+   * Export the loaded profile as a JSON string (VisualProfile format),
+   * scrubbed of URLs, paths and arguments when `anonymize` is given.
+   */
+  exportJSON(anonymize?: AnonymizeRules): string | null;
   /** Export the current view as an SVG string. */
-  exportSVG(width?: number, height?: number): string | null;
+  exportSVG(width?: number, height?: number, options?: ImageExportOptions): string | null;
+  /**
+   * Export the current view as a PNG, rasterized from the SVG export at
+   * `pixelRatio` device pixels per CSS pixel.
+   */
+  exportPNG(
+    width?: number,
+    height?: number,
+    options?: ImageExportOptions,
+    pixelRatio?: number,
+  ): Promise<Blob | null>;
 }
 
-/** Profile export (JSON and SVG). */
+/** Profile export (JSON, SVG and PNG). */
 export function useExport(): ExportState {
   const store = useFlameCatStore();
 
-  const exportJSON = useCallback((): string | null => {
-    let result: string | null = null;
-    store.exec((w) => {
-      result = w.exportProfile() ?? null;
-    });
-    return result;
-  }, [store]);
+  const exportJSON = useCallback(
+    (anonymize?: AnonymizeRules): string | null => {
+      let result: string | null = null;
+      store.exec((w) => {
+        result =
+          (anonymize ? w.exportProfile(JSON.stringify(anonymize)) : w.exportProfile()) ?? null;
+      });
+      return result;
+    },
+    [store],
+  );
 
   const exportSVG = useCallback(
-    (width = 1200, height = 600): string | null => {
+    (width = 1200, height = 600, options?: ImageExportOptions): string | null => {
       const w = Number.isFinite(width) && width > 0 ? Math.min(width, 8000) : 1200;
       const h = Number.isFinite(height) && height > 0 ? Math.min(height, 8000) : 600;
       let result: string | null = null;
       store.exec((wasm) => {
-        result = wasm.exportSVG(w, h) ?? null;
+        result =
+          (options ? wasm.exportSVG(w, h, JSON.stringify(options)) : wasm.exportSVG(w, h)) ??
+          null;
       });
       return result;
     },
     [store],
   );
 
-  return { exportJSON, exportSVG };
+  const exportPNG = useCallback(
+    async (
+      width?: number,
+      height?: number,
+      options?: ImageExportOptions,
+      pixelRatio = 2,
+    ): Promise<Blob | null> => {
+      const svg = exportSVG(width, height, options);
+      if (!svg) return null;
+      const url = URL.createObjectURL(new Blob([svg], { type: "image/svg+xml" }));
+      try {
+        const img = new Image();
+        img.src = url;
+        await img.decode();
+        const ratio = Number.isFinite(pixelRatio) && pixelRatio > 0 ? Math.min(pixelRatio, 4) : 1;
+        const canvas = document.createElement("canvas");
+        canvas.width = Math.round(img.naturalWidth * ratio);
+        canvas.height = Math.round(img.naturalHeight * ratio);
+        const ctx = canvas.getContext("2d");
+        if (!ctx) return null;
+        ctx.drawImage(img, 0, 0, canvas.width, canvas.height);
+        return await new Promise((resolve) => canvas.toBlob(resolve, "image/png"));
+      } finally {
+        URL.revokeObjectURL(url);
+      }
+    },
+    [exportSVG],
+  );
+
+  return { exportJSON, exportSVG, exportPNG };
 }
 
 // ── useHotkeys ─────────────────────────────────────────────────────────
@@ -504,6 +575,8 @@ export interface HotkeyMap {
   focusSearch?: string[];
   /** Clear selection (default: "Escape"). */
   clearSelection?: string[];
+  /** Follow the selected span's flow arrow (default: "F", i.e. Shift+F). */
+  followFlow?: string[];
 }
 
 const DEFAULT_HOTKEYS: Required<HotkeyMap> = {
@@ -511,6 +584,7 @@ const DEFAULT_HOTKEYS: Required<HotkeyMap> = {
   toggleTheme: ["t"],
   focusSearch: ["/", "f"],
   clearSelection: ["Escape"],
+  followFlow: ["F"],
 };
 
 /**
@@ -571,6 +645,9 @@ export function useHotkeys(
       } else if (e.key === "]") {
         e.preventDefault();
         store.exec((w) => w.navigateToChild());
+      } else if (map.followFlow.includes(e.key)) {
+        e.preventDefault();
+        store.exec((w) => w.followFlow());
       } else if (e.key === "Enter" && e.shiftKey) {
         if (store.getSnapshot().search) {
           e.preventDefault();
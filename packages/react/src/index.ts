@@ -54,4 +54,33 @@ export type {
   ViewportInfo,
   SelectedSpanInfo,
   ViewType,
+  HeatScale,
+  DiffNormalization,
+  ViewRegion,
+  Annotation,
+  Alignment,
+  SessionInfo,
+  GpuSubmission,
+  HotPath,
+  ProfileMatches,
+  SpanInstance,
+  PinnedRow,
+  RowSpan,
+  AccessNode,
+  GeometryBatch,
+  LimitsHit,
+  PartialParse,
+  ImageExportOptions,
+  AnonymizeRules,
+  NameRewrite,
+  Scrub,
+  DurationFormat,
+  CategoryBudget,
+  BinaryEntry,
+  CategoryTotal,
+  CategorySummary,
+  ChildTotal,
+  SpanInfo,
+  CodeClass,
+  ClassRules,
 } from "./types";
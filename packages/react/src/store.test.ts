@@ -7,9 +7,9 @@ function mockWasm(): WasmExports {
   const state = {
     profile: null,
     lanes: [
-      { name: "Main", kind: "thread", height: 200, visible: true, span_count: 42 },
-      { name: "Worker", kind: "thread", height: 100, visible: true, span_count: 10 },
-      { name: "Async", kind: "async", height: 60, visible: false, span_count: 5 },
+      { name: "Main", kind: "thread", height: 200, visible: true, span_count: 42, pinned: false },
+      { name: "Worker", kind: "thread", height: 100, visible: true, span_count: 10, pinned: false },
+      { name: "Async", kind: "async", height: 60, visible: false, span_count: 5, pinned: false },
     ],
     viewport: { start: 0, end: 1, scroll_y: 0 },
     selected: null,
@@ -17,13 +17,32 @@ function mockWasm(): WasmExports {
     theme: "dark",
     view_type: "time_order",
     color_mode: "by_name",
+    heat_scale: "siblings",
+    thread_sort: "span_count",
     can_go_back: false,
     can_go_forward: false,
+    playback_rate: 1,
+    playing: false,
   };
 
   return {
     startOnCanvas: vi.fn(),
     loadProfile: vi.fn(),
+    loadProfileWithHint: vi.fn(),
+    loadProfileFromUrl: vi.fn(async () => {}),
+    applySourceMap: vi.fn(),
+    applySourceMapFromUrl: vi.fn(async () => {}),
+    attachJitMap: vi.fn(),
+    importAnnotations: vi.fn(),
+    listAnnotations: vi.fn(() => "[]"),
+    setProfileMeta: vi.fn(),
+    setBaseline: vi.fn(),
+    setDiffNormalization: vi.fn(),
+    setNameRewrites: vi.fn(),
+    setClassRules: vi.fn(),
+    getRankedEntries: vi.fn(() => "[]"),
+    setThreadSort: vi.fn(),
+    pinThread: vi.fn(),
     setTheme: vi.fn((mode: string) => {
       state.theme = mode;
       stateCallback?.();
@@ -40,6 +59,11 @@ function mockWasm(): WasmExports {
       state.viewport = { start, end, scroll_y: 0 };
       stateCallback?.();
     }),
+    setPlayback: vi.fn((rate: number) => {
+      if (rate > 0) state.playback_rate = rate;
+      state.playing = rate > 0;
+      stateCallback?.();
+    }),
     setLaneVisibility: vi.fn((i: number, v: boolean) => {
       if (state.lanes[i]) state.lanes[i].visible = v;
       stateCallback?.();
@@ -63,6 +87,22 @@ function mockWasm(): WasmExports {
       state.color_mode = mode;
       stateCallback?.();
     }),
+    setRenderLimits: vi.fn(),
+    setDurationFormat: vi.fn(),
+    setHeatScale: vi.fn(),
+    saveRegion: vi.fn(),
+    listRegions: vi.fn(() => "[]"),
+    gotoRegion: vi.fn(),
+    toggleBookmark: vi.fn(),
+    listBookmarks: vi.fn(() => "[]"),
+    getSessionInfo: vi.fn(() => "{}"),
+    togglePin: vi.fn(),
+    getPinnedSpans: vi.fn(() => "[]"),
+    setPermalink: vi.fn(),
+    exportMarkdown: vi.fn(() => ""),
+    exportComparison: vi.fn(() => ""),
+    setCounterBudget: vi.fn(),
+    setLabelTemplate: vi.fn(),
     navigateBack: vi.fn(() => {
       state.can_go_forward = true;
       stateCallback?.();
@@ -75,10 +115,53 @@ function mockWasm(): WasmExports {
     navigateToChild: vi.fn(),
     navigateToNextSibling: vi.fn(),
     navigateToPrevSibling: vi.fn(),
+    followFlow: vi.fn(),
+    getRowSpans: vi.fn(() => "[]"),
+    findAdjacentSpan: vi.fn(() => undefined),
+    getStackAt: vi.fn(() => "[]"),
+    getScreenshotAt: vi.fn(() => undefined),
+    getFlowsForSpan: vi.fn(() => "[]"),
+    getCategoryLegend: vi.fn(() => "[]"),
+    getBinaryBreakdown: vi.fn(() => "[]"),
+    getCategorySummary: vi.fn(() => '{"categories":[],"kinds":[]}'),
+    getClassSummary: vi.fn(() => "[]"),
+    getSpanInfo: vi.fn(() => undefined),
+    getTopLevelTasks: vi.fn(() => "[]"),
+    getEventLoopAttribution: vi.fn(() => '{"turns":0,"turn_time":0,"by_api":[]}'),
+    getTaskInitiators: vi.fn(() => "[]"),
+    getInsights: vi.fn(() => "[]"),
+    setCategoryBudgets: vi.fn(),
+    getGpuSubmissions: vi.fn(() => "[]"),
+    getHotPaths: vi.fn(() => "[]"),
+    searchSession: vi.fn(() => "[]"),
+    getThreadUtilization: vi.fn(() => "[]"),
+    setCategoryFilter: vi.fn(),
+    setBinaryFilter: vi.fn(),
+    setClassFilter: vi.fn(),
+    setHideKernel: vi.fn(),
+    setExpandInlined: vi.fn(),
     nextSearchResult: vi.fn(),
     prevSearchResult: vi.fn(),
+    openLiveSession: vi.fn(),
+    appendLiveEvents: vi.fn(),
+    closeLiveSession: vi.fn(),
     exportProfile: vi.fn(() => '{"meta":{}}'),
     exportSVG: vi.fn(() => '<svg></svg>'),
+    renderView: vi.fn(() => "[]"),
+    renderGeometry: vi.fn(() => ({
+      count: 0,
+      rects: new Float32Array(),
+      colors: new Uint8Array(),
+      borderColors: new Uint8Array(),
+      labelIds: new Uint32Array(),
+      frameIds: new Float64Array(),
+      labels: [],
+      overlay: [],
+    })),
+    getAccessibilityTree: vi.fn(() => "[]"),
+    renderOverlay: vi.fn(() => "[]"),
+    minimapHitTest: vi.fn(() => "outside" as const),
+    minimapDragRange: vi.fn(() => new Float64Array([0, 1])),
     selectSpan: vi.fn((fid: number | undefined) => {
       state.selected = fid != null
         ? { name: "test", frame_id: fid, lane_index: 0, start_us: 0, end_us: 100 }
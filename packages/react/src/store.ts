@@ -13,8 +13,11 @@ const EMPTY_SNAPSHOT: StateSnapshot = {
   theme: "dark",
   view_type: "time_order",
   color_mode: "by_name",
+  heat_scale: "siblings",
   can_go_back: false,
   can_go_forward: false,
+  playback_rate: 1,
+  playing: false,
 };
 
 /**
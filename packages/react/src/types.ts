@@ -8,25 +8,502 @@ export interface StateSnapshot {
   search: string;
   theme: "dark" | "light";
   view_type: ViewType;
-  color_mode: "by_name" | "by_depth";
+  color_mode: ColorMode;
+  heat_scale: HeatScale;
+  thread_sort: ThreadSort;
   can_go_back: boolean;
   can_go_forward: boolean;
+  /** Replay speed, as a multiple of real time. */
+  playback_rate: number;
+  /** Whether replay is sliding the viewport along the trace. */
+  playing: boolean;
 }
 
+/** How spans are colored; the heat modes color by duration or self time. */
+export type ColorMode = "by_name" | "by_depth" | "by_duration" | "by_self_time";
+
+/** What the heat color modes compare a span with. */
+export type HeatScale = "siblings" | "absolute";
+
+/**
+ * How the baseline is scaled before comparison: as recorded, by recording
+ * length, or by share of each profile's total.
+ */
+export type DiffNormalization = "per_sample" | "per_second" | "per_total";
+
+/** Order of thread lanes below the pinned ones. */
+export type ThreadSort = "span_count" | "name" | "busy_time" | "first_activity";
+
 /** Visualization mode. */
 export type ViewType = "time_order" | "left_heavy" | "sandwich" | "ranked" | "icicle";
 
+/**
+ * Guards against pathological profiles; spans past them are summarized by an
+ * "N more…" label. Defaults: `max_depth` 256, `max_commands` 200000.
+ */
+export interface RenderLimits {
+  max_depth?: number;
+  max_commands?: number;
+}
+
+/**
+ * How durations are written in tooltips, axis labels, ranked rows and
+ * exports. By default the unit follows the magnitude (`1.5ms`, `2.50s`).
+ */
+export interface DurationFormat {
+  /** Digits after the decimal mark; by default 2 for s, 1 for ms, 0 below, one more in tooltips and on the axis. */
+  decimals?: number | null;
+  unit?: "auto" | "seconds" | "milliseconds" | "microseconds" | "nanoseconds";
+  /** `plain` 1234.5, `comma_point` 1,234.5, `point_comma` 1.234,5, `space_comma` 1 234,5. */
+  separators?: "plain" | "comma_point" | "point_comma" | "space_comma";
+}
+
+/**
+ * Options for `renderView`, passed as `JSON.stringify(options)`. Omitted
+ * fields take their defaults.
+ */
+export interface RenderOptions {
+  /** Schema version; defaults to the newest the module supports. */
+  version?: number;
+  view?: ViewType;
+  viewport?: { x: number; y: number; width: number; height: number; dpr: number };
+  /** Absolute window in µs; the whole profile when omitted. */
+  range?: { start: number; end: number } | null;
+  /** Thread ids to include; empty means all threads. */
+  threads?: number[];
+  /** Span the sandwich view is centered on. */
+  selected_frame?: number | null;
+  color_mode?: ColorMode;
+  heat_scale?: HeatScale;
+  /** `expand_inlined` draws inlined functions on rows of their own in the time-order view. */
+  lod?: { min_span_px?: number; expand_inlined?: boolean };
+  limits?: RenderLimits;
+  /**
+   * `category` keeps only spans of that color key (see `getCategoryLegend`),
+   * `binary` only spans that ran in that binary (see `getBinaryBreakdown`),
+   * `code_class` only spans of that class under `class_rules`.
+   */
+  search?: {
+    query?: string;
+    case_sensitive?: boolean;
+    category?: string | null;
+    binary?: string | null;
+    code_class?: CodeClass | null;
+    class_rules?: ClassRules;
+  };
+  /** Span label template per view, e.g. `{ time_order: "{name} ({dur})" }`. */
+  label_templates?: Partial<Record<ViewType, string>>;
+  format?: DurationFormat;
+  /**
+   * Collapse merged subtrees under this share of the total (0–100) into one
+   * "other (N frames)" block in the left-heavy, icicle and sandwich views.
+   */
+  prune_percent?: number;
+  /** Leave out spans in the `kernel` category (perf, eBPF, annotated collapsed stacks). */
+  hide_kernel?: boolean;
+}
+
+/**
+ * A node of `getAccessibilityTree`: a view, a lane, one flame row of a lane,
+ * or a labelled span inside one.
+ */
+export interface AccessNode {
+  role: "view" | "lane" | "row" | "item";
+  /** Lane name, `"flame row depth 3"`, or the span's label. */
+  label: string;
+  frame_id?: number;
+  /** Where an item was drawn, in viewport pixels. */
+  rect?: { x: number; y: number; w: number; h: number };
+  children?: AccessNode[];
+}
+
+/**
+ * Result of `renderGeometry`: the rects of a render as instance buffers for a
+ * WebGL or WebGPU renderer. Quad `i` has its entries at `i * 4` in `rects`,
+ * `colors` and `borderColors`, and at `i` in `labelIds` and `frameIds`.
+ */
+export interface GeometryBatch {
+  count: number;
+  /** `x, y, w, h` per quad, in viewport pixels. */
+  rects: Float32Array;
+  /** `r, g, b, a` fill per quad; search non-matches are faded. */
+  colors: Uint8Array;
+  /** `r, g, b, a` one-pixel border per quad; alpha 0 for none. */
+  borderColors: Uint8Array;
+  /** Index into `labels`, or `0xffffffff` for unlabeled quads. */
+  labelIds: Uint32Array;
+  /** Span id per quad, or -1. */
+  frameIds: Float64Array;
+  /** Each distinct label once. */
+  labels: string[];
+  /** The render commands that are not rects (text, lines, groups), to draw on top. */
+  overlay: unknown[];
+}
+
+/** One row of `getCategoryLegend`: a by-name color key and its share of the profile. */
+export interface CategoryLegendEntry {
+  /** Package prefix of the span names colored this way. */
+  category: string;
+  /** Hue in degrees. */
+  hue: number;
+  /** `#rrggbb` fill for the current theme. */
+  color: string;
+  /** Summed self time, in the profile's value unit. */
+  total_time: number;
+  span_count: number;
+}
+
+/** One row of `getBinaryBreakdown`: a mapped binary and its share of the profile. */
+export interface BinaryEntry {
+  /** File name as recorded, e.g. `/usr/lib/libc.so.6`. */
+  name: string;
+  build_id?: string;
+  /** Summed self time, in the profile's value unit. */
+  total_time: number;
+  span_count: number;
+}
+
+/** Time of one span category or span kind, in the profile's value unit. */
+export interface CategoryTotal {
+  name: string;
+  /** Summed durations, not counting spans nested in one of the same group twice. */
+  total_time: number;
+  /** Summed self time. */
+  self_time: number;
+  span_count: number;
+}
+
+/** `getCategorySummary`: where a profile's time goes, heaviest first. */
+export interface CategorySummary {
+  /** By span category; spans without one under `"uncategorized"`. */
+  categories: CategoryTotal[];
+  /** By span kind: `"event"`, `"sample"` or `"synthetic"`. */
+  kinds: CategoryTotal[];
+}
+
+/** Direct children of one name under a span, in the profile's value unit. */
+export interface ChildTotal {
+  name: string;
+  /** Summed durations of the children of this name. */
+  total_time: number;
+  /** `total_time` as a percentage (0–100) of the parent span's duration. */
+  percent: number;
+  span_count: number;
+}
+
+/** `getSpanInfo`: one span and where its time went. */
+export interface SpanInfo {
+  id: number;
+  name: string;
+  start: number;
+  end: number;
+  depth: number;
+  self_time: number;
+  /** Direct children grouped by name, heaviest first. */
+  children: ChildTotal[];
+}
+
+/** Whose code a span ran: the program's own, a dependency's, or the runtime's. */
+export type CodeClass = "first_party" | "dependency" | "runtime";
+
+/**
+ * Prefixes of span names (`tokio::`), source paths (`src/`) or binaries
+ * (`libfoo.so`) per code class; a prefix also matches after any `/`. Tried
+ * first-party, dependency, runtime in turn, then built-in rules for common
+ * package managers, runtimes and system libraries; spans nothing matches
+ * count as first-party.
+ */
+export interface ClassRules {
+  first_party?: string[];
+  dependencies?: string[];
+  runtime?: string[];
+}
+
+/** Totals of one span name, as in the ranked view. */
+export interface RankedTotals {
+  name: string;
+  self_time: number;
+  total_time: number;
+  count: number;
+}
+
+/** One occurrence of a ranked name. */
+export interface SpanInstance {
+  frame_id: number;
+  start: number;
+  duration: number;
+  /** Thread group id. */
+  thread: number;
+}
+
+/** A span as laid out in one flame chart row, from `getRowSpans` or `findAdjacentSpan`. */
+export interface RowSpan {
+  frame_id: number;
+  name: string;
+  start: number;
+  end: number;
+  depth: number;
+  /** Thread group id. */
+  thread: number;
+}
+
+/** A span in the comparison tray, as listed by `getPinnedSpans`. */
+export interface PinnedRow {
+  pin: { profile: number; span: number };
+  /** Label of the span's profile. */
+  label: string;
+  name: string;
+  /** Extent on the session timeline, in µs. */
+  start_us: number;
+  end_us: number;
+  /** Duration and self time in the profile's value unit. */
+  duration: number;
+  self_value: number;
+  /** Duration relative to the first pinned span. */
+  ratio: number | null;
+}
+
+/** One row of `getRankedEntries`. */
+export interface RankedEntry extends RankedTotals {
+  /** Up to five of the longest spans with this name, slowest first. */
+  slowest?: SpanInstance[];
+  /** The same name's totals in the baseline profile. */
+  baseline: RankedTotals | null;
+  /** Times formatted with their change from the baseline, e.g. `"12.3ms (+4.1ms vs baseline)"`. */
+  self_label: string;
+  total_label: string;
+}
+
+/** One row of `getTopLevelTasks`. */
+export interface TopLevelTask {
+  span_id: number;
+  name: string;
+  start: number;
+  end: number;
+  duration: number;
+  /** Longest direct child, which usually names what the task was doing. */
+  dominant_child: { span_id: number; name: string; duration: number } | null;
+}
+
+/** One track of `getThreadUtilization`. */
+export interface ThreadUtilization {
+  /** `null` for the total, which sums the threads (200 = two busy threads). */
+  thread_id: number | null;
+  counter: {
+    name: string;
+    unit: "Percent";
+    /** Busy percentage at each bucket start, in the profile's clock. */
+    samples: { ts: number; value: number }[];
+  };
+}
+
+/** API that scheduled an event-loop callback. */
+export type InitiatorApi = "timer" | "animation_frame" | "fetch" | "microtask";
+
+/** Result of `getEventLoopAttribution`. */
+export interface EventLoopAttribution {
+  /** Top-level tasks across all threads. */
+  turns: number;
+  /** Summed task duration; what `by_api` leaves over ran outside any known callback. */
+  turn_time: number;
+  /** Callback time per API, heaviest first. */
+  by_api: { api: InitiatorApi; total_time: number; callbacks: number }[];
+}
+
+/** One link of `getTaskInitiators`: the span that scheduled the previous one. */
+export interface TaskInitiator {
+  span_id: number;
+  name: string;
+  ts: number;
+  api: InitiatorApi | null;
+}
+
+/** Something in the profile an `Insight` points at. */
+export type InsightLink =
+  | { type: "object_event"; label: string; index: number; ts: number }
+  | { type: "time_range"; label: string; start: number; end: number };
+
+/** One finding of `getInsights`. */
+export interface Insight {
+  /** Check that produced it, e.g. `"memory_leak"`. */
+  kind: string;
+  title: string;
+  detail: string;
+  links: InsightLink[];
+}
+
+/** Most of the profile's self time one span category may take. */
+export interface CategoryBudget {
+  category: string;
+  /** Share of the total self time, 0–100. */
+  max_percent: number;
+}
+
+/** One row of `getHotPaths`: a complete stack and the self time at its leaf. */
+export interface HotPath {
+  /** Span names from root to leaf joined with `;`, as in folded stacks. */
+  stack: string;
+  self_time: number;
+  /** Share of the profile's total self time, 0–100. */
+  percent: number;
+}
+
+/** One row of `searchSession`: search matches within one profile of the session. */
+export interface ProfileMatches {
+  /** Index of the profile in the session. */
+  profile: number;
+  label: string;
+  count: number;
+  /** Id of the earliest matching span. */
+  first_span: number;
+  /** Start of the earliest match on the session timeline, in µs. */
+  first_match_us: number;
+}
+
+/** One link of `getGpuSubmissions`: GPU queue work and the CPU span that submitted it. */
+export interface GpuSubmission {
+  submit_span: number;
+  /** Thread group of the submitting CPU thread. */
+  submit_thread: number;
+  submit_start: number;
+  gpu_span: number;
+  /** Thread group of the GPU queue. */
+  gpu_thread: number;
+  gpu_start: number;
+  /** Time from the submit's start to the GPU span's start. */
+  latency: number;
+}
+
+/** A saved viewport, as listed by `listRegions`. */
+export interface ViewRegion {
+  name: string;
+  /** Window as fractions of the session duration. */
+  view_start: number;
+  view_end: number;
+}
+
+/** How a profile was placed on the session timeline. */
+export type Alignment =
+  | { kind: "origin" }
+  | { kind: "same_clock" }
+  | { kind: "navigation_start" }
+  | { kind: "marker"; name: string; reference: number }
+  | { kind: "session_start" }
+  | { kind: "drift" };
+
+/** Placement of every session profile, as returned by `getSessionInfo`. */
+export interface SessionInfo {
+  start_us: number;
+  end_us: number;
+  baseline: number | null;
+  profiles: {
+    label: string;
+    offset_us: number;
+    rate: number;
+    start_us: number;
+    end_us: number;
+    /** `null` for profiles of older session files. */
+    alignment: Alignment | null;
+  }[];
+  /** Rules span names are rewritten with, set by `setNameRewrites`. */
+  name_rewrites: NameRewrite[];
+  /** How spans are sorted into code classes, set by `setClassRules`. */
+  class_rules: ClassRules;
+}
+
+/** A regex rewrite of span names; `replacement` may use `$1` or `$name`. */
+export interface NameRewrite {
+  pattern: string;
+  replacement: string;
+}
+
+/** A note on the session timeline, as listed by `listAnnotations`. */
+export interface Annotation {
+  /** Session time in µs. */
+  start_us: number;
+  end_us?: number;
+  text: string;
+  /** Lane the note belongs to, by name. */
+  lane?: string;
+}
+
+/** What a limited parse left out of a profile; all zero for a full load. */
+export interface LimitsHit {
+  /** Input bytes past the memory budget that were never parsed. */
+  bytes_skipped: number;
+  /** Spans dropped once the event limit was reached. */
+  events_dropped: number;
+  /** Spans dropped for sitting deeper than the depth limit. */
+  too_deep: number;
+  /** Span names cut to the string length limit. */
+  strings_cut: number;
+}
+
+/** Where a truncated or corrupt trace stopped parsing. */
+export interface PartialParse {
+  /** Complete events kept from before the damage. */
+  events: number;
+  /** Latest timestamp (µs) among the kept events. */
+  cut_off: number;
+  /** Why parsing stopped. */
+  error: string;
+}
+
+/** How `exportSVG` and `exportPNG` style the exported image. */
+export interface ImageExportOptions {
+  /** Palette to draw with; the viewer's current theme by default. */
+  theme?: "dark" | "light";
+  /** CSS colors replacing the theme's, keyed by theme token (e.g. `FlameHot`). */
+  palette?: Record<string, string>;
+  /** Leave out the background so the image sits on the page's own. */
+  transparent?: boolean;
+  /** Heading drawn in a block above the view. */
+  title?: string;
+  /** Draw a time scale bar below the view (time-order view only). */
+  scale_bar?: boolean;
+}
+
+/** What to do with one kind of sensitive text when anonymizing. */
+export type Scrub = "keep" | "hash" | "strip";
+
+/**
+ * What `exportJSON` scrubs before a profile is shared. Each kind is hashed
+ * by default, so equal values still match each other.
+ */
+export interface AnonymizeRules {
+  /** `scheme://…` URLs. */
+  urls?: Scrub;
+  /** File paths, Unix or Windows. */
+  paths?: Scrub;
+  /** Quoted string literals in names; screenshots are dropped unless kept. */
+  args?: Scrub;
+  /** Mixed into every hash so tokens can't be matched to guessed values. */
+  salt?: string;
+}
+
 export interface ProfileInfo {
   name: string | null;
+  /** Session index of the baseline profile. */
+  baseline: number | null;
+  /** How the baseline is scaled for comparison. */
+  diff_normalization: DiffNormalization;
+  description: string | null;
+  tags: string[];
   format: string;
+  /** Time between samples (µs), recorded or set with `setProfileMeta`. */
+  sample_interval_us: number | null;
   duration_us: number;
   start_time: number;
   end_time: number;
   span_count: number;
   thread_count: number;
+  /** Nonzero when hostile or corrupt input was only loaded in part. */
+  limits_hit: LimitsHit;
+  /** Set when a truncated or corrupt trace was loaded up to the damage. */
+  partial: PartialParse | null;
 }
 
-export type LaneKind = "thread" | "counter" | "async" | "markers" | "cpu_samples" | "frame_track" | "object_track";
+export type LaneKind = "thread" | "tasks" | "utilization" | "counter" | "async" | "markers" | "cpu_samples" | "frame_track" | "object_track";
 
 export interface LaneInfo {
   name: string;
@@ -34,6 +511,8 @@ export interface LaneInfo {
   height: number;
   visible: boolean;
   span_count: number;
+  /** Thread lane pinned above the sorted threads. */
+  pinned: boolean;
 }
 
 export interface ViewportInfo {
@@ -56,6 +535,76 @@ export interface WasmExports {
   startOnCanvas(canvasId: string): void;
   /** Load a profiling file (any supported format). Accepts raw file bytes. */
   loadProfile(data: Uint8Array): void;
+  /**
+   * Load a profile with a JSON `FormatHint`:
+   * `{ format?, split_profiles?, recover?, raw_names? }`. `format` skips
+   * detection ("chrome", "speedscope", ...); `split_profiles` loads each
+   * profile of a speedscope file as its own session profile; `recover` loads
+   * a truncated or corrupt Chrome trace up to the damage instead of failing;
+   * `raw_names` keeps C++ and Rust symbols in perf, pprof and eBPF frames
+   * mangled.
+   */
+  loadProfileWithHint(data: Uint8Array, hint: string): void;
+  /**
+   * Fetch and load a profile from a URL, with optional extra request headers
+   * (e.g. `Authorization`). Gzip files are decompressed.
+   */
+  loadProfileFromUrl(url: string, headers?: Record<string, string>): Promise<void>;
+  /**
+   * Apply a source map (JSON bytes) to frames from `scriptUrl`, restoring
+   * original function names and positions. `scriptUrl` may be a trailing
+   * path such as `"bundle.js"`; omit it to use the map's `file`.
+   */
+  applySourceMap(scriptUrl: string | undefined, mapBytes: Uint8Array): void;
+  /** Fetch a source map from `mapUrl` and apply it like `applySourceMap`. */
+  applySourceMapFromUrl(scriptUrl: string | undefined, mapUrl: string): Promise<void>;
+  /**
+   * Resolve address-only JIT frames (Node, Deno, JVM) of the session profile
+   * at `profileIndex` with a `/tmp/perf-<pid>.map` file's bytes.
+   */
+  attachJitMap(profileIndex: number, bytes: Uint8Array): void;
+  /**
+   * Add notes from an annotation sidecar (JSON `{ annotations: [{ text, ts?, end?, span?, lane? }] }`),
+   * with times on the clock of the profile at `profileIndex`. Saved with the session.
+   */
+  importAnnotations(profileIndex: number, bytes: Uint8Array): void;
+  /** The session's notes as JSON `Annotation[]`. */
+  listAnnotations(): string;
+  /**
+   * Rename, describe or tag a loaded profile from JSON
+   * `{ name?, description?, tags?, sample_interval_us? }`. Omitted fields are
+   * kept; blank ones, and an interval of 0, cleared. The sampling interval
+   * turns sample counts into time and sets where sample gaps split.
+   */
+  setProfileMeta(profileIndex: number, edit: string): void;
+  /**
+   * Compare ranked rows and span tooltips against the loaded profile at
+   * `profileIndex`; `null` clears the baseline.
+   */
+  setBaseline(profileIndex: number | null): void;
+  /** Scale the baseline so profiles with different sample counts or lengths compare fairly. */
+  setDiffNormalization(normalization: DiffNormalization): void;
+  /**
+   * Rewrite span names with JSON `NameRewrite[]` rules before aggregation,
+   * replacing earlier rules; `"[]"` restores the names as loaded. Saved with
+   * the session. Throws on malformed JSON or an invalid regex.
+   */
+  setNameRewrites(rules: string): void;
+  /**
+   * Sort spans into code classes with JSON `ClassRules`, replacing earlier
+   * rules. Saved with the session. Throws on malformed JSON.
+   */
+  setClassRules(rules: string): void;
+  /**
+   * Ranked rows of the primary profile, as JSON `RankedEntry[]`: one per span
+   * name, with `"binary"` one per mapped binary, or with `"class"` one per
+   * `CodeClass` (both without baselines).
+   */
+  getRankedEntries(group?: "name" | "binary" | "class"): string;
+  /** Order thread lanes. Pinned threads stay on top. */
+  setThreadSort(sort: ThreadSort): void;
+  /** Pin a thread lane (by thread name) to the top, or unpin it. */
+  pinThread(name: string, pinned: boolean): void;
   /** Set the color theme. Accepts `"dark"` or `"light"`. */
   setTheme(mode: string): void;
   /** Set the search query. Matching spans are highlighted; non-matches are dimmed. */
@@ -64,6 +613,11 @@ export interface WasmExports {
   resetZoom(): void;
   /** Set the visible viewport range (0–1 fractional). Values are clamped. */
   setViewport(start: number, end: number): void;
+  /**
+   * Replay the trace: slide the viewport along at `rate` times real time, its
+   * right edge being the playhead. A rate of 0 or less pauses.
+   */
+  setPlayback(rate: number): void;
   /** Toggle visibility of a lane by index. */
   setLaneVisibility(index: number, visible: boolean): void;
   /** Set the pixel height of a lane (clamped to 16–600). */
@@ -80,6 +634,53 @@ export interface WasmExports {
   navigateForward(): void;
   /** Set the span coloring strategy: `"by_name"` or `"by_depth"`. */
   setColorMode(mode: string): void;
+  /** Compare heat-colored spans with their `"siblings"` or an `"absolute"` 1µs–1s scale. */
+  setHeatScale(scale: HeatScale): void;
+  /** Save the current viewport as a named region. Throws on an empty name. */
+  saveRegion(name: string): void;
+  /** Saved regions as JSON `ViewRegion[]`. */
+  listRegions(): string;
+  /** Animate to a saved region; unknown names are ignored. */
+  gotoRegion(name: string): void;
+  /** Bookmark a span for `exportMarkdown`, or remove its bookmark. Saved with the session. */
+  toggleBookmark(frameId: number): void;
+  /** Bookmarked span ids, oldest first, as a JSON array. */
+  listBookmarks(): string;
+  /** Where each profile sits on the session timeline, as JSON `SessionInfo`. */
+  getSessionInfo(): string;
+  /** Pin a span of a profile to the comparison tray, or unpin it. Saved with the session. */
+  togglePin(profile: number, frameId: number): void;
+  /** Pinned spans side by side as JSON `PinnedRow[]`, in pin order. */
+  getPinnedSpans(): string;
+  /** Link Markdown exports to where the session was saved; `null` leaves the link out. */
+  setPermalink(url: string | null): void;
+  /**
+   * Bookmarked spans (function, file:line, duration, % of total) and
+   * annotations as Markdown for review comments, with the permalink.
+   */
+  exportMarkdown(): string;
+  /**
+   * How the primary profile differs from the baseline (or the second
+   * profile) as a Markdown or standalone HTML report: biggest function
+   * changes, new and removed functions, counters and frame timing. Throws
+   * when fewer than two profiles are loaded.
+   */
+  exportComparison(format: "markdown" | "html"): string;
+  /**
+   * Shade the samples of a counter that break `limit` (below it for battery
+   * charge); `null` clears the budget. Saved with the session.
+   */
+  setCounterBudget(name: string, limit: number | null): void;
+  /** Tune the viewer's render guards (JSON `RenderLimits`). */
+  setRenderLimits(limits: string): void;
+  /** Set how durations are written everywhere (JSON `DurationFormat`). */
+  setDurationFormat(format: string): void;
+  /**
+   * Label spans of a view with a template such as `"{name} ({dur})"`, using
+   * `{name}`, `{dur}`, `{self}`, `{category}`, `{source}`, `{url}` and
+   * `{depth}`; `null` restores plain names. Throws on an invalid template.
+   */
+  setLabelTemplate(viewType: string, template: string | null): void;
   /** Select the parent of the currently selected span. */
   navigateToParent(): void;
   /** Select the first child of the currently selected span. */
@@ -88,14 +689,154 @@ export interface WasmExports {
   navigateToNextSibling(): void;
   /** Select the previous sibling of the currently selected span. */
   navigateToPrevSibling(): void;
+  /** Select the span at the other end of the selected span's first flow arrow. */
+  followFlow(): void;
+  /**
+   * Spans of one thread row overlapping a window of the profile's own time,
+   * as JSON `RowSpan[]` ordered by start.
+   */
+  getRowSpans(
+    profileIndex: number,
+    threadId: number,
+    depth: number,
+    viewStart: number,
+    viewEnd: number,
+  ): string;
+  /**
+   * Function names of the CPU sample running at `ts` (profile time) as a JSON
+   * string array, leaf first; empty where nothing was sampled.
+   */
+  getStackAt(profileIndex: number, ts: number): string;
+  /**
+   * The screenshot on screen at `ts` (profile time) as JSON `{ ts, data }`,
+   * `data` being base64 image bytes; `undefined` before the first one.
+   */
+  getScreenshotAt(profileIndex: number, ts: number): string | undefined;
+  /**
+   * The span one arrow-key step (`"left"`, `"right"`, `"up"`, `"down"`) away
+   * in the first profile's flame chart, as JSON `RowSpan`; `undefined` at the
+   * edge. Throws on an unknown direction.
+   */
+  findAdjacentSpan(
+    frameId: number,
+    direction: "left" | "right" | "up" | "down",
+  ): string | undefined;
+  /**
+   * Flow arrows starting or ending within a span, as JSON
+   * `[{ arrow, outgoing }]` ordered by when they touch the span.
+   */
+  getFlowsForSpan(frameId: number): string;
+  /** Color keys of a loaded profile, heaviest first, as JSON `CategoryLegendEntry[]`. */
+  getCategoryLegend(profileIndex: number): string;
+  /** Mapped binaries of a loaded profile, heaviest first, as JSON `BinaryEntry[]`. */
+  getBinaryBreakdown(profileIndex: number): string;
+  /** Total and self time of a loaded profile per span category and kind, as JSON `CategorySummary`. */
+  getCategorySummary(profileIndex: number): string;
+  /**
+   * Total and self time of a loaded profile per code class, as JSON
+   * `CategoryTotal[]` named `first_party`, `dependency` and `runtime`.
+   */
+  getClassSummary(profileIndex: number): string;
+  /**
+   * A span of a loaded profile with its direct children totalled by name, as
+   * JSON `SpanInfo`; `undefined` if the profile has no such span.
+   */
+  getSpanInfo(profileIndex: number, frameId: number): string | undefined;
+  /** Top-level tasks of a thread of the primary profile, as JSON `TopLevelTask[]`. */
+  getTopLevelTasks(threadId: number): string;
+  /**
+   * Busy percentage of every thread of a loaded profile in `bucketUs`
+   * buckets, then the total, as JSON `ThreadUtilization[]`.
+   */
+  getThreadUtilization(profileIndex: number, bucketUs: number): string;
+  /** Event-loop time of the primary profile per scheduling API, as JSON `EventLoopAttribution`. */
+  getEventLoopAttribution(): string;
+  /** Spans that scheduled a span, following flow arrows back, as JSON `TaskInitiator[]`. */
+  getTaskInitiators(frameId: number): string;
+  /** Findings about the primary profile, such as leak suspects and broken budgets, as JSON `Insight[]`. */
+  getInsights(): string;
+  /**
+   * Cap the share of self time per span category, as JSON `CategoryBudget[]`
+   * or a list such as `"js < 50%, layout < 10%"`. Broken budgets show up in
+   * `getInsights`. Throws on a malformed list.
+   */
+  setCategoryBudgets(budgets: string): void;
+  /** GPU queue spans linked to the CPU submits that produced them, as JSON `GpuSubmission[]`. */
+  getGpuSubmissions(): string;
+  /** The `n` stacks with the most self time, as JSON `HotPath[]`. */
+  getHotPaths(n: number): string;
+  /** Matches of `query` in every loaded profile, as JSON `ProfileMatches[]`. */
+  searchSession(query: string): string;
+  /** Dim every span outside one color key; `null` clears the filter. */
+  setCategoryFilter(category: string | null): void;
+  /** Dim every span that did not run in one binary; `null` clears the filter. */
+  setBinaryFilter(binary: string | null): void;
+  /** Dim every span not of one code class; `null` clears the filter. Throws on an unknown class. */
+  setClassFilter(codeClass: CodeClass | null): void;
+  /** Leave kernel frames of perf/eBPF profiles out of thread lanes. */
+  setHideKernel(hide: boolean): void;
+  /** Draw functions inlined into a frame on rows of their own instead of folding them into its label. */
+  setExpandInlined(expand: boolean): void;
   /** Jump to the next span matching the current search query. */
   nextSearchResult(): void;
   /** Jump to the previous span matching the current search query. */
   prevSearchResult(): void;
-  /** Export the loaded profile as a JSON string, or `undefined` if no profile is loaded. */
-  exportProfile(): string | undefined;
-  /** Render the current view as an SVG string at the given dimensions. */
-  exportSVG(width: number, height: number): string | undefined;
+  /** Start following a live recording; replaces the loaded profile. */
+  openLiveSession(): void;
+  /** Append a batch of Chrome trace events (JSON bytes) to the live session. */
+  appendLiveEvents(batch: Uint8Array): void;
+  /** Stop following the live recording, keeping what was received. */
+  closeLiveSession(): void;
+  /**
+   * Export the loaded profile as a JSON string, or `undefined` if no profile
+   * is loaded, first scrubbed by JSON `AnonymizeRules` when given. Throws on
+   * malformed rules.
+   */
+  exportProfile(anonymize?: string): string | undefined;
+  /**
+   * Render the current view as an SVG string at the given dimensions,
+   * styled by JSON `ImageExportOptions`. Throws on malformed options.
+   */
+  exportSVG(width: number, height: number, options?: string): string | undefined;
+  /**
+   * Render the loaded profile headlessly and return its render commands as
+   * JSON. Throws on malformed or newer-versioned options.
+   */
+  renderView(options: string): string;
+  /**
+   * Render like `renderView` but return the rects as GPU-ready instance
+   * buffers, colored for `theme` (`"dark"` or `"light"`, default the app's).
+   */
+  renderGeometry(options: string, theme?: string): GeometryBatch;
+  /**
+   * Time-order render commands of one thread of profile `baseIdx` with the
+   * same-named thread of profile `otherIdx` drawn translucent on top, aligned
+   * on the session timeline. `options` is `JSON.stringify(RenderOptions)`.
+   */
+  renderOverlay(baseIdx: number, otherIdx: number, threadId: number, options: string): string;
+  /**
+   * The same render as nested `AccessNode[]` JSON, for screen-reader
+   * summaries and keyboard navigation over the canvas.
+   */
+  getAccessibilityTree(options: string): string;
+  /** What a pointer at `x` on a `width`-wide minimap lands on. */
+  minimapHitTest(
+    x: number,
+    width: number,
+    viewStart: number,
+    viewEnd: number,
+  ): "left_handle" | "right_handle" | "viewport" | "outside";
+  /**
+   * Visible `[start, end]` fractions for a minimap drag pressed at `pressX`:
+   * a pan or resize inside the viewport, a new range outside it.
+   */
+  minimapDragRange(
+    pressX: number,
+    x: number,
+    width: number,
+    viewStart: number,
+    viewEnd: number,
+  ): Float64Array;
   /** Register a callback invoked whenever the viewer state changes. */
   onStateChange(callback: () => void): void;
   /** Get the full viewer state as a JSON string (used by the store for snapshots). */